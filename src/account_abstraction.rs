@@ -0,0 +1,439 @@
+use ethers::abi::{self, Token};
+use crate::eth_client::RpcProvider;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::keccak256;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+/// ERC-4337 EntryPoint v0.6 合约地址（各链相同）
+pub const DEFAULT_ENTRY_POINT: &str = "0x5FF137D4b0FDCD49DcA30c7CF57E578a026d2789";
+
+/// EntryPoint.getNonce(address,uint192) 函数选择器
+const GET_NONCE_SELECTOR: [u8; 4] = [0x35, 0x56, 0x7e, 0x1a];
+
+/// SimpleAccount.execute(address,uint256,bytes) 函数选择器
+const EXECUTE_SELECTOR: [u8; 4] = [0xb6, 0x1d, 0x27, 0xf6];
+
+/// SimpleAccount.executeBatch(address[],bytes[]) 函数选择器
+const EXECUTE_BATCH_SELECTOR: [u8; 4] = [0x18, 0xdc, 0xe8, 0xa8];
+
+/// Gas 估算时使用的占位签名（65 字节，能通过 ecrecover 但不对应任何账户）
+const DUMMY_SIGNATURE: &str = "0xfffffffffffffffffffffffffffffff0000000000000000000000000000000007aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa1c";
+
+/// 账户抽象错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum AccountAbstractionError {
+    #[error("提供者错误: {0}")]
+    ProviderError(#[from] ProviderError),
+
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("未配置 {0}")]
+    NotConfigured(&'static str),
+
+    #[error("Bundler 返回错误 ({code}): {message}")]
+    RpcError { code: i64, message: String },
+
+    #[error("无效的响应: {0}")]
+    InvalidResponse(String),
+
+    #[error("签名失败: {0}")]
+    SignerError(String),
+}
+
+/// ERC-4337 UserOperation（EntryPoint v0.6 格式）
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperation {
+    pub sender: Address,
+    pub nonce: U256,
+    pub init_code: Bytes,
+    pub call_data: Bytes,
+    pub call_gas_limit: U256,
+    pub verification_gas_limit: U256,
+    pub pre_verification_gas: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub paymaster_and_data: Bytes,
+    pub signature: Bytes,
+}
+
+impl UserOperation {
+    /// 使用占位签名创建 UserOperation（Gas 字段待估算后填写）
+    pub fn new(sender: Address, nonce: U256, call_data: Bytes) -> Self {
+        Self {
+            sender,
+            nonce,
+            call_data,
+            signature: DUMMY_SIGNATURE.parse().expect("硬编码签名应该有效"),
+            ..Default::default()
+        }
+    }
+
+    /// 计算 userOpHash = keccak256(abi.encode(keccak256(pack(op)), entryPoint, chainId))
+    /// pack 时动态字段（initCode、callData、paymasterAndData）先取哈希，且不包含签名
+    pub fn hash(&self, entry_point: Address, chain_id: u64) -> H256 {
+        let packed = abi::encode(&[
+            Token::Address(self.sender),
+            Token::Uint(self.nonce),
+            Token::FixedBytes(keccak256(&self.init_code).to_vec()),
+            Token::FixedBytes(keccak256(&self.call_data).to_vec()),
+            Token::Uint(self.call_gas_limit),
+            Token::Uint(self.verification_gas_limit),
+            Token::Uint(self.pre_verification_gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.max_priority_fee_per_gas),
+            Token::FixedBytes(keccak256(&self.paymaster_and_data).to_vec()),
+        ]);
+
+        H256::from(keccak256(abi::encode(&[
+            Token::FixedBytes(keccak256(packed).to_vec()),
+            Token::Address(entry_point),
+            Token::Uint(U256::from(chain_id)),
+        ])))
+    }
+
+    /// 使用智能账户的所有者私钥签名（EIP-191，与 SimpleAccount 的校验方式一致）
+    pub async fn sign<S: Signer>(
+        &mut self,
+        owner: &S,
+        entry_point: Address,
+        chain_id: u64,
+    ) -> Result<H256, AccountAbstractionError> {
+        let hash = self.hash(entry_point, chain_id);
+        let signature = owner
+            .sign_message(hash.as_bytes())
+            .await
+            .map_err(|e| AccountAbstractionError::SignerError(e.to_string()))?;
+        self.signature = Bytes::from(signature.to_vec());
+        Ok(hash)
+    }
+
+    /// 填写 Gas 估算结果
+    pub fn apply_gas(&mut self, gas: &UserOperationGas) {
+        self.pre_verification_gas = gas.pre_verification_gas;
+        self.verification_gas_limit = gas.verification_gas_limit;
+        self.call_gas_limit = gas.call_gas_limit;
+    }
+
+    /// 最大 Gas 费用 = (callGas + verificationGas + preVerificationGas) × maxFeePerGas
+    pub fn max_gas_cost(&self) -> U256 {
+        (self.call_gas_limit + self.verification_gas_limit + self.pre_verification_gas)
+            * self.max_fee_per_gas
+    }
+}
+
+/// 智能账户的一次内部调用
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountCall {
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+}
+
+/// 构建智能账户 callData：单笔调用使用 execute，多笔调用使用 executeBatch
+/// executeBatch 不支持携带 ETH，多笔调用中有 value 时返回 None
+pub fn account_call_data(calls: &[AccountCall]) -> Option<Bytes> {
+    match calls {
+        [] => None,
+        [call] => {
+            let mut data = EXECUTE_SELECTOR.to_vec();
+            data.extend(abi::encode(&[
+                Token::Address(call.to),
+                Token::Uint(call.value),
+                Token::Bytes(call.data.to_vec()),
+            ]));
+            Some(Bytes::from(data))
+        }
+        calls if calls.iter().any(|call| !call.value.is_zero()) => None,
+        calls => {
+            let mut data = EXECUTE_BATCH_SELECTOR.to_vec();
+            data.extend(abi::encode(&[
+                Token::Array(calls.iter().map(|call| Token::Address(call.to)).collect()),
+                Token::Array(
+                    calls
+                        .iter()
+                        .map(|call| Token::Bytes(call.data.to_vec()))
+                        .collect(),
+                ),
+            ]));
+            Some(Bytes::from(data))
+        }
+    }
+}
+
+/// Bundler 返回的 Gas 估算
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserOperationGas {
+    pub pre_verification_gas: U256,
+    pub verification_gas_limit: U256,
+    pub call_gas_limit: U256,
+}
+
+/// Paymaster 赞助结果（部分 Paymaster 同时返回 Gas 估算）
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymasterSponsorship {
+    pub paymaster_and_data: Bytes,
+    #[serde(flatten)]
+    pub gas: Option<UserOperationGas>,
+}
+
+#[derive(serde::Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(serde::Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// Bundler / Paymaster 客户端
+#[derive(Clone)]
+pub struct BundlerClient {
+    provider: Option<Arc<RpcProvider>>,
+    http: reqwest::Client,
+    bundler_url: Option<String>,
+    paymaster_url: Option<String>,
+    entry_point: Address,
+}
+
+impl BundlerClient {
+    /// 创建新的 Bundler 客户端
+    pub fn new(
+        provider: Option<Arc<RpcProvider>>,
+        bundler_url: Option<String>,
+        paymaster_url: Option<String>,
+        entry_point: Address,
+    ) -> Self {
+        Self {
+            provider,
+            http: reqwest::Client::new(),
+            bundler_url,
+            paymaster_url,
+            entry_point,
+        }
+    }
+
+    /// 检查是否配置了 Bundler
+    pub fn is_available(&self) -> bool {
+        self.bundler_url.is_some() && self.provider.is_some()
+    }
+
+    /// 检查是否配置了 Paymaster
+    pub fn has_paymaster(&self) -> bool {
+        self.paymaster_url.is_some()
+    }
+
+    /// EntryPoint 合约地址
+    pub fn entry_point(&self) -> Address {
+        self.entry_point
+    }
+
+    /// 使用其他 EntryPoint 的客户端（共享 Provider、Bundler 和 Paymaster 配置）
+    pub fn with_entry_point(&self, entry_point: Address) -> Self {
+        Self {
+            entry_point,
+            ..self.clone()
+        }
+    }
+
+    /// 查询智能账户在 EntryPoint 上的 nonce（key = 0）
+    #[instrument(skip(self))]
+    pub async fn get_nonce(&self, sender: Address) -> Result<U256, AccountAbstractionError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(AccountAbstractionError::NotConfigured("ETHEREUM_RPC_URL"))?;
+
+        let mut data = GET_NONCE_SELECTOR.to_vec();
+        data.extend(abi::encode(&[Token::Address(sender), Token::Uint(U256::zero())]));
+
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(self.entry_point)
+            .data(data)
+            .into();
+        let result = provider.call(&tx, None).await?;
+
+        if result.len() < 32 {
+            return Err(AccountAbstractionError::InvalidResponse(format!(
+                "getNonce 返回数据长度异常: {} 字节",
+                result.len()
+            )));
+        }
+        Ok(U256::from_big_endian(&result[..32]))
+    }
+
+    /// 通过 Bundler 估算 UserOperation 的 Gas（eth_estimateUserOperationGas）
+    #[instrument(skip(self, op))]
+    pub async fn estimate_gas(
+        &self,
+        op: &UserOperation,
+    ) -> Result<UserOperationGas, AccountAbstractionError> {
+        let url = self.bundler_url()?;
+        self.rpc(url, "eth_estimateUserOperationGas", serde_json::json!([op, self.entry_point]))
+            .await
+    }
+
+    /// 请求 Paymaster 赞助（pm_sponsorUserOperation）
+    #[instrument(skip(self, op))]
+    pub async fn sponsor(
+        &self,
+        op: &UserOperation,
+    ) -> Result<PaymasterSponsorship, AccountAbstractionError> {
+        let url = self
+            .paymaster_url
+            .as_deref()
+            .ok_or(AccountAbstractionError::NotConfigured("AA_PAYMASTER_URL"))?;
+        self.rpc(url, "pm_sponsorUserOperation", serde_json::json!([op, self.entry_point]))
+            .await
+    }
+
+    /// 提交已签名的 UserOperation（eth_sendUserOperation），返回 Bundler 确认的 userOpHash
+    #[instrument(skip(self, op))]
+    pub async fn send(&self, op: &UserOperation) -> Result<H256, AccountAbstractionError> {
+        let url = self.bundler_url()?;
+        self.rpc(url, "eth_sendUserOperation", serde_json::json!([op, self.entry_point]))
+            .await
+    }
+
+    fn bundler_url(&self) -> Result<&str, AccountAbstractionError> {
+        self.bundler_url
+            .as_deref()
+            .ok_or(AccountAbstractionError::NotConfigured("AA_BUNDLER_URL"))
+    }
+
+    async fn rpc<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T, AccountAbstractionError> {
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response: JsonRpcResponse<T> = self
+            .http
+            .post(url)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        debug!(method, "收到 Bundler 响应");
+
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(AccountAbstractionError::RpcError {
+                code: error.code,
+                message: error.message,
+            }),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(AccountAbstractionError::InvalidResponse(format!(
+                "{} 响应缺少 result",
+                method
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_operation_serializes_camel_case_hex() {
+        let op = UserOperation::new(Address::repeat_byte(0x11), U256::from(7), Bytes::from(vec![0xab]));
+        let json = serde_json::to_value(&op).unwrap();
+
+        assert_eq!(json["nonce"], "0x7");
+        assert_eq!(json["callData"], "0xab");
+        assert_eq!(json["paymasterAndData"], "0x");
+        assert_eq!(json["signature"].as_str().unwrap().len(), 2 + 65 * 2);
+    }
+
+    #[test]
+    fn test_user_operation_hash_ignores_signature() {
+        let entry_point: Address = DEFAULT_ENTRY_POINT.parse().unwrap();
+        let mut op = UserOperation::new(Address::repeat_byte(0x11), U256::zero(), Bytes::new());
+        let hash = op.hash(entry_point, 1);
+
+        op.signature = Bytes::from(vec![1, 2, 3]);
+        assert_eq!(op.hash(entry_point, 1), hash);
+
+        // chainId 和 Gas 字段都参与哈希
+        assert_ne!(op.hash(entry_point, 5), hash);
+        op.call_gas_limit = U256::from(100_000);
+        assert_ne!(op.hash(entry_point, 1), hash);
+    }
+
+    #[tokio::test]
+    async fn test_sign_recovers_owner() {
+        let owner: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap();
+        let entry_point: Address = DEFAULT_ENTRY_POINT.parse().unwrap();
+        let mut op = UserOperation::new(Address::repeat_byte(0x11), U256::zero(), Bytes::new());
+
+        let hash = op.sign(&owner, entry_point, 1).await.unwrap();
+        let signature = Signature::try_from(op.signature.as_ref()).unwrap();
+
+        assert_eq!(signature.recover(hash.as_bytes()).unwrap(), owner.address());
+    }
+
+    #[test]
+    fn test_account_call_data_selects_execute_or_batch() {
+        let call = |value: u64| AccountCall {
+            to: Address::repeat_byte(0x22),
+            value: U256::from(value),
+            data: Bytes::from(vec![0x01, 0x02]),
+        };
+
+        assert!(account_call_data(&[]).is_none());
+        assert_eq!(account_call_data(&[call(5)]).unwrap()[..4], EXECUTE_SELECTOR);
+        assert_eq!(
+            account_call_data(&[call(0), call(0)]).unwrap()[..4],
+            EXECUTE_BATCH_SELECTOR
+        );
+        assert!(account_call_data(&[call(0), call(1)]).is_none());
+    }
+
+    #[test]
+    fn test_with_entry_point() {
+        let client = BundlerClient::new(
+            None,
+            Some("http://localhost:4337".to_string()),
+            None,
+            DEFAULT_ENTRY_POINT.parse().unwrap(),
+        );
+        let other = client.with_entry_point(Address::repeat_byte(0x44));
+        assert_eq!(other.entry_point(), Address::repeat_byte(0x44));
+        assert_eq!(other.bundler_url, client.bundler_url);
+        assert_eq!(client.entry_point(), DEFAULT_ENTRY_POINT.parse::<Address>().unwrap());
+    }
+
+    #[test]
+    fn test_paymaster_sponsorship_optional_gas() {
+        let sponsorship: PaymasterSponsorship =
+            serde_json::from_str(r#"{"paymasterAndData":"0x1234"}"#).unwrap();
+        assert!(sponsorship.gas.is_none());
+
+        let sponsorship: PaymasterSponsorship = serde_json::from_str(
+            r#"{"paymasterAndData":"0x1234","preVerificationGas":"0x10","verificationGasLimit":"0x20","callGasLimit":"0x30"}"#,
+        )
+        .unwrap();
+        assert_eq!(sponsorship.gas.unwrap().call_gas_limit, U256::from(0x30));
+    }
+}
@@ -0,0 +1,196 @@
+use crate::chains::ChainInfo;
+use crate::diagnostics::record_rpc_call;
+use ethers::prelude::*;
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument};
+
+/// Alchemy 请求超时时间
+const ALCHEMY_TIMEOUT: Duration = Duration::from_secs(15);
+/// alchemy_getTokenBalances 最多翻页次数（每页 100 个代币）
+const MAX_TOKEN_BALANCE_PAGES: usize = 10;
+
+/// Alchemy API 错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum AlchemyError {
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("未配置 ALCHEMY_API_KEY")]
+    ApiKeyMissing,
+
+    #[error("Alchemy 返回错误: {0}")]
+    ApiError(String),
+
+    #[error("响应格式错误: {0}")]
+    InvalidResponse(String),
+}
+
+/// 一页 alchemy_getTokenBalances 结果
+#[derive(Debug, Default, PartialEq)]
+pub struct TokenBalancesPage {
+    /// 非零余额 (代币地址, 余额)
+    pub balances: Vec<(Address, U256)>,
+    pub page_key: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenBalancesResult {
+    token_balances: Vec<TokenBalanceEntry>,
+    #[serde(default)]
+    page_key: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenBalanceEntry {
+    contract_address: String,
+    /// 查询失败的代币为 null
+    token_balance: Option<String>,
+}
+
+impl TokenBalancesPage {
+    /// 解析 alchemy_getTokenBalances 的 result 字段，跳过零余额和查询失败的代币
+    pub fn parse(result: serde_json::Value) -> Result<Self, AlchemyError> {
+        let result: TokenBalancesResult = serde_json::from_value(result)
+            .map_err(|e| AlchemyError::InvalidResponse(e.to_string()))?;
+
+        let balances = result
+            .token_balances
+            .into_iter()
+            .filter_map(|entry| {
+                let token = entry.contract_address.parse::<Address>().ok()?;
+                let balance = U256::from_str_radix(entry.token_balance?.trim_start_matches("0x"), 16).ok()?;
+                (!balance.is_zero()).then_some((token, balance))
+            })
+            .collect();
+
+        Ok(Self {
+            balances,
+            page_key: result.page_key.filter(|key| !key.is_empty()),
+        })
+    }
+}
+
+/// Alchemy 增强 API 客户端（未配置 ALCHEMY_API_KEY 时不可用）
+#[derive(Clone)]
+pub struct AlchemyClient {
+    http: reqwest::Client,
+    endpoint: Option<String>,
+}
+
+impl AlchemyClient {
+    /// 创建指定链的 Alchemy 客户端
+    pub fn new(api_key: Option<String>, chain: &ChainInfo) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(ALCHEMY_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            endpoint: api_key
+                .map(|key| format!("https://{}.g.alchemy.com/v2/{}", chain.alchemy_network, key)),
+        }
+    }
+
+    /// 检查是否配置了 API Key
+    pub fn is_available(&self) -> bool {
+        self.endpoint.is_some()
+    }
+
+    /// 查询钱包持有的所有 ERC20 代币余额（只返回非零余额）
+    #[instrument(skip(self))]
+    pub async fn token_balances(&self, owner: Address) -> Result<Vec<(Address, U256)>, AlchemyError> {
+        let mut balances = Vec::new();
+        let mut page_key: Option<String> = None;
+
+        for _ in 0..MAX_TOKEN_BALANCE_PAGES {
+            let mut params = vec![
+                serde_json::json!(format!("{:?}", owner)),
+                serde_json::json!("erc20"),
+            ];
+            if let Some(ref key) = page_key {
+                params.push(serde_json::json!({ "pageKey": key }));
+            }
+
+            let page = TokenBalancesPage::parse(self.request("alchemy_getTokenBalances", params).await?)?;
+            balances.extend(page.balances);
+            page_key = page.page_key;
+            if page_key.is_none() {
+                break;
+            }
+        }
+
+        debug!(count = balances.len(), truncated = page_key.is_some(), "获取代币余额");
+
+        Ok(balances)
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> Result<serde_json::Value, AlchemyError> {
+        let endpoint = self.endpoint.as_deref().ok_or(AlchemyError::ApiKeyMissing)?;
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let started = Instant::now();
+        let response = self.send(endpoint, &body).await;
+        record_rpc_call(method, None, started.elapsed(), 0, response.is_ok());
+        let mut response = response?;
+
+        if let Some(error) = response.get("error") {
+            return Err(AlchemyError::ApiError(
+                error["message"].as_str().unwrap_or("未知错误").to_string(),
+            ));
+        }
+        Ok(response["result"].take())
+    }
+
+    async fn send(&self, endpoint: &str, body: &serde_json::Value) -> Result<serde_json::Value, AlchemyError> {
+        Ok(self
+            .http
+            .post(endpoint)
+            .json(body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_token_balances_page() {
+        let page = TokenBalancesPage::parse(serde_json::json!({
+            "address": "0xd8da6bf26964af9d7eed9e03e53415d37aa96045",
+            "tokenBalances": [
+                {"contractAddress": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", "tokenBalance": "0x00000000000000000000000000000000000000000000000000000000000f4240"},
+                {"contractAddress": "0xdac17f958d2ee523a2206206994597c13d831ec7", "tokenBalance": "0x0000000000000000000000000000000000000000000000000000000000000000"},
+                {"contractAddress": "0x6b175474e89094c44da98b954eedeac495271d0f", "tokenBalance": null, "error": "execution reverted"}
+            ],
+            "pageKey": "0x6b17"
+        }))
+        .unwrap();
+
+        assert_eq!(page.balances.len(), 1);
+        assert_eq!(page.balances[0].1, U256::from(1_000_000u64));
+        assert_eq!(page.page_key.as_deref(), Some("0x6b17"));
+
+        assert!(TokenBalancesPage::parse(serde_json::json!({"tokenBalances": 1})).is_err());
+    }
+
+    #[test]
+    fn test_client_requires_api_key() {
+        assert!(!AlchemyClient::new(None, &crate::chains::MAINNET).is_available());
+        assert!(AlchemyClient::new(Some("key".to_string()), &crate::chains::BASE).is_available());
+    }
+}
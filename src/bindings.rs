@@ -0,0 +1,300 @@
+use crate::eth_client::RpcProvider;
+use ethers::prelude::*;
+use ethers::abi::{Abi, Event, Function};
+
+abigen!(
+    IERC20,
+    r#"[
+        function name() external view returns (string)
+        function symbol() external view returns (string)
+        function decimals() external view returns (uint8)
+        function totalSupply() external view returns (uint256)
+        function version() external view returns (string)
+        function DOMAIN_SEPARATOR() external view returns (bytes32)
+        function balanceOf(address owner) external view returns (uint256)
+        function allowance(address owner, address spender) external view returns (uint256)
+        function approve(address spender, uint256 amount) external returns (bool)
+        function transfer(address to, uint256 amount) external returns (bool)
+        function transferFrom(address from, address to, uint256 amount) external returns (bool)
+        event Transfer(address indexed from, address indexed to, uint256 value)
+        event Approval(address indexed owner, address indexed spender, uint256 value)
+    ]"#
+);
+
+abigen!(
+    IERC721,
+    r#"[
+        function name() external view returns (string)
+        function symbol() external view returns (string)
+        function balanceOf(address owner) external view returns (uint256)
+        function ownerOf(uint256 tokenId) external view returns (address)
+        function tokenOfOwnerByIndex(address owner, uint256 index) external view returns (uint256)
+        function supportsInterface(bytes4 interfaceId) external view returns (bool)
+    ]"#
+);
+
+abigen!(
+    IERC1155,
+    r#"[
+        function balanceOf(address account, uint256 id) external view returns (uint256)
+        function balanceOfBatch(address[] accounts, uint256[] ids) external view returns (uint256[])
+    ]"#
+);
+
+abigen!(
+    IUniswapV2Factory,
+    r#"[
+        function getPair(address tokenA, address tokenB) external view returns (address)
+        function createPair(address tokenA, address tokenB) external returns (address)
+        event PairCreated(address indexed token0, address indexed token1, address pair, uint256)
+    ]"#
+);
+
+abigen!(
+    IUniswapV2Pair,
+    r#"[
+        function token0() external view returns (address)
+        function token1() external view returns (address)
+        function getReserves() external view returns (uint112, uint112, uint32)
+        function price0CumulativeLast() external view returns (uint256)
+        function price1CumulativeLast() external view returns (uint256)
+        function swap(uint256 amount0Out, uint256 amount1Out, address to, bytes data) external
+        function mint(address to) external returns (uint256 liquidity)
+        function burn(address to) external returns (uint256 amount0, uint256 amount1)
+        function skim(address to) external
+        function sync() external
+        event Swap(address indexed sender, uint256 amount0In, uint256 amount1In, uint256 amount0Out, uint256 amount1Out, address indexed to)
+        event Sync(uint112 reserve0, uint112 reserve1)
+        event Mint(address indexed sender, uint256 amount0, uint256 amount1)
+        event Burn(address indexed sender, uint256 amount0, uint256 amount1, address indexed to)
+    ]"#
+);
+
+abigen!(
+    IUniswapV2Router02,
+    r#"[
+        function swapExactTokensForTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline) external returns (uint256[] amounts)
+        function swapTokensForExactTokens(uint256 amountOut, uint256 amountInMax, address[] path, address to, uint256 deadline) external returns (uint256[] amounts)
+        function swapExactETHForTokens(uint256 amountOutMin, address[] path, address to, uint256 deadline) external payable returns (uint256[] amounts)
+        function swapExactTokensForETH(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline) external returns (uint256[] amounts)
+        function swapETHForExactTokens(uint256 amountOut, address[] path, address to, uint256 deadline) external payable returns (uint256[] amounts)
+        function swapTokensForExactETH(uint256 amountOut, uint256 amountInMax, address[] path, address to, uint256 deadline) external returns (uint256[] amounts)
+        function swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline) external
+        function swapExactETHForTokensSupportingFeeOnTransferTokens(uint256 amountOutMin, address[] path, address to, uint256 deadline) external payable
+        function swapExactTokensForETHSupportingFeeOnTransferTokens(uint256 amountIn, uint256 amountOutMin, address[] path, address to, uint256 deadline) external
+        function addLiquidity(address tokenA, address tokenB, uint256 amountADesired, uint256 amountBDesired, uint256 amountAMin, uint256 amountBMin, address to, uint256 deadline) external returns (uint256 amountA, uint256 amountB, uint256 liquidity)
+        function addLiquidityETH(address token, uint256 amountTokenDesired, uint256 amountTokenMin, uint256 amountETHMin, address to, uint256 deadline) external payable returns (uint256 amountToken, uint256 amountETH, uint256 liquidity)
+        function removeLiquidity(address tokenA, address tokenB, uint256 liquidity, uint256 amountAMin, uint256 amountBMin, address to, uint256 deadline) external returns (uint256 amountA, uint256 amountB)
+        function removeLiquidityETH(address token, uint256 liquidity, uint256 amountTokenMin, uint256 amountETHMin, address to, uint256 deadline) external returns (uint256 amountToken, uint256 amountETH)
+    ]"#
+);
+
+abigen!(
+    INonfungiblePositionManager,
+    r#"[
+        function factory() external view returns (address)
+        function balanceOf(address owner) external view returns (uint256)
+        function ownerOf(uint256 tokenId) external view returns (address)
+        function tokenOfOwnerByIndex(address owner, uint256 index) external view returns (uint256)
+        function positions(uint256 tokenId) external view returns (uint96 nonce, address operator, address token0, address token1, uint24 fee, int24 tickLower, int24 tickUpper, uint128 liquidity, uint256 feeGrowthInside0LastX128, uint256 feeGrowthInside1LastX128, uint128 tokensOwed0, uint128 tokensOwed1)
+    ]"#
+);
+
+abigen!(
+    IUniswapV3Factory,
+    r#"[
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool)
+    ]"#
+);
+
+abigen!(
+    IUniswapV3Pool,
+    r#"[
+        function slot0() external view returns (uint160 sqrtPriceX96, int24 tick, uint16 observationIndex, uint16 observationCardinality, uint16 observationCardinalityNext, uint8 feeProtocol, bool unlocked)
+        function feeGrowthGlobal0X128() external view returns (uint256)
+        function feeGrowthGlobal1X128() external view returns (uint256)
+        function ticks(int24 tick) external view returns (uint128 liquidityGross, int128 liquidityNet, uint256 feeGrowthOutside0X128, uint256 feeGrowthOutside1X128, int56 tickCumulativeOutside, uint160 secondsPerLiquidityOutsideX128, uint32 secondsOutside, bool initialized)
+    ]"#
+);
+
+abigen!(
+    ISafe,
+    r#"[
+        function nonce() external view returns (uint256)
+        function getThreshold() external view returns (uint256)
+        function getOwners() external view returns (address[])
+    ]"#
+);
+
+abigen!(
+    ICurvePool,
+    r#"[
+        function get_dy(int128 i, int128 j, uint256 dx) external view returns (uint256)
+    ]"#
+);
+
+/// 以 eth_call 执行只读调用，返回未解码的返回值（`block` 为 None 时查询最新区块）
+pub async fn eth_call<C: EthCall>(
+    provider: &RpcProvider,
+    to: Address,
+    call: C,
+    block: Option<BlockId>,
+) -> Result<Bytes, ProviderError> {
+    let tx = Eip1559TransactionRequest::new().to(to).data(call.encode());
+    provider.call(&tx.into(), block).await
+}
+
+/// 内置的已知合约 ABI(合约名称, ABI),用于识别和解码调用数据
+pub fn known_abis() -> [(&'static str, &'static Abi); 4] {
+    [
+        ("ERC20", &ierc20::IERC20_ABI),
+        ("UniswapV2Router02", &i_uniswap_v2_router_02::IUNISWAPV2ROUTER02_ABI),
+        ("UniswapV2Factory", &i_uniswap_v2_factory::IUNISWAPV2FACTORY_ABI),
+        ("UniswapV2Pair", &i_uniswap_v2_pair::IUNISWAPV2PAIR_ABI),
+    ]
+}
+
+/// 按函数选择器在已知 ABI 中查找函数,返回 (合约名称, 函数)
+pub fn find_known_function(selector: &[u8]) -> Option<(&'static str, &'static Function)> {
+    known_abis().into_iter().find_map(|(contract, abi)| {
+        abi.functions()
+            .find(|function| function.short_signature().as_slice() == selector)
+            .map(|function| (contract, function))
+    })
+}
+
+/// 按事件签名(topic0)在已知 ABI 中查找事件,返回 (合约名称, 事件)
+pub fn find_known_event(topic0: H256) -> Option<(&'static str, &'static Event)> {
+    known_abis().into_iter().find_map(|(contract, abi)| {
+        abi.events()
+            .find(|event| event.signature() == topic0)
+            .map(|event| (contract, event))
+    })
+}
+
+/// 按事件名称(不区分大小写)在已知 ABI 中查找事件
+pub fn find_known_event_by_name(name: &str) -> Option<(&'static str, &'static Event)> {
+    known_abis().into_iter().find_map(|(contract, abi)| {
+        abi.events()
+            .find(|event| event.name.eq_ignore_ascii_case(name))
+            .map(|event| (contract, event))
+    })
+}
+
+/// 函数签名,如 `approve(address,uint256)`
+pub fn function_signature(function: &Function) -> String {
+    let inputs: Vec<String> = function.inputs.iter().map(|param| param.kind.to_string()).collect();
+    format!("{}({})", function.name, inputs.join(","))
+}
+
+/// 按函数选择器识别已知函数(ERC20、Uniswap V2 Router/Factory/Pair),返回函数签名
+pub fn known_method_signature(data: &[u8]) -> Option<String> {
+    find_known_function(data.get(..4)?).map(|(_, function)| function_signature(function))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::erc20::{APPROVAL_EVENT_TOPIC, TRANSFER_EVENT_TOPIC};
+    use ethers::abi::AbiEncode;
+    use crate::uniswap::{PAIR_CREATED_EVENT_TOPIC, SWAP_EVENT_TOPIC};
+
+    #[test]
+    fn test_selectors_match_known_values() {
+        assert_eq!(ierc20::BalanceOfCall::selector(), [0x70, 0xa0, 0x82, 0x31]);
+        assert_eq!(ierc20::SymbolCall::selector(), [0x95, 0xd8, 0x9b, 0x41]);
+        assert_eq!(ierc20::DomainSeparatorCall::selector(), [0x36, 0x44, 0xe5, 0x15]);
+        assert_eq!(ierc20::TotalSupplyCall::selector(), [0x18, 0x16, 0x0d, 0xdd]);
+        assert_eq!(i_uniswap_v2_factory::GetPairCall::selector(), [0xe6, 0xa4, 0x39, 0x05]);
+        assert_eq!(i_uniswap_v2_pair::GetReservesCall::selector(), [0x09, 0x02, 0xf1, 0xac]);
+        assert_eq!(i_uniswap_v2_pair::Price0CumulativeLastCall::selector(), [0x59, 0x09, 0xc0, 0xd5]);
+        assert_eq!(i_uniswap_v2_pair::Price1CumulativeLastCall::selector(), [0x5a, 0x3d, 0x54, 0x93]);
+        assert_eq!(
+            i_uniswap_v2_router_02::SwapExactTokensForTokensCall::selector(),
+            [0x38, 0xed, 0x17, 0x39]
+        );
+        assert_eq!(
+            i_uniswap_v2_router_02::SwapTokensForExactTokensCall::selector(),
+            [0x88, 0x03, 0xdb, 0xee]
+        );
+        assert_eq!(i_uniswap_v2_router_02::SwapExactETHForTokensCall::selector(), [0x7f, 0xf3, 0x6a, 0xb5]);
+        assert_eq!(i_uniswap_v2_router_02::SwapExactTokensForETHCall::selector(), [0x18, 0xcb, 0xaf, 0xe5]);
+        assert_eq!(i_uniswap_v2_router_02::SwapETHForExactTokensCall::selector(), [0xfb, 0x3b, 0xdb, 0x41]);
+        assert_eq!(i_uniswap_v2_router_02::SwapTokensForExactETHCall::selector(), [0x4a, 0x25, 0xd9, 0x4a]);
+        assert_eq!(
+            i_uniswap_v2_router_02::SwapExactTokensForTokensSupportingFeeOnTransferTokensCall::selector(),
+            [0x5c, 0x11, 0xd7, 0x95]
+        );
+        assert_eq!(
+            i_uniswap_v2_router_02::SwapExactETHForTokensSupportingFeeOnTransferTokensCall::selector(),
+            [0xb6, 0xf9, 0xde, 0x95]
+        );
+        assert_eq!(
+            i_uniswap_v2_router_02::SwapExactTokensForETHSupportingFeeOnTransferTokensCall::selector(),
+            [0x79, 0x1a, 0xc9, 0x47]
+        );
+        assert_eq!(i_curve_pool::GetDyCall::selector(), [0x5e, 0x0d, 0x44, 0x3f]);
+        assert_eq!(ierc20::TransferFromCall::selector(), [0x23, 0xb8, 0x72, 0xdd]);
+        assert_eq!(i_uniswap_v2_factory::CreatePairCall::selector(), [0xc9, 0xc6, 0x53, 0x96]);
+        assert_eq!(i_uniswap_v2_pair::SwapCall::selector(), [0x02, 0x2c, 0x0d, 0x9f]);
+        assert_eq!(i_uniswap_v2_pair::SyncCall::selector(), [0xff, 0xf6, 0xca, 0xe9]);
+        assert_eq!(i_uniswap_v2_router_02::AddLiquidityCall::selector(), [0xe8, 0xe3, 0x37, 0x00]);
+        assert_eq!(i_uniswap_v2_router_02::AddLiquidityETHCall::selector(), [0xf3, 0x05, 0xd7, 0x19]);
+        assert_eq!(i_uniswap_v2_router_02::RemoveLiquidityCall::selector(), [0xba, 0xa2, 0xab, 0xde]);
+        assert_eq!(i_uniswap_v2_router_02::RemoveLiquidityETHCall::selector(), [0x02, 0x75, 0x1c, 0xec]);
+        assert_eq!(i_safe::NonceCall::selector(), [0xaf, 0xfe, 0xd0, 0xe0]);
+        assert_eq!(ierc721::TokenOfOwnerByIndexCall::selector(), [0x2f, 0x74, 0x5c, 0x59]);
+        assert_eq!(ierc721::SupportsInterfaceCall::selector(), [0x01, 0xff, 0xc9, 0xa7]);
+        assert_eq!(ierc1155::BalanceOfCall::selector(), [0x00, 0xfd, 0xd5, 0x8e]);
+        assert_eq!(ierc1155::BalanceOfBatchCall::selector(), [0x4e, 0x12, 0x73, 0xf4]);
+        assert_eq!(i_nonfungible_position_manager::PositionsCall::selector(), [0x99, 0xfb, 0xab, 0x88]);
+        assert_eq!(i_uniswap_v3_factory::GetPoolCall::selector(), [0x16, 0x98, 0xee, 0x82]);
+        assert_eq!(i_uniswap_v3_pool::Slot0Call::selector(), [0x38, 0x50, 0xc7, 0xbd]);
+        assert_eq!(i_uniswap_v3_pool::TicksCall::selector(), [0xf3, 0x0d, 0xba, 0x93]);
+        assert_eq!(i_safe::GetThresholdCall::selector(), [0xe7, 0x52, 0x35, 0xb8]);
+        assert_eq!(i_safe::GetOwnersCall::selector(), [0xa0, 0xe6, 0x7e, 0x2b]);
+    }
+
+    #[test]
+    fn test_event_topics_match_constants() {
+        let topic = |s: &str| s.parse::<H256>().unwrap();
+        assert_eq!(ierc20::TransferFilter::signature(), topic(TRANSFER_EVENT_TOPIC));
+        assert_eq!(ierc20::ApprovalFilter::signature(), topic(APPROVAL_EVENT_TOPIC));
+        assert_eq!(i_uniswap_v2_factory::PairCreatedFilter::signature(), topic(PAIR_CREATED_EVENT_TOPIC));
+        assert_eq!(i_uniswap_v2_pair::SwapFilter::signature(), topic(SWAP_EVENT_TOPIC));
+    }
+
+    #[test]
+    fn test_find_known_event() {
+        let (contract, event) = find_known_event_by_name("sync").unwrap();
+        assert_eq!(contract, "UniswapV2Pair");
+        assert_eq!(
+            event.signature(),
+            "0x1c411e9a96e071241c2f21f7726b17ae89e3cab4c78be50e062b03a9fffbbad1".parse::<H256>().unwrap()
+        );
+        assert_eq!(find_known_event(event.signature()).map(|(_, e)| e.name.as_str()), Some("Sync"));
+
+        let (contract, _) = find_known_event_by_name("Approval").unwrap();
+        assert_eq!(contract, "ERC20");
+        assert!(find_known_event_by_name("Deposit").is_none());
+        assert!(find_known_event(H256::zero()).is_none());
+    }
+
+    #[test]
+    fn test_known_method_signature() {
+        let call = ierc20::ApproveCall {
+            spender: Address::repeat_byte(0x11),
+            amount: U256::MAX,
+        };
+        assert_eq!(
+            known_method_signature(&call.encode()).as_deref(),
+            Some("approve(address,uint256)")
+        );
+        assert_eq!(
+            known_method_signature(&[0x38, 0xed, 0x17, 0x39]).as_deref(),
+            Some("swapExactTokensForTokens(uint256,uint256,address[],address,uint256)")
+        );
+        assert_eq!(known_method_signature(&[0xde, 0xad, 0xbe, 0xef]), None);
+        assert_eq!(known_method_signature(&[0x38, 0xed]), None);
+    }
+}
@@ -0,0 +1,272 @@
+use ethers::types::{Address, H256};
+
+/// Uniswap V2 交易对合约的 init code hash（用于 CREATE2 计算交易对地址）
+pub const UNISWAP_V2_INIT_CODE_HASH: &str = "0x96e8ac4277198ff8b6f785478aa9a39f403cb768dd02cbee326c3e7da348845f";
+
+/// 支持的 V2 兼容场所 ID
+pub const V2_VENUE_IDS: [&str; 2] = ["uniswap_v2", "sushiswap"];
+
+/// Uniswap V2 兼容的 DEX 部署（接口相同，Factory、Router02 和交易对 init code hash 不同）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct V2Venue {
+    /// 场所 ID（报价结果中的 venue，也用于 DEX_VENUES 配置）
+    pub id: &'static str,
+    pub name: &'static str,
+    pub factory: &'static str,
+    pub router: &'static str,
+    /// 交易对 init code hash，未内置时交易对地址通过 Factory.getPair 查询
+    pub init_code_hash: Option<&'static str>,
+}
+
+impl V2Venue {
+    pub fn factory_address(&self) -> Address {
+        self.factory.parse().expect("硬编码地址应该有效")
+    }
+
+    pub fn router_address(&self) -> Address {
+        self.router.parse().expect("硬编码地址应该有效")
+    }
+
+    pub fn init_code_hash(&self) -> Option<H256> {
+        self.init_code_hash.map(|hash| hash.parse().expect("硬编码哈希应该有效"))
+    }
+}
+
+/// 单条链上的 Uniswap V2 部署和基础代币地址
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainInfo {
+    pub chain_id: u64,
+    pub name: &'static str,
+    /// 原生代币符号（作为包装代币的别名注册）
+    pub native_symbol: &'static str,
+    pub native_name: &'static str,
+    /// 包装原生代币（WETH、WPOL），所有报价以它为中间代币
+    pub wrapped_native_symbol: &'static str,
+    pub wrapped_native_name: &'static str,
+    pub wrapped_native: &'static str,
+    /// 用于 USD 报价的稳定币（USDC，6 位小数）
+    pub usdc: &'static str,
+    pub uniswap_v2_factory: &'static str,
+    pub uniswap_v2_router: &'static str,
+    /// Uniswap V3 NonfungiblePositionManager（头寸 NFT）
+    pub uniswap_v3_position_manager: &'static str,
+    /// Alchemy 网络名（`https://<network>.g.alchemy.com/v2/<key>`）
+    pub alchemy_network: &'static str,
+    /// CoinGecko 资产平台 ID（测试网没有报价）
+    pub coingecko_platform: Option<&'static str>,
+    /// DefiLlama 链名（`coins.llama.fi` 的 `<chain>:<address>` 前缀，测试网没有报价）
+    pub defillama_chain: Option<&'static str>,
+    /// Safe Transaction Service 官方实例地址
+    pub safe_tx_service: &'static str,
+    /// SushiSwap V2 部署（未部署时为空）
+    pub sushiswap: Option<V2Venue>,
+}
+
+impl ChainInfo {
+    pub fn wrapped_native_address(&self) -> Address {
+        self.wrapped_native.parse().expect("硬编码地址应该有效")
+    }
+
+    pub fn usdc_address(&self) -> Address {
+        self.usdc.parse().expect("硬编码地址应该有效")
+    }
+
+    pub fn uniswap_v3_position_manager_address(&self) -> Address {
+        self.uniswap_v3_position_manager.parse().expect("硬编码地址应该有效")
+    }
+
+    /// 当前链上的 Uniswap V2 部署
+    pub fn uniswap_v2(&self) -> V2Venue {
+        V2Venue {
+            id: "uniswap_v2",
+            name: "Uniswap V2",
+            factory: self.uniswap_v2_factory,
+            router: self.uniswap_v2_router,
+            init_code_hash: Some(UNISWAP_V2_INIT_CODE_HASH),
+        }
+    }
+
+    /// 当前链上部署的所有 V2 兼容场所（Uniswap V2 在前）
+    pub fn v2_venues(&self) -> Vec<V2Venue> {
+        std::iter::once(self.uniswap_v2()).chain(self.sushiswap).collect()
+    }
+}
+
+/// SushiSwap 在以太坊主网的部署
+const SUSHISWAP_MAINNET: V2Venue = V2Venue {
+    id: "sushiswap",
+    name: "SushiSwap",
+    factory: "0xC0AEe478e3658e2610c5F7A4A2E1777cE9e4f2Ac",
+    router: "0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F",
+    init_code_hash: None,
+};
+
+/// SushiSwap 在 Arbitrum、Polygon 等早期扩展链上的部署（地址相同）
+const SUSHISWAP_SIDECHAIN: V2Venue = V2Venue {
+    id: "sushiswap",
+    name: "SushiSwap",
+    factory: "0xc35DADB65012eC5796536bD9864eD8773aBc74C4",
+    router: "0x1b02dA8Cb0d097eB8D57A175b88c7D8b47997506",
+    init_code_hash: None,
+};
+
+pub const MAINNET: ChainInfo = ChainInfo {
+    chain_id: 1,
+    name: "Ethereum",
+    native_symbol: "ETH",
+    native_name: "Ether",
+    wrapped_native_symbol: "WETH",
+    wrapped_native_name: "Wrapped Ether",
+    wrapped_native: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2",
+    usdc: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48",
+    uniswap_v2_factory: "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f",
+    uniswap_v2_router: "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D",
+    uniswap_v3_position_manager: "0xC36442b4a4522E871399CD717aBDD847Ab11FE88",
+    alchemy_network: "eth-mainnet",
+    coingecko_platform: Some("ethereum"),
+    defillama_chain: Some("ethereum"),
+    safe_tx_service: "https://safe-transaction-mainnet.safe.global",
+    sushiswap: Some(SUSHISWAP_MAINNET),
+};
+
+pub const SEPOLIA: ChainInfo = ChainInfo {
+    chain_id: 11155111,
+    name: "Sepolia",
+    native_symbol: "ETH",
+    native_name: "Ether",
+    wrapped_native_symbol: "WETH",
+    wrapped_native_name: "Wrapped Ether",
+    wrapped_native: "0xfFf9976782d46CC05630D1f6eBAb18b2324d6B14",
+    usdc: "0x1c7D4B196Cb0C7B01d743Fbc6116a902379C7238",
+    uniswap_v2_factory: "0xF62c03E08ada871A0bEb309762E260a7a6a880E6",
+    uniswap_v2_router: "0xeE567Fe1712Faf6149d80dA1E6934E354124CfE3",
+    uniswap_v3_position_manager: "0x1238536071E1c677A632429e3655c799b22cDA52",
+    alchemy_network: "eth-sepolia",
+    coingecko_platform: None,
+    defillama_chain: None,
+    safe_tx_service: "https://safe-transaction-sepolia.safe.global",
+    sushiswap: None,
+};
+
+pub const ARBITRUM: ChainInfo = ChainInfo {
+    chain_id: 42161,
+    name: "Arbitrum One",
+    native_symbol: "ETH",
+    native_name: "Ether",
+    wrapped_native_symbol: "WETH",
+    wrapped_native_name: "Wrapped Ether",
+    wrapped_native: "0x82aF49447D8a07e3bd95BD0d56f35241523fBab1",
+    usdc: "0xaf88d065e77c8cC2239327C5EDb3A432268e5831",
+    uniswap_v2_factory: "0xf1D7CC64Fb4452F05c498126312eBE29f30Fbcf9",
+    uniswap_v2_router: "0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24",
+    uniswap_v3_position_manager: "0xC36442b4a4522E871399CD717aBDD847Ab11FE88",
+    alchemy_network: "arb-mainnet",
+    coingecko_platform: Some("arbitrum-one"),
+    defillama_chain: Some("arbitrum"),
+    safe_tx_service: "https://safe-transaction-arbitrum.safe.global",
+    sushiswap: Some(SUSHISWAP_SIDECHAIN),
+};
+
+pub const BASE: ChainInfo = ChainInfo {
+    chain_id: 8453,
+    name: "Base",
+    native_symbol: "ETH",
+    native_name: "Ether",
+    wrapped_native_symbol: "WETH",
+    wrapped_native_name: "Wrapped Ether",
+    wrapped_native: "0x4200000000000000000000000000000000000006",
+    usdc: "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913",
+    uniswap_v2_factory: "0x8909Dc15e40173Ff4699343b6eB8132c65e18eC6",
+    uniswap_v2_router: "0x4752ba5DBc23f44D87826276BF6Fd6b1C372aD24",
+    uniswap_v3_position_manager: "0x03a520b32C04BF3bEEf7BEb72E919cf822Ed34f1",
+    alchemy_network: "base-mainnet",
+    coingecko_platform: Some("base"),
+    defillama_chain: Some("base"),
+    safe_tx_service: "https://safe-transaction-base.safe.global",
+    sushiswap: None,
+};
+
+pub const OPTIMISM: ChainInfo = ChainInfo {
+    chain_id: 10,
+    name: "OP Mainnet",
+    native_symbol: "ETH",
+    native_name: "Ether",
+    wrapped_native_symbol: "WETH",
+    wrapped_native_name: "Wrapped Ether",
+    wrapped_native: "0x4200000000000000000000000000000000000006",
+    usdc: "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85",
+    uniswap_v2_factory: "0x0c3c1c532F1e39EdF36BE9Fe0bE1410313E074Bf",
+    uniswap_v2_router: "0x4A7b5Da61326A6379179b40d00F57E5bbDC962c2",
+    uniswap_v3_position_manager: "0xC36442b4a4522E871399CD717aBDD847Ab11FE88",
+    alchemy_network: "opt-mainnet",
+    coingecko_platform: Some("optimistic-ethereum"),
+    defillama_chain: Some("optimism"),
+    safe_tx_service: "https://safe-transaction-optimism.safe.global",
+    sushiswap: None,
+};
+
+pub const POLYGON: ChainInfo = ChainInfo {
+    chain_id: 137,
+    name: "Polygon PoS",
+    native_symbol: "POL",
+    native_name: "POL",
+    wrapped_native_symbol: "WPOL",
+    wrapped_native_name: "Wrapped POL",
+    wrapped_native: "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270",
+    usdc: "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359",
+    uniswap_v2_factory: "0x9e5A52f57b3038F1B8EeE45F28b3C1967e22799C",
+    uniswap_v2_router: "0xedf6066a2b290C185783862C7F4776A2C8077AD1",
+    uniswap_v3_position_manager: "0xC36442b4a4522E871399CD717aBDD847Ab11FE88",
+    alchemy_network: "polygon-mainnet",
+    coingecko_platform: Some("polygon-pos"),
+    defillama_chain: Some("polygon"),
+    safe_tx_service: "https://safe-transaction-polygon.safe.global",
+    sushiswap: Some(SUSHISWAP_SIDECHAIN),
+};
+
+/// 支持的链
+pub const SUPPORTED_CHAINS: [ChainInfo; 6] = [MAINNET, SEPOLIA, ARBITRUM, BASE, OPTIMISM, POLYGON];
+
+/// 按 chain_id 查找链配置
+pub fn chain_info(chain_id: u64) -> Option<&'static ChainInfo> {
+    SUPPORTED_CHAINS.iter().find(|chain| chain.chain_id == chain_id)
+}
+
+/// 支持的链列表（用于错误提示）
+pub fn supported_chains_description() -> String {
+    SUPPORTED_CHAINS
+        .iter()
+        .map(|chain| format!("{} ({})", chain.chain_id, chain.name))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_chain_registry() {
+        assert_eq!(chain_info(1), Some(&MAINNET));
+        assert_eq!(chain_info(137).unwrap().wrapped_native_symbol, "WPOL");
+        assert_eq!(chain_info(5), None);
+
+        let ids: HashSet<u64> = SUPPORTED_CHAINS.iter().map(|chain| chain.chain_id).collect();
+        assert_eq!(ids.len(), SUPPORTED_CHAINS.len());
+
+        // 所有硬编码地址都能解析
+        for chain in SUPPORTED_CHAINS {
+            chain.wrapped_native_address();
+            chain.usdc_address();
+            chain.uniswap_v3_position_manager_address();
+            for venue in chain.v2_venues() {
+                venue.factory_address();
+                venue.router_address();
+                venue.init_code_hash();
+            }
+        }
+        assert_eq!(MAINNET.v2_venues().len(), 2);
+        assert_eq!(BASE.v2_venues(), vec![BASE.uniswap_v2()]);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::chains::ChainInfo;
+use crate::diagnostics::record_rpc_call;
+use ethers::types::Address;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument};
+
+/// CoinGecko 请求超时时间
+const COINGECKO_TIMEOUT: Duration = Duration::from_secs(10);
+/// 公共 API（Demo Key 通过 x-cg-demo-api-key 头传递，未配置时按免费额度限流）
+const COINGECKO_API_BASE: &str = "https://api.coingecko.com/api/v3";
+
+/// CoinGecko API 错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum CoinGeckoError {
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("CoinGecko 不支持当前链")]
+    UnsupportedChain,
+
+    #[error("CoinGecko 没有该代币的 {0} 价格")]
+    PriceNotFound(String),
+
+    #[error("响应格式错误: {0}")]
+    InvalidResponse(String),
+}
+
+/// CoinGecko 简单价格 API 客户端
+#[derive(Clone)]
+pub struct CoinGeckoClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    /// 当前链的 CoinGecko 平台 ID（如 ethereum、arbitrum-one），测试网为空
+    platform: Option<&'static str>,
+}
+
+impl CoinGeckoClient {
+    /// 创建指定链的 CoinGecko 客户端
+    pub fn new(api_key: Option<String>, chain: &ChainInfo) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(COINGECKO_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            base_url: COINGECKO_API_BASE.to_string(),
+            api_key,
+            platform: chain.coingecko_platform,
+        }
+    }
+
+    /// 当前链是否有 CoinGecko 报价
+    pub fn is_available(&self) -> bool {
+        self.platform.is_some()
+    }
+
+    /// 查询代币价格，`vs_currency` 为 usd 或 eth（不区分大小写）
+    #[instrument(skip(self))]
+    pub async fn token_price(&self, token: Address, vs_currency: &str) -> Result<Decimal, CoinGeckoError> {
+        let platform = self.platform.ok_or(CoinGeckoError::UnsupportedChain)?;
+        let vs_currency = vs_currency.to_lowercase();
+        let contract = format!("{:?}", token);
+
+        let url = format!("{}/simple/token_price/{}", self.base_url, platform);
+        let mut request = self
+            .http
+            .get(&url)
+            .query(&[("contract_addresses", contract.as_str()), ("vs_currencies", vs_currency.as_str())]);
+        if let Some(ref key) = self.api_key {
+            request = request.header("x-cg-demo-api-key", key);
+        }
+
+        let started = Instant::now();
+        let response = async { request.send().await?.error_for_status()?.json().await }.await;
+        record_rpc_call("coingecko_simple_token_price", None, started.elapsed(), 0, response.is_ok());
+        let response: serde_json::Value = response?;
+
+        let price = parse_token_price(&response, &contract, &vs_currency)?;
+        debug!(token = %contract, price = %price, currency = %vs_currency, "CoinGecko 价格");
+        Ok(price)
+    }
+}
+
+/// 解析 simple/token_price 响应：`{"<小写合约地址>": {"usd": 1.0}}`
+fn parse_token_price(response: &serde_json::Value, contract: &str, vs_currency: &str) -> Result<Decimal, CoinGeckoError> {
+    if !response.is_object() {
+        return Err(CoinGeckoError::InvalidResponse(response.to_string()));
+    }
+
+    let value = response
+        .get(contract.to_lowercase())
+        .and_then(|prices| prices.get(vs_currency))
+        .ok_or_else(|| CoinGeckoError::PriceNotFound(vs_currency.to_uppercase()))?;
+
+    // 小额价格可能以科学计数法返回（如 1.2e-7）
+    let text = value.to_string();
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .map(|price| price.normalize())
+        .map_err(|_| CoinGeckoError::InvalidResponse(format!("无效的价格: {}", text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_token_price() {
+        let usdc = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+        let response = serde_json::json!({ usdc: { "usd": 0.999_8, "eth": 1.2e-7 } });
+
+        assert_eq!(parse_token_price(&response, usdc, "usd").unwrap(), Decimal::from_str("0.9998").unwrap());
+        assert_eq!(parse_token_price(&response, usdc, "eth").unwrap(), Decimal::from_str("0.00000012").unwrap());
+
+        // 地址按小写匹配
+        let checksummed = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+        assert!(parse_token_price(&response, checksummed, "usd").is_ok());
+
+        // CoinGecko 未收录的代币返回空对象
+        assert!(matches!(
+            parse_token_price(&serde_json::json!({}), usdc, "usd"),
+            Err(CoinGeckoError::PriceNotFound(_))
+        ));
+        assert!(matches!(
+            parse_token_price(&serde_json::json!([]), usdc, "usd"),
+            Err(CoinGeckoError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_client_requires_platform() {
+        assert!(CoinGeckoClient::new(None, &crate::chains::MAINNET).is_available());
+        assert!(!CoinGeckoClient::new(None, &crate::chains::SEPOLIA).is_available());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::token_registry::TokenRegistry;
+use rmcp::model::{ArgumentInfo, CompletionInfo};
+
+/// 按代币注册表补全的参数名
+const TOKEN_ARGUMENTS: &[&str] = &["token", "from_token", "to_token", "token_address"];
+
+/// 补全代币类参数
+/// 输入以 0x 开头时按地址前缀匹配,否则按符号前缀匹配(不区分大小写);其他参数返回空列表
+pub fn complete_argument(registry: &TokenRegistry, argument: &ArgumentInfo) -> CompletionInfo {
+    if !TOKEN_ARGUMENTS.contains(&argument.name.as_str()) {
+        return CompletionInfo::default();
+    }
+
+    let prefix = argument.value.trim().to_lowercase();
+    let by_address = prefix.starts_with("0x");
+
+    let mut values: Vec<String> = registry
+        .all_tokens()
+        .into_iter()
+        .filter_map(|token| {
+            let candidate = if by_address { token.address } else { token.symbol };
+            candidate.to_lowercase().starts_with(&prefix).then_some(candidate)
+        })
+        .collect();
+    values.sort();
+    values.dedup();
+
+    let total = values.len();
+    let has_more = total > CompletionInfo::MAX_VALUES;
+    values.truncate(CompletionInfo::MAX_VALUES);
+
+    CompletionInfo {
+        values,
+        total: Some(total as u32),
+        has_more: Some(has_more),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argument(name: &str, value: &str) -> ArgumentInfo {
+        ArgumentInfo {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_complete_token_symbol() {
+        let registry = TokenRegistry::new();
+
+        let completion = complete_argument(&registry, &argument("from_token", "us"));
+        assert!(completion.values.contains(&"USDC".to_string()));
+        assert!(completion.values.iter().all(|v| v.starts_with("US")));
+        assert_eq!(completion.has_more, Some(false));
+    }
+
+    #[test]
+    fn test_complete_token_address() {
+        let registry = TokenRegistry::new();
+        let usdc = registry.resolve("USDC").unwrap();
+
+        let completion = complete_argument(&registry, &argument("token", &usdc.address[..8]));
+        assert!(completion.values.contains(&usdc.address));
+    }
+
+    #[test]
+    fn test_complete_other_argument() {
+        let registry = TokenRegistry::new();
+
+        let completion = complete_argument(&registry, &argument("address", "0x"));
+        assert!(completion.values.is_empty());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::config::ComplianceConfig;
+use crate::store::{AuditEntry, Store};
+use ethers::prelude::*;
+use rmcp::ErrorData as McpError;
+use std::collections::HashSet;
+use tracing::{info, warn};
+
+/// 合规检查错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum ComplianceError {
+    #[error("读取制裁名单失败: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("制裁名单第 {line} 行不是有效的地址: {value}")]
+    InvalidAddress { line: usize, value: String },
+}
+
+/// 命中制裁名单时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanctionsAction {
+    /// 拒绝执行
+    Block,
+    /// 允许执行，在结果中标记
+    Flag,
+}
+
+impl SanctionsAction {
+    /// 解析处理方式（不区分大小写）
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.to_lowercase().as_str() {
+            "block" => Ok(Self::Block),
+            "flag" => Ok(Self::Flag),
+            _ => Err(format!("未知的制裁名单处理方式: {} (支持 block、flag)", value)),
+        }
+    }
+}
+
+/// 制裁地址名单
+/// 文件格式：每行一个地址，`#` 之后为注释，空行忽略
+#[derive(Debug, Clone, Default)]
+pub struct SanctionsList {
+    addresses: HashSet<Address>,
+}
+
+impl SanctionsList {
+    /// 从文件加载名单
+    pub fn load(path: &str) -> Result<Self, ComplianceError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// 解析名单内容
+    pub fn parse(content: &str) -> Result<Self, ComplianceError> {
+        let mut addresses = HashSet::new();
+        for (index, line) in content.lines().enumerate() {
+            let value = line.split('#').next().unwrap_or_default().trim();
+            if value.is_empty() {
+                continue;
+            }
+            let address = value.parse::<Address>().map_err(|_| ComplianceError::InvalidAddress {
+                line: index + 1,
+                value: value.to_string(),
+            })?;
+            addresses.insert(address);
+        }
+        Ok(Self { addresses })
+    }
+
+    pub fn contains(&self, address: &Address) -> bool {
+        self.addresses.contains(address)
+    }
+}
+
+/// 筛查结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningOutcome {
+    /// 未命中
+    Clear,
+    /// 命中但允许执行
+    Flagged,
+    /// 命中并拒绝执行
+    Refused,
+}
+
+impl ScreeningOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Clear => "clear",
+            Self::Flagged => "flagged",
+            Self::Refused => "refused",
+        }
+    }
+}
+
+/// 命中名单的交易方
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SanctionedParty {
+    /// 在交互中的角色（如 recipient、target）
+    pub role: String,
+    pub address: String,
+}
+
+/// 单次筛查的结论
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ScreeningDecision {
+    pub outcome: ScreeningOutcome,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<SanctionedParty>,
+}
+
+/// 制裁地址筛查：未配置名单时不做任何检查
+#[derive(Debug, Clone)]
+pub struct ComplianceScreen {
+    list: Option<SanctionsList>,
+    action: SanctionsAction,
+}
+
+impl ComplianceScreen {
+    pub fn new(list: Option<SanctionsList>, action: SanctionsAction) -> Self {
+        Self { list, action }
+    }
+
+    /// 按配置加载名单（未配置 SANCTIONS_LIST_PATH 时禁用）
+    pub fn from_config(config: &ComplianceConfig) -> Result<Self, String> {
+        let action = SanctionsAction::parse(&config.sanctions_action)?;
+        let list = config
+            .sanctions_list_path
+            .as_deref()
+            .map(SanctionsList::load)
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        Ok(Self::new(list, action))
+    }
+
+    /// 按名单筛查交易方（未启用时返回 None）
+    pub fn decide(&self, parties: &[(&str, Address)]) -> Option<ScreeningDecision> {
+        let list = self.list.as_ref()?;
+
+        let matches: Vec<SanctionedParty> = parties
+            .iter()
+            .filter(|(_, address)| list.contains(address))
+            .map(|(role, address)| SanctionedParty {
+                role: role.to_string(),
+                address: format!("{:?}", address),
+            })
+            .collect();
+
+        let outcome = match (matches.is_empty(), self.action) {
+            (true, _) => ScreeningOutcome::Clear,
+            (false, SanctionsAction::Flag) => ScreeningOutcome::Flagged,
+            (false, SanctionsAction::Block) => ScreeningOutcome::Refused,
+        };
+
+        Some(ScreeningDecision { outcome, matches })
+    }
+
+    /// 筛查交易方并把结论写入审计日志
+    /// 命中且处理方式为 block 时返回结构化的拒绝错误；命中但只标记时返回结论，供调用方附在结果中
+    pub fn screen(
+        &self,
+        store: &Store,
+        tool: &str,
+        parties: &[(&str, Address)],
+    ) -> Result<Option<ScreeningDecision>, McpError> {
+        let Some(decision) = self.decide(parties) else {
+            return Ok(None);
+        };
+
+        let screened: Vec<_> = parties
+            .iter()
+            .map(|(role, address)| serde_json::json!({ "role": role, "address": format!("{:?}", address) }))
+            .collect();
+        store.audit(AuditEntry {
+            tool: tool.to_string(),
+            category: "sanctions_screening".to_string(),
+            decision: decision.outcome.as_str().to_string(),
+            details: serde_json::json!({ "parties": screened, "matches": decision.matches }),
+        });
+
+        match decision.outcome {
+            ScreeningOutcome::Clear => {
+                info!(tool, parties = parties.len(), "制裁名单筛查通过");
+                Ok(None)
+            }
+            ScreeningOutcome::Flagged => {
+                warn!(tool, matches = ?decision.matches, "交易方命中制裁名单,已标记");
+                Ok(Some(decision))
+            }
+            ScreeningOutcome::Refused => {
+                warn!(tool, matches = ?decision.matches, "交易方命中制裁名单,已拒绝");
+                let addresses: Vec<_> = decision.matches.iter().map(|m| m.address.as_str()).collect();
+                Err(McpError::invalid_request(
+                    format!("交易方命中制裁名单,已拒绝执行: {}", addresses.join(", ")),
+                    Some(serde_json::json!({
+                        "refused": true,
+                        "reason": "sanctioned_address",
+                        "matches": decision.matches,
+                    })),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ErrorCode;
+
+    const SANCTIONED: &str = "0x8589427373D6D84E98730D7795D8f6f8731FDA16";
+
+    fn screen(action: SanctionsAction) -> ComplianceScreen {
+        let list = SanctionsList::parse(&format!("# OFAC SDN\n\n{}  # Tornado Cash\n", SANCTIONED)).unwrap();
+        ComplianceScreen::new(Some(list), action)
+    }
+
+    #[test]
+    fn test_parse_sanctions_list() {
+        let list = SanctionsList::parse(&format!("# header\n{}\n\n{}\n", SANCTIONED, SANCTIONED.to_lowercase())).unwrap();
+        assert_eq!(list.addresses.len(), 1);
+        assert!(list.contains(&SANCTIONED.parse().unwrap()));
+
+        let err = SanctionsList::parse("# header\nnot-an-address\n").unwrap_err();
+        assert!(matches!(err, ComplianceError::InvalidAddress { line: 2, .. }));
+    }
+
+    #[test]
+    fn test_screen_records_audit_and_refuses() {
+        let store = Store::open(Some(":memory:")).unwrap();
+        let sanctioned: Address = SANCTIONED.parse().unwrap();
+        let clean = Address::repeat_byte(0x11);
+
+        let disabled = ComplianceScreen::new(None, SanctionsAction::Block);
+        assert_eq!(disabled.screen(&store, "t", &[("recipient", sanctioned)]).unwrap(), None);
+
+        let block = screen(SanctionsAction::Block);
+        assert_eq!(block.screen(&store, "relay_transaction", &[("target", clean)]).unwrap(), None);
+
+        let err = block
+            .screen(&store, "relay_transaction", &[("target", sanctioned)])
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_REQUEST);
+        assert_eq!(err.data.unwrap()["reason"], "sanctioned_address");
+
+        let flagged = screen(SanctionsAction::Flag)
+            .screen(&store, "sign_transfer_authorization", &[("recipient", sanctioned)])
+            .unwrap()
+            .unwrap();
+        assert_eq!(flagged.outcome, ScreeningOutcome::Flagged);
+        assert_eq!(flagged.matches[0].role, "recipient");
+
+        let decisions: Vec<_> = store.audit_entries(None, 10).unwrap().into_iter().map(|e| e.decision).collect();
+        assert_eq!(decisions, vec!["flagged", "refused", "clear"]);
+    }
+}
@@ -1,5 +1,24 @@
+use crate::account_abstraction::DEFAULT_ENTRY_POINT;
+use crate::chains::{self, ChainInfo, V2Venue};
+use crate::compliance::ComplianceScreen;
+use crate::logging::warn;
+use crate::signer::SignerBackend;
+use crate::token_registry::TokenRegistry;
+use crate::types::{ReadFinality, TxType};
 use ethers::prelude::*;
 use std::env;
+use std::net::SocketAddr;
+
+/// 默认的路由中间代币（包装原生代币始终参与路由）
+const DEFAULT_ROUTE_INTERMEDIATES: &str = "USDC,USDT,DAI,WBTC";
+/// 默认参与报价比较的链上场所
+const DEFAULT_DEX_VENUES: &str = "uniswap_v2,sushiswap,curve";
+/// 除 V2 兼容场所外支持的链上场所
+const OTHER_DEX_VENUES: [&str; 1] = ["curve"];
+/// 远程代币列表默认刷新间隔（6 小时）
+const DEFAULT_TOKEN_LIST_REFRESH_SECS: u64 = 6 * 3600;
+/// 远程代币列表最短刷新间隔
+const MIN_TOKEN_LIST_REFRESH_SECS: u64 = 60;
 
 /// 服务器配置结构体
 #[derive(Debug, Clone)]
@@ -16,17 +35,29 @@ pub struct ServerConfig {
     pub test_mode: bool,
     /// 测试模式返回的余额值
     pub test_balance: f64,
+    /// 传输层：stdio（默认）或 http（Streamable HTTP / SSE，可供多个客户端共享）
+    pub transport: String,
+    /// HTTP 传输监听地址
+    pub http_bind_addr: String,
+    /// HTTP 传输的 Bearer Token（可选，配置后所有请求都需要携带）
+    pub http_auth_token: Option<String>,
 }
 
 /// 以太坊网络配置
 #[derive(Debug, Clone)]
 pub struct EthereumConfig {
-    /// RPC 节点地址
-    pub rpc_url: Option<String>,
+    /// RPC 节点地址（ETHEREUM_RPC_URLS 配置多个时按顺序故障转移）
+    pub rpc_urls: Vec<String>,
     /// Chain ID
     pub chain_id: u64,
     /// 私钥（用于签名交易）
     pub private_key: Option<String>,
+    /// WebSocket 节点地址（可选，用于订阅新区块和日志）
+    pub ws_url: Option<String>,
+    /// Beacon 节点 API 地址（可选，用于共识层收益估算）
+    pub beacon_api_url: Option<String>,
+    /// 读取余额、价格时的确认深度（区块数，0 表示读取最新区块）
+    pub finality_blocks: u64,
 }
 
 /// 交易配置
@@ -38,6 +69,20 @@ pub struct TradingConfig {
     pub gas_price_strategy: String,
     /// 最大 Gas 限制
     pub max_gas_limit: u64,
+    /// 交易类型（auto/legacy/eip1559，auto 按链上是否支持 EIP-1559 自动选择）
+    pub tx_type: String,
+    /// 执行类工具广播后等待的确认数（包含交易所在区块）
+    pub tx_confirmations: u64,
+    /// 允许的最大价格影响（基点，0 表示不限制），超过时拒绝返回报价
+    pub max_price_impact_bps: u32,
+    /// 是否监听内存池，按同一交易对上的待确认交换动态调整建议滑点
+    pub mempool_watch: bool,
+    /// Uniswap V2 池子流动性（USD）低于该值时 get_token_price 改用 CoinGecko 报价（0 表示只在没有交易对时回退）
+    pub price_fallback_min_liquidity_usd: u64,
+    /// 是否使用 DefiLlama 交叉验证链上价格并报告协议 TVL
+    pub defillama_enabled: bool,
+    /// 链上价格与 DefiLlama 市场价格偏离超过该值（基点）时标记异常
+    pub price_deviation_alert_bps: u32,
 }
 
 /// Uniswap 配置
@@ -47,6 +92,16 @@ pub struct UniswapConfig {
     pub v2_router: String,
     /// Uniswap V3 Router 地址
     pub v3_router: String,
+    /// 是否推送新建交易对通知
+    pub new_pair_notifications: bool,
+    /// 路由搜索除包装原生代币外额外尝试的中间代币（符号或地址，当前链注册表中不存在的符号会被忽略）
+    pub route_intermediates: Vec<String>,
+    /// compare_quotes 比较的链上场所 ID（当前链未部署的场所会被跳过）
+    pub dex_venues: Vec<String>,
+    /// Uniswap V2 子图 GraphQL 地址（配置后价格结果包含 24 小时交易量和手续费）
+    pub v2_subgraph_url: Option<String>,
+    /// Uniswap V3 子图 GraphQL 地址
+    pub v3_subgraph_url: Option<String>,
 }
 
 /// API 密钥配置
@@ -60,6 +115,12 @@ pub struct ApiKeysConfig {
     pub etherscan_api_key: Option<String>,
     /// CoinGecko API Key
     pub coingecko_api_key: Option<String>,
+    /// Gelato Relay 1Balance 赞助 Key（sponsored / erc2771 模式需要）
+    pub gelato_api_key: Option<String>,
+    /// 0x Swap API Key（配置后 compare_quotes 和 swap_tokens 请求 0x 报价）
+    pub zerox_api_key: Option<String>,
+    /// Safe Transaction Service API Key（托管网关需要，自建服务可不配置）
+    pub safe_api_key: Option<String>,
 }
 
 /// 性能配置
@@ -73,6 +134,60 @@ pub struct PerformanceConfig {
     pub rpc_retry_count: u32,
     /// 价格缓存时间（秒）
     pub price_cache_ttl: u64,
+    /// 每个客户端每分钟允许的工具调用次数（0 表示不限流）
+    pub rate_limit_per_minute: u32,
+    /// 每个客户端允许的突发调用次数（令牌桶容量）
+    pub rate_limit_burst: u32,
+}
+
+/// ERC-4337 账户抽象配置
+#[derive(Debug, Clone)]
+pub struct AccountAbstractionConfig {
+    /// Bundler RPC 地址（配置后启用智能账户交易）
+    pub bundler_url: Option<String>,
+    /// Paymaster RPC 地址（可选，用于 Gas 赞助）
+    pub paymaster_url: Option<String>,
+    /// EntryPoint 合约地址
+    pub entry_point: String,
+    /// 智能账户地址（所有者为签名器地址）
+    pub smart_account: Option<String>,
+}
+
+/// Safe 多签配置
+#[derive(Debug, Clone)]
+pub struct SafeConfig {
+    /// Safe 合约地址（配置后执行类工具输出 Safe 交易，不再用签名器直接广播）
+    pub address: Option<String>,
+    /// Safe Transaction Service 地址（未配置时使用当前链的官方实例）
+    pub tx_service_url: Option<String>,
+}
+
+/// 合规配置
+#[derive(Debug, Clone)]
+pub struct ComplianceConfig {
+    /// 制裁地址名单文件路径（配置后筛查交易对手和接收方）
+    pub sanctions_list_path: Option<String>,
+    /// 命中名单时的处理方式（block 拒绝 / flag 标记）
+    pub sanctions_action: String,
+}
+
+/// 签名器配置
+#[derive(Debug, Clone)]
+pub struct SignerConfig {
+    /// 签名后端（private_key / keystore / ledger / aws_kms）
+    pub backend: String,
+    /// 加密 JSON keystore 文件路径（keystore 后端）
+    pub keystore_path: Option<String>,
+    /// keystore 密码（未配置时启动时在终端提示输入）
+    pub keystore_password: Option<String>,
+    /// Ledger Live 派生路径的账户索引（ledger 后端）
+    pub ledger_account_index: usize,
+    /// 自定义 Ledger 派生路径（配置后忽略账户索引）
+    pub ledger_hd_path: Option<String>,
+    /// AWS KMS 密钥 ID 或 ARN（aws_kms 后端）
+    pub aws_kms_key_id: Option<String>,
+    /// 签名器地址（启动时加载签名器后填写）
+    pub address: Option<Address>,
 }
 
 /// 完整配置
@@ -80,12 +195,26 @@ pub struct PerformanceConfig {
 pub struct Config {
     pub server: ServerConfig,
     pub ethereum: EthereumConfig,
+    pub signer: SignerConfig,
     pub trading: TradingConfig,
     pub uniswap: UniswapConfig,
     pub api_keys: ApiKeysConfig,
     pub performance: PerformanceConfig,
+    pub account_abstraction: AccountAbstractionConfig,
+    pub safe: SafeConfig,
+    pub compliance: ComplianceConfig,
     /// 代币注册表文件路径
     pub token_registry_path: Option<String>,
+    /// 是否查询代币在主流代币列表（Uniswap、CoinGecko）中的收录情况
+    pub token_list_check: bool,
+    /// 导入代币注册表的远程代币列表（Token Lists 标准格式）
+    pub token_list_urls: Vec<String>,
+    /// 远程代币列表刷新间隔（秒）
+    pub token_list_refresh_secs: u64,
+    /// SQLite 数据库路径（可选，用于持久化报价、模拟和执行记录）
+    pub database_path: Option<String>,
+    /// 离线报价快照路径（可选，配置后报价类工具基于快照计算，不访问 RPC）
+    pub offline_snapshot_path: Option<String>,
 }
 
 impl Config {
@@ -111,13 +240,19 @@ impl Config {
                 .unwrap_or_else(|_| "100.0".to_string())
                 .parse()
                 .unwrap_or(100.0),
+            transport: env::var("TRANSPORT")
+                .unwrap_or_else(|_| "stdio".to_string())
+                .to_lowercase(),
+            http_bind_addr: env::var("HTTP_BIND_ADDR")
+                .unwrap_or_else(|_| "127.0.0.1:8080".to_string()),
+            http_auth_token: env::var("HTTP_AUTH_TOKEN").ok().filter(|s| !s.is_empty()),
         };
 
         let ethereum = EthereumConfig {
-            rpc_url: env::var("ETHEREUM_RPC_URL")
-                .ok()
-                .filter(|s| !s.is_empty())
-                .or_else(|| Some("https://eth.llamarpc.com".to_string())),
+            rpc_urls: parse_rpc_urls(
+                env::var("ETHEREUM_RPC_URLS").ok(),
+                env::var("ETHEREUM_RPC_URL").ok(),
+            ),
             chain_id: env::var("CHAIN_ID")
                 .ok()
                 .and_then(|s| s.parse().ok())
@@ -125,6 +260,41 @@ impl Config {
             private_key: env::var("ETH_PRIVATE_KEY")
                 .ok()
                 .filter(|s| !s.is_empty()),
+            ws_url: env::var("ETHEREUM_WS_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            beacon_api_url: env::var("BEACON_API_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            finality_blocks: env::var("FINALITY_BLOCKS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+        };
+
+        let signer = SignerConfig {
+            backend: env::var("SIGNER_BACKEND")
+                .ok()
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| "private_key".to_string())
+                .to_lowercase(),
+            keystore_path: env::var("KEYSTORE_PATH")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            keystore_password: env::var("KEYSTORE_PASSWORD")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            ledger_account_index: env::var("LEDGER_ACCOUNT_INDEX")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            ledger_hd_path: env::var("LEDGER_HD_PATH")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            aws_kms_key_id: env::var("AWS_KMS_KEY_ID")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            address: None,
         };
 
         let trading = TradingConfig {
@@ -138,13 +308,60 @@ impl Config {
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(500000),
+            tx_type: env::var("TX_TYPE").unwrap_or_else(|_| "auto".to_string()),
+            tx_confirmations: env::var("TX_CONFIRMATIONS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1),
+            max_price_impact_bps: env::var("MAX_PRICE_IMPACT_BPS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1000),
+            mempool_watch: env::var("MEMPOOL_WATCH")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
+            price_fallback_min_liquidity_usd: env::var("PRICE_FALLBACK_MIN_LIQUIDITY_USD")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(10_000),
+            defillama_enabled: env::var("DEFILLAMA_ENABLED")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
+            price_deviation_alert_bps: env::var("PRICE_DEVIATION_ALERT_BPS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(500),
         };
 
         let uniswap = UniswapConfig {
-            v2_router: env::var("UNISWAP_V2_ROUTER")
-                .unwrap_or_else(|_| "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D".to_string()),
+            // 默认使用当前链上的 Router02 部署
+            v2_router: env::var("UNISWAP_V2_ROUTER").unwrap_or_else(|_| {
+                chains::chain_info(ethereum.chain_id)
+                    .unwrap_or(&chains::MAINNET)
+                    .uniswap_v2_router
+                    .to_string()
+            }),
             v3_router: env::var("UNISWAP_V3_ROUTER")
                 .unwrap_or_else(|_| "0xE592427A0AEce92De3Edee1F18E0157C05861564".to_string()),
+            new_pair_notifications: env::var("NEW_PAIR_NOTIFICATIONS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
+            route_intermediates: parse_csv(
+                &env::var("ROUTE_INTERMEDIATES").unwrap_or_else(|_| DEFAULT_ROUTE_INTERMEDIATES.to_string()),
+            ),
+            dex_venues: parse_csv(&env::var("DEX_VENUES").unwrap_or_else(|_| DEFAULT_DEX_VENUES.to_string()))
+                .into_iter()
+                .map(|venue| venue.to_lowercase())
+                .collect(),
+            v2_subgraph_url: env::var("UNISWAP_V2_SUBGRAPH_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            v3_subgraph_url: env::var("UNISWAP_V3_SUBGRAPH_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
         };
 
         let api_keys = ApiKeysConfig {
@@ -160,6 +377,15 @@ impl Config {
             coingecko_api_key: env::var("COINGECKO_API_KEY")
                 .ok()
                 .filter(|s| !s.is_empty()),
+            gelato_api_key: env::var("GELATO_API_KEY")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            zerox_api_key: env::var("ZEROX_API_KEY")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            safe_api_key: env::var("SAFE_API_KEY")
+                .ok()
+                .filter(|s| !s.is_empty()),
         };
 
         let performance = PerformanceConfig {
@@ -179,28 +405,126 @@ impl Config {
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(60),
+            rate_limit_per_minute: env::var("RATE_LIMIT_PER_MINUTE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            rate_limit_burst: env::var("RATE_LIMIT_BURST")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(10),
+        };
+
+        let account_abstraction = AccountAbstractionConfig {
+            bundler_url: env::var("AA_BUNDLER_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            paymaster_url: env::var("AA_PAYMASTER_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            entry_point: env::var("AA_ENTRY_POINT")
+                .ok()
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| DEFAULT_ENTRY_POINT.to_string()),
+            smart_account: env::var("AA_SMART_ACCOUNT")
+                .ok()
+                .filter(|s| !s.is_empty()),
+        };
+
+        let safe = SafeConfig {
+            address: env::var("SAFE_ADDRESS")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            tx_service_url: env::var("SAFE_TX_SERVICE_URL")
+                .ok()
+                .filter(|s| !s.is_empty()),
+        };
+
+        let compliance = ComplianceConfig {
+            sanctions_list_path: env::var("SANCTIONS_LIST_PATH")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            sanctions_action: env::var("SANCTIONS_ACTION").unwrap_or_else(|_| "block".to_string()),
         };
 
         let token_registry_path = env::var("TOKEN_REGISTRY_PATH")
             .ok()
             .filter(|s| !s.is_empty());
 
+        let token_list_check = env::var("TOKEN_LIST_CHECK")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(true);
+
+        let token_list_urls = parse_csv(&env::var("TOKEN_LIST_URLS").unwrap_or_default());
+        let token_list_refresh_secs = env::var("TOKEN_LIST_REFRESH_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_TOKEN_LIST_REFRESH_SECS);
+
+        let database_path = env::var("DATABASE_PATH")
+            .ok()
+            .filter(|s| !s.is_empty());
+
+        let offline_snapshot_path = env::var("OFFLINE_SNAPSHOT_PATH")
+            .ok()
+            .filter(|s| !s.is_empty());
+
         Ok(Config {
             server,
             ethereum,
+            signer,
             trading,
             uniswap,
             api_keys,
             performance,
+            account_abstraction,
+            safe,
+            compliance,
             token_registry_path,
+            token_list_check,
+            token_list_urls,
+            token_list_refresh_secs,
+            database_path,
+            offline_snapshot_path,
         })
     }
 
     /// 验证配置的有效性
     pub fn validate(&self) -> anyhow::Result<()> {
-        // 如果不是测试模式，需要配置 RPC URL
-        if !self.server.test_mode && self.ethereum.rpc_url.is_none() {
-            anyhow::bail!("非测试模式下必须配置 ETHEREUM_RPC_URL");
+        // 如果不是测试模式，需要配置 RPC URL（离线快照模式除外）
+        if !self.server.test_mode
+            && self.ethereum.rpc_urls.is_empty()
+            && self.offline_snapshot_path.is_none()
+        {
+            anyhow::bail!("非测试模式下必须配置 ETHEREUM_RPC_URL 或 OFFLINE_SNAPSHOT_PATH");
+        }
+
+        // 验证 RPC 节点地址（不在错误中输出 URL，避免泄露 API Key）
+        if let Some(index) = self
+            .ethereum
+            .rpc_urls
+            .iter()
+            .position(|url| reqwest::Url::parse(url).is_err())
+        {
+            anyhow::bail!("第 {} 个 RPC 节点地址无效，请检查 ETHEREUM_RPC_URLS / ETHEREUM_RPC_URL", index + 1);
+        }
+
+        if let Some(ref ws_url) = self.ethereum.ws_url
+            && !(ws_url.starts_with("ws://") || ws_url.starts_with("wss://"))
+        {
+            anyhow::bail!("ETHEREUM_WS_URL 必须以 ws:// 或 wss:// 开头");
+        }
+
+        // 验证传输层
+        match self.server.transport.as_str() {
+            "stdio" => {}
+            "http" => {
+                if self.server.http_bind_addr.parse::<SocketAddr>().is_err() {
+                    anyhow::bail!("HTTP_BIND_ADDR 不是有效的监听地址（例如 127.0.0.1:8080）");
+                }
+            }
+            _ => anyhow::bail!("TRANSPORT 必须是 stdio 或 http 之一"),
         }
 
         // 验证测试余额值
@@ -213,6 +537,65 @@ impl Config {
             anyhow::bail!("DEFAULT_SLIPPAGE_BPS 不能超过 10000（100%）");
         }
 
+        // 验证价格影响上限
+        if self.trading.max_price_impact_bps > 10000 {
+            anyhow::bail!("MAX_PRICE_IMPACT_BPS 不能超过 10000（100%）");
+        }
+
+        // 验证确认数
+        if !(1..=64).contains(&self.trading.tx_confirmations) {
+            anyhow::bail!("TX_CONFIRMATIONS 必须在 1 到 64 之间");
+        }
+
+        // 验证账户抽象配置
+        if self.account_abstraction.entry_point.parse::<Address>().is_err() {
+            anyhow::bail!("AA_ENTRY_POINT 不是有效的地址");
+        }
+        if let Some(ref account) = self.account_abstraction.smart_account
+            && account.parse::<Address>().is_err()
+        {
+            anyhow::bail!("AA_SMART_ACCOUNT 不是有效的地址");
+        }
+
+        // 验证 Safe 配置
+        if let Some(ref safe) = self.safe.address
+            && safe.parse::<Address>().is_err()
+        {
+            anyhow::bail!("SAFE_ADDRESS 不是有效的地址");
+        }
+        if let Some(ref url) = self.safe.tx_service_url
+            && !reqwest::Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https"))
+        {
+            anyhow::bail!("SAFE_TX_SERVICE_URL 不是有效的 HTTP 地址");
+        }
+
+        // 验证制裁名单（名单无法加载时拒绝启动，避免在未筛查的情况下执行）
+        if let Err(e) = ComplianceScreen::from_config(&self.compliance) {
+            anyhow::bail!("制裁名单配置无效: {}", e);
+        }
+
+        // 筛查结论只有配置数据库时才写入 audit_log,否则 get_audit_log 查不到
+        if self.compliance.sanctions_list_path.is_some() && self.database_path.is_none() {
+            warn!("已配置 SANCTIONS_LIST_PATH 但未配置 DATABASE_PATH,筛查结论只写入日志,不会持久化到 audit_log");
+        }
+
+        // 验证代币注册表文件（包含无效代币时拒绝启动，避免符号解析到错误的地址）
+        if let Err(e) = TokenRegistry::load(self.chain(), self.token_registry_path.as_deref()) {
+            anyhow::bail!("TOKEN_REGISTRY_PATH 配置无效: {}", e);
+        }
+
+        // 验证远程代币列表
+        if let Some(url) = self
+            .token_list_urls
+            .iter()
+            .find(|url| !reqwest::Url::parse(url).is_ok_and(|u| matches!(u.scheme(), "http" | "https")))
+        {
+            anyhow::bail!("TOKEN_LIST_URLS 包含无效的地址: {}", url);
+        }
+        if !self.token_list_urls.is_empty() && self.token_list_refresh_secs < MIN_TOKEN_LIST_REFRESH_SECS {
+            anyhow::bail!("TOKEN_LIST_REFRESH_SECS 不能小于 {}", MIN_TOKEN_LIST_REFRESH_SECS);
+        }
+
         // 验证 Gas 价格策略
         let valid_strategies = ["fast", "standard", "slow"];
         if !valid_strategies.contains(&self.trading.gas_price_strategy.as_str()) {
@@ -221,12 +604,53 @@ impl Config {
             );
         }
 
-        // 验证 Chain ID
-        let valid_chain_ids = [1, 5, 11155111]; // 主网、Goerli、Sepolia
-        if !valid_chain_ids.contains(&self.ethereum.chain_id) {
-            eprintln!(
-                "⚠️  警告: Chain ID {} 不在常用网络列表中",
-                self.ethereum.chain_id
+        // 验证报价场所
+        if let Some(venue) = self
+            .uniswap
+            .dex_venues
+            .iter()
+            .find(|venue| {
+                !chains::V2_VENUE_IDS.contains(&venue.as_str()) && !OTHER_DEX_VENUES.contains(&venue.as_str())
+            })
+        {
+            anyhow::bail!(
+                "DEX_VENUES 包含未知场所 {}，支持: {}, {}",
+                venue,
+                chains::V2_VENUE_IDS.join(", "),
+                OTHER_DEX_VENUES.join(", ")
+            );
+        }
+
+        // 验证签名后端（未编译的后端在启动时拒绝，避免执行时才发现无法签名）
+        let signer_backend = SignerBackend::parse(&self.signer.backend).map_err(|e| anyhow::anyhow!("SIGNER_BACKEND 配置无效: {}", e))?;
+        if let Some(feature) = signer_backend.required_feature() {
+            anyhow::bail!("SIGNER_BACKEND={} 需要使用 --features {} 编译", signer_backend.as_str(), feature);
+        }
+        match signer_backend {
+            SignerBackend::Keystore => match self.signer.keystore_path.as_deref() {
+                None => anyhow::bail!("SIGNER_BACKEND=keystore 需要配置 KEYSTORE_PATH"),
+                Some(path) if !std::path::Path::new(path).is_file() => {
+                    anyhow::bail!("KEYSTORE_PATH 文件不存在: {}", path)
+                }
+                Some(_) => {}
+            },
+            SignerBackend::AwsKms if self.signer.aws_kms_key_id.is_none() => {
+                anyhow::bail!("SIGNER_BACKEND=aws_kms 需要配置 AWS_KMS_KEY_ID")
+            }
+            _ => {}
+        }
+
+        // 验证交易类型
+        if let Err(e) = TxType::parse_preference(&self.trading.tx_type) {
+            anyhow::bail!("TX_TYPE 配置无效: {}", e);
+        }
+
+        // 验证 Chain ID（需要已知该链的 Uniswap V2 部署和基础代币地址）
+        if chains::chain_info(self.ethereum.chain_id).is_none() {
+            anyhow::bail!(
+                "不支持的 CHAIN_ID {}，支持的链: {}",
+                self.ethereum.chain_id,
+                chains::supported_chains_description()
             );
         }
 
@@ -236,14 +660,19 @@ impl Config {
     /// 获取用于模拟的钱包地址
     ///
     /// 优先级：
-    /// 1. 从 private_key 派生地址
-    /// 2. 使用知名的高余额地址（Vitalik 地址）作为默认模拟地址
+    /// 1. 已加载的签名器地址（keystore、Ledger、AWS KMS）
+    /// 2. 从 private_key 派生地址
+    /// 3. 使用知名的高余额地址（Vitalik 地址）作为默认模拟地址
     pub fn get_simulation_address(&self) -> Address {
+        if let Some(address) = self.signer.address {
+            return address;
+        }
+
         // 尝试从 private_key 派生地址
-        if let Some(ref key_str) = self.ethereum.private_key {
-            if let Ok(wallet) = key_str.parse::<LocalWallet>() {
-                return wallet.address();
-            }
+        if let Some(ref key_str) = self.ethereum.private_key
+            && let Ok(wallet) = key_str.parse::<LocalWallet>()
+        {
+            return wallet.address();
         }
 
         // 使用 Vitalik 的地址作为默认模拟地址（已知有大量余额和代币）
@@ -253,6 +682,64 @@ impl Config {
             .expect("硬编码地址应该有效")
     }
 
+    /// 当前配置的链（CHAIN_ID 已在配置校验中验证，未知链回退到主网）
+    pub fn chain(&self) -> &'static ChainInfo {
+        chains::chain_info(self.ethereum.chain_id).unwrap_or(&chains::MAINNET)
+    }
+
+    /// Safe 模式使用的 Safe 合约地址（未配置 SAFE_ADDRESS 时为 None）
+    pub fn safe_address(&self) -> Option<Address> {
+        self.safe.address.as_deref().and_then(|safe| safe.parse().ok())
+    }
+
+    /// Safe Transaction Service 地址：SAFE_TX_SERVICE_URL 优先，其次当前链的官方实例
+    pub fn safe_tx_service_url(&self) -> String {
+        self.safe
+            .tx_service_url
+            .clone()
+            .unwrap_or_else(|| self.chain().safe_tx_service.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    /// 当前链上已配置（DEX_VENUES）且已部署的 V2 兼容场所
+    pub fn v2_venues(&self) -> Vec<V2Venue> {
+        self.chain()
+            .v2_venues()
+            .into_iter()
+            .filter(|venue| self.dex_venue_enabled(venue.id))
+            .collect()
+    }
+
+    /// DEX_VENUES 是否包含该场所
+    pub fn dex_venue_enabled(&self, id: &str) -> bool {
+        self.uniswap.dex_venues.iter().any(|venue| venue == id)
+    }
+
+    /// 交易类型偏好：单次调用指定的值优先，否则使用 TX_TYPE 配置
+    /// 返回 None 表示自动探测
+    pub fn tx_type_preference(&self, override_value: Option<&str>) -> Result<Option<TxType>, String> {
+        TxType::parse_preference(override_value.unwrap_or(&self.trading.tx_type))
+    }
+
+    /// 价格影响上限：单次调用指定的值优先，否则使用 MAX_PRICE_IMPACT_BPS 配置，0 表示不限制
+    pub fn price_impact_limit(&self, override_value: Option<u32>) -> Result<Option<u32>, String> {
+        match override_value.unwrap_or(self.trading.max_price_impact_bps) {
+            0 => Ok(None),
+            bps if bps > 10000 => Err(format!("价格影响上限无效: {} bps (必须 ≤ 10000，即 ≤ 100%)", bps)),
+            bps => Ok(Some(bps)),
+        }
+    }
+
+    /// 读取确认深度：单次调用指定的值优先，否则 FINALITY_BLOCKS > 0 时默认 confirmed
+    pub fn read_finality(&self, override_value: Option<&str>) -> Result<ReadFinality, String> {
+        match override_value {
+            Some(value) => ReadFinality::parse(value),
+            None if self.ethereum.finality_blocks > 0 => Ok(ReadFinality::Confirmed),
+            None => Ok(ReadFinality::Latest),
+        }
+    }
+
     /// 打印配置信息（隐藏敏感信息）
     pub fn print_info(&self) {
         eprintln!("📋 配置信息:");
@@ -265,9 +752,19 @@ impl Config {
         if self.server.test_mode {
             eprintln!("  测试余额: {} ETH", self.server.test_balance);
         }
+        if self.server.transport == "http" {
+            eprintln!("  传输层: HTTP ({})", self.server.http_bind_addr);
+            if self.server.http_auth_token.is_some() {
+                eprintln!("  Bearer 认证: ✅ 已配置");
+            } else {
+                eprintln!("  Bearer 认证: ❌ 未配置");
+            }
+        } else {
+            eprintln!("  传输层: stdio");
+        }
 
         eprintln!("\n🌐 以太坊网络:");
-        if let Some(ref rpc_url) = self.ethereum.rpc_url {
+        for rpc_url in &self.ethereum.rpc_urls {
             // 隐藏 API Key 部分
             let masked_url = if rpc_url.contains("?") {
                 rpc_url.split('?').next().unwrap_or(rpc_url).to_string() + "?***"
@@ -276,12 +773,31 @@ impl Config {
             };
             eprintln!("  RPC 节点: {}", masked_url);
         }
+        if self.ethereum.ws_url.is_some() {
+            eprintln!("  WebSocket 节点: ✅ 已配置（新区块订阅）");
+        }
         eprintln!("  Chain ID: {}", self.ethereum.chain_id);
 
-        if self.ethereum.private_key.is_some() {
-            eprintln!("  私钥: ✅ 已配置");
-        } else {
-            eprintln!("  私钥: ❌ 未配置（只读模式）");
+        match self.signer.backend.as_str() {
+            "private_key" if self.ethereum.private_key.is_some() => eprintln!("  私钥: ✅ 已配置"),
+            "private_key" => eprintln!("  私钥: ❌ 未配置（只读模式）"),
+            "keystore" => eprintln!(
+                "  签名器: keystore ({})",
+                self.signer.keystore_path.as_deref().unwrap_or_default()
+            ),
+            "ledger" => match self.signer.ledger_hd_path.as_deref() {
+                Some(path) => eprintln!("  签名器: Ledger ({})", path),
+                None => eprintln!("  签名器: Ledger (Ledger Live 账户 {})", self.signer.ledger_account_index),
+            },
+            backend => eprintln!("  签名器: {}", backend),
+        }
+
+        if let Some(ref beacon_url) = self.ethereum.beacon_api_url {
+            eprintln!("  Beacon API: {}", beacon_url);
+        }
+
+        if self.ethereum.finality_blocks > 0 {
+            eprintln!("  确认深度: {} 个区块", self.ethereum.finality_blocks);
         }
 
         eprintln!("\n💱 交易配置:");
@@ -292,10 +808,55 @@ impl Config {
         );
         eprintln!("  Gas 策略: {}", self.trading.gas_price_strategy);
         eprintln!("  最大 Gas: {}", self.trading.max_gas_limit);
+        eprintln!("  交易类型: {}", self.trading.tx_type);
+        eprintln!("  确认数: {}", self.trading.tx_confirmations);
+        if self.trading.max_price_impact_bps > 0 {
+            eprintln!(
+                "  价格影响上限: {} bps ({}%)",
+                self.trading.max_price_impact_bps,
+                self.trading.max_price_impact_bps as f64 / 100.0
+            );
+        } else {
+            eprintln!("  价格影响上限: 不限制");
+        }
+        if self.trading.mempool_watch {
+            eprintln!("  内存池动态滑点: ✅ 已启用");
+        }
+        if self.trading.price_fallback_min_liquidity_usd > 0 {
+            eprintln!(
+                "  CoinGecko 回退: 流动性低于 ${}",
+                self.trading.price_fallback_min_liquidity_usd
+            );
+        }
+        if self.trading.defillama_enabled {
+            eprintln!(
+                "  DefiLlama 价格校验: ✅ 偏离超过 {} bps 时告警",
+                self.trading.price_deviation_alert_bps
+            );
+        }
 
         eprintln!("\n🦄 Uniswap:");
         eprintln!("  V2 Router: {}", self.uniswap.v2_router);
         eprintln!("  V3 Router: {}", self.uniswap.v3_router);
+        if self.uniswap.new_pair_notifications {
+            eprintln!("  新交易对通知: ✅ 已启用");
+        }
+        if self.uniswap.v2_subgraph_url.is_some() || self.uniswap.v3_subgraph_url.is_some() {
+            let status = |url: &Option<String>| if url.is_some() { "✅" } else { "❌" };
+            eprintln!(
+                "  子图: V2 {} / V3 {}",
+                status(&self.uniswap.v2_subgraph_url),
+                status(&self.uniswap.v3_subgraph_url)
+            );
+        }
+        if !self.uniswap.route_intermediates.is_empty() {
+            eprintln!("  路由中间代币: {}", self.uniswap.route_intermediates.join(", "));
+        }
+        let mut venues: Vec<&str> = self.v2_venues().iter().map(|venue| venue.name).collect();
+        if self.dex_venue_enabled("curve") {
+            venues.push("Curve");
+        }
+        eprintln!("  报价场所: {}", venues.join(", "));
 
         eprintln!("\n🔑 API 密钥:");
         if self.api_keys.alchemy_api_key.is_some() {
@@ -310,6 +871,12 @@ impl Config {
         if self.api_keys.coingecko_api_key.is_some() {
             eprintln!("  CoinGecko: ✅ 已配置");
         }
+        if self.api_keys.gelato_api_key.is_some() {
+            eprintln!("  Gelato Relay: ✅ 已配置");
+        }
+        if self.api_keys.zerox_api_key.is_some() {
+            eprintln!("  0x Swap API: ✅ 已配置");
+        }
 
         eprintln!("\n⚡ 性能:");
         eprintln!("  HTTP 超时: {}s", self.performance.http_timeout);
@@ -319,11 +886,82 @@ impl Config {
         );
         eprintln!("  RPC 重试: {}", self.performance.rpc_retry_count);
         eprintln!("  价格缓存: {}s", self.performance.price_cache_ttl);
+        if self.performance.rate_limit_per_minute > 0 {
+            eprintln!(
+                "  限流: {} 次/分钟 (突发 {})",
+                self.performance.rate_limit_per_minute, self.performance.rate_limit_burst
+            );
+        }
+
+        if self.account_abstraction.bundler_url.is_some() {
+            eprintln!("\n🧾 账户抽象 (ERC-4337):");
+            eprintln!("  EntryPoint: {}", self.account_abstraction.entry_point);
+            match self.account_abstraction.smart_account {
+                Some(ref account) => eprintln!("  智能账户: {}", account),
+                None => eprintln!("  智能账户: ❌ 未配置"),
+            }
+            if self.account_abstraction.paymaster_url.is_some() {
+                eprintln!("  Paymaster: ✅ 已配置");
+            }
+        }
+
+        if let Some(ref safe) = self.safe.address {
+            eprintln!("\n🔐 Safe 多签:");
+            eprintln!("  Safe 地址: {}", safe);
+            eprintln!("  交易服务: {}", self.safe_tx_service_url());
+            if self.api_keys.safe_api_key.is_some() {
+                eprintln!("  API Key: ✅ 已配置");
+            }
+        }
+
+        if let Some(ref path) = self.compliance.sanctions_list_path {
+            eprintln!("\n🛡️  制裁名单: {} (命中时 {})", path, self.compliance.sanctions_action);
+        }
 
         if let Some(ref path) = self.token_registry_path {
             eprintln!("\n📄 代币注册表: {}", path);
         }
+
+        if !self.token_list_urls.is_empty() {
+            eprintln!(
+                "\n📋 远程代币列表: {} 个 (每 {} 秒刷新)",
+                self.token_list_urls.len(),
+                self.token_list_refresh_secs
+            );
+        }
+
+        if let Some(ref path) = self.database_path {
+            eprintln!("\n💾 持久化数据库: {}", path);
+        }
+
+        if let Some(ref path) = self.offline_snapshot_path {
+            eprintln!("\n📦 离线报价快照: {}", path);
+        }
+    }
+}
+
+/// 解析逗号分隔的列表，忽略空白项
+fn parse_csv(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 解析 RPC 节点列表：ETHEREUM_RPC_URLS（逗号分隔）优先，其次 ETHEREUM_RPC_URL，都未配置时使用公共节点
+fn parse_rpc_urls(urls: Option<String>, url: Option<String>) -> Vec<String> {
+    let urls = parse_csv(&urls.unwrap_or_default());
+    if !urls.is_empty() {
+        return urls;
     }
+
+    let url = url
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "https://eth.llamarpc.com".to_string());
+    vec![url]
 }
 
 #[cfg(test)]
@@ -357,6 +995,93 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_chain_id_validation() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+
+        config.ethereum.chain_id = 8453;
+        assert!(config.validate().is_ok());
+        assert_eq!(config.chain().name, "Base");
+
+        // 没有内置地址的链直接拒绝
+        config.ethereum.chain_id = 5;
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("不支持的 CHAIN_ID 5"), "{}", err);
+        assert!(err.contains("42161 (Arbitrum One)"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_rpc_urls() {
+        let urls = parse_rpc_urls(
+            Some(" https://a.example.com , https://b.example.com/v2/key,,".to_string()),
+            Some("https://single.example.com".to_string()),
+        );
+        assert_eq!(urls, vec!["https://a.example.com", "https://b.example.com/v2/key"]);
+
+        // 未配置 ETHEREUM_RPC_URLS 时回退到 ETHEREUM_RPC_URL，再回退到公共节点
+        assert_eq!(
+            parse_rpc_urls(Some(" ".to_string()), Some("https://single.example.com".to_string())),
+            vec!["https://single.example.com"]
+        );
+        assert_eq!(parse_rpc_urls(None, Some(String::new())), vec!["https://eth.llamarpc.com"]);
+
+        let mut config = Config::from_env().expect("应该能创建配置");
+        config.ethereum.rpc_urls = vec!["https://a.example.com".to_string(), "not a url".to_string()];
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("第 2 个 RPC 节点地址无效"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        assert_eq!(parse_csv(" USDC, dai ,,WBTC "), vec!["USDC", "dai", "WBTC"]);
+        assert!(parse_csv(" , ").is_empty());
+        assert_eq!(parse_csv(DEFAULT_ROUTE_INTERMEDIATES), vec!["USDC", "USDT", "DAI", "WBTC"]);
+    }
+
+    #[test]
+    fn test_dex_venues() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+        config.ethereum.chain_id = 1;
+        config.uniswap.dex_venues = vec!["uniswap_v2".to_string(), "sushiswap".to_string()];
+        let ids: Vec<_> = config.v2_venues().iter().map(|venue| venue.id).collect();
+        assert_eq!(ids, vec!["uniswap_v2", "sushiswap"]);
+
+        // 当前链未部署的场所被跳过
+        config.ethereum.chain_id = 8453;
+        assert_eq!(config.v2_venues().len(), 1);
+
+        config.uniswap.dex_venues = vec!["pancakeswap".to_string()];
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("DEX_VENUES 包含未知场所 pancakeswap"), "{}", err);
+    }
+
+    #[test]
+    fn test_transport_validation() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+        assert_eq!(config.server.transport, "stdio");
+
+        config.server.transport = "http".to_string();
+        config.server.http_bind_addr = "0.0.0.0:3000".to_string();
+        assert!(config.validate().is_ok());
+
+        config.server.http_bind_addr = "localhost".to_string();
+        assert!(config.validate().is_err());
+
+        config.server.transport = "websocket".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_ws_url_validation() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+
+        config.ethereum.ws_url = Some("wss://eth-mainnet.g.alchemy.com/v2/key".to_string());
+        assert!(config.validate().is_ok());
+
+        config.ethereum.ws_url = Some("https://eth.llamarpc.com".to_string());
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_gas_strategy_validation() {
         let mut config = Config::from_env().expect("应该能创建配置");
@@ -375,4 +1100,122 @@ mod tests {
         config.trading.gas_price_strategy = "invalid".to_string();
         assert!(config.validate().is_err());
     }
+
+    #[test]
+    fn test_read_finality_default() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+
+        config.ethereum.finality_blocks = 0;
+        assert_eq!(config.read_finality(None).unwrap(), ReadFinality::Latest);
+
+        config.ethereum.finality_blocks = 12;
+        assert_eq!(config.read_finality(None).unwrap(), ReadFinality::Confirmed);
+        assert_eq!(config.read_finality(Some("finalized")).unwrap(), ReadFinality::Finalized);
+        assert!(config.read_finality(Some("pending")).is_err());
+    }
+
+    #[test]
+    fn test_tx_type_preference() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+
+        config.trading.tx_type = "legacy".to_string();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.tx_type_preference(None).unwrap(), Some(TxType::Legacy));
+        // 单次调用指定的值优先
+        assert_eq!(config.tx_type_preference(Some("auto")).unwrap(), None);
+
+        config.trading.tx_type = "type3".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_tx_confirmations_validation() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+
+        config.trading.tx_confirmations = 3;
+        assert!(config.validate().is_ok());
+        config.trading.tx_confirmations = 0;
+        assert!(config.validate().unwrap_err().to_string().contains("TX_CONFIRMATIONS"));
+    }
+
+    #[test]
+    fn test_signer_validation() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+
+        config.signer.backend = "trezor".to_string();
+        assert!(config.validate().unwrap_err().to_string().contains("SIGNER_BACKEND"));
+
+        config.signer.backend = "keystore".to_string();
+        config.signer.keystore_path = None;
+        assert!(config.validate().unwrap_err().to_string().contains("KEYSTORE_PATH"));
+        config.signer.keystore_path = Some("/nonexistent/keystore.json".to_string());
+        assert!(config.validate().unwrap_err().to_string().contains("文件不存在"));
+
+        // 默认构建不包含 Ledger 支持
+        config.signer.backend = "ledger".to_string();
+        assert_eq!(config.validate().is_ok(), cfg!(feature = "ledger"));
+
+        // 加载签名器后优先使用签名器地址模拟
+        let address = Address::repeat_byte(0x11);
+        config.signer.address = Some(address);
+        assert_eq!(config.get_simulation_address(), address);
+    }
+
+    #[test]
+    fn test_safe_config() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+        config.ethereum.chain_id = 8453;
+        config.safe.tx_service_url = None;
+        assert_eq!(config.safe_tx_service_url(), "https://safe-transaction-base.safe.global");
+
+        config.safe.tx_service_url = Some("https://safe.example.com/".to_string());
+        assert_eq!(config.safe_tx_service_url(), "https://safe.example.com");
+        config.safe.tx_service_url = Some("ftp://safe.example.com".to_string());
+        assert!(config.validate().unwrap_err().to_string().contains("SAFE_TX_SERVICE_URL"));
+        config.safe.tx_service_url = None;
+
+        config.safe.address = Some("0x1234".to_string());
+        assert!(config.validate().unwrap_err().to_string().contains("SAFE_ADDRESS"));
+        config.safe.address = Some("0x1111111111111111111111111111111111111111".to_string());
+        assert_eq!(config.safe_address(), Some(Address::repeat_byte(0x11)));
+    }
+
+    #[test]
+    fn test_price_impact_limit() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+
+        config.trading.max_price_impact_bps = 300;
+        assert_eq!(config.price_impact_limit(None).unwrap(), Some(300));
+        // 单次调用指定的值优先，0 表示不限制
+        assert_eq!(config.price_impact_limit(Some(50)).unwrap(), Some(50));
+        assert_eq!(config.price_impact_limit(Some(0)).unwrap(), None);
+        assert!(config.price_impact_limit(Some(10001)).is_err());
+
+        config.trading.max_price_impact_bps = 20000;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_token_registry_path_validation() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+
+        config.token_registry_path = Some("/nonexistent/tokens.json".to_string());
+        let err = config.validate().unwrap_err().to_string();
+        assert!(err.contains("TOKEN_REGISTRY_PATH"));
+    }
+
+    #[test]
+    fn test_token_list_urls_validation() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+
+        config.token_list_urls = vec!["https://tokens.uniswap.org".to_string()];
+        assert!(config.validate().is_ok());
+
+        config.token_list_refresh_secs = 10;
+        assert!(config.validate().is_err());
+
+        config.token_list_refresh_secs = 3600;
+        config.token_list_urls.push("ftp://example.com/list.json".to_string());
+        assert!(config.validate().is_err());
+    }
 }
@@ -0,0 +1,438 @@
+use ethers::prelude::*;
+use ethers::types::transaction::eip712::TypedData;
+use tracing::{debug, instrument};
+
+/// GPv2Settlement 合约地址（各链相同，作为 EIP-712 verifyingContract）
+pub const COW_SETTLEMENT: &str = "0x9008D19f58AAbD9eD0D60971565AA8510560ab41";
+
+/// GPv2VaultRelayer 合约地址（卖出代币需要授权给它）
+pub const COW_VAULT_RELAYER: &str = "0xC92E8bdf79f0507f65a392b0ab4667716BFE0110";
+
+/// 空 appData（keccak256("{}")）
+const DEFAULT_APP_DATA: &str =
+    "0xb48d38f93eaa084033fc5970bf96e559c33c4cdc07d889ab00b4d63f9590739d";
+
+/// CoW Protocol 错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum CowError {
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("CoW Protocol 不支持 Chain ID {0}")]
+    UnsupportedChain(u64),
+
+    #[error("CoW API 返回错误 ({status}): {message}")]
+    ApiError { status: u16, message: String },
+
+    #[error("EIP-712 编码错误: {0}")]
+    TypedDataError(String),
+
+    #[error("报价与请求不一致: {0}")]
+    QuoteMismatch(String),
+}
+
+/// CoW 订单（GPv2Order.Data）
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CowOrder {
+    pub sell_token: Address,
+    pub buy_token: Address,
+    pub receiver: Address,
+    #[serde(with = "decimal_u256")]
+    pub sell_amount: U256,
+    #[serde(with = "decimal_u256")]
+    pub buy_amount: U256,
+    pub valid_to: u32,
+    pub app_data: H256,
+    #[serde(with = "decimal_u256")]
+    pub fee_amount: U256,
+    /// sell / buy
+    pub kind: String,
+    pub partially_fillable: bool,
+    pub sell_token_balance: String,
+    pub buy_token_balance: String,
+}
+
+impl CowOrder {
+    /// GPv2Order 的 EIP-712 结构化数据
+    pub fn typed_data(&self, chain_id: u64) -> Result<TypedData, CowError> {
+        let value = serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                "Order": [
+                    { "name": "sellToken", "type": "address" },
+                    { "name": "buyToken", "type": "address" },
+                    { "name": "receiver", "type": "address" },
+                    { "name": "sellAmount", "type": "uint256" },
+                    { "name": "buyAmount", "type": "uint256" },
+                    { "name": "validTo", "type": "uint32" },
+                    { "name": "appData", "type": "bytes32" },
+                    { "name": "feeAmount", "type": "uint256" },
+                    { "name": "kind", "type": "string" },
+                    { "name": "partiallyFillable", "type": "bool" },
+                    { "name": "sellTokenBalance", "type": "string" },
+                    { "name": "buyTokenBalance", "type": "string" },
+                ],
+            },
+            "primaryType": "Order",
+            "domain": {
+                "name": "Gnosis Protocol",
+                "version": "v2",
+                "chainId": chain_id,
+                "verifyingContract": COW_SETTLEMENT,
+            },
+            "message": self,
+        });
+
+        serde_json::from_value(value).map_err(|e| CowError::TypedDataError(e.to_string()))
+    }
+
+    /// 根据报价构建卖出订单
+    /// 协议费从成交盈余中扣除：订单 feeAmount 为 0，卖出数量包含报价中的费用，
+    /// 最小买入数量按滑点下调
+    pub fn from_quote(quote: &CowQuote, slippage_bps: u32) -> Self {
+        Self {
+            sell_token: quote.sell_token,
+            buy_token: quote.buy_token,
+            receiver: quote.receiver.unwrap_or_default(),
+            sell_amount: quote.sell_amount + quote.fee_amount,
+            buy_amount: quote.buy_amount * U256::from(10000 - slippage_bps) / U256::from(10000),
+            valid_to: quote.valid_to,
+            app_data: quote.app_data,
+            fee_amount: U256::zero(),
+            kind: quote.kind.clone(),
+            partially_fillable: quote.partially_fillable,
+            sell_token_balance: quote.sell_token_balance.clone(),
+            buy_token_balance: quote.buy_token_balance.clone(),
+        }
+    }
+}
+
+/// 报价接口返回的订单参数
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CowQuote {
+    pub sell_token: Address,
+    pub buy_token: Address,
+    #[serde(default)]
+    pub receiver: Option<Address>,
+    #[serde(with = "decimal_u256")]
+    pub sell_amount: U256,
+    #[serde(with = "decimal_u256")]
+    pub buy_amount: U256,
+    pub valid_to: u32,
+    pub app_data: H256,
+    #[serde(with = "decimal_u256")]
+    pub fee_amount: U256,
+    pub kind: String,
+    pub partially_fillable: bool,
+    pub sell_token_balance: String,
+    pub buy_token_balance: String,
+}
+
+impl CowQuote {
+    /// 签名前校验报价与请求一致：代币对、接收方(零地址或 owner)、卖出总量(含费用)和订单类型，
+    /// 防止被篡改的报价让钱包签出付给他人或卖出其他代币的订单
+    pub fn verify(
+        &self,
+        sell_token: Address,
+        buy_token: Address,
+        sell_amount: U256,
+        owner: Address,
+    ) -> Result<(), CowError> {
+        if self.sell_token != sell_token {
+            return Err(CowError::QuoteMismatch(format!(
+                "sellToken 为 {:?},期望 {:?}",
+                self.sell_token, sell_token
+            )));
+        }
+        if self.buy_token != buy_token {
+            return Err(CowError::QuoteMismatch(format!(
+                "buyToken 为 {:?},期望 {:?}",
+                self.buy_token, buy_token
+            )));
+        }
+        if let Some(receiver) = self.receiver
+            && !receiver.is_zero()
+            && receiver != owner
+        {
+            return Err(CowError::QuoteMismatch(format!(
+                "receiver 为 {:?},期望 {:?}",
+                receiver, owner
+            )));
+        }
+        if self.sell_amount.checked_add(self.fee_amount) != Some(sell_amount) {
+            return Err(CowError::QuoteMismatch(format!(
+                "sellAmount + feeAmount 为 {} + {},期望 {}",
+                self.sell_amount, self.fee_amount, sell_amount
+            )));
+        }
+        if self.kind != "sell" {
+            return Err(CowError::QuoteMismatch(format!("kind 为 {},期望 sell", self.kind)));
+        }
+        Ok(())
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct QuoteResponse {
+    quote: CowQuote,
+    id: Option<u64>,
+}
+
+/// 订单状态
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CowOrderStatus {
+    pub uid: String,
+    /// open / fulfilled / cancelled / expired / presignaturePending
+    pub status: String,
+    #[serde(default)]
+    pub executed_sell_amount: Option<String>,
+    #[serde(default)]
+    pub executed_buy_amount: Option<String>,
+    #[serde(default)]
+    pub creation_date: Option<String>,
+}
+
+/// CoW Protocol 订单簿客户端
+#[derive(Clone)]
+pub struct CowClient {
+    http: reqwest::Client,
+    chain_id: u64,
+}
+
+impl CowClient {
+    /// 创建新的 CoW 客户端
+    pub fn new(chain_id: u64) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            chain_id,
+        }
+    }
+
+    /// 当前链的订单簿 API 地址
+    pub fn base_url(&self) -> Result<String, CowError> {
+        let network = match self.chain_id {
+            1 => "mainnet",
+            100 => "xdai",
+            42161 => "arbitrum_one",
+            8453 => "base",
+            11155111 => "sepolia",
+            other => return Err(CowError::UnsupportedChain(other)),
+        };
+        Ok(format!("https://api.cow.fi/{}/api/v1", network))
+    }
+
+    /// 获取卖出报价（kind = sell，数量为扣除费用前的卖出数量）
+    #[instrument(skip(self))]
+    pub async fn quote(
+        &self,
+        sell_token: Address,
+        buy_token: Address,
+        sell_amount: U256,
+        from: Address,
+        valid_to: u32,
+    ) -> Result<(CowQuote, Option<u64>), CowError> {
+        let body = serde_json::json!({
+            "sellToken": format!("{:?}", sell_token),
+            "buyToken": format!("{:?}", buy_token),
+            "from": format!("{:?}", from),
+            "receiver": format!("{:?}", from),
+            "kind": "sell",
+            "sellAmountBeforeFee": sell_amount.to_string(),
+            "validTo": valid_to,
+            "appData": DEFAULT_APP_DATA,
+            "partiallyFillable": false,
+            "sellTokenBalance": "erc20",
+            "buyTokenBalance": "erc20",
+            "signingScheme": "eip712",
+        });
+
+        let url = format!("{}/quote", self.base_url()?);
+        let response = self.http.post(&url).json(&body).send().await?;
+        let response: QuoteResponse = Self::parse(response).await?;
+
+        debug!(
+            buy_amount = %response.quote.buy_amount,
+            fee_amount = %response.quote.fee_amount,
+            "获取 CoW 报价"
+        );
+
+        Ok((response.quote, response.id))
+    }
+
+    /// 提交已签名订单，返回订单 UID
+    #[instrument(skip(self, order, signature))]
+    pub async fn submit(
+        &self,
+        order: &CowOrder,
+        signature: &Signature,
+        from: Address,
+        quote_id: Option<u64>,
+    ) -> Result<String, CowError> {
+        let mut body = serde_json::to_value(order).map_err(|e| CowError::TypedDataError(e.to_string()))?;
+        body["signingScheme"] = serde_json::json!("eip712");
+        body["signature"] = serde_json::json!(format!("0x{}", signature));
+        body["from"] = serde_json::json!(format!("{:?}", from));
+        if let Some(id) = quote_id {
+            body["quoteId"] = serde_json::json!(id);
+        }
+
+        let url = format!("{}/orders", self.base_url()?);
+        let response = self.http.post(&url).json(&body).send().await?;
+        let uid: String = Self::parse(response).await?;
+
+        debug!(uid = %uid, "CoW 订单已提交");
+
+        Ok(uid)
+    }
+
+    /// 查询订单状态
+    #[instrument(skip(self))]
+    pub async fn order_status(&self, uid: &str) -> Result<CowOrderStatus, CowError> {
+        let url = format!("{}/orders/{}", self.base_url()?, uid);
+        let response = self.http.get(&url).send().await?;
+        Self::parse(response).await
+    }
+
+    async fn parse<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T, CowError> {
+        let status = response.status();
+        if !status.is_success() {
+            // CoW 错误响应格式: {"errorType": "...", "description": "..."}
+            let message = response
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .map(|v| {
+                    format!(
+                        "{} {}",
+                        v["errorType"].as_str().unwrap_or_default(),
+                        v["description"].as_str().unwrap_or_default()
+                    )
+                    .trim()
+                    .to_string()
+                })
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| status.to_string());
+            return Err(CowError::ApiError {
+                status: status.as_u16(),
+                message,
+            });
+        }
+        Ok(response.json().await?)
+    }
+}
+
+/// CoW API 使用十进制字符串表示 uint256
+mod decimal_u256 {
+    use ethers::types::U256;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        U256::from_dec_str(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::transaction::eip712::Eip712;
+
+    fn sample_quote() -> CowQuote {
+        serde_json::from_str(
+            r#"{
+                "sellToken": "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+                "buyToken": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                "receiver": "0x1111111111111111111111111111111111111111",
+                "sellAmount": "990000000000000000",
+                "buyAmount": "2970000000",
+                "validTo": 1700000000,
+                "appData": "0xb48d38f93eaa084033fc5970bf96e559c33c4cdc07d889ab00b4d63f9590739d",
+                "feeAmount": "10000000000000000",
+                "kind": "sell",
+                "partiallyFillable": false,
+                "sellTokenBalance": "erc20",
+                "buyTokenBalance": "erc20"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_order_from_quote_folds_fee_and_applies_slippage() {
+        let order = CowOrder::from_quote(&sample_quote(), 50);
+
+        assert_eq!(order.sell_amount, U256::exp10(18));
+        assert_eq!(order.fee_amount, U256::zero());
+        assert_eq!(order.buy_amount, U256::from(2_955_150_000u64));
+        assert_eq!(order.receiver, Address::repeat_byte(0x11));
+    }
+
+    #[test]
+    fn test_quote_verify_rejects_tampering() {
+        let quote = sample_quote();
+        let owner = Address::repeat_byte(0x11);
+        let (sell, buy) = (quote.sell_token, quote.buy_token);
+        assert!(quote.verify(sell, buy, U256::exp10(18), owner).is_ok());
+
+        let tampered = |edit: fn(&mut CowQuote)| {
+            let mut quote = sample_quote();
+            edit(&mut quote);
+            quote.verify(sell, buy, U256::exp10(18), owner)
+        };
+        assert!(tampered(|q| q.receiver = None).is_ok());
+        assert!(tampered(|q| q.receiver = Some(Address::zero())).is_ok());
+
+        for result in [
+            tampered(|q| q.sell_token = Address::repeat_byte(0x22)),
+            tampered(|q| q.buy_token = Address::repeat_byte(0x22)),
+            tampered(|q| q.receiver = Some(Address::repeat_byte(0x22))),
+            tampered(|q| q.sell_amount *= 2),
+            tampered(|q| q.fee_amount = U256::MAX),
+            tampered(|q| q.kind = "buy".to_string()),
+        ] {
+            assert!(matches!(result, Err(CowError::QuoteMismatch(_))));
+        }
+    }
+
+    #[test]
+    fn test_order_serializes_decimal_amounts() {
+        let order = CowOrder::from_quote(&sample_quote(), 0);
+        let json = serde_json::to_value(&order).unwrap();
+
+        assert_eq!(json["sellAmount"], "1000000000000000000");
+        assert_eq!(json["feeAmount"], "0");
+        assert_eq!(json["validTo"], 1700000000);
+    }
+
+    #[tokio::test]
+    async fn test_order_signature_recovers_owner() {
+        let wallet: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap();
+        let typed = CowOrder::from_quote(&sample_quote(), 50).typed_data(1).unwrap();
+        let signature = wallet.sign_typed_data(&typed).await.unwrap();
+
+        assert_eq!(
+            signature.recover(typed.encode_eip712().unwrap()).unwrap(),
+            wallet.address()
+        );
+    }
+
+    #[test]
+    fn test_base_url_by_chain() {
+        assert_eq!(CowClient::new(1).base_url().unwrap(), "https://api.cow.fi/mainnet/api/v1");
+        assert!(matches!(CowClient::new(5).base_url(), Err(CowError::UnsupportedChain(5))));
+    }
+}
@@ -0,0 +1,172 @@
+use crate::bindings::{self, i_curve_pool};
+use crate::chains::ChainInfo;
+use crate::eth_client::RpcProvider;
+use ethers::abi::AbiDecode;
+use ethers::prelude::*;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+/// Curve 池子中原生 ETH 的占位地址
+pub const CURVE_NATIVE_COIN: &str = "0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE";
+
+/// Curve 池子（只收录稳定币和锚定资产池子，coins 顺序即 get_dy 的 i/j 索引）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurvePool {
+    pub name: &'static str,
+    pub address: &'static str,
+    pub coins: &'static [&'static str],
+}
+
+impl CurvePool {
+    pub fn pool_address(&self) -> Address {
+        self.address.parse().expect("硬编码地址应该有效")
+    }
+
+    /// 代币在池子中的索引
+    fn coin_index(&self, token: Address) -> Option<i128> {
+        self.coins
+            .iter()
+            .position(|coin| coin.parse::<Address>().ok() == Some(token))
+            .map(|index| index as i128)
+    }
+}
+
+/// 主网 Curve 池子
+pub const MAINNET_POOLS: [CurvePool; 2] = [
+    CurvePool {
+        name: "3pool",
+        address: "0xbEbc44782C7dB0a1A60Cb6fe97d0b483032FF1C7",
+        coins: &[
+            "0x6B175474E89094C44Da98b954EedeAC495271d0F", // DAI
+            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", // USDC
+            "0xdAC17F958D2ee523a2206206994597C13D831ec7", // USDT
+        ],
+    },
+    CurvePool {
+        name: "steth",
+        address: "0xDC24316b9AE028F1497c275EB9192a3Ea0f67022",
+        coins: &[
+            CURVE_NATIVE_COIN,
+            "0xae7ab96520DE3A18E5e111B5EaAb095312D7fE84", // stETH
+        ],
+    },
+];
+
+/// Curve 报价错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum CurveError {
+    #[error("提供者错误: {0}")]
+    ProviderError(#[from] ProviderError),
+
+    #[error("Provider 不可用")]
+    ProviderUnavailable,
+
+    #[error("没有同时包含两个代币的 Curve 池子")]
+    PoolNotFound,
+
+    #[error("ABI 编码/解码错误: {0}")]
+    AbiError(String),
+}
+
+/// Curve 池子报价
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurveQuote {
+    pub pool: &'static str,
+    pub pool_address: Address,
+    pub amount_in: U256,
+    /// get_dy 返回的输出数量（已扣除池子手续费）
+    pub amount_out: U256,
+}
+
+/// Curve 客户端：按 get_dy 报价稳定币和锚定资产交易对
+#[derive(Clone)]
+pub struct CurveClient {
+    provider: Option<Arc<RpcProvider>>,
+    pools: &'static [CurvePool],
+    /// 包装原生代币（注册表中 ETH 与 WETH 共享地址，按池子中的原生 ETH 报价）
+    wrapped_native: Address,
+}
+
+impl CurveClient {
+    /// 创建指定链的 Curve 客户端（目前只收录主网池子）
+    pub fn new(provider: Option<Arc<RpcProvider>>, chain: &ChainInfo) -> Self {
+        Self {
+            provider,
+            pools: if chain.chain_id == 1 { &MAINNET_POOLS } else { &[] },
+            wrapped_native: chain.wrapped_native_address(),
+        }
+    }
+
+    /// 检查客户端是否可用（需要 Provider 且当前链有收录的池子）
+    pub fn is_available(&self) -> bool {
+        self.provider.is_some() && !self.pools.is_empty()
+    }
+
+    /// 查找同时包含两个代币的池子，返回 (池子, i, j)
+    pub fn find_pool(&self, token_in: Address, token_out: Address) -> Option<(&'static CurvePool, i128, i128)> {
+        let native: Address = CURVE_NATIVE_COIN.parse().expect("硬编码地址应该有效");
+        let coin = |token: Address| if token == self.wrapped_native { native } else { token };
+
+        self.pools.iter().find_map(|pool| {
+            let i = pool.coin_index(coin(token_in))?;
+            let j = pool.coin_index(coin(token_out))?;
+            (i != j).then_some((pool, i, j))
+        })
+    }
+
+    /// 通过池子的 get_dy(i, j, dx) 报价
+    #[instrument(skip(self))]
+    pub async fn quote(&self, token_in: Address, token_out: Address, amount_in: U256) -> Result<CurveQuote, CurveError> {
+        let provider = self.provider.as_ref().ok_or(CurveError::ProviderUnavailable)?;
+        let (pool, i, j) = self.find_pool(token_in, token_out).ok_or(CurveError::PoolNotFound)?;
+
+        let call = i_curve_pool::GetDyCall { i, j, dx: amount_in };
+        let result = bindings::eth_call(provider, pool.pool_address(), call, None).await?;
+        let amount_out = i_curve_pool::GetDyReturn::decode(&result)
+            .map_err(|e| CurveError::AbiError(e.to_string()))?
+            .0;
+
+        debug!(pool = pool.name, amount_out = %amount_out, "Curve 报价");
+        Ok(CurveQuote {
+            pool: pool.name,
+            pool_address: pool.pool_address(),
+            amount_in,
+            amount_out,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::{MAINNET, POLYGON};
+
+    #[test]
+    fn test_find_pool() {
+        let client = CurveClient::new(None, &MAINNET);
+        let usdc = MAINNET.usdc_address();
+        let usdt: Address = "0xdAC17F958D2ee523a2206206994597C13D831ec7".parse().unwrap();
+        let steth: Address = "0xae7ab96520DE3A18E5e111B5EaAb095312D7fE84".parse().unwrap();
+
+        let (pool, i, j) = client.find_pool(usdc, usdt).unwrap();
+        assert_eq!((pool.name, i, j), ("3pool", 1, 2));
+
+        // WETH 按池子中的原生 ETH 匹配
+        let (pool, i, j) = client.find_pool(steth, MAINNET.wrapped_native_address()).unwrap();
+        assert_eq!((pool.name, i, j), ("steth", 1, 0));
+
+        assert!(client.find_pool(usdc, usdc).is_none());
+        assert!(client.find_pool(usdc, steth).is_none());
+
+        // 没有 Provider 或当前链没有收录池子时不可用
+        assert!(!client.is_available());
+        assert!(CurveClient::new(None, &POLYGON).find_pool(usdc, usdt).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_quote_without_provider() {
+        let client = CurveClient::new(None, &MAINNET);
+        let result = client.quote(Address::zero(), Address::zero(), U256::one()).await;
+        assert!(matches!(result, Err(CurveError::ProviderUnavailable)));
+    }
+}
@@ -0,0 +1,205 @@
+use crate::chains::ChainInfo;
+use crate::diagnostics::record_rpc_call;
+use ethers::types::Address;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument};
+
+/// DefiLlama 请求超时时间
+const DEFILLAMA_TIMEOUT: Duration = Duration::from_secs(10);
+/// 代币价格 API（聚合多个 DEX/CEX 的市场价格，无需 API Key）
+const DEFILLAMA_COINS_BASE: &str = "https://coins.llama.fi";
+/// 协议 TVL API
+const DEFILLAMA_API_BASE: &str = "https://api.llama.fi";
+
+/// DefiLlama 协议 slug
+pub const UNISWAP_V2_PROTOCOL: &str = "uniswap-v2";
+pub const UNISWAP_V3_PROTOCOL: &str = "uniswap-v3";
+
+/// DefiLlama API 错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum DefiLlamaError {
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("DefiLlama 不支持当前链")]
+    UnsupportedChain,
+
+    #[error("响应格式错误: {0}")]
+    InvalidResponse(String),
+}
+
+/// DefiLlama 聚合市场价格
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketPrice {
+    pub price_usd: Decimal,
+    /// DefiLlama 对价格的置信度（0-1，流动性差的代币较低）
+    pub confidence: Option<Decimal>,
+}
+
+/// DefiLlama 价格和 TVL 客户端
+#[derive(Clone)]
+pub struct DefiLlamaClient {
+    http: reqwest::Client,
+    coins_base: String,
+    api_base: String,
+    /// 当前链的 DefiLlama 链名，测试网为空
+    chain: Option<&'static str>,
+}
+
+impl DefiLlamaClient {
+    /// 创建指定链的 DefiLlama 客户端
+    pub fn new(chain: &ChainInfo) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(DEFILLAMA_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            coins_base: DEFILLAMA_COINS_BASE.to_string(),
+            api_base: DEFILLAMA_API_BASE.to_string(),
+            chain: chain.defillama_chain,
+        }
+    }
+
+    /// 当前链是否有 DefiLlama 报价
+    pub fn is_available(&self) -> bool {
+        self.chain.is_some()
+    }
+
+    /// 批量查询代币的当前 USD 价格（单次请求），返回值与 `tokens` 顺序一致，没有报价的代币为 None
+    #[instrument(skip(self))]
+    pub async fn token_prices(&self, tokens: &[Address]) -> Result<Vec<Option<MarketPrice>>, DefiLlamaError> {
+        let chain = self.chain.ok_or(DefiLlamaError::UnsupportedChain)?;
+        let coins: Vec<String> = tokens.iter().map(|token| coin_id(chain, *token)).collect();
+
+        let url = format!("{}/prices/current/{}", self.coins_base, coins.join(","));
+        let started = Instant::now();
+        let response = async { self.http.get(&url).send().await?.error_for_status()?.json().await }.await;
+        record_rpc_call("defillama_prices_current", None, started.elapsed(), 0, response.is_ok());
+        let response: serde_json::Value = response?;
+
+        let prices = coins
+            .iter()
+            .map(|coin| parse_coin_price(&response, coin))
+            .collect::<Result<Vec<_>, _>>()?;
+        debug!(coins = ?coins, prices = ?prices, "DefiLlama 价格");
+        Ok(prices)
+    }
+
+    /// 查询协议在所有链上的当前 TVL（USD）
+    #[instrument(skip(self))]
+    pub async fn protocol_tvl(&self, protocol: &str) -> Result<Decimal, DefiLlamaError> {
+        let url = format!("{}/tvl/{}", self.api_base, protocol);
+        let started = Instant::now();
+        let response = async { self.http.get(&url).send().await?.error_for_status()?.json().await }.await;
+        record_rpc_call("defillama_tvl", None, started.elapsed(), 0, response.is_ok());
+        let response: serde_json::Value = response?;
+
+        parse_number(&response)
+            .ok_or_else(|| DefiLlamaError::InvalidResponse(format!("无效的 TVL: {}", response)))
+    }
+}
+
+/// DefiLlama 的代币 ID：`<chain>:<小写地址>`
+fn coin_id(chain: &str, token: Address) -> String {
+    format!("{}:{:?}", chain, token)
+}
+
+/// 解析 prices/current 响应：`{"coins": {"ethereum:0x...": {"price": 1.0, "confidence": 0.99}}}`
+fn parse_coin_price(response: &serde_json::Value, coin: &str) -> Result<Option<MarketPrice>, DefiLlamaError> {
+    let coins = response
+        .get("coins")
+        .filter(|coins| coins.is_object())
+        .ok_or_else(|| DefiLlamaError::InvalidResponse(response.to_string()))?;
+
+    // 未收录的代币不出现在 coins 中
+    let Some(entry) = coins.get(coin) else {
+        return Ok(None);
+    };
+    let price = entry
+        .get("price")
+        .and_then(parse_number)
+        .ok_or_else(|| DefiLlamaError::InvalidResponse(format!("无效的价格: {}", entry)))?;
+
+    Ok(Some(MarketPrice {
+        price_usd: price,
+        confidence: entry.get("confidence").and_then(parse_number),
+    }))
+}
+
+/// 解析 JSON 数值（小额价格可能以科学计数法返回，如 1.2e-7）
+fn parse_number(value: &serde_json::Value) -> Option<Decimal> {
+    if !value.is_number() {
+        return None;
+    }
+    let text = value.to_string();
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .ok()
+        .map(|number| number.normalize())
+}
+
+/// 链上价格相对市场价格的偏离百分比（保留 2 位小数，市场价格为 0 时返回 None）
+pub fn deviation_percent(onchain: Decimal, market: Decimal) -> Option<Decimal> {
+    if market.is_zero() {
+        return None;
+    }
+    Some(((onchain - market) / market * Decimal::ONE_HUNDRED).round_dp(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_coin_price() {
+        let usdc = coin_id("ethereum", "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap());
+        assert_eq!(usdc, "ethereum:0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48");
+
+        let response = serde_json::json!({
+            "coins": {
+                usdc.as_str(): { "decimals": 6, "symbol": "USDC", "price": 0.999_8, "confidence": 0.99 },
+                "ethereum:0x01": { "price": 1.2e-7 }
+            }
+        });
+        let price = parse_coin_price(&response, &usdc).unwrap().unwrap();
+        assert_eq!(price.price_usd, Decimal::from_str("0.9998").unwrap());
+        assert_eq!(price.confidence, Some(Decimal::from_str("0.99").unwrap()));
+
+        let price = parse_coin_price(&response, "ethereum:0x01").unwrap().unwrap();
+        assert_eq!(price.price_usd, Decimal::from_str("0.00000012").unwrap());
+        assert_eq!(price.confidence, None);
+
+        // 未收录的代币
+        assert_eq!(parse_coin_price(&response, "ethereum:0x02").unwrap(), None);
+        assert!(matches!(
+            parse_coin_price(&serde_json::json!({}), &usdc),
+            Err(DefiLlamaError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number(&serde_json::json!(4_521_337_012.5)), Some(Decimal::from_str("4521337012.5").unwrap()));
+        assert_eq!(parse_number(&serde_json::json!("1")), None);
+    }
+
+    #[test]
+    fn test_deviation_percent() {
+        let market = Decimal::from(2000);
+        assert_eq!(deviation_percent(Decimal::from(2100), market), Some(Decimal::from(5)));
+        assert_eq!(deviation_percent(Decimal::from(1900), market), Some(Decimal::from(-5)));
+        assert_eq!(
+            deviation_percent(Decimal::from_str("2000.2").unwrap(), market),
+            Some(Decimal::from_str("0.01").unwrap())
+        );
+        assert_eq!(deviation_percent(Decimal::ONE, Decimal::ZERO), None);
+    }
+
+    #[test]
+    fn test_client_requires_chain() {
+        assert!(DefiLlamaClient::new(&crate::chains::MAINNET).is_available());
+        assert!(!DefiLlamaClient::new(&crate::chains::SEPOLIA).is_available());
+    }
+}
@@ -0,0 +1,475 @@
+use crate::config::PerformanceConfig;
+use crate::eth_client::FailoverHttp;
+use ethers::providers::{
+    HttpClientError, JsonRpcClient, JsonRpcError, ProviderError, RpcError, WsClientError,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::hash_map::RandomState;
+use std::fmt::Debug;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::{debug, warn};
+
+/// 首次重试前的等待时间（之后每次翻倍）
+const RPC_RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+/// 单次重试等待时间上限（不含抖动）
+const RPC_RETRY_MAX_DELAY: Duration = Duration::from_secs(5);
+/// 节点限流常用的 JSON-RPC 错误码（429 透传，-32005 为 Infura/Alchemy 的 limit exceeded）
+const RATE_LIMIT_RPC_CODES: [i64; 2] = [429, -32005];
+/// 网关错误页面中表示可重试的 HTTP 状态
+const TRANSIENT_STATUS_MARKERS: [&str; 8] = [
+    "429",
+    "Too Many Requests",
+    "502",
+    "Bad Gateway",
+    "503",
+    "Service Unavailable",
+    "504",
+    "Gateway Timeout",
+];
+
+/// 单次 RPC 调用的耗时
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RpcCallTiming {
+    pub method: String,
+    /// 含重试在内的总耗时（毫秒）
+    pub duration_ms: u64,
+    /// 传输失败后的重试次数
+    pub retries: u32,
+    pub success: bool,
+    /// 实际处理请求的 RPC 节点（多节点故障转移时区分）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+}
+
+/// 单次缓存查询
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CacheLookup {
+    pub cache: String,
+    pub key: String,
+    pub hit: bool,
+}
+
+/// 一次工具调用的耗时明细（`debug: true` 时附加到响应）
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TimingBreakdown {
+    /// 工具调用总耗时（毫秒）
+    pub total_ms: u64,
+    /// 各 RPC 调用耗时之和（并发调用会重叠，可能大于总耗时）
+    pub rpc_ms: u64,
+    pub rpc_calls: Vec<RpcCallTiming>,
+    pub cache_hits: usize,
+    pub cache_misses: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cache_lookups: Vec<CacheLookup>,
+}
+
+/// 当前工具调用的耗时记录
+#[derive(Debug, Default)]
+struct TimingRecorder {
+    rpc_calls: Mutex<Vec<RpcCallTiming>>,
+    cache_lookups: Mutex<Vec<CacheLookup>>,
+}
+
+impl TimingRecorder {
+    fn breakdown(&self, elapsed: Duration) -> TimingBreakdown {
+        let rpc_calls = self.rpc_calls.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let cache_lookups = self.cache_lookups.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let cache_hits = cache_lookups.iter().filter(|lookup| lookup.hit).count();
+
+        TimingBreakdown {
+            total_ms: elapsed.as_millis() as u64,
+            rpc_ms: rpc_calls.iter().map(|call| call.duration_ms).sum(),
+            cache_misses: cache_lookups.len() - cache_hits,
+            cache_hits,
+            rpc_calls,
+            cache_lookups,
+        }
+    }
+}
+
+tokio::task_local! {
+    static RECORDER: Arc<TimingRecorder>;
+}
+
+/// 执行 future 并记录其中的 RPC 调用和缓存查询
+/// 工具内部 `tokio::join!` 并发执行的 future 仍在同一任务中，记录同样生效；
+/// `tokio::spawn` 出去的任务不在记录范围内
+pub async fn with_timing<F: Future>(future: F) -> (F::Output, TimingBreakdown) {
+    let recorder = Arc::new(TimingRecorder::default());
+    let started = Instant::now();
+    let output = RECORDER.scope(recorder.clone(), future).await;
+    (output, recorder.breakdown(started.elapsed()))
+}
+
+/// 记录一次 RPC 调用（不在 `with_timing` 范围内时忽略）
+pub fn record_rpc_call(
+    method: &str,
+    endpoint: Option<&str>,
+    duration: Duration,
+    retries: u32,
+    success: bool,
+) {
+    let _ = RECORDER.try_with(|recorder| {
+        recorder
+            .rpc_calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(RpcCallTiming {
+                method: method.to_string(),
+                duration_ms: duration.as_millis() as u64,
+                retries,
+                success,
+                endpoint: endpoint.map(str::to_string),
+            });
+    });
+}
+
+/// 记录一次缓存查询（不在 `with_timing` 范围内时忽略）
+pub fn record_cache_lookup(cache: &str, key: &str, hit: bool) {
+    let _ = RECORDER.try_with(|recorder| {
+        recorder
+            .cache_lookups
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(CacheLookup {
+                cache: cache.to_string(),
+                key: key.to_string(),
+                hit,
+            });
+    });
+}
+
+/// RPC 重试策略：指数退避 + 随机抖动，只重试暂时性错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// 最大重试次数（RPC_RETRY_COUNT）
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            base_delay: RPC_RETRY_BASE_DELAY,
+            max_delay: RPC_RETRY_MAX_DELAY,
+        }
+    }
+
+    /// 第 `attempt` 次重试（从 1 开始）前的退避时间（不含抖动）
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << attempt.saturating_sub(1).min(16);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// 退避时间加上 [0, backoff/2) 的随机抖动，避免多个请求同时重试
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.backoff(attempt);
+        let jitter_range = (backoff.as_millis() as u64 / 2).max(1);
+        let jitter = RandomState::new().build_hasher().finish() % jitter_range;
+        backoff + Duration::from_millis(jitter)
+    }
+
+    /// 是否为值得重试的暂时性错误（超时、连接失败、限流、网关错误）
+    /// 节点返回的其他 JSON-RPC 错误（如 execution reverted）不重试
+    pub fn is_transient(error: &HttpClientError) -> bool {
+        match error {
+            HttpClientError::ReqwestError(e) => {
+                e.is_timeout()
+                    || e.is_connect()
+                    || e.is_request()
+                    || e.is_body()
+                    || e.status().is_some_and(|status| {
+                        status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+                    })
+            }
+            HttpClientError::JsonRpcError(e) => {
+                let message = e.message.to_lowercase();
+                RATE_LIMIT_RPC_CODES.contains(&e.code)
+                    || message.contains("rate limit")
+                    || message.contains("too many requests")
+            }
+            // 网关/限流返回的非 JSON 错误页面
+            HttpClientError::SerdeJson { text, .. } => TRANSIENT_STATUS_MARKERS
+                .iter()
+                .any(|marker| text.contains(marker)),
+        }
+    }
+}
+
+/// RPC 传输错误
+#[derive(Debug, thiserror::Error)]
+pub enum RpcTransportError {
+    #[error("RPC 请求超时: {method} 在 {timeout_secs} 秒内未响应（HTTP_TIMEOUT）")]
+    Timeout { method: String, timeout_secs: u64 },
+
+    #[error(transparent)]
+    Http(#[from] HttpClientError),
+
+    #[error("WebSocket 错误: {0}")]
+    Ws(#[from] WsClientError),
+
+    #[error("HTTP 节点不支持订阅，请配置 ETHEREUM_WS_URL")]
+    PubsubUnsupported,
+}
+
+impl RpcError for RpcTransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            Self::Http(e) => e.as_error_response(),
+            Self::Ws(e) => e.as_error_response(),
+            Self::Timeout { .. } | Self::PubsubUnsupported => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            Self::Http(e) => e.as_serde_error(),
+            Self::Ws(e) => e.as_serde_error(),
+            Self::Timeout { .. } | Self::PubsubUnsupported => None,
+        }
+    }
+}
+
+impl From<RpcTransportError> for ProviderError {
+    fn from(e: RpcTransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
+
+/// RPC 传输配置，克隆后共享同一个并发限制
+#[derive(Debug, Clone)]
+pub struct RpcTransportConfig {
+    pub retry: RetryPolicy,
+    /// 单次 HTTP 请求超时（HTTP_TIMEOUT）
+    pub timeout: Duration,
+    /// 在途 RPC 请求上限（MAX_CONCURRENT_REQUESTS，0 表示不限制）
+    limiter: Option<Arc<Semaphore>>,
+}
+
+impl RpcTransportConfig {
+    pub fn new(max_retries: u32, timeout: Duration, max_concurrent: usize) -> Self {
+        Self {
+            retry: RetryPolicy::new(max_retries),
+            timeout,
+            limiter: (max_concurrent > 0).then(|| Arc::new(Semaphore::new(max_concurrent))),
+        }
+    }
+
+    pub fn from_config(performance: &PerformanceConfig) -> Self {
+        Self::new(
+            performance.rpc_retry_count,
+            Duration::from_secs(performance.http_timeout),
+            performance.max_concurrent_requests,
+        )
+    }
+
+    /// 等待一个并发许可（未启用限制时返回 None）
+    async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let limiter = self.limiter.as_ref()?;
+        if limiter.available_permits() == 0 {
+            debug!("RPC 并发已达上限,等待空闲许可");
+        }
+        // 信号量不会被关闭
+        limiter.acquire().await.ok()
+    }
+}
+
+/// 带计时、超时、并发限制和重试的 HTTP 传输
+/// 暂时性错误按 `RetryPolicy` 指数退避重试；节点返回的其他 JSON-RPC 错误不重试
+#[derive(Debug)]
+pub struct TimedHttp {
+    inner: FailoverHttp,
+    transport: RpcTransportConfig,
+}
+
+impl TimedHttp {
+    /// 创建 HTTP 传输（多个节点时按顺序故障转移）
+    pub fn new(urls: &[String], transport: &RpcTransportConfig) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder().timeout(transport.timeout).build()?;
+        Ok(Self {
+            inner: FailoverHttp::new(urls, client)?,
+            transport: transport.clone(),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl JsonRpcClient for TimedHttp {
+    type Error = RpcTransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // 先序列化参数，重试时复用
+        let params = serde_json::to_value(&params).map_err(|err| HttpClientError::SerdeJson {
+            err,
+            text: format!("{:?}", params),
+        })?;
+
+        let started = Instant::now();
+        let mut retries = 0;
+        let (result, endpoint) = loop {
+            // 每次尝试单独占用许可，退避等待期间不占用
+            let permit = self.transport.acquire().await;
+            let (attempt, endpoint) = self.inner.request(method, &params).await;
+            drop(permit);
+            match attempt {
+                Err(e) if retries < self.transport.retry.max_retries && RetryPolicy::is_transient(&e) => {
+                    retries += 1;
+                    let delay = self.transport.retry.delay(retries);
+                    warn!(method, retries, delay_ms = delay.as_millis() as u64, error = %e, "RPC 请求失败,准备重试");
+                    tokio::time::sleep(delay).await;
+                }
+                result => break (result, endpoint),
+            }
+        };
+
+        let elapsed = started.elapsed();
+        debug!(method, endpoint, elapsed_ms = elapsed.as_millis() as u64, retries, "RPC 调用完成");
+        record_rpc_call(method, Some(endpoint), elapsed, retries, result.is_ok());
+        result.map_err(|e| match e {
+            HttpClientError::ReqwestError(ref inner) if inner.is_timeout() => RpcTransportError::Timeout {
+                method: method.to_string(),
+                timeout_secs: self.transport.timeout.as_secs(),
+            },
+            e => e.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_with_timing_collects_records() {
+        let ((), breakdown) = with_timing(async {
+            record_rpc_call("eth_call", Some("eth.llamarpc.com"), Duration::from_millis(120), 1, true);
+            record_rpc_call("eth_blockNumber", None, Duration::from_millis(30), 0, false);
+            record_cache_lookup("token_list", "uniswap", true);
+            record_cache_lookup("token_list", "coingecko", false);
+        })
+        .await;
+
+        assert_eq!(breakdown.rpc_ms, 150);
+        assert_eq!(breakdown.rpc_calls[0].method, "eth_call");
+        assert_eq!(breakdown.rpc_calls[0].retries, 1);
+        assert_eq!(breakdown.rpc_calls[0].endpoint.as_deref(), Some("eth.llamarpc.com"));
+        assert!(!breakdown.rpc_calls[1].success);
+        assert_eq!((breakdown.cache_hits, breakdown.cache_misses), (1, 1));
+
+        // 范围之外的记录被忽略
+        record_rpc_call("eth_call", None, Duration::from_millis(10), 0, true);
+        let ((), empty) = with_timing(async {}).await;
+        assert!(empty.rpc_calls.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_with_timing_covers_joined_futures() {
+        let ((), breakdown) = with_timing(async {
+            tokio::join!(
+                async { record_rpc_call("eth_getBalance", None, Duration::from_millis(5), 0, true) },
+                async { record_rpc_call("eth_call", None, Duration::from_millis(5), 0, true) },
+            );
+        })
+        .await;
+
+        assert_eq!(breakdown.rpc_calls.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_surfaces_timeout_error() {
+        // 只接受连接、从不响应的节点
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.push(stream);
+            }
+        });
+
+        let transport = TimedHttp {
+            inner: FailoverHttp::new(
+                &[url],
+                reqwest::Client::builder().timeout(Duration::from_millis(100)).build().unwrap(),
+            )
+            .unwrap(),
+            transport: RpcTransportConfig::new(0, Duration::from_millis(100), 1),
+        };
+        let error = transport
+            .request::<_, serde_json::Value>("eth_blockNumber", ())
+            .await
+            .unwrap_err();
+        server.abort();
+
+        assert!(matches!(error, RpcTransportError::Timeout { ref method, .. } if method == "eth_blockNumber"));
+        assert!(ProviderError::from(error).to_string().contains("RPC 请求超时"));
+    }
+
+    #[tokio::test]
+    async fn test_transport_config_shares_limiter() {
+        let transport = RpcTransportConfig::new(3, Duration::from_secs(30), 2);
+        let shared = transport.clone();
+
+        let first = transport.acquire().await;
+        let second = shared.acquire().await;
+        assert!(first.is_some() && second.is_some());
+        // 两个副本共享同一组许可
+        assert_eq!(transport.limiter.as_ref().unwrap().available_permits(), 0);
+        drop(first);
+        assert_eq!(shared.limiter.as_ref().unwrap().available_permits(), 1);
+
+        let unlimited = RpcTransportConfig::new(3, Duration::from_secs(30), 0);
+        assert!(unlimited.acquire().await.is_none());
+    }
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy::new(3);
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(10), RPC_RETRY_MAX_DELAY);
+        assert_eq!(policy.backoff(u32::MAX), RPC_RETRY_MAX_DELAY);
+
+        for attempt in 1..=5 {
+            let delay = policy.delay(attempt);
+            let backoff = policy.backoff(attempt);
+            assert!(delay >= backoff && delay < backoff + backoff / 2);
+        }
+    }
+
+    #[test]
+    fn test_retry_policy_transient_errors() {
+        let rpc_error = |code: i64, message: &str| {
+            HttpClientError::JsonRpcError(ethers::providers::JsonRpcError {
+                code,
+                message: message.to_string(),
+                data: None,
+            })
+        };
+        let page = |text: &str| HttpClientError::SerdeJson {
+            err: serde_json::from_str::<serde_json::Value>("<").unwrap_err(),
+            text: text.to_string(),
+        };
+
+        assert!(RetryPolicy::is_transient(&rpc_error(429, "Too Many Requests")));
+        assert!(RetryPolicy::is_transient(&rpc_error(-32005, "limit exceeded")));
+        assert!(RetryPolicy::is_transient(&rpc_error(-32000, "daily rate limit reached")));
+        assert!(!RetryPolicy::is_transient(&rpc_error(3, "execution reverted")));
+        assert!(!RetryPolicy::is_transient(&rpc_error(-32000, "nonce too low")));
+
+        assert!(RetryPolicy::is_transient(&page("<html><h1>502 Bad Gateway</h1></html>")));
+        assert!(RetryPolicy::is_transient(&page("Too Many Requests")));
+        assert!(!RetryPolicy::is_transient(&page("not json")));
+    }
+}
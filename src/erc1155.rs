@@ -0,0 +1,146 @@
+use crate::bindings::{self, ierc1155};
+use crate::eth_client::RpcProvider;
+use ethers::abi::AbiDecode;
+use ethers::prelude::*;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+/// ERC-1155 的 ERC-165 接口 ID
+pub const ERC1155_INTERFACE_ID: [u8; 4] = [0xd9, 0xb6, 0x7a, 0x26];
+
+/// ERC-1155 查询错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum Erc1155Error {
+    #[error("提供者错误: {0}")]
+    ProviderError(#[from] ProviderError),
+
+    #[error("ABI 编码/解码错误: {0}")]
+    AbiError(String),
+
+    #[error("Provider 不可用")]
+    ProviderUnavailable,
+}
+
+/// ERC-1155 多代币合约查询客户端
+pub struct Erc1155Client {
+    provider: Option<Arc<RpcProvider>>,
+}
+
+impl Erc1155Client {
+    pub fn new(provider: Option<Arc<RpcProvider>>) -> Self {
+        Self { provider }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// 查询钱包持有的单个 token ID 数量 balanceOf(account, id)
+    #[instrument(skip(self))]
+    pub async fn balance_of(&self, collection: Address, owner: Address, id: U256) -> Result<U256, Erc1155Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc1155Error::ProviderUnavailable)?;
+
+        let call = ierc1155::BalanceOfCall { account: owner, id };
+        let result = bindings::eth_call(provider, collection, call, None).await?;
+
+        decode_return::<ierc1155::BalanceOfReturn>(&result).map(|r| r.0)
+    }
+
+    /// 通过 balanceOfBatch 批量查询钱包持有的多个 token ID 数量（单次 RPC）
+    /// 返回值与 `ids` 顺序一致
+    #[instrument(skip(self, ids), fields(count = ids.len()))]
+    pub async fn balance_of_batch(
+        &self,
+        collection: Address,
+        owner: Address,
+        ids: &[U256],
+    ) -> Result<Vec<U256>, Erc1155Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc1155Error::ProviderUnavailable)?;
+
+        debug!(collection = %collection, owner = %owner, "批量查询 ERC-1155 余额");
+
+        let call = ierc1155::BalanceOfBatchCall {
+            accounts: vec![owner; ids.len()],
+            ids: ids.to_vec(),
+        };
+        let result = bindings::eth_call(provider, collection, call, None).await?;
+        let balances = decode_return::<ierc1155::BalanceOfBatchReturn>(&result)?.0;
+
+        if balances.len() != ids.len() {
+            return Err(Erc1155Error::AbiError(format!(
+                "balanceOfBatch 返回 {} 个余额,请求了 {} 个",
+                balances.len(),
+                ids.len()
+            )));
+        }
+
+        Ok(balances)
+    }
+}
+
+/// 解析 token ID（十进制或 0x 开头的十六进制）
+pub fn parse_token_id(value: &str) -> Result<U256, String> {
+    let value = value.trim();
+    let parsed = match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) if !hex.is_empty() => U256::from_str_radix(hex, 16).ok(),
+        None if !value.is_empty() => U256::from_dec_str(value).ok(),
+        _ => None,
+    };
+    parsed.ok_or_else(|| format!("无效的 token ID: {}", value))
+}
+
+fn decode_return<R: AbiDecode>(data: &[u8]) -> Result<R, Erc1155Error> {
+    R::decode(data).map_err(|e| Erc1155Error::AbiError(format!("解码返回值失败: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::id;
+
+    #[test]
+    fn test_interface_id() {
+        let interface_id = [
+            "safeTransferFrom(address,address,uint256,uint256,bytes)",
+            "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+            "balanceOf(address,uint256)",
+            "balanceOfBatch(address[],uint256[])",
+            "setApprovalForAll(address,bool)",
+            "isApprovedForAll(address,address)",
+        ]
+        .iter()
+        .fold([0u8; 4], |mut acc, signature| {
+            for (byte, selector_byte) in acc.iter_mut().zip(id(signature)) {
+                *byte ^= selector_byte;
+            }
+            acc
+        });
+        assert_eq!(interface_id, ERC1155_INTERFACE_ID);
+    }
+
+    #[test]
+    fn test_parse_token_id() {
+        assert_eq!(parse_token_id("42").unwrap(), U256::from(42));
+        assert_eq!(parse_token_id(" 0x2a ").unwrap(), U256::from(42));
+        assert_eq!(parse_token_id("0X2A").unwrap(), U256::from(42));
+        assert!(parse_token_id("").is_err());
+        assert!(parse_token_id("abc").is_err());
+        assert!(parse_token_id("-1").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_client_without_provider() {
+        let client = Erc1155Client::new(None);
+        assert!(!client.is_available());
+        assert!(matches!(
+            client.balance_of_batch(Address::zero(), Address::zero(), &[U256::one()]).await,
+            Err(Erc1155Error::ProviderUnavailable)
+        ));
+    }
+}
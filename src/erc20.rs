@@ -1,10 +1,25 @@
-use crate::types::TokenInfo;
+use crate::bindings::{self, ierc20};
+use crate::eth_client::RpcProvider;
+use crate::multicall::{self, Call3, Call3Result, MulticallError};
+use crate::types::{checksum_address, TokenInfo};
+use ethers::abi::{AbiDecode, AbiEncode};
 use ethers::prelude::*;
 use rust_decimal::Decimal;
 use std::str::FromStr;
 use std::sync::Arc;
 use tracing::{debug, instrument};
 
+/// Transfer(address,address,uint256) 事件签名
+pub const TRANSFER_EVENT_TOPIC: &str =
+    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+/// Approval(address,address,uint256) 事件签名
+pub const APPROVAL_EVENT_TOPIC: &str =
+    "0x8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925";
+
+/// 单次 eth_getLogs 查询的区块跨度（多数 RPC 提供商限制为 10000）
+pub(crate) const LOG_CHUNK_BLOCKS: u64 = 10_000;
+
 /// ERC20 代币错误类型
 #[derive(Debug, thiserror::Error)]
 pub enum Erc20Error {
@@ -16,17 +31,45 @@ pub enum Erc20Error {
 
     #[error("Provider 不可用")]
     ProviderUnavailable,
+
+    #[error("Multicall 错误: {0}")]
+    MulticallError(#[from] MulticallError),
+}
+
+/// 解析后的 ERC20 Transfer 事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferLog {
+    /// 代币合约地址
+    pub token: Address,
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub block_number: u64,
+    pub tx_hash: H256,
+    pub log_index: u64,
+}
+
+/// 解析后的 ERC20 Approval 事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalLog {
+    /// 代币合约地址
+    pub token: Address,
+    pub owner: Address,
+    pub spender: Address,
+    pub value: U256,
+    pub block_number: u64,
+    pub log_index: u64,
 }
 
 /// ERC20 客户端
 #[derive(Clone)]
 pub struct Erc20Client {
-    provider: Option<Arc<Provider<Http>>>,
+    provider: Option<Arc<RpcProvider>>,
 }
 
 impl Erc20Client {
     /// 创建新的 ERC20 客户端
-    pub fn new(provider: Option<Arc<Provider<Http>>>) -> Self {
+    pub fn new(provider: Option<Arc<RpcProvider>>) -> Self {
         Self { provider }
     }
 
@@ -35,12 +78,13 @@ impl Erc20Client {
         self.provider.is_some()
     }
 
-    /// 查询 ERC20 代币余额
+    /// 查询 ERC20 代币余额（`block` 为 None 时查询最新区块）
     #[instrument(skip(self))]
     pub async fn balance_of(
         &self,
         token: Address,
         owner: Address,
+        block: Option<BlockId>,
     ) -> Result<U256, Erc20Error> {
         let provider = self
             .provider
@@ -53,28 +97,96 @@ impl Erc20Client {
             "查询 ERC20 余额"
         );
 
-        // 构建 balanceOf(address) 调用数据
-        // function selector: 0x70a08231
-        let mut data = vec![0x70, 0xa0, 0x82, 0x31];
-        // owner 地址（32 字节，左填充 0）
-        data.extend_from_slice(&[0u8; 12]);
-        data.extend_from_slice(owner.as_bytes());
+        let result = bindings::eth_call(provider, token, ierc20::BalanceOfCall { owner }, block).await?;
+
+        decode_return::<ierc20::BalanceOfReturn>(&result).map(|r| r.0)
+    }
+
+    /// 查询 ERC20 授权额度 allowance(owner, spender)
+    #[instrument(skip(self))]
+    pub async fn allowance(
+        &self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+    ) -> Result<U256, Erc20Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc20Error::ProviderUnavailable)?;
+
+        let call = ierc20::AllowanceCall { owner, spender };
+        let result = bindings::eth_call(provider, token, call, None).await?;
+
+        decode_return::<ierc20::AllowanceReturn>(&result).map(|r| r.0)
+    }
+
+    /// 批量查询授权额度 (token, owner, spender)（Multicall3 单次 RPC）
+    /// 返回值与输入顺序一致，单个调用失败时对应位置为 None
+    #[instrument(skip(self, queries), fields(count = queries.len()))]
+    pub async fn allowances(
+        &self,
+        queries: &[(Address, Address, Address)],
+    ) -> Result<Vec<Option<U256>>, Erc20Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc20Error::ProviderUnavailable)?;
+
+        let calls = queries
+            .iter()
+            .map(|(token, owner, spender)| {
+                Call3::new(*token, ierc20::AllowanceCall { owner: *owner, spender: *spender }.encode())
+            })
+            .collect();
+
+        let results = multicall::aggregate3(provider, calls, None).await?;
+
+        Ok(results.iter().map(|r| r.as_u256()).collect())
+    }
+
+    /// 估算 approve(spender, amount) 的 Gas（以 owner 身份调用）
+    #[instrument(skip(self))]
+    pub async fn estimate_approve_gas(
+        &self,
+        token: Address,
+        owner: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<U256, Erc20Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc20Error::ProviderUnavailable)?;
 
         let tx = Eip1559TransactionRequest::new()
+            .from(owner)
             .to(token)
-            .data(Bytes::from(data));
+            .data(Bytes::from(approve_calldata(spender, amount)));
 
-        let result = provider.call(&tx.into(), None).await?;
+        Ok(provider.estimate_gas(&tx.into(), None).await?)
+    }
 
-        // 解析返回值（uint256）
-        if result.len() != 32 {
-            return Err(Erc20Error::AbiError(format!(
-                "期望 32 字节返回值，实际 {} 字节",
-                result.len()
-            )));
-        }
+    /// 批量查询 ERC20 余额（Multicall3 单次 RPC）
+    /// 返回值与输入顺序一致，单个调用失败时对应位置为 None
+    #[instrument(skip(self, queries), fields(count = queries.len()))]
+    pub async fn balances_of(
+        &self,
+        queries: &[(Address, Address)],
+    ) -> Result<Vec<Option<U256>>, Erc20Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc20Error::ProviderUnavailable)?;
+
+        let calls = queries
+            .iter()
+            .map(|(token, owner)| Call3::new(*token, ierc20::BalanceOfCall { owner: *owner }.encode()))
+            .collect();
+
+        let results = multicall::aggregate3(provider, calls, None).await?;
 
-        Ok(U256::from_big_endian(&result))
+        Ok(results.iter().map(|r| r.as_u256()).collect())
     }
 
     /// 查询代币符号（symbol）
@@ -85,16 +197,8 @@ impl Erc20Client {
             .as_ref()
             .ok_or(Erc20Error::ProviderUnavailable)?;
 
-        // function selector: symbol() = 0x95d89b41
-        let data = vec![0x95, 0xd8, 0x9b, 0x41];
+        let result = bindings::eth_call(provider, token, ierc20::SymbolCall, None).await?;
 
-        let tx = Eip1559TransactionRequest::new()
-            .to(token)
-            .data(Bytes::from(data));
-
-        let result = provider.call(&tx.into(), None).await?;
-
-        // 解析字符串返回值
         parse_string_return(&result).ok_or_else(|| {
             Erc20Error::AbiError("无法解析 symbol 返回值".to_string())
         })
@@ -108,20 +212,41 @@ impl Erc20Client {
             .as_ref()
             .ok_or(Erc20Error::ProviderUnavailable)?;
 
-        // function selector: name() = 0x06fdde03
-        let data = vec![0x06, 0xfd, 0xde, 0x03];
+        let result = bindings::eth_call(provider, token, ierc20::NameCall, None).await?;
 
-        let tx = Eip1559TransactionRequest::new()
-            .to(token)
-            .data(Bytes::from(data));
+        parse_string_return(&result).ok_or_else(|| {
+            Erc20Error::AbiError("无法解析 name 返回值".to_string())
+        })
+    }
 
-        let result = provider.call(&tx.into(), None).await?;
+    /// 查询 EIP-712 域版本（version），未实现时返回错误
+    #[instrument(skip(self))]
+    pub async fn version(&self, token: Address) -> Result<String, Erc20Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc20Error::ProviderUnavailable)?;
+
+        let result = bindings::eth_call(provider, token, ierc20::VersionCall, None).await?;
 
         parse_string_return(&result).ok_or_else(|| {
-            Erc20Error::AbiError("无法解析 name 返回值".to_string())
+            Erc20Error::AbiError("无法解析 version 返回值".to_string())
         })
     }
 
+    /// 查询 EIP-712 域分隔符（DOMAIN_SEPARATOR）
+    #[instrument(skip(self))]
+    pub async fn domain_separator(&self, token: Address) -> Result<H256, Erc20Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc20Error::ProviderUnavailable)?;
+
+        let result = bindings::eth_call(provider, token, ierc20::DomainSeparatorCall, None).await?;
+
+        decode_return::<ierc20::DomainSeparatorReturn>(&result).map(|r| H256::from(r.0))
+    }
+
     /// 查询代币小数位数（decimals）
     #[instrument(skip(self))]
     pub async fn decimals(&self, token: Address) -> Result<u8, Erc20Error> {
@@ -130,38 +255,36 @@ impl Erc20Client {
             .as_ref()
             .ok_or(Erc20Error::ProviderUnavailable)?;
 
-        // function selector: decimals() = 0x313ce567
-        let data = vec![0x31, 0x3c, 0xe5, 0x67];
+        let result = bindings::eth_call(provider, token, ierc20::DecimalsCall, None).await?;
 
-        let tx = Eip1559TransactionRequest::new()
-            .to(token)
-            .data(Bytes::from(data));
+        parse_decimals_return(&result)
+    }
 
-        let result = provider.call(&tx.into(), None).await?;
+    /// 查询代币总供应量（totalSupply）
+    #[instrument(skip(self))]
+    pub async fn total_supply(&self, token: Address) -> Result<U256, Erc20Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc20Error::ProviderUnavailable)?;
 
-        if result.is_empty() {
-            return Err(Erc20Error::AbiError("空返回值".to_string()));
-        }
+        let result = bindings::eth_call(provider, token, ierc20::TotalSupplyCall, None).await?;
 
-        // decimals 通常返回 uint8，但某些合约返回 uint256
-        if result.len() == 32 {
-            let value = U256::from_big_endian(&result);
-            Ok(value.as_u32() as u8)
-        } else if result.len() == 1 {
-            Ok(result[0])
-        } else {
-            Err(Erc20Error::AbiError(format!(
-                "意外的 decimals 返回值长度: {}",
-                result.len()
-            )))
-        }
+        decode_return::<ierc20::TotalSupplyReturn>(&result).map(|r| r.0)
     }
 
     /// 查询完整代币信息
+    /// 优先通过 Multicall3 一次取回三个字段，Multicall3 不可用时逐个查询
     #[instrument(skip(self))]
     pub async fn token_info(&self, token: Address) -> Result<TokenInfo, Erc20Error> {
         debug!(token_address = %token, "查询代币信息");
 
+        match self.tokens_info(&[token]).await.map(|mut infos| infos.pop()) {
+            Ok(Some(info)) => return Ok(info),
+            Ok(None) => {}
+            Err(e) => debug!(error = %e, "Multicall 查询代币信息失败,改为逐个查询"),
+        }
+
         // 并发查询三个字段
         let (symbol_res, name_res, decimals_res) = tokio::join!(
             self.symbol(token),
@@ -169,43 +292,264 @@ impl Erc20Client {
             self.decimals(token)
         );
 
-        // 使用默认值处理错误（有些代币可能没有实现全部接口）
-        let symbol = symbol_res.unwrap_or_else(|_| "UNKNOWN".to_string());
-        let name = name_res.unwrap_or_else(|_| "Unknown Token".to_string());
-        let decimals = decimals_res.unwrap_or(18); // 默认 18 位
+        Ok(build_token_info(token, symbol_res.ok(), name_res.ok(), decimals_res.ok()))
+    }
 
-        Ok(TokenInfo {
-            symbol,
-            name,
-            address: format!("{:?}", token),
-            decimals,
-        })
+    /// 批量查询代币信息（Multicall3 单次 RPC，每个代币 symbol/name/decimals 三个子调用）
+    /// 返回值与输入顺序一致，未实现的字段使用默认值
+    #[instrument(skip(self, tokens), fields(count = tokens.len()))]
+    pub async fn tokens_info(&self, tokens: &[Address]) -> Result<Vec<TokenInfo>, Erc20Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc20Error::ProviderUnavailable)?;
+
+        let calls = tokens
+            .iter()
+            .flat_map(|token| {
+                [
+                    ierc20::SymbolCall.encode(),
+                    ierc20::NameCall.encode(),
+                    ierc20::DecimalsCall.encode(),
+                ]
+                .map(|data| Call3::new(*token, data))
+            })
+            .collect();
+
+        let results = multicall::aggregate3(provider, calls, None).await?;
+
+        Ok(tokens
+            .iter()
+            .zip(results.chunks(3))
+            .map(|(token, fields)| token_info_from_results(*token, fields))
+            .collect())
+    }
+
+    /// 查询钱包在区块区间内转入和转出的 ERC20 Transfer 事件（`tokens` 为空时查询所有代币）
+    /// 按 LOG_CHUNK_BLOCKS 分段查询，结果按 (区块号, 日志索引) 排序
+    #[instrument(skip(self))]
+    pub async fn transfer_logs(
+        &self,
+        wallet: Address,
+        tokens: &[Address],
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<TransferLog>, Erc20Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc20Error::ProviderUnavailable)?;
+
+        let transfer_topic: H256 = TRANSFER_EVENT_TOPIC.parse().expect("硬编码事件签名应该有效");
+        let wallet_topic = H256::from(wallet);
+
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut chunk_start = from_block;
+        while chunk_start <= to_block {
+            let chunk_end = (chunk_start + LOG_CHUNK_BLOCKS - 1).min(to_block);
+
+            // 转出（topic1 = from）和转入（topic2 = to）各查询一次
+            let mut base = Filter::new()
+                .from_block(chunk_start)
+                .to_block(chunk_end)
+                .topic0(transfer_topic);
+            if !tokens.is_empty() {
+                base = base.address(tokens.to_vec());
+            }
+            for filter in [base.clone().topic1(wallet_topic), base.topic2(wallet_topic)] {
+                let provider = provider.clone();
+                tasks.spawn(async move { provider.get_logs(&filter).await });
+            }
+
+            chunk_start = chunk_end + 1;
+        }
+
+        let mut transfers = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            let logs = joined.map_err(|e| Erc20Error::AbiError(format!("日志查询任务失败: {}", e)))??;
+            transfers.extend(logs.iter().filter_map(parse_transfer_log));
+        }
+
+        // 自转账会同时出现在两次查询中，按 (交易, 日志索引) 去重
+        transfers.sort_by_key(|t| (t.block_number, t.log_index));
+        transfers.dedup_by_key(|t| (t.tx_hash, t.log_index));
+
+        debug!(count = transfers.len(), "查询到 Transfer 事件");
+
+        Ok(transfers)
+    }
+
+    /// 查询钱包在区块区间内发出的 ERC20 Approval 事件（`tokens` 为空时查询所有代币）
+    /// 按 LOG_CHUNK_BLOCKS 分段查询，结果按 (区块号, 日志索引) 排序
+    #[instrument(skip(self, tokens), fields(tokens = tokens.len()))]
+    pub async fn approval_logs(
+        &self,
+        owner: Address,
+        tokens: &[Address],
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<ApprovalLog>, Erc20Error> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(Erc20Error::ProviderUnavailable)?;
+
+        let approval_topic: H256 = APPROVAL_EVENT_TOPIC.parse().expect("硬编码事件签名应该有效");
+        let owner_topic = H256::from(owner);
+
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut chunk_start = from_block;
+        while chunk_start <= to_block {
+            let chunk_end = (chunk_start + LOG_CHUNK_BLOCKS - 1).min(to_block);
+
+            let mut filter = Filter::new()
+                .from_block(chunk_start)
+                .to_block(chunk_end)
+                .topic0(approval_topic)
+                .topic1(owner_topic);
+            if !tokens.is_empty() {
+                filter = filter.address(tokens.to_vec());
+            }
+            let provider = provider.clone();
+            tasks.spawn(async move { provider.get_logs(&filter).await });
+
+            chunk_start = chunk_end + 1;
+        }
+
+        let mut approvals = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            let logs = joined.map_err(|e| Erc20Error::AbiError(format!("日志查询任务失败: {}", e)))??;
+            approvals.extend(logs.iter().filter_map(parse_approval_log));
+        }
+        approvals.sort_by_key(|a| (a.block_number, a.log_index));
+
+        debug!(count = approvals.len(), "查询到 Approval 事件");
+
+        Ok(approvals)
     }
 }
 
-/// 解析 ABI 编码的字符串返回值
-fn parse_string_return(data: &[u8]) -> Option<String> {
-    if data.len() < 64 {
+/// 解析 ERC20 Approval 日志
+/// ERC721 的 Approval 事件 tokenId 也是 indexed（4 个 topic），会被忽略
+pub fn parse_approval_log(log: &Log) -> Option<ApprovalLog> {
+    if log.topics.len() != 3 || log.data.len() != 32 {
         return None;
     }
 
-    // ABI 字符串编码：
-    // 前 32 字节：offset (通常是 32)
-    // 接下来 32 字节：length
-    // 剩余：实际数据
+    Some(ApprovalLog {
+        token: log.address,
+        owner: Address::from(log.topics[1]),
+        spender: Address::from(log.topics[2]),
+        value: U256::from_big_endian(&log.data),
+        block_number: log.block_number?.as_u64(),
+        log_index: log.log_index?.as_u64(),
+    })
+}
 
-    let offset = U256::from_big_endian(&data[0..32]).as_usize();
-    if offset >= data.len() {
+/// 解析 ERC20 Transfer 日志
+/// ERC721 的 Transfer 事件 tokenId 也是 indexed（4 个 topic），会被忽略
+pub fn parse_transfer_log(log: &Log) -> Option<TransferLog> {
+    if log.topics.len() != 3 || log.data.len() != 32 {
         return None;
     }
 
-    let length = U256::from_big_endian(&data[offset..offset + 32]).as_usize();
-    if offset + 32 + length > data.len() {
-        return None;
+    Some(TransferLog {
+        token: log.address,
+        from: Address::from(log.topics[1]),
+        to: Address::from(log.topics[2]),
+        value: U256::from_big_endian(&log.data),
+        block_number: log.block_number?.as_u64(),
+        tx_hash: log.transaction_hash?,
+        log_index: log.log_index?.as_u64(),
+    })
+}
+
+/// 构建 approve(spender, amount) 调用数据
+pub fn approve_calldata(spender: Address, amount: U256) -> Vec<u8> {
+    ierc20::ApproveCall { spender, amount }.encode()
+}
+
+/// 构建 transfer(to, amount) 调用数据
+pub fn transfer_calldata(to: Address, amount: U256) -> Vec<u8> {
+    ierc20::TransferCall { to, amount }.encode()
+}
+
+/// 按绑定的返回类型解码 eth_call 结果
+fn decode_return<R: AbiDecode>(data: &[u8]) -> Result<R, Erc20Error> {
+    R::decode(data).map_err(|e| Erc20Error::AbiError(format!("解码返回值失败: {}", e)))
+}
+
+/// 由 symbol/name/decimals 三个子调用结果构建代币信息
+fn token_info_from_results(token: Address, fields: &[Call3Result]) -> TokenInfo {
+    let field = |index: usize| {
+        fields
+            .get(index)
+            .filter(|result| result.success)
+            .map(|result| &result.return_data[..])
+    };
+
+    build_token_info(
+        token,
+        field(0).and_then(parse_string_return),
+        field(1).and_then(parse_string_return),
+        field(2).and_then(|data| parse_decimals_return(data).ok()),
+    )
+}
+
+/// 构建代币信息，未查询到的字段使用默认值（有些代币可能没有实现全部接口）
+fn build_token_info(
+    token: Address,
+    symbol: Option<String>,
+    name: Option<String>,
+    decimals: Option<u8>,
+) -> TokenInfo {
+    TokenInfo {
+        symbol: symbol.unwrap_or_else(|| "UNKNOWN".to_string()),
+        name: name.unwrap_or_else(|| "Unknown Token".to_string()),
+        address: checksum_address(token),
+        decimals: decimals.unwrap_or(18), // 默认 18 位
+        listed_on: Vec::new(),
+    }
+}
+
+/// 解析 decimals 返回值
+/// decimals 通常返回 uint8，但某些合约返回 uint256 或单字节，不经过绑定的严格解码
+fn parse_decimals_return(data: &[u8]) -> Result<u8, Erc20Error> {
+    if data.is_empty() {
+        return Err(Erc20Error::AbiError("空返回值".to_string()));
     }
 
-    let string_data = &data[offset + 32..offset + 32 + length];
-    String::from_utf8(string_data.to_vec()).ok()
+    if data.len() == 32 {
+        let value = U256::from_big_endian(data);
+        Ok(value.as_u32() as u8)
+    } else if data.len() == 1 {
+        Ok(data[0])
+    } else {
+        Err(Erc20Error::AbiError(format!(
+            "意外的 decimals 返回值长度: {}",
+            data.len()
+        )))
+    }
+}
+
+/// 解析 ABI 编码的字符串返回值（symbol/name/version 返回类型相同）
+/// offset 和 length 来自合约返回值，越界或溢出时解码失败
+/// MKR、SAI 等早期代币返回固定长度的 bytes32（右侧补零），动态字符串至少 64 字节，按长度区分
+pub(crate) fn parse_string_return(data: &[u8]) -> Option<String> {
+    if data.len() == 32 {
+        return parse_bytes32_string(data);
+    }
+    ierc20::SymbolReturn::decode(data).ok().map(|r| r.0)
+}
+
+/// 解析 bytes32 字符串：去掉右侧补零，要求是不含控制字符的 UTF-8
+fn parse_bytes32_string(data: &[u8]) -> Option<String> {
+    let end = data.iter().rposition(|byte| *byte != 0)? + 1;
+    let value = std::str::from_utf8(&data[..end]).ok()?;
+    if value.chars().any(char::is_control) {
+        return None;
+    }
+    Some(value.to_string())
 }
 
 /// 格式化代币金额
@@ -357,6 +701,104 @@ mod tests {
         assert_eq!(result, U256::from_dec_str("150000000000000000000").unwrap());
     }
 
+    #[test]
+    fn test_parse_bytes32_string_return() {
+        let bytes32 = |value: &str| {
+            let mut data = [0u8; 32];
+            data[..value.len()].copy_from_slice(value.as_bytes());
+            data
+        };
+
+        // MKR: symbol() 和 name() 都返回 bytes32
+        assert_eq!(parse_string_return(&bytes32("MKR")).as_deref(), Some("MKR"));
+        assert_eq!(parse_string_return(&bytes32("Maker")).as_deref(), Some("Maker"));
+        // SAI（单抵押 DAI）
+        assert_eq!(parse_string_return(&bytes32("DAI")).as_deref(), Some("DAI"));
+        assert_eq!(parse_string_return(&bytes32("Dai Stablecoin v1.0")).as_deref(), Some("Dai Stablecoin v1.0"));
+
+        // 全零、中间含 NUL 或非 UTF-8 的返回值无法解析
+        assert_eq!(parse_string_return(&[0u8; 32]), None);
+        let mut embedded_nul = bytes32("MKR");
+        embedded_nul[5] = b'X';
+        assert_eq!(parse_string_return(&embedded_nul), None);
+        let mut invalid_utf8 = bytes32("MKR");
+        invalid_utf8[0] = 0xff;
+        assert_eq!(parse_string_return(&invalid_utf8), None);
+
+        // 通过 Multicall 查询时同样生效
+        let result = |data: Vec<u8>| Call3Result {
+            success: true,
+            return_data: Bytes::from(data),
+        };
+        let fields = [
+            result(bytes32("MKR").to_vec()),
+            result(bytes32("Maker").to_vec()),
+            result(ethers::abi::encode(&[ethers::abi::Token::Uint(U256::from(18))])),
+        ];
+        let info = token_info_from_results(Address::zero(), &fields);
+        assert_eq!(info.symbol, "MKR");
+        assert_eq!(info.name, "Maker");
+    }
+
+    #[test]
+    fn test_parse_string_return_malformed() {
+        // 正常编码的 "USDC"
+        let mut data = vec![0u8; 96];
+        data[31] = 32;
+        data[63] = 4;
+        data[64..68].copy_from_slice(b"USDC");
+        assert_eq!(parse_string_return(&data).as_deref(), Some("USDC"));
+
+        // offset 超过 usize 范围
+        let mut malicious = data.clone();
+        malicious[0] = 0xff;
+        assert_eq!(parse_string_return(&malicious), None);
+
+        // offset 指向末尾附近，读取 length 会越界
+        let mut malicious = data.clone();
+        malicious[31] = 80;
+        assert_eq!(parse_string_return(&malicious), None);
+
+        // length 超大，相加会溢出
+        let mut malicious = data.clone();
+        malicious[32..64].copy_from_slice(&[0xff; 32]);
+        assert_eq!(parse_string_return(&malicious), None);
+    }
+
+    #[test]
+    fn test_allowance_calldata() {
+        let owner = Address::repeat_byte(0x11);
+        let spender = Address::repeat_byte(0x22);
+        let data = ierc20::AllowanceCall { owner, spender }.encode();
+
+        assert_eq!(data.len(), 68);
+        assert_eq!(&data[..4], &[0xdd, 0x62, 0xed, 0x3e]);
+        assert_eq!(&data[16..36], owner.as_bytes());
+        assert_eq!(&data[48..68], spender.as_bytes());
+    }
+
+    #[test]
+    fn test_approve_calldata() {
+        let spender = Address::repeat_byte(0x22);
+        let data = approve_calldata(spender, U256::from(1000));
+
+        assert_eq!(data.len(), 68);
+        assert_eq!(&data[..4], &[0x09, 0x5e, 0xa7, 0xb3]);
+        assert_eq!(&data[16..36], spender.as_bytes());
+        assert_eq!(U256::from_big_endian(&data[36..68]), U256::from(1000));
+    }
+
+    #[test]
+    fn test_transfer_calldata() {
+        let to = Address::repeat_byte(0x33);
+        let data = transfer_calldata(to, U256::from(42));
+
+        assert_eq!(data.len(), 68);
+        assert_eq!(&data[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(&data[16..36], to.as_bytes());
+        assert_eq!(U256::from_big_endian(&data[36..68]), U256::from(42));
+    }
+
     #[tokio::test]
     async fn test_symbol_without_provider_returns_error() {
         let client = Erc20Client::new(None);
@@ -378,6 +820,35 @@ mod tests {
         assert!(matches!(result, Err(Erc20Error::ProviderUnavailable)));
     }
 
+    #[test]
+    fn test_token_info_from_results() {
+        let token = Address::repeat_byte(0x11);
+        let string_result = |value: &str| Call3Result {
+            success: true,
+            return_data: Bytes::from(ethers::abi::encode(&[ethers::abi::Token::String(value.to_string())])),
+        };
+        let decimals = Call3Result {
+            success: true,
+            return_data: Bytes::from(ethers::abi::encode(&[ethers::abi::Token::Uint(U256::from(6))])),
+        };
+
+        let info = token_info_from_results(token, &[string_result("USDC"), string_result("USD Coin"), decimals]);
+        assert_eq!(info.symbol, "USDC");
+        assert_eq!(info.name, "USD Coin");
+        assert_eq!(info.decimals, 6);
+        assert_eq!(info.address, checksum_address(token));
+
+        // 子调用失败的字段使用默认值
+        let failed = Call3Result {
+            success: false,
+            return_data: Bytes::new(),
+        };
+        let info = token_info_from_results(token, &[failed.clone(), string_result("Token"), failed]);
+        assert_eq!(info.symbol, "UNKNOWN");
+        assert_eq!(info.name, "Token");
+        assert_eq!(info.decimals, 18);
+    }
+
     #[tokio::test]
     async fn test_token_info_without_provider_uses_defaults() {
         let client = Erc20Client::new(None);
@@ -418,7 +889,76 @@ mod tests {
         let token = Address::zero();
         let owner = Address::zero();
 
-        let result = client.balance_of(token, owner).await;
+        let result = client.balance_of(token, owner, None).await;
         assert!(result.is_err());
+        assert!(client.total_supply(token).await.is_err());
+    }
+
+    #[test]
+    fn test_parse_transfer_log() {
+        let from: Address = "0x0000000000000000000000000000000000000001".parse().unwrap();
+        let to: Address = "0x0000000000000000000000000000000000000002".parse().unwrap();
+
+        let mut value = [0u8; 32];
+        U256::from(1_000_000u64).to_big_endian(&mut value);
+
+        let mut log = Log {
+            address: Address::repeat_byte(0xaa),
+            topics: vec![
+                TRANSFER_EVENT_TOPIC.parse().unwrap(),
+                H256::from(from),
+                H256::from(to),
+            ],
+            data: Bytes::from(value.to_vec()),
+            block_number: Some(U64::from(100)),
+            transaction_hash: Some(H256::repeat_byte(0x11)),
+            log_index: Some(U256::from(3)),
+            ..Default::default()
+        };
+
+        let transfer = parse_transfer_log(&log).expect("应该能解析 Transfer 日志");
+        assert_eq!(transfer.token, Address::repeat_byte(0xaa));
+        assert_eq!(transfer.from, from);
+        assert_eq!(transfer.to, to);
+        assert_eq!(transfer.value, U256::from(1_000_000u64));
+        assert_eq!(transfer.block_number, 100);
+        assert_eq!(transfer.log_index, 3);
+
+        // ERC721 Transfer（tokenId 为 indexed topic）应被忽略
+        log.topics.push(H256::zero());
+        log.data = Bytes::new();
+        assert!(parse_transfer_log(&log).is_none());
+    }
+
+    #[test]
+    fn test_parse_approval_log() {
+        let owner = Address::repeat_byte(0x01);
+        let spender = Address::repeat_byte(0x02);
+
+        let mut log = Log {
+            address: Address::repeat_byte(0xaa),
+            topics: vec![
+                APPROVAL_EVENT_TOPIC.parse().unwrap(),
+                H256::from(owner),
+                H256::from(spender),
+            ],
+            data: Bytes::from([0xffu8; 32].to_vec()),
+            block_number: Some(U64::from(200)),
+            log_index: Some(U256::from(7)),
+            ..Default::default()
+        };
+
+        let approval = parse_approval_log(&log).expect("应该能解析 Approval 日志");
+        assert_eq!(approval.token, Address::repeat_byte(0xaa));
+        assert_eq!(approval.owner, owner);
+        assert_eq!(approval.spender, spender);
+        assert_eq!(approval.value, U256::MAX);
+        assert_eq!(approval.block_number, 200);
+        assert_eq!(approval.log_index, 7);
+
+        // ERC721 Approval（tokenId 为 indexed topic）应被忽略
+        log.topics.push(H256::zero());
+        log.data = Bytes::new();
+        assert!(parse_approval_log(&log).is_none());
     }
 }
@@ -1,5 +1,17 @@
+use crate::diagnostics::{
+    record_rpc_call, RetryPolicy, RpcTransportConfig, RpcTransportError, TimedHttp,
+};
+use crate::multicall::{self, Call3, Call3Result, MulticallError};
+use crate::types::{ReadFinality, TxType};
+use crate::workers::WorkerManager;
 use ethers::prelude::*;
-use std::sync::Arc;
+use ethers::types::spoof;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::providers::HttpClientError;
+use serde::de::DeserializeOwned;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, instrument, warn};
 
 /// Ethereum 客户端错误类型
@@ -11,9 +23,13 @@ pub enum EthClientError {
     #[error("无效的地址: {0}")]
     InvalidAddress(String),
 
+    #[error("Multicall 错误: {0}")]
+    MulticallError(#[from] MulticallError),
+
     #[error("RPC URL 未配置")]
     NoRpcUrl,
 
+    #[allow(dead_code)]
     #[error("连接超时")]
     Timeout,
 
@@ -21,54 +37,337 @@ pub enum EthClientError {
     Other(String),
 }
 
+/// 费用估算时回溯的区块数量（eth_feeHistory）
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// EIP-1559 费用估算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeEstimate {
+    /// 下一个区块的基础费用
+    pub base_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    /// 2 × 基础费用 + 小费，可承受连续 6 个满区块的基础费用上涨
+    pub max_fee_per_gas: U256,
+}
+
+/// 按交易类型估算的费用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxFees {
+    Legacy { gas_price: U256 },
+    Eip1559(FeeEstimate),
+}
+
+impl TxFees {
+    pub fn tx_type(&self) -> TxType {
+        match self {
+            Self::Legacy { .. } => TxType::Legacy,
+            Self::Eip1559(_) => TxType::Eip1559,
+        }
+    }
+
+    /// 预计实际支付的单位 Gas 费用
+    pub fn expected_fee_per_gas(&self) -> U256 {
+        match self {
+            Self::Legacy { gas_price } => *gas_price,
+            Self::Eip1559(fees) => fees.base_fee_per_gas + fees.max_priority_fee_per_gas,
+        }
+    }
+
+    /// 最高可能支付的单位 Gas 费用
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match self {
+            Self::Legacy { gas_price } => *gas_price,
+            Self::Eip1559(fees) => fees.max_fee_per_gas,
+        }
+    }
+
+    /// 把费用写入交易（交易类型需与费用类型一致）
+    pub fn apply(&self, tx: &mut TypedTransaction) {
+        match (self, tx) {
+            (Self::Eip1559(fees), TypedTransaction::Eip1559(request)) => {
+                request.max_fee_per_gas = Some(fees.max_fee_per_gas);
+                request.max_priority_fee_per_gas = Some(fees.max_priority_fee_per_gas);
+            }
+            (fees, tx) => {
+                tx.set_gas_price(fees.max_fee_per_gas());
+            }
+        }
+    }
+}
+
+/// 连续失败达到该次数后节点进入冷却期
+const ENDPOINT_FAILURE_THRESHOLD: u32 = 3;
+/// 节点冷却时间，期间只在其他节点都失败时才使用
+const ENDPOINT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// 单个 RPC 节点的健康状态
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndpointHealth {
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    /// 冷却截止时间（连续失败过多时设置）
+    pub cooldown_until: Option<Instant>,
+    pub last_error: Option<String>,
+}
+
+impl EndpointHealth {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.cooldown_until.is_none_or(|until| now >= until)
+    }
+
+    fn record_success(&mut self) {
+        self.successes += 1;
+        self.consecutive_failures = 0;
+        self.cooldown_until = None;
+    }
+
+    fn record_failure(&mut self, error: &HttpClientError, now: Instant) {
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        self.last_error = Some(error.to_string());
+        if self.consecutive_failures >= ENDPOINT_FAILURE_THRESHOLD {
+            self.cooldown_until = Some(now + ENDPOINT_COOLDOWN);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RpcEndpoint {
+    /// 节点标识（只含主机名，不暴露 URL 中的 API Key）
+    label: String,
+    http: Http,
+    health: Mutex<EndpointHealth>,
+}
+
+/// 多节点故障转移传输（ETHEREUM_RPC_URLS）
+/// 当前节点出现暂时性错误（超时、限流、网关错误等）时切换到下一个节点；
+/// 连续失败的节点进入冷却期，冷却期内优先使用其他节点
+#[derive(Debug)]
+pub struct FailoverHttp {
+    endpoints: Vec<RpcEndpoint>,
+    /// 当前优先使用的节点
+    active: AtomicUsize,
+}
+
+impl FailoverHttp {
+    pub fn new(urls: &[String], client: reqwest::Client) -> anyhow::Result<Self> {
+        if urls.is_empty() {
+            anyhow::bail!("RPC URL 未配置");
+        }
+
+        let mut endpoints: Vec<RpcEndpoint> = Vec::with_capacity(urls.len());
+        for url in urls {
+            let url = reqwest::Url::parse(url)?;
+            let host = url.host_str().unwrap_or("unknown").to_string();
+            // 同一主机配置了多个节点（如不同 API Key）时追加序号区分
+            let label = if endpoints.iter().any(|endpoint| endpoint.label == host) {
+                format!("{}#{}", host, endpoints.len() + 1)
+            } else {
+                host
+            };
+            endpoints.push(RpcEndpoint {
+                label,
+                http: Http::new_with_client(url, client.clone()),
+                health: Mutex::new(EndpointHealth::default()),
+            });
+        }
+
+        Ok(Self {
+            endpoints,
+            active: AtomicUsize::new(0),
+        })
+    }
+
+    /// 本次请求尝试节点的顺序：从当前节点开始轮转，冷却中的节点排在最后
+    fn attempt_order(&self, now: Instant) -> Vec<usize> {
+        let len = self.endpoints.len();
+        let start = self.active.load(Ordering::Relaxed) % len;
+        let (mut healthy, cooling): (Vec<usize>, Vec<usize>) = (0..len)
+            .map(|offset| (start + offset) % len)
+            .partition(|&index| {
+                self.endpoints[index]
+                    .health
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .is_healthy(now)
+            });
+        healthy.extend(cooling);
+        healthy
+    }
+
+    /// 发送请求，返回结果和实际处理请求的节点标识
+    pub async fn request<R>(
+        &self,
+        method: &str,
+        params: &serde_json::Value,
+    ) -> (Result<R, HttpClientError>, &str)
+    where
+        R: DeserializeOwned + Send,
+    {
+        let order = self.attempt_order(Instant::now());
+        let mut last = None;
+
+        for (attempt, &index) in order.iter().enumerate() {
+            let endpoint = &self.endpoints[index];
+            let result = JsonRpcClient::request(&endpoint.http, method, params.clone()).await;
+            let mut health = endpoint.health.lock().unwrap_or_else(|e| e.into_inner());
+
+            match result {
+                Err(e) if RetryPolicy::is_transient(&e) => {
+                    health.record_failure(&e, Instant::now());
+                    let consecutive_failures = health.consecutive_failures;
+                    drop(health);
+                    if let Some(&next) = order.get(attempt + 1) {
+                        warn!(
+                            method,
+                            endpoint = %endpoint.label,
+                            consecutive_failures,
+                            next = %self.endpoints[next].label,
+                            error = %e,
+                            "RPC 节点请求失败,切换到下一个节点"
+                        );
+                        self.active.store(next, Ordering::Relaxed);
+                    }
+                    last = Some((Err(e), endpoint.label.as_str()));
+                }
+                // 节点正常响应（包括 execution reverted 等 JSON-RPC 错误）
+                result => {
+                    health.record_success();
+                    return (result, endpoint.label.as_str());
+                }
+            }
+        }
+
+        last.expect("至少配置了一个节点")
+    }
+}
+
+/// WebSocket 连接断开后的自动重连次数
+const WS_RECONNECTS: usize = 5;
+/// 新区块订阅断开后首次重新订阅的等待时间（之后按 WorkerManager 的指数退避）
+const BLOCK_SUBSCRIPTION_RETRY: Duration = Duration::from_secs(5);
+
+/// 所有客户端共用的 Provider
+pub type RpcProvider = Provider<RpcTransport>;
+
+/// RPC 传输：HTTP（重试、超时、故障转移）或 WebSocket（额外支持订阅新区块和日志）
+#[derive(Debug)]
+pub enum RpcTransport {
+    Http(TimedHttp),
+    Ws(Ws),
+}
+
+impl RpcTransport {
+    /// 创建 HTTP Provider（多个节点时按顺序故障转移）
+    pub fn http(urls: &[String], transport: &RpcTransportConfig) -> anyhow::Result<RpcProvider> {
+        Ok(Provider::new(Self::Http(TimedHttp::new(urls, transport)?)))
+    }
+
+    /// 连接 WebSocket 节点（ETHEREUM_WS_URL）
+    pub async fn ws(url: &str) -> anyhow::Result<RpcProvider> {
+        let ws = Ws::connect_with_reconnects(url, WS_RECONNECTS).await?;
+        Ok(Provider::new(Self::Ws(ws)))
+    }
+
+}
+
+#[async_trait::async_trait]
+impl JsonRpcClient for RpcTransport {
+    type Error = RpcTransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: std::fmt::Debug + serde::Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            Self::Http(http) => JsonRpcClient::request(http, method, params).await,
+            Self::Ws(ws) => {
+                let started = Instant::now();
+                let result = JsonRpcClient::request(ws, method, params).await;
+                record_rpc_call(method, Some("websocket"), started.elapsed(), 0, result.is_ok());
+                Ok(result?)
+            }
+        }
+    }
+}
+
+impl PubsubClient for RpcTransport {
+    type NotificationStream = <Ws as PubsubClient>::NotificationStream;
+
+    fn subscribe<T: Into<U256>>(&self, id: T) -> Result<Self::NotificationStream, Self::Error> {
+        match self {
+            Self::Ws(ws) => Ok(ws.subscribe(id)?),
+            Self::Http(_) => Err(RpcTransportError::PubsubUnsupported),
+        }
+    }
+
+    fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), Self::Error> {
+        match self {
+            Self::Ws(ws) => Ok(ws.unsubscribe(id)?),
+            Self::Http(_) => Err(RpcTransportError::PubsubUnsupported),
+        }
+    }
+}
+
+/// 启动新区块订阅任务（需要 WebSocket Provider）
+/// 每个新区块调用一次 `on_block`；订阅断开时由 WorkerManager 退避后重新订阅
+pub fn spawn_block_subscription<F>(workers: &WorkerManager, provider: Arc<RpcProvider>, on_block: F)
+where
+    F: Fn(&Block<H256>) + Clone + Send + Sync + 'static,
+{
+    workers.spawn_periodic(
+        "block_subscription",
+        "通过 WebSocket 订阅新区块,新区块到达时刷新最新区块的储备量缓存",
+        BLOCK_SUBSCRIPTION_RETRY,
+        move || {
+            let provider = provider.clone();
+            let on_block = on_block.clone();
+
+            async move {
+                let mut blocks = provider
+                    .subscribe_blocks()
+                    .await
+                    .map_err(|e| format!("订阅新区块失败: {}", e))?;
+
+                while let Some(block) = blocks.next().await {
+                    debug!(number = ?block.number, hash = ?block.hash, "收到新区块");
+                    on_block(&block);
+                }
+
+                Err("新区块订阅已断开".to_string())
+            }
+        },
+    );
+}
+
 /// Ethereum RPC 客户端
 #[derive(Clone)]
 pub struct EthClient {
-    provider: Option<Arc<Provider<Http>>>,
+    provider: Option<Arc<RpcProvider>>,
+    /// 链是否支持 EIP-1559（首次探测后缓存）
+    eip1559_support: Arc<tokio::sync::OnceCell<bool>>,
 }
 
 impl EthClient {
     /// 创建新的 Ethereum 客户端
     ///
     /// # 参数
-    /// - `rpc_url`: RPC 节点地址（可选）
+    /// - `rpc_urls`: RPC 节点地址（为空时客户端不可用，多个节点时按顺序故障转移）
     /// - `network_id`: 网络 ID（可选）
-    #[instrument(skip(rpc_url))]
-    pub async fn new(rpc_url: Option<&str>, network_id: Option<u64>) -> anyhow::Result<Self> {
-        let provider = if let Some(url) = rpc_url {
-            info!(rpc_url = %url, "初始化 Ethereum 客户端");
-
-            match Provider::<Http>::try_from(url) {
-                Ok(provider) => {
-                    // 测试连接
-                    match provider.get_chainid().await {
-                        Ok(chain_id) => {
-                            let chain_id_u64 = chain_id.as_u64();
-                            if let Some(expected) = network_id {
-                                if expected != chain_id_u64 {
-                                    warn!(
-                                        expected = expected,
-                                        actual = chain_id_u64,
-                                        "提供的 Chain ID 与节点返回值不一致"
-                                    );
-                                }
-                            }
-
-                            info!(
-                                chain_id = %chain_id_u64,
-                                "成功连接到 Ethereum 节点"
-                            );
-                            Some(Arc::new(provider))
-                        }
-                        Err(e) => {
-                            warn!(
-                                error = %e,
-                                "无法连接到 Ethereum 节点，将在测试模式下运行"
-                            );
-                            None
-                        }
-                    }
-                }
+    /// - `transport`: RPC 重试、超时和并发限制（与其他客户端共享）
+    #[instrument(skip(rpc_urls, transport))]
+    pub async fn new(
+        rpc_urls: &[String],
+        network_id: Option<u64>,
+        transport: &RpcTransportConfig,
+    ) -> anyhow::Result<Self> {
+        let provider = if !rpc_urls.is_empty() {
+            info!(endpoints = rpc_urls.len(), "初始化 Ethereum 客户端");
+
+            match RpcTransport::http(rpc_urls, transport) {
+                Ok(provider) => Some(Arc::new(provider)),
                 Err(e) => {
                     error!(error = %e, "创建 Provider 失败");
                     None
@@ -79,7 +378,41 @@ impl EthClient {
             None
         };
 
-        Ok(Self { provider })
+        let mut client = Self {
+            provider,
+            eip1559_support: Arc::new(tokio::sync::OnceCell::new()),
+        };
+
+        // 测试连接
+        if client.is_available() {
+            match client.get_chain_id().await {
+                Ok(chain_id) => {
+                    if let Some(expected) = network_id
+                        && expected != chain_id
+                    {
+                        warn!(
+                            expected = expected,
+                            actual = chain_id,
+                            "提供的 Chain ID 与节点返回值不一致"
+                        );
+                    }
+
+                    info!(
+                        chain_id = %chain_id,
+                        "成功连接到 Ethereum 节点"
+                    );
+                }
+                Err(e) => {
+                    warn!(
+                        error = %e,
+                        "无法连接到 Ethereum 节点，将在测试模式下运行"
+                    );
+                    client.provider = None;
+                }
+            }
+        }
+
+        Ok(client)
     }
 
     /// 检查客户端是否可用
@@ -91,11 +424,16 @@ impl EthClient {
     ///
     /// # 参数
     /// - `address`: 以太坊地址字符串
+    /// - `block`: 区块（None 表示最新区块）
     ///
     /// # 返回
     /// 余额（以 Wei 为单位的 U256）
     #[instrument(skip(self))]
-    pub async fn get_balance(&self, address: &str) -> Result<U256, EthClientError> {
+    pub async fn get_balance(
+        &self,
+        address: &str,
+        block: Option<BlockId>,
+    ) -> Result<U256, EthClientError> {
         // 检查客户端是否可用
         let provider = self
             .provider
@@ -110,17 +448,57 @@ impl EthClient {
             .map_err(|_| EthClientError::InvalidAddress(address.to_string()))?;
 
         // 查询余额
-        let balance_wei = provider.get_balance(addr, None).await?;
+        let balance_wei = provider.get_balance(addr, block).await?;
 
         info!(
             address = %address,
             balance_wei = %balance_wei,
+            balance_eth = %wei_to_eth(balance_wei),
             "成功查询余额"
         );
 
         Ok(balance_wei)
     }
 
+    /// 批量查询多个地址的 ETH 余额（通过 Multicall3 单次 RPC 完成）
+    #[instrument(skip(self, addresses), fields(count = addresses.len()))]
+    pub async fn get_balances(&self, addresses: &[Address]) -> Result<Vec<U256>, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let calls = addresses.iter().map(|addr| Call3::eth_balance(*addr)).collect();
+        let results = multicall::aggregate3(provider, calls, None).await?;
+
+        results
+            .iter()
+            .zip(addresses)
+            .map(|(result, addr)| {
+                result.as_u256().ok_or_else(|| {
+                    EthClientError::Other(format!("查询 {:?} 的 ETH 余额失败", addr))
+                })
+            })
+            .collect()
+    }
+
+    /// 以 `from` 为调用方、在状态覆盖之上通过 Multicall3.aggregate3Value 顺序执行子调用
+    #[instrument(skip(self, calls, state), fields(calls = calls.len()))]
+    pub async fn simulate_multicall(
+        &self,
+        from: Address,
+        calls: &[Call3],
+        state: &spoof::State,
+        block: Option<BlockId>,
+    ) -> Result<Vec<Call3Result>, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        Ok(multicall::aggregate3_value(provider, from, calls, state, block).await?)
+    }
+
     /// 获取当前区块号
     #[instrument(skip(self))]
     pub async fn get_block_number(&self) -> Result<u64, EthClientError> {
@@ -136,6 +514,473 @@ impl EthClient {
         Ok(block_number.as_u64())
     }
 
+    /// 获取地址在指定区块的交易计数（latest 为已确认的 nonce，pending 包含内存池中的交易）
+    #[instrument(skip(self))]
+    pub async fn get_transaction_count(&self, address: Address, block: BlockNumber) -> Result<u64, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let count = provider.get_transaction_count(address, Some(block.into())).await?;
+
+        debug!(address = ?address, block = ?block, count = %count, "获取交易计数");
+
+        Ok(count.as_u64())
+    }
+
+    /// 查询事件日志（eth_getLogs），区块区间由调用方限制
+    #[instrument(skip(self))]
+    pub async fn get_logs(&self, filter: &Filter) -> Result<Vec<Log>, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let logs = provider.get_logs(filter).await?;
+
+        debug!(count = logs.len(), "查询事件日志");
+
+        Ok(logs)
+    }
+
+    /// 获取 pending 区块中的交易（节点当前打包候选，近似内存池视图）
+    #[instrument(skip(self))]
+    pub async fn pending_transactions(&self) -> Result<Vec<Transaction>, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let block = provider.get_block_with_txs(BlockNumber::Pending).await?;
+        let transactions = block.map(|b| b.transactions).unwrap_or_default();
+
+        debug!(count = transactions.len(), "获取 pending 交易");
+
+        Ok(transactions)
+    }
+
+    /// 按哈希获取交易和回执（交易不存在时返回 None，尚未打包时回执为 None）
+    #[instrument(skip(self))]
+    pub async fn get_transaction(
+        &self,
+        hash: H256,
+    ) -> Result<Option<(Transaction, Option<TransactionReceipt>)>, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let (tx, receipt) = tokio::join!(
+            provider.get_transaction(hash),
+            provider.get_transaction_receipt(hash)
+        );
+        let Some(tx) = tx? else {
+            return Ok(None);
+        };
+        let receipt = receipt?;
+
+        debug!(tx_hash = ?hash, mined = receipt.is_some(), "获取交易");
+
+        Ok(Some((tx, receipt)))
+    }
+
+    /// 获取交易回执（尚未打包或交易不存在时返回 None）
+    #[instrument(skip(self))]
+    pub async fn get_transaction_receipt(&self, hash: H256) -> Result<Option<TransactionReceipt>, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        Ok(provider.get_transaction_receipt(hash).await?)
+    }
+
+    /// 广播已签名的原始交易（eth_sendRawTransaction），返回交易哈希
+    #[instrument(skip(self, raw), fields(len = raw.len()))]
+    pub async fn send_raw_transaction(&self, raw: Bytes) -> Result<H256, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let pending = provider.send_raw_transaction(raw).await?;
+        let tx_hash = pending.tx_hash();
+
+        info!(tx_hash = ?tx_hash, "原始交易已广播");
+
+        Ok(tx_hash)
+    }
+
+    /// 获取区块号和时间戳（秒）
+    ///
+    /// # 参数
+    /// - `block`: 区块号或标签（latest/finalized 等）
+    #[instrument(skip(self))]
+    pub async fn get_block_timestamp(&self, block: BlockNumber) -> Result<(u64, u64), EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let block_data = provider
+            .get_block(block)
+            .await?
+            .ok_or_else(|| EthClientError::Other(format!("区块不存在: {:?}", block)))?;
+
+        let number = block_data
+            .number
+            .ok_or_else(|| EthClientError::Other("区块尚未确认".to_string()))?
+            .as_u64();
+        let timestamp = block_data.timestamp.as_u64();
+
+        debug!(block_number = number, timestamp = timestamp, "获取区块时间戳");
+
+        Ok((number, timestamp))
+    }
+
+    /// 二分查找时间戳不早于 `timestamp` 的第一个区块
+    /// 时间戳晚于最新区块时返回最新区块号
+    #[instrument(skip(self))]
+    pub async fn find_block_by_timestamp(&self, timestamp: u64) -> Result<u64, EthClientError> {
+        let (latest, latest_timestamp) = self.get_block_timestamp(BlockNumber::Latest).await?;
+        if timestamp >= latest_timestamp {
+            return Ok(latest);
+        }
+
+        let (mut low, mut high) = (0u64, latest);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let (_, mid_timestamp) = self
+                .get_block_timestamp(BlockNumber::Number(mid.into()))
+                .await?;
+
+            if mid_timestamp < timestamp {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        debug!(timestamp, block_number = low, "按时间戳定位区块");
+
+        Ok(low)
+    }
+
+    /// 获取地址的合约代码（`block` 为 None 时查询最新区块，非合约地址返回空）
+    #[instrument(skip(self))]
+    pub async fn get_code(&self, address: Address, block: Option<BlockId>) -> Result<Bytes, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        Ok(provider.get_code(address, block).await?)
+    }
+
+    /// 读取合约存储槽（`block` 为 None 时查询最新区块）
+    #[instrument(skip(self))]
+    pub async fn get_storage_at(&self, address: Address, slot: H256, block: Option<BlockId>) -> Result<H256, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        Ok(provider.get_storage_at(address, slot, block).await?)
+    }
+
+    /// 二分查找合约代码首次出现的区块（需要归档节点）
+    /// 最新区块没有合约代码时返回 None
+    #[instrument(skip(self))]
+    pub async fn find_contract_creation_block(&self, address: Address) -> Result<Option<u64>, EthClientError> {
+        let latest = self.get_block_number().await?;
+        let has_code = |block: u64| async move {
+            self.get_code(address, Some(BlockId::Number(block.into())))
+                .await
+                .map(|code| !code.is_empty())
+        };
+        if !has_code(latest).await? {
+            return Ok(None);
+        }
+
+        let (mut low, mut high) = (0u64, latest);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if has_code(mid).await? {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+
+        debug!(address = ?address, block_number = low, "定位合约创建区块");
+
+        Ok(Some(low))
+    }
+
+    /// 按确认深度确定读取的区块号
+    /// 返回 None 表示直接读取最新区块；finalized 标签解析为具体区块号，保证同一请求内的多次读取一致
+    #[instrument(skip(self))]
+    pub async fn resolve_read_block(
+        &self,
+        finality: ReadFinality,
+        finality_blocks: u64,
+    ) -> Result<Option<u64>, EthClientError> {
+        match finality {
+            ReadFinality::Latest => Ok(None),
+            ReadFinality::Confirmed => {
+                let latest = self.get_block_number().await?;
+                Ok(Some(latest.saturating_sub(finality_blocks)))
+            }
+            ReadFinality::Finalized => {
+                let (number, _) = self.get_block_timestamp(BlockNumber::Finalized).await?;
+                Ok(Some(number))
+            }
+        }
+    }
+
+    /// 获取账户及存储槽的 Merkle 证明（eth_getProof）
+    ///
+    /// # 参数
+    /// - `address`: 账户地址字符串
+    /// - `storage_keys`: 需要证明的存储槽
+    /// - `block`: 区块（None 表示最新区块）
+    #[instrument(skip(self))]
+    pub async fn get_proof(
+        &self,
+        address: &str,
+        storage_keys: Vec<H256>,
+        block: Option<BlockId>,
+    ) -> Result<EIP1186ProofResponse, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let addr: Address = address
+            .parse()
+            .map_err(|_| EthClientError::InvalidAddress(address.to_string()))?;
+
+        let proof = provider.get_proof(addr, storage_keys, block).await?;
+
+        debug!(
+            address = %address,
+            storage_proofs = proof.storage_proof.len(),
+            "获取 Merkle 证明"
+        );
+
+        Ok(proof)
+    }
+
+    /// 估算交易所需的 Gas（eth_estimateGas）
+    #[instrument(skip(self, tx))]
+    pub async fn estimate_gas(&self, tx: &TypedTransaction) -> Result<U256, EthClientError> {
+        self.estimate_gas_at(tx, None).await
+    }
+
+    /// 在指定区块上估算 Gas（`block` 为 None 时使用最新区块）
+    #[instrument(skip(self, tx))]
+    pub async fn estimate_gas_at(&self, tx: &TypedTransaction, block: Option<BlockId>) -> Result<U256, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let gas = provider.estimate_gas(tx, block).await?;
+
+        debug!(gas = %gas, "估算 Gas");
+
+        Ok(gas)
+    }
+
+    /// 执行只读调用（eth_call，latest 区块）
+    #[instrument(skip(self, tx))]
+    pub async fn call(&self, tx: &TypedTransaction) -> Result<Bytes, EthClientError> {
+        self.call_at(tx, None).await
+    }
+
+    /// 在指定区块上执行只读调用（`block` 为 None 时使用最新区块）
+    #[instrument(skip(self, tx))]
+    pub async fn call_at(&self, tx: &TypedTransaction, block: Option<BlockId>) -> Result<Bytes, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let output = provider.call(tx, block).await?;
+
+        debug!(len = output.len(), "eth_call 返回");
+
+        Ok(output)
+    }
+
+    /// 使用 callTracer 跟踪调用（debug_traceCall，包含各调用帧的事件日志）
+    /// `state` 为可选的状态覆盖，用于在前序交易的执行结果上继续模拟
+    /// 需要节点开放 debug 命名空间
+    #[instrument(skip(self, tx, state))]
+    pub async fn trace_call(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+        state: Option<spoof::State>,
+    ) -> Result<CallFrame, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let options = GethDebugTracingCallOptions {
+            tracing_options: GethDebugTracingOptions {
+                tracer: Some(GethDebugTracerType::BuiltInTracer(
+                    GethDebugBuiltInTracerType::CallTracer,
+                )),
+                tracer_config: Some(GethDebugTracerConfig::BuiltInTracer(
+                    GethDebugBuiltInTracerConfig::CallTracer(CallConfig {
+                        only_top_call: Some(false),
+                        with_log: Some(true),
+                    }),
+                )),
+                ..Default::default()
+            },
+            state_overrides: state,
+            block_overrides: None,
+        };
+
+        match provider.debug_trace_call(tx.clone(), block, options).await? {
+            GethTrace::Known(GethTraceFrame::CallTracer(frame)) => Ok(frame),
+            other => Err(EthClientError::Other(format!("无法解析 callTracer 结果: {:?}", other))),
+        }
+    }
+
+    /// 使用 prestateTracer(diffMode)获取调用前后的状态差异
+    /// 与 `trace_call` 使用相同的状态覆盖，返回的 post 状态可合并进下一次调用的覆盖
+    #[instrument(skip(self, tx, state))]
+    pub async fn trace_state_diff(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+        state: Option<spoof::State>,
+    ) -> Result<DiffMode, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let options = GethDebugTracingCallOptions {
+            tracing_options: GethDebugTracingOptions {
+                tracer: Some(GethDebugTracerType::BuiltInTracer(
+                    GethDebugBuiltInTracerType::PreStateTracer,
+                )),
+                tracer_config: Some(GethDebugTracerConfig::BuiltInTracer(
+                    GethDebugBuiltInTracerConfig::PreStateTracer(PreStateConfig {
+                        diff_mode: Some(true),
+                    }),
+                )),
+                ..Default::default()
+            },
+            state_overrides: state,
+            block_overrides: None,
+        };
+
+        match provider.debug_trace_call(tx.clone(), block, options).await? {
+            GethTrace::Known(GethTraceFrame::PreStateTracer(PreStateFrame::Diff(diff))) => Ok(diff),
+            other => Err(EthClientError::Other(format!("无法解析 prestateTracer 结果: {:?}", other))),
+        }
+    }
+
+    /// 按 Gas 价格策略估算 EIP-1559 费用
+    /// 小费取最近 FEE_HISTORY_BLOCKS 个区块在策略百分位上的中位数
+    #[instrument(skip(self))]
+    pub async fn estimate_fees(&self, strategy: &str) -> Result<FeeEstimate, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let history = provider
+            .fee_history(
+                FEE_HISTORY_BLOCKS,
+                BlockNumber::Latest,
+                &[strategy_reward_percentile(strategy)],
+            )
+            .await?;
+
+        let fees = fee_from_history(&history.base_fee_per_gas, &history.reward)
+            .ok_or_else(|| EthClientError::Other("节点未返回费用历史".to_string()))?;
+
+        debug!(
+            strategy = %strategy,
+            base_fee = %fees.base_fee_per_gas,
+            priority_fee = %fees.max_priority_fee_per_gas,
+            "估算 EIP-1559 费用"
+        );
+
+        Ok(fees)
+    }
+
+    /// 按 Gas 价格策略估算传统交易的 gasPrice
+    #[instrument(skip(self))]
+    pub async fn estimate_legacy_gas_price(&self, strategy: &str) -> Result<U256, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let gas_price = provider.get_gas_price().await?;
+        let adjusted = gas_price * U256::from(strategy_gas_price_percent(strategy)) / U256::from(100);
+
+        debug!(strategy = %strategy, gas_price = %adjusted, "估算传统交易 gasPrice");
+
+        Ok(adjusted)
+    }
+
+    /// 按交易类型和 Gas 价格策略估算费用
+    pub async fn estimate_tx_fees(&self, strategy: &str, tx_type: TxType) -> Result<TxFees, EthClientError> {
+        match tx_type {
+            TxType::Legacy => Ok(TxFees::Legacy {
+                gas_price: self.estimate_legacy_gas_price(strategy).await?,
+            }),
+            TxType::Eip1559 => Ok(TxFees::Eip1559(self.estimate_fees(strategy).await?)),
+        }
+    }
+
+    /// 探测链是否支持 EIP-1559（最新区块是否包含 baseFeePerGas）
+    #[instrument(skip(self))]
+    pub async fn supports_eip1559(&self) -> Result<bool, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let supported = self
+            .eip1559_support
+            .get_or_try_init(|| async {
+                let block = provider
+                    .get_block(BlockNumber::Latest)
+                    .await?
+                    .ok_or_else(|| EthClientError::Other("无法获取最新区块".to_string()))?;
+                Ok::<_, EthClientError>(block.base_fee_per_gas.is_some())
+            })
+            .await?;
+
+        debug!(supported = *supported, "EIP-1559 支持情况");
+
+        Ok(*supported)
+    }
+
+    /// 确定交易类型：指定了偏好则直接使用，否则按链上探测结果选择
+    pub async fn resolve_tx_type(&self, preference: Option<TxType>) -> Result<TxType, EthClientError> {
+        if let Some(tx_type) = preference {
+            return Ok(tx_type);
+        }
+
+        Ok(if self.supports_eip1559().await? {
+            TxType::Eip1559
+        } else {
+            TxType::Legacy
+        })
+    }
+
     /// 获取链 ID
     #[instrument(skip(self))]
     pub async fn get_chain_id(&self) -> Result<u64, EthClientError> {
@@ -151,40 +996,199 @@ impl EthClient {
         Ok(chain_id.as_u64())
     }
 
-    /// 获取网络 Gas 价格
+    /// 获取网络 Gas 价格（eth_gasPrice，单位 Wei）
     #[instrument(skip(self))]
-    pub async fn get_gas_price(&self) -> Result<f64, EthClientError> {
+    pub async fn get_gas_price(&self) -> Result<U256, EthClientError> {
         let provider = self
             .provider
             .as_ref()
             .ok_or(EthClientError::NoRpcUrl)?;
 
         let gas_price_wei = provider.get_gas_price().await?;
-        let gas_price_gwei = wei_to_gwei(gas_price_wei);
 
-        debug!(gas_price_gwei = %gas_price_gwei, "获取 Gas 价格");
+        debug!(gas_price_gwei = %wei_to_gwei(gas_price_wei), "获取 Gas 价格");
+
+        Ok(gas_price_wei)
+    }
+
+    /// 按 slow/standard/fast 三档估算 EIP-1559 费用（单次 eth_feeHistory 查询三个百分位）
+    #[instrument(skip(self))]
+    pub async fn estimate_fee_tiers(&self) -> Result<Vec<(&'static str, FeeEstimate)>, EthClientError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(EthClientError::NoRpcUrl)?;
+
+        let percentiles = GAS_STRATEGIES.map(strategy_reward_percentile);
+        let history = provider
+            .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &percentiles)
+            .await?;
+
+        GAS_STRATEGIES
+            .iter()
+            .enumerate()
+            .map(|(index, strategy)| {
+                fee_from_history_at(&history.base_fee_per_gas, &history.reward, index)
+                    .map(|fees| (*strategy, fees))
+                    .ok_or_else(|| EthClientError::Other("节点未返回费用历史".to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Gas 价格策略，从慢到快
+pub const GAS_STRATEGIES: [&str; 3] = ["slow", "standard", "fast"];
+
+/// Gas 价格策略对应的小费百分位
+pub fn strategy_reward_percentile(strategy: &str) -> f64 {
+    match strategy {
+        "fast" => 90.0,
+        "slow" => 10.0,
+        _ => 50.0,
+    }
+}
 
-        Ok(gas_price_gwei)
+/// Gas 价格策略对应的传统 gasPrice 调整比例（百分比）
+pub fn strategy_gas_price_percent(strategy: &str) -> u64 {
+    match strategy {
+        "fast" => 120,
+        "slow" => 90,
+        _ => 100,
     }
 }
 
+/// 根据 eth_feeHistory 结果计算费用
+/// `base_fees` 的最后一项为下一个区块的基础费用，`rewards` 每项为单个区块的小费百分位
+fn fee_from_history(base_fees: &[U256], rewards: &[Vec<U256>]) -> Option<FeeEstimate> {
+    fee_from_history_at(base_fees, rewards, 0)
+}
+
+/// 同 fee_from_history，小费取每个区块的第 `index` 个百分位
+fn fee_from_history_at(base_fees: &[U256], rewards: &[Vec<U256>], index: usize) -> Option<FeeEstimate> {
+    let base_fee_per_gas = *base_fees.last()?;
+
+    let mut tips: Vec<U256> = rewards.iter().filter_map(|r| r.get(index).copied()).collect();
+    tips.sort();
+    let max_priority_fee_per_gas = tips.get(tips.len() / 2).copied().unwrap_or_default();
+
+    Some(FeeEstimate {
+        base_fee_per_gas,
+        max_priority_fee_per_gas,
+        max_fee_per_gas: base_fee_per_gas * 2 + max_priority_fee_per_gas,
+    })
+}
+
 /// 将 Wei 转换为 ETH
 fn wei_to_eth(wei: U256) -> f64 {
     let eth_decimals = U256::from(10).pow(U256::from(18));
-    let eth_value = wei.as_u128() as f64 / eth_decimals.as_u128() as f64;
-    eth_value
+    wei.as_u128() as f64 / eth_decimals.as_u128() as f64
 }
 
 /// 将 Wei 转换为 Gwei
 fn wei_to_gwei(wei: U256) -> f64 {
     let gwei_decimals = U256::from(10).pow(U256::from(9));
-    let gwei_value = wei.as_u128() as f64 / gwei_decimals.as_u128() as f64;
-    gwei_value
+    wei.as_u128() as f64 / gwei_decimals.as_u128() as f64
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// 启动只返回固定响应的本地 RPC 节点
+    async fn spawn_rpc_stub(status: &'static str, body: &'static str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_failover_rotates_to_healthy_endpoint() {
+        let bad = spawn_rpc_stub("502 Bad Gateway", "<html>502 Bad Gateway</html>").await;
+        let good = spawn_rpc_stub("200 OK", r#"{"jsonrpc":"2.0","id":1,"result":"0x10"}"#).await;
+        let failover = FailoverHttp::new(&[bad, good], reqwest::Client::new()).unwrap();
+
+        let (result, endpoint) = failover
+            .request::<U64>("eth_blockNumber", &serde_json::json!([]))
+            .await;
+        assert_eq!(result.unwrap(), U64::from(16));
+        // 同一主机的第二个节点追加序号
+        assert_eq!(endpoint, "127.0.0.1#2");
+        assert_eq!(failover.active.load(Ordering::Relaxed), 1);
+
+        let bad_health = failover.endpoints[0].health.lock().unwrap().clone();
+        assert_eq!((bad_health.failures, bad_health.consecutive_failures), (1, 1));
+        assert!(bad_health.last_error.unwrap().contains("502"));
+        assert_eq!(failover.endpoints[1].health.lock().unwrap().successes, 1);
+
+        // 后续请求直接使用切换后的节点
+        let (_, endpoint) = failover
+            .request::<U64>("eth_blockNumber", &serde_json::json!([]))
+            .await;
+        assert_eq!(endpoint, "127.0.0.1#2");
+        assert_eq!(failover.endpoints[0].health.lock().unwrap().failures, 1);
+    }
+
+    #[test]
+    fn test_http_transport_rejects_subscriptions() {
+        let http = TimedHttp::new(
+            &["http://127.0.0.1:8545".to_string()],
+            &RpcTransportConfig::new(0, Duration::from_secs(1), 0),
+        )
+        .unwrap();
+        let transport = RpcTransport::Http(http);
+
+        assert!(matches!(transport.subscribe(1u64), Err(RpcTransportError::PubsubUnsupported)));
+        assert!(matches!(transport.unsubscribe(1u64), Err(RpcTransportError::PubsubUnsupported)));
+    }
+
+    #[test]
+    fn test_failover_skips_cooling_endpoints() {
+        let urls = [
+            "https://a.example.com".to_string(),
+            "https://b.example.com/v2/key".to_string(),
+            "https://c.example.com".to_string(),
+        ];
+        let failover = FailoverHttp::new(&urls, reqwest::Client::new()).unwrap();
+        assert_eq!(failover.endpoints[1].label, "b.example.com");
+        assert!(FailoverHttp::new(&[], reqwest::Client::new()).is_err());
+
+        let now = Instant::now();
+        assert_eq!(failover.attempt_order(now), vec![0, 1, 2]);
+
+        // 连续失败达到阈值后进入冷却，排到最后
+        let error = HttpClientError::SerdeJson {
+            err: serde_json::from_str::<serde_json::Value>("<").unwrap_err(),
+            text: "429 Too Many Requests".to_string(),
+        };
+        {
+            let mut health = failover.endpoints[0].health.lock().unwrap();
+            for _ in 0..ENDPOINT_FAILURE_THRESHOLD {
+                health.record_failure(&error, now);
+            }
+        }
+        assert_eq!(failover.attempt_order(now), vec![1, 2, 0]);
+        assert_eq!(failover.attempt_order(now + ENDPOINT_COOLDOWN), vec![0, 1, 2]);
+
+        failover.active.store(2, Ordering::Relaxed);
+        assert_eq!(failover.attempt_order(now), vec![2, 1, 0]);
+
+        failover.endpoints[0].health.lock().unwrap().record_success();
+        assert_eq!(failover.attempt_order(now), vec![2, 0, 1]);
+    }
 
     #[test]
     fn test_wei_to_eth() {
@@ -211,30 +1215,116 @@ mod tests {
         assert_eq!(wei_to_gwei(fifty_gwei), 50.0);
     }
 
+    #[test]
+    fn test_fee_from_history() {
+        let gwei = |n: u64| U256::from(n) * U256::exp10(9);
+        let base_fees = vec![gwei(10), gwei(12), gwei(11)];
+        let rewards = vec![vec![gwei(3)], vec![gwei(1)], vec![gwei(2)]];
+
+        let fees = fee_from_history(&base_fees, &rewards).unwrap();
+        assert_eq!(fees.base_fee_per_gas, gwei(11));
+        assert_eq!(fees.max_priority_fee_per_gas, gwei(2));
+        assert_eq!(fees.max_fee_per_gas, gwei(24));
+
+        assert!(fee_from_history(&[], &rewards).is_none());
+
+        // 多个百分位：按列取中位数
+        let rewards = vec![vec![gwei(1), gwei(5)], vec![gwei(2), gwei(7)], vec![gwei(1), gwei(6)]];
+        assert_eq!(fee_from_history_at(&base_fees, &rewards, 0).unwrap().max_priority_fee_per_gas, gwei(1));
+        assert_eq!(fee_from_history_at(&base_fees, &rewards, 1).unwrap().max_priority_fee_per_gas, gwei(6));
+    }
+
+    #[test]
+    fn test_tx_fees() {
+        let gwei = |n: u64| U256::from(n) * U256::exp10(9);
+
+        let legacy = TxFees::Legacy { gas_price: gwei(30) };
+        assert_eq!(legacy.tx_type(), TxType::Legacy);
+        assert_eq!(legacy.expected_fee_per_gas(), gwei(30));
+        assert_eq!(legacy.max_fee_per_gas(), gwei(30));
+
+        let eip1559 = TxFees::Eip1559(FeeEstimate {
+            base_fee_per_gas: gwei(20),
+            max_priority_fee_per_gas: gwei(2),
+            max_fee_per_gas: gwei(42),
+        });
+        assert_eq!(eip1559.tx_type(), TxType::Eip1559);
+        assert_eq!(eip1559.expected_fee_per_gas(), gwei(22));
+        assert_eq!(eip1559.max_fee_per_gas(), gwei(42));
+
+        let mut tx = TxType::Eip1559.new_request();
+        eip1559.apply(&mut tx);
+        let TypedTransaction::Eip1559(ref request) = tx else {
+            panic!("应该是 EIP-1559 交易");
+        };
+        assert_eq!(request.max_fee_per_gas, Some(gwei(42)));
+        assert_eq!(request.max_priority_fee_per_gas, Some(gwei(2)));
+
+        let mut tx = TxType::Legacy.new_request();
+        legacy.apply(&mut tx);
+        assert_eq!(tx.gas_price(), Some(gwei(30)));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_read_block_latest() {
+        // latest 不需要 RPC
+        let client = EthClient::new(&[], None, &RpcTransportConfig::new(0, Duration::from_secs(30), 0)).await.unwrap();
+        assert_eq!(
+            client.resolve_read_block(ReadFinality::Latest, 12).await.unwrap(),
+            None
+        );
+        assert!(client.resolve_read_block(ReadFinality::Confirmed, 12).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_tx_type_with_preference() {
+        // 指定偏好时不需要 RPC
+        let client = EthClient::new(&[], None, &RpcTransportConfig::new(0, Duration::from_secs(30), 0)).await.unwrap();
+        assert_eq!(
+            client.resolve_tx_type(Some(TxType::Legacy)).await.unwrap(),
+            TxType::Legacy
+        );
+        assert!(client.resolve_tx_type(None).await.is_err());
+    }
+
+    #[test]
+    fn test_strategy_reward_percentile() {
+        assert_eq!(strategy_reward_percentile("fast"), 90.0);
+        assert_eq!(strategy_reward_percentile("standard"), 50.0);
+        assert_eq!(strategy_reward_percentile("slow"), 10.0);
+    }
+
     #[tokio::test]
     async fn test_eth_client_without_provider() {
-        let client = EthClient::new(None, None).await.unwrap();
+        let client = EthClient::new(&[], None, &RpcTransportConfig::new(0, Duration::from_secs(30), 0)).await.unwrap();
         assert!(!client.is_available());
 
-        let result = client.get_balance("0x0").await;
+        let result = client.get_balance("0x0", None).await;
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_get_code_without_provider() {
+        let client = EthClient::new(&[], None, &RpcTransportConfig::new(0, Duration::from_secs(30), 0)).await.unwrap();
+        assert!(client.get_code(Address::zero(), None).await.is_err());
+        assert!(client.find_contract_creation_block(Address::zero()).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_get_block_number_without_provider() {
-        let client = EthClient::new(None, None).await.unwrap();
+        let client = EthClient::new(&[], None, &RpcTransportConfig::new(0, Duration::from_secs(30), 0)).await.unwrap();
         assert!(client.get_block_number().await.is_err());
     }
 
     #[tokio::test]
     async fn test_get_chain_id_without_provider() {
-        let client = EthClient::new(None, None).await.unwrap();
+        let client = EthClient::new(&[], None, &RpcTransportConfig::new(0, Duration::from_secs(30), 0)).await.unwrap();
         assert!(client.get_chain_id().await.is_err());
     }
 
     #[tokio::test]
     async fn test_get_gas_price_without_provider() {
-        let client = EthClient::new(None, None).await.unwrap();
+        let client = EthClient::new(&[], None, &RpcTransportConfig::new(0, Duration::from_secs(30), 0)).await.unwrap();
         assert!(client.get_gas_price().await.is_err());
     }
 
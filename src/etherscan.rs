@@ -0,0 +1,382 @@
+use crate::diagnostics::record_rpc_call;
+use crate::erc20::TransferLog;
+use ethers::abi::Abi;
+use ethers::types::{Address, H256, U256};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument};
+
+/// Etherscan API 地址（v2，通过 chainid 参数区分链）
+const ETHERSCAN_API_BASE: &str = "https://api.etherscan.io/v2/api";
+/// Etherscan 请求超时时间
+const ETHERSCAN_TIMEOUT: Duration = Duration::from_secs(10);
+/// 单次查询返回的最大转账记录数（Etherscan 单页上限为 10000）
+pub const ETHERSCAN_TRANSFER_PAGE_SIZE: usize = 1_000;
+
+/// Etherscan API 错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum EtherscanError {
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("未配置 ETHERSCAN_API_KEY")]
+    MissingApiKey,
+
+    #[error("Etherscan 返回错误: {0}")]
+    ApiError(String),
+
+    #[error("响应格式错误: {0}")]
+    InvalidResponse(String),
+}
+
+/// 合约源码验证信息（getsourcecode）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractSource {
+    /// 源码是否已在 Etherscan 验证
+    pub verified: bool,
+    pub contract_name: Option<String>,
+    pub compiler_version: Option<String>,
+    pub optimization_used: Option<bool>,
+    pub optimization_runs: Option<u64>,
+    pub evm_version: Option<String>,
+    pub license: Option<String>,
+    /// Etherscan 是否将其识别为代理合约
+    pub proxy: bool,
+    /// 代理合约指向的实现合约
+    pub implementation: Option<Address>,
+}
+
+/// Etherscan API 客户端
+#[derive(Clone)]
+pub struct EtherscanClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    chain_id: u64,
+    /// 已验证合约的源码信息（验证后不会撤销，可以长期缓存）
+    verified_sources: Arc<RwLock<HashMap<Address, ContractSource>>>,
+}
+
+impl EtherscanClient {
+    /// 创建 Etherscan 客户端（未配置 API Key 时不可用）
+    pub fn new(api_key: Option<String>, chain_id: u64) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(ETHERSCAN_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            base_url: ETHERSCAN_API_BASE.to_string(),
+            api_key,
+            chain_id,
+            verified_sources: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    /// 获取已验证合约的 ABI
+    #[instrument(skip(self))]
+    pub async fn contract_abi(&self, address: Address) -> Result<Abi, EtherscanError> {
+        let address = format!("{:?}", address);
+        let result = self
+            .request("contract_getabi", &[("module", "contract"), ("action", "getabi"), ("address", &address)])
+            .await?;
+
+        let abi = parse_abi(&result)?;
+        debug!(address = %address, functions = abi.functions().count(), "获取 Etherscan ABI");
+        Ok(abi)
+    }
+
+    /// 查询合约的源码验证信息（合约名称、编译器版本和代理状态）
+    #[instrument(skip(self))]
+    pub async fn contract_source(&self, address: Address) -> Result<ContractSource, EtherscanError> {
+        if let Some(source) = self.verified_sources.read().unwrap().get(&address) {
+            return Ok(source.clone());
+        }
+
+        let address_str = format!("{:?}", address);
+        let result = self
+            .request(
+                "contract_getsourcecode",
+                &[("module", "contract"), ("action", "getsourcecode"), ("address", &address_str)],
+            )
+            .await?;
+
+        let source = parse_contract_source(&result)?;
+        debug!(address = %address_str, verified = source.verified, proxy = source.proxy, "获取 Etherscan 源码信息");
+        if source.verified {
+            self.verified_sources.write().unwrap().insert(address, source.clone());
+        }
+        Ok(source)
+    }
+
+    /// 查询钱包在区块区间内的 ERC20 转账（`token` 为 None 时查询所有代币）
+    /// 按区块倒序返回最新的 ETHERSCAN_TRANSFER_PAGE_SIZE 条
+    #[instrument(skip(self))]
+    pub async fn token_transfers(
+        &self,
+        wallet: Address,
+        token: Option<Address>,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<TransferLog>, EtherscanError> {
+        let wallet = format!("{:?}", wallet);
+        let token = token.map(|token| format!("{:?}", token));
+        let from_block = from_block.to_string();
+        let to_block = to_block.to_string();
+        let page_size = ETHERSCAN_TRANSFER_PAGE_SIZE.to_string();
+
+        let mut params = vec![
+            ("module", "account"),
+            ("action", "tokentx"),
+            ("address", wallet.as_str()),
+            ("startblock", from_block.as_str()),
+            ("endblock", to_block.as_str()),
+            ("page", "1"),
+            ("offset", page_size.as_str()),
+            ("sort", "desc"),
+        ];
+        if let Some(ref token) = token {
+            params.push(("contractaddress", token.as_str()));
+        }
+
+        let result = self.request("account_tokentx", &params).await?;
+        let transfers = parse_token_transfers(&result)?;
+        debug!(wallet = %wallet, count = transfers.len(), "获取 Etherscan 转账记录");
+        Ok(transfers)
+    }
+
+    /// 发送 API 请求，返回响应中的 result 字段
+    async fn request(&self, method: &str, params: &[(&str, &str)]) -> Result<serde_json::Value, EtherscanError> {
+        let api_key = self.api_key.as_deref().ok_or(EtherscanError::MissingApiKey)?;
+
+        let chain_id = self.chain_id.to_string();
+        let request = self
+            .http
+            .get(&self.base_url)
+            .query(&[("chainid", chain_id.as_str()), ("apikey", api_key)])
+            .query(params);
+
+        let started = Instant::now();
+        let response = async { request.send().await?.error_for_status()?.json().await }.await;
+        record_rpc_call(&format!("etherscan_{}", method), None, started.elapsed(), 0, response.is_ok());
+        let response: serde_json::Value = response?;
+
+        parse_response(response)
+    }
+}
+
+/// 解析通用响应：`{"status": "1", "message": "OK", "result": ...}`，失败时 result 为错误说明
+fn parse_response(mut response: serde_json::Value) -> Result<serde_json::Value, EtherscanError> {
+    let status = response["status"].as_str().unwrap_or_default().to_string();
+    let result = response
+        .get_mut("result")
+        .map(serde_json::Value::take)
+        .ok_or_else(|| EtherscanError::InvalidResponse(response.to_string()))?;
+
+    // 列表接口没有记录时返回 status 0 和空数组（No transactions found）
+    if status != "1" && result.as_array().is_some_and(Vec::is_empty) {
+        return Ok(result);
+    }
+    if status != "1" {
+        let message = result
+            .as_str()
+            .or(response["message"].as_str())
+            .unwrap_or("未知错误")
+            .to_string();
+        return Err(EtherscanError::ApiError(message));
+    }
+    Ok(result)
+}
+
+/// getabi 的 result 是 JSON 编码的 ABI 字符串
+fn parse_abi(result: &serde_json::Value) -> Result<Abi, EtherscanError> {
+    let text = result
+        .as_str()
+        .ok_or_else(|| EtherscanError::InvalidResponse(result.to_string()))?;
+    serde_json::from_str(text).map_err(|e| EtherscanError::InvalidResponse(format!("无效的 ABI: {}", e)))
+}
+
+/// 解析 getsourcecode 的 result：单元素数组，未验证的合约 SourceCode 为空、ABI 为提示文字
+fn parse_contract_source(result: &serde_json::Value) -> Result<ContractSource, EtherscanError> {
+    let item = result
+        .as_array()
+        .and_then(|items| items.first())
+        .ok_or_else(|| EtherscanError::InvalidResponse(result.to_string()))?;
+
+    let text = |key: &str| {
+        item[key]
+            .as_str()
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+
+    let verified = text("SourceCode").is_some();
+    let implementation = text("Implementation")
+        .and_then(|address| address.parse::<Address>().ok())
+        .filter(|address| !address.is_zero());
+
+    Ok(ContractSource {
+        verified,
+        contract_name: text("ContractName"),
+        compiler_version: text("CompilerVersion"),
+        optimization_used: verified.then(|| text("OptimizationUsed").as_deref() == Some("1")),
+        optimization_runs: text("Runs").and_then(|runs| runs.parse().ok()),
+        evm_version: text("EVMVersion"),
+        license: text("LicenseType"),
+        proxy: text("Proxy").as_deref() == Some("1"),
+        implementation,
+    })
+}
+
+/// 解析 tokentx 的 result：数值字段均为十进制字符串
+fn parse_token_transfers(result: &serde_json::Value) -> Result<Vec<TransferLog>, EtherscanError> {
+    let items = result
+        .as_array()
+        .ok_or_else(|| EtherscanError::InvalidResponse(result.to_string()))?;
+
+    items
+        .iter()
+        .map(|item| {
+            let field = |key: &str| {
+                item[key]
+                    .as_str()
+                    .ok_or_else(|| EtherscanError::InvalidResponse(format!("缺少字段 {}", key)))
+            };
+            let invalid = |key: &str| EtherscanError::InvalidResponse(format!("无效的 {}: {}", key, item[key]));
+            let address = |key: &str| field(key)?.parse::<Address>().map_err(|_| invalid(key));
+            let number = |key: &str| field(key)?.parse::<u64>().map_err(|_| invalid(key));
+
+            Ok(TransferLog {
+                token: address("contractAddress")?,
+                from: address("from")?,
+                to: address("to")?,
+                value: U256::from_dec_str(field("value")?).map_err(|_| invalid("value"))?,
+                block_number: number("blockNumber")?,
+                tx_hash: field("hash")?.parse::<H256>().map_err(|_| invalid("hash"))?,
+                // 部分链的 tokentx 不返回 logIndex
+                log_index: number("logIndex").unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_abi_response() {
+        let abi = r#"[{"type":"function","name":"deposit","inputs":[],"outputs":[],"stateMutability":"payable"}]"#;
+        let response = serde_json::json!({ "status": "1", "message": "OK", "result": abi });
+
+        let abi = parse_abi(&parse_response(response).unwrap()).unwrap();
+        assert!(abi.function("deposit").is_ok());
+
+        let response = serde_json::json!({
+            "status": "0",
+            "message": "NOTOK",
+            "result": "Contract source code not verified"
+        });
+        assert!(matches!(
+            parse_response(response),
+            Err(EtherscanError::ApiError(message)) if message == "Contract source code not verified"
+        ));
+
+        assert!(matches!(
+            parse_response(serde_json::json!({ "status": "1" })),
+            Err(EtherscanError::InvalidResponse(_))
+        ));
+        assert!(parse_abi(&serde_json::json!("not json")).is_err());
+    }
+
+    #[test]
+    fn test_parse_token_transfers() {
+        let response = serde_json::json!({
+            "status": "1",
+            "message": "OK",
+            "result": [{
+                "blockNumber": "19000000",
+                "timeStamp": "1705000000",
+                "hash": format!("0x{}", "ab".repeat(32)),
+                "from": "0x1111111111111111111111111111111111111111",
+                "to": "0x2222222222222222222222222222222222222222",
+                "contractAddress": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+                "value": "1500000",
+                "tokenSymbol": "USDC",
+                "tokenDecimal": "6",
+                "logIndex": "12"
+            }]
+        });
+
+        let transfers = parse_token_transfers(&parse_response(response).unwrap()).unwrap();
+        assert_eq!(transfers.len(), 1);
+        assert_eq!(transfers[0].value, U256::from(1_500_000u64));
+        assert_eq!(transfers[0].block_number, 19_000_000);
+        assert_eq!(transfers[0].log_index, 12);
+        assert_eq!(transfers[0].from, Address::repeat_byte(0x11));
+
+        // 没有记录时返回空列表而不是错误
+        let empty = serde_json::json!({ "status": "0", "message": "No transactions found", "result": [] });
+        assert!(parse_token_transfers(&parse_response(empty).unwrap()).unwrap().is_empty());
+
+        assert!(parse_token_transfers(&serde_json::json!([{ "value": "1" }])).is_err());
+    }
+
+    #[test]
+    fn test_parse_contract_source() {
+        let verified = serde_json::json!([{
+            "SourceCode": "pragma solidity 0.8.20; contract FiatTokenProxy {}",
+            "ABI": "[]",
+            "ContractName": "FiatTokenProxy",
+            "CompilerVersion": "v0.4.24+commit.e67f0147",
+            "OptimizationUsed": "0",
+            "Runs": "200",
+            "EVMVersion": "Default",
+            "LicenseType": "",
+            "Proxy": "1",
+            "Implementation": "0x43506849d7c04f9138d1a2050bbf3a0c054402dd"
+        }]);
+        let source = parse_contract_source(&verified).unwrap();
+        assert!(source.verified);
+        assert_eq!(source.contract_name.as_deref(), Some("FiatTokenProxy"));
+        assert_eq!(source.compiler_version.as_deref(), Some("v0.4.24+commit.e67f0147"));
+        assert_eq!(source.optimization_used, Some(false));
+        assert_eq!(source.optimization_runs, Some(200));
+        assert_eq!(source.license, None);
+        assert!(source.proxy);
+        assert_eq!(
+            source.implementation,
+            Some("0x43506849d7c04f9138d1a2050bbf3a0c054402dd".parse().unwrap())
+        );
+
+        let unverified = serde_json::json!([{
+            "SourceCode": "",
+            "ABI": "Contract source code not verified",
+            "ContractName": "",
+            "CompilerVersion": "",
+            "OptimizationUsed": "",
+            "Runs": "",
+            "Proxy": "0",
+            "Implementation": ""
+        }]);
+        let source = parse_contract_source(&unverified).unwrap();
+        assert!(!source.verified);
+        assert_eq!(source.contract_name, None);
+        assert_eq!(source.optimization_used, None);
+        assert!(!source.proxy);
+        assert_eq!(source.implementation, None);
+
+        assert!(parse_contract_source(&serde_json::json!([])).is_err());
+    }
+
+    #[test]
+    fn test_client_requires_api_key() {
+        assert!(!EtherscanClient::new(None, 1).is_available());
+        assert!(EtherscanClient::new(Some("key".to_string()), 1).is_available());
+    }
+}
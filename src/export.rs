@@ -0,0 +1,110 @@
+use rmcp::{model::*, ErrorData as McpError};
+use serde::Serialize;
+
+/// 工具结果的导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    /// 解析 `export` 参数（不区分大小写，默认 JSON）
+    pub fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(|v| v.to_lowercase()) {
+            None => Ok(Self::Json),
+            Some(v) if v == "json" => Ok(Self::Json),
+            Some(v) if v == "csv" => Ok(Self::Csv),
+            Some(v) => Err(format!("不支持的导出格式: {} (支持 json、csv)", v)),
+        }
+    }
+}
+
+/// 可以导出为 CSV 表格的工具结果
+pub trait CsvExport {
+    /// 表头
+    fn csv_headers(&self) -> Vec<&'static str>;
+    /// 数据行（每行字段数应与表头一致）
+    fn csv_rows(&self) -> Vec<Vec<String>>;
+}
+
+/// 将结果渲染为 CSV 文本
+pub fn to_csv<T: CsvExport>(value: &T) -> String {
+    let mut lines = vec![value
+        .csv_headers()
+        .iter()
+        .map(|h| escape_csv_field(h))
+        .collect::<Vec<_>>()
+        .join(",")];
+
+    for row in value.csv_rows() {
+        lines.push(
+            row.iter()
+                .map(|field| escape_csv_field(field))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// 按导出格式构建工具返回值
+pub fn render<T: Serialize + CsvExport>(
+    value: &T,
+    format: ExportFormat,
+) -> Result<CallToolResult, McpError> {
+    let text = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(value)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?,
+        ExportFormat::Csv => to_csv(value),
+    };
+
+    Ok(CallToolResult::success(vec![Content::text(text)]))
+}
+
+/// CSV 字段转义：包含逗号、引号或换行时用双引号包裹
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Sample;
+
+    impl CsvExport for Sample {
+        fn csv_headers(&self) -> Vec<&'static str> {
+            vec!["name", "amount"]
+        }
+
+        fn csv_rows(&self) -> Vec<Vec<String>> {
+            vec![
+                vec!["USDC".to_string(), "1.5".to_string()],
+                vec!["Token, \"Quoted\"".to_string(), "2".to_string()],
+            ]
+        }
+    }
+
+    #[test]
+    fn test_export_format_parse() {
+        assert_eq!(ExportFormat::parse(None).unwrap(), ExportFormat::Json);
+        assert_eq!(ExportFormat::parse(Some("CSV")).unwrap(), ExportFormat::Csv);
+        assert_eq!(ExportFormat::parse(Some("json")).unwrap(), ExportFormat::Json);
+        assert!(ExportFormat::parse(Some("xml")).is_err());
+    }
+
+    #[test]
+    fn test_to_csv_escaping() {
+        let csv = to_csv(&Sample);
+        assert_eq!(
+            csv,
+            "name,amount\nUSDC,1.5\n\"Token, \"\"Quoted\"\"\",2\n"
+        );
+    }
+}
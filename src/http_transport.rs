@@ -0,0 +1,152 @@
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::{StreamableHttpServerConfig, StreamableHttpService};
+use rmcp::ServerHandler;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// MCP 端点路径
+pub const MCP_PATH: &str = "/mcp";
+
+/// 构建 Streamable HTTP（含 SSE 推送）路由，每个会话使用一份服务器副本
+/// 配置 `auth_token` 时所有请求都必须携带 `Authorization: Bearer <token>`
+pub fn router<S>(server: S, auth_token: Option<String>) -> axum::Router
+where
+    S: ServerHandler + Clone + Send + 'static,
+{
+    let service = StreamableHttpService::new(
+        move || Ok(server.clone()),
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig::default(),
+    );
+
+    let router = axum::Router::new().nest_service(MCP_PATH, service);
+    match auth_token {
+        Some(token) => router.layer(middleware::from_fn_with_state(Arc::<str>::from(token), require_bearer)),
+        None => router,
+    }
+}
+
+/// 监听 `bind` 地址提供 MCP 服务，收到 Ctrl+C 时停止
+pub async fn serve<S>(server: S, bind: SocketAddr, auth_token: Option<String>) -> anyhow::Result<()>
+where
+    S: ServerHandler + Clone + Send + 'static,
+{
+    if auth_token.is_none() && !bind.ip().is_loopback() {
+        warn!(bind = %bind, "HTTP 传输监听非本地地址但未配置 HTTP_AUTH_TOKEN");
+    }
+
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    info!(bind = %listener.local_addr()?, path = MCP_PATH, "HTTP 传输已启动");
+
+    // 记录对端地址,供按客户端限流使用
+    let app = router(server, auth_token).into_make_service_with_connect_info::<SocketAddr>();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+async fn require_bearer(State(token): State<Arc<str>>, request: Request, next: Next) -> Response {
+    if bearer_matches(request.headers(), &token) {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "缺少或无效的 Bearer Token",
+        )
+            .into_response()
+    }
+}
+
+/// 校验 Authorization 头（逐字节比较全部内容，耗时与不匹配位置无关）
+fn bearer_matches(headers: &HeaderMap, token: &str) -> bool {
+    let Some(provided) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+
+    provided.len() == token.len()
+        && provided
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use rmcp::model::ServerInfo;
+
+    #[derive(Clone)]
+    struct EmptyServer;
+
+    impl ServerHandler for EmptyServer {
+        fn get_info(&self) -> ServerInfo {
+            ServerInfo::default()
+        }
+    }
+
+    #[test]
+    fn test_bearer_matches() {
+        let mut headers = HeaderMap::new();
+        assert!(!bearer_matches(&headers, "secret"));
+
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+        assert!(bearer_matches(&headers, "secret"));
+        assert!(!bearer_matches(&headers, "secret2"));
+        assert!(!bearer_matches(&headers, "secreT"));
+
+        headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Basic secret"));
+        assert!(!bearer_matches(&headers, "secret"));
+    }
+
+    #[tokio::test]
+    async fn test_router_requires_token() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(axum::serve(listener, router(EmptyServer, Some("secret".to_string()))).into_future());
+
+        let url = format!("http://{}{}", addr, MCP_PATH);
+        let initialize = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": {"name": "test", "version": "0.1.0"}
+            }
+        });
+        let client = reqwest::Client::new();
+        let send = |token: Option<&str>| {
+            let mut request = client
+                .post(&url)
+                .header("Accept", "application/json, text/event-stream")
+                .json(&initialize);
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            request.send()
+        };
+
+        assert_eq!(send(None).await.unwrap().status().as_u16(), 401);
+        assert_eq!(send(Some("wrong")).await.unwrap().status().as_u16(), 401);
+
+        let response = send(Some("secret")).await.unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+        assert!(response.headers().contains_key("mcp-session-id"));
+    }
+}
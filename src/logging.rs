@@ -1,3 +1,4 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use tracing::Level;
 use tracing_subscriber::{
     fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry,
@@ -53,6 +54,16 @@ pub fn init_logging(log_level: &str, json_format: bool) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// 生成工具调用的请求 ID(毫秒时间戳 + 进程内递增序号)
+/// 附加到日志 span 和错误响应中，用于将客户端看到的错误与服务器日志对应
+pub fn next_request_id() -> String {
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+    let millis = chrono::Utc::now().timestamp_millis() as u64;
+    let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:x}", millis, sequence)
+}
+
 /// 解析日志级别字符串
 fn parse_log_level(level_str: &str) -> Level {
     match level_str.to_lowercase().as_str() {
@@ -72,7 +83,7 @@ fn parse_log_level(level_str: &str) -> Level {
 }
 
 /// 日志宏的便捷重导出（仅暴露实际使用的宏）
-pub use tracing::info;
+pub use tracing::{info, warn};
 
 #[cfg(test)]
 mod tests {
@@ -87,4 +98,12 @@ mod tests {
         assert_eq!(parse_log_level("error"), Level::ERROR);
         assert_eq!(parse_log_level("invalid"), Level::INFO);
     }
+
+    #[test]
+    fn test_next_request_id_is_unique() {
+        let first = next_request_id();
+        let second = next_request_id();
+        assert_ne!(first, second);
+        assert!(first.contains('-'));
+    }
 }
@@ -1,33 +1,152 @@
+mod account_abstraction;
+mod alchemy;
+mod bindings;
+mod chains;
+mod coingecko;
+mod completion;
+mod compliance;
 mod config;
+mod cow;
+mod curve;
+mod defillama;
+mod diagnostics;
+mod eip3009;
+mod erc1155;
 mod erc20;
 mod eth_client;
+mod etherscan;
+mod export;
+mod http_transport;
 mod logging;
+mod mempool;
+mod mev;
+mod multicall;
+mod nft;
+mod pagination;
+mod panic_guard;
+mod pnl;
+mod quoting;
+mod rate_limit;
+mod relay;
+mod safe;
+mod signer;
+mod snapshot;
+mod staking;
+mod store;
+mod subgraph;
+mod token_lists;
 mod token_registry;
 mod tools;
+mod tx_manager;
 mod types;
 mod uniswap;
+mod uniswap_v3;
+mod workers;
+mod zerox;
 
+use account_abstraction::BundlerClient;
+use alchemy::AlchemyClient;
+use coingecko::CoinGeckoClient;
+use compliance::ComplianceScreen;
 use config::Config;
+use cow::CowClient;
+use curve::CurveClient;
+use defillama::DefiLlamaClient;
+use diagnostics::RpcTransportConfig;
+use erc1155::Erc1155Client;
 use erc20::Erc20Client;
-use eth_client::EthClient;
-use ethers::prelude::*;
-use logging::info;
+use eth_client::{EthClient, RpcProvider, RpcTransport};
+use etherscan::EtherscanClient;
+use logging::{info, warn};
+use mempool::MempoolWatcher;
+use nft::NftClient;
+use tracing::Instrument;
+use quoting::{AggregatorBackend, AggregatorSource, QuoteAggregator, QuoteBackend};
+use rate_limit::RateLimiter;
+use relay::GelatoRelayClient;
+use safe::SafeClient;
+use signer::TxSigner;
+use snapshot::{MarketSnapshot, SnapshotStore};
+use staking::StakingClient;
+use store::Store;
+use subgraph::SubgraphClient;
+use token_lists::TokenListClient;
 use token_registry::TokenRegistry;
+use tx_manager::TxManager;
 use tools::{
     balance::{get_balance, GetBalanceArgs},
     price::{get_token_price, GetTokenPriceArgs},
     swap::{swap_tokens, SwapTokensArgs},
+    reserve_history::{get_reserve_history, GetReserveHistoryArgs},
+    chain_time::{get_chain_time, GetChainTimeArgs},
+    proof::{get_proof, GetProofArgs},
+    staking::{get_staking_apr, GetStakingAprArgs},
+    aggregate_balance::{get_aggregate_balance, GetAggregateBalanceArgs},
+    history::{get_audit_log, get_recorded_history, GetAuditLogArgs, GetRecordedHistoryArgs},
+    pnl::{get_pnl, GetPnlArgs},
+    cost_basis::{get_cost_basis, GetCostBasisArgs},
+    tax_report::{generate_tax_report, GenerateTaxReportArgs},
+    position_size::{suggest_position_size, SuggestPositionSizeArgs},
+    quote_drift::{compare_quote_drift, CompareQuoteDriftArgs},
+    new_pairs::{get_new_pairs, GetNewPairsArgs},
+    trending::{get_trending_tokens, GetTrendingTokensArgs},
+    gas::{estimate_gas, get_gas_price, EstimateGasArgs, GetGasPriceArgs},
+    batch::{batch_query, BatchQueryArgs},
+    workers::{list_workers, ListWorkersArgs},
+    snapshot::{
+        export_market_snapshot, import_market_snapshot, ExportMarketSnapshotArgs,
+        ImportMarketSnapshotArgs,
+    },
+    preview::{preview_transaction, PreviewTransactionArgs},
+    bundle::{simulate_transactions, SimulateTransactionsArgs},
+    user_operation::{send_user_operation, SendUserOperationArgs},
+    relay::{
+        get_relay_task_status, relay_transaction, GetRelayTaskStatusArgs, RelayTransactionArgs,
+    },
+    authorization::{sign_transfer_authorization, SignTransferAuthorizationArgs},
+    cow::{get_order_status, place_cow_order, GetOrderStatusArgs, PlaceCowOrderArgs},
+    quotes::{compare_quotes, CompareQuotesArgs},
+    execute_swap::{execute_swap, ExecuteSwapArgs},
+    allowance::{get_allowance, GetAllowanceArgs},
+    approve::{approve_token, ApproveTokenArgs},
+    portfolio::{get_portfolio, GetPortfolioArgs},
+    registry::{register_token, remove_token, RegisterTokenArgs, RemoveTokenArgs},
+    token_metadata::{get_token_metadata, GetTokenMetadataArgs},
+    twap::{get_twap_price, GetTwapPriceArgs},
+    transaction::{get_transaction, GetTransactionArgs},
+    account::{get_account_info, GetAccountInfoArgs},
+    calldata::{decode_calldata, DecodeCalldataArgs},
+    simulate::{simulate_transaction, SimulateTransactionArgs},
+    logs::{get_logs, GetLogsArgs},
+    token_transfers::{get_token_transfers, GetTokenTransfersArgs},
+    transfer::{transfer_token, TransferTokenArgs},
+    raw_transaction::{send_raw_transaction, SendRawTransactionArgs},
+    submissions::{get_submitted_transactions, GetSubmittedTransactionsArgs},
+    token_safety::{check_token_safety, CheckTokenSafetyArgs},
+    contract_verification::{check_contract_verification, CheckContractVerificationArgs},
+    approval_audit::{approval_audit, ApprovalAuditArgs},
+    nft::{get_nft_balance, GetNftBalanceArgs},
+    lp_position::{get_lp_position, GetLpPositionArgs},
+    liquidity::{simulate_add_liquidity, SimulateAddLiquidityArgs},
+    v3_position::{get_v3_position, GetV3PositionArgs},
 };
 use uniswap::UniswapV2Client;
+use uniswap_v3::UniswapV3Client;
+use workers::WorkerManager;
+use zerox::ZeroExClient;
 
 use rmcp::{
-    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
+    handler::server::{router::tool::ToolRouter, tool::ToolCallContext, wrapper::Parameters},
     model::*,
+    service::{Peer, RequestContext},
     ErrorData as McpError,
+    RoleServer,
     ServerHandler,
     ServiceExt,
 };
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Ethereum Trading MCP Server
 /// 提供以太坊交易相关的工具
@@ -38,89 +157,1365 @@ struct EthereumTradingServer {
     erc20_client: Arc<Erc20Client>,
     uniswap_client: Arc<UniswapV2Client>,
     token_registry: Arc<TokenRegistry>,
+    staking_client: Arc<StakingClient>,
+    bundler_client: Arc<BundlerClient>,
+    relay_client: Arc<GelatoRelayClient>,
+    cow_client: Arc<CowClient>,
+    curve_client: Arc<CurveClient>,
+    quote_aggregator: Arc<QuoteAggregator>,
+    /// DEX 聚合器（swap_tokens 请求可执行报价）
+    aggregator_sources: Arc<Vec<Arc<dyn AggregatorSource>>>,
+    store: Arc<Store>,
+    snapshots: Arc<SnapshotStore>,
+    mempool: Arc<MempoolWatcher>,
+    compliance: Arc<ComplianceScreen>,
+    token_lists: Arc<TokenListClient>,
+    alchemy_client: Arc<AlchemyClient>,
+    coingecko_client: Arc<CoinGeckoClient>,
+    subgraph_client: Arc<SubgraphClient>,
+    defillama_client: Arc<DefiLlamaClient>,
+    etherscan_client: Arc<EtherscanClient>,
+    /// Safe 多签模式（配置 SAFE_ADDRESS 时执行类工具输出 Safe 交易）
+    safe_client: Arc<SafeClient>,
+    nft_client: Arc<NftClient>,
+    erc1155_client: Arc<Erc1155Client>,
+    uniswap_v3_client: Arc<UniswapV3Client>,
+    /// 执行类工具的交易提交（nonce 管理和确认跟踪）
+    tx_manager: Arc<TxManager>,
+    /// 执行类工具的签名器（未配置时为只读模式）
+    signer: Option<Arc<TxSigner>>,
+    /// WebSocket Provider（配置 ETHEREUM_WS_URL 时用于订阅）
+    ws_provider: Option<Arc<RpcProvider>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    workers: Arc<WorkerManager>,
     tool_router: ToolRouter<Self>,
 }
 
 #[rmcp::tool_router]
 impl EthereumTradingServer {
-    fn new(config: Config, eth_client: EthClient, provider: Option<Arc<Provider<Http>>>) -> Self {
+    fn new(config: Config, eth_client: EthClient, provider: Option<Arc<RpcProvider>>) -> Self {
         let erc20_client = Erc20Client::new(provider.clone());
-        let uniswap_client = UniswapV2Client::new(provider);
-        let token_registry = TokenRegistry::new();
+        let staking_client =
+            StakingClient::new(provider.clone(), config.ethereum.beacon_api_url.clone());
+        let bundler_client = BundlerClient::new(
+            provider.clone(),
+            config.account_abstraction.bundler_url.clone(),
+            config.account_abstraction.paymaster_url.clone(),
+            config
+                .account_abstraction
+                .entry_point
+                .parse()
+                .expect("AA_ENTRY_POINT 已在配置校验中验证"),
+        );
+        let relay_client = GelatoRelayClient::new(config.api_keys.gelato_api_key.clone());
+        let safe_client = SafeClient::new(
+            provider.clone(),
+            config.safe_address(),
+            config.safe_tx_service_url(),
+            config.api_keys.safe_api_key.clone(),
+            config.ethereum.chain_id,
+        );
+        let nft_client = NftClient::new(provider.clone());
+        let erc1155_client = Erc1155Client::new(provider.clone());
+        let uniswap_v3_client =
+            UniswapV3Client::new(provider.clone(), config.chain().uniswap_v3_position_manager_address());
+        let cow_client = CowClient::new(config.ethereum.chain_id);
+        let token_registry = TokenRegistry::load(config.chain(), config.token_registry_path.as_deref())
+            .expect("代币注册表已在配置校验中验证");
+        // 当前链注册表中不存在的中间代币符号直接忽略
+        let route_intermediates = config
+            .uniswap
+            .route_intermediates
+            .iter()
+            .filter_map(|token| token_registry.resolve(token).ok())
+            .filter_map(|info| info.address.parse().ok())
+            .collect();
+        let curve_client = Arc::new(CurveClient::new(provider.clone(), config.chain()));
+        let uniswap_client = Arc::new(
+            UniswapV2Client::new(provider, config.chain())
+                .with_reserve_cache_ttl(Duration::from_secs(config.performance.price_cache_ttl))
+                .with_route_intermediates(route_intermediates),
+        );
+        let cow_client = Arc::new(cow_client);
+        // DEX_VENUES 中的 V2 兼容场所共享 Provider 和储备量缓存
+        let quote_aggregator = config
+            .v2_venues()
+            .iter()
+            .fold(QuoteAggregator::new(), |aggregator, venue| {
+                let backend: Arc<dyn QuoteBackend> = if venue.id == uniswap_client.venue_id() {
+                    uniswap_client.clone()
+                } else {
+                    Arc::new(uniswap_client.with_venue(venue))
+                };
+                aggregator.with_backend(backend)
+            })
+            .with_backend(cow_client.clone());
+        let quote_aggregator = if config.dex_venue_enabled("curve") {
+            quote_aggregator.with_backend(curve_client.clone())
+        } else {
+            quote_aggregator
+        };
+        let aggregator_sources: Vec<Arc<dyn AggregatorSource>> = vec![Arc::new(ZeroExClient::new(
+            config.api_keys.zerox_api_key.clone(),
+            config.ethereum.chain_id,
+        ))];
+        let quote_aggregator = aggregator_sources.iter().fold(quote_aggregator, |aggregator, source| {
+            aggregator.with_backend(Arc::new(AggregatorBackend(source.clone())))
+        });
+        let compliance = ComplianceScreen::from_config(&config.compliance)
+            .expect("制裁名单已在配置校验中验证");
+        let token_lists = TokenListClient::new(config.token_list_check);
+        let alchemy_client = AlchemyClient::new(config.api_keys.alchemy_api_key.clone(), config.chain());
+        let coingecko_client = CoinGeckoClient::new(config.api_keys.coingecko_api_key.clone(), config.chain());
+        let subgraph_client =
+            SubgraphClient::new(config.uniswap.v2_subgraph_url.clone(), config.uniswap.v3_subgraph_url.clone());
+        let defillama_client = DefiLlamaClient::new(config.chain());
+        let etherscan_client =
+            EtherscanClient::new(config.api_keys.etherscan_api_key.clone(), config.ethereum.chain_id);
+        let eth_client = Arc::new(eth_client);
+        let tx_manager = TxManager::new(eth_client.clone(), config.trading.tx_confirmations);
+
+        // 持久化存储打开失败时降级为禁用，不影响其他工具
+        let store = Store::open(config.database_path.as_deref()).unwrap_or_else(|e| {
+            eprintln!("⚠️  无法打开持久化数据库: {}", e);
+            Store::disabled()
+        });
+
+        // 离线快照加载失败时回退到实时报价
+        let snapshot = config.offline_snapshot_path.as_deref().and_then(|path| {
+            MarketSnapshot::load(path)
+                .inspect(|s| eprintln!("📦 已加载离线快照: 区块 {} ({} 个交易对)", s.block_number, s.pairs.len()))
+                .map_err(|e| eprintln!("⚠️  无法加载离线快照: {}", e))
+                .ok()
+        });
+
+        let rate_limiter = RateLimiter::new(
+            config.performance.rate_limit_per_minute,
+            config.performance.rate_limit_burst,
+        )
+        .map(Arc::new);
+
+        Self {
+            config: Arc::new(config),
+            eth_client,
+            erc20_client: Arc::new(erc20_client),
+            uniswap_client,
+            token_registry: Arc::new(token_registry),
+            staking_client: Arc::new(staking_client),
+            bundler_client: Arc::new(bundler_client),
+            relay_client: Arc::new(relay_client),
+            cow_client,
+            curve_client,
+            quote_aggregator: Arc::new(quote_aggregator),
+            aggregator_sources: Arc::new(aggregator_sources),
+            store: Arc::new(store),
+            snapshots: Arc::new(SnapshotStore::new(snapshot)),
+            mempool: Arc::new(MempoolWatcher::new()),
+            compliance: Arc::new(compliance),
+            token_lists: Arc::new(token_lists),
+            alchemy_client: Arc::new(alchemy_client),
+            coingecko_client: Arc::new(coingecko_client),
+            subgraph_client: Arc::new(subgraph_client),
+            defillama_client: Arc::new(defillama_client),
+            etherscan_client: Arc::new(etherscan_client),
+            safe_client: Arc::new(safe_client),
+            nft_client: Arc::new(nft_client),
+            erc1155_client: Arc::new(erc1155_client),
+            uniswap_v3_client: Arc::new(uniswap_v3_client),
+            tx_manager: Arc::new(tx_manager),
+            signer: None,
+            ws_provider: None,
+            rate_limiter,
+            workers: Arc::new(WorkerManager::new()),
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    /// 获取以太坊地址余额(支持 ETH 和 ERC20)
+    #[rmcp::tool(description = "获取以太坊地址余额(支持 ETH 和 ERC20 代币)")]
+    async fn get_balance(
+        &self,
+        args: Parameters<GetBalanceArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_balance(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.token_registry,
+            &self.token_lists,
+            args,
+        )
+        .await
+    }
+
+    /// 获取代币价格(支持 USD 和 ETH 报价)
+    #[rmcp::tool(description = "获取代币在 Uniswap V2 上的价格(支持 USD 和 ETH 报价)")]
+    async fn get_token_price(
+        &self,
+        args: Parameters<GetTokenPriceArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_token_price(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            &self.store,
+            &self.snapshots,
+            &self.token_lists,
+            &self.coingecko_client,
+            &self.subgraph_client,
+            &self.defillama_client,
+            args,
+        )
+        .await
+    }
+
+    /// 模拟代币交换(Uniswap V2)
+    #[rmcp::tool(description = "模拟 Uniswap V2 代币交换,返回预估输出和价格影响(exact_output=true 时按指定输出数量反推所需输入)")]
+    async fn swap_tokens(
+        &self,
+        args: Parameters<SwapTokensArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        swap_tokens(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            &self.store,
+            &self.snapshots,
+            &self.mempool,
+            &self.compliance,
+            &self.token_lists,
+            &self.curve_client,
+            &self.aggregator_sources,
+            &self.etherscan_client,
+            args,
+        )
+        .await
+    }
+
+    /// 查询交易对储备量历史
+    #[rmcp::tool(description = "按区块区间采样 Uniswap V2 交易对的储备量和价格历史(需要归档节点)")]
+    async fn get_reserve_history(
+        &self,
+        args: Parameters<GetReserveHistoryArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_reserve_history(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            args,
+        )
+        .await
+    }
+
+    /// 获取链上时间及时钟偏差
+    #[rmcp::tool(description = "获取最新区块时间戳、与服务器时钟的偏差以及平均出块时间")]
+    async fn get_chain_time(
+        &self,
+        args: Parameters<GetChainTimeArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_chain_time(
+            &self.config,
+            &self.eth_client,
+            args,
+        )
+        .await
+    }
+
+    /// 获取账户/存储槽 Merkle 证明
+    #[rmcp::tool(description = "通过 eth_getProof 获取账户及存储槽的 Merkle 证明")]
+    async fn get_proof(
+        &self,
+        args: Parameters<GetProofArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_proof(
+            &self.config,
+            &self.eth_client,
+            args,
+        )
+        .await
+    }
+
+    /// 获取 ETH 质押年化收益
+    #[rmcp::tool(description = "获取 ETH 质押年化收益(Lido stETH oracle 报告 APR,配置 Beacon API 时附带共识层估算)")]
+    async fn get_staking_apr(
+        &self,
+        args: Parameters<GetStakingAprArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_staking_apr(
+            &self.config,
+            &self.staking_client,
+            args,
+        )
+        .await
+    }
+
+    /// 汇总多个钱包的余额
+    #[rmcp::tool(description = "汇总多个钱包地址的 ETH 或 ERC20 余额(Multicall 批量查询)")]
+    async fn get_aggregate_balance(
+        &self,
+        args: Parameters<GetAggregateBalanceArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_aggregate_balance(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 查询持久化的报价/模拟/执行记录
+    #[rmcp::tool(description = "查询本地持久化的报价、模拟和执行记录(需配置 DATABASE_PATH)")]
+    fn get_recorded_history(
+        &self,
+        args: Parameters<GetRecordedHistoryArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_recorded_history(
+            &self.store,
+            &self.token_registry,
+            args,
+        )
+    }
+
+    /// 查询持久化的审计日志
+    #[rmcp::tool(description = "查询本地持久化的审计日志(制裁名单筛查结论等,最新在前,需配置 DATABASE_PATH);可按工具名过滤")]
+    fn get_audit_log(
+        &self,
+        args: Parameters<GetAuditLogArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_audit_log(&self.store, args)
+    }
+
+    /// 计算钱包盈亏(已实现/未实现)
+    #[rmcp::tool(description = "基于转账历史和历史价格计算钱包各代币的已实现和未实现盈亏(平均成本法)")]
+    async fn get_pnl(
+        &self,
+        args: Parameters<GetPnlArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_pnl(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            &self.store,
+            args,
+        )
+        .await
+    }
+
+    /// 查询持仓平均成本
+    #[rmcp::tool(description = "查询钱包各代币的平均买入成本、手续费和已实现盈亏(来自本地成本台账,需配置 DATABASE_PATH)")]
+    fn get_cost_basis(
+        &self,
+        args: Parameters<GetCostBasisArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_cost_basis(
+            &self.store,
+            &self.token_registry,
+            args,
+        )
+    }
+
+    /// 生成年度税务报告(CSV)
+    #[rmcp::tool(description = "生成钱包年度税务报告:将转账和交换分类为买入/卖出,按交易时的 USD 价值导出 CSV(generic/Koinly)")]
+    async fn generate_tax_report(
+        &self,
+        args: Parameters<GenerateTaxReportArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        generate_tax_report(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            &self.store,
+            args,
+        )
+        .await
+    }
+
+    /// 按风险和池子深度建议仓位
+    #[rmcp::tool(description = "结合实时价格、池子深度和风险参数(组合价值、风险比例、止损价)建议买入仓位,使价格影响和止损亏损都在限制内")]
+    async fn suggest_position_size(
+        &self,
+        args: Parameters<SuggestPositionSizeArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        suggest_position_size(
+            &self.config,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 比较两个区块的报价漂移
+    #[rmcp::tool(description = "在两个区块高度(或当前与 N 个区块前)重新报价同一笔 Uniswap V2 交易,报告输出变化以评估报价的时效性(历史区块需要归档节点)")]
+    async fn compare_quote_drift(
+        &self,
+        args: Parameters<CompareQuoteDriftArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        compare_quote_drift(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 查询新建交易对
+    #[rmcp::tool(description = "查询最近在 Uniswap V2 Factory 新建的交易对(PairCreated 事件),附带初始和当前流动性")]
+    async fn get_new_pairs(
+        &self,
+        args: Parameters<GetNewPairsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_new_pairs(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 查询热门代币
+    #[rmcp::tool(description = "结合新建交易对、成交量增长和独立买家数(来自 Uniswap V2 Swap 事件)列出近期最活跃的代币,并做基础安全筛查")]
+    async fn get_trending_tokens(
+        &self,
+        args: Parameters<GetTrendingTokensArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_trending_tokens(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 估算任意交易的 Gas 和费用
+    #[rmcp::tool(description = "对任意交易调用 eth_estimateGas 估算 Gas,并按配置的 Gas 价格策略计算 EIP-1559 费用")]
+    async fn estimate_gas(
+        &self,
+        args: Parameters<EstimateGasArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        estimate_gas(
+            &self.config,
+            &self.eth_client,
+            args,
+        )
+        .await
+    }
+
+    /// 批量只读查询
+    #[rmcp::tool(description = "在一次调用中并发执行多个只读子请求(余额、价格、授权额度)")]
+    async fn batch_query(
+        &self,
+        args: Parameters<BatchQueryArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        batch_query(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.uniswap_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 列出后台任务
+    #[rmcp::tool(description = "列出正在运行的后台任务及其健康状态(状态、重启次数、最近错误)")]
+    fn list_workers(
+        &self,
+        args: Parameters<ListWorkersArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        list_workers(
+            &self.workers,
+            args,
+        )
+    }
+
+    /// 导出市场快照
+    #[rmcp::tool(description = "导出指定区块的 Uniswap V2 交易对储备量、价格和代币元数据快照,可用于可复现分析和离线报价")]
+    async fn export_market_snapshot(
+        &self,
+        args: Parameters<ExportMarketSnapshotArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        export_market_snapshot(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 导入市场快照
+    #[rmcp::tool(description = "导入市场快照文件,之后 get_token_price 和 swap_tokens 基于快照储备量离线计算")]
+    fn import_market_snapshot(
+        &self,
+        args: Parameters<ImportMarketSnapshotArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        import_market_snapshot(
+            &self.config,
+            &self.snapshots,
+            args,
+        )
+    }
+
+    /// 预览交易余额变化
+    #[rmcp::tool(description = "通过 debug_traceCall 跟踪预备交易,列出所有涉及地址的 ETH 和代币余额变化(带符号的变化表),用于确认前核对")]
+    async fn preview_transaction(
+        &self,
+        args: Parameters<PreviewTransactionArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        preview_transaction(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 模拟交易序列
+    #[rmcp::tool(description = "按顺序模拟一组交易(如授权、交换、添加流动性),每笔交易都在前序交易的状态变化之上执行,返回每笔交易的执行结果和累计余额变化")]
+    async fn simulate_transactions(
+        &self,
+        args: Parameters<SimulateTransactionsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        simulate_transactions(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 通过智能账户执行转账、授权或交换
+    #[rmcp::tool(description = "通过 ERC-4337 智能账户构建转账、ERC20 授权或 Uniswap V2 交换的 UserOperation,经 Bundler 估算 Gas(可选 Paymaster 赞助)并签名,submit 为 true 时提交到 Bundler;可传入 smart_account 和 entry_point,unsigned 为 true 或未配置签名器时返回未签名的 UserOperation 和 userOpHash 供外部签名")]
+    async fn send_user_operation(
+        &self,
+        args: Parameters<SendUserOperationArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        send_user_operation(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            &self.bundler_client,
+            &self.store,
+            &self.compliance,
+            self.signer.as_deref(),
+            args,
+        )
+        .await
+    }
+
+    /// 通过 Gelato Relay 提交免 Gas 交易
+    #[rmcp::tool(description = "通过 Gelato Relay 免 Gas 提交合约调用:sponsored(1Balance 赞助)、sync_fee(目标合约支付手续费)或 erc2771(用户签名,目标合约识别原始用户),返回中继任务 ID")]
+    async fn relay_transaction(
+        &self,
+        args: Parameters<RelayTransactionArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        relay_transaction(
+            &self.config,
+            &self.eth_client,
+            &self.token_registry,
+            &self.relay_client,
+            &self.store,
+            &self.compliance,
+            self.signer.as_deref(),
+            args,
+        )
+        .await
+    }
+
+    /// 查询中继任务状态
+    #[rmcp::tool(description = "查询 Gelato Relay 中继任务状态,返回任务状态和上链交易哈希")]
+    async fn get_relay_task_status(
+        &self,
+        args: Parameters<GetRelayTaskStatusArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_relay_task_status(
+            &self.config,
+            &self.relay_client,
+            args,
+        )
+        .await
+    }
+
+    /// 签名 EIP-3009 转账授权
+    #[rmcp::tool(description = "为支持 EIP-3009 的代币(如 USDC)签名 transferWithAuthorization 授权,返回签名载荷和 calldata,持有人无需支付 Gas;可选通过 Gelato Relay 直接提交")]
+    async fn sign_transfer_authorization(
+        &self,
+        args: Parameters<SignTransferAuthorizationArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        sign_transfer_authorization(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.token_registry,
+            &self.relay_client,
+            &self.store,
+            &self.compliance,
+            self.signer.as_deref(),
+            args,
+        )
+        .await
+    }
+
+    /// 通过 CoW Protocol 下单
+    #[rmcp::tool(description = "通过 CoW Protocol 获取报价并签名 EIP-712 订单(防 MEV、无需 Gas),submit 为 true 时提交到订单簿,返回订单 UID")]
+    async fn place_cow_order(
+        &self,
+        args: Parameters<PlaceCowOrderArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        place_cow_order(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.token_registry,
+            &self.cow_client,
+            &self.store,
+            &self.compliance,
+            self.signer.as_deref(),
+            args,
+        )
+        .await
+    }
+
+    /// 查询 CoW 订单状态
+    #[rmcp::tool(description = "查询 CoW Protocol 订单状态(open/fulfilled/cancelled/expired)和已成交数量")]
+    async fn get_order_status(
+        &self,
+        args: Parameters<GetOrderStatusArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_order_status(
+            &self.config,
+            &self.cow_client,
+            args,
+        )
+        .await
+    }
+
+    /// 比较各场所报价
+    #[rmcp::tool(description = "对同一笔卖出交易向所有报价后端(Uniswap V2、SushiSwap、Curve 等链上 AMM 路由,以及 CoW、0x 等链下确定报价)请求报价,按统一口径比较买入数量并标出最优场所")]
+    async fn compare_quotes(
+        &self,
+        args: Parameters<CompareQuotesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        compare_quotes(
+            &self.config,
+            &self.erc20_client,
+            &self.token_registry,
+            &self.quote_aggregator,
+            args,
+        )
+        .await
+    }
+
+    /// 签名并广播代币交换
+    #[rmcp::tool(description = "使用配置的签名器签名并广播 Uniswap V2 交换交易,返回交易哈希和回执状态(只读模拟请使用 swap_tokens);配置 SAFE_ADDRESS 时改为从 Safe 多签构建交易,签名器签名后提议到 Safe 交易服务,不直接广播")]
+    async fn execute_swap(
+        &self,
+        args: Parameters<ExecuteSwapArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        execute_swap(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            &self.store,
+            &self.compliance,
+            &self.tx_manager,
+            &self.etherscan_client,
+            &self.safe_client,
+            self.signer.as_deref(),
+            args,
+        )
+        .await
+    }
+
+    /// 查询 ERC20 授权额度
+    #[rmcp::tool(description = "查询 ERC20 代币授权额度,spender 默认为 Uniswap V2 Router,返回原始值和格式化值")]
+    async fn get_allowance(
+        &self,
+        args: Parameters<GetAllowanceArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_allowance(
+            &self.config,
+            &self.erc20_client,
+            &self.uniswap_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 构建、模拟并可选广播 ERC20 授权交易
+    #[rmcp::tool(description = "构建 ERC20 approve 交易并模拟、估算 Gas(spender 默认 Uniswap V2 Router);confirm 为 true 时使用配置的签名器签名并广播;配置 SAFE_ADDRESS 时以 Safe 为 owner,返回 Safe 交易数据,confirm 时提议到 Safe 交易服务")]
+    async fn approve_token(
+        &self,
+        args: Parameters<ApproveTokenArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        approve_token(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.uniswap_client,
+            &self.token_registry,
+            &self.store,
+            &self.compliance,
+            &self.tx_manager,
+            &self.safe_client,
+            self.signer.as_deref(),
+            args,
+        )
+        .await
+    }
 
-        Self {
-            config: Arc::new(config),
-            eth_client: Arc::new(eth_client),
-            erc20_client: Arc::new(erc20_client),
-            uniswap_client: Arc::new(uniswap_client),
-            token_registry: Arc::new(token_registry),
-            tool_router: Self::tool_router(),
-        }
+    /// 查询钱包的全部代币持仓
+    #[rmcp::tool(description = "列出钱包的全部非零代币余额(含符号、精度、格式化数量),配置 ALCHEMY_API_KEY 时自动发现所有 ERC20,可选计算 USD 价值")]
+    async fn get_portfolio(
+        &self,
+        args: Parameters<GetPortfolioArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_portfolio(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.uniswap_client,
+            &self.token_registry,
+            &self.alchemy_client,
+            args,
+        )
+        .await
     }
 
-    /// 获取以太坊地址余额(支持 ETH 和 ERC20)
-    #[rmcp::tool(description = "获取以太坊地址余额(支持 ETH 和 ERC20 代币)")]
-    fn get_balance(
+    /// 查询当前 Gas 价格
+    #[rmcp::tool(description = "查询当前 gasPrice、EIP-1559 基础费用和 slow/standard/fast 建议小费,以及转账和交换的预计费用(ETH/USD)")]
+    async fn get_gas_price(
         &self,
-        args: Parameters<GetBalanceArgs>,
+        args: Parameters<GetGasPriceArgs>,
     ) -> Result<CallToolResult, McpError> {
-        get_balance(
+        get_gas_price(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            args,
+        )
+        .await
+    }
+
+    /// 以别名注册代币
+    #[rmcp::tool(description = "按合约地址读取链上 symbol、name、decimals,以指定别名(默认链上 symbol)注册到代币注册表,之后可按别名使用;别名已指向其他地址时需 overwrite: true")]
+    async fn register_token(
+        &self,
+        args: Parameters<RegisterTokenArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        register_token(
+            &self.config,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 从代币注册表移除代币
+    #[rmcp::tool(description = "按符号或别名从代币注册表移除代币(仅当前运行期间生效,原生代币和包装原生代币不能移除)")]
+    async fn remove_token(
+        &self,
+        args: Parameters<RemoveTokenArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        remove_token(
+            &self.config,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 查询代币元数据和合约信息
+    #[rmcp::tool(description = "查询代币的 symbol、name、decimals、totalSupply、是否为合约、合约创建区块和时间(需要归档节点),以及是否在代币注册表和主流代币列表中,用于在报价前审查陌生代币")]
+    async fn get_token_metadata(
+        &self,
+        args: Parameters<GetTokenMetadataArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_token_metadata(
             &self.config,
             &self.eth_client,
             &self.erc20_client,
             &self.token_registry,
+            &self.token_lists,
             args,
         )
+        .await
     }
 
-    /// 获取代币价格(支持 USD 和 ETH 报价)
-    #[rmcp::tool(description = "获取代币在 Uniswap V2 上的价格(支持 USD 和 ETH 报价)")]
-    fn get_token_price(
+    /// 查询 TWAP 价格
+    #[rmcp::tool(description = "基于 Uniswap V2 的 price0CumulativeLast/price1CumulativeLast 计算窗口内的时间加权平均价格(TWAP),同时返回现货价格和偏离百分比,用于识别被操纵的池子(需要归档节点)")]
+    async fn get_twap_price(
         &self,
-        args: Parameters<GetTokenPriceArgs>,
+        args: Parameters<GetTwapPriceArgs>,
     ) -> Result<CallToolResult, McpError> {
-        get_token_price(
+        get_twap_price(
             &self.config,
+            &self.eth_client,
             &self.uniswap_client,
             &self.erc20_client,
             &self.token_registry,
             args,
         )
+        .await
     }
 
-    /// 模拟代币交换(Uniswap V2)
-    #[rmcp::tool(description = "模拟 Uniswap V2 代币交换,返回预估输出和价格影响")]
-    fn swap_tokens(
+    /// 查询交易状态
+    #[rmcp::tool(description = "按交易哈希查询交易状态(pending/success/reverted)、所在区块、确认数、Gas 用量、实际单位 Gas 费用和交易费用,并识别常见函数(ERC20 transfer/approve、Uniswap V2 Router 交换)")]
+    async fn get_transaction(
         &self,
-        args: Parameters<SwapTokensArgs>,
+        args: Parameters<GetTransactionArgs>,
     ) -> Result<CallToolResult, McpError> {
-        swap_tokens(
+        get_transaction(&self.config, &self.eth_client, args).await
+    }
+
+    /// 查询账户信息
+    #[rmcp::tool(description = "一次返回地址的 nonce、pending nonce、已确认和待打包的交易数、是否为合约(eth_getCode)以及原生代币余额,用于构造交易前的检查")]
+    async fn get_account_info(
+        &self,
+        args: Parameters<GetAccountInfoArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_account_info(&self.config, &self.eth_client, args).await
+    }
+
+    /// 解码调用数据
+    #[rmcp::tool(description = "解码调用数据的函数选择器和参数:先匹配内置 ABI(ERC20、Uniswap V2 Router/Factory/Pair),无法识别且传入 contract_address 时从 Etherscan 获取已验证合约的 ABI(需要 ETHERSCAN_API_KEY),用于向用户解释待签名或 pending 交易")]
+    async fn decode_calldata(
+        &self,
+        args: Parameters<DecodeCalldataArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        decode_calldata(&self.config, &self.etherscan_client, args).await
+    }
+
+    /// 模拟任意交易
+    #[rmcp::tool(description = "以 eth_call 在最新或指定区块上模拟任意交易(to、from、value、data),成功时返回原始返回数据、按已知 ABI 解码的返回值和 Gas 估算,失败时返回 revert 原因和原始 revert 数据,不会广播交易")]
+    async fn simulate_transaction(
+        &self,
+        args: Parameters<SimulateTransactionArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        simulate_transaction(&self.config, &self.eth_client, args).await
+    }
+
+    /// 查询事件日志
+    #[rmcp::tool(description = "查询事件日志(eth_getLogs):按合约地址或代币符号、常见事件名称(Transfer/Approval/Swap/Sync/Mint/Burn)或 topic0 以及 topic1-3 过滤,区间最多 10000 个区块,按已知 ABI 解码为参数列表,ERC20 数量按代币精度格式化")]
+    async fn get_logs(
+        &self,
+        args: Parameters<GetLogsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_logs(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 查询钱包代币转账记录
+    #[rmcp::tool(description = "查询钱包最近的 ERC20 转入/转出记录:可指定代币地址或符号,不指定时查询代币注册表中的所有代币;默认回溯 50000 个区块,按 10000 个区块分段调用 eth_getLogs,区间超过 200000 个区块或节点查询失败时使用 Etherscan(需要 ETHERSCAN_API_KEY);返回方向、对手方和格式化数量,按区块倒序")]
+    async fn get_token_transfers(
+        &self,
+        args: Parameters<GetTokenTransfersArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_token_transfers(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.etherscan_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 构建、模拟并可选广播 ERC20 或 ETH 转账
+    #[rmcp::tool(description = "构建 ERC20 transfer 或原生代币(ETH)转账交易:检查发送方代币余额和 Gas 所需 ETH,以 eth_call 模拟并估算 Gas,返回未签名交易字段(nonce、Gas 上限、费用);confirm 为 true 时使用配置的签名器签名并广播,模拟失败或余额不足时拒绝广播;配置 SAFE_ADDRESS 时从 Safe 转出,返回 Safe 交易数据,confirm 时提议到 Safe 交易服务")]
+    async fn transfer_token(
+        &self,
+        args: Parameters<TransferTokenArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        transfer_token(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.uniswap_client,
+            &self.token_registry,
+            &self.store,
+            &self.compliance,
+            &self.tx_manager,
+            &self.safe_client,
+            self.signer.as_deref(),
+            args,
+        )
+        .await
+    }
+
+    /// 广播外部签名的原始交易并等待确认
+    #[rmcp::tool(description = "广播外部签名(硬件钱包、MPC 等)的原始交易(eth_sendRawTransaction):先解码交易并从签名恢复发送方、校验 chain_id,然后轮询回执直到达到 confirmations 个确认(默认 1)或超时;请求带 progressToken 时推送进度通知,返回各阶段进度和最终状态")]
+    async fn send_raw_transaction(
+        &self,
+        args: Parameters<SendRawTransactionArgs>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let progress_reporter = context
+            .meta
+            .get_progress_token()
+            .map(|token| (context.peer.clone(), token));
+        send_raw_transaction(
+            &self.config,
+            &self.eth_client,
+            &self.store,
+            &self.compliance,
+            &self.tx_manager,
+            progress_reporter,
+            args,
+        )
+        .await
+    }
+
+    /// 查询执行类工具提交的交易状态
+    #[rmcp::tool(description = "列出本次运行中 execute_swap、approve_token、transfer_token、send_raw_transaction 提交的交易(最新在前):nonce、广播尝试次数、状态(pending/success/reverted)和确认数,待确认的交易会先刷新回执;指定 wallet 时返回本地记录的下一个 nonce")]
+    async fn get_submitted_transactions(
+        &self,
+        args: Parameters<GetSubmittedTransactionsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_submitted_transactions(&self.config, &self.tx_manager, args).await
+    }
+
+    /// 检查代币是否为蜜罐
+    #[rmcp::tool(description = "交易陌生代币前检查是否为蜜罐:在状态覆盖的 eth_call 中通过 Uniswap V2 Router 用少量 ETH 试买代币并立即卖出,返回买入税和卖出税(实际到账相对按储备量应得数量的损耗)、卖出回滚原因、风险标记(sell_reverted、absurd_tax、high_buy_tax、high_sell_tax、buy_reverted)和 risk_level")]
+    async fn check_token_safety(
+        &self,
+        args: Parameters<CheckTokenSafetyArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        check_token_safety(
+            &self.config,
+            &self.eth_client,
+            &self.uniswap_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 查询合约源码验证状态
+    #[rmcp::tool(description = "查询代币、交易对或任意合约是否在 Etherscan 验证源码(需要 ETHERSCAN_API_KEY),返回合约名称、编译器版本、优化设置、许可证和代理状态(Etherscan 标记或 EIP-1967 实现槽)及实现合约的验证状态;safe_to_quote 为 false 时除非用户明确确认,不应交换为该代币")]
+    async fn check_contract_verification(
+        &self,
+        args: Parameters<CheckContractVerificationArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        check_contract_verification(
+            &self.config,
+            &self.eth_client,
+            &self.etherscan_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 审计钱包的 ERC20 授权
+    #[rmcp::tool(description = "扫描钱包在代币注册表(或指定代币列表)上的 Approval 事件(默认最近 200000 个区块),并始终检查对已知 DEX Router、Permit2 和 CoW Protocol 的授权,列出当前仍有效的授权额度(按代币精度格式化),标记无限授权、未知被授权方和普通地址被授权方等风险")]
+    async fn approval_audit(
+        &self,
+        args: Parameters<ApprovalAuditArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        approval_audit(
+            &self.config,
+            &self.eth_client,
+            &self.erc20_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 查询钱包持有的 ERC-721 / ERC-1155 NFT
+    #[rmcp::tool(description = "查询钱包在 NFT 合集中的持仓,并返回合集名称和符号。ERC-721 合集返回持有数量(balanceOf),支持 ERC-721 Enumerable 时通过 tokenOfOwnerByIndex 列出持有的 token ID(默认最多 100 个,可用 max_token_ids 调整);ERC-1155 合集(游戏道具、RWA 等)需要通过 token_ids 指定 token ID,使用 balanceOfBatch 批量返回每个 token ID 的持有数量")]
+    async fn get_nft_balance(
+        &self,
+        args: Parameters<GetNftBalanceArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_nft_balance(&self.config, &self.nft_client, &self.erc1155_client, args).await
+    }
+
+    /// 估值 Uniswap V2 LP 头寸
+    #[rmcp::tool(description = "计算钱包持有的 Uniswap V2 LP 头寸:通过交易对地址(即 LP 代币地址)或两侧代币定位交易对,按 LP 余额占总供应量的比例给出池子份额、按当前储备量可赎回的两侧代币数量,以及两侧和合计的 USD 价值")]
+    async fn get_lp_position(
+        &self,
+        args: Parameters<GetLpPositionArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_lp_position(
             &self.config,
+            &self.erc20_client,
             &self.uniswap_client,
+            &self.token_registry,
+            args,
+        )
+        .await
+    }
+
+    /// 模拟添加 Uniswap V2 流动性
+    #[rmcp::tool(description = "模拟 Uniswap V2 Router.addLiquidity(原生代币一侧为 addLiquidityETH):按当前储备量计算与 amount_a 配对的 token_b 数量(或在两侧期望数量中取最优组合),预计铸造的 LP 代币数量和存入后的池子份额,以 eth_call 模拟 Router 调用并估算 Gas,同时检查两侧代币对 Router 的授权。只模拟,不发送交易")]
+    async fn simulate_add_liquidity(
+        &self,
+        args: Parameters<SimulateAddLiquidityArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        simulate_add_liquidity(
+            &self.config,
+            &self.eth_client,
             &self.erc20_client,
+            &self.uniswap_client,
             &self.token_registry,
             args,
         )
+        .await
+    }
+
+    /// 查询 Uniswap V3 头寸 NFT
+    #[rmcp::tool(description = "读取 Uniswap V3 头寸 NFT(NonfungiblePositionManager):按 token_id 查询单个头寸,或按 wallet 列出钱包持有的头寸。返回价格区间(tick 和 token1/token0 价格)、当前价格和是否在区间内、按当前价格可赎回的两侧代币数量,以及未领取的手续费")]
+    async fn get_v3_position(
+        &self,
+        args: Parameters<GetV3PositionArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        get_v3_position(
+            &self.config,
+            &self.erc20_client,
+            &self.uniswap_v3_client,
+            &self.subgraph_client,
+            &self.defillama_client,
+            args,
+        )
+        .await
+    }
+}
+
+impl EthereumTradingServer {
+    /// 设置用于订阅新区块的 WebSocket Provider
+    fn with_ws_provider(mut self, ws_provider: Option<Arc<RpcProvider>>) -> Self {
+        self.ws_provider = ws_provider;
+        self
+    }
+
+    fn with_signer(mut self, signer: Option<TxSigner>) -> Self {
+        self.signer = signer.map(Arc::new);
+        self
+    }
+
+    /// 启动后台任务
+    /// 新交易对通知需要推送到客户端，只在 stdio 传输（单一客户端 `peer`）下启用
+    fn start_workers(&self, peer: Option<Peer<RoleServer>>) {
+        if self.config.server.test_mode {
+            return;
+        }
+
+        // 远程代币列表只需 HTTP，离线快照模式下同样刷新
+        if !self.config.token_list_urls.is_empty() {
+            token_lists::spawn_token_list_refresher(
+                &self.workers,
+                self.token_lists.clone(),
+                self.token_registry.clone(),
+                self.config.token_list_urls.clone(),
+                self.config.ethereum.chain_id,
+                Duration::from_secs(self.config.token_list_refresh_secs),
+            );
+        }
+
+        if !self.uniswap_client.is_available() {
+            return;
+        }
+
+        if let Some(ref ws_provider) = self.ws_provider {
+            let uniswap_client = self.uniswap_client.clone();
+            eth_client::spawn_block_subscription(&self.workers, ws_provider.clone(), move |_| {
+                uniswap_client.on_new_block()
+            });
+        }
+
+        if let (true, Some(peer)) = (self.config.uniswap.new_pair_notifications, peer) {
+            tools::new_pairs::spawn_new_pair_notifier(
+                &self.workers,
+                self.eth_client.clone(),
+                self.uniswap_client.clone(),
+                self.erc20_client.clone(),
+                self.token_registry.clone(),
+                peer,
+            );
+        }
+
+        if self.config.trading.mempool_watch {
+            mempool::spawn_mempool_watcher(
+                &self.workers,
+                self.eth_client.clone(),
+                self.uniswap_client.router_address(),
+                self.mempool.clone(),
+            );
+        }
+    }
+
+    /// 限流检查后分发到对应的工具
+    async fn dispatch_tool_call(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // 按客户端限流
+        if let Some(ref limiter) = self.rate_limiter {
+            let client = client_key(&context);
+            if let Err(wait) = limiter.check(&client) {
+                let retry_after_secs = wait.as_secs_f64().ceil() as u64;
+                warn!(client = %client, retry_after_secs, "请求过于频繁");
+                return Err(McpError::invalid_request(
+                    format!("请求过于频繁,请在 {} 秒后重试", retry_after_secs),
+                    Some(serde_json::json!({ "retry_after_secs": retry_after_secs })),
+                ));
+            }
+        }
+
+        let tcc = ToolCallContext::new(self, request, context);
+        self.tool_router.call(tcc).await
+    }
+}
+
+/// 在错误消息和 data 中附加请求 ID
+fn attach_request_id(mut error: McpError, request_id: &str) -> McpError {
+    error.message = format!("{} (request_id: {})", error.message, request_id).into();
+    error.data = Some(match error.data.take() {
+        Some(serde_json::Value::Object(mut data)) => {
+            data.insert("request_id".to_string(), request_id.into());
+            serde_json::Value::Object(data)
+        }
+        Some(details) => serde_json::json!({ "request_id": request_id, "details": details }),
+        None => serde_json::json!({ "request_id": request_id }),
+    });
+    error
+}
+
+/// 取出参数中的 `debug` 选项(所有工具通用,不传给工具本身)
+fn take_debug_flag(request: &mut CallToolRequestParam) -> bool {
+    request
+        .arguments
+        .as_mut()
+        .and_then(|args| args.remove("debug"))
+        .is_some_and(|value| value.as_bool() == Some(true))
+}
+
+/// 在响应末尾附加耗时明细;调用失败时附加到错误的 data 中
+fn attach_timing(
+    result: Result<CallToolResult, McpError>,
+    timing: &diagnostics::TimingBreakdown,
+) -> Result<CallToolResult, McpError> {
+    let timing_json = serde_json::json!({ "timing": timing });
+    match result {
+        Ok(mut result) => {
+            let text = serde_json::to_string_pretty(&timing_json)
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            result.content.push(Content::text(text));
+            Ok(result)
+        }
+        Err(mut error) => {
+            error.data = Some(match error.data.take() {
+                Some(serde_json::Value::Object(mut data)) => {
+                    data.insert("timing".to_string(), timing_json["timing"].clone());
+                    serde_json::Value::Object(data)
+                }
+                Some(details) => serde_json::json!({ "details": details, "timing": timing }),
+                None => timing_json,
+            });
+            Err(error)
+        }
     }
 }
 
-#[rmcp::tool_handler]
+/// 限流使用的客户端标识:HTTP 传输为服务器观测到的对端 IP(客户端上报的名称可随意更换,不可信),
+/// stdio 传输只有一个客户端
+fn client_key(context: &RequestContext<RoleServer>) -> String {
+    context
+        .extensions
+        .get::<axum::http::request::Parts>()
+        .and_then(|parts| parts.extensions.get::<axum::extract::ConnectInfo<SocketAddr>>())
+        .map(|axum::extract::ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_else(|| "stdio".to_string())
+}
+
 impl ServerHandler for EthereumTradingServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder()
+                .enable_logging()
+                .enable_completions()
+                .enable_tools()
+                .build(),
             server_info: Implementation::from_build_env(),
             instructions: Some(
                 "以太坊交易 MCP 服务器 - 提供余额查询、价格查询和交换模拟功能。\n\
                  可用工具:\n\
                  - get_balance: 获取以太坊地址余额(支持 ETH 和 ERC20)\n\
                  - get_token_price: 获取代币在 Uniswap V2 上的价格(支持 USD 和 ETH 报价)\n\
-                 - swap_tokens: 模拟 Uniswap V2 代币交换(返回预估输出和价格影响)"
+                 - swap_tokens: 模拟 Uniswap V2 代币交换(返回预估输出和价格影响)\n\
+                 - get_reserve_history: 查询交易对储备量历史\n\
+                 - get_chain_time: 获取链上时间及时钟偏差\n\
+                 - get_proof: 获取账户/存储槽 Merkle 证明\n\
+                 - get_staking_apr: 获取 ETH 质押年化收益\n\
+                 - get_aggregate_balance: 汇总多个钱包的余额\n\
+                 - get_recorded_history: 查询持久化的报价/模拟/执行记录\n\
+                 - get_audit_log: 查询持久化的审计日志\n\
+                 - get_pnl: 计算钱包盈亏(已实现/未实现)\n\
+                 - get_cost_basis: 查询持仓平均成本\n\
+                 - generate_tax_report: 生成年度税务报告(CSV)\n\
+                 - suggest_position_size: 按风险和池子深度建议仓位\n\
+                 - compare_quote_drift: 比较两个区块的报价漂移\n\
+                 - get_new_pairs: 查询新建交易对\n\
+                 - get_trending_tokens: 查询热门代币\n\
+                 - estimate_gas: 估算任意交易的 Gas 和费用\n\
+                 - batch_query: 批量只读查询\n\
+                 - list_workers: 列出后台任务\n\
+                 - export_market_snapshot: 导出市场快照\n\
+                 - import_market_snapshot: 导入市场快照\n\
+                 - preview_transaction: 预览交易余额变化\n\
+                 - simulate_transactions: 模拟交易序列\n\
+                 - send_user_operation: 通过智能账户执行转账、授权或交换(可返回未签名的 UserOperation)\n\
+                 - relay_transaction: 通过 Gelato Relay 提交免 Gas 交易\n\
+                 - get_relay_task_status: 查询中继任务状态\n\
+                 - sign_transfer_authorization: 签名 EIP-3009 转账授权\n\
+                 - place_cow_order: 通过 CoW Protocol 下单\n\
+                 - get_order_status: 查询 CoW 订单状态\n\
+                 - compare_quotes: 比较各场所报价\n\
+                 - execute_swap: 签名并广播 Uniswap V2 交换(真实交易)\n\
+                 - get_allowance: 查询 ERC20 授权额度(默认 Router)\n\
+                 - approve_token: 构建并模拟 ERC20 授权,confirm 时签名广播\n\
+                 - get_portfolio: 列出钱包全部代币持仓\n\
+                 - get_gas_price: 查询 Gas 价格和预计费用\n\
+                 - register_token: 按地址读取链上元数据并以别名注册代币\n\
+                 - remove_token: 从代币注册表移除代币\n\
+                 - get_token_metadata: 查询代币元数据、总供应量和合约创建信息\n\
+                 - get_twap_price: 查询 Uniswap V2 时间加权平均价格并与现货价格对比\n\
+                 - get_transaction: 按哈希查询交易状态、回执和确认数\n\
+                 - get_account_info: 查询地址的 nonce、交易数、是否为合约和余额\n\
+                 - decode_calldata: 解码调用数据的函数和参数\n\
+                 - simulate_transaction: 以 eth_call 模拟任意交易并返回返回值或 revert 原因\n\
+                 - get_logs: 查询并解码事件日志\n\
+                 - get_token_transfers: 查询钱包的 ERC20 转账记录\n\
+                 - transfer_token: 构建并模拟 ERC20 或 ETH 转账,confirm 时签名广播\n\
+                 - send_raw_transaction: 广播外部签名的交易并等待确认\n\
+                 - get_submitted_transactions: 查询已提交交易的 nonce 和确认状态\n\
+                 - check_token_safety: 试买并卖出代币,检测蜜罐和买卖税\n\
+                 - check_contract_verification: 查询合约是否在 Etherscan 验证源码及代理状态\n\
+                 - approval_audit: 扫描钱包当前有效的 ERC20 授权并标记风险\n\
+                 - get_nft_balance: 查询钱包持有的 ERC-721 NFT 数量和 token ID,或 ERC-1155 指定 token ID 的余额\n\
+                 - get_lp_position: 计算 Uniswap V2 LP 头寸的池子份额、可赎回代币数量和 USD 价值\n\
+                 - simulate_add_liquidity: 模拟 Uniswap V2 添加流动性(配对数量、铸造 LP、份额和 Gas)\n\
+                 - get_v3_position: 查询 Uniswap V3 头寸 NFT(区间、可赎回数量、未领取手续费)\n\
+                 目标代币合约未验证源码时 swap_tokens 和 execute_swap 会拒绝报价,只有用户明确确认后才能传入 allow_unverified: true。\n\
+                 配置 SAFE_ADDRESS 时 execute_swap、approve_token、transfer_token 以 Safe 多签为资金账户,返回 Safe 交易数据(safe 字段)并提议到 Safe 交易服务,需要 Safe 所有者确认后执行。\n\
+                 所有工具都支持 debug: true 参数,在响应末尾附加 RPC 调用耗时明细"
                     .to_string(),
             ),
         }
     }
+
+    async fn call_tool(
+        &self,
+        mut request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // 每次调用生成请求 ID,附加到日志 span 和错误响应
+        let request_id = logging::next_request_id();
+        let span = tracing::info_span!("tool_call", request_id = %request_id, tool = %request.name);
+        let debug = take_debug_flag(&mut request);
+
+        async {
+            // 捕获工具内部的 panic,转换为 MCP 内部错误
+            let call = panic_guard::catch_panic(self.dispatch_tool_call(request, context));
+            let (result, timing) = if debug {
+                let (result, timing) = diagnostics::with_timing(call).await;
+                (result, Some(timing))
+            } else {
+                (call.await, None)
+            };
+            let result = result.unwrap_or_else(|message| {
+                Err(McpError::internal_error(
+                    format!("工具执行时发生内部错误: {}", message),
+                    None,
+                ))
+            });
+            if let Err(ref e) = result {
+                warn!(error = %e.message, "工具调用失败");
+            }
+            match timing {
+                Some(timing) => attach_timing(result, &timing),
+                None => result,
+            }
+        }
+        .instrument(span)
+        .await
+        .map_err(|e| attach_request_id(e, &request_id))
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult::with_all_items(self.tool_router.list_all()))
+    }
+
+    /// 后台任务只推送 info 级别的通知,忽略客户端设置的日志级别
+    async fn set_level(
+        &self,
+        _request: SetLevelRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        Ok(())
+    }
+
+    /// 根据代币注册表补全 token、from_token、to_token 等参数
+    async fn complete(
+        &self,
+        request: CompleteRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, McpError> {
+        Ok(CompleteResult {
+            completion: completion::complete_argument(&self.token_registry, &request.argument),
+        })
+    }
 }
 
 #[tokio::main]
@@ -129,7 +1524,7 @@ async fn main() -> anyhow::Result<()> {
     eprintln!();
 
     // 加载配置
-    let config = Config::from_env()?;
+    let mut config = Config::from_env()?;
 
     // 初始化日志系统
     logging::init_logging(&config.server.log_level, config.server.log_json_format)?;
@@ -142,15 +1537,32 @@ async fn main() -> anyhow::Result<()> {
     config.print_info();
     eprintln!();
 
+    // 加载签名器（keystore 未配置密码时在终端提示输入，Ledger 需要连接设备）
+    let signer = signer::load_signer(
+        &config.signer,
+        config.ethereum.private_key.as_deref(),
+        config.ethereum.chain_id,
+    )
+    .await?;
+    if let Some(ref signer) = signer {
+        let address = ethers::signers::Signer::address(signer);
+        eprintln!("✍️  签名地址: {} ({})", types::checksum_address(address), config.signer.backend);
+        eprintln!();
+        config.signer.address = Some(address);
+    }
+
     // 创建 Ethereum 客户端和 Provider
-    let rpc_url = if config.server.test_mode {
-        None
+    let rpc_urls: &[String] = if config.server.test_mode {
+        &[]
     } else {
-        config.ethereum.rpc_url.as_deref()
+        &config.ethereum.rpc_urls
     };
 
-    let provider = if let Some(url) = rpc_url {
-        match Provider::<Http>::try_from(url) {
+    // 所有 Provider 共享重试策略、超时和并发限制
+    let transport = RpcTransportConfig::from_config(&config.performance);
+
+    let provider = if !rpc_urls.is_empty() {
+        match RpcTransport::http(rpc_urls, &transport) {
             Ok(provider) => Some(Arc::new(provider)),
             Err(e) => {
                 eprintln!("⚠️  无法创建 Provider: {}", e);
@@ -161,7 +1573,12 @@ async fn main() -> anyhow::Result<()> {
         None
     };
 
-    let eth_client = EthClient::new(rpc_url, Some(config.ethereum.chain_id)).await?;
+    let eth_client = EthClient::new(
+        rpc_urls,
+        Some(config.ethereum.chain_id),
+        &transport,
+    )
+    .await?;
 
     if eth_client.is_available() {
         info!("Ethereum 客户端已连接");
@@ -170,20 +1587,101 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // 创建服务器实例
-    let server = EthereumTradingServer::new(config, eth_client, provider);
+    // WebSocket 节点只用于订阅，连接失败不影响 HTTP 查询
+    let ws_provider = match config.ethereum.ws_url.as_deref() {
+        Some(url) if !config.server.test_mode => match RpcTransport::ws(url).await {
+            Ok(provider) => Some(Arc::new(provider)),
+            Err(e) => {
+                eprintln!("⚠️  无法连接 WebSocket 节点: {}", e);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    let server = EthereumTradingServer::new(config, eth_client, provider)
+        .with_ws_provider(ws_provider)
+        .with_signer(signer);
 
     eprintln!("🔧 可用工具:");
     eprintln!("   - get_balance: 获取以太坊地址余额");
     eprintln!("   - get_token_price: 获取代币价格");
     eprintln!("   - swap_tokens: 模拟代币交换");
+    eprintln!("   - get_reserve_history: 查询交易对储备量历史");
+    eprintln!("   - get_chain_time: 获取链上时间及时钟偏差");
+    eprintln!("   - get_proof: 获取账户/存储槽 Merkle 证明");
+    eprintln!("   - get_staking_apr: 获取 ETH 质押年化收益");
+    eprintln!("   - get_aggregate_balance: 汇总多个钱包的余额");
+    eprintln!("   - get_recorded_history: 查询持久化的报价/模拟/执行记录");
+    eprintln!("   - get_audit_log: 查询持久化的审计日志");
+    eprintln!("   - get_pnl: 计算钱包盈亏(已实现/未实现)");
+    eprintln!("   - get_cost_basis: 查询持仓平均成本");
+    eprintln!("   - generate_tax_report: 生成年度税务报告(CSV)");
+    eprintln!("   - suggest_position_size: 按风险和池子深度建议仓位");
+    eprintln!("   - compare_quote_drift: 比较两个区块的报价漂移");
+    eprintln!("   - get_new_pairs: 查询新建交易对");
+    eprintln!("   - get_trending_tokens: 查询热门代币");
+    eprintln!("   - estimate_gas: 估算任意交易的 Gas 和费用");
+    eprintln!("   - batch_query: 批量只读查询");
+    eprintln!("   - list_workers: 列出后台任务");
+    eprintln!("   - export_market_snapshot: 导出市场快照");
+    eprintln!("   - import_market_snapshot: 导入市场快照");
+    eprintln!("   - preview_transaction: 预览交易余额变化");
+    eprintln!("   - simulate_transactions: 模拟交易序列");
+    eprintln!("   - send_user_operation: 通过智能账户执行转账、授权或交换");
+    eprintln!("   - relay_transaction: 通过 Gelato Relay 提交免 Gas 交易");
+    eprintln!("   - get_relay_task_status: 查询中继任务状态");
+    eprintln!("   - sign_transfer_authorization: 签名 EIP-3009 转账授权");
+    eprintln!("   - place_cow_order: 通过 CoW Protocol 下单");
+    eprintln!("   - get_order_status: 查询 CoW 订单状态");
+    eprintln!("   - compare_quotes: 比较各场所报价");
+    eprintln!("   - execute_swap: 签名并广播代币交换");
+    eprintln!("   - get_allowance: 查询 ERC20 授权额度");
+    eprintln!("   - approve_token: 构建、模拟并可选广播 ERC20 授权");
+    eprintln!("   - get_portfolio: 列出钱包全部代币持仓");
+    eprintln!("   - get_gas_price: 查询 Gas 价格和预计费用");
+    eprintln!("   - register_token: 以别名注册代币");
+    eprintln!("   - remove_token: 从代币注册表移除代币");
+    eprintln!("   - get_token_metadata: 查询代币元数据和合约信息");
+    eprintln!("   - get_twap_price: 查询 TWAP 价格");
+    eprintln!("   - get_transaction: 查询交易状态和回执");
+    eprintln!("   - get_account_info: 查询账户 nonce 和余额");
+    eprintln!("   - decode_calldata: 解码调用数据");
+    eprintln!("   - simulate_transaction: 模拟任意交易");
+    eprintln!("   - get_logs: 查询事件日志");
+    eprintln!("   - get_token_transfers: 查询钱包转账记录");
+    eprintln!("   - transfer_token: 构建、模拟并可选广播转账");
+    eprintln!("   - send_raw_transaction: 广播已签名交易");
+    eprintln!("   - get_submitted_transactions: 查询已提交交易状态");
+    eprintln!("   - check_token_safety: 检测蜜罐和买卖税");
+    eprintln!("   - check_contract_verification: 查询合约源码验证状态");
+    eprintln!("   - approval_audit: 审计钱包 ERC20 授权");
+    eprintln!("   - get_nft_balance: 查询 ERC-721 / ERC-1155 NFT 持仓");
+    eprintln!("   - get_lp_position: 估值 Uniswap V2 LP 头寸");
+    eprintln!("   - simulate_add_liquidity: 模拟添加 Uniswap V2 流动性");
+    eprintln!("   - get_v3_position: 查询 Uniswap V3 头寸");
     eprintln!();
 
     eprintln!("✅ 服务器已准备就绪,等待连接...");
     eprintln!();
 
-    // 使用 stdio 传输层启动服务器
-    let service = server.serve(rmcp::transport::stdio()).await?;
-    service.waiting().await?;
+    if server.config.server.transport == "http" {
+        // HTTP 传输：多个客户端共享同一组客户端和后台任务
+        let bind = server.config.server.http_bind_addr.parse()?;
+        let auth_token = server.config.server.http_auth_token.clone();
+        if server.config.uniswap.new_pair_notifications {
+            eprintln!("⚠️  HTTP 传输下不推送新交易对通知");
+        }
+        eprintln!("🌐 HTTP 传输监听 http://{}{}", bind, http_transport::MCP_PATH);
+        server.start_workers(None);
+        http_transport::serve(server, bind, auth_token).await?;
+    } else {
+        // 使用 stdio 传输层启动服务器
+        let background = server.clone();
+        let service = server.serve(rmcp::transport::stdio()).await?;
+        background.start_workers(Some(service.peer().clone()));
+        service.waiting().await?;
+    }
 
     Ok(())
 }
@@ -197,7 +1695,7 @@ mod tests {
 
     /// 创建测试用 EthClient
     async fn create_test_eth_client() -> EthClient {
-        EthClient::new(None, None)
+        EthClient::new(&[], None, &RpcTransportConfig::new(0, Duration::from_secs(30), 0))
             .await
             .expect("应该能创建测试客户端")
     }
@@ -210,6 +1708,43 @@ mod tests {
         config
     }
 
+    #[test]
+    fn test_attach_request_id() {
+        let error = attach_request_id(McpError::invalid_params("无效的地址: 0x1", None), "abc-1");
+        assert_eq!(error.message, "无效的地址: 0x1 (request_id: abc-1)");
+        assert_eq!(error.data, Some(serde_json::json!({ "request_id": "abc-1" })));
+
+        let data = Some(serde_json::json!({ "retry_after_secs": 3 }));
+        let error = attach_request_id(McpError::invalid_request("请求过于频繁", data), "abc-2");
+        let data = error.data.unwrap();
+        assert_eq!(data["retry_after_secs"], 3);
+        assert_eq!(data["request_id"], "abc-2");
+    }
+
+    #[test]
+    fn test_debug_flag_and_timing() {
+        let mut request = CallToolRequestParam {
+            name: "get_token_price".into(),
+            arguments: serde_json::json!({ "token": "USDC", "debug": true }).as_object().cloned(),
+        };
+        assert!(take_debug_flag(&mut request));
+        assert!(!request.arguments.as_ref().unwrap().contains_key("debug"));
+        assert!(!take_debug_flag(&mut request));
+
+        let timing = diagnostics::TimingBreakdown {
+            total_ms: 42,
+            ..Default::default()
+        };
+        let result = attach_timing(Ok(CallToolResult::success(vec![Content::text("{}")])), &timing).unwrap();
+        assert_eq!(result.content.len(), 2);
+
+        let data = Some(serde_json::json!({ "refused": true }));
+        let error = attach_timing(Err(McpError::invalid_request("拒绝", data)), &timing).unwrap_err();
+        let data = error.data.unwrap();
+        assert_eq!(data["refused"], true);
+        assert_eq!(data["timing"]["total_ms"], 42);
+    }
+
     #[tokio::test]
     async fn test_server_creation() {
         let config = create_test_config();
@@ -227,9 +1762,11 @@ mod tests {
         let args = GetBalanceArgs {
             address: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
             token_address: None,
+            finality: None,
+            block_number: None,
         };
 
-        let result = server.get_balance(Parameters(args));
+        let result = server.get_balance(Parameters(args)).await;
         assert!(result.is_ok(), "get_balance 应该成功返回");
 
         let call_result = result.unwrap();
@@ -249,9 +1786,11 @@ mod tests {
         let args = GetBalanceArgs {
             address: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
             token_address: Some("USDC".to_string()),
+            finality: None,
+            block_number: None,
         };
 
-        let result = server.get_balance(Parameters(args));
+        let result = server.get_balance(Parameters(args)).await;
         assert!(result.is_ok(), "get_balance 应该成功返回");
     }
 
@@ -275,6 +1814,7 @@ mod tests {
             balance: "100000000000000000000".to_string(),
             decimals: 18,
             formatted_balance: "100".to_string(),
+            block_number: None,
         };
 
         let json = serde_json::to_string(&result).expect("应该能序列化");
@@ -309,8 +1849,10 @@ mod tests {
                 let args = GetBalanceArgs {
                     address: format!("0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb{}", i),
                     token_address: None,
+                    finality: None,
+                    block_number: None,
                 };
-                server_clone.get_balance(Parameters(args))
+                server_clone.get_balance(Parameters(args)).await
             });
             handles.push(handle);
         }
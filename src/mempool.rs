@@ -0,0 +1,312 @@
+use crate::eth_client::EthClient;
+use crate::workers::WorkerManager;
+use ethers::abi::{self, ParamType, Token};
+use ethers::prelude::*;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::debug;
+
+/// 内存池轮询间隔
+const MEMPOOL_POLL_INTERVAL: Duration = Duration::from_secs(6);
+/// 内存池视图超过该时长未刷新时视为过期，不再参与滑点建议
+const MEMPOOL_STALE_SECS: i64 = 60;
+/// 每笔竞争交易增加的滑点（基点）
+const SLIPPAGE_PER_COMPETING_SWAP_BPS: u32 = 25;
+/// 因竞争交易增加的滑点上限（基点）
+const MAX_EXTRA_SLIPPAGE_BPS: u32 = 150;
+/// 竞争交易达到该数量时建议私有提交（继续放宽滑点只会扩大被夹空间）
+const PRIVATE_SUBMISSION_THRESHOLD: usize = 3;
+
+/// Uniswap V2 Router 上按路径交换的函数签名及 path 参数位置
+/// ETH 作为输入的函数没有 amountIn 参数，path 位于第 2 个参数
+const ROUTER_SWAP_SIGNATURES: [(&str, usize); 9] = [
+    ("swapExactTokensForTokens(uint256,uint256,address[],address,uint256)", 2),
+    ("swapTokensForExactTokens(uint256,uint256,address[],address,uint256)", 2),
+    ("swapExactTokensForETH(uint256,uint256,address[],address,uint256)", 2),
+    ("swapTokensForExactETH(uint256,uint256,address[],address,uint256)", 2),
+    ("swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)", 2),
+    ("swapExactTokensForETHSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)", 2),
+    ("swapExactETHForTokens(uint256,address[],address,uint256)", 1),
+    ("swapETHForExactTokens(uint256,address[],address,uint256)", 1),
+    ("swapExactETHForTokensSupportingFeeOnTransferTokens(uint256,address[],address,uint256)", 1),
+];
+
+/// 内存池中待确认的 Router 交换
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingSwap {
+    pub hash: H256,
+    pub from: Address,
+    pub path: Vec<Address>,
+}
+
+impl PendingSwap {
+    /// 是否与给定路径经过同一个交易对（不区分方向）
+    pub fn touches(&self, path: &[Address]) -> bool {
+        let theirs = pair_keys(&self.path);
+        pair_keys(path).iter().any(|key| theirs.contains(key))
+    }
+}
+
+/// 基于内存池状况的滑点建议
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SlippageAdvice {
+    /// 调用方指定或默认的滑点（基点）
+    pub base_slippage_bps: u32,
+    /// 建议滑点（基点）
+    pub recommended_slippage_bps: u32,
+    /// 同一交易对上待确认的其他交换数量
+    pub competing_swaps: usize,
+    /// 是否建议通过私有交易池（如 Flashbots Protect）提交
+    pub private_submission: bool,
+    pub reason: String,
+}
+
+/// 最近一次轮询得到的内存池视图
+#[derive(Debug, Clone)]
+struct MempoolView {
+    swaps: Vec<PendingSwap>,
+    observed_at: i64,
+}
+
+/// 内存池监听结果：后台任务定期刷新，交换工具读取后给出滑点建议
+#[derive(Debug, Default)]
+pub struct MempoolWatcher {
+    view: RwLock<Option<MempoolView>>,
+}
+
+impl MempoolWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 用最新一次轮询结果替换内存池视图
+    pub fn update(&self, swaps: Vec<PendingSwap>, observed_at: i64) {
+        *self.view.write().unwrap_or_else(|e| e.into_inner()) = Some(MempoolView { swaps, observed_at });
+    }
+
+    /// 同一交易对上其他地址待确认的交换数量（`wallet` 自己的交易不计入）
+    /// 尚未轮询或视图已过期时返回 None
+    pub fn competing_swaps(&self, path: &[Address], wallet: Address, now: i64) -> Option<usize> {
+        let view = self.view.read().unwrap_or_else(|e| e.into_inner());
+        let view = view.as_ref().filter(|v| now - v.observed_at <= MEMPOOL_STALE_SECS)?;
+
+        Some(
+            view.swaps
+                .iter()
+                .filter(|swap| swap.from != wallet && swap.touches(path))
+                .count(),
+        )
+    }
+
+    /// 根据竞争交易数量给出滑点建议（没有可用的内存池视图时返回 None）
+    pub fn slippage_advice(
+        &self,
+        path: &[Address],
+        wallet: Address,
+        base_slippage_bps: u32,
+        now: i64,
+    ) -> Option<SlippageAdvice> {
+        self.competing_swaps(path, wallet, now)
+            .map(|competing| recommend_slippage(base_slippage_bps, competing))
+    }
+}
+
+/// 竞争交易越多，成交前价格被推动的幅度越大：每笔增加固定滑点，并设置上限
+/// 达到阈值时不再继续放宽，而是建议私有提交
+pub fn recommend_slippage(base_slippage_bps: u32, competing_swaps: usize) -> SlippageAdvice {
+    let extra = (competing_swaps as u32)
+        .saturating_mul(SLIPPAGE_PER_COMPETING_SWAP_BPS)
+        .min(MAX_EXTRA_SLIPPAGE_BPS);
+    let recommended_slippage_bps = base_slippage_bps.saturating_add(extra).min(10000);
+    let private_submission = competing_swaps >= PRIVATE_SUBMISSION_THRESHOLD;
+
+    let reason = if competing_swaps == 0 {
+        "内存池中没有同一交易对上的待确认交换,保持原滑点".to_string()
+    } else if private_submission {
+        format!(
+            "内存池中有 {} 笔同一交易对上的待确认交换,价格可能在成交前被推动且存在被夹风险;滑点放宽到 {} bps,建议通过私有交易池提交",
+            competing_swaps, recommended_slippage_bps
+        )
+    } else {
+        format!(
+            "内存池中有 {} 笔同一交易对上的待确认交换,价格可能在成交前被推动;滑点放宽到 {} bps",
+            competing_swaps, recommended_slippage_bps
+        )
+    };
+
+    SlippageAdvice {
+        base_slippage_bps,
+        recommended_slippage_bps,
+        competing_swaps,
+        private_submission,
+        reason,
+    }
+}
+
+/// 解析发往 Router 的交换交易，其他交易返回 None
+pub fn decode_pending_swap(tx: &Transaction, router: Address) -> Option<PendingSwap> {
+    if tx.to != Some(router) || tx.input.len() < 4 {
+        return None;
+    }
+
+    let (_, path_index) = ROUTER_SWAP_SIGNATURES
+        .iter()
+        .find(|(signature, _)| ethers::utils::id(signature) == tx.input[..4])?;
+
+    let mut params = vec![ParamType::Uint(256); *path_index];
+    params.extend([
+        ParamType::Array(Box::new(ParamType::Address)),
+        ParamType::Address,
+        ParamType::Uint(256),
+    ]);
+
+    let path = abi::decode(&params, &tx.input[4..])
+        .ok()?
+        .into_iter()
+        .nth(*path_index)?
+        .into_array()?
+        .into_iter()
+        .filter_map(Token::into_address)
+        .collect::<Vec<_>>();
+
+    (path.len() >= 2).then_some(PendingSwap {
+        hash: tx.hash,
+        from: tx.from,
+        path,
+    })
+}
+
+/// 启动内存池监听任务：定期读取 pending 交易，记录发往 Router 的交换
+pub fn spawn_mempool_watcher(
+    workers: &WorkerManager,
+    eth_client: Arc<EthClient>,
+    router: Address,
+    watcher: Arc<MempoolWatcher>,
+) {
+    workers.spawn_periodic(
+        "mempool_watcher",
+        "监听内存池中的 Uniswap V2 交换,用于动态滑点建议",
+        MEMPOOL_POLL_INTERVAL,
+        move || {
+            let eth_client = eth_client.clone();
+            let watcher = watcher.clone();
+
+            async move {
+                let transactions = eth_client
+                    .pending_transactions()
+                    .await
+                    .map_err(|e| format!("查询 pending 交易失败: {}", e))?;
+
+                let swaps: Vec<PendingSwap> = transactions
+                    .iter()
+                    .filter_map(|tx| decode_pending_swap(tx, router))
+                    .collect();
+
+                debug!(pending = transactions.len(), swaps = swaps.len(), "刷新内存池视图");
+
+                watcher.update(swaps, chrono::Utc::now().timestamp());
+                Ok(())
+            }
+        },
+    );
+}
+
+/// 路径上每一跳的交易对（代币按地址排序，不区分方向）
+fn pair_keys(path: &[Address]) -> Vec<(Address, Address)> {
+    path.windows(2)
+        .map(|hop| if hop[0] < hop[1] { (hop[0], hop[1]) } else { (hop[1], hop[0]) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uniswap::{NativeLeg, SwapCall};
+
+    fn token(byte: u8) -> Address {
+        Address::repeat_byte(byte)
+    }
+
+    fn swap(from: u8, path: &[u8]) -> PendingSwap {
+        PendingSwap {
+            hash: H256::repeat_byte(from),
+            from: token(from),
+            path: path.iter().map(|b| token(*b)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_decode_pending_swap() {
+        let router = token(0xaa);
+        let call = SwapCall {
+            amount_in: U256::exp10(18),
+            amount_out_min: U256::zero(),
+            path: vec![token(1), token(2)],
+            to: token(0xbb),
+            deadline: U256::from(1_700_000_000u64),
+            native: NativeLeg::None,
+            fee_on_transfer: false,
+        };
+        let mut tx = Transaction {
+            to: Some(router),
+            from: token(0xbb),
+            input: call.encode().into(),
+            ..Default::default()
+        };
+        assert_eq!(decode_pending_swap(&tx, router).unwrap().path, call.path);
+
+        // swapExactETHForTokens: path 位于第 2 个参数
+        let mut data = ethers::utils::id(ROUTER_SWAP_SIGNATURES[6].0).to_vec();
+        data.extend(abi::encode(&[
+            Token::Uint(U256::zero()),
+            Token::Array(vec![Token::Address(token(3)), Token::Address(token(4))]),
+            Token::Address(token(0xbb)),
+            Token::Uint(U256::from(1_700_000_000u64)),
+        ]));
+        tx.input = data.into();
+        assert_eq!(decode_pending_swap(&tx, router).unwrap().path, vec![token(3), token(4)]);
+
+        // 发往其他合约或非交换调用时忽略
+        assert!(decode_pending_swap(&tx, token(0xcc)).is_none());
+        tx.input = vec![0x09, 0x5e, 0xa7, 0xb3].into();
+        assert!(decode_pending_swap(&tx, router).is_none());
+    }
+
+    #[test]
+    fn test_competing_swaps_match_pairs_and_skip_own() {
+        let watcher = MempoolWatcher::new();
+        let path = [token(1), token(2)];
+        assert_eq!(watcher.competing_swaps(&path, token(9), 100), None);
+
+        watcher.update(
+            vec![
+                swap(0x10, &[2, 1]),    // 反方向同一交易对
+                swap(0x11, &[3, 1, 2]), // 多跳经过同一交易对
+                swap(0x12, &[1, 3]),    // 不同交易对
+                swap(0x09, &[1, 2]),    // 自己的交易
+            ],
+            100,
+        );
+        assert_eq!(watcher.competing_swaps(&path, token(9), 110), Some(2));
+
+        // 视图过期后不再给出建议
+        assert_eq!(watcher.competing_swaps(&path, token(9), 100 + MEMPOOL_STALE_SECS + 1), None);
+    }
+
+    #[test]
+    fn test_recommend_slippage() {
+        let quiet = recommend_slippage(50, 0);
+        assert_eq!(quiet.recommended_slippage_bps, 50);
+        assert!(!quiet.private_submission);
+
+        let busy = recommend_slippage(50, 2);
+        assert_eq!(busy.recommended_slippage_bps, 100);
+        assert!(!busy.private_submission);
+
+        let crowded = recommend_slippage(50, 10);
+        assert_eq!(crowded.recommended_slippage_bps, 50 + MAX_EXTRA_SLIPPAGE_BPS);
+        assert!(crowded.private_submission);
+
+        assert_eq!(recommend_slippage(9_990, 4).recommended_slippage_bps, 10000);
+    }
+}
@@ -0,0 +1,299 @@
+use crate::uniswap::SwapLog;
+use ethers::prelude::*;
+use std::collections::HashMap;
+
+/// 判断池子是否常被夹击时回看的区块数
+pub const SANDWICH_LOOKBACK_BLOCKS: u64 = 1_000;
+/// 三明治攻击（抢跑 + 尾随两笔交换）的 Gas 用量估算
+pub const SANDWICH_GAS: u64 = 250_000;
+/// 回看区间内至少被夹击这么多次，才视为常见目标
+const COMMON_TARGET_MIN_SANDWICHES: usize = 2;
+/// 被夹击的交换占比达到该值（基点）时视为常见目标
+const COMMON_TARGET_MIN_SHARE_BPS: usize = 500;
+/// 搜索抢跑数量时的上限（池子输入侧储备量的倍数）
+const MAX_FRONT_RUN_RESERVE_MULTIPLE: u64 = 100;
+/// 二分 / 三分搜索的迭代次数
+const SEARCH_ITERATIONS: usize = 128;
+
+/// 交换的 MEV（三明治攻击）风险评估
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MevRisk {
+    /// 风险等级：low / medium / high
+    pub risk_level: String,
+    /// 按当前滑点容差，攻击者最多可获得的三明治利润（源代币，未扣除 Gas）
+    pub expected_sandwich_profit: String,
+    /// 攻击两笔交换的 Gas 成本（源代币，无法折算时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attack_gas_cost: Option<String>,
+    /// 扣除 Gas 后攻击是否有利可图
+    pub profitable_after_gas: bool,
+    /// 利润最高的被攻击池子
+    pub attacked_pool: String,
+    /// 路径上是否有池子近期频繁被夹击
+    pub common_sandwich_target: bool,
+    /// 回看区间内路径池子上的交换数量（查询事件失败时为 None）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recent_swaps: Option<usize>,
+    /// 回看区间内识别出的三明治攻击数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recent_sandwiches: Option<usize>,
+    pub lookback_blocks: u64,
+    /// 攻击利润不超过 Gas 成本的最大滑点（基点）
+    pub recommended_max_slippage_bps: u32,
+    pub reason: String,
+}
+
+/// 路径上单个池子的储备量与受害交换在该池子上的输入/输出
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HopState {
+    pub pool: Address,
+    pub reserve_in: U256,
+    pub reserve_out: U256,
+    pub amount_in: U256,
+}
+
+/// 单个池子近期被夹击的统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SandwichActivity {
+    pub swaps: usize,
+    pub sandwiches: usize,
+}
+
+impl SandwichActivity {
+    pub fn is_common_target(&self) -> bool {
+        self.sandwiches >= COMMON_TARGET_MIN_SANDWICHES
+            && self.sandwiches * 10_000 >= self.swaps * COMMON_TARGET_MIN_SHARE_BPS
+    }
+}
+
+/// V2 池子输出数量（含 0.3% 手续费），无效输入返回 0
+fn amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256) -> U256 {
+    if amount_in.is_zero() || reserve_in.is_zero() || reserve_out.is_zero() {
+        return U256::zero();
+    }
+    let amount_in_with_fee = amount_in * 997;
+    amount_in_with_fee * reserve_out / (reserve_in * 1000 + amount_in_with_fee)
+}
+
+/// 抢跑 `front_run` 后受害交换得到的输出，以及攻击者尾随卖出后的利润（池子输入代币）
+fn sandwich_outcome(hop: &HopState, front_run: U256) -> (U256, U256) {
+    let bought = amount_out(front_run, hop.reserve_in, hop.reserve_out);
+    let (reserve_in, reserve_out) = (hop.reserve_in + front_run, hop.reserve_out - bought);
+
+    let victim_out = amount_out(hop.amount_in, reserve_in, reserve_out);
+    let (reserve_in, reserve_out) = (reserve_in + hop.amount_in, reserve_out - victim_out);
+
+    // 尾随：卖出抢跑买到的代币
+    let back_run = amount_out(bought, reserve_out, reserve_in);
+    (victim_out, back_run.saturating_sub(front_run))
+}
+
+/// 单个池子上的三明治攻击利润（池子输入代币，未扣除 Gas）
+///
+/// 攻击者的抢跑数量受受害交换的最小输出约束：抢跑越多价格推得越高，受害交换输出低于
+/// 最小输出时整笔回滚。先二分出满足约束的最大抢跑数量，再在该范围内三分搜索最优利润
+pub fn sandwich_profit(hop: &HopState, slippage_bps: u32) -> U256 {
+    let quoted = amount_out(hop.amount_in, hop.reserve_in, hop.reserve_out);
+    if quoted.is_zero() {
+        return U256::zero();
+    }
+    let minimum = quoted * (10_000 - slippage_bps.min(10_000)) / 10_000;
+
+    let (mut low, mut high) = (U256::zero(), hop.reserve_in * MAX_FRONT_RUN_RESERVE_MULTIPLE);
+    for _ in 0..SEARCH_ITERATIONS {
+        if high - low <= U256::one() {
+            break;
+        }
+        let mid = (low + high) / 2;
+        if sandwich_outcome(hop, mid).0 >= minimum {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    let profit = |front_run: U256| sandwich_outcome(hop, front_run).1;
+    let (mut left, mut right) = (U256::zero(), low);
+    for _ in 0..SEARCH_ITERATIONS {
+        if right - left <= U256::from(2u64) {
+            break;
+        }
+        let third = (right - left) / 3;
+        if profit(left + third) < profit(right - third) {
+            left += third;
+        } else {
+            right -= third;
+        }
+    }
+
+    [left, (left + right) / 2, right, low]
+        .into_iter()
+        .map(profit)
+        .max()
+        .unwrap_or_default()
+}
+
+/// 攻击利润不超过 `max_profit` 的最大滑点（基点），利润随滑点单调不减
+pub fn max_safe_slippage_bps(hop: &HopState, max_profit: U256) -> u32 {
+    let (mut low, mut high) = (0u32, 10_000u32);
+    if sandwich_profit(hop, high) <= max_profit {
+        return high;
+    }
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+        if sandwich_profit(hop, mid) <= max_profit {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// 按池子统计近期的三明治攻击
+///
+/// 同一区块内，同一接收方先后在相反方向成交、中间夹着另一接收方与抢跑同方向的交换，
+/// 视为一次夹击（`swaps` 需按成交顺序排列）
+pub fn sandwich_activity(swaps: &[SwapLog]) -> HashMap<Address, SandwichActivity> {
+    let mut by_block: HashMap<(Address, u64), Vec<&SwapLog>> = HashMap::new();
+    for swap in swaps {
+        by_block.entry((swap.pair, swap.block_number)).or_default().push(swap);
+    }
+
+    let mut activity: HashMap<Address, SandwichActivity> = HashMap::new();
+    for ((pair, _), block_swaps) in by_block {
+        let entry = activity.entry(pair).or_default();
+        entry.swaps += block_swaps.len();
+        entry.sandwiches += count_block_sandwiches(&block_swaps);
+    }
+    activity
+}
+
+fn count_block_sandwiches(swaps: &[&SwapLog]) -> usize {
+    let mut sandwiches = 0;
+    let mut used = vec![false; swaps.len()];
+
+    for front in 0..swaps.len() {
+        if used[front] {
+            continue;
+        }
+        let attacker = swaps[front].to;
+        let direction = swaps[front].sells_token0();
+
+        let back = (front + 1..swaps.len())
+            .find(|&i| !used[i] && swaps[i].to == attacker && swaps[i].sells_token0() != direction);
+        let Some(back) = back else {
+            continue;
+        };
+        let has_victim = swaps[front + 1..back]
+            .iter()
+            .any(|swap| swap.to != attacker && swap.sells_token0() == direction);
+        if has_victim {
+            used[front] = true;
+            used[back] = true;
+            sandwiches += 1;
+        }
+    }
+    sandwiches
+}
+
+/// 风险等级：扣除 Gas 后有利可图且池子常被夹击为 high，满足其一为 medium
+pub fn risk_level(profitable_after_gas: bool, common_target: bool) -> &'static str {
+    match (profitable_after_gas, common_target) {
+        (true, true) => "high",
+        (true, false) | (false, true) => "medium",
+        (false, false) => "low",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ether(amount: u64) -> U256 {
+        U256::from(amount) * U256::exp10(18)
+    }
+
+    fn hop(amount_in: U256) -> HopState {
+        HopState {
+            pool: Address::repeat_byte(0x03),
+            reserve_in: ether(1_000),
+            reserve_out: ether(2_000_000),
+            amount_in,
+        }
+    }
+
+    fn swap(block: u64, log_index: u64, to: u8, sells_token0: bool) -> SwapLog {
+        let (amount0_in, amount1_in) = if sells_token0 { (1u64, 0u64) } else { (0, 1) };
+        SwapLog {
+            pair: Address::repeat_byte(0x03),
+            sender: Address::zero(),
+            to: Address::repeat_byte(to),
+            amount0_in: U256::from(amount0_in),
+            amount1_in: U256::from(amount1_in),
+            amount0_out: U256::from(amount1_in),
+            amount1_out: U256::from(amount0_in),
+            block_number: block,
+            log_index,
+        }
+    }
+
+    #[test]
+    fn test_sandwich_profit_grows_with_slippage() {
+        let trade = hop(ether(10));
+
+        // 零滑点时无法抢跑
+        assert!(sandwich_profit(&trade, 0).is_zero());
+
+        let at_1_percent = sandwich_profit(&trade, 100);
+        let at_5_percent = sandwich_profit(&trade, 500);
+        assert!(!at_1_percent.is_zero());
+        assert!(at_5_percent > at_1_percent);
+
+        // 相对池子很小的交易，抢跑付出的手续费超过受害者的滑点损失
+        let small = hop(ether(1) / 10);
+        assert!(sandwich_profit(&small, 500).is_zero());
+    }
+
+    #[test]
+    fn test_max_safe_slippage() {
+        let trade = hop(ether(10));
+        let safe = max_safe_slippage_bps(&trade, U256::zero());
+        assert!(sandwich_profit(&trade, safe).is_zero());
+        assert!(!sandwich_profit(&trade, safe + 1).is_zero());
+
+        // 允许的利润越高（攻击 Gas 越贵），可接受的滑点越大
+        assert!(max_safe_slippage_bps(&trade, ether(1) / 10) > safe);
+        assert_eq!(max_safe_slippage_bps(&trade, ether(1_000)), 10_000);
+    }
+
+    #[test]
+    fn test_sandwich_activity() {
+        let swaps = vec![
+            // 区块 1：0x0a 抢跑、0x0b 受害、0x0a 尾随
+            swap(1, 0, 0x0a, true),
+            swap(1, 1, 0x0b, true),
+            swap(1, 2, 0x0a, false),
+            // 区块 2：同一地址来回交易但中间没有同方向的其他交换
+            swap(2, 0, 0x0c, true),
+            swap(2, 1, 0x0d, false),
+            swap(2, 2, 0x0c, false),
+            // 区块 3：普通交换
+            swap(3, 0, 0x0e, true),
+        ];
+
+        let activity = sandwich_activity(&swaps);
+        let pool = activity[&Address::repeat_byte(0x03)];
+        assert_eq!(pool, SandwichActivity { swaps: 7, sandwiches: 1 });
+        assert!(!pool.is_common_target());
+        assert!(SandwichActivity { swaps: 20, sandwiches: 2 }.is_common_target());
+        assert!(!SandwichActivity { swaps: 200, sandwiches: 2 }.is_common_target());
+    }
+
+    #[test]
+    fn test_risk_level() {
+        assert_eq!(risk_level(true, true), "high");
+        assert_eq!(risk_level(false, true), "medium");
+        assert_eq!(risk_level(false, false), "low");
+    }
+}
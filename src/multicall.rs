@@ -0,0 +1,288 @@
+use ethers::abi::{self, ParamType, Token};
+use crate::eth_client::RpcProvider;
+use ethers::prelude::*;
+use ethers::types::spoof;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::id;
+use tracing::{debug, instrument};
+
+/// Multicall3 合约地址（所有主流 EVM 链上相同）
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// 单次 aggregate3 调用的最大子调用数量，避免超出节点的 eth_call gas 上限
+const MAX_CALLS_PER_BATCH: usize = 500;
+
+/// Multicall 错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum MulticallError {
+    #[error("提供者错误: {0}")]
+    ProviderError(#[from] ProviderError),
+
+    #[error("ABI 编码/解码错误: {0}")]
+    AbiError(String),
+}
+
+/// aggregate3 的单个子调用
+#[derive(Debug, Clone)]
+pub struct Call3 {
+    pub target: Address,
+    pub allow_failure: bool,
+    pub call_data: Bytes,
+    /// 随子调用发送的 ETH（仅 aggregate3Value 使用）
+    pub value: U256,
+}
+
+/// aggregate3 的单个子调用结果
+#[derive(Debug, Clone)]
+pub struct Call3Result {
+    pub success: bool,
+    pub return_data: Bytes,
+}
+
+impl Call3 {
+    /// 创建允许失败的子调用（单个代币异常不影响整批结果）
+    pub fn new(target: Address, call_data: Vec<u8>) -> Self {
+        Self {
+            target,
+            allow_failure: true,
+            call_data: Bytes::from(call_data),
+            value: U256::zero(),
+        }
+    }
+
+    /// 设置随子调用发送的 ETH
+    pub fn with_value(mut self, value: U256) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Multicall3.getEthBalance(address) 子调用
+    pub fn eth_balance(owner: Address) -> Self {
+        let multicall: Address = MULTICALL3_ADDRESS.parse().expect("硬编码地址应该有效");
+        let mut data = id("getEthBalance(address)").to_vec();
+        data.extend(abi::encode(&[Token::Address(owner)]));
+        Self::new(multicall, data)
+    }
+}
+
+impl Call3Result {
+    /// 将返回值解析为 uint256（失败或长度不符时返回 None）
+    pub fn as_u256(&self) -> Option<U256> {
+        if self.success && self.return_data.len() == 32 {
+            Some(U256::from_big_endian(&self.return_data))
+        } else {
+            None
+        }
+    }
+}
+
+/// 通过 Multicall3.aggregate3 批量执行只读调用
+/// 超过 MAX_CALLS_PER_BATCH 时自动分批，返回结果与输入顺序一致
+#[instrument(skip(provider, calls), fields(calls = calls.len()))]
+pub async fn aggregate3(
+    provider: &RpcProvider,
+    calls: Vec<Call3>,
+    block: Option<BlockId>,
+) -> Result<Vec<Call3Result>, MulticallError> {
+    let multicall: Address = MULTICALL3_ADDRESS.parse().expect("硬编码地址应该有效");
+    let mut results = Vec::with_capacity(calls.len());
+
+    for chunk in calls.chunks(MAX_CALLS_PER_BATCH) {
+        let tx = Eip1559TransactionRequest::new()
+            .to(multicall)
+            .data(encode_aggregate3(chunk));
+
+        let output = provider.call(&tx.into(), block).await?;
+        let decoded = decode_aggregate3(&output)?;
+
+        if decoded.len() != chunk.len() {
+            return Err(MulticallError::AbiError(format!(
+                "期望 {} 个结果，实际 {} 个",
+                chunk.len(),
+                decoded.len()
+            )));
+        }
+
+        results.extend(decoded);
+    }
+
+    debug!(results = results.len(), "Multicall 批量调用完成");
+    Ok(results)
+}
+
+/// 通过 Multicall3.aggregate3Value 在同一次 eth_call 中按顺序执行可携带 ETH 的子调用
+/// 子调用的 msg.sender 为 Multicall3 合约，前序子调用的状态变化对后续子调用可见；
+/// `state` 为状态覆盖（如为 `from` 注入 ETH 余额），msg.value 为各子调用 value 之和
+#[instrument(skip(provider, calls, state), fields(calls = calls.len()))]
+pub async fn aggregate3_value(
+    provider: &RpcProvider,
+    from: Address,
+    calls: &[Call3],
+    state: &spoof::State,
+    block: Option<BlockId>,
+) -> Result<Vec<Call3Result>, MulticallError> {
+    let multicall: Address = MULTICALL3_ADDRESS.parse().expect("硬编码地址应该有效");
+    let total_value = calls
+        .iter()
+        .fold(U256::zero(), |total, call| total.saturating_add(call.value));
+
+    let tx: TypedTransaction = Eip1559TransactionRequest::new()
+        .from(from)
+        .to(multicall)
+        .value(total_value)
+        .data(encode_aggregate3_value(calls))
+        .into();
+
+    let mut call = provider.call_raw(&tx).state(state);
+    if let Some(block) = block {
+        call = call.block(block);
+    }
+    let output = call.await?;
+    let results = decode_aggregate3(&output)?;
+
+    if results.len() != calls.len() {
+        return Err(MulticallError::AbiError(format!(
+            "期望 {} 个结果，实际 {} 个",
+            calls.len(),
+            results.len()
+        )));
+    }
+
+    debug!(results = results.len(), "Multicall aggregate3Value 调用完成");
+    Ok(results)
+}
+
+/// 编码 aggregate3Value((address,bool,uint256,bytes)[]) 调用数据
+fn encode_aggregate3_value(calls: &[Call3]) -> Bytes {
+    let tokens = calls
+        .iter()
+        .map(|call| {
+            Token::Tuple(vec![
+                Token::Address(call.target),
+                Token::Bool(call.allow_failure),
+                Token::Uint(call.value),
+                Token::Bytes(call.call_data.to_vec()),
+            ])
+        })
+        .collect();
+
+    let mut data = id("aggregate3Value((address,bool,uint256,bytes)[])").to_vec();
+    data.extend(abi::encode(&[Token::Array(tokens)]));
+    Bytes::from(data)
+}
+
+/// 编码 aggregate3((address,bool,bytes)[]) 调用数据
+fn encode_aggregate3(calls: &[Call3]) -> Bytes {
+    let tokens = calls
+        .iter()
+        .map(|call| {
+            Token::Tuple(vec![
+                Token::Address(call.target),
+                Token::Bool(call.allow_failure),
+                Token::Bytes(call.call_data.to_vec()),
+            ])
+        })
+        .collect();
+
+    let mut data = id("aggregate3((address,bool,bytes)[])").to_vec();
+    data.extend(abi::encode(&[Token::Array(tokens)]));
+    Bytes::from(data)
+}
+
+/// 解码 aggregate3 返回值 (bool success, bytes returnData)[]
+fn decode_aggregate3(data: &[u8]) -> Result<Vec<Call3Result>, MulticallError> {
+    let result_type = ParamType::Array(Box::new(ParamType::Tuple(vec![
+        ParamType::Bool,
+        ParamType::Bytes,
+    ])));
+
+    let tokens = abi::decode(&[result_type], data)
+        .map_err(|e| MulticallError::AbiError(e.to_string()))?;
+
+    let items = match tokens.into_iter().next() {
+        Some(Token::Array(items)) => items,
+        _ => return Err(MulticallError::AbiError("aggregate3 返回值格式错误".to_string())),
+    };
+
+    items
+        .into_iter()
+        .map(|item| match item {
+            Token::Tuple(fields) => match fields.as_slice() {
+                [Token::Bool(success), Token::Bytes(return_data)] => Ok(Call3Result {
+                    success: *success,
+                    return_data: Bytes::from(return_data.clone()),
+                }),
+                _ => Err(MulticallError::AbiError("aggregate3 结果元组格式错误".to_string())),
+            },
+            _ => Err(MulticallError::AbiError("aggregate3 结果格式错误".to_string())),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_aggregate3_selector() {
+        let data = encode_aggregate3(&[Call3::eth_balance(Address::zero())]);
+        // aggregate3 selector: 0x82ad56cb
+        assert_eq!(&data[0..4], &[0x82, 0xad, 0x56, 0xcb]);
+    }
+
+    #[test]
+    fn test_encode_aggregate3_value() {
+        let call = Call3::new(Address::repeat_byte(0x22), vec![0xaa]).with_value(U256::from(7));
+        let data = encode_aggregate3_value(&[call]);
+        // aggregate3Value selector: 0x174dea71
+        assert_eq!(&data[0..4], &[0x17, 0x4d, 0xea, 0x71]);
+
+        let tuple = ParamType::Tuple(vec![
+            ParamType::Address,
+            ParamType::Bool,
+            ParamType::Uint(256),
+            ParamType::Bytes,
+        ]);
+        let decoded = abi::decode(&[ParamType::Array(Box::new(tuple))], &data[4..]).unwrap();
+        let Token::Array(items) = &decoded[0] else { panic!("应为数组") };
+        assert_eq!(
+            items[0],
+            Token::Tuple(vec![
+                Token::Address(Address::repeat_byte(0x22)),
+                Token::Bool(true),
+                Token::Uint(U256::from(7)),
+                Token::Bytes(vec![0xaa]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_eth_balance_call() {
+        let call = Call3::eth_balance(Address::repeat_byte(0x11));
+        // getEthBalance(address) selector: 0x4d2301cc
+        assert_eq!(&call.call_data[0..4], &[0x4d, 0x23, 0x01, 0xcc]);
+        assert_eq!(call.call_data.len(), 36);
+        assert!(call.allow_failure);
+    }
+
+    #[test]
+    fn test_decode_aggregate3_roundtrip() {
+        let mut balance = [0u8; 32];
+        U256::from(42).to_big_endian(&mut balance);
+
+        let encoded = abi::encode(&[Token::Array(vec![
+            Token::Tuple(vec![Token::Bool(true), Token::Bytes(balance.to_vec())]),
+            Token::Tuple(vec![Token::Bool(false), Token::Bytes(vec![])]),
+        ])]);
+
+        let results = decode_aggregate3(&encoded).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_u256(), Some(U256::from(42)));
+        assert_eq!(results[1].as_u256(), None);
+    }
+
+    #[test]
+    fn test_decode_aggregate3_invalid() {
+        assert!(decode_aggregate3(&[0u8; 10]).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::bindings::{self, ierc721};
+use crate::erc1155::ERC1155_INTERFACE_ID;
+use crate::erc20::parse_string_return;
+use crate::eth_client::RpcProvider;
+use crate::multicall::{self, Call3, Call3Result, MulticallError};
+use ethers::abi::{AbiDecode, AbiEncode};
+use ethers::prelude::*;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+/// ERC-721 的 ERC-165 接口 ID
+pub const ERC721_INTERFACE_ID: [u8; 4] = [0x80, 0xac, 0x58, 0xcd];
+
+/// ERC-721 Enumerable 扩展的 ERC-165 接口 ID
+pub const ERC721_ENUMERABLE_INTERFACE_ID: [u8; 4] = [0x78, 0x0e, 0x9d, 0x63];
+
+/// NFT 查询错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum NftError {
+    #[error("提供者错误: {0}")]
+    ProviderError(#[from] ProviderError),
+
+    #[error("ABI 编码/解码错误: {0}")]
+    AbiError(String),
+
+    #[error("Provider 不可用")]
+    ProviderUnavailable,
+
+    #[error("Multicall 错误: {0}")]
+    MulticallError(#[from] MulticallError),
+}
+
+/// NFT 合集的基本信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CollectionInfo {
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    /// 是否通过 ERC-165 声明支持 ERC-721
+    pub erc721: bool,
+    /// 是否支持 ERC-721 Enumerable（可以按索引列出持有的 token ID）
+    pub enumerable: bool,
+    /// 是否通过 ERC-165 声明支持 ERC-1155
+    pub erc1155: bool,
+}
+
+/// NFT 合约查询客户端
+pub struct NftClient {
+    provider: Option<Arc<RpcProvider>>,
+}
+
+impl NftClient {
+    pub fn new(provider: Option<Arc<RpcProvider>>) -> Self {
+        Self { provider }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    fn provider(&self) -> Result<&Arc<RpcProvider>, NftError> {
+        self.provider.as_ref().ok_or(NftError::ProviderUnavailable)
+    }
+
+    /// 查询合集名称、符号和 ERC-165 接口支持情况（Multicall3 单次 RPC）
+    #[instrument(skip(self))]
+    pub async fn collection_info(&self, collection: Address) -> Result<CollectionInfo, NftError> {
+        let provider = self.provider()?;
+
+        let calls = vec![
+            Call3::new(collection, ierc721::NameCall.encode()),
+            Call3::new(collection, ierc721::SymbolCall.encode()),
+            Call3::new(collection, supports_interface_calldata(ERC721_INTERFACE_ID)),
+            Call3::new(collection, supports_interface_calldata(ERC721_ENUMERABLE_INTERFACE_ID)),
+            Call3::new(collection, supports_interface_calldata(ERC1155_INTERFACE_ID)),
+        ];
+        let results = multicall::aggregate3(provider, calls, None).await?;
+
+        Ok(collection_info_from_results(&results))
+    }
+
+    /// 查询钱包持有的 ERC-721 数量 balanceOf(owner)
+    #[instrument(skip(self))]
+    pub async fn erc721_balance(&self, collection: Address, owner: Address) -> Result<U256, NftError> {
+        let provider = self.provider()?;
+
+        debug!(collection = %collection, owner = %owner, "查询 ERC-721 余额");
+
+        let result = bindings::eth_call(provider, collection, ierc721::BalanceOfCall { owner }, None).await?;
+
+        decode_return::<ierc721::BalanceOfReturn>(&result).map(|r| r.0)
+    }
+
+    /// 通过 tokenOfOwnerByIndex 列出钱包持有的前 `count` 个 token ID（需要 Enumerable 扩展）
+    /// 单个索引查询失败时跳过该索引
+    #[instrument(skip(self))]
+    pub async fn owned_token_ids(
+        &self,
+        collection: Address,
+        owner: Address,
+        count: usize,
+    ) -> Result<Vec<U256>, NftError> {
+        let provider = self.provider()?;
+
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let calls = (0..count)
+            .map(|index| {
+                let call = ierc721::TokenOfOwnerByIndexCall { owner, index: U256::from(index) };
+                Call3::new(collection, call.encode())
+            })
+            .collect();
+        let results = multicall::aggregate3(provider, calls, None).await?;
+
+        Ok(results.iter().filter_map(Call3Result::as_u256).collect())
+    }
+}
+
+/// supportsInterface(bytes4) 调用数据
+fn supports_interface_calldata(interface_id: [u8; 4]) -> Vec<u8> {
+    ierc721::SupportsInterfaceCall { interface_id }.encode()
+}
+
+/// 由 name/symbol/supportsInterface×3 五个子调用结果构建合集信息
+fn collection_info_from_results(results: &[Call3Result]) -> CollectionInfo {
+    let field = |index: usize| {
+        results
+            .get(index)
+            .filter(|result| result.success)
+            .map(|result| &result.return_data[..])
+    };
+    // 未实现 ERC-165 的合约调用失败或返回空数据，视为不支持
+    let supports = |index: usize| field(index).is_some_and(|data| data.len() == 32 && data[31] == 1);
+
+    CollectionInfo {
+        name: field(0).and_then(parse_string_return),
+        symbol: field(1).and_then(parse_string_return),
+        erc721: supports(2),
+        enumerable: supports(3),
+        erc1155: supports(4),
+    }
+}
+
+fn decode_return<R: AbiDecode>(data: &[u8]) -> Result<R, NftError> {
+    R::decode(data).map_err(|e| NftError::AbiError(format!("解码返回值失败: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::id;
+
+    fn xor_selectors(signatures: &[&str]) -> [u8; 4] {
+        signatures.iter().fold([0u8; 4], |mut acc, signature| {
+            for (byte, selector_byte) in acc.iter_mut().zip(id(signature)) {
+                *byte ^= selector_byte;
+            }
+            acc
+        })
+    }
+
+    fn success(data: Vec<u8>) -> Call3Result {
+        Call3Result { success: true, return_data: Bytes::from(data) }
+    }
+
+    #[test]
+    fn test_interface_ids() {
+        assert_eq!(
+            xor_selectors(&[
+                "balanceOf(address)",
+                "ownerOf(uint256)",
+                "safeTransferFrom(address,address,uint256,bytes)",
+                "safeTransferFrom(address,address,uint256)",
+                "transferFrom(address,address,uint256)",
+                "approve(address,uint256)",
+                "setApprovalForAll(address,bool)",
+                "getApproved(uint256)",
+                "isApprovedForAll(address,address)",
+            ]),
+            ERC721_INTERFACE_ID
+        );
+        assert_eq!(
+            xor_selectors(&["totalSupply()", "tokenOfOwnerByIndex(address,uint256)", "tokenByIndex(uint256)"]),
+            ERC721_ENUMERABLE_INTERFACE_ID
+        );
+    }
+
+    #[test]
+    fn test_collection_info_from_results() {
+        let results = vec![
+            success("Bored Ape Yacht Club".to_string().encode()),
+            success("BAYC".to_string().encode()),
+            success(true.encode()),
+            success(false.encode()),
+            success(false.encode()),
+        ];
+        let info = collection_info_from_results(&results);
+        assert_eq!(info.name.as_deref(), Some("Bored Ape Yacht Club"));
+        assert_eq!(info.symbol.as_deref(), Some("BAYC"));
+        assert!(info.erc721);
+        assert!(!info.enumerable);
+        assert!(!info.erc1155);
+
+        // 没有 name/symbol 且未实现 ERC-165 的合约
+        let failed = Call3Result { success: false, return_data: Bytes::new() };
+        let info = collection_info_from_results(&[failed.clone(), failed.clone(), success(Vec::new()), failed.clone(), failed]);
+        assert_eq!(info, CollectionInfo::default());
+    }
+}
@@ -0,0 +1,152 @@
+use serde::Serialize;
+
+/// 单次响应允许的最大 JSON 字节数，超过时缩小当前页并返回续页游标
+pub const MAX_RESPONSE_BYTES: usize = 256 * 1024;
+
+/// 分页信息(附加在列表类工具的返回结果中)
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PageInfo {
+    /// 下一页游标(没有更多数据时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// 当前页是否因响应大小限制被截断(少于请求的 limit)
+    pub truncated: bool,
+}
+
+/// 解析后的分页请求
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageRequest {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl PageRequest {
+    /// 解析 limit/cursor 参数，limit 必须在 1-max_limit 之间
+    pub fn parse(
+        limit: Option<usize>,
+        cursor: Option<&str>,
+        default_limit: usize,
+        max_limit: usize,
+    ) -> Result<Self, String> {
+        let limit = limit.unwrap_or(default_limit);
+        if limit == 0 || limit > max_limit {
+            return Err(format!(
+                "limit 参数无效: {} (必须在 1-{} 之间)",
+                limit, max_limit
+            ));
+        }
+
+        let offset = match cursor {
+            Some(cursor) => cursor
+                .parse()
+                .map_err(|_| format!("无效的 cursor: {}", cursor))?,
+            None => 0,
+        };
+
+        Ok(Self { offset, limit })
+    }
+
+    /// 从完整列表中截取当前页及之后的数据
+    pub fn window<'a, T>(&self, items: &'a [T]) -> &'a [T] {
+        &items[self.offset.min(items.len())..]
+    }
+
+    /// 构建当前页结果
+    /// `window` 为从 offset 开始的数据(可以多于 limit)，`has_more` 表示 window 之后还有数据；
+    /// 序列化结果超过 MAX_RESPONSE_BYTES 时逐步减半当前页，直到只剩一条
+    pub fn fit<T: Clone, R: Serialize>(
+        &self,
+        window: &[T],
+        has_more: bool,
+        build: impl Fn(Vec<T>, PageInfo) -> R,
+    ) -> R {
+        self.fit_within(window, has_more, MAX_RESPONSE_BYTES, build)
+    }
+
+    fn fit_within<T: Clone, R: Serialize>(
+        &self,
+        window: &[T],
+        has_more: bool,
+        max_bytes: usize,
+        build: impl Fn(Vec<T>, PageInfo) -> R,
+    ) -> R {
+        let full = self.limit.min(window.len());
+        let mut take = full;
+
+        loop {
+            let more = take < window.len() || has_more;
+            let page = PageInfo {
+                next_cursor: more.then(|| (self.offset + take).to_string()),
+                truncated: take < full,
+            };
+            let result = build(window[..take].to_vec(), page);
+
+            let size = serde_json::to_vec(&result).map(|v| v.len()).unwrap_or(0);
+            if size <= max_bytes || take <= 1 {
+                return result;
+            }
+            take /= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct TestPage {
+        items: Vec<String>,
+        page: PageInfo,
+    }
+
+    fn build(items: Vec<String>, page: PageInfo) -> TestPage {
+        TestPage { items, page }
+    }
+
+    #[test]
+    fn test_parse_page_request() {
+        let page = PageRequest::parse(None, None, 20, 100).unwrap();
+        assert_eq!(page, PageRequest { offset: 0, limit: 20 });
+
+        let page = PageRequest::parse(Some(5), Some("40"), 20, 100).unwrap();
+        assert_eq!(page, PageRequest { offset: 40, limit: 5 });
+
+        assert!(PageRequest::parse(Some(0), None, 20, 100).is_err());
+        assert!(PageRequest::parse(Some(101), None, 20, 100).is_err());
+        assert!(PageRequest::parse(None, Some("abc"), 20, 100).is_err());
+    }
+
+    #[test]
+    fn test_fit_pages_through_items() {
+        let items: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+
+        let page = PageRequest::parse(Some(2), None, 20, 100).unwrap();
+        let result = page.fit(page.window(&items), false, build);
+        assert_eq!(result.items, vec!["0", "1"]);
+        assert_eq!(result.page.next_cursor.as_deref(), Some("2"));
+        assert!(!result.page.truncated);
+
+        let page = PageRequest::parse(Some(2), Some("4"), 20, 100).unwrap();
+        let result = page.fit(page.window(&items), false, build);
+        assert_eq!(result.items, vec!["4"]);
+        assert!(result.page.next_cursor.is_none());
+
+        // 游标超出范围时返回空页
+        let page = PageRequest::parse(Some(2), Some("10"), 20, 100).unwrap();
+        let result = page.fit(page.window(&items), false, build);
+        assert!(result.items.is_empty());
+        assert!(result.page.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_fit_truncates_oversized_page() {
+        let items: Vec<String> = (0..8).map(|_| "x".repeat(100)).collect();
+        let page = PageRequest::parse(Some(8), Some("10"), 20, 100).unwrap();
+
+        let result = page.fit_within(&items, true, 450, build);
+        assert_eq!(result.items.len(), 2);
+        assert!(result.page.truncated);
+        assert_eq!(result.page.next_cursor.as_deref(), Some("12"));
+    }
+}
@@ -0,0 +1,56 @@
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::task::Poll;
+
+/// 执行 future 并捕获其中的 panic，返回 panic 消息
+/// 用于隔离工具调用：单个工具 panic 只让该次调用失败，不影响 stdio 服务器
+pub async fn catch_panic<F: Future>(future: F) -> Result<F::Output, String> {
+    let mut future = std::pin::pin!(future);
+
+    std::future::poll_fn(move |cx| {
+        match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(panic_message(payload.as_ref()))),
+        }
+    })
+    .await
+}
+
+/// 提取 panic 负载中的消息
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "未知 panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_catch_panic_returns_output() {
+        let result = catch_panic(async {
+            tokio::task::yield_now().await;
+            42
+        })
+        .await;
+        assert_eq!(result, Ok(42));
+    }
+
+    #[tokio::test]
+    async fn test_catch_panic_converts_panic() {
+        let result = catch_panic(async {
+            tokio::task::yield_now().await;
+            panic!("返回值长度异常: {}", 3);
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err(), "返回值长度异常: 3");
+    }
+}
@@ -0,0 +1,451 @@
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+/// 平均出块时间（秒），用于把时间区间换算为区块数
+pub const SECONDS_PER_BLOCK: u64 = 12;
+
+/// 台账记录方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerSide {
+    /// 买入/转入
+    Acquire,
+    /// 卖出/转出
+    Dispose,
+}
+
+impl LedgerSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Acquire => "acquire",
+            Self::Dispose => "dispose",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "acquire" => Some(Self::Acquire),
+            "dispose" => Some(Self::Dispose),
+            _ => None,
+        }
+    }
+}
+
+/// 成本台账中的一条记录（单个代币的一次变动）
+#[derive(Debug, Clone, PartialEq)]
+pub struct LedgerEntry {
+    /// 代币地址
+    pub token: String,
+    pub symbol: String,
+    pub side: LedgerSide,
+    /// 数量（已按 decimals 格式化）
+    pub amount: Decimal,
+    /// 发生时的 USD 单价
+    pub price_usd: Decimal,
+    /// 手续费和 Gas（USD）
+    pub fee_usd: Decimal,
+    pub block_number: u64,
+    /// 区块时间戳（Unix 秒）
+    pub timestamp: u64,
+    pub tx_hash: String,
+    /// 日志索引（同一交易内区分多次变动）
+    pub log_index: i64,
+    /// 记录来源（transfer / execution）
+    pub source: String,
+}
+
+/// 单个代币按平均成本法计算的持仓
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub token: String,
+    pub symbol: String,
+    /// 当前持有数量
+    pub quantity: Decimal,
+    /// 剩余持仓的总成本（USD）
+    pub cost_basis: Decimal,
+    /// 已实现盈亏（USD，仅统计 realized_since 之后的卖出）
+    pub realized_pnl: Decimal,
+    /// 超出已知持仓的卖出数量（成本未知，不计入已实现盈亏）
+    pub unmatched_disposal: Decimal,
+    /// 累计手续费和 Gas（USD）
+    pub fees_usd: Decimal,
+    /// 台账记录数量
+    pub entry_count: usize,
+}
+
+impl Position {
+    fn new(token: &str, symbol: &str) -> Self {
+        Self {
+            token: token.to_string(),
+            symbol: symbol.to_string(),
+            quantity: Decimal::ZERO,
+            cost_basis: Decimal::ZERO,
+            realized_pnl: Decimal::ZERO,
+            unmatched_disposal: Decimal::ZERO,
+            fees_usd: Decimal::ZERO,
+            entry_count: 0,
+        }
+    }
+
+    /// 平均持仓成本（USD）
+    pub fn average_cost(&self) -> Decimal {
+        if self.quantity.is_zero() {
+            Decimal::ZERO
+        } else {
+            self.cost_basis / self.quantity
+        }
+    }
+
+    /// 按给定现价计算的未实现盈亏（USD）
+    pub fn unrealized_pnl(&self, current_price_usd: Decimal) -> Decimal {
+        self.quantity * current_price_usd - self.cost_basis
+    }
+}
+
+/// 单次卖出按平均成本法计算的损益
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisposalGain {
+    pub tx_hash: String,
+    pub log_index: i64,
+    pub token: String,
+    pub symbol: String,
+    pub timestamp: u64,
+    pub amount: Decimal,
+    /// 卖出所得（扣除手续费）
+    pub proceeds_usd: Decimal,
+    /// 已匹配部分的成本
+    pub cost_basis_usd: Decimal,
+    pub gain_usd: Decimal,
+    /// 超出已知持仓的数量（成本未知）
+    pub unmatched: Decimal,
+}
+
+/// 按平均成本法汇总台账，返回各代币持仓（按代币地址排序）
+///
+/// - 买入：成本 = 数量 × 单价 + 手续费
+/// - 卖出：已实现盈亏 = 卖出数量 × 单价 - 手续费 - 卖出数量 × 平均成本
+/// - `realized_since` 之前的卖出只影响持仓，不计入已实现盈亏
+pub fn compute_positions(entries: &[LedgerEntry], realized_since: Option<u64>) -> Vec<Position> {
+    replay(entries, realized_since).0
+}
+
+/// 按平均成本法计算每次卖出的损益（按区块顺序）
+pub fn compute_disposal_gains(entries: &[LedgerEntry]) -> Vec<DisposalGain> {
+    replay(entries, None).1
+}
+
+fn replay(entries: &[LedgerEntry], realized_since: Option<u64>) -> (Vec<Position>, Vec<DisposalGain>) {
+    let mut sorted: Vec<&LedgerEntry> = entries.iter().collect();
+    sorted.sort_by_key(|e| (e.block_number, e.log_index));
+
+    let mut positions: BTreeMap<String, Position> = BTreeMap::new();
+    let mut disposals = Vec::new();
+
+    for entry in sorted {
+        let position = positions
+            .entry(entry.token.to_lowercase())
+            .or_insert_with(|| Position::new(&entry.token, &entry.symbol));
+        position.fees_usd += entry.fee_usd;
+        position.entry_count += 1;
+
+        match entry.side {
+            LedgerSide::Acquire => {
+                position.quantity += entry.amount;
+                position.cost_basis += entry.amount * entry.price_usd + entry.fee_usd;
+            }
+            LedgerSide::Dispose => {
+                let matched = entry.amount.min(position.quantity);
+                let unmatched = entry.amount - matched;
+                let cost_removed = matched * position.average_cost();
+
+                // 手续费按已匹配数量的比例分摊
+                let fee = if entry.amount.is_zero() {
+                    Decimal::ZERO
+                } else {
+                    entry.fee_usd * matched / entry.amount
+                };
+                let gain = matched * entry.price_usd - fee - cost_removed;
+
+                if realized_since.is_none_or(|since| entry.timestamp >= since) {
+                    position.realized_pnl += gain;
+                }
+
+                disposals.push(DisposalGain {
+                    tx_hash: entry.tx_hash.clone(),
+                    log_index: entry.log_index,
+                    token: entry.token.clone(),
+                    symbol: entry.symbol.clone(),
+                    timestamp: entry.timestamp,
+                    amount: entry.amount,
+                    proceeds_usd: entry.amount * entry.price_usd - entry.fee_usd,
+                    cost_basis_usd: cost_removed,
+                    gain_usd: gain,
+                    unmatched,
+                });
+
+                position.quantity -= matched;
+                position.cost_basis -= cost_removed;
+                position.unmatched_disposal += unmatched;
+            }
+        }
+    }
+
+    (positions.into_values().collect(), disposals)
+}
+
+/// 一笔已执行的交易（由执行类工具在交易确认后记录）
+/// 转账和授权没有买入的一侧，买入数量为 0
+#[derive(Debug, Clone)]
+pub struct ExecutionFill {
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub timestamp: u64,
+    pub from_token: String,
+    pub from_symbol: String,
+    /// 实际卖出数量
+    pub amount_in: Decimal,
+    pub to_token: String,
+    pub to_symbol: String,
+    /// 实际买入数量（没有买入时为 0）
+    pub amount_out: Decimal,
+    /// 成交时卖出代币的 USD 单价
+    pub from_price_usd: Option<Decimal>,
+    /// 成交时买入代币的 USD 单价
+    pub to_price_usd: Option<Decimal>,
+    /// Gas 费用（USD）
+    pub gas_fee_usd: Decimal,
+}
+
+impl ExecutionFill {
+    /// 成交的 USD 价值，优先按卖出代币计价
+    pub fn trade_value_usd(&self) -> Option<Decimal> {
+        self.from_price_usd
+            .map(|price| self.amount_in * price)
+            .or_else(|| self.to_price_usd.map(|price| self.amount_out * price))
+    }
+}
+
+/// 将成交拆分为卖出和买入两条台账记录
+/// 买入单价按实际成交价（成交价值 / 买入数量）计算，Gas 计入买入成本
+/// 没有买入时只记录卖出，按卖出代币单价计价，Gas 计入卖出手续费
+/// 无法计价时返回 None
+pub fn execution_entries(fill: &ExecutionFill) -> Option<Vec<LedgerEntry>> {
+    if fill.amount_in.is_zero() {
+        return None;
+    }
+    let value = if fill.amount_out.is_zero() {
+        fill.amount_in * fill.from_price_usd?
+    } else {
+        fill.trade_value_usd()?
+    };
+
+    let base = LedgerEntry {
+        token: fill.from_token.clone(),
+        symbol: fill.from_symbol.clone(),
+        side: LedgerSide::Dispose,
+        amount: fill.amount_in,
+        price_usd: value / fill.amount_in,
+        fee_usd: Decimal::ZERO,
+        block_number: fill.block_number,
+        timestamp: fill.timestamp,
+        tx_hash: fill.tx_hash.clone(),
+        log_index: 0,
+        source: "execution".to_string(),
+    };
+
+    if fill.amount_out.is_zero() {
+        return Some(vec![LedgerEntry {
+            fee_usd: fill.gas_fee_usd,
+            ..base
+        }]);
+    }
+
+    let acquire = LedgerEntry {
+        token: fill.to_token.clone(),
+        symbol: fill.to_symbol.clone(),
+        side: LedgerSide::Acquire,
+        amount: fill.amount_out,
+        price_usd: value / fill.amount_out,
+        fee_usd: fill.gas_fee_usd,
+        log_index: 1,
+        ..base.clone()
+    };
+
+    Some(vec![base, acquire])
+}
+
+/// 解析时间区间（如 24h、7d、30d、1y），返回秒数
+pub fn parse_period(period: &str) -> Result<u64, String> {
+    let period = period.trim().to_lowercase();
+    let invalid = || format!("无效的时间区间: {} (示例: 24h、7d、30d、1y)", period);
+
+    if period.len() < 2 {
+        return Err(invalid());
+    }
+
+    let (value, unit) = period.split_at(period.len() - 1);
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    if value == 0 {
+        return Err(invalid());
+    }
+
+    let unit_secs = match unit {
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        "y" => 365 * 86_400,
+        _ => return Err(invalid()),
+    };
+
+    Ok(value * unit_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn entry(side: LedgerSide, amount: &str, price: &str, block: u64) -> LedgerEntry {
+        LedgerEntry {
+            token: "0xToken".to_string(),
+            symbol: "TKN".to_string(),
+            side,
+            amount: Decimal::from_str(amount).unwrap(),
+            price_usd: Decimal::from_str(price).unwrap(),
+            fee_usd: Decimal::ZERO,
+            block_number: block,
+            timestamp: block * SECONDS_PER_BLOCK,
+            tx_hash: format!("0x{:x}", block),
+            log_index: 0,
+            source: "transfer".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_average_cost_positions() {
+        let entries = vec![
+            entry(LedgerSide::Acquire, "10", "100", 1),
+            entry(LedgerSide::Acquire, "10", "200", 2),
+            // 平均成本 150，以 300 卖出 5 个：已实现 (300 - 150) × 5 = 750
+            entry(LedgerSide::Dispose, "5", "300", 3),
+        ];
+
+        let positions = compute_positions(&entries, None);
+        assert_eq!(positions.len(), 1);
+
+        let position = &positions[0];
+        assert_eq!(position.quantity, Decimal::from(15));
+        assert_eq!(position.average_cost(), Decimal::from(150));
+        assert_eq!(position.realized_pnl, Decimal::from(750));
+        // 现价 200：未实现 (200 - 150) × 15 = 750
+        assert_eq!(position.unrealized_pnl(Decimal::from(200)), Decimal::from(750));
+    }
+
+    #[test]
+    fn test_fees_and_unmatched_disposal() {
+        let mut buy = entry(LedgerSide::Acquire, "2", "100", 1);
+        buy.fee_usd = Decimal::from(10);
+        // 卖出超过已知持仓：多出的 1 个成本未知
+        let mut sell = entry(LedgerSide::Dispose, "3", "150", 2);
+        sell.fee_usd = Decimal::from(3);
+
+        let positions = compute_positions(&[buy, sell], None);
+        let position = &positions[0];
+
+        // 成本 210；匹配 2 个，手续费分摊 2；已实现 300 - 2 - 210 = 88
+        assert_eq!(position.realized_pnl, Decimal::from(88));
+        assert_eq!(position.quantity, Decimal::ZERO);
+        assert_eq!(position.unmatched_disposal, Decimal::from(1));
+    }
+
+    #[test]
+    fn test_compute_disposal_gains() {
+        let entries = vec![
+            entry(LedgerSide::Acquire, "10", "100", 1),
+            entry(LedgerSide::Dispose, "4", "150", 2),
+            entry(LedgerSide::Dispose, "8", "50", 3),
+        ];
+
+        let gains = compute_disposal_gains(&entries);
+        assert_eq!(gains.len(), 2);
+        assert_eq!(gains[0].gain_usd, Decimal::from(200));
+        assert_eq!(gains[0].cost_basis_usd, Decimal::from(400));
+        // 剩余 6 个以 50 卖出：(50 - 100) × 6 = -300，多出的 2 个成本未知
+        assert_eq!(gains[1].gain_usd, Decimal::from(-300));
+        assert_eq!(gains[1].unmatched, Decimal::from(2));
+    }
+
+    #[test]
+    fn test_realized_since_filter() {
+        let entries = vec![
+            entry(LedgerSide::Acquire, "10", "100", 1),
+            entry(LedgerSide::Dispose, "5", "200", 2),
+            entry(LedgerSide::Dispose, "5", "300", 10),
+        ];
+
+        // 只统计区块 10 之后的卖出
+        let positions = compute_positions(&entries, Some(10 * SECONDS_PER_BLOCK));
+        assert_eq!(positions[0].realized_pnl, Decimal::from(1000));
+        assert_eq!(positions[0].quantity, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_execution_entries() {
+        let fill = ExecutionFill {
+            tx_hash: "0xabc".to_string(),
+            block_number: 100,
+            timestamp: 1_200,
+            from_token: "0xUSDC".to_string(),
+            from_symbol: "USDC".to_string(),
+            amount_in: Decimal::from(1000),
+            to_token: "0xUNI".to_string(),
+            to_symbol: "UNI".to_string(),
+            amount_out: Decimal::from(125),
+            from_price_usd: Some(Decimal::ONE),
+            to_price_usd: None,
+            gas_fee_usd: Decimal::from(5),
+        };
+
+        let entries = execution_entries(&fill).expect("应该能拆分成交");
+        let [dispose, acquire] = <[LedgerEntry; 2]>::try_from(entries).unwrap();
+        assert_eq!(dispose.side, LedgerSide::Dispose);
+        assert_eq!(dispose.price_usd, Decimal::ONE);
+        assert_eq!(acquire.price_usd, Decimal::from(8));
+        assert_eq!(acquire.fee_usd, Decimal::from(5));
+
+        // 平均成本 = (1000 + 5) / 125 = 8.04
+        let positions = compute_positions(&[dispose, acquire], None);
+        let uni = positions.iter().find(|p| p.symbol == "UNI").unwrap();
+        assert_eq!(uni.average_cost(), Decimal::from_str("8.04").unwrap());
+        assert_eq!(uni.fees_usd, Decimal::from(5));
+
+        // 转账：只有卖出一侧，Gas 计入卖出手续费
+        let transfer = ExecutionFill {
+            amount_out: Decimal::ZERO,
+            ..fill.clone()
+        };
+        let entries = execution_entries(&transfer).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].side, LedgerSide::Dispose);
+        assert_eq!(entries[0].price_usd, Decimal::ONE);
+        assert_eq!(entries[0].fee_usd, Decimal::from(5));
+
+        // 两侧都无法计价
+        let unpriced = ExecutionFill {
+            from_price_usd: None,
+            ..fill
+        };
+        assert!(execution_entries(&unpriced).is_none());
+        assert!(execution_entries(&ExecutionFill { amount_out: Decimal::ZERO, ..unpriced }).is_none());
+    }
+
+    #[test]
+    fn test_parse_period() {
+        assert_eq!(parse_period("24h").unwrap(), 86_400);
+        assert_eq!(parse_period("7D").unwrap(), 7 * 86_400);
+        assert_eq!(parse_period("2w").unwrap(), 14 * 86_400);
+        assert_eq!(parse_period("1y").unwrap(), 365 * 86_400);
+        assert!(parse_period("0d").is_err());
+        assert!(parse_period("30").is_err());
+        assert!(parse_period("abc").is_err());
+    }
+}
@@ -0,0 +1,448 @@
+use crate::cow::{CowClient, CowError};
+use crate::curve::{CurveClient, CurveError};
+use crate::uniswap::{UniswapError, UniswapV2Client};
+use crate::zerox::ZeroExError;
+use ethers::prelude::*;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// RFQ 报价默认有效期（秒）
+const RFQ_QUOTE_VALID_SECS: u64 = 1800;
+
+/// 报价后端返回的 Future
+pub type QuoteFuture<'a> = Pin<Box<dyn Future<Output = Result<VenueQuote, QuoteError>> + Send + 'a>>;
+
+/// 报价后端错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum QuoteError {
+    #[error("Uniswap 报价失败: {0}")]
+    Uniswap(#[from] UniswapError),
+
+    #[error("CoW 报价失败: {0}")]
+    Cow(#[from] CowError),
+
+    #[error("Curve 报价失败: {0}")]
+    Curve(#[from] CurveError),
+
+    #[error("0x 报价失败: {0}")]
+    ZeroEx(#[from] ZeroExError),
+
+    #[error("报价后端不可用: {0}")]
+    Unavailable(String),
+}
+
+/// 报价类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteKind {
+    /// 链上 AMM 路由（按当前储备量计算，成交价随区块变化，需要支付 Gas）
+    OnChain,
+    /// 链下做市商/solver 的确定报价（在有效期内按签名价格成交）
+    Firm,
+}
+
+/// 统一的报价请求（按卖出数量报价）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteRequest {
+    pub sell_token: Address,
+    pub buy_token: Address,
+    pub sell_amount: U256,
+    /// 下单地址（RFQ 后端需要，用于余额/授权检查）
+    pub owner: Address,
+}
+
+/// 统一的报价结果
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct VenueQuote {
+    pub venue: String,
+    pub kind: QuoteKind,
+    /// 扣除协议费后预计收到的买入代币数量
+    pub buy_amount: U256,
+    /// 协议费（以卖出代币计，已计入 buy_amount）
+    pub fee_amount: U256,
+    /// 价格影响（百分比，仅链上路由）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_impact: Option<f64>,
+    /// 报价有效期（unix 秒，仅确定报价）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_to: Option<u64>,
+    /// 代币路径（仅链上路由）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<Address>,
+}
+
+/// 报价后端：链上 AMM 与链下 RFQ/意图类场所实现同一接口，便于统一比较
+pub trait QuoteBackend: Send + Sync {
+    /// 场所名称
+    fn venue(&self) -> &'static str;
+
+    /// 报价类型
+    fn kind(&self) -> QuoteKind;
+
+    /// 后端当前是否可用（如 Provider 已连接、链受支持）
+    fn is_available(&self) -> bool;
+
+    /// 后端是否支持该交易对（如 Curve 只收录部分池子），不支持时跳过而不是返回错误
+    fn supports(&self, _request: &QuoteRequest) -> bool {
+        true
+    }
+
+    /// 按卖出数量获取报价
+    fn quote<'a>(&'a self, request: &'a QuoteRequest) -> QuoteFuture<'a>;
+}
+
+impl QuoteBackend for UniswapV2Client {
+    fn venue(&self) -> &'static str {
+        self.venue_id()
+    }
+
+    fn kind(&self) -> QuoteKind {
+        QuoteKind::OnChain
+    }
+
+    fn is_available(&self) -> bool {
+        UniswapV2Client::is_available(self)
+    }
+
+    fn quote<'a>(&'a self, request: &'a QuoteRequest) -> QuoteFuture<'a> {
+        Box::pin(async move {
+            let quote = self
+                .quote_swap(request.sell_token, request.buy_token, request.sell_amount)
+                .await?;
+            Ok(VenueQuote {
+                venue: self.venue().to_string(),
+                kind: self.kind(),
+                buy_amount: quote.amount_out,
+                fee_amount: U256::zero(),
+                price_impact: Some(quote.price_impact),
+                valid_to: None,
+                path: quote.path,
+            })
+        })
+    }
+}
+
+impl QuoteBackend for CowClient {
+    fn venue(&self) -> &'static str {
+        "cow"
+    }
+
+    fn kind(&self) -> QuoteKind {
+        QuoteKind::Firm
+    }
+
+    fn is_available(&self) -> bool {
+        self.base_url().is_ok()
+    }
+
+    fn quote<'a>(&'a self, request: &'a QuoteRequest) -> QuoteFuture<'a> {
+        Box::pin(async move {
+            let now = chrono::Utc::now().timestamp() as u64;
+            let valid_to = u32::try_from(now + RFQ_QUOTE_VALID_SECS)
+                .map_err(|_| QuoteError::Unavailable("报价有效期超出范围".to_string()))?;
+            let (quote, _) = CowClient::quote(
+                self,
+                request.sell_token,
+                request.buy_token,
+                request.sell_amount,
+                request.owner,
+                valid_to,
+            )
+            .await?;
+            Ok(VenueQuote {
+                venue: self.venue().to_string(),
+                kind: self.kind(),
+                buy_amount: quote.buy_amount,
+                fee_amount: quote.fee_amount,
+                price_impact: None,
+                valid_to: Some(quote.valid_to as u64),
+                path: Vec::new(),
+            })
+        })
+    }
+}
+
+impl QuoteBackend for CurveClient {
+    fn venue(&self) -> &'static str {
+        "curve"
+    }
+
+    fn kind(&self) -> QuoteKind {
+        QuoteKind::OnChain
+    }
+
+    fn is_available(&self) -> bool {
+        CurveClient::is_available(self)
+    }
+
+    fn supports(&self, request: &QuoteRequest) -> bool {
+        self.find_pool(request.sell_token, request.buy_token).is_some()
+    }
+
+    fn quote<'a>(&'a self, request: &'a QuoteRequest) -> QuoteFuture<'a> {
+        Box::pin(async move {
+            let quote = CurveClient::quote(self, request.sell_token, request.buy_token, request.sell_amount).await?;
+            Ok(VenueQuote {
+                venue: self.venue().to_string(),
+                kind: self.kind(),
+                buy_amount: quote.amount_out,
+                fee_amount: U256::zero(),
+                price_impact: None,
+                valid_to: None,
+                path: vec![request.sell_token, request.buy_token],
+            })
+        })
+    }
+}
+
+/// 聚合器 API 约定的原生代币地址
+pub const NATIVE_TOKEN_ADDRESS: &str = "0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE";
+
+/// 聚合器返回的 Future
+pub type AggregatorFuture<'a> = Pin<Box<dyn Future<Output = Result<AggregatorQuote, QuoteError>> + Send + 'a>>;
+
+/// 聚合器的可执行报价（含成交交易）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AggregatorQuote {
+    pub source: &'static str,
+    pub sell_amount: U256,
+    pub buy_amount: U256,
+    /// 保证成交的最少买入数量（已计入滑点）
+    pub min_buy_amount: U256,
+    /// 卖出代币需要授权给的地址（卖出原生代币时为空）
+    pub allowance_target: Option<Address>,
+    /// 成交交易
+    pub to: Address,
+    pub calldata: Bytes,
+    pub value: U256,
+    pub gas: Option<U256>,
+}
+
+/// DEX 聚合器：返回带成交 calldata 的确定报价，实现该接口即可接入 compare_quotes 和 swap_tokens
+pub trait AggregatorSource: Send + Sync {
+    /// 聚合器名称
+    fn name(&self) -> &'static str;
+
+    /// 聚合器当前是否可用（如已配置 API Key、链受支持）
+    fn is_available(&self) -> bool;
+
+    /// 按卖出数量获取可执行报价（`request.owner` 为成交地址）
+    fn firm_quote<'a>(&'a self, request: &'a QuoteRequest) -> AggregatorFuture<'a>;
+}
+
+/// 把聚合器注册为 compare_quotes 的报价后端
+pub struct AggregatorBackend(pub Arc<dyn AggregatorSource>);
+
+impl QuoteBackend for AggregatorBackend {
+    fn venue(&self) -> &'static str {
+        self.0.name()
+    }
+
+    fn kind(&self) -> QuoteKind {
+        QuoteKind::Firm
+    }
+
+    fn is_available(&self) -> bool {
+        self.0.is_available()
+    }
+
+    fn quote<'a>(&'a self, request: &'a QuoteRequest) -> QuoteFuture<'a> {
+        Box::pin(async move {
+            let quote = self.0.firm_quote(request).await?;
+            Ok(VenueQuote {
+                venue: quote.source.to_string(),
+                kind: self.kind(),
+                buy_amount: quote.buy_amount,
+                fee_amount: U256::zero(),
+                price_impact: None,
+                valid_to: None,
+                path: Vec::new(),
+            })
+        })
+    }
+}
+
+/// 单个后端的报价结果（失败时保留错误信息）
+#[derive(Debug)]
+pub struct BackendQuote {
+    pub venue: &'static str,
+    pub kind: QuoteKind,
+    pub result: Result<VenueQuote, QuoteError>,
+}
+
+/// 报价聚合：并发向所有可用后端请求报价
+#[derive(Clone, Default)]
+pub struct QuoteAggregator {
+    backends: Vec<Arc<dyn QuoteBackend>>,
+}
+
+impl QuoteAggregator {
+    /// 创建空的聚合器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册报价后端
+    pub fn with_backend(mut self, backend: Arc<dyn QuoteBackend>) -> Self {
+        self.backends.push(backend);
+        self
+    }
+
+    /// 并发请求所有可用后端的报价（不可用或不支持该交易对的后端直接跳过）
+    pub async fn quote_all(&self, request: &QuoteRequest) -> Vec<BackendQuote> {
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, backend) in self.backends.iter().enumerate() {
+            if !backend.is_available() {
+                debug!(venue = backend.venue(), "跳过不可用的报价后端");
+                continue;
+            }
+            if !backend.supports(request) {
+                debug!(venue = backend.venue(), "报价后端不支持该交易对");
+                continue;
+            }
+            let backend = backend.clone();
+            let request = request.clone();
+            tasks.spawn(async move {
+                let result = backend.quote(&request).await;
+                (
+                    index,
+                    BackendQuote {
+                        venue: backend.venue(),
+                        kind: backend.kind(),
+                        result,
+                    },
+                )
+            });
+        }
+
+        let mut quotes = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok(quote) => quotes.push(quote),
+                Err(e) => warn!(error = %e, "报价任务异常退出"),
+            }
+        }
+
+        // 保持注册顺序，便于结果稳定
+        quotes.sort_by_key(|(index, _)| *index);
+        quotes.into_iter().map(|(_, quote)| quote).collect()
+    }
+}
+
+/// 选出买入数量最多的报价
+pub fn best_quote<'a>(quotes: impl IntoIterator<Item = &'a VenueQuote>) -> Option<&'a VenueQuote> {
+    quotes.into_iter().fold(None, |best: Option<&VenueQuote>, quote| match best {
+        Some(current) if current.buy_amount >= quote.buy_amount => Some(current),
+        _ => Some(quote),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedBackend {
+        venue: &'static str,
+        buy_amount: Option<u64>,
+    }
+
+    /// 不支持任何交易对的后端
+    struct UnsupportedBackend;
+
+    impl QuoteBackend for UnsupportedBackend {
+        fn venue(&self) -> &'static str {
+            "unsupported"
+        }
+
+        fn kind(&self) -> QuoteKind {
+            QuoteKind::OnChain
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+
+        fn supports(&self, _request: &QuoteRequest) -> bool {
+            false
+        }
+
+        fn quote<'a>(&'a self, _request: &'a QuoteRequest) -> QuoteFuture<'a> {
+            unreachable!("不支持的交易对不应请求报价")
+        }
+    }
+
+    impl QuoteBackend for FixedBackend {
+        fn venue(&self) -> &'static str {
+            self.venue
+        }
+
+        fn kind(&self) -> QuoteKind {
+            QuoteKind::Firm
+        }
+
+        fn is_available(&self) -> bool {
+            true
+        }
+
+        fn quote<'a>(&'a self, _request: &'a QuoteRequest) -> QuoteFuture<'a> {
+            Box::pin(async move {
+                let buy_amount = self
+                    .buy_amount
+                    .ok_or_else(|| QuoteError::Unavailable(self.venue.to_string()))?;
+                Ok(VenueQuote {
+                    venue: self.venue.to_string(),
+                    kind: self.kind(),
+                    buy_amount: U256::from(buy_amount),
+                    fee_amount: U256::zero(),
+                    price_impact: None,
+                    valid_to: None,
+                    path: Vec::new(),
+                })
+            })
+        }
+    }
+
+    fn request() -> QuoteRequest {
+        QuoteRequest {
+            sell_token: Address::repeat_byte(0x01),
+            buy_token: Address::repeat_byte(0x02),
+            sell_amount: U256::exp10(18),
+            owner: Address::repeat_byte(0x03),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quote_all_keeps_order_and_errors() {
+        let aggregator = QuoteAggregator::new()
+            .with_backend(Arc::new(FixedBackend { venue: "a", buy_amount: Some(100) }))
+            .with_backend(Arc::new(FixedBackend { venue: "b", buy_amount: None }))
+            .with_backend(Arc::new(UnsupportedBackend))
+            .with_backend(Arc::new(FixedBackend { venue: "c", buy_amount: Some(120) }));
+
+        let quotes = aggregator.quote_all(&request()).await;
+        let venues: Vec<_> = quotes.iter().map(|q| q.venue).collect();
+        assert_eq!(venues, vec!["a", "b", "c"]);
+        assert!(quotes[1].result.is_err());
+
+        let best = best_quote(quotes.iter().filter_map(|q| q.result.as_ref().ok())).unwrap();
+        assert_eq!(best.venue, "c");
+    }
+
+    #[test]
+    fn test_best_quote_prefers_first_on_tie() {
+        let quote = |venue: &str, amount: u64| VenueQuote {
+            venue: venue.to_string(),
+            kind: QuoteKind::OnChain,
+            buy_amount: U256::from(amount),
+            fee_amount: U256::zero(),
+            price_impact: None,
+            valid_to: None,
+            path: Vec::new(),
+        };
+        let quotes = [quote("a", 10), quote("b", 10), quote("c", 5)];
+        assert_eq!(best_quote(&quotes).unwrap().venue, "a");
+        assert!(best_quote(&[]).is_none());
+    }
+}
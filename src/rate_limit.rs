@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 单个客户端的令牌桶
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// 按客户端区分的令牌桶限流器
+/// 每个客户端以 `per_minute / 60` 的速率补充令牌，最多累积 `burst` 个；
+/// 已补满的桶与新建的桶等价，出现新客户端时清理，避免表无限增长
+#[derive(Debug)]
+pub struct RateLimiter {
+    refill_per_sec: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// 创建限流器，`per_minute` 为 0 时返回 None（不限流）
+    pub fn new(per_minute: u32, burst: u32) -> Option<Self> {
+        if per_minute == 0 {
+            return None;
+        }
+
+        Some(Self {
+            refill_per_sec: per_minute as f64 / 60.0,
+            burst: burst.max(1) as f64,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// 为客户端消耗一个令牌，令牌不足时返回需要等待的时间
+    pub fn check(&self, client: &str) -> Result<(), Duration> {
+        self.check_at(client, Instant::now())
+    }
+
+    fn check_at(&self, client: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if !buckets.contains_key(client) {
+            buckets.retain(|_, bucket| !self.is_full(bucket, now));
+        }
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket {
+            tokens: self.burst,
+            updated_at: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.burst);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let wait = (1.0 - bucket.tokens) / self.refill_per_sec;
+            Err(Duration::from_secs_f64(wait))
+        }
+    }
+
+    /// 空闲期间补充的令牌已达到 burst
+    fn is_full(&self, bucket: &Bucket, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens + elapsed * self.refill_per_sec >= self.burst
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_when_rate_is_zero() {
+        assert!(RateLimiter::new(0, 10).is_none());
+    }
+
+    #[test]
+    fn test_burst_then_retry_after() {
+        // 每分钟 60 次 = 每秒补充 1 个令牌
+        let limiter = RateLimiter::new(60, 2).unwrap();
+        let start = Instant::now();
+
+        assert!(limiter.check_at("a", start).is_ok());
+        assert!(limiter.check_at("a", start).is_ok());
+
+        let wait = limiter.check_at("a", start).unwrap_err();
+        assert!((wait.as_secs_f64() - 1.0).abs() < 1e-6);
+
+        // 其他客户端不受影响
+        assert!(limiter.check_at("b", start).is_ok());
+
+        // 等待后令牌恢复
+        assert!(limiter.check_at("a", start + Duration::from_secs(1)).is_ok());
+        assert!(limiter.check_at("a", start + Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn test_tokens_capped_at_burst() {
+        let limiter = RateLimiter::new(60, 1).unwrap();
+        let start = Instant::now();
+
+        assert!(limiter.check_at("a", start).is_ok());
+
+        // 长时间空闲后最多只累积 burst 个令牌
+        let later = start + Duration::from_secs(600);
+        assert!(limiter.check_at("a", later).is_ok());
+        assert!(limiter.check_at("a", later).is_err());
+    }
+
+    #[test]
+    fn test_idle_buckets_pruned() {
+        let limiter = RateLimiter::new(60, 2).unwrap();
+        let start = Instant::now();
+
+        for client in ["a", "b", "c"] {
+            assert!(limiter.check_at(client, start).is_ok());
+        }
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 3);
+
+        // 未补满的桶保留，已补满的桶在新客户端出现时清理
+        assert!(limiter.check_at("a", start + Duration::from_millis(500)).is_ok());
+        assert!(limiter.check_at("d", start + Duration::from_secs(1)).is_ok());
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), 2);
+        assert!(buckets.contains_key("a") && buckets.contains_key("d"));
+    }
+}
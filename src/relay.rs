@@ -0,0 +1,328 @@
+use ethers::prelude::*;
+use ethers::types::transaction::eip712::TypedData;
+use tracing::{debug, instrument};
+
+/// Gelato Relay API 地址
+const GELATO_API_URL: &str = "https://api.gelato.digital";
+
+/// GelatoRelay1BalanceERC2771 合约地址（主网和主要 L2 相同）
+pub const GELATO_ERC2771_RELAY: &str = "0xd8253782c45a12053594b9deB72d8e8aB2Fca54c";
+
+/// callWithSyncFee 使用原生 ETH 支付手续费时的代币地址
+pub const NATIVE_FEE_TOKEN: &str = "0xEeeeeEeeeEeEeeEeEeEeeEEEeeeeEeeeeeeeEEeE";
+
+/// 中继错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum RelayError {
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("未配置 GELATO_API_KEY")]
+    ApiKeyMissing,
+
+    #[error("Gelato 返回错误 ({status}): {message}")]
+    ApiError { status: u16, message: String },
+
+    #[error("EIP-712 编码错误: {0}")]
+    TypedDataError(String),
+}
+
+/// 中继模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayMode {
+    /// 1Balance 赞助，目标合约看到的 msg.sender 是 Gelato
+    Sponsored,
+    /// 目标合约在调用中向 Gelato 支付手续费
+    SyncFee,
+    /// 1Balance 赞助 + 用户 EIP-712 签名，目标合约通过 ERC-2771 识别原始用户
+    Erc2771,
+}
+
+impl RelayMode {
+    /// 解析中继模式字符串
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.to_lowercase().as_str() {
+            "sponsored" => Ok(Self::Sponsored),
+            "sync_fee" | "syncfee" => Ok(Self::SyncFee),
+            "erc2771" => Ok(Self::Erc2771),
+            other => Err(format!(
+                "未知的中继模式: {} (支持 sponsored、sync_fee、erc2771)",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sponsored => "sponsored",
+            Self::SyncFee => "sync_fee",
+            Self::Erc2771 => "erc2771",
+        }
+    }
+}
+
+/// ERC-2771 中继请求（由用户签名 CallWithERC2771）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Erc2771Request {
+    pub chain_id: u64,
+    pub target: Address,
+    pub data: Bytes,
+    pub user: Address,
+    pub user_nonce: U256,
+    pub user_deadline: u64,
+}
+
+impl Erc2771Request {
+    /// CallWithERC2771 的 EIP-712 结构化数据
+    pub fn typed_data(&self) -> Result<TypedData, RelayError> {
+        let value = serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "version", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                "CallWithERC2771": [
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "target", "type": "address" },
+                    { "name": "data", "type": "bytes" },
+                    { "name": "user", "type": "address" },
+                    { "name": "userNonce", "type": "uint256" },
+                    { "name": "userDeadline", "type": "uint256" },
+                ],
+            },
+            "primaryType": "CallWithERC2771",
+            "domain": {
+                "name": "GelatoRelay1BalanceERC2771",
+                "version": "1",
+                "chainId": self.chain_id,
+                "verifyingContract": GELATO_ERC2771_RELAY,
+            },
+            "message": self.message(),
+        });
+
+        serde_json::from_value(value).map_err(|e| RelayError::TypedDataError(e.to_string()))
+    }
+
+    /// 签名消息字段（同时作为 API 请求体的一部分）
+    fn message(&self) -> serde_json::Value {
+        serde_json::json!({
+            "chainId": self.chain_id,
+            "target": format!("{:?}", self.target),
+            "data": self.data.to_string(),
+            "user": format!("{:?}", self.user),
+            "userNonce": self.user_nonce.to_string(),
+            "userDeadline": self.user_deadline,
+        })
+    }
+}
+
+/// 中继任务状态
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayTaskStatus {
+    pub task_id: String,
+    /// CheckPending / ExecPending / WaitingForConfirmation / ExecSuccess / ExecReverted / Cancelled
+    pub task_state: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_check_message: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskResponse {
+    task_id: String,
+}
+
+#[derive(serde::Deserialize)]
+struct StatusResponse {
+    task: RelayTaskStatus,
+}
+
+/// Gelato Relay 客户端
+#[derive(Clone)]
+pub struct GelatoRelayClient {
+    http: reqwest::Client,
+    api_key: Option<String>,
+}
+
+impl GelatoRelayClient {
+    /// 创建新的 Gelato Relay 客户端
+    pub fn new(api_key: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            api_key,
+        }
+    }
+
+    /// 检查是否配置了 1Balance 赞助 Key
+    pub fn has_api_key(&self) -> bool {
+        self.api_key.is_some()
+    }
+
+    /// 赞助调用（sponsoredCall），返回任务 ID
+    #[instrument(skip(self, data))]
+    pub async fn sponsored_call(
+        &self,
+        chain_id: u64,
+        target: Address,
+        data: &Bytes,
+    ) -> Result<String, RelayError> {
+        let api_key = self.api_key.as_deref().ok_or(RelayError::ApiKeyMissing)?;
+        self.submit(
+            "sponsored-call",
+            serde_json::json!({
+                "chainId": chain_id,
+                "target": format!("{:?}", target),
+                "data": data.to_string(),
+                "sponsorApiKey": api_key,
+            }),
+        )
+        .await
+    }
+
+    /// 同步付费调用（callWithSyncFee），目标合约需要继承 GelatoRelayContext
+    #[instrument(skip(self, data))]
+    pub async fn call_with_sync_fee(
+        &self,
+        chain_id: u64,
+        target: Address,
+        data: &Bytes,
+        fee_token: Address,
+    ) -> Result<String, RelayError> {
+        self.submit(
+            "call-with-sync-fee",
+            serde_json::json!({
+                "chainId": chain_id,
+                "target": format!("{:?}", target),
+                "data": data.to_string(),
+                "feeToken": format!("{:?}", fee_token),
+                "isRelayContext": true,
+            }),
+        )
+        .await
+    }
+
+    /// ERC-2771 赞助调用（sponsoredCallERC2771）
+    #[instrument(skip(self, request, signature))]
+    pub async fn sponsored_call_erc2771(
+        &self,
+        request: &Erc2771Request,
+        signature: &Signature,
+    ) -> Result<String, RelayError> {
+        let api_key = self.api_key.as_deref().ok_or(RelayError::ApiKeyMissing)?;
+
+        let mut body = request.message();
+        body["userSignature"] = serde_json::Value::String(format!("0x{}", signature));
+        body["sponsorApiKey"] = serde_json::Value::String(api_key.to_string());
+
+        self.submit("sponsored-call-erc2771", body).await
+    }
+
+    /// 查询中继任务状态
+    #[instrument(skip(self))]
+    pub async fn task_status(&self, task_id: &str) -> Result<RelayTaskStatus, RelayError> {
+        let url = format!("{}/tasks/status/{}", GELATO_API_URL, task_id);
+        let response = self.http.get(&url).send().await?;
+        let response: StatusResponse = Self::parse(response).await?;
+
+        debug!(task_id, state = %response.task.task_state, "获取中继任务状态");
+
+        Ok(response.task)
+    }
+
+    async fn submit(&self, endpoint: &str, body: serde_json::Value) -> Result<String, RelayError> {
+        let url = format!("{}/relays/v2/{}", GELATO_API_URL, endpoint);
+        let response = self.http.post(&url).json(&body).send().await?;
+        let response: TaskResponse = Self::parse(response).await?;
+
+        debug!(endpoint, task_id = %response.task_id, "中继任务已提交");
+
+        Ok(response.task_id)
+    }
+
+    async fn parse<T: serde::de::DeserializeOwned>(
+        response: reqwest::Response,
+    ) -> Result<T, RelayError> {
+        let status = response.status();
+        if !status.is_success() {
+            // Gelato 错误响应格式: {"message": "..."}
+            let message = response
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|v| v["message"].as_str().map(str::to_string))
+                .unwrap_or_else(|| status.to_string());
+            return Err(RelayError::ApiError {
+                status: status.as_u16(),
+                message,
+            });
+        }
+        Ok(response.json().await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::transaction::eip712::Eip712;
+
+    fn sample_request() -> Erc2771Request {
+        Erc2771Request {
+            chain_id: 1,
+            target: Address::repeat_byte(0x22),
+            data: Bytes::from(vec![0xa9, 0x05, 0x9c, 0xbb]),
+            user: Address::repeat_byte(0x11),
+            user_nonce: U256::from(3),
+            user_deadline: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_relay_mode_parse() {
+        assert_eq!(RelayMode::parse("sponsored").unwrap(), RelayMode::Sponsored);
+        assert_eq!(RelayMode::parse("SYNC_FEE").unwrap(), RelayMode::SyncFee);
+        assert_eq!(RelayMode::parse("erc2771").unwrap().as_str(), "erc2771");
+        assert!(RelayMode::parse("flashbots").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_erc2771_signature_recovers_user() {
+        let wallet: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap();
+        let request = Erc2771Request {
+            user: wallet.address(),
+            ..sample_request()
+        };
+
+        let typed = request.typed_data().unwrap();
+        let signature = wallet.sign_typed_data(&typed).await.unwrap();
+        let digest = typed.encode_eip712().unwrap();
+
+        assert_eq!(signature.recover(digest).unwrap(), wallet.address());
+
+        // 修改 nonce 会改变签名摘要
+        let other = Erc2771Request {
+            user_nonce: U256::from(4),
+            ..request
+        };
+        assert_ne!(other.typed_data().unwrap().encode_eip712().unwrap(), digest);
+    }
+
+    #[test]
+    fn test_task_status_deserializes_optional_fields() {
+        let status: StatusResponse = serde_json::from_str(
+            r#"{"task":{"chainId":1,"taskId":"0xabc","taskState":"ExecPending","creationDate":"2024-01-01"}}"#,
+        )
+        .unwrap();
+        assert_eq!(status.task.task_id, "0xabc");
+        assert_eq!(status.task.task_state, "ExecPending");
+        assert!(status.task.transaction_hash.is_none());
+    }
+}
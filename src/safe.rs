@@ -0,0 +1,577 @@
+use crate::bindings::{self, i_safe};
+use crate::diagnostics::record_rpc_call;
+use crate::eth_client::RpcProvider;
+use crate::types::checksum_address;
+use ethers::abi::{self, AbiDecode, Token};
+use ethers::prelude::*;
+use ethers::types::transaction::eip712::{EIP712Domain, TypedData};
+use ethers::utils::keccak256;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument, warn};
+
+/// Safe 交易服务请求超时时间
+const SAFE_SERVICE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 提议交易时上报的来源标识（显示在 Safe 界面的交易详情中）
+const PROPOSAL_ORIGIN: &str = "ethereum-trading-mcp-server";
+
+/// keccak256("SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,uint256 baseGas,uint256 gasPrice,address gasToken,address refundReceiver,uint256 nonce)")
+pub const SAFE_TX_TYPEHASH: &str =
+    "0xbb8310d486368db6bd6f849402fdd73ad53d316b5a4b2644ad6efe0f941286d8";
+
+/// Safe 错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum SafeError {
+    #[error("提供者错误: {0}")]
+    ProviderError(#[from] ProviderError),
+
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("未配置 {0}")]
+    NotConfigured(&'static str),
+
+    #[error("Safe 交易服务返回错误 ({status}): {message}")]
+    ServiceError { status: u16, message: String },
+
+    #[error("无效的响应: {0}")]
+    InvalidResponse(String),
+
+    #[error("签名失败: {0}")]
+    SignerError(String),
+}
+
+/// Safe 多签交易（SafeTx，operation 0 为 CALL，1 为 DELEGATECALL）
+/// 执行类工具只构建 CALL，且不使用 Gas 退款（safeTxGas、baseGas、gasPrice 均为 0）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SafeTransaction {
+    pub to: Address,
+    pub value: U256,
+    pub data: Bytes,
+    pub operation: u8,
+    pub safe_tx_gas: U256,
+    pub base_gas: U256,
+    pub gas_price: U256,
+    pub gas_token: Address,
+    pub refund_receiver: Address,
+    pub nonce: u64,
+}
+
+impl SafeTransaction {
+    /// 构建普通调用（CALL，无 Gas 退款）
+    pub fn call(to: Address, value: U256, data: Bytes, nonce: u64) -> Self {
+        Self {
+            to,
+            value,
+            data,
+            nonce,
+            ..Default::default()
+        }
+    }
+
+    /// Safe 合约的 EIP-712 域（只包含 chainId 和 verifyingContract）
+    pub fn domain(safe: Address, chain_id: u64) -> EIP712Domain {
+        EIP712Domain {
+            name: None,
+            version: None,
+            chain_id: Some(U256::from(chain_id)),
+            verifying_contract: Some(safe),
+            salt: None,
+        }
+    }
+
+    /// SafeTx 的 EIP-712 结构化数据（所有者签名时使用）
+    pub fn typed_data(&self, safe: Address, chain_id: u64) -> Result<TypedData, String> {
+        let value = serde_json::json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                "SafeTx": [
+                    { "name": "to", "type": "address" },
+                    { "name": "value", "type": "uint256" },
+                    { "name": "data", "type": "bytes" },
+                    { "name": "operation", "type": "uint8" },
+                    { "name": "safeTxGas", "type": "uint256" },
+                    { "name": "baseGas", "type": "uint256" },
+                    { "name": "gasPrice", "type": "uint256" },
+                    { "name": "gasToken", "type": "address" },
+                    { "name": "refundReceiver", "type": "address" },
+                    { "name": "nonce", "type": "uint256" },
+                ],
+            },
+            "primaryType": "SafeTx",
+            "domain": Self::domain(safe, chain_id),
+            "message": {
+                "to": format!("{:?}", self.to),
+                "value": self.value.to_string(),
+                "data": format!("{}", self.data),
+                "operation": self.operation,
+                "safeTxGas": self.safe_tx_gas.to_string(),
+                "baseGas": self.base_gas.to_string(),
+                "gasPrice": self.gas_price.to_string(),
+                "gasToken": format!("{:?}", self.gas_token),
+                "refundReceiver": format!("{:?}", self.refund_receiver),
+                "nonce": self.nonce,
+            },
+        });
+
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    /// 计算 safeTxHash（与 Safe.getTransactionHash 一致）
+    pub fn hash(&self, safe: Address, chain_id: u64) -> H256 {
+        let type_hash: H256 = SAFE_TX_TYPEHASH.parse().expect("硬编码哈希应该有效");
+        let struct_hash = keccak256(abi::encode(&[
+            Token::FixedBytes(type_hash.as_bytes().to_vec()),
+            Token::Address(self.to),
+            Token::Uint(self.value),
+            Token::FixedBytes(keccak256(&self.data).to_vec()),
+            Token::Uint(U256::from(self.operation)),
+            Token::Uint(self.safe_tx_gas),
+            Token::Uint(self.base_gas),
+            Token::Uint(self.gas_price),
+            Token::Address(self.gas_token),
+            Token::Address(self.refund_receiver),
+            Token::Uint(U256::from(self.nonce)),
+        ]));
+
+        let domain_separator = Self::domain(safe, chain_id).separator();
+        H256::from(keccak256([&[0x19, 0x01], &domain_separator[..], &struct_hash[..]].concat()))
+    }
+
+    /// 可导入 Safe 界面或交易服务的交易字段（地址为校验和格式，数值为十进制字符串）
+    pub fn payload(&self) -> SafeTransactionPayload {
+        SafeTransactionPayload {
+            to: checksum_address(self.to),
+            value: self.value.to_string(),
+            data: format!("{}", self.data),
+            operation: self.operation,
+            safe_tx_gas: self.safe_tx_gas.to_string(),
+            base_gas: self.base_gas.to_string(),
+            gas_price: self.gas_price.to_string(),
+            gas_token: checksum_address(self.gas_token),
+            refund_receiver: checksum_address(self.refund_receiver),
+            nonce: self.nonce,
+        }
+    }
+}
+
+/// Safe 交易字段（Safe Transaction Service 的 multisig-transactions 格式）
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeTransactionPayload {
+    pub to: String,
+    pub value: String,
+    pub data: String,
+    pub operation: u8,
+    pub safe_tx_gas: String,
+    pub base_gas: String,
+    pub gas_price: String,
+    pub gas_token: String,
+    pub refund_receiver: String,
+    pub nonce: u64,
+}
+
+/// 执行类工具在 Safe 模式下返回的提议结果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SafeProposal {
+    pub safe: String,
+    pub chain_id: u64,
+    /// Safe 所有者需要签名的 EIP-712 哈希
+    pub safe_tx_hash: String,
+    pub transaction: SafeTransactionPayload,
+    /// 执行所需的所有者签名数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<u64>,
+    /// 是否已签名并提交到 Safe 交易服务
+    pub proposed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proposer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proposal_error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 将签名转为 Safe 接受的 EOA 签名格式 r ‖ s ‖ v（v 为 27/28）
+/// 部分签名后端返回 EIP-155 格式的 v（chainId * 2 + 35 + recid），需要还原
+pub fn safe_signature(signature: &Signature) -> Bytes {
+    let mut signature = *signature;
+    signature.v = match signature.v {
+        0 | 1 => signature.v + 27,
+        v if v >= 35 => 27 + (v - 35) % 2,
+        v => v,
+    };
+    Bytes::from(signature.to_vec())
+}
+
+/// Safe 合约和 Safe Transaction Service 客户端
+#[derive(Clone)]
+pub struct SafeClient {
+    provider: Option<Arc<RpcProvider>>,
+    http: reqwest::Client,
+    safe: Option<Address>,
+    service_url: String,
+    api_key: Option<String>,
+    chain_id: u64,
+}
+
+impl SafeClient {
+    /// 创建 Safe 客户端（`safe` 为 None 时不启用 Safe 模式）
+    pub fn new(
+        provider: Option<Arc<RpcProvider>>,
+        safe: Option<Address>,
+        service_url: String,
+        api_key: Option<String>,
+        chain_id: u64,
+    ) -> Self {
+        Self {
+            provider,
+            http: reqwest::Client::builder()
+                .timeout(SAFE_SERVICE_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            safe,
+            service_url,
+            api_key,
+            chain_id,
+        }
+    }
+
+    /// 配置了 SAFE_ADDRESS 时，执行类工具输出 Safe 交易而不是直接广播
+    pub fn safe_address(&self) -> Option<Address> {
+        self.safe
+    }
+
+    /// 查询 Safe 合约当前的 nonce
+    #[instrument(skip(self))]
+    pub async fn nonce(&self, safe: Address) -> Result<u64, SafeError> {
+        let result = bindings::eth_call(self.provider()?, safe, i_safe::NonceCall, None).await?;
+        let nonce = decode_return::<i_safe::NonceReturn>(&result)?.0;
+        Ok(nonce.low_u64())
+    }
+
+    /// 查询执行所需的所有者签名数
+    #[instrument(skip(self))]
+    pub async fn threshold(&self, safe: Address) -> Result<u64, SafeError> {
+        let result = bindings::eth_call(self.provider()?, safe, i_safe::GetThresholdCall, None).await?;
+        let threshold = decode_return::<i_safe::GetThresholdReturn>(&result)?.0;
+        Ok(threshold.low_u64())
+    }
+
+    /// 查询 Safe 所有者列表
+    #[instrument(skip(self))]
+    pub async fn owners(&self, safe: Address) -> Result<Vec<Address>, SafeError> {
+        let result = bindings::eth_call(self.provider()?, safe, i_safe::GetOwnersCall, None).await?;
+        Ok(decode_return::<i_safe::GetOwnersReturn>(&result)?.0)
+    }
+
+    /// 交易服务中排队（未执行）交易的最大 nonce
+    #[instrument(skip(self))]
+    pub async fn queued_nonce(&self, safe: Address) -> Result<Option<u64>, SafeError> {
+        let url = format!(
+            "{}/api/v1/safes/{}/multisig-transactions/",
+            self.service_url,
+            checksum_address(safe)
+        );
+        let request = self
+            .authorize(self.http.get(&url))
+            .query(&[("executed", "false"), ("ordering", "-nonce"), ("limit", "1")]);
+
+        let started = Instant::now();
+        let response = async { request.send().await?.error_for_status()?.json().await }.await;
+        record_rpc_call("safe_multisig_transactions", None, started.elapsed(), 0, response.is_ok());
+        let response: serde_json::Value = response?;
+
+        parse_queued_nonce(&response)
+    }
+
+    /// 下一笔 Safe 交易的 nonce：链上 nonce 与交易服务中排队交易之后的 nonce 取较大值
+    /// 交易服务不可用时只使用链上 nonce
+    pub async fn next_nonce(&self, safe: Address) -> Result<u64, SafeError> {
+        let (onchain, queued) = tokio::join!(self.nonce(safe), self.queued_nonce(safe));
+        let onchain = onchain?;
+        let queued = queued
+            .inspect_err(|e| warn!(safe = %safe, error = %e, "查询排队的 Safe 交易失败,使用链上 nonce"))
+            .ok()
+            .flatten();
+        Ok(queued.map_or(onchain, |queued| onchain.max(queued + 1)))
+    }
+
+    /// 将已签名的交易提议到 Safe 交易服务，其他所有者可在 Safe 界面确认
+    #[instrument(skip(self, tx, signature))]
+    pub async fn propose(
+        &self,
+        safe: Address,
+        tx: &SafeTransaction,
+        safe_tx_hash: H256,
+        sender: Address,
+        signature: &Bytes,
+    ) -> Result<(), SafeError> {
+        let url = format!(
+            "{}/api/v1/safes/{}/multisig-transactions/",
+            self.service_url,
+            checksum_address(safe)
+        );
+        let body = proposal_body(tx, safe_tx_hash, sender, signature);
+
+        let started = Instant::now();
+        let response = self.authorize(self.http.post(&url)).json(&body).send().await;
+        record_rpc_call("safe_propose_transaction", None, started.elapsed(), 0, response.is_ok());
+
+        let response = response?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            return Err(SafeError::ServiceError {
+                status: status.as_u16(),
+                message,
+            });
+        }
+
+        debug!(safe_tx_hash = ?safe_tx_hash, "已提议 Safe 交易");
+        Ok(())
+    }
+
+    /// 构建 Safe 交易，提供签名器时签名并提议到交易服务
+    /// 提议失败时仍返回交易数据，错误记录在 proposal_error 中
+    pub async fn prepare<S: Signer>(
+        &self,
+        to: Address,
+        value: U256,
+        data: Bytes,
+        proposer: Option<&S>,
+    ) -> Result<SafeProposal, SafeError> {
+        let safe = self.safe.ok_or(SafeError::NotConfigured("SAFE_ADDRESS"))?;
+
+        let (nonce, threshold, owners) =
+            tokio::join!(self.next_nonce(safe), self.threshold(safe), self.owners(safe));
+        let nonce = nonce?;
+        let threshold = threshold
+            .inspect_err(|e| warn!(safe = %safe, error = %e, "查询 Safe 签名门限失败"))
+            .ok();
+        let owners = owners
+            .inspect_err(|e| warn!(safe = %safe, error = %e, "查询 Safe 所有者失败"))
+            .ok();
+
+        let tx = SafeTransaction::call(to, value, data, nonce);
+        let safe_tx_hash = tx.hash(safe, self.chain_id);
+
+        let mut proposal = SafeProposal {
+            safe: checksum_address(safe),
+            chain_id: self.chain_id,
+            safe_tx_hash: format!("{:?}", safe_tx_hash),
+            transaction: tx.payload(),
+            threshold,
+            proposed: false,
+            proposer: None,
+            signature: None,
+            service_url: None,
+            proposal_error: None,
+            notes: Vec::new(),
+        };
+
+        let Some(proposer) = proposer else {
+            proposal.notes.push(
+                "未签名提议,只返回 Safe 交易数据,可在 Safe 界面导入或由所有者签名 safe_tx_hash 后提议".to_string(),
+            );
+            return Ok(proposal);
+        };
+
+        let sender = proposer.address();
+        if owners.is_some_and(|owners| !owners.contains(&sender)) {
+            proposal.notes.push(format!(
+                "签名器 {} 不是 Safe 所有者,交易服务只接受已登记为代理(delegate)的提议者",
+                checksum_address(sender)
+            ));
+        }
+
+        let typed = tx
+            .typed_data(safe, self.chain_id)
+            .map_err(SafeError::SignerError)?;
+        let signature = proposer
+            .sign_typed_data(&typed)
+            .await
+            .map_err(|e| SafeError::SignerError(e.to_string()))?;
+        let signature = safe_signature(&signature);
+
+        proposal.proposer = Some(checksum_address(sender));
+        proposal.signature = Some(format!("{}", signature));
+        proposal.service_url = Some(self.service_url.clone());
+
+        match self.propose(safe, &tx, safe_tx_hash, sender, &signature).await {
+            Ok(()) => {
+                proposal.proposed = true;
+                if let Some(threshold) = threshold.filter(|threshold| *threshold > 1) {
+                    proposal
+                        .notes
+                        .push(format!("已提议,还需要其他所有者确认(门限 {} 个签名)后才能执行", threshold));
+                }
+            }
+            Err(e) => {
+                warn!(safe = %safe, error = %e, "提议 Safe 交易失败");
+                proposal.proposal_error = Some(e.to_string());
+            }
+        }
+
+        Ok(proposal)
+    }
+
+    fn provider(&self) -> Result<&RpcProvider, SafeError> {
+        self.provider
+            .as_deref()
+            .ok_or(SafeError::NotConfigured("ETHEREUM_RPC_URL"))
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.api_key {
+            Some(ref api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+}
+
+/// 提议交易的请求体（交易字段 + contractTransactionHash、sender、signature）
+fn proposal_body(tx: &SafeTransaction, safe_tx_hash: H256, sender: Address, signature: &Bytes) -> serde_json::Value {
+    let mut body = serde_json::to_value(tx.payload()).unwrap_or_default();
+    body["contractTransactionHash"] = serde_json::json!(format!("{:?}", safe_tx_hash));
+    body["sender"] = serde_json::json!(checksum_address(sender));
+    body["signature"] = serde_json::json!(format!("{}", signature));
+    body["origin"] = serde_json::json!(PROPOSAL_ORIGIN);
+    body
+}
+
+/// 解析 multisig-transactions 列表中第一条记录的 nonce（服务端可能返回数字或字符串）
+fn parse_queued_nonce(response: &serde_json::Value) -> Result<Option<u64>, SafeError> {
+    let results = response["results"]
+        .as_array()
+        .ok_or_else(|| SafeError::InvalidResponse("multisig-transactions 响应缺少 results".to_string()))?;
+
+    let Some(first) = results.first() else {
+        return Ok(None);
+    };
+    let nonce = match &first["nonce"] {
+        serde_json::Value::Number(n) => n.as_u64(),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    };
+    nonce
+        .map(Some)
+        .ok_or_else(|| SafeError::InvalidResponse(format!("无效的 nonce: {}", first["nonce"])))
+}
+
+fn decode_return<R: AbiDecode>(data: &[u8]) -> Result<R, SafeError> {
+    R::decode(data).map_err(|e| SafeError::InvalidResponse(format!("解码返回值失败: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::transaction::eip712::Eip712;
+
+    fn sample_transaction() -> SafeTransaction {
+        SafeTransaction::call(
+            "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap(),
+            U256::zero(),
+            Bytes::from(vec![0xa9, 0x05, 0x9c, 0xbb, 0x01]),
+            7,
+        )
+    }
+
+    #[test]
+    fn test_type_hashes_match_safe_contract() {
+        // Safe v1.3.0 起的 DOMAIN_SEPARATOR_TYPEHASH
+        assert_eq!(
+            format!("{:?}", H256::from(keccak256("EIP712Domain(uint256 chainId,address verifyingContract)"))),
+            "0x47e79534a245952e8b16893a336b85a3d9ea9fa8c573f3d803afb92a79469218"
+        );
+        assert_eq!(
+            H256::from(keccak256(
+                "SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,uint256 baseGas,uint256 gasPrice,address gasToken,address refundReceiver,uint256 nonce)"
+            )),
+            SAFE_TX_TYPEHASH.parse::<H256>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_hash_matches_typed_data() {
+        let safe = Address::repeat_byte(0x5a);
+        let tx = sample_transaction();
+        let hash = tx.hash(safe, 1);
+
+        let typed = tx.typed_data(safe, 1).unwrap();
+        assert_eq!(H256::from(typed.encode_eip712().unwrap()), hash);
+
+        // chainId、Safe 地址和 nonce 都参与哈希
+        assert_ne!(tx.hash(safe, 8453), hash);
+        assert_ne!(tx.hash(Address::repeat_byte(0x5b), 1), hash);
+        assert_ne!(SafeTransaction { nonce: 8, ..tx }.hash(safe, 1), hash);
+    }
+
+    #[tokio::test]
+    async fn test_signature_recovers_owner() {
+        let owner: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse()
+            .unwrap();
+        let safe = Address::repeat_byte(0x5a);
+        let tx = sample_transaction();
+
+        let signature = owner.sign_typed_data(&tx.typed_data(safe, 1).unwrap()).await.unwrap();
+        let bytes = safe_signature(&signature);
+        assert_eq!(bytes.len(), 65);
+        assert!(matches!(bytes[64], 27 | 28));
+
+        let recovered = Signature::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(recovered.recover(tx.hash(safe, 1)).unwrap(), owner.address());
+    }
+
+    #[test]
+    fn test_safe_signature_normalizes_v() {
+        let signature = |v: u64| Signature {
+            r: U256::one(),
+            s: U256::one(),
+            v,
+        };
+        assert_eq!(safe_signature(&signature(0))[64], 27);
+        assert_eq!(safe_signature(&signature(28))[64], 28);
+        // EIP-155: chainId 1 时 v 为 37 / 38
+        assert_eq!(safe_signature(&signature(37))[64], 27);
+        assert_eq!(safe_signature(&signature(38))[64], 28);
+    }
+
+    #[test]
+    fn test_proposal_body() {
+        let tx = sample_transaction();
+        let sender = Address::repeat_byte(0x11);
+        let body = proposal_body(&tx, H256::repeat_byte(0x22), sender, &Bytes::from(vec![0xab]));
+
+        assert_eq!(body["to"], "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48");
+        assert_eq!(body["value"], "0");
+        assert_eq!(body["data"], "0xa9059cbb01");
+        assert_eq!(body["safeTxGas"], "0");
+        assert_eq!(body["nonce"], 7);
+        assert_eq!(body["sender"], checksum_address(sender));
+        assert_eq!(body["signature"], "0xab");
+        assert_eq!(body["contractTransactionHash"], format!("{:?}", H256::repeat_byte(0x22)));
+    }
+
+    #[test]
+    fn test_parse_queued_nonce() {
+        let response = serde_json::json!({ "count": 0, "results": [] });
+        assert_eq!(parse_queued_nonce(&response).unwrap(), None);
+
+        let response = serde_json::json!({ "results": [{ "nonce": 12 }] });
+        assert_eq!(parse_queued_nonce(&response).unwrap(), Some(12));
+        let response = serde_json::json!({ "results": [{ "nonce": "13" }] });
+        assert_eq!(parse_queued_nonce(&response).unwrap(), Some(13));
+
+        assert!(parse_queued_nonce(&serde_json::json!({ "detail": "Not found." })).is_err());
+    }
+}
@@ -0,0 +1,328 @@
+use crate::config::SignerConfig;
+use async_trait::async_trait;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::process::Command;
+
+/// 签名后端
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignerBackend {
+    /// ETH_PRIVATE_KEY 明文私钥
+    PrivateKey,
+    /// 加密 JSON keystore 文件（密码来自 KEYSTORE_PASSWORD 或终端输入）
+    Keystore,
+    /// Ledger 硬件钱包（需要启用 ledger feature 编译）
+    Ledger,
+    /// AWS KMS 托管的 secp256k1 密钥（需要启用 aws-kms feature 编译）
+    AwsKms,
+}
+
+impl SignerBackend {
+    pub const ALL: [SignerBackend; 4] = [Self::PrivateKey, Self::Keystore, Self::Ledger, Self::AwsKms];
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|backend| backend.as_str() == value.to_lowercase())
+            .ok_or_else(|| {
+                let supported: Vec<&str> = Self::ALL.iter().map(|backend| backend.as_str()).collect();
+                format!("未知的签名后端 {}，支持: {}", value, supported.join(", "))
+            })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PrivateKey => "private_key",
+            Self::Keystore => "keystore",
+            Self::Ledger => "ledger",
+            Self::AwsKms => "aws_kms",
+        }
+    }
+
+    /// 编译该后端需要的 cargo feature（默认构建已包含的后端为 None）
+    pub fn required_feature(&self) -> Option<&'static str> {
+        match self {
+            Self::Ledger if !cfg!(feature = "ledger") => Some("ledger"),
+            Self::AwsKms if !cfg!(feature = "aws-kms") => Some("aws-kms"),
+            _ => None,
+        }
+    }
+}
+
+/// 签名器错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum SignerError {
+    #[error("签名器配置无效: {0}")]
+    Config(String),
+
+    #[error("加载签名器失败: {0}")]
+    Load(String),
+
+    #[error(transparent)]
+    Wallet(#[from] WalletError),
+
+    #[cfg(feature = "ledger")]
+    #[error("Ledger 错误: {0}")]
+    Ledger(#[from] LedgerError),
+
+    #[cfg(feature = "aws-kms")]
+    #[error("AWS KMS 错误: {0}")]
+    Aws(#[from] AwsSignerError),
+}
+
+/// 执行类工具使用的签名器，按 SIGNER_BACKEND 选择具体实现
+#[derive(Debug)]
+pub enum TxSigner {
+    /// 明文私钥或解密后的 keystore
+    Local(LocalWallet),
+    #[cfg(feature = "ledger")]
+    Ledger(Ledger),
+    #[cfg(feature = "aws-kms")]
+    AwsKms(AwsSigner),
+}
+
+/// 将调用分派到具体的签名器，并统一错误类型
+macro_rules! dispatch {
+    ($self:expr, $signer:ident => $call:expr) => {
+        match $self {
+            TxSigner::Local($signer) => $call.map_err(SignerError::from),
+            #[cfg(feature = "ledger")]
+            TxSigner::Ledger($signer) => $call.map_err(SignerError::from),
+            #[cfg(feature = "aws-kms")]
+            TxSigner::AwsKms($signer) => $call.map_err(SignerError::from),
+        }
+    };
+}
+
+#[async_trait]
+impl Signer for TxSigner {
+    type Error = SignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(&self, message: S) -> Result<Signature, Self::Error> {
+        dispatch!(self, signer => signer.sign_message(message).await)
+    }
+
+    async fn sign_transaction(&self, message: &TypedTransaction) -> Result<Signature, Self::Error> {
+        dispatch!(self, signer => signer.sign_transaction(message).await)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(&self, payload: &T) -> Result<Signature, Self::Error> {
+        dispatch!(self, signer => signer.sign_typed_data(payload).await)
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            Self::Local(signer) => signer.address(),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(signer) => signer.address(),
+            #[cfg(feature = "aws-kms")]
+            Self::AwsKms(signer) => signer.address(),
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            Self::Local(signer) => signer.chain_id(),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(signer) => signer.chain_id(),
+            #[cfg(feature = "aws-kms")]
+            Self::AwsKms(signer) => signer.chain_id(),
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            Self::Local(signer) => Self::Local(signer.with_chain_id(chain_id)),
+            #[cfg(feature = "ledger")]
+            Self::Ledger(signer) => Self::Ledger(signer.with_chain_id(chain_id)),
+            #[cfg(feature = "aws-kms")]
+            Self::AwsKms(signer) => Self::AwsKms(signer.with_chain_id(chain_id)),
+        }
+    }
+}
+
+/// 按配置加载签名器
+///
+/// private_key 后端未配置 ETH_PRIVATE_KEY 时返回 None（只读模式）；
+/// 其他后端配置不完整或加载失败时返回错误，避免以错误的账户启动
+pub async fn load_signer(
+    config: &SignerConfig,
+    private_key: Option<&str>,
+    chain_id: u64,
+) -> Result<Option<TxSigner>, SignerError> {
+    let backend = SignerBackend::parse(&config.backend).map_err(SignerError::Config)?;
+    if let Some(feature) = backend.required_feature() {
+        return Err(SignerError::Config(format!(
+            "{} 后端需要使用 --features {} 编译",
+            backend.as_str(),
+            feature
+        )));
+    }
+
+    let signer = match backend {
+        SignerBackend::PrivateKey => {
+            let Some(key) = private_key else {
+                return Ok(None);
+            };
+            let wallet: LocalWallet = key
+                .parse()
+                .map_err(|_| SignerError::Config("ETH_PRIVATE_KEY 无效".to_string()))?;
+            TxSigner::Local(wallet)
+        }
+        SignerBackend::Keystore => TxSigner::Local(load_keystore(config)?),
+        SignerBackend::Ledger => load_ledger(config, chain_id).await?,
+        SignerBackend::AwsKms => load_aws_kms(config, chain_id).await?,
+    };
+
+    Ok(Some(signer.with_chain_id(chain_id)))
+}
+
+fn load_keystore(config: &SignerConfig) -> Result<LocalWallet, SignerError> {
+    let path = config
+        .keystore_path
+        .as_deref()
+        .ok_or_else(|| SignerError::Config("keystore 后端需要配置 KEYSTORE_PATH".to_string()))?;
+    let password = match config.keystore_password.as_deref() {
+        Some(password) => password.to_string(),
+        None => prompt_password(&format!("🔐 请输入 keystore 密码 ({}): ", path))?,
+    };
+
+    LocalWallet::decrypt_keystore(path, password)
+        .map_err(|e| SignerError::Load(format!("解密 keystore 失败（密码错误或文件损坏）: {}", e)))
+}
+
+#[cfg(feature = "ledger")]
+async fn load_ledger(config: &SignerConfig, chain_id: u64) -> Result<TxSigner, SignerError> {
+    let path = match config.ledger_hd_path.as_deref() {
+        Some(path) => HDPath::Other(path.to_string()),
+        None => HDPath::LedgerLive(config.ledger_account_index),
+    };
+    eprintln!("🔌 正在连接 Ledger（请解锁设备并打开 Ethereum 应用）...");
+    Ok(TxSigner::Ledger(Ledger::new(path, chain_id).await?))
+}
+
+#[cfg(not(feature = "ledger"))]
+async fn load_ledger(_config: &SignerConfig, _chain_id: u64) -> Result<TxSigner, SignerError> {
+    unreachable!("未启用 ledger feature 时在加载前已拒绝")
+}
+
+#[cfg(feature = "aws-kms")]
+async fn load_aws_kms(config: &SignerConfig, chain_id: u64) -> Result<TxSigner, SignerError> {
+    let key_id = config
+        .aws_kms_key_id
+        .as_deref()
+        .ok_or_else(|| SignerError::Config("aws_kms 后端需要配置 AWS_KMS_KEY_ID".to_string()))?;
+    // 区域和凭证使用 AWS 标准环境变量（AWS_REGION、AWS_ACCESS_KEY_ID 等）或实例角色
+    let client = rusoto_kms::KmsClient::new(rusoto_core::Region::default());
+    Ok(TxSigner::AwsKms(AwsSigner::new(client, key_id, chain_id).await?))
+}
+
+#[cfg(not(feature = "aws-kms"))]
+async fn load_aws_kms(_config: &SignerConfig, _chain_id: u64) -> Result<TxSigner, SignerError> {
+    unreachable!("未启用 aws-kms feature 时在加载前已拒绝")
+}
+
+/// 在终端提示输入密码
+/// stdio 传输模式下标准输入被 MCP 协议占用，因此直接读写 /dev/tty，并在输入期间关闭回显
+fn prompt_password(prompt: &str) -> Result<String, SignerError> {
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .map_err(|_| {
+            SignerError::Config("未配置 KEYSTORE_PASSWORD，且当前没有可输入密码的终端".to_string())
+        })?;
+
+    let set_echo = |tty: &File, enabled: bool| {
+        if let Ok(stdin) = tty.try_clone() {
+            let _ = Command::new("stty").arg(if enabled { "echo" } else { "-echo" }).stdin(stdin).status();
+        }
+    };
+
+    let _ = tty.write_all(prompt.as_bytes());
+    let _ = tty.flush();
+    set_echo(&tty, false);
+    let mut line = String::new();
+    let result = tty.try_clone().and_then(|input| BufReader::new(input).read_line(&mut line));
+    set_echo(&tty, true);
+    let _ = tty.write_all(b"\n");
+
+    result.map_err(|e| SignerError::Load(format!("读取密码失败: {}", e)))?;
+    Ok(trim_line_ending(&line).to_string())
+}
+
+fn trim_line_ending(line: &str) -> &str {
+    line.trim_end_matches(['\r', '\n'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    fn signer_config(backend: &str) -> SignerConfig {
+        SignerConfig {
+            backend: backend.to_string(),
+            keystore_path: None,
+            keystore_password: None,
+            ledger_account_index: 0,
+            ledger_hd_path: None,
+            aws_kms_key_id: None,
+            address: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!(SignerBackend::parse("private_key").unwrap(), SignerBackend::PrivateKey);
+        assert_eq!(SignerBackend::parse("Keystore").unwrap(), SignerBackend::Keystore);
+        assert_eq!(SignerBackend::parse("aws_kms").unwrap(), SignerBackend::AwsKms);
+        assert!(SignerBackend::parse("trezor").is_err());
+        assert_eq!(SignerBackend::Keystore.required_feature(), None);
+    }
+
+    #[test]
+    fn test_trim_line_ending() {
+        assert_eq!(trim_line_ending("secret\r\n"), "secret");
+        assert_eq!(trim_line_ending(" pass word \n"), " pass word ");
+    }
+
+    #[tokio::test]
+    async fn test_load_private_key() {
+        let config = signer_config("private_key");
+        assert!(load_signer(&config, None, 1).await.unwrap().is_none());
+        assert!(load_signer(&config, Some("0x1234"), 1).await.is_err());
+
+        let signer = load_signer(&config, Some(TEST_KEY), 8453).await.unwrap().unwrap();
+        let wallet: LocalWallet = TEST_KEY.parse().unwrap();
+        assert_eq!(signer.address(), wallet.address());
+        assert_eq!(signer.chain_id(), 8453);
+    }
+
+    #[tokio::test]
+    async fn test_load_keystore() {
+        let dir = std::env::temp_dir().join(format!("signer-keystore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (wallet, name) =
+            LocalWallet::new_keystore(&dir, &mut ethers::core::rand::thread_rng(), "correct horse", None).unwrap();
+
+        let mut config = signer_config("keystore");
+        config.keystore_path = Some(dir.join(&name).to_string_lossy().to_string());
+        config.keystore_password = Some("correct horse".to_string());
+        let signer = load_signer(&config, None, 1).await.unwrap().unwrap();
+        assert_eq!(signer.address(), wallet.address());
+
+        // 签名结果与原私钥一致
+        let signature = signer.sign_message("hello").await.unwrap();
+        assert_eq!(signature.recover("hello").unwrap(), wallet.address());
+
+        config.keystore_password = Some("wrong".to_string());
+        assert!(matches!(load_signer(&config, None, 1).await, Err(SignerError::Load(_))));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
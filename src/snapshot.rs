@@ -0,0 +1,269 @@
+use crate::token_registry::TokenRegistry;
+use crate::types::TokenInfo;
+use crate::uniswap::PathReserves;
+use ethers::prelude::*;
+use std::sync::{Arc, RwLock};
+
+/// 市场快照错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum SnapshotError {
+    #[error("读取快照文件失败: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("解析快照失败: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("快照中没有交易对: {0}")]
+    PairNotFound(String),
+
+    #[error("快照数据无效: {0}")]
+    InvalidData(String),
+}
+
+/// 某个区块的市场快照(交易对储备量和代币元数据)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MarketSnapshot {
+    /// 快照对应的区块号
+    pub block_number: u64,
+    pub chain_id: u64,
+    /// 导出时间(Unix 秒)
+    pub exported_at: i64,
+    pub tokens: Vec<TokenInfo>,
+    pub pairs: Vec<PairSnapshot>,
+    /// 导出时的代币价格(仅供参考,离线报价始终基于储备量重新计算)
+    #[serde(default)]
+    pub prices: Vec<SnapshotPrice>,
+}
+
+/// 快照区块的代币价格
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotPrice {
+    pub token: String,
+    pub symbol: String,
+    pub price_eth: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_usd: Option<String>,
+}
+
+/// 单个交易对的储备量快照
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PairSnapshot {
+    pub pair: String,
+    pub token0: String,
+    pub token1: String,
+    /// token0 原始储备量(最小单位)
+    pub reserve0: String,
+    /// token1 原始储备量(最小单位)
+    pub reserve1: String,
+}
+
+impl PairSnapshot {
+    fn parse(&self) -> Result<(Address, Address, Address, U256, U256), SnapshotError> {
+        let address = |s: &str| {
+            s.parse::<Address>()
+                .map_err(|_| SnapshotError::InvalidData(format!("无效的地址: {}", s)))
+        };
+        let amount = |s: &str| {
+            U256::from_dec_str(s)
+                .map_err(|_| SnapshotError::InvalidData(format!("无效的储备量: {}", s)))
+        };
+
+        Ok((
+            address(&self.pair)?,
+            address(&self.token0)?,
+            address(&self.token1)?,
+            amount(&self.reserve0)?,
+            amount(&self.reserve1)?,
+        ))
+    }
+}
+
+impl MarketSnapshot {
+    /// 从 JSON 文件加载快照
+    pub fn load(path: &str) -> Result<Self, SnapshotError> {
+        let content = std::fs::read_to_string(path)?;
+        let snapshot: Self = serde_json::from_str(&content)?;
+
+        // 提前校验所有交易对，避免报价时才发现数据损坏
+        for pair in &snapshot.pairs {
+            pair.parse()?;
+        }
+
+        Ok(snapshot)
+    }
+
+    /// 保存快照到 JSON 文件
+    pub fn save(&self, path: &str) -> Result<(), SnapshotError> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// 按符号或地址查找快照中的代币，找不到时使用注册表中的已知代币
+    pub fn resolve_token(&self, token_registry: &TokenRegistry, query: &str) -> Option<TokenInfo> {
+        let query = query.trim();
+        self.tokens
+            .iter()
+            .find(|t| t.symbol.eq_ignore_ascii_case(query) || t.address.eq_ignore_ascii_case(query))
+            .cloned()
+            .or_else(|| token_registry.resolve(query).ok().filter(|t| t.symbol != "UNKNOWN"))
+    }
+
+    /// 查询两个代币的交易对，返回 (pair, reserve_a, reserve_b)
+    pub fn reserves(
+        &self,
+        token_a: Address,
+        token_b: Address,
+    ) -> Result<(Address, U256, U256), SnapshotError> {
+        for pair in &self.pairs {
+            let (pair_addr, token0, token1, reserve0, reserve1) = pair.parse()?;
+            if token0 == token_a && token1 == token_b {
+                return Ok((pair_addr, reserve0, reserve1));
+            }
+            if token0 == token_b && token1 == token_a {
+                return Ok((pair_addr, reserve1, reserve0));
+            }
+        }
+
+        Err(SnapshotError::PairNotFound(format!("{:?}/{:?}", token_a, token_b)))
+    }
+
+    /// 查询路径上每一跳的 (reserve_in, reserve_out) 和交易对地址
+    pub fn path_reserves(&self, path: &[Address]) -> Result<PathReserves, SnapshotError> {
+        let mut reserves = Vec::with_capacity(path.len().saturating_sub(1));
+        let mut pairs = Vec::with_capacity(path.len().saturating_sub(1));
+
+        for hop in path.windows(2) {
+            let (pair, reserve_in, reserve_out) = self.reserves(hop[0], hop[1])?;
+            reserves.push((reserve_in, reserve_out));
+            pairs.push(pair);
+        }
+
+        Ok((reserves, pairs))
+    }
+}
+
+/// 当前加载的离线快照
+/// 加载了快照时报价类工具进入离线模式，完全基于快照数据计算
+#[derive(Default)]
+pub struct SnapshotStore {
+    current: RwLock<Option<Arc<MarketSnapshot>>>,
+}
+
+impl SnapshotStore {
+    pub fn new(snapshot: Option<MarketSnapshot>) -> Self {
+        Self {
+            current: RwLock::new(snapshot.map(Arc::new)),
+        }
+    }
+
+    /// 当前快照(未加载时为 None)
+    pub fn current(&self) -> Option<Arc<MarketSnapshot>> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// 替换当前快照，返回之前的快照
+    pub fn replace(&self, snapshot: MarketSnapshot) -> Option<Arc<MarketSnapshot>> {
+        self.current
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(Arc::new(snapshot))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+    pub(crate) const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    /// 测试用快照：1 个 USDC/WETH 池子(100 WETH / 300000 USDC)
+    pub(crate) fn sample_snapshot() -> MarketSnapshot {
+        MarketSnapshot {
+            block_number: 19_000_000,
+            chain_id: 1,
+            exported_at: 1_700_000_000,
+            tokens: vec![TokenInfo {
+                symbol: "USDC".to_string(),
+                name: "USD Coin".to_string(),
+                address: USDC.to_string(),
+                decimals: 6,
+                listed_on: Vec::new(),
+            }],
+            pairs: vec![PairSnapshot {
+                pair: "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc".to_string(),
+                token0: USDC.to_string(),
+                token1: WETH.to_string(),
+                reserve0: "300000000000".to_string(),
+                reserve1: "100000000000000000000".to_string(),
+            }],
+            prices: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_reserves_orientation() {
+        let snapshot = sample_snapshot();
+        let usdc: Address = USDC.parse().unwrap();
+        let weth: Address = WETH.parse().unwrap();
+
+        let (_, usdc_reserve, weth_reserve) = snapshot.reserves(usdc, weth).unwrap();
+        assert_eq!(usdc_reserve, U256::from(300_000_000_000u64));
+        assert_eq!(weth_reserve, U256::exp10(20));
+
+        let (reserves, pairs) = snapshot.path_reserves(&[weth, usdc]).unwrap();
+        assert_eq!(reserves, vec![(U256::exp10(20), U256::from(300_000_000_000u64))]);
+        assert_eq!(pairs.len(), 1);
+
+        assert!(matches!(
+            snapshot.reserves(usdc, Address::zero()),
+            Err(SnapshotError::PairNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_resolve_token() {
+        let snapshot = sample_snapshot();
+        let registry = TokenRegistry::new();
+
+        assert_eq!(snapshot.resolve_token(&registry, "usdc").unwrap().decimals, 6);
+        // 快照中没有但注册表已知的代币
+        assert_eq!(snapshot.resolve_token(&registry, "WETH").unwrap().address, WETH);
+        // 未知地址没有元数据
+        assert!(snapshot
+            .resolve_token(&registry, "0x0000000000000000000000000000000000000001")
+            .is_none());
+    }
+
+    #[test]
+    fn test_load_snapshot_file() {
+        let path = std::env::temp_dir().join(format!("snapshot-test-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        sample_snapshot().save(path).unwrap();
+
+        let snapshot = MarketSnapshot::load(path).unwrap();
+        assert_eq!(snapshot.block_number, 19_000_000);
+        assert_eq!(snapshot.pairs.len(), 1);
+        std::fs::remove_file(path).ok();
+
+        assert!(MarketSnapshot::load("/nonexistent/snapshot.json").is_err());
+    }
+
+    #[test]
+    fn test_snapshot_store_replace() {
+        let store = SnapshotStore::new(None);
+        assert!(store.current().is_none());
+
+        assert!(store.replace(sample_snapshot()).is_none());
+        let mut newer = sample_snapshot();
+        newer.block_number += 1;
+        let previous = store.replace(newer).unwrap();
+
+        assert_eq!(previous.block_number, 19_000_000);
+        assert_eq!(store.current().unwrap().block_number, 19_000_001);
+    }
+}
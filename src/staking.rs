@@ -0,0 +1,255 @@
+use crate::eth_client::RpcProvider;
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+/// Lido stETH 合约地址（主网）
+const LIDO_STETH_ADDRESS: &str = "0xae7ab96520DE3A18E5e111B5EaAb095312D7fE84";
+
+/// 查询 TokenRebased 事件时回溯的区块数（Lido 每日报告一次，约 7200 区块）
+const REBASE_LOOKBACK_BLOCKS: u64 = 14_400;
+
+/// 一年的秒数
+const SECONDS_PER_YEAR: f64 = 31_536_000.0;
+
+/// 验证者的有效余额（ETH），用于估算总质押量
+const VALIDATOR_EFFECTIVE_BALANCE_ETH: f64 = 32.0;
+
+/// 质押收益查询错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum StakingError {
+    #[error("提供者错误: {0}")]
+    ProviderError(#[from] ProviderError),
+
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("Provider 不可用")]
+    ProviderUnavailable,
+
+    #[error("未配置 Beacon API")]
+    BeaconApiUnavailable,
+
+    #[error("未找到 Lido 报告事件")]
+    NoRebaseEvent,
+
+    #[error("ABI 编码/解码错误: {0}")]
+    AbiError(String),
+}
+
+/// Lido 最近一次报告计算出的 APR
+#[derive(Debug, Clone)]
+pub struct LidoApr {
+    pub apr_percent: f64,
+    pub report_timestamp: u64,
+    pub time_elapsed: u64,
+    pub block_number: u64,
+}
+
+/// 基于 Beacon API 的共识层收益估算
+#[derive(Debug, Clone)]
+pub struct BeaconAprEstimate {
+    pub active_validators: u64,
+    pub total_staked_eth: f64,
+    pub consensus_apr_percent: f64,
+}
+
+/// 质押收益客户端
+#[derive(Clone)]
+pub struct StakingClient {
+    provider: Option<Arc<RpcProvider>>,
+    http: reqwest::Client,
+    beacon_api_url: Option<String>,
+}
+
+impl StakingClient {
+    /// 创建新的质押收益客户端
+    pub fn new(provider: Option<Arc<RpcProvider>>, beacon_api_url: Option<String>) -> Self {
+        Self {
+            provider,
+            http: reqwest::Client::new(),
+            beacon_api_url,
+        }
+    }
+
+    /// 检查是否配置了 Beacon API
+    pub fn has_beacon_api(&self) -> bool {
+        self.beacon_api_url.is_some()
+    }
+
+    /// 从最近的 Lido TokenRebased 事件计算 stETH APR
+    /// TokenRebased(uint256 indexed reportTimestamp, uint256 timeElapsed, uint256 preTotalShares,
+    ///              uint256 preTotalEther, uint256 postTotalShares, uint256 postTotalEther,
+    ///              uint256 sharesMintedAsFees)
+    #[instrument(skip(self))]
+    pub async fn lido_apr(&self) -> Result<LidoApr, StakingError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(StakingError::ProviderUnavailable)?;
+
+        let lido: Address = LIDO_STETH_ADDRESS.parse().expect("硬编码地址应该有效");
+        let topic = H256::from(keccak256(
+            "TokenRebased(uint256,uint256,uint256,uint256,uint256,uint256,uint256)",
+        ));
+
+        let latest = provider.get_block_number().await?.as_u64();
+        let filter = Filter::new()
+            .address(lido)
+            .topic0(topic)
+            .from_block(latest.saturating_sub(REBASE_LOOKBACK_BLOCKS))
+            .to_block(latest);
+
+        let logs = provider.get_logs(&filter).await?;
+        let log = logs.last().ok_or(StakingError::NoRebaseEvent)?;
+
+        debug!(events = logs.len(), "获取到 Lido TokenRebased 事件");
+
+        if log.data.len() < 32 * 6 || log.topics.len() < 2 {
+            return Err(StakingError::AbiError(format!(
+                "TokenRebased 事件数据长度异常: {} 字节",
+                log.data.len()
+            )));
+        }
+
+        let word = |i: usize| U256::from_big_endian(&log.data[i * 32..(i + 1) * 32]);
+        let time_elapsed = word(0);
+        let apr_percent = calculate_lido_apr(time_elapsed, word(1), word(2), word(3), word(4))
+            .ok_or_else(|| StakingError::AbiError("TokenRebased 事件数据无效".to_string()))?;
+
+        Ok(LidoApr {
+            apr_percent,
+            report_timestamp: U256::from_big_endian(log.topics[1].as_bytes()).as_u64(),
+            time_elapsed: time_elapsed.as_u64(),
+            block_number: log.block_number.map(|b| b.as_u64()).unwrap_or_default(),
+        })
+    }
+
+    /// 基于当前 epoch 的活跃验证者数量估算共识层 APR
+    #[instrument(skip(self))]
+    pub async fn beacon_apr_estimate(&self) -> Result<BeaconAprEstimate, StakingError> {
+        let base_url = self
+            .beacon_api_url
+            .as_ref()
+            .ok_or(StakingError::BeaconApiUnavailable)?;
+
+        // committees 覆盖当前 epoch 的全部活跃验证者
+        let url = format!(
+            "{}/eth/v1/beacon/states/head/committees",
+            base_url.trim_end_matches('/')
+        );
+        let response: serde_json::Value = self
+            .http
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let active_validators = response["data"]
+            .as_array()
+            .map(|committees| {
+                committees
+                    .iter()
+                    .filter_map(|c| c["validators"].as_array())
+                    .map(|v| v.len() as u64)
+                    .sum::<u64>()
+            })
+            .ok_or_else(|| StakingError::AbiError("无法解析 committees 响应".to_string()))?;
+
+        let total_staked_eth = active_validators as f64 * VALIDATOR_EFFECTIVE_BALANCE_ETH;
+
+        debug!(active_validators, total_staked_eth, "获取到活跃验证者数量");
+
+        Ok(BeaconAprEstimate {
+            active_validators,
+            total_staked_eth,
+            consensus_apr_percent: estimate_consensus_apr(total_staked_eth),
+        })
+    }
+}
+
+/// 根据报告前后的 share rate 计算年化收益（百分比）
+/// APR = (postRate - preRate) / preRate * 一年秒数 / timeElapsed
+fn calculate_lido_apr(
+    time_elapsed: U256,
+    pre_total_shares: U256,
+    pre_total_ether: U256,
+    post_total_shares: U256,
+    post_total_ether: U256,
+) -> Option<f64> {
+    if time_elapsed.is_zero() || pre_total_shares.is_zero() || post_total_shares.is_zero() {
+        return None;
+    }
+
+    // share rate 放大 10^27 保持精度
+    let scale = U256::exp10(27);
+    let pre_rate = pre_total_ether.checked_mul(scale)? / pre_total_shares;
+    let post_rate = post_total_ether.checked_mul(scale)? / post_total_shares;
+
+    if pre_rate.is_zero() {
+        return None;
+    }
+
+    let pre = pre_rate.to_string().parse::<f64>().ok()?;
+    let post = post_rate.to_string().parse::<f64>().ok()?;
+    let elapsed = time_elapsed.as_u64() as f64;
+
+    Some((post - pre) / pre * SECONDS_PER_YEAR / elapsed * 100.0)
+}
+
+/// 共识层年化收益近似公式：APR ≈ 2.6 × 64 / √(总质押 ETH)
+fn estimate_consensus_apr(total_staked_eth: f64) -> f64 {
+    if total_staked_eth <= 0.0 {
+        return 0.0;
+    }
+    2.6 * 64.0 / total_staked_eth.sqrt() * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_lido_apr() {
+        // share rate 从 1.0 增长到 1.0001，间隔 1 天 => 约 3.65% APR
+        let shares = U256::exp10(24);
+        let pre_ether = U256::exp10(24);
+        let post_ether = U256::exp10(24) + U256::exp10(20);
+        let apr = calculate_lido_apr(U256::from(86_400), shares, pre_ether, shares, post_ether)
+            .unwrap();
+        assert!((apr - 3.65).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_calculate_lido_apr_invalid() {
+        assert!(calculate_lido_apr(U256::zero(), U256::one(), U256::one(), U256::one(), U256::one())
+            .is_none());
+        assert!(calculate_lido_apr(U256::one(), U256::zero(), U256::one(), U256::one(), U256::one())
+            .is_none());
+    }
+
+    #[test]
+    fn test_estimate_consensus_apr() {
+        // 约 3400 万 ETH 质押时共识层 APR 约 2.85%
+        let apr = estimate_consensus_apr(34_000_000.0);
+        assert!((apr - 2.85).abs() < 0.05);
+        assert_eq!(estimate_consensus_apr(0.0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_staking_client_without_provider() {
+        let client = StakingClient::new(None, None);
+        assert!(!client.has_beacon_api());
+        assert!(matches!(
+            client.lido_apr().await,
+            Err(StakingError::ProviderUnavailable)
+        ));
+        assert!(matches!(
+            client.beacon_apr_estimate().await,
+            Err(StakingError::BeaconApiUnavailable)
+        ));
+    }
+}
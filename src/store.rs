@@ -0,0 +1,592 @@
+use crate::pnl::{LedgerEntry, LedgerSide};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tracing::{debug, info, instrument, warn};
+
+/// 持久化错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("数据库错误: {0}")]
+    DatabaseError(#[from] rusqlite::Error),
+
+    #[error("序列化错误: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("持久化未启用")]
+    Disabled,
+}
+
+/// 记录类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    /// 价格/交换报价
+    Quote,
+    /// 交易模拟
+    Simulation,
+    /// 真实执行并成功确认的交易
+    Execution,
+    /// 未成功确认的执行（Safe 提议、回滚或等待回执超时）
+    Submission,
+}
+
+impl RecordKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Quote => "quote",
+            Self::Simulation => "simulation",
+            Self::Execution => "execution",
+            Self::Submission => "submission",
+        }
+    }
+
+    /// 解析记录类型（不区分大小写）
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.to_lowercase().as_str() {
+            "quote" | "quotes" => Ok(Self::Quote),
+            "simulation" | "simulations" => Ok(Self::Simulation),
+            "execution" | "executions" => Ok(Self::Execution),
+            "submission" | "submissions" => Ok(Self::Submission),
+            _ => Err(format!(
+                "未知的记录类型: {} (支持 quote、simulation、execution、submission)",
+                value
+            )),
+        }
+    }
+}
+
+/// 待写入的记录
+#[derive(Debug, Clone)]
+pub struct NewRecord {
+    pub kind: RecordKind,
+    /// 产生记录的工具名
+    pub tool: String,
+    pub block_number: Option<u64>,
+    pub from_token: String,
+    pub to_token: String,
+    pub amount_in: String,
+    pub amount_out: Option<String>,
+    /// 类型相关的其他字段（JSON 对象）
+    pub details: serde_json::Value,
+}
+
+/// 已存储的记录
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StoredRecord {
+    pub id: i64,
+    pub kind: String,
+    pub tool: String,
+    /// 记录时间（Unix 秒）
+    pub created_at: i64,
+    pub block_number: Option<u64>,
+    pub from_token: String,
+    pub to_token: String,
+    pub amount_in: String,
+    pub amount_out: Option<String>,
+    pub details: serde_json::Value,
+}
+
+/// 待写入的审计日志
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    /// 产生决定的工具名
+    pub tool: String,
+    /// 审计类别（如 sanctions_screening）
+    pub category: String,
+    /// 决定（如 clear、flagged、refused）
+    pub decision: String,
+    /// 决定依据（JSON 对象）
+    pub details: serde_json::Value,
+}
+
+/// 已存储的审计日志
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StoredAuditEntry {
+    pub id: i64,
+    pub tool: String,
+    pub category: String,
+    pub decision: String,
+    /// 记录时间（Unix 秒）
+    pub created_at: i64,
+    pub details: serde_json::Value,
+}
+
+/// 查询条件
+#[derive(Debug, Clone, Default)]
+pub struct RecordQuery {
+    pub kind: Option<RecordKind>,
+    /// 匹配 from_token 或 to_token（不区分大小写）
+    pub token: Option<String>,
+    /// 只返回该时间之后的记录（Unix 秒）
+    pub since: Option<i64>,
+    pub limit: usize,
+    /// 跳过的记录数（分页）
+    pub offset: usize,
+}
+
+/// 嵌入式 SQLite 存储
+/// 未配置 DATABASE_PATH 时禁用，所有写入为空操作
+pub struct Store {
+    conn: Option<Mutex<Connection>>,
+}
+
+impl Store {
+    /// 打开（或创建）数据库并初始化表结构
+    /// `path` 为 None 时返回禁用的存储
+    pub fn open(path: Option<&str>) -> Result<Self, StoreError> {
+        let Some(path) = path else {
+            debug!("未配置 DATABASE_PATH，持久化已禁用");
+            return Ok(Self::disabled());
+        };
+
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS records (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                tool TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                block_number INTEGER,
+                from_token TEXT NOT NULL,
+                to_token TEXT NOT NULL,
+                amount_in TEXT NOT NULL,
+                amount_out TEXT,
+                details TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_records_kind_created ON records (kind, created_at);
+            CREATE TABLE IF NOT EXISTS ledger (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                wallet TEXT NOT NULL,
+                token TEXT NOT NULL,
+                symbol TEXT NOT NULL,
+                side TEXT NOT NULL,
+                amount TEXT NOT NULL,
+                price_usd TEXT NOT NULL,
+                fee_usd TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL,
+                tx_hash TEXT NOT NULL,
+                log_index INTEGER NOT NULL,
+                source TEXT NOT NULL,
+                UNIQUE (wallet, tx_hash, log_index, side)
+            );
+            CREATE INDEX IF NOT EXISTS idx_ledger_wallet_token ON ledger (wallet, token);
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                tool TEXT NOT NULL,
+                category TEXT NOT NULL,
+                decision TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                details TEXT NOT NULL
+            );",
+        )?;
+
+        info!(path = %path, "持久化存储已启用");
+
+        Ok(Self {
+            conn: Some(Mutex::new(conn)),
+        })
+    }
+
+    /// 创建禁用的存储
+    pub fn disabled() -> Self {
+        Self { conn: None }
+    }
+
+    /// 检查持久化是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.conn.is_some()
+    }
+
+    /// 写入一条记录，返回记录 ID
+    #[instrument(skip(self, record), fields(kind = record.kind.as_str()))]
+    pub fn insert(&self, record: &NewRecord) -> Result<i64, StoreError> {
+        let conn = self.conn.as_ref().ok_or(StoreError::Disabled)?;
+        let conn = conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO records
+                (kind, tool, created_at, block_number, from_token, to_token, amount_in, amount_out, details)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                record.kind.as_str(),
+                record.tool,
+                Utc::now().timestamp(),
+                record.block_number.map(|b| b as i64),
+                record.from_token,
+                record.to_token,
+                record.amount_in,
+                record.amount_out,
+                serde_json::to_string(&record.details)?,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 写入记录；失败时只记录警告，不影响调用方的主流程
+    pub fn record(&self, record: NewRecord) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        if let Err(e) = self.insert(&record) {
+            warn!(error = %e, kind = record.kind.as_str(), "写入持久化记录失败");
+        }
+    }
+
+    /// 写入一条审计日志，返回日志 ID
+    #[instrument(skip(self, entry), fields(category = %entry.category, decision = %entry.decision))]
+    pub fn insert_audit(&self, entry: &AuditEntry) -> Result<i64, StoreError> {
+        let conn = self.conn.as_ref().ok_or(StoreError::Disabled)?;
+        let conn = conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO audit_log (tool, category, decision, created_at, details)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                entry.tool,
+                entry.category,
+                entry.decision,
+                Utc::now().timestamp(),
+                serde_json::to_string(&entry.details)?,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 写入审计日志；失败时只记录警告，不影响调用方的主流程
+    pub fn audit(&self, entry: AuditEntry) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        if let Err(e) = self.insert_audit(&entry) {
+            warn!(error = %e, category = %entry.category, "写入审计日志失败");
+        }
+    }
+
+    /// 查询最近的审计日志（按时间倒序），`tool` 为 None 时返回所有工具的日志
+    #[instrument(skip(self))]
+    pub fn audit_entries(&self, tool: Option<&str>, limit: usize) -> Result<Vec<StoredAuditEntry>, StoreError> {
+        let conn = self.conn.as_ref().ok_or(StoreError::Disabled)?;
+        let conn = conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, tool, category, decision, created_at, details
+             FROM audit_log
+             WHERE ?1 IS NULL OR tool = ?1
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?2",
+        )?;
+
+        let rows = stmt.query_map(params![tool, limit as i64], |row| {
+            let details: String = row.get(5)?;
+            Ok(StoredAuditEntry {
+                id: row.get(0)?,
+                tool: row.get(1)?,
+                category: row.get(2)?,
+                decision: row.get(3)?,
+                created_at: row.get(4)?,
+                details: serde_json::from_str(&details).unwrap_or(serde_json::Value::Null),
+            })
+        })?;
+
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// 按条件查询记录（按时间倒序）
+    #[instrument(skip(self))]
+    pub fn query(&self, query: &RecordQuery) -> Result<Vec<StoredRecord>, StoreError> {
+        let conn = self.conn.as_ref().ok_or(StoreError::Disabled)?;
+        let conn = conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, tool, created_at, block_number, from_token, to_token,
+                    amount_in, amount_out, details
+             FROM records
+             WHERE (?1 IS NULL OR kind = ?1)
+               AND (?2 IS NULL OR lower(from_token) = lower(?2) OR lower(to_token) = lower(?2))
+               AND (?3 IS NULL OR created_at >= ?3)
+             ORDER BY created_at DESC, id DESC
+             LIMIT ?4 OFFSET ?5",
+        )?;
+
+        let rows = stmt.query_map(
+            params![
+                query.kind.map(|k| k.as_str()),
+                query.token,
+                query.since,
+                query.limit as i64,
+                query.offset as i64,
+            ],
+            |row| {
+                let details: String = row.get(9)?;
+                Ok(StoredRecord {
+                    id: row.get(0)?,
+                    kind: row.get(1)?,
+                    tool: row.get(2)?,
+                    created_at: row.get(3)?,
+                    block_number: row.get::<_, Option<i64>>(4)?.map(|b| b as u64),
+                    from_token: row.get(5)?,
+                    to_token: row.get(6)?,
+                    amount_in: row.get(7)?,
+                    amount_out: row.get(8)?,
+                    details: serde_json::from_str(&details).unwrap_or(serde_json::Value::Null),
+                })
+            },
+        )?;
+
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// 记录执行类工具成交的台账（包含成交价、手续费和 Gas）
+    /// 同一交易之前从 Transfer 事件同步的记录会被替换，避免重复计算
+    #[instrument(skip(self, entries), fields(count = entries.len()))]
+    pub fn record_execution(&self, wallet: &str, entries: &[LedgerEntry]) -> Result<usize, StoreError> {
+        {
+            let conn = self.conn.as_ref().ok_or(StoreError::Disabled)?;
+            let conn = conn.lock().unwrap();
+            for tx_hash in entries.iter().map(|e| e.tx_hash.to_lowercase()) {
+                conn.execute(
+                    "DELETE FROM ledger WHERE wallet = ?1 AND tx_hash = ?2 AND source = 'transfer'",
+                    params![wallet.to_lowercase(), tx_hash],
+                )?;
+            }
+        }
+
+        self.insert_ledger_entries(wallet, entries)
+    }
+
+    /// 写入钱包的成本台账记录（已存在的同一笔变动会被忽略），返回新写入的数量
+    /// 已有执行记录的交易不再写入 Transfer 来源的记录
+    #[instrument(skip(self, entries), fields(count = entries.len()))]
+    pub fn insert_ledger_entries(
+        &self,
+        wallet: &str,
+        entries: &[LedgerEntry],
+    ) -> Result<usize, StoreError> {
+        let conn = self.conn.as_ref().ok_or(StoreError::Disabled)?;
+        let mut conn = conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO ledger
+                    (wallet, token, symbol, side, amount, price_usd, fee_usd,
+                     block_number, timestamp, tx_hash, log_index, source)
+                 SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12
+                 WHERE ?12 != 'transfer' OR NOT EXISTS (
+                     SELECT 1 FROM ledger
+                     WHERE wallet = ?1 AND tx_hash = ?10 AND source = 'execution'
+                 )",
+            )?;
+
+            for entry in entries {
+                inserted += stmt.execute(params![
+                    wallet.to_lowercase(),
+                    entry.token.to_lowercase(),
+                    entry.symbol,
+                    entry.side.as_str(),
+                    entry.amount.to_string(),
+                    entry.price_usd.to_string(),
+                    entry.fee_usd.to_string(),
+                    entry.block_number as i64,
+                    entry.timestamp as i64,
+                    entry.tx_hash.to_lowercase(),
+                    entry.log_index,
+                    entry.source,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    /// 查询钱包的成本台账（按区块顺序），可按代币地址过滤
+    #[instrument(skip(self))]
+    pub fn ledger_entries(
+        &self,
+        wallet: &str,
+        token: Option<&str>,
+    ) -> Result<Vec<LedgerEntry>, StoreError> {
+        let conn = self.conn.as_ref().ok_or(StoreError::Disabled)?;
+        let conn = conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT token, symbol, side, amount, price_usd, fee_usd,
+                    block_number, timestamp, tx_hash, log_index, source
+             FROM ledger
+             WHERE wallet = ?1 AND (?2 IS NULL OR token = ?2)
+             ORDER BY block_number, log_index",
+        )?;
+
+        let rows = stmt.query_map(
+            params![wallet.to_lowercase(), token.map(|t| t.to_lowercase())],
+            |row| {
+                let side: String = row.get(2)?;
+                let amount: String = row.get(3)?;
+                let price_usd: String = row.get(4)?;
+                let fee_usd: String = row.get(5)?;
+                Ok(LedgerEntry {
+                    token: row.get(0)?,
+                    symbol: row.get(1)?,
+                    side: LedgerSide::parse(&side).unwrap_or(LedgerSide::Acquire),
+                    amount: Decimal::from_str(&amount).unwrap_or_default(),
+                    price_usd: Decimal::from_str(&price_usd).unwrap_or_default(),
+                    fee_usd: Decimal::from_str(&fee_usd).unwrap_or_default(),
+                    block_number: row.get::<_, i64>(6)? as u64,
+                    timestamp: row.get::<_, i64>(7)? as u64,
+                    tx_hash: row.get(8)?,
+                    log_index: row.get(9)?,
+                    source: row.get(10)?,
+                })
+            },
+        )?;
+
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_record(kind: RecordKind, from: &str, to: &str) -> NewRecord {
+        NewRecord {
+            kind,
+            tool: "swap_tokens".to_string(),
+            block_number: Some(100),
+            from_token: from.to_string(),
+            to_token: to.to_string(),
+            amount_in: "1".to_string(),
+            amount_out: Some("2000".to_string()),
+            details: serde_json::json!({ "price_impact": "0.10%" }),
+        }
+    }
+
+    #[test]
+    fn test_disabled_store() {
+        let store = Store::open(None).unwrap();
+        assert!(!store.is_enabled());
+
+        // 禁用时写入为空操作
+        store.record(sample_record(RecordKind::Quote, "ETH", "USDC"));
+        assert!(matches!(
+            store.query(&RecordQuery::default()),
+            Err(StoreError::Disabled)
+        ));
+    }
+
+    #[test]
+    fn test_insert_and_query() {
+        let store = Store::open(Some(":memory:")).unwrap();
+        assert!(store.is_enabled());
+
+        store.record(sample_record(RecordKind::Quote, "ETH", "USDC"));
+        store.record(sample_record(RecordKind::Simulation, "ETH", "USDC"));
+        store.record(sample_record(RecordKind::Simulation, "DAI", "WBTC"));
+
+        let all = store
+            .query(&RecordQuery { limit: 10, ..Default::default() })
+            .unwrap();
+        assert_eq!(all.len(), 3);
+        // 按时间倒序，同一秒内按 ID 倒序
+        assert_eq!(all[0].from_token, "DAI");
+        assert_eq!(all[0].details["price_impact"], "0.10%");
+
+        let simulations = store
+            .query(&RecordQuery {
+                kind: Some(RecordKind::Simulation),
+                token: Some("usdc".to_string()),
+                limit: 10,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(simulations.len(), 1);
+        assert_eq!(simulations[0].block_number, Some(100));
+
+        let limited = store
+            .query(&RecordQuery { limit: 1, ..Default::default() })
+            .unwrap();
+        assert_eq!(limited.len(), 1);
+    }
+
+    #[test]
+    fn test_ledger_entries_deduplicated() {
+        let store = Store::open(Some(":memory:")).unwrap();
+        let entry = LedgerEntry {
+            token: "0xToken".to_string(),
+            symbol: "TKN".to_string(),
+            side: LedgerSide::Acquire,
+            amount: Decimal::from_str("1.5").unwrap(),
+            price_usd: Decimal::from(2000),
+            fee_usd: Decimal::ZERO,
+            block_number: 10,
+            timestamp: 120,
+            tx_hash: "0xabc".to_string(),
+            log_index: 1,
+            source: "transfer".to_string(),
+        };
+
+        let inserted = store
+            .insert_ledger_entries("0xWallet", &[entry.clone(), entry.clone()])
+            .unwrap();
+        assert_eq!(inserted, 1);
+
+        let entries = store.ledger_entries("0xwallet", Some("0xTOKEN")).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].amount, entry.amount);
+        assert_eq!(entries[0].side, LedgerSide::Acquire);
+    }
+
+    #[test]
+    fn test_execution_replaces_transfer_entries() {
+        let store = Store::open(Some(":memory:")).unwrap();
+        let transfer = LedgerEntry {
+            token: "0xUNI".to_string(),
+            symbol: "UNI".to_string(),
+            side: LedgerSide::Acquire,
+            amount: Decimal::from(125),
+            price_usd: Decimal::from(8),
+            fee_usd: Decimal::ZERO,
+            block_number: 10,
+            timestamp: 120,
+            tx_hash: "0xabc".to_string(),
+            log_index: 7,
+            source: "transfer".to_string(),
+        };
+        let execution = LedgerEntry {
+            fee_usd: Decimal::from(5),
+            log_index: 1,
+            source: "execution".to_string(),
+            ..transfer.clone()
+        };
+
+        store.insert_ledger_entries("0xWallet", std::slice::from_ref(&transfer)).unwrap();
+        store.record_execution("0xWallet", &[execution]).unwrap();
+
+        // 再次同步同一交易的 Transfer 记录应被忽略
+        let inserted = store.insert_ledger_entries("0xWallet", &[transfer]).unwrap();
+        assert_eq!(inserted, 0);
+
+        let entries = store.ledger_entries("0xWallet", None).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].source, "execution");
+        assert_eq!(entries[0].fee_usd, Decimal::from(5));
+    }
+
+    #[test]
+    fn test_record_kind_parse() {
+        assert_eq!(RecordKind::parse("quotes").unwrap(), RecordKind::Quote);
+        assert_eq!(RecordKind::parse("Simulation").unwrap(), RecordKind::Simulation);
+        assert_eq!(RecordKind::parse("execution").unwrap(), RecordKind::Execution);
+        assert_eq!(RecordKind::parse("submissions").unwrap(), RecordKind::Submission);
+        assert!(RecordKind::parse("other").is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use crate::diagnostics::record_rpc_call;
+use ethers::types::Address;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, instrument};
+
+/// 子图请求超时时间
+const SUBGRAPH_TIMEOUT: Duration = Duration::from_secs(10);
+/// 统计窗口（24 小时，按小时数据汇总）
+const WINDOW_SECS: u64 = 24 * 60 * 60;
+/// Uniswap V2 交易手续费率（0.3%，子图不单独记录手续费）
+const V2_FEE_RATE: Decimal = Decimal::from_parts(3, 0, 0, false, 3);
+
+const V2_HOURLY_QUERY: &str = "query($pair: String!, $since: Int!) { \
+    pairHourDatas(first: 24, orderBy: hourStartUnix, orderDirection: desc, \
+    where: { pair: $pair, hourStartUnix_gt: $since }) { hourlyVolumeUSD hourlyTxns reserveUSD } }";
+
+const V3_HOURLY_QUERY: &str = "query($pool: String!, $since: Int!) { \
+    poolHourDatas(first: 24, orderBy: periodStartUnix, orderDirection: desc, \
+    where: { pool: $pool, periodStartUnix_gt: $since }) { volumeUSD feesUSD txCount tvlUSD } }";
+
+/// 子图查询错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum SubgraphError {
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("未配置 {0} 子图地址")]
+    NotConfigured(&'static str),
+
+    #[error("GraphQL 错误: {0}")]
+    GraphQlError(String),
+
+    #[error("响应格式错误: {0}")]
+    InvalidResponse(String),
+}
+
+/// 池子最近 24 小时的活跃度统计
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PoolActivity {
+    pub volume_24h_usd: String,
+    pub fees_24h_usd: String,
+    pub tx_count_24h: u64,
+    /// 子图记录的最新 TVL（USD）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tvl_usd: Option<String>,
+    /// 有成交记录的小时数（少于 24 说明部分时段没有交易）
+    pub active_hours: u32,
+    pub source: String,
+}
+
+/// Uniswap V2/V3 子图 GraphQL 客户端
+#[derive(Clone)]
+pub struct SubgraphClient {
+    http: reqwest::Client,
+    v2_url: Option<String>,
+    v3_url: Option<String>,
+}
+
+impl SubgraphClient {
+    pub fn new(v2_url: Option<String>, v3_url: Option<String>) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(SUBGRAPH_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            v2_url,
+            v3_url,
+        }
+    }
+
+    /// 是否配置了 V2 子图
+    pub fn has_v2(&self) -> bool {
+        self.v2_url.is_some()
+    }
+
+    /// 是否配置了 V3 子图
+    pub fn has_v3(&self) -> bool {
+        self.v3_url.is_some()
+    }
+
+    /// 查询 Uniswap V2 交易对最近 24 小时的交易量、手续费和交易笔数
+    #[instrument(skip(self))]
+    pub async fn v2_pair_activity(&self, pair: Address) -> Result<PoolActivity, SubgraphError> {
+        let url = self.v2_url.as_deref().ok_or(SubgraphError::NotConfigured("Uniswap V2"))?;
+        let variables = serde_json::json!({ "pair": format!("{:?}", pair), "since": window_start() });
+        let data = self.query(url, "subgraph_pair_hour_datas", V2_HOURLY_QUERY, variables).await?;
+
+        let rows = hour_rows(&data, "pairHourDatas")?;
+        let activity = aggregate_hours(rows, &HOURLY_V2, "Uniswap V2 Subgraph")?;
+        debug!(pair = ?pair, volume = %activity.volume_24h_usd, "V2 子图活跃度");
+        Ok(activity)
+    }
+
+    /// 查询 Uniswap V3 池子最近 24 小时的交易量、手续费和交易笔数
+    #[instrument(skip(self))]
+    pub async fn v3_pool_activity(&self, pool: Address) -> Result<PoolActivity, SubgraphError> {
+        let url = self.v3_url.as_deref().ok_or(SubgraphError::NotConfigured("Uniswap V3"))?;
+        let variables = serde_json::json!({ "pool": format!("{:?}", pool), "since": window_start() });
+        let data = self.query(url, "subgraph_pool_hour_datas", V3_HOURLY_QUERY, variables).await?;
+
+        let rows = hour_rows(&data, "poolHourDatas")?;
+        let activity = aggregate_hours(rows, &HOURLY_V3, "Uniswap V3 Subgraph")?;
+        debug!(pool = ?pool, volume = %activity.volume_24h_usd, "V3 子图活跃度");
+        Ok(activity)
+    }
+
+    /// 发送 GraphQL 查询并返回 `data` 字段
+    async fn query(
+        &self,
+        url: &str,
+        method: &str,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<serde_json::Value, SubgraphError> {
+        let body = serde_json::json!({ "query": query, "variables": variables });
+
+        let started = Instant::now();
+        let response = async { self.http.post(url).json(&body).send().await?.error_for_status()?.json().await }.await;
+        record_rpc_call(method, None, started.elapsed(), 0, response.is_ok());
+        let mut response: serde_json::Value = response?;
+
+        if let Some(errors) = response.get("errors").and_then(|errors| errors.as_array())
+            && !errors.is_empty()
+        {
+            let messages: Vec<&str> = errors
+                .iter()
+                .filter_map(|error| error.get("message").and_then(|message| message.as_str()))
+                .collect();
+            return Err(SubgraphError::GraphQlError(messages.join("; ")));
+        }
+
+        match response.get_mut("data") {
+            Some(data) if data.is_object() => Ok(data.take()),
+            _ => Err(SubgraphError::InvalidResponse(response.to_string())),
+        }
+    }
+}
+
+/// 小时数据的字段名（V2 和 V3 子图的 schema 不同）
+struct HourlyFields {
+    volume_usd: &'static str,
+    /// 没有手续费字段时按交易量 × 费率估算
+    fees_usd: Option<&'static str>,
+    tx_count: &'static str,
+    tvl_usd: &'static str,
+}
+
+const HOURLY_V2: HourlyFields = HourlyFields {
+    volume_usd: "hourlyVolumeUSD",
+    fees_usd: None,
+    tx_count: "hourlyTxns",
+    tvl_usd: "reserveUSD",
+};
+
+const HOURLY_V3: HourlyFields = HourlyFields {
+    volume_usd: "volumeUSD",
+    fees_usd: Some("feesUSD"),
+    tx_count: "txCount",
+    tvl_usd: "tvlUSD",
+};
+
+/// 统计窗口的起始时间戳（不含）
+fn window_start() -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    now.saturating_sub(WINDOW_SECS)
+}
+
+fn hour_rows<'a>(data: &'a serde_json::Value, entity: &str) -> Result<&'a [serde_json::Value], SubgraphError> {
+    data.get(entity)
+        .and_then(|rows| rows.as_array())
+        .map(Vec::as_slice)
+        .ok_or_else(|| SubgraphError::InvalidResponse(format!("缺少 {} 字段", entity)))
+}
+
+/// 汇总按时间倒序排列的小时数据，TVL 取最新一小时
+fn aggregate_hours(
+    rows: &[serde_json::Value],
+    fields: &HourlyFields,
+    source: &str,
+) -> Result<PoolActivity, SubgraphError> {
+    let mut volume = Decimal::ZERO;
+    let mut fees = Decimal::ZERO;
+    let mut tx_count = 0u64;
+    for row in rows {
+        let row_volume = decimal_field(row, fields.volume_usd)?;
+        volume += row_volume;
+        fees += match fields.fees_usd {
+            Some(field) => decimal_field(row, field)?,
+            None => row_volume * V2_FEE_RATE,
+        };
+        tx_count += decimal_field(row, fields.tx_count)?.trunc().try_into().unwrap_or(0u64);
+    }
+    let tvl = rows.first().map(|row| decimal_field(row, fields.tvl_usd)).transpose()?;
+
+    Ok(PoolActivity {
+        volume_24h_usd: volume.round_dp(2).normalize().to_string(),
+        fees_24h_usd: fees.round_dp(2).normalize().to_string(),
+        tx_count_24h: tx_count,
+        tvl_usd: tvl.map(|tvl| tvl.round_dp(2).normalize().to_string()),
+        active_hours: rows.len() as u32,
+        source: source.to_string(),
+    })
+}
+
+/// 解析子图的 BigDecimal/BigInt 字段（以字符串返回，小数位可能超过 Decimal 的精度）
+fn decimal_field(row: &serde_json::Value, field: &str) -> Result<Decimal, SubgraphError> {
+    let text = match row.get(field) {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(serde_json::Value::Number(number)) => number.to_string(),
+        _ => return Err(SubgraphError::InvalidResponse(format!("缺少 {} 字段", field))),
+    };
+    let truncated = match text.split_once('.') {
+        Some((integer, fraction)) => format!("{}.{}", integer, &fraction[..fraction.len().min(12)]),
+        None => text.clone(),
+    };
+    Decimal::from_str(&truncated)
+        .map_err(|_| SubgraphError::InvalidResponse(format!("无效的 {}: {}", field, text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_v2_hours() {
+        let rows = serde_json::json!([
+            { "hourlyVolumeUSD": "1000.123456789012345678901234", "hourlyTxns": "12", "reserveUSD": "500000.5" },
+            { "hourlyVolumeUSD": "3000", "hourlyTxns": "8", "reserveUSD": "499000" },
+        ]);
+        let activity = aggregate_hours(rows.as_array().unwrap(), &HOURLY_V2, "Uniswap V2 Subgraph").unwrap();
+        assert_eq!(activity.volume_24h_usd, "4000.12");
+        // V2 手续费按 0.3% 估算
+        assert_eq!(activity.fees_24h_usd, "12");
+        assert_eq!(activity.tx_count_24h, 20);
+        assert_eq!(activity.tvl_usd.as_deref(), Some("500000.5"));
+        assert_eq!(activity.active_hours, 2);
+    }
+
+    #[test]
+    fn test_aggregate_v3_hours() {
+        let rows = serde_json::json!([
+            { "volumeUSD": "2000", "feesUSD": "1", "txCount": "5", "tvlUSD": "1e3" },
+        ]);
+        // 非十进制格式的数值返回错误
+        assert!(matches!(
+            aggregate_hours(rows.as_array().unwrap(), &HOURLY_V3, "Uniswap V3 Subgraph"),
+            Err(SubgraphError::InvalidResponse(_))
+        ));
+
+        let rows = serde_json::json!([
+            { "volumeUSD": "2000", "feesUSD": "1", "txCount": "5", "tvlUSD": "1000" },
+            { "volumeUSD": "500.5", "feesUSD": "0.25", "txCount": 3, "tvlUSD": "900" },
+        ]);
+        let activity = aggregate_hours(rows.as_array().unwrap(), &HOURLY_V3, "Uniswap V3 Subgraph").unwrap();
+        assert_eq!(activity.volume_24h_usd, "2500.5");
+        assert_eq!(activity.fees_24h_usd, "1.25");
+        assert_eq!(activity.tx_count_24h, 8);
+        assert_eq!(activity.tvl_usd.as_deref(), Some("1000"));
+
+        // 最近 24 小时没有交易
+        let activity = aggregate_hours(&[], &HOURLY_V3, "Uniswap V3 Subgraph").unwrap();
+        assert_eq!(activity.volume_24h_usd, "0");
+        assert_eq!(activity.tvl_usd, None);
+        assert_eq!(activity.active_hours, 0);
+    }
+
+    #[test]
+    fn test_hour_rows() {
+        let data = serde_json::json!({ "pairHourDatas": [] });
+        assert!(hour_rows(&data, "pairHourDatas").unwrap().is_empty());
+        assert!(matches!(
+            hour_rows(&data, "poolHourDatas"),
+            Err(SubgraphError::InvalidResponse(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_client_requires_url() {
+        let client = SubgraphClient::new(None, Some("http://localhost:8000".to_string()));
+        assert!(!client.has_v2());
+        assert!(client.has_v3());
+        assert!(matches!(
+            client.v2_pair_activity(Address::zero()).await,
+            Err(SubgraphError::NotConfigured("Uniswap V2"))
+        ));
+    }
+}
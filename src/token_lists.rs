@@ -0,0 +1,273 @@
+use crate::diagnostics::record_cache_lookup;
+use crate::token_registry::{TokenRegistry, MAX_TOKEN_DECIMALS};
+use crate::types::{checksum_address, TokenInfo};
+use crate::workers::WorkerManager;
+use ethers::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, instrument, warn};
+
+/// 代币列表缓存有效期
+const TOKEN_LIST_TTL: Duration = Duration::from_secs(6 * 3600);
+/// 获取代币列表的超时时间（CoinGecko 列表较大）
+const TOKEN_LIST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 主流代币列表（Token Lists 标准格式）
+pub const TOKEN_LISTS: [(&str, &str); 2] = [
+    ("uniswap", "https://tokens.uniswap.org"),
+    ("coingecko", "https://tokens.coingecko.com/uniswap/all.json"),
+];
+
+/// 代币列表错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum TokenListError {
+    #[error("HTTP 请求失败: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("代币列表格式错误: {0}")]
+    InvalidList(#[from] serde_json::Error),
+}
+
+#[derive(serde::Deserialize)]
+struct TokenListDocument {
+    tokens: Vec<TokenListEntry>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenListEntry {
+    chain_id: u64,
+    address: String,
+    #[serde(default)]
+    symbol: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    decimals: Option<u8>,
+}
+
+impl TokenListEntry {
+    /// 转换为注册表代币，地址、符号或精度无效时返回 None
+    fn to_token_info(&self) -> Option<TokenInfo> {
+        let address = self.address.parse::<Address>().ok().filter(|a| !a.is_zero())?;
+        let symbol = self.symbol.trim();
+        if symbol.is_empty() || symbol.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return None;
+        }
+        let decimals = self.decimals.filter(|d| *d <= MAX_TOKEN_DECIMALS)?;
+        let name = match self.name.trim() {
+            "" => symbol,
+            name => name,
+        };
+        Some(TokenInfo {
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            address: checksum_address(address),
+            decimals,
+            listed_on: Vec::new(),
+        })
+    }
+}
+
+/// 解析 Token Lists 标准格式中属于 `chain_id` 的代币（无效条目忽略）
+pub fn parse_chain_tokens(json: &str, chain_id: u64) -> Result<Vec<TokenInfo>, TokenListError> {
+    let document: TokenListDocument = serde_json::from_str(json)?;
+    Ok(document
+        .tokens
+        .iter()
+        .filter(|entry| entry.chain_id == chain_id)
+        .filter_map(TokenListEntry::to_token_info)
+        .collect())
+}
+
+/// 已下载的代币列表：链 ID -> 代币地址集合
+#[derive(Debug, Clone, Default)]
+pub struct TokenList {
+    tokens: HashMap<u64, HashSet<Address>>,
+}
+
+impl TokenList {
+    /// 解析 Token Lists 标准格式（无效地址的条目忽略）
+    pub fn parse(json: &str) -> Result<Self, TokenListError> {
+        let document: TokenListDocument = serde_json::from_str(json)?;
+        let mut tokens: HashMap<u64, HashSet<Address>> = HashMap::new();
+        for entry in document.tokens {
+            if let Ok(address) = entry.address.parse::<Address>() {
+                tokens.entry(entry.chain_id).or_default().insert(address);
+            }
+        }
+        Ok(Self { tokens })
+    }
+
+    pub fn contains(&self, chain_id: u64, address: Address) -> bool {
+        self.tokens
+            .get(&chain_id)
+            .is_some_and(|tokens| tokens.contains(&address))
+    }
+}
+
+/// 代币列表客户端：按需下载主流代币列表并缓存
+pub struct TokenListClient {
+    http: reqwest::Client,
+    enabled: bool,
+    cache: RwLock<HashMap<&'static str, (TokenList, Instant)>>,
+}
+
+impl TokenListClient {
+    /// 创建新的代币列表客户端（`enabled` 为 false 时不发起任何请求）
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(TOKEN_LIST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            enabled,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 查询收录该代币的列表名称
+    /// 单个列表下载失败时跳过该列表，不影响调用方
+    #[instrument(skip(self))]
+    pub async fn listed_on(&self, chain_id: u64, address: Address) -> Vec<String> {
+        if !self.enabled {
+            return Vec::new();
+        }
+
+        let mut listed_on = Vec::new();
+        for (name, url) in TOKEN_LISTS {
+            match self.list(name, url).await {
+                Ok(list) if list.contains(chain_id, address) => listed_on.push(name.to_string()),
+                Ok(_) => {}
+                Err(e) => warn!(list = name, error = %e, "获取代币列表失败"),
+            }
+        }
+
+        debug!(listed_on = ?listed_on, "代币列表收录情况");
+        listed_on
+    }
+
+    /// 下载远程代币列表，返回属于 `chain_id` 的代币（不受 TOKEN_LIST_CHECK 影响，不缓存）
+    #[instrument(skip(self))]
+    pub async fn fetch_chain_tokens(&self, url: &str, chain_id: u64) -> Result<Vec<TokenInfo>, TokenListError> {
+        let body = self.http.get(url).send().await?.error_for_status()?.text().await?;
+        let tokens = parse_chain_tokens(&body, chain_id)?;
+        debug!(tokens = tokens.len(), "已下载远程代币列表");
+        Ok(tokens)
+    }
+
+    /// 读取缓存的列表，过期或未下载时重新获取
+    async fn list(&self, name: &'static str, url: &str) -> Result<TokenList, TokenListError> {
+        if let Some((list, fetched_at)) = self.cache.read().unwrap().get(name)
+            && fetched_at.elapsed() < TOKEN_LIST_TTL
+        {
+            record_cache_lookup("token_list", name, true);
+            return Ok(list.clone());
+        }
+        record_cache_lookup("token_list", name, false);
+
+        let body = self.http.get(url).send().await?.error_for_status()?.text().await?;
+        let list = TokenList::parse(&body)?;
+        self.cache
+            .write()
+            .unwrap()
+            .insert(name, (list.clone(), Instant::now()));
+        Ok(list)
+    }
+}
+
+/// 启动远程代币列表刷新任务：按配置顺序下载全部列表并合并到注册表
+/// 单个列表失败时仍合并其余列表（已导入的代币保留），并把本轮记为失败以便退避重试
+pub fn spawn_token_list_refresher(
+    workers: &WorkerManager,
+    client: Arc<TokenListClient>,
+    registry: Arc<TokenRegistry>,
+    urls: Vec<String>,
+    chain_id: u64,
+    interval: Duration,
+) {
+    workers.spawn_periodic("token_list_refresher", "刷新远程代币列表", interval, move || {
+        let client = client.clone();
+        let registry = registry.clone();
+        let urls = urls.clone();
+
+        async move {
+            let mut tokens = Vec::new();
+            let mut failed = Vec::new();
+            for url in &urls {
+                match client.fetch_chain_tokens(url, chain_id).await {
+                    Ok(list) => tokens.extend(list),
+                    Err(e) => {
+                        warn!(url = %url, error = %e, "下载远程代币列表失败");
+                        failed.push(url.as_str());
+                    }
+                }
+            }
+
+            let merged = registry.merge_remote(tokens);
+            info!(merged, lists = urls.len() - failed.len(), "远程代币列表已合并到注册表");
+
+            if failed.is_empty() {
+                Ok(())
+            } else {
+                Err(format!("{} 个代币列表下载失败: {}", failed.len(), failed.join(", ")))
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_token_list() {
+        let json = r#"{
+            "name": "Uniswap Labs Default",
+            "tokens": [
+                {"chainId": 1, "address": "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", "symbol": "USDC", "decimals": 6},
+                {"chainId": 10, "address": "0x0b2C639c533813f4Aa9D7837CAf62653d097Ff85", "symbol": "USDC", "decimals": 6},
+                {"chainId": 1, "address": "not-an-address", "symbol": "BAD", "decimals": 18}
+            ]
+        }"#;
+
+        let list = TokenList::parse(json).unwrap();
+        let usdc: Address = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".parse().unwrap();
+        assert!(list.contains(1, usdc));
+        assert!(!list.contains(10, usdc));
+        assert!(!list.contains(1, Address::zero()));
+
+        assert!(TokenList::parse(r#"{"tokens": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_chain_tokens() {
+        let json = r#"{
+            "name": "Custom",
+            "tokens": [
+                {"chainId": 1, "address": "0x514910771AF9Ca656af840dff83E8264EcF986CA", "symbol": "LINK", "name": "ChainLink Token", "decimals": 18, "logoURI": "https://example.com/link.png"},
+                {"chainId": 10, "address": "0x350a791Bfc2C21F9Ed5d10980Dad2e2638ffa7f6", "symbol": "LINK", "name": "ChainLink Token", "decimals": 18},
+                {"chainId": 1, "address": "0x1234567890123456789012345678901234567890", "symbol": "BAD SYMBOL", "name": "Bad", "decimals": 18},
+                {"chainId": 1, "address": "0x1234567890123456789012345678901234567890", "symbol": "NODEC", "name": "No Decimals"},
+                {"chainId": 1, "address": "0x1234567890123456789012345678901234567890", "symbol": "NONAME", "decimals": 8}
+            ]
+        }"#;
+
+        let tokens = parse_chain_tokens(json, 1).unwrap();
+        let symbols: Vec<_> = tokens.iter().map(|t| t.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["LINK", "NONAME"]);
+        assert_eq!(tokens[0].address, "0x514910771AF9Ca656af840dff83E8264EcF986CA");
+        // 缺少名称时使用符号
+        assert_eq!(tokens[1].name, "NONAME");
+
+        assert_eq!(parse_chain_tokens(json, 10).unwrap().len(), 1);
+        assert!(parse_chain_tokens(json, 137).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_client_skips_lookup() {
+        let client = TokenListClient::new(false);
+        assert!(client.listed_on(1, Address::zero()).await.is_empty());
+    }
+}
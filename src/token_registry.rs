@@ -1,68 +1,180 @@
-use crate::types::TokenInfo;
-use std::collections::HashMap;
+use crate::chains::{ChainInfo, MAINNET};
+use crate::types::{checksum_address, parse_address, TokenInfo};
+use ethers::types::Address;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::RwLock;
 
+/// 代币精度上限（10^77 仍在 U256 范围内）
+pub const MAX_TOKEN_DECIMALS: u8 = 77;
+
+/// 代币注册表文件错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum TokenRegistryError {
+    #[error("读取代币注册表失败: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("代币注册表格式错误: {0}")]
+    Parse(String),
+
+    #[error("代币注册表第 {index} 个代币无效: {reason}")]
+    InvalidEntry { index: usize, reason: String },
+
+    #[error("未知的代币: {0}")]
+    UnknownToken(String),
+
+    #[error("{0}")]
+    InvalidAddress(String),
+}
+
+/// 代币注册表文件中的一个代币
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenFileEntry {
+    symbol: String,
+    name: String,
+    address: String,
+    decimals: u8,
+    chain_id: u64,
+}
+
+/// 代币注册表文件：`{"tokens": [...]}`（TOML 中为 `[[tokens]]`），JSON 也可直接是代币数组
+#[derive(Debug, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenFile {
+    tokens: Vec<TokenFileEntry>,
+}
+
 /// 代币注册表
 /// 管理常用代币的符号到地址的映射
 /// 支持动态查询链上信息并缓存
 pub struct TokenRegistry {
     tokens: RwLock<HashMap<String, TokenInfo>>,
+    /// 来自远程代币列表的符号，刷新时可被覆盖
+    remote_symbols: RwLock<HashSet<String>>,
 }
 
 impl TokenRegistry {
-    /// 创建新的注册表，预加载常用代币
+    /// 创建新的注册表，预加载主网常用代币
     pub fn new() -> Self {
+        Self::for_chain(&MAINNET)
+    }
+
+    /// 创建指定链的注册表，预加载该链的包装原生代币、原生代币别名和 USDC
+    pub fn for_chain(chain: &ChainInfo) -> Self {
         let mut tokens = HashMap::new();
 
         // 加载默认代币
-        for (symbol, info) in default_mainnet_tokens() {
+        for (symbol, info) in default_chain_tokens(chain) {
             tokens.insert(symbol.to_uppercase(), info);
         }
 
         Self {
             tokens: RwLock::new(tokens),
+            remote_symbols: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// 创建指定链的注册表，并加载代币注册表文件中属于该链的代币（同名符号覆盖默认代币）
+    pub fn load(chain: &ChainInfo, path: Option<&str>) -> Result<Self, TokenRegistryError> {
+        let registry = Self::for_chain(chain);
+        if let Some(path) = path {
+            for (symbol, info) in load_token_file(path, chain.chain_id)? {
+                registry.register(symbol, info);
+            }
         }
+        Ok(registry)
     }
 
     /// 解析代币地址或符号
     /// 如果输入是有效的以太坊地址，直接返回
     /// 如果是符号，从注册表查找
-    pub fn resolve(&self, symbol_or_address: &str) -> Option<TokenInfo> {
+    /// 大小写混合但 EIP-55 校验和错误的地址返回 InvalidAddress，未注册的符号返回 UnknownToken
+    pub fn resolve(&self, symbol_or_address: &str) -> Result<TokenInfo, TokenRegistryError> {
         let tokens = self.tokens.read().unwrap();
 
         // 检查是否为以太坊地址（0x 开头，42 位）
         if symbol_or_address.starts_with("0x") && symbol_or_address.len() == 42 {
             // 验证是否为十六进制
             if symbol_or_address[2..].chars().all(|c| c.is_ascii_hexdigit()) {
+                let address = parse_address(symbol_or_address).map_err(TokenRegistryError::InvalidAddress)?;
                 // 这是地址，尝试从注册表查找详细信息
                 // 如果找不到，返回 UNKNOWN 标记（调用方应主动查询链上信息）
-                return tokens
+                return Ok(tokens
                     .values()
                     .find(|t| t.address.to_lowercase() == symbol_or_address.to_lowercase())
                     .cloned()
-                    .or_else(|| {
-                        Some(TokenInfo {
-                            symbol: "UNKNOWN".to_string(),
-                            name: "Unknown Token".to_string(),
-                            address: symbol_or_address.to_string(),
-                            decimals: 18, // 🔴 占位符，调用方应查询真实值
-                        })
-                    });
+                    .unwrap_or_else(|| TokenInfo {
+                        symbol: "UNKNOWN".to_string(),
+                        name: "Unknown Token".to_string(),
+                        address: checksum_address(address),
+                        decimals: 18, // 🔴 占位符，调用方应查询真实值
+                        listed_on: Vec::new(),
+                    }));
             }
         }
 
         // 作为符号查找
-        tokens.get(&symbol_or_address.to_uppercase()).cloned()
+        tokens
+            .get(&symbol_or_address.to_uppercase())
+            .cloned()
+            .ok_or_else(|| TokenRegistryError::UnknownToken(symbol_or_address.to_string()))
     }
 
     /// 添加或更新代币信息
     pub fn register(&self, symbol: String, info: TokenInfo) {
         let mut tokens = self.tokens.write().unwrap();
-        tokens.insert(symbol.to_uppercase(), info.clone());
+        let symbol = symbol.to_uppercase();
+        self.remote_symbols.write().unwrap().remove(&symbol);
+        tokens.insert(symbol, info.clone());
         // 同时用地址作为 key 缓存
         tokens.insert(info.address.to_lowercase(), info);
     }
 
+    /// 移除代币，同时移除注册时写入的地址缓存；返回被移除的代币
+    pub fn remove(&self, symbol: &str) -> Option<TokenInfo> {
+        let mut tokens = self.tokens.write().unwrap();
+        let symbol = symbol.to_uppercase();
+        let removed = tokens.remove(&symbol)?;
+        self.remote_symbols.write().unwrap().remove(&symbol);
+
+        let address_key = removed.address.to_lowercase();
+        if tokens
+            .get(&address_key)
+            .is_some_and(|cached| cached.symbol.eq_ignore_ascii_case(&symbol))
+        {
+            tokens.remove(&address_key);
+        }
+        Some(removed)
+    }
+
+    /// 合并远程代币列表中的代币，返回新增或更新的数量
+    /// 不覆盖内置、文件或手动注册的同名代币，避免远程列表中的仿冒符号替换已知代币；
+    /// 同一批次中重复的符号以先出现的为准
+    pub fn merge_remote(&self, remote: Vec<TokenInfo>) -> usize {
+        let mut tokens = self.tokens.write().unwrap();
+        let mut remote_symbols = self.remote_symbols.write().unwrap();
+        let mut batch = HashSet::new();
+        let mut merged = 0;
+
+        for info in remote {
+            let symbol = info.symbol.to_uppercase();
+            if !batch.insert(symbol.clone()) {
+                continue;
+            }
+            if tokens.contains_key(&symbol) && !remote_symbols.contains(&symbol) {
+                continue;
+            }
+            if tokens.get(&symbol).is_some_and(|existing| existing.address == info.address && existing.decimals == info.decimals) {
+                continue;
+            }
+            remote_symbols.insert(symbol.clone());
+            tokens.insert(symbol, info);
+            merged += 1;
+        }
+        merged
+    }
+
     /// 获取所有已注册代币
     pub fn all_tokens(&self) -> Vec<TokenInfo> {
         let tokens = self.tokens.read().unwrap();
@@ -82,27 +194,93 @@ impl Default for TokenRegistry {
     }
 }
 
-/// 以太坊主网常用代币列表
-fn default_mainnet_tokens() -> Vec<(String, TokenInfo)> {
-    vec![
-        // WETH 排在前面，通过地址查询时优先返回
+/// 读取代币注册表文件，`.toml` 扩展名按 TOML 解析，其余按 JSON 解析
+fn load_token_file(path: &str, chain_id: u64) -> Result<Vec<(String, TokenInfo)>, TokenRegistryError> {
+    let content = std::fs::read_to_string(path)?;
+    let is_toml = Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+    let entries = if is_toml {
+        toml::from_str::<TokenFile>(&content)
+            .map_err(|e| TokenRegistryError::Parse(e.to_string()))?
+            .tokens
+    } else if content.trim_start().starts_with('[') {
+        serde_json::from_str(&content).map_err(|e| TokenRegistryError::Parse(e.to_string()))?
+    } else {
+        serde_json::from_str::<TokenFile>(&content)
+            .map_err(|e| TokenRegistryError::Parse(e.to_string()))?
+            .tokens
+    };
+    parse_token_entries(entries, chain_id)
+}
+
+/// 校验文件中的全部代币（包括其他链的代币），只返回属于 `chain_id` 的代币
+fn parse_token_entries(entries: Vec<TokenFileEntry>, chain_id: u64) -> Result<Vec<(String, TokenInfo)>, TokenRegistryError> {
+    let mut seen = HashSet::new();
+    let mut tokens = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let invalid = |reason: String| TokenRegistryError::InvalidEntry { index: index + 1, reason };
+
+        let symbol = entry.symbol.trim().to_string();
+        if symbol.is_empty() || symbol.chars().any(char::is_whitespace) {
+            return Err(invalid(format!("符号不能为空或包含空白: {:?}", entry.symbol)));
+        }
+        if entry.name.trim().is_empty() {
+            return Err(invalid(format!("{} 缺少名称", symbol)));
+        }
+        let address = entry
+            .address
+            .parse::<Address>()
+            .map_err(|_| invalid(format!("{} 的地址无效: {}", symbol, entry.address)))?;
+        if address.is_zero() {
+            return Err(invalid(format!("{} 的地址不能是零地址", symbol)));
+        }
+        if entry.decimals > MAX_TOKEN_DECIMALS {
+            return Err(invalid(format!("{} 的精度 {} 超过 {}", symbol, entry.decimals, MAX_TOKEN_DECIMALS)));
+        }
+        if !seen.insert((entry.chain_id, symbol.to_uppercase())) {
+            return Err(invalid(format!("链 {} 上的符号 {} 重复", entry.chain_id, symbol)));
+        }
+
+        if entry.chain_id == chain_id {
+            tokens.push((
+                symbol.clone(),
+                TokenInfo {
+                    symbol,
+                    name: entry.name.trim().to_string(),
+                    address: checksum_address(address),
+                    decimals: entry.decimals,
+                    listed_on: Vec::new(),
+                },
+            ));
+        }
+    }
+    Ok(tokens)
+}
+
+/// 各链通用的默认代币，主网额外加载其他常用代币
+fn default_chain_tokens(chain: &ChainInfo) -> Vec<(String, TokenInfo)> {
+    let mut tokens = vec![
+        // 包装原生代币排在前面，通过地址查询时优先返回
         (
-            "WETH".to_string(),
+            chain.wrapped_native_symbol.to_string(),
             TokenInfo {
-                symbol: "WETH".to_string(),
-                name: "Wrapped Ether".to_string(),
-                address: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(),
+                symbol: chain.wrapped_native_symbol.to_string(),
+                name: chain.wrapped_native_name.to_string(),
+                address: chain.wrapped_native.to_string(),
                 decimals: 18,
+                listed_on: Vec::new(),
             },
         ),
-        // ETH 别名：用户友好的符号，映射到 WETH 合约
+        // 原生代币别名：用户友好的符号，映射到包装代币合约
         (
-            "ETH".to_string(),
+            chain.native_symbol.to_string(),
             TokenInfo {
-                symbol: "ETH".to_string(),
-                name: "Ether".to_string(),
-                address: "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(), // WETH 地址
+                symbol: chain.native_symbol.to_string(),
+                name: chain.native_name.to_string(),
+                address: chain.wrapped_native.to_string(),
                 decimals: 18,
+                listed_on: Vec::new(),
             },
         ),
         (
@@ -110,10 +288,22 @@ fn default_mainnet_tokens() -> Vec<(String, TokenInfo)> {
             TokenInfo {
                 symbol: "USDC".to_string(),
                 name: "USD Coin".to_string(),
-                address: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+                address: chain.usdc.to_string(),
                 decimals: 6,
+                listed_on: Vec::new(),
             },
         ),
+    ];
+
+    if chain.chain_id == MAINNET.chain_id {
+        tokens.extend(default_mainnet_tokens());
+    }
+    tokens
+}
+
+/// 以太坊主网其他常用代币
+fn default_mainnet_tokens() -> Vec<(String, TokenInfo)> {
+    vec![
         (
             "USDT".to_string(),
             TokenInfo {
@@ -121,6 +311,7 @@ fn default_mainnet_tokens() -> Vec<(String, TokenInfo)> {
                 name: "Tether USD".to_string(),
                 address: "0xdAC17F958D2ee523a2206206994597C13D831ec7".to_string(),
                 decimals: 6,
+                listed_on: Vec::new(),
             },
         ),
         (
@@ -130,6 +321,7 @@ fn default_mainnet_tokens() -> Vec<(String, TokenInfo)> {
                 name: "Dai Stablecoin".to_string(),
                 address: "0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(),
                 decimals: 18,
+                listed_on: Vec::new(),
             },
         ),
         (
@@ -139,6 +331,7 @@ fn default_mainnet_tokens() -> Vec<(String, TokenInfo)> {
                 name: "Wrapped BTC".to_string(),
                 address: "0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599".to_string(),
                 decimals: 8,
+                listed_on: Vec::new(),
             },
         ),
         (
@@ -148,6 +341,7 @@ fn default_mainnet_tokens() -> Vec<(String, TokenInfo)> {
                 name: "Uniswap".to_string(),
                 address: "0x1f9840a85d5aF5bf1D1762F925BDADdC4201F984".to_string(),
                 decimals: 18,
+                listed_on: Vec::new(),
             },
         ),
     ]
@@ -232,10 +426,15 @@ mod tests {
         let registry = TokenRegistry::new();
 
         // 无效符号
-        assert!(registry.resolve("INVALID").is_none());
+        assert!(matches!(registry.resolve("INVALID"), Err(TokenRegistryError::UnknownToken(_))));
 
         // 无效地址格式
-        assert!(registry.resolve("0xinvalid").is_none());
+        assert!(registry.resolve("0xinvalid").is_err());
+
+        // EIP-55 校验和错误的地址返回校验和错误，而不是未知代币
+        let err = registry.resolve("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB49").unwrap_err();
+        assert!(matches!(err, TokenRegistryError::InvalidAddress(_)));
+        assert!(err.to_string().contains("EIP-55"));
     }
 
     #[test]
@@ -247,6 +446,7 @@ mod tests {
             name: "Custom Token".to_string(),
             address: "0x1234567890123456789012345678901234567890".to_string(),
             decimals: 18,
+            listed_on: Vec::new(),
         };
 
         registry.register("CUSTOM".to_string(), custom.clone());
@@ -256,6 +456,167 @@ mod tests {
         assert_eq!(resolved.address, custom.address);
     }
 
+    #[test]
+    fn test_remove_token() {
+        let registry = TokenRegistry::new();
+        let address = "0x1234567890123456789012345678901234567890";
+        registry.register(
+            "Custom".to_string(),
+            TokenInfo {
+                symbol: "Custom".to_string(),
+                name: "Custom Token".to_string(),
+                address: address.to_string(),
+                decimals: 18,
+                listed_on: Vec::new(),
+            },
+        );
+
+        assert_eq!(registry.remove("custom").unwrap().name, "Custom Token");
+        assert!(!registry.contains("CUSTOM"));
+        // 地址缓存一并移除，按地址查询回到未知代币
+        assert_eq!(registry.resolve(address).unwrap().symbol, "UNKNOWN");
+        assert!(registry.remove("CUSTOM").is_none());
+
+        assert!(registry.remove("DAI").is_some());
+        assert!(registry.resolve("DAI").is_err());
+    }
+
+    #[test]
+    fn test_chain_specific_defaults() {
+        let registry = TokenRegistry::for_chain(&crate::chains::POLYGON);
+
+        // Polygon 上原生代币别名是 POL，映射到 WPOL
+        let pol = registry.resolve("POL").unwrap();
+        let wpol = registry.resolve("WPOL").unwrap();
+        assert_eq!(pol.address, wpol.address);
+        assert!(registry.resolve("ETH").is_err());
+
+        // USDC 使用 Polygon 原生 USDC 地址，主网专属代币不加载
+        assert_eq!(registry.resolve("USDC").unwrap().address, crate::chains::POLYGON.usdc);
+        assert!(registry.resolve("DAI").is_err());
+    }
+
+    fn entry(symbol: &str, address: &str, decimals: u8, chain_id: u64) -> TokenFileEntry {
+        TokenFileEntry {
+            symbol: symbol.to_string(),
+            name: format!("{} Token", symbol),
+            address: address.to_string(),
+            decimals,
+            chain_id,
+        }
+    }
+
+    #[test]
+    fn test_parse_token_entries() {
+        let addr = "0x1234567890123456789012345678901234567890";
+        let tokens = parse_token_entries(
+            vec![entry("FOO", addr, 9, 1), entry("BAR", addr, 18, 137)],
+            1,
+        )
+        .unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].1.symbol, "FOO");
+        assert_eq!(tokens[0].1.decimals, 9);
+
+        // 其他链的代币同样校验
+        let cases = [
+            entry("", addr, 18, 1),
+            entry("A B", addr, 18, 1),
+            entry("FOO", "0x1234", 18, 1),
+            entry("FOO", "0x0000000000000000000000000000000000000000", 18, 1),
+            entry("FOO", addr, 78, 137),
+        ];
+        for case in cases {
+            assert!(matches!(
+                parse_token_entries(vec![case], 1),
+                Err(TokenRegistryError::InvalidEntry { index: 1, .. })
+            ));
+        }
+
+        // 同一条链上的符号不区分大小写去重
+        let duplicated = parse_token_entries(vec![entry("FOO", addr, 18, 1), entry("foo", addr, 18, 1)], 1);
+        assert!(matches!(duplicated, Err(TokenRegistryError::InvalidEntry { index: 2, .. })));
+        assert!(parse_token_entries(vec![entry("FOO", addr, 18, 1), entry("FOO", addr, 18, 10)], 1).is_ok());
+    }
+
+    #[test]
+    fn test_load_token_file() {
+        let dir = std::env::temp_dir().join(format!("token-registry-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let json = dir.join("tokens.json");
+        std::fs::write(
+            &json,
+            r#"{"tokens": [
+                {"symbol": "FOO", "name": "Foo", "address": "0x1234567890123456789012345678901234567890", "decimals": 9, "chain_id": 1},
+                {"symbol": "USDC", "name": "Bridged USDC", "address": "0x2222222222222222222222222222222222222222", "decimals": 6, "chain_id": 1}
+            ]}"#,
+        )
+        .unwrap();
+        let registry = TokenRegistry::load(&MAINNET, json.to_str()).unwrap();
+        assert_eq!(registry.resolve("foo").unwrap().decimals, 9);
+        // 文件中的符号覆盖默认代币
+        assert_eq!(registry.resolve("USDC").unwrap().name, "Bridged USDC");
+        assert!(registry.contains("WETH"));
+
+        let toml = dir.join("tokens.toml");
+        std::fs::write(
+            &toml,
+            r#"
+[[tokens]]
+symbol = "BAR"
+name = "Bar"
+address = "0x1234567890123456789012345678901234567890"
+decimals = 18
+chain_id = 137
+"#,
+        )
+        .unwrap();
+        let registry = TokenRegistry::load(&crate::chains::POLYGON, toml.to_str()).unwrap();
+        assert!(registry.contains("BAR"));
+        assert!(!TokenRegistry::load(&MAINNET, toml.to_str()).unwrap().contains("BAR"));
+
+        // 缺少字段或未知字段都视为格式错误
+        std::fs::write(&json, r#"[{"symbol": "FOO", "address": "0x1234567890123456789012345678901234567890", "decimals": 9, "chain_id": 1}]"#).unwrap();
+        assert!(matches!(TokenRegistry::load(&MAINNET, json.to_str()), Err(TokenRegistryError::Parse(_))));
+        assert!(matches!(
+            TokenRegistry::load(&MAINNET, Some("/nonexistent/tokens.json")),
+            Err(TokenRegistryError::Io(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_merge_remote_tokens() {
+        let registry = TokenRegistry::new();
+        let token = |symbol: &str, address: &str| TokenInfo {
+            symbol: symbol.to_string(),
+            name: symbol.to_string(),
+            address: address.to_string(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+        let link = "0x514910771af9ca656af840dff83e8264ecf986ca";
+        let spoof = "0x1234567890123456789012345678901234567890";
+
+        // 远程列表不能覆盖内置代币，批次内重复符号取第一个
+        let merged = registry.merge_remote(vec![token("LINK", link), token("USDC", spoof), token("link", spoof)]);
+        assert_eq!(merged, 1);
+        assert_eq!(registry.resolve("LINK").unwrap().address, link);
+        assert_ne!(registry.resolve("USDC").unwrap().address, spoof);
+
+        // 刷新时更新远程代币，未变化的不计数
+        assert_eq!(registry.merge_remote(vec![token("LINK", link)]), 0);
+        assert_eq!(registry.merge_remote(vec![token("LINK", spoof)]), 1);
+        assert_eq!(registry.resolve("LINK").unwrap().address, spoof);
+
+        // 手动注册后远程列表不再覆盖
+        registry.register("LINK".to_string(), token("LINK", link));
+        assert_eq!(registry.merge_remote(vec![token("LINK", spoof)]), 0);
+        assert_eq!(registry.resolve("LINK").unwrap().address, link);
+    }
+
     #[test]
     fn test_all_tokens() {
         let registry = TokenRegistry::new();
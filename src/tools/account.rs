@@ -0,0 +1,144 @@
+use crate::{
+    config::Config,
+    erc20::format_units,
+    eth_client::EthClient,
+    logging::info,
+    types::{checksum_address, parse_address},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// GetAccountInfo 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetAccountInfoArgs {
+    /// 钱包或合约地址(必需)
+    pub address: String,
+}
+
+/// GetAccountInfo 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AccountInfoResult {
+    pub address: String,
+    /// 最新区块的 nonce
+    pub nonce: u64,
+    /// 包含内存池交易的 nonce,即下一笔交易应使用的 nonce
+    pub pending_nonce: u64,
+    /// 已确认发出的交易数(等于 nonce;合约地址为已创建的合约数 + 1)
+    pub transaction_count: u64,
+    /// 已广播但尚未打包的交易数
+    pub pending_transaction_count: u64,
+    /// 地址上是否有合约代码
+    pub is_contract: bool,
+    /// 合约代码大小(字节)
+    pub code_size: usize,
+    /// 原生代币余额(Wei)
+    pub balance: String,
+    pub formatted_balance: String,
+    pub native_symbol: String,
+}
+
+/// 一次返回地址的 nonce、交易数、是否为合约和原生代币余额,用于构造交易前的检查
+pub async fn get_account_info(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    Parameters(args): Parameters<GetAccountInfoArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_account_info 请求");
+
+    let address = parse_address(&args.address).map_err(|e| McpError::invalid_params(e, None))?;
+    let native_symbol = config.chain().native_symbol;
+
+    info!(address = ?address, "查询账户信息");
+
+    // 测试模式
+    if config.server.test_mode {
+        let result = build_account_info(address, 42, 43, 0, U256::exp10(18), native_symbol);
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let address_str = format!("{:?}", address);
+    let (nonce, pending_nonce, code, balance) = tokio::join!(
+        eth_client.get_transaction_count(address, BlockNumber::Latest),
+        eth_client.get_transaction_count(address, BlockNumber::Pending),
+        eth_client.get_code(address, None),
+        eth_client.get_balance(&address_str, None)
+    );
+    let nonce = nonce.map_err(|e| McpError::internal_error(format!("查询 nonce 失败: {}", e), None))?;
+    let pending_nonce = pending_nonce
+        .map_err(|e| McpError::internal_error(format!("查询 pending nonce 失败: {}", e), None))?;
+    let code = code.map_err(|e| McpError::internal_error(format!("查询合约代码失败: {}", e), None))?;
+    let balance = balance.map_err(|e| McpError::internal_error(format!("查询余额失败: {}", e), None))?;
+
+    let result = build_account_info(address, nonce, pending_nonce, code.len(), balance, native_symbol);
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        address = %result.address,
+        nonce = result.nonce,
+        pending_nonce = result.pending_nonce,
+        "成功返回账户信息"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 汇总账户信息(节点间不同步时 pending nonce 可能小于 latest nonce,按 latest 处理)
+fn build_account_info(
+    address: Address,
+    nonce: u64,
+    pending_nonce: u64,
+    code_size: usize,
+    balance: U256,
+    native_symbol: &str,
+) -> AccountInfoResult {
+    let pending_nonce = pending_nonce.max(nonce);
+    AccountInfoResult {
+        address: checksum_address(address),
+        nonce,
+        pending_nonce,
+        transaction_count: nonce,
+        pending_transaction_count: pending_nonce - nonce,
+        is_contract: code_size > 0,
+        code_size,
+        balance: balance.to_string(),
+        formatted_balance: format_units(balance, 18),
+        native_symbol: native_symbol.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_account_info() {
+        let info = build_account_info(Address::repeat_byte(0xab), 7, 9, 0, U256::exp10(17), "ETH");
+        assert_eq!(info.pending_nonce, 9);
+        assert_eq!(info.pending_transaction_count, 2);
+        assert!(!info.is_contract);
+        assert_eq!(info.formatted_balance, "0.1");
+
+        // pending nonce 落后于 latest 时不产生负数
+        let info = build_account_info(Address::repeat_byte(0xab), 7, 5, 1024, U256::zero(), "ETH");
+        assert_eq!(info.pending_nonce, 7);
+        assert_eq!(info.pending_transaction_count, 0);
+        assert!(info.is_contract);
+    }
+}
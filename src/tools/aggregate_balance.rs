@@ -0,0 +1,267 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    eth_client::EthClient,
+    export::{self, CsvExport, ExportFormat},
+    logging::info,
+    token_registry::TokenRegistry,
+    types::{checksum_address, parse_address, TokenInfo},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 单次查询允许的最大钱包数量
+const MAX_WALLETS: usize = 100;
+
+/// GetAggregateBalance 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetAggregateBalanceArgs {
+    /// 钱包地址列表(必需,最多 100 个)
+    pub addresses: Vec<String>,
+    /// ERC20 代币地址或符号(可选,不填则查询 ETH 余额)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_address: Option<String>,
+    /// 导出格式(可选,json/csv,默认 json)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export: Option<String>,
+}
+
+/// GetAggregateBalance 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AggregateBalanceResult {
+    pub token: TokenInfo,
+    pub wallet_count: usize,
+    pub total_balance: String,
+    pub decimals: u8,
+    pub formatted_total_balance: String,
+    pub wallets: Vec<WalletBalance>,
+}
+
+/// 单个钱包的余额
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct WalletBalance {
+    pub address: String,
+    pub balance: String,
+    pub formatted_balance: String,
+}
+
+/// 汇总多个钱包的余额(ETH 或 ERC20)
+pub async fn get_aggregate_balance(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<GetAggregateBalanceArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_aggregate_balance 请求");
+
+    let export_format = ExportFormat::parse(args.export.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    if args.addresses.is_empty() {
+        return Err(McpError::invalid_params("地址列表不能为空", None));
+    }
+
+    if args.addresses.len() > MAX_WALLETS {
+        return Err(McpError::invalid_params(
+            format!("地址数量过多: {} (最多 {} 个)", args.addresses.len(), MAX_WALLETS),
+            None,
+        ));
+    }
+
+    info!(
+        wallets = args.addresses.len(),
+        token = ?args.token_address,
+        "汇总钱包余额"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let token = if args.token_address.is_some() {
+            TokenInfo {
+                symbol: "TEST".to_string(),
+                name: "Test Token".to_string(),
+                address: args.token_address.clone().unwrap_or_default(),
+                decimals: 18,
+                listed_on: Vec::new(),
+            }
+        } else {
+            TokenInfo::eth()
+        };
+
+        let balances = vec![U256::exp10(20); args.addresses.len()];
+        let result = build_aggregate_result(token, &args.addresses, &balances);
+
+        return export::render(&result, export_format);
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    // 解析钱包地址
+    let wallet_addrs = args
+        .addresses
+        .iter()
+        .map(|addr| parse_address(addr).map_err(|e| McpError::invalid_params(e, None)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let addresses: Vec<String> = wallet_addrs.iter().map(|addr| checksum_address(*addr)).collect();
+
+    let (token_info, balances) = if let Some(ref token_address) = args.token_address {
+        let mut token_info = token_registry
+            .resolve(token_address)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+        let token_addr: Address = token_info.address.parse().map_err(|_| {
+            McpError::internal_error("无效的代币地址".to_string(), None)
+        })?;
+
+        // 🔍 动态查询未知代币信息
+        if token_info.symbol == "UNKNOWN" && erc20_client.is_available() {
+            let erc20_client_clone = erc20_client.clone();
+            let real_info = erc20_client_clone
+                .token_info(token_addr)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
+
+            // 缓存到注册表
+            token_registry.register(real_info.symbol.clone(), real_info.clone());
+            token_info = real_info;
+        }
+
+        let queries: Vec<(Address, Address)> =
+            wallet_addrs.iter().map(|owner| (token_addr, *owner)).collect();
+        let erc20_client = erc20_client.clone();
+
+        let results = async { erc20_client.balances_of(&queries).await        }
+        .await
+        .map_err(|e| McpError::internal_error(format!("批量查询 ERC20 余额失败: {}", e), None))?;
+
+        let balances = results
+            .into_iter()
+            .zip(&addresses)
+            .map(|(balance, addr)| {
+                balance.ok_or_else(|| {
+                    McpError::internal_error(format!("查询 {} 的 ERC20 余额失败", addr), None)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        (token_info, balances)
+    } else {
+        let eth_client = eth_client.clone();
+
+        let balances = async { eth_client.get_balances(&wallet_addrs).await        }
+        .await
+        .map_err(|e| McpError::internal_error(format!("批量查询 ETH 余额失败: {}", e), None))?;
+
+        (TokenInfo::native(config.chain()), balances)
+    };
+
+    let result = build_aggregate_result(token_info, &addresses, &balances);
+
+    info!("成功返回汇总余额");
+
+    export::render(&result, export_format)
+}
+
+impl CsvExport for AggregateBalanceResult {
+    fn csv_headers(&self) -> Vec<&'static str> {
+        vec!["address", "token", "token_address", "balance", "formatted_balance"]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        self.wallets
+            .iter()
+            .map(|w| {
+                vec![
+                    w.address.clone(),
+                    self.token.symbol.clone(),
+                    self.token.address.clone(),
+                    w.balance.clone(),
+                    w.formatted_balance.clone(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// 汇总各钱包余额并格式化
+fn build_aggregate_result(
+    token: TokenInfo,
+    addresses: &[String],
+    balances: &[U256],
+) -> AggregateBalanceResult {
+    let decimals = token.decimals;
+    let total = balances
+        .iter()
+        .fold(U256::zero(), |acc, b| acc.saturating_add(*b));
+
+    let wallets = addresses
+        .iter()
+        .zip(balances)
+        .map(|(address, balance)| WalletBalance {
+            address: address.clone(),
+            balance: balance.to_string(),
+            formatted_balance: format_units(*balance, decimals),
+        })
+        .collect();
+
+    AggregateBalanceResult {
+        token,
+        wallet_count: addresses.len(),
+        total_balance: total.to_string(),
+        decimals,
+        formatted_total_balance: format_units(total, decimals),
+        wallets,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_aggregate_result() {
+        let addresses = vec!["0x1".to_string(), "0x2".to_string()];
+        let balances = vec![
+            U256::from(1_500_000_000_000_000_000u64),
+            U256::from(500_000_000_000_000_000u64),
+        ];
+
+        let result = build_aggregate_result(TokenInfo::eth(), &addresses, &balances);
+        assert_eq!(result.wallet_count, 2);
+        assert_eq!(result.formatted_total_balance, "2");
+        assert_eq!(result.wallets[0].formatted_balance, "1.5");
+        assert_eq!(result.wallets[1].formatted_balance, "0.5");
+    }
+
+    #[test]
+    fn test_get_aggregate_balance_args_deserialization() {
+        let json = r#"{"addresses":["0x1","0x2"],"token_address":"USDC"}"#;
+        let args: GetAggregateBalanceArgs = serde_json::from_str(json).expect("应该能反序列化");
+        assert_eq!(args.addresses.len(), 2);
+        assert_eq!(args.token_address, Some("USDC".to_string()));
+        assert_eq!(args.export, None);
+    }
+
+    #[test]
+    fn test_aggregate_result_csv() {
+        let addresses = vec!["0x1".to_string()];
+        let balances = vec![U256::from(1_000_000_000_000_000_000u64)];
+        let result = build_aggregate_result(TokenInfo::eth(), &addresses, &balances);
+
+        let csv = export::to_csv(&result);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "address,token,token_address,balance,formatted_balance");
+        assert!(lines[1].starts_with("0x1,ETH,"));
+        assert!(lines[1].ends_with(",1000000000000000000,1"));
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    logging::info,
+    token_registry::TokenRegistry,
+    tools::user_operation::{resolve_token, token_address},
+    types::{checksum_address, parse_address, TokenInfo},
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// GetAllowance 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetAllowanceArgs {
+    /// ERC20 代币地址或符号(必需)
+    pub token: String,
+    /// 授权方钱包地址(必需)
+    pub owner: String,
+    /// 被授权方地址(可选,默认 Uniswap V2 Router)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spender: Option<String>,
+}
+
+/// GetAllowance 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AllowanceResult {
+    pub token: TokenInfo,
+    pub owner: String,
+    pub spender: String,
+    /// 被授权方为 Uniswap V2 Router 时为 true
+    pub spender_is_router: bool,
+    /// 原始授权额度(最小单位)
+    pub allowance: String,
+    pub formatted_allowance: String,
+    /// 无限授权(额度不低于 2^255,常见的 type(uint256).max 授权)
+    pub unlimited: bool,
+}
+
+/// 查询 ERC20 授权额度
+pub async fn get_allowance(
+    config: &Arc<Config>,
+    erc20_client: &Arc<Erc20Client>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<GetAllowanceArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_allowance 请求");
+
+    let owner = parse_address(&args.owner).map_err(|e| McpError::invalid_params(e, None))?;
+    let router = uniswap_client.router_address();
+    let spender = match args.spender.as_deref() {
+        Some(spender) => parse_address(spender).map_err(|e| McpError::invalid_params(e, None))?,
+        None => router,
+    };
+
+    info!(token = %args.token, owner = ?owner, spender = ?spender, "查询授权额度");
+
+    // 测试模式
+    if config.server.test_mode {
+        let token = TokenInfo {
+            symbol: "TEST".to_string(),
+            name: "Test Token".to_string(),
+            address: args.token.clone(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+        let result = build_result(token, owner, spender, router, U256::exp10(20));
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !erc20_client.is_available() {
+        return Err(McpError::internal_error(
+            "ERC20 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+
+    let result = async {
+        let token_info = resolve_token(&token_registry, &erc20_client, &args.token).await?;
+        let token_addr = token_address(&token_info)?;
+
+        let allowance = erc20_client
+            .allowance(token_addr, owner, spender)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询授权额度失败: {}", e), None))?;
+
+        Ok::<_, McpError>(build_result(token_info, owner, spender, router, allowance))
+    }
+    .await?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(allowance = %result.allowance, "成功返回授权额度");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+fn build_result(
+    token: TokenInfo,
+    owner: Address,
+    spender: Address,
+    router: Address,
+    allowance: U256,
+) -> AllowanceResult {
+    AllowanceResult {
+        owner: checksum_address(owner),
+        spender: checksum_address(spender),
+        spender_is_router: spender == router,
+        allowance: allowance.to_string(),
+        formatted_allowance: format_units(allowance, token.decimals),
+        unlimited: allowance.bit(255),
+        token,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_result_flags() {
+        let usdc = TokenInfo {
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            address: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+            decimals: 6,
+            listed_on: Vec::new(),
+        };
+        let owner = Address::repeat_byte(0x01);
+        let router = Address::repeat_byte(0x02);
+
+        let result = build_result(usdc.clone(), owner, router, router, U256::from(1_500_000u64));
+        assert!(result.spender_is_router);
+        assert_eq!(result.allowance, "1500000");
+        assert_eq!(result.formatted_allowance, "1.5");
+        assert!(!result.unlimited);
+
+        let result = build_result(usdc, owner, Address::repeat_byte(0x03), router, U256::MAX);
+        assert!(!result.spender_is_router);
+        assert!(result.unlimited);
+    }
+
+    #[test]
+    fn test_get_allowance_args_deserialization() {
+        let args: GetAllowanceArgs =
+            serde_json::from_str(r#"{"token":"USDC","owner":"0x123"}"#).unwrap();
+        assert_eq!(args.token, "USDC");
+        assert!(args.spender.is_none());
+    }
+}
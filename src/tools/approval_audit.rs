@@ -0,0 +1,477 @@
+use crate::{
+    config::Config,
+    cow::COW_VAULT_RELAYER,
+    erc20::{format_units, ApprovalLog, Erc20Client},
+    eth_client::EthClient,
+    logging::{info, warn},
+    token_registry::TokenRegistry,
+    tools::user_operation::{resolve_token, token_address},
+    types::{checksum_address, parse_address, TokenInfo},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// 未指定 from_block 时回溯的区块数(约一个月)
+const DEFAULT_APPROVAL_RANGE_BLOCKS: u64 = 200_000;
+/// 单次扫描允许的最大区块区间
+const MAX_APPROVAL_RANGE_BLOCKS: u64 = 1_000_000;
+/// Uniswap Permit2(所有链上地址相同)
+const PERMIT2_ADDRESS: &str = "0x000000000022D473030F116dDEE9F6B43aC78BA3";
+
+/// ApprovalAudit 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ApprovalAuditArgs {
+    /// 钱包地址(必需)
+    pub wallet: String,
+    /// 代币地址或符号列表(可选,默认代币注册表中的所有代币)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<Vec<String>>,
+    /// 起始区块(可选,默认 to_block 前 200000 个区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_block: Option<u64>,
+    /// 结束区块(可选,默认最新区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_block: Option<u64>,
+}
+
+/// ApprovalAudit 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ApprovalAuditResult {
+    pub wallet: String,
+    /// Approval 事件扫描区间
+    pub from_block: u64,
+    pub to_block: u64,
+    pub tokens_scanned: usize,
+    /// 当前仍有效(额度大于 0)的授权,高风险在前
+    pub approvals: Vec<ApprovalEntry>,
+    pub unlimited_count: usize,
+    pub high_risk_count: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 单个代币对单个被授权方的授权
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ApprovalEntry {
+    pub token_symbol: String,
+    pub token_address: String,
+    pub spender: String,
+    /// 已知被授权方的名称(如 Uniswap V2 Router)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spender_label: Option<String>,
+    /// 被授权方地址上是否有合约代码(查询失败时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spender_is_contract: Option<bool>,
+    /// 当前授权额度(最小单位)
+    pub allowance: String,
+    pub formatted_allowance: String,
+    /// 无限授权(额度不低于 2^255)
+    pub unlimited: bool,
+    /// 扫描区间内最近一次 Approval 事件的区块(区间外的授权为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_approved_block: Option<u64>,
+    /// high / medium / low
+    pub risk_level: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub risk_notes: Vec<String>,
+}
+
+/// 扫描钱包的 Approval 事件,列出当前仍有效的 ERC20 授权及风险提示
+pub async fn approval_audit(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<ApprovalAuditArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 approval_audit 请求");
+
+    let wallet = parse_address(&args.wallet).map_err(|e| McpError::invalid_params(e, None))?;
+    if args.tokens.as_ref().is_some_and(Vec::is_empty) {
+        return Err(McpError::invalid_params("tokens 不能为空列表", None));
+    }
+    if let (Some(from_block), Some(to_block)) = (args.from_block, args.to_block)
+        && from_block > to_block
+    {
+        return Err(McpError::invalid_params(
+            format!("from_block ({}) 不能大于 to_block ({})", from_block, to_block),
+            None,
+        ));
+    }
+
+    info!(
+        wallet = ?wallet,
+        tokens = ?args.tokens,
+        from_block = ?args.from_block,
+        to_block = ?args.to_block,
+        "扫描钱包授权"
+    );
+
+    let known = known_spenders(config);
+
+    // 测试模式
+    if config.server.test_mode {
+        let token = TokenInfo {
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            address: checksum_address(Address::repeat_byte(0x11)),
+            decimals: 6,
+            listed_on: Vec::new(),
+        };
+        let router = config.chain().uniswap_v2().router_address();
+        let to_block = args.to_block.unwrap_or(20_000_000);
+        let approvals = vec![
+            build_entry(&token, router, U256::MAX, Some(to_block), known.get(&router).cloned(), Some(true)),
+            build_entry(&token, Address::repeat_byte(0x22), U256::from(5_000_000u64), None, None, Some(false)),
+        ];
+
+        let result = build_result(
+            wallet,
+            args.from_block.unwrap_or(to_block.saturating_sub(DEFAULT_APPROVAL_RANGE_BLOCKS - 1)),
+            to_block,
+            1,
+            approvals,
+            Vec::new(),
+        );
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() || !erc20_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    // 指定代币列表时只扫描这些代币,否则扫描注册表中的全部代币(同一地址的别名只保留一个)
+    let tokens: HashMap<Address, TokenInfo> = match args.tokens {
+        Some(ref queries) => {
+            let mut tokens = HashMap::new();
+            for query in queries {
+                let info = resolve_token(token_registry, erc20_client, query).await?;
+                tokens.insert(token_address(&info)?, info);
+            }
+            tokens
+        }
+        None => token_registry
+            .all_tokens()
+            .into_iter()
+            .filter_map(|info| Some((info.address.parse().ok()?, info)))
+            .collect(),
+    };
+    let token_addrs: Vec<Address> = tokens.keys().copied().collect();
+
+    let to_block = match args.to_block {
+        Some(block) => block,
+        None => eth_client
+            .get_block_number()
+            .await
+            .map_err(|e| McpError::internal_error(format!("获取区块高度失败: {}", e), None))?,
+    };
+    let from_block = args
+        .from_block
+        .unwrap_or(to_block.saturating_sub(DEFAULT_APPROVAL_RANGE_BLOCKS - 1))
+        .min(to_block);
+    let range_blocks = to_block - from_block + 1;
+    if range_blocks > MAX_APPROVAL_RANGE_BLOCKS {
+        return Err(McpError::invalid_params(
+            format!(
+                "区块区间过大: {} 个区块(最多 {} 个),请缩小区间分段扫描",
+                range_blocks, MAX_APPROVAL_RANGE_BLOCKS
+            ),
+            None,
+        ));
+    }
+
+    let mut notes = Vec::new();
+    let logs = erc20_client
+        .approval_logs(wallet, &token_addrs, from_block, to_block)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询 Approval 事件失败: {}", e), None))?;
+
+    // 扫描区间外的授权无法从事件发现,已知被授权方总是直接查询当前额度
+    let candidates = candidate_pairs(&logs, &token_addrs, &known);
+    let queries: Vec<_> = candidates
+        .keys()
+        .map(|(token, spender)| (*token, wallet, *spender))
+        .collect();
+    let allowances = erc20_client
+        .allowances(&queries)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询授权额度失败: {}", e), None))?;
+
+    let failed = allowances.iter().filter(|allowance| allowance.is_none()).count();
+    if failed > 0 {
+        notes.push(format!("{} 个授权额度查询失败(代币合约可能不是标准 ERC20),已忽略", failed));
+    }
+
+    let outstanding: Vec<_> = candidates
+        .iter()
+        .zip(allowances)
+        .filter_map(|((&(token, spender), &last_block), allowance)| {
+            allowance
+                .filter(|allowance| !allowance.is_zero())
+                .map(|allowance| (token, spender, allowance, last_block))
+        })
+        .collect();
+
+    let mut spenders: Vec<Address> = outstanding.iter().map(|(_, spender, _, _)| *spender).collect();
+    spenders.sort();
+    spenders.dedup();
+    let spender_is_contract = contract_flags(eth_client, &spenders).await;
+
+    let approvals = outstanding
+        .into_iter()
+        .map(|(token, spender, allowance, last_block)| {
+            build_entry(
+                &tokens[&token],
+                spender,
+                allowance,
+                last_block,
+                known.get(&spender).cloned(),
+                spender_is_contract.get(&spender).copied(),
+            )
+        })
+        .collect();
+
+    let result = build_result(wallet, from_block, to_block, tokens.len(), approvals, notes);
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        approvals = result.approvals.len(),
+        unlimited = result.unlimited_count,
+        high_risk = result.high_risk_count,
+        "成功返回钱包授权审计结果"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 当前链上常见的被授权方(DEX Router、Permit2、CoW Protocol)
+fn known_spenders(config: &Config) -> HashMap<Address, String> {
+    let mut known: HashMap<Address, String> = config
+        .chain()
+        .v2_venues()
+        .into_iter()
+        .map(|venue| (venue.router_address(), format!("{} Router", venue.name)))
+        .collect();
+
+    let fixed = [
+        (config.uniswap.v3_router.as_str(), "Uniswap V3 Router"),
+        (PERMIT2_ADDRESS, "Uniswap Permit2"),
+        (COW_VAULT_RELAYER, "CoW Protocol Vault Relayer"),
+    ];
+    for (address, label) in fixed {
+        if let Ok(address) = address.parse::<Address>() {
+            known.entry(address).or_insert_with(|| label.to_string());
+        }
+    }
+    known
+}
+
+/// 需要查询额度的 (代币, 被授权方),值为扫描区间内最近一次 Approval 的区块
+fn candidate_pairs(
+    logs: &[ApprovalLog],
+    tokens: &[Address],
+    known: &HashMap<Address, String>,
+) -> BTreeMap<(Address, Address), Option<u64>> {
+    let mut pairs: BTreeMap<(Address, Address), Option<u64>> = tokens
+        .iter()
+        .flat_map(|token| known.keys().map(move |spender| ((*token, *spender), None)))
+        .collect();
+
+    for log in logs {
+        let last = pairs.entry((log.token, log.spender)).or_default();
+        *last = (*last).max(Some(log.block_number));
+    }
+    pairs
+}
+
+/// 并发查询被授权方是否为合约(单个查询失败时不返回该地址)
+async fn contract_flags(eth_client: &Arc<EthClient>, addresses: &[Address]) -> HashMap<Address, bool> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for address in addresses {
+        let eth_client = eth_client.clone();
+        let address = *address;
+        tasks.spawn(async move { (address, eth_client.get_code(address, None).await) });
+    }
+
+    let mut flags = HashMap::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((address, Ok(code))) => {
+                flags.insert(address, !code.is_empty());
+            }
+            Ok((address, Err(e))) => warn!(spender = ?address, error = %e, "查询被授权方合约代码失败"),
+            Err(e) => warn!(error = %e, "查询被授权方合约代码的任务失败"),
+        }
+    }
+    flags
+}
+
+/// 按额度、被授权方是否已知以及是否为合约评估风险
+fn assess_risk(unlimited: bool, known: bool, is_contract: Option<bool>) -> (&'static str, Vec<String>) {
+    let mut notes = Vec::new();
+    if unlimited {
+        notes.push("无限授权:被授权方可以随时转走该代币的全部余额".to_string());
+    }
+    if !known {
+        notes.push("未知的被授权方,请确认是否为你使用过的协议".to_string());
+    }
+    let externally_owned = is_contract == Some(false);
+    if externally_owned {
+        notes.push("被授权方是普通地址而不是合约,常见于钓鱼授权".to_string());
+    }
+
+    let level = if externally_owned || (unlimited && !known) {
+        "high"
+    } else if unlimited || !known {
+        "medium"
+    } else {
+        "low"
+    };
+    (level, notes)
+}
+
+fn build_entry(
+    token: &TokenInfo,
+    spender: Address,
+    allowance: U256,
+    last_approved_block: Option<u64>,
+    spender_label: Option<String>,
+    spender_is_contract: Option<bool>,
+) -> ApprovalEntry {
+    let unlimited = allowance.bit(255);
+    let (risk_level, risk_notes) = assess_risk(unlimited, spender_label.is_some(), spender_is_contract);
+
+    ApprovalEntry {
+        token_symbol: token.symbol.clone(),
+        token_address: token.address.clone(),
+        spender: checksum_address(spender),
+        spender_label,
+        spender_is_contract,
+        allowance: allowance.to_string(),
+        formatted_allowance: if unlimited {
+            "unlimited".to_string()
+        } else {
+            format_units(allowance, token.decimals)
+        },
+        unlimited,
+        last_approved_block,
+        risk_level: risk_level.to_string(),
+        risk_notes,
+    }
+}
+
+/// 汇总授权列表,按风险等级(高在前)和代币符号排序
+fn build_result(
+    wallet: Address,
+    from_block: u64,
+    to_block: u64,
+    tokens_scanned: usize,
+    mut approvals: Vec<ApprovalEntry>,
+    mut notes: Vec<String>,
+) -> ApprovalAuditResult {
+    let rank = |level: &str| match level {
+        "high" => 0,
+        "medium" => 1,
+        _ => 2,
+    };
+    approvals.sort_by(|a, b| {
+        rank(&a.risk_level)
+            .cmp(&rank(&b.risk_level))
+            .then_with(|| a.token_symbol.cmp(&b.token_symbol))
+            .then_with(|| a.spender.cmp(&b.spender))
+    });
+
+    let unlimited_count = approvals.iter().filter(|approval| approval.unlimited).count();
+    let high_risk_count = approvals.iter().filter(|approval| approval.risk_level == "high").count();
+    if high_risk_count > 0 {
+        notes.push("存在高风险授权,可使用 approve_token 将额度设为 0 撤销".to_string());
+    }
+
+    ApprovalAuditResult {
+        wallet: checksum_address(wallet),
+        from_block,
+        to_block,
+        tokens_scanned,
+        approvals,
+        unlimited_count,
+        high_risk_count,
+        notes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assess_risk() {
+        assert_eq!(assess_risk(false, true, Some(true)), ("low", Vec::new()));
+        assert_eq!(assess_risk(true, true, Some(true)).0, "medium");
+        assert_eq!(assess_risk(false, false, Some(true)).0, "medium");
+        assert_eq!(assess_risk(true, false, None).0, "high");
+
+        let (level, notes) = assess_risk(false, false, Some(false));
+        assert_eq!(level, "high");
+        assert_eq!(notes.len(), 2);
+    }
+
+    #[test]
+    fn test_candidate_pairs() {
+        let usdc = Address::repeat_byte(0x11);
+        let router = Address::repeat_byte(0x22);
+        let drainer = Address::repeat_byte(0x33);
+        let known = HashMap::from([(router, "Uniswap V2 Router".to_string())]);
+        let log = |spender, block_number| ApprovalLog {
+            token: usdc,
+            owner: Address::repeat_byte(0xaa),
+            spender,
+            value: U256::MAX,
+            block_number,
+            log_index: 0,
+        };
+
+        let pairs = candidate_pairs(&[log(drainer, 10), log(drainer, 30), log(drainer, 20)], &[usdc], &known);
+        assert_eq!(pairs.len(), 2);
+        // 已知被授权方即使区间内没有事件也会查询
+        assert_eq!(pairs[&(usdc, router)], None);
+        assert_eq!(pairs[&(usdc, drainer)], Some(30));
+    }
+
+    #[test]
+    fn test_build_result_orders_and_counts() {
+        let usdc = TokenInfo {
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            address: checksum_address(Address::repeat_byte(0x11)),
+            decimals: 6,
+            listed_on: Vec::new(),
+        };
+        let router = Address::repeat_byte(0x22);
+        let approvals = vec![
+            build_entry(&usdc, router, U256::from(2_500_000u64), None, Some("Uniswap V2 Router".to_string()), Some(true)),
+            build_entry(&usdc, Address::repeat_byte(0x33), U256::MAX, Some(5), None, Some(false)),
+        ];
+
+        let result = build_result(Address::repeat_byte(0xaa), 0, 10, 1, approvals, Vec::new());
+        assert_eq!(result.approvals[0].risk_level, "high");
+        assert_eq!(result.approvals[0].formatted_allowance, "unlimited");
+        assert_eq!(result.approvals[1].formatted_allowance, "2.5");
+        assert_eq!(result.unlimited_count, 1);
+        assert_eq!(result.high_risk_count, 1);
+        assert_eq!(result.notes.len(), 1);
+    }
+}
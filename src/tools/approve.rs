@@ -0,0 +1,367 @@
+use crate::{
+    compliance::{ComplianceScreen, ScreeningDecision},
+    config::Config,
+    erc20::{approve_calldata, format_units, parse_units, Erc20Client},
+    eth_client::EthClient,
+    logging::info,
+    safe::{SafeClient, SafeProposal},
+    store::Store,
+    token_registry::TokenRegistry,
+    tools::execute_swap::{gas_limit_with_buffer, receipt_status, record_ledger_fill, RECEIPT_TIMEOUT},
+    tools::user_operation::{resolve_token, token_address},
+    signer::TxSigner,
+    tx_manager::TxManager,
+    types::{checksum_address, parse_address, TokenInfo, TxType},
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// ApproveToken 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ApproveTokenArgs {
+    /// ERC20 代币地址或符号(必需)
+    pub token: String,
+    /// 授权数量(必需,"max" 表示无限授权)
+    pub amount: String,
+    /// 被授权方地址(可选,默认 Uniswap V2 Router)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spender: Option<String>,
+    /// 交易类型(可选,auto/legacy/eip1559,默认使用 TX_TYPE 配置)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<String>,
+    /// 为 true 时使用配置的签名器签名并广播(可选,默认 false,只构建和模拟)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<bool>,
+}
+
+/// ApproveToken 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ApproveTokenResult {
+    pub token: TokenInfo,
+    pub owner: String,
+    pub spender: String,
+    pub spender_is_router: bool,
+    /// 授权数量(最小单位)
+    pub amount: String,
+    pub formatted_amount: String,
+    pub unlimited: bool,
+    /// 当前授权额度(最小单位)
+    pub current_allowance: String,
+    /// approve(spender, amount) calldata
+    pub calldata: String,
+    pub tx_type: String,
+    /// eth_call 模拟是否成功(回滚或返回 false 时为 false)
+    pub simulation_success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulation_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_estimate: Option<String>,
+    /// 是否已签名并广播
+    pub sent: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    /// success / reverted / pending(仅广播后返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// 返回时的确认数(仅广播后返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// 制裁名单命中但只标记时的筛查结论
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ScreeningDecision>,
+    /// Safe 模式下的 Safe 交易数据和提议结果
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub safe: Option<SafeProposal>,
+}
+
+/// 构建并模拟 ERC20 授权交易,确认后签名广播(Safe 模式下确认后提议到 Safe 交易服务)
+#[allow(clippy::too_many_arguments)]
+pub async fn approve_token(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    token_registry: &Arc<TokenRegistry>,
+    store: &Arc<Store>,
+    compliance: &Arc<ComplianceScreen>,
+    tx_manager: &Arc<TxManager>,
+    safe_client: &Arc<SafeClient>,
+    signer: Option<&TxSigner>,
+    Parameters(args): Parameters<ApproveTokenArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 approve_token 请求");
+
+    let confirm = args.confirm.unwrap_or(false);
+    let router = uniswap_client.router_address();
+    let spender = match args.spender.as_deref() {
+        Some(spender) => parse_address(spender).map_err(|e| McpError::invalid_params(e, None))?,
+        None => router,
+    };
+
+    let tx_type_preference = config
+        .tx_type_preference(args.tx_type.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    // 广播需要签名器,只模拟时以签名器地址或默认模拟地址作为 owner
+    // Safe 模式下 owner 为 Safe,签名器(可选)只用于提议
+    let safe = safe_client.safe_address();
+    let wallet = if confirm && safe.is_none() {
+        Some(signer.ok_or_else(|| McpError::invalid_params("未配置签名器,无法广播授权交易", None))?)
+    } else {
+        None
+    };
+    let owner = safe
+        .or(wallet.as_ref().map(|wallet| wallet.address()))
+        .unwrap_or_else(|| config.get_simulation_address());
+
+    info!(
+        token = %args.token,
+        amount = %args.amount,
+        spender = ?spender,
+        confirm,
+        "构建授权交易"
+    );
+
+    // 测试模式:不访问 RPC,也不签名
+    if config.server.test_mode {
+        let token = TokenInfo {
+            symbol: "TEST".to_string(),
+            name: "Test Token".to_string(),
+            address: args.token.clone(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+        let amount = parse_approve_amount(&args.amount, token.decimals)?;
+
+        let result = ApproveTokenResult {
+            owner: checksum_address(owner),
+            spender: checksum_address(spender),
+            spender_is_router: spender == router,
+            amount: amount.to_string(),
+            formatted_amount: format_units(amount, token.decimals),
+            unlimited: amount == U256::MAX,
+            current_allowance: "0".to_string(),
+            calldata: format!("{}", Bytes::from(approve_calldata(spender, amount))),
+            tx_type: tx_type_preference.unwrap_or(TxType::Eip1559).as_str().to_string(),
+            simulation_success: true,
+            simulation_error: None,
+            gas_estimate: Some("46000".to_string()),
+            sent: confirm,
+            tx_hash: confirm.then(|| format!("{:?}", H256::zero())),
+            nonce: confirm.then_some(0),
+            status: confirm.then(|| "success".to_string()),
+            confirmations: confirm.then_some(config.trading.tx_confirmations),
+            block_number: None,
+            compliance: None,
+            safe: None,
+            token,
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() || !erc20_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    // 🛡️ 制裁名单筛查
+    let screening = compliance.screen(store, "approve_token", &[("wallet", owner), ("spender", spender)])?;
+
+    let eth_client = eth_client.clone();
+    let tx_manager = tx_manager.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+    let gas_strategy = config.trading.gas_price_strategy.clone();
+    let max_gas_limit = config.trading.max_gas_limit;
+    let chain_id = config.ethereum.chain_id;
+
+    let mut result = async {
+        let token_info = resolve_token(&token_registry, &erc20_client, &args.token).await?;
+        let token_addr = token_address(&token_info)?;
+        let amount = parse_approve_amount(&args.amount, token_info.decimals)?;
+
+        let current_allowance = erc20_client
+            .allowance(token_addr, owner, spender)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询授权额度失败: {}", e), None))?;
+
+        let tx_type = eth_client
+            .resolve_tx_type(tx_type_preference)
+            .await
+            .map_err(|e| McpError::internal_error(format!("探测交易类型失败: {}", e), None))?;
+
+        let calldata = Bytes::from(approve_calldata(spender, amount));
+        let mut tx = tx_type.new_request();
+        tx.set_from(owner).set_to(token_addr).set_data(calldata.clone());
+
+        // 模拟:回滚或返回 false 都视为失败
+        let simulation = match eth_client.call(&tx).await {
+            Ok(output) if approve_succeeded(&output) => Ok(()),
+            Ok(_) => Err("approve 返回 false".to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let gas_estimate = match simulation {
+            Ok(()) => Some(eth_client.estimate_gas(&tx).await.map_err(|e| {
+                McpError::internal_error(format!("估算 Gas 失败: {}", e), None)
+            })?),
+            Err(_) => None,
+        };
+
+        let mut result = ApproveTokenResult {
+            owner: checksum_address(owner),
+            spender: checksum_address(spender),
+            spender_is_router: spender == router,
+            amount: amount.to_string(),
+            formatted_amount: format_units(amount, token_info.decimals),
+            unlimited: amount == U256::MAX,
+            current_allowance: current_allowance.to_string(),
+            calldata: format!("{}", calldata),
+            tx_type: tx_type.as_str().to_string(),
+            simulation_success: simulation.is_ok(),
+            simulation_error: simulation.as_ref().err().cloned(),
+            gas_estimate: gas_estimate.map(|gas| gas.to_string()),
+            sent: false,
+            tx_hash: None,
+            nonce: None,
+            status: None,
+            confirmations: None,
+            block_number: None,
+            compliance: None,
+            safe: None,
+            token: token_info,
+        };
+
+        // 🔐 Safe 模式:不广播,返回 Safe 交易数据,确认时签名并提议
+        if safe.is_some() {
+            if let (true, Err(reason)) = (confirm, simulation) {
+                return Err(simulation_refusal(reason));
+            }
+            let proposal = safe_client
+                .prepare(token_addr, U256::zero(), calldata, signer.filter(|_| confirm))
+                .await
+                .map_err(|e| McpError::internal_error(format!("构建 Safe 交易失败: {}", e), None))?;
+            result.safe = Some(proposal);
+            return Ok(result);
+        }
+
+        let (Some(wallet), Some(gas_estimate)) = (wallet, gas_estimate) else {
+            // 模拟失败时不广播
+            if let (true, Err(reason)) = (confirm, simulation) {
+                return Err(simulation_refusal(reason));
+            }
+            return Ok(result);
+        };
+
+        let gas_limit = gas_limit_with_buffer(gas_estimate, max_gas_limit)?;
+        let fees = eth_client
+            .estimate_tx_fees(&gas_strategy, tx_type)
+            .await
+            .map_err(|e| McpError::internal_error(format!("估算 Gas 费用失败: {}", e), None))?;
+        fees.apply(&mut tx);
+        tx.set_gas(gas_limit).set_chain_id(chain_id);
+
+        let submission = tx_manager
+            .submit("approve_token", wallet, tx, RECEIPT_TIMEOUT)
+            .await
+            .map_err(|e| McpError::internal_error(format!("广播交易失败: {}", e), None))?;
+        let receipt = submission.receipt.as_ref();
+
+        result.sent = true;
+        result.tx_hash = Some(format!("{:?}", submission.tx_hash));
+        result.nonce = Some(submission.nonce);
+        result.status = Some(receipt_status(receipt).to_string());
+        result.confirmations = Some(submission.confirmations);
+        result.block_number = receipt.and_then(|r| r.block_number).map(|n| n.as_u64());
+
+        // 📒 授权不改变代币持仓,只把消耗的 Gas 作为原生代币支出写入成本台账
+        if let Some(receipt) = receipt {
+            let gas_wei = receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default();
+            record_ledger_fill(
+                store,
+                &eth_client,
+                uniswap_client,
+                owner,
+                receipt,
+                (&TokenInfo::native(config.chain()), gas_wei),
+                None,
+            )
+            .await;
+        }
+        Ok::<_, McpError>(result)
+    }
+    .await?;
+    result.compliance = screening;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        simulation_success = result.simulation_success,
+        sent = result.sent,
+        "成功返回授权交易"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 模拟失败时拒绝广播或提议
+fn simulation_refusal(reason: String) -> McpError {
+    McpError::invalid_request(
+        format!("授权交易模拟失败,未广播: {}", reason),
+        Some(serde_json::json!({
+            "refused": true,
+            "reason": "simulation_failed",
+            "simulation_error": reason,
+        })),
+    )
+}
+
+/// 解析授权数量,"max" 表示 type(uint256).max
+pub(crate) fn parse_approve_amount(amount: &str, decimals: u8) -> Result<U256, McpError> {
+    if amount.trim().eq_ignore_ascii_case("max") {
+        return Ok(U256::MAX);
+    }
+    parse_units(amount, decimals)
+        .map_err(|e| McpError::invalid_params(format!("解析金额失败: {}", e), None))
+}
+
+/// approve 调用是否成功:部分代币(如 USDT)不返回值,视为成功
+fn approve_succeeded(output: &Bytes) -> bool {
+    output.is_empty() || output.iter().any(|byte| *byte != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_approve_amount() {
+        assert_eq!(parse_approve_amount("MAX", 6).unwrap(), U256::MAX);
+        assert_eq!(parse_approve_amount("1.5", 6).unwrap(), U256::from(1_500_000u64));
+        assert!(parse_approve_amount("abc", 6).is_err());
+    }
+
+    #[test]
+    fn test_approve_succeeded() {
+        let mut word = [0u8; 32];
+        assert!(!approve_succeeded(&Bytes::from(word.to_vec())));
+        word[31] = 1;
+        assert!(approve_succeeded(&Bytes::from(word.to_vec())));
+        assert!(approve_succeeded(&Bytes::new()));
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{
+    compliance::{ComplianceScreen, ScreeningDecision},
+    config::Config,
+    eip3009::TransferAuthorization,
+    erc20::{parse_units, Erc20Client},
+    eth_client::EthClient,
+    logging::{info, warn},
+    relay::GelatoRelayClient,
+    signer::TxSigner,
+    store::Store,
+    token_registry::TokenRegistry,
+    types::{checksum_address, parse_address, TxType},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 授权默认有效期(秒)
+const DEFAULT_VALID_SECS: u64 = 3600;
+
+/// SignTransferAuthorization 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SignTransferAuthorizationArgs {
+    /// 代币地址或符号(必需,需支持 EIP-3009,如 USDC)
+    pub token: String,
+    /// 接收地址(必需)
+    pub to: String,
+    /// 转账数量(必需)
+    pub amount: String,
+    /// 授权有效期(秒,可选,默认 3600)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_secs: Option<u64>,
+    /// 是否通过 Gelato Relay(sponsored)提交(可选,默认 false,仅返回签名载荷)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relay: Option<bool>,
+}
+
+/// SignTransferAuthorization 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SignTransferAuthorizationResult {
+    pub token: String,
+    pub symbol: String,
+    pub from: String,
+    pub to: String,
+    pub value: String,
+    pub valid_after: u64,
+    pub valid_before: u64,
+    pub nonce: String,
+    pub v: u64,
+    pub r: String,
+    pub s: String,
+    /// transferWithAuthorization calldata,任何地址都可以提交
+    pub calldata: String,
+    /// eth_call 模拟是否成功
+    pub simulation_success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    /// Gelato 中继任务 ID(relay 为 true 时)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relay_task_id: Option<String>,
+    /// 制裁名单命中但只标记时的筛查结论
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ScreeningDecision>,
+}
+
+/// 签名 EIP-3009 转账授权
+#[allow(clippy::too_many_arguments)]
+pub async fn sign_transfer_authorization(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    relay_client: &Arc<GelatoRelayClient>,
+    store: &Arc<Store>,
+    compliance: &Arc<ComplianceScreen>,
+    signer: Option<&TxSigner>,
+    Parameters(args): Parameters<SignTransferAuthorizationArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 sign_transfer_authorization 请求");
+
+    let to = parse_address(&args.to).map_err(|e| McpError::invalid_params(e, None))?;
+    let valid_secs = args.valid_secs.unwrap_or(DEFAULT_VALID_SECS);
+    let relay = args.relay.unwrap_or(false);
+
+    let token_info = token_registry
+        .resolve(&args.token)
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+    if token_info.is_eth() {
+        return Err(McpError::invalid_params("ETH 不支持 EIP-3009 授权", None));
+    }
+    let token: Address = token_info
+        .address
+        .parse()
+        .map_err(|_| McpError::internal_error("无效的代币地址".to_string(), None))?;
+
+    let chain_id = config.ethereum.chain_id;
+
+    // 🛡️ 制裁名单筛查(签名前)
+    let screening = compliance.screen(
+        store,
+        "sign_transfer_authorization",
+        &[("recipient", to), ("token", token)],
+    )?;
+
+    info!(
+        token = %args.token,
+        to = %args.to,
+        amount = %args.amount,
+        valid_secs,
+        relay,
+        "签名 EIP-3009 转账授权"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let value = parse_units(&args.amount, token_info.decimals)
+            .map_err(|e| McpError::invalid_params(format!("解析金额失败: {}", e), None))?;
+        let authorization = TransferAuthorization {
+            token,
+            from: config.get_simulation_address(),
+            to,
+            value,
+            valid_after: 0,
+            valid_before: valid_secs,
+            nonce: H256::zero(),
+        };
+        let signature = Signature {
+            r: U256::zero(),
+            s: U256::zero(),
+            v: 27,
+        };
+        let mut result = build_result(&authorization, &token_info.symbol, &signature, true, None, None);
+        result.compliance = screening;
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    let wallet = signer.ok_or_else(|| McpError::invalid_params("未配置签名器", None))?;
+
+    // 真实模式:需要检查客户端可用性
+    if !erc20_client.is_available() {
+        return Err(McpError::internal_error(
+            "ERC20 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+    if relay && !relay_client.has_api_key() {
+        return Err(McpError::invalid_params("relay 需要配置 GELATO_API_KEY", None));
+    }
+
+    let eth_client = eth_client.clone();
+    let erc20_client = erc20_client.clone();
+    let relay_client = relay_client.clone();
+
+    let mut result = async {
+        let token_info = if token_info.symbol == "UNKNOWN" {
+            let real_info = erc20_client
+                .token_info(token)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
+            token_registry.register(real_info.symbol.clone(), real_info.clone());
+            real_info
+        } else {
+            token_info
+        };
+
+        let value = parse_units(&args.amount, token_info.decimals)
+            .map_err(|e| McpError::invalid_params(format!("解析金额失败: {}", e), None))?;
+
+        // EIP-712 域使用链上 name/version,并与 DOMAIN_SEPARATOR 核对
+        let name = erc20_client
+            .name(token)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询代币名称失败: {}", e), None))?;
+        let version = erc20_client.version(token).await.unwrap_or_else(|_| "1".to_string());
+        let separator = erc20_client.domain_separator(token).await.map_err(|e| {
+            McpError::invalid_params(format!("代币不支持 EIP-712 (DOMAIN_SEPARATOR): {}", e), None)
+        })?;
+
+        let (_, now) = eth_client
+            .get_block_timestamp(BlockNumber::Latest)
+            .await
+            .map_err(|e| McpError::internal_error(format!("获取区块时间失败: {}", e), None))?;
+
+        let authorization = TransferAuthorization {
+            token,
+            from: wallet.address(),
+            to,
+            value,
+            valid_after: 0,
+            valid_before: now + valid_secs,
+            nonce: TransferAuthorization::random_nonce(),
+        };
+
+        if H256::from(authorization.domain(&name, &version, chain_id).separator()) != separator {
+            return Err(McpError::invalid_params(
+                format!(
+                    "代币的 EIP-712 域与 name=\"{}\" version=\"{}\" 不一致,可能不支持 EIP-3009",
+                    name, version
+                ),
+                None,
+            ));
+        }
+
+        let signature = sign(wallet, &authorization, &name, &version, chain_id).await?;
+        let calldata = authorization.calldata(&signature);
+
+        // 模拟提交：transferWithAuthorization 不校验 msg.sender，任意地址均可提交
+        let mut tx = TxType::Eip1559.new_request();
+        tx.set_to(token).set_data(calldata.clone());
+        let revert_reason = match eth_client.call(&tx).await {
+            Ok(_) => None,
+            Err(e) => {
+                warn!(error = %e, "transferWithAuthorization 模拟失败");
+                Some(e.to_string())
+            }
+        };
+
+        // 模拟失败时不提交中继
+        let relay_task_id = if relay && revert_reason.is_none() {
+            Some(
+                relay_client
+                    .sponsored_call(chain_id, token, &calldata)
+                    .await
+                    .map_err(|e| McpError::internal_error(format!("提交中继交易失败: {}", e), None))?,
+            )
+        } else {
+            None
+        };
+
+        Ok::<_, McpError>(build_result(
+            &authorization,
+            &token_info.symbol,
+            &signature,
+            revert_reason.is_none(),
+            revert_reason,
+            relay_task_id,
+        ))
+    }
+    .await?;
+    result.compliance = screening;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        simulation_success = result.simulation_success,
+        relayed = result.relay_task_id.is_some(),
+        "成功返回 EIP-3009 授权"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+async fn sign(
+    wallet: &TxSigner,
+    authorization: &TransferAuthorization,
+    name: &str,
+    version: &str,
+    chain_id: u64,
+) -> Result<Signature, McpError> {
+    let typed = authorization
+        .typed_data(name, version, chain_id)
+        .map_err(|e| McpError::internal_error(format!("构建 EIP-712 数据失败: {}", e), None))?;
+    wallet
+        .sign_typed_data(&typed)
+        .await
+        .map_err(|e| McpError::internal_error(format!("签名失败: {}", e), None))
+}
+
+fn build_result(
+    authorization: &TransferAuthorization,
+    symbol: &str,
+    signature: &Signature,
+    simulation_success: bool,
+    revert_reason: Option<String>,
+    relay_task_id: Option<String>,
+) -> SignTransferAuthorizationResult {
+    SignTransferAuthorizationResult {
+        token: checksum_address(authorization.token),
+        symbol: symbol.to_string(),
+        from: checksum_address(authorization.from),
+        to: checksum_address(authorization.to),
+        value: authorization.value.to_string(),
+        valid_after: authorization.valid_after,
+        valid_before: authorization.valid_before,
+        nonce: format!("{:?}", authorization.nonce),
+        v: signature.v,
+        r: word_hex(signature.r),
+        s: word_hex(signature.s),
+        calldata: authorization.calldata(signature).to_string(),
+        simulation_success,
+        revert_reason,
+        relay_task_id,
+        compliance: None,
+    }
+}
+
+/// 32 字节定长十六进制(签名 r/s)
+fn word_hex(value: U256) -> String {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    format!("{:?}", H256::from(bytes))
+}
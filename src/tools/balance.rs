@@ -3,12 +3,13 @@ use crate::{
     erc20::{format_units, Erc20Client},
     eth_client::EthClient,
     logging::info,
+    token_lists::TokenListClient,
     token_registry::TokenRegistry,
-    types::TokenInfo,
+    types::{checksum_address, parse_address, TokenInfo},
 };
 use ethers::prelude::*;
 use rmcp::{
-    handler::server::wrapper::Parameters, model::*, schemars, tool, ErrorData as McpError,
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
 };
 use std::sync::Arc;
 
@@ -20,6 +21,12 @@ pub struct GetBalanceArgs {
     /// ERC20 代币地址或符号(可选,不填则查询 ETH 余额)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token_address: Option<String>,
+    /// 确认深度(可选,latest/confirmed/finalized,FINALITY_BLOCKS > 0 时默认 confirmed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finality: Option<String>,
+    /// 历史区块号(可选,查询该区块结束时的余额,需要归档节点,不能与 finality 同时指定)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
 }
 
 /// GetBalance 工具的返回结果
@@ -30,21 +37,38 @@ pub struct BalanceResult {
     pub balance: String,
     pub decimals: u8,
     pub formatted_balance: String,
+    /// 读取余额的区块号(读取最新区块时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
 }
 
 /// 获取以太坊地址余额(支持 ETH 和 ERC20)
-#[tool(description = "获取以太坊地址余额(支持 ETH 和 ERC20 代币)")]
-pub fn get_balance(
+pub async fn get_balance(
     config: &Arc<Config>,
     eth_client: &Arc<EthClient>,
     erc20_client: &Arc<Erc20Client>,
     token_registry: &Arc<TokenRegistry>,
+    token_lists: &Arc<TokenListClient>,
     Parameters(args): Parameters<GetBalanceArgs>,
 ) -> Result<CallToolResult, McpError> {
     info!("收到 get_balance 请求");
 
     let wallet_address = &args.address;
-    info!(address = %wallet_address, "查询地址余额");
+    if args.block_number.is_some() && args.finality.is_some() {
+        return Err(McpError::invalid_params(
+            "block_number 与 finality 不能同时指定",
+            None,
+        ));
+    }
+    let finality = config
+        .read_finality(args.finality.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+    info!(
+        address = %wallet_address,
+        finality = ?finality,
+        block_number = ?args.block_number,
+        "查询地址余额"
+    );
 
     // 测试模式
     if config.server.test_mode {
@@ -54,6 +78,7 @@ pub fn get_balance(
                 name: "Test Token".to_string(),
                 address: args.token_address.clone().unwrap_or_default(),
                 decimals: 18,
+                listed_on: Vec::new(),
             }
         } else {
             TokenInfo::eth()
@@ -65,6 +90,7 @@ pub fn get_balance(
             balance: "100000000000000000000".to_string(), // 100 in wei
             decimals: 18,
             formatted_balance: "100".to_string(),
+            block_number: args.block_number,
         };
 
         let json_str = serde_json::to_string_pretty(&result)
@@ -82,21 +108,27 @@ pub fn get_balance(
     }
 
     // 解析钱包地址
-    let wallet_addr: Address = wallet_address.parse().map_err(|_| {
-        McpError::invalid_params(format!("无效的地址: {}", wallet_address), None)
-    })?;
+    let wallet_addr = parse_address(wallet_address).map_err(|e| McpError::invalid_params(e, None))?;
+
+    // 指定历史区块时直接读取该区块,否则按确认深度确定读取区块
+    let read_block = if let Some(block_number) = args.block_number {
+        Some(block_number)
+    } else {
+        let eth_client = eth_client.clone();
+        let finality_blocks = config.ethereum.finality_blocks;
+        eth_client
+            .resolve_read_block(finality, finality_blocks)
+            .await
+            .map_err(|e| McpError::internal_error(format!("确定读取区块失败: {}", e), None))?
+    };
+    let block_id = read_block.map(BlockId::from);
 
     // 查询余额
     let (token_info, balance, decimals) = if let Some(ref token_address) = args.token_address {
         // 查询 ERC20 余额
         let mut token_info = token_registry
             .resolve(token_address)
-            .ok_or_else(|| {
-                McpError::invalid_params(
-                    format!("未知的代币: {}", token_address),
-                    None,
-                )
-            })?;
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
         let token_addr: Address = token_info.address.parse().map_err(|_| {
             McpError::internal_error("无效的代币地址".to_string(), None)
@@ -105,12 +137,10 @@ pub fn get_balance(
         // 🔍 动态查询未知代币信息
         if token_info.symbol == "UNKNOWN" && erc20_client.is_available() {
             let erc20_client_clone = erc20_client.clone();
-            let real_info = tokio::task::block_in_place(|| {
-                tokio::runtime::Handle::current().block_on(async {
-                    erc20_client_clone.token_info(token_addr).await
-                })
-            })
-            .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
+            let real_info = erc20_client_clone
+                .token_info(token_addr)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
 
             // 缓存到注册表
             token_registry.register(real_info.symbol.clone(), real_info.clone());
@@ -120,12 +150,16 @@ pub fn get_balance(
         let erc20_client = erc20_client.clone();
         let decimals = token_info.decimals;
 
-        let balance = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                erc20_client.balance_of(token_addr, wallet_addr).await
-            })
-        })
-        .map_err(|e| McpError::internal_error(format!("查询 ERC20 余额失败: {}", e), None))?;
+        let chain_id = config.ethereum.chain_id;
+
+        // 余额与代币列表收录情况并行查询
+        let (balance, listed_on) = tokio::join!(
+            erc20_client.balance_of(token_addr, wallet_addr, block_id),
+            token_lists.listed_on(chain_id, token_addr)
+        );
+        let balance = balance
+            .map_err(|e| McpError::internal_error(balance_error("ERC20", &e, args.block_number), None))?;
+        token_info.listed_on = listed_on;
 
         (token_info, balance, decimals)
     } else {
@@ -133,25 +167,24 @@ pub fn get_balance(
         let eth_client = eth_client.clone();
         let addr_str = wallet_address.clone();
 
-        let balance_wei = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                eth_client.get_balance(&addr_str).await
-            })
-        })
-        .map_err(|e| McpError::internal_error(format!("查询 ETH 余额失败: {}", e), None))?;
+        let balance_wei = eth_client
+            .get_balance(&addr_str, block_id)
+            .await
+            .map_err(|e| McpError::internal_error(balance_error("ETH", &e, args.block_number), None))?;
 
-        (TokenInfo::eth(), balance_wei, 18)
+        (TokenInfo::native(config.chain()), balance_wei, 18)
     };
 
     // 格式化余额
     let formatted_balance = format_units(balance, decimals);
 
     let result = BalanceResult {
-        address: wallet_address.clone(),
+        address: checksum_address(wallet_addr),
         token: token_info,
         balance: balance.to_string(),
         decimals,
         formatted_balance,
+        block_number: read_block,
     };
 
     let json_str = serde_json::to_string_pretty(&result)
@@ -162,6 +195,14 @@ pub fn get_balance(
     Ok(CallToolResult::success(vec![Content::text(json_str)]))
 }
 
+/// 历史区块查询失败时提示可能需要归档节点(非归档节点只保留最近约 128 个区块的状态)
+fn balance_error(kind: &str, error: &dyn std::fmt::Display, block_number: Option<u64>) -> String {
+    match block_number {
+        Some(block) => format!("查询区块 {} 的 {} 余额失败(历史状态需要归档节点): {}", block, kind, error),
+        None => format!("查询 {} 余额失败: {}", kind, error),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,6 +215,7 @@ mod tests {
             balance: "100000000000000000000".to_string(),
             decimals: 18,
             formatted_balance: "100".to_string(),
+            block_number: None,
         };
 
         let json = serde_json::to_string(&result).expect("应该能序列化");
@@ -195,5 +237,17 @@ mod tests {
         let args: GetBalanceArgs = serde_json::from_str(json).expect("应该能反序列化");
         assert_eq!(args.address, "0x123");
         assert_eq!(args.token_address, None);
+        assert_eq!(args.block_number, None);
+
+        // 测试历史区块
+        let json = r#"{"address":"0x123","block_number":17000000}"#;
+        let args: GetBalanceArgs = serde_json::from_str(json).expect("应该能反序列化");
+        assert_eq!(args.block_number, Some(17_000_000));
+    }
+
+    #[test]
+    fn test_balance_error_mentions_archive_node() {
+        assert_eq!(balance_error("ETH", &"timeout", None), "查询 ETH 余额失败: timeout");
+        assert!(balance_error("ERC20", &"missing trie node", Some(100)).contains("归档节点"));
     }
 }
@@ -0,0 +1,421 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    eth_client::EthClient,
+    logging::info,
+    token_registry::TokenRegistry,
+    tools::price::fetch_token_price_usd_at,
+    types::{parse_address, TokenInfo},
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+/// 单次批量查询允许的最大子请求数量
+const MAX_QUERIES: usize = 50;
+
+/// BatchQuery 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct BatchQueryArgs {
+    /// 只读子请求列表(必需,最多 50 个)
+    pub queries: Vec<BatchQueryItem>,
+}
+
+/// 单个只读子请求
+#[derive(Debug, Clone, serde::Deserialize, schemars::JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchQueryItem {
+    /// 余额查询
+    Balance {
+        /// 钱包地址(必需)
+        address: String,
+        /// ERC20 代币地址或符号(可选,不填则查询 ETH 余额)
+        #[serde(default)]
+        token: Option<String>,
+    },
+    /// USD 价格查询
+    Price {
+        /// 代币地址或符号(必需)
+        token: String,
+    },
+    /// 授权额度查询
+    Allowance {
+        /// 代币地址或符号(必需)
+        token: String,
+        /// 授权方地址(必需)
+        owner: String,
+        /// 被授权方地址(必需)
+        spender: String,
+    },
+}
+
+impl BatchQueryItem {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Balance { .. } => "balance",
+            Self::Price { .. } => "price",
+            Self::Allowance { .. } => "allowance",
+        }
+    }
+}
+
+/// BatchQuery 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BatchQueryResult {
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// 与输入顺序一致的子请求结果
+    pub results: Vec<BatchQueryOutcome>,
+}
+
+/// 单个子请求的结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BatchQueryOutcome {
+    pub index: usize,
+    #[serde(rename = "type")]
+    pub query_type: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct BalanceValue {
+    address: String,
+    token: TokenInfo,
+    balance: String,
+    formatted_balance: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct PriceValue {
+    token: TokenInfo,
+    price_usd: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct AllowanceValue {
+    token: TokenInfo,
+    owner: String,
+    spender: String,
+    allowance: String,
+    formatted_allowance: String,
+}
+
+/// 批量执行只读查询(余额、价格、授权额度)
+pub async fn batch_query(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<BatchQueryArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 batch_query 请求");
+
+    if args.queries.is_empty() {
+        return Err(McpError::invalid_params("子请求列表不能为空", None));
+    }
+
+    if args.queries.len() > MAX_QUERIES {
+        return Err(McpError::invalid_params(
+            format!("子请求数量过多: {} (最多 {} 个)", args.queries.len(), MAX_QUERIES),
+            None,
+        ));
+    }
+
+    info!(queries = args.queries.len(), "批量执行只读查询");
+
+    // 测试模式
+    if config.server.test_mode {
+        let outcomes = args
+            .queries
+            .iter()
+            .enumerate()
+            .map(|(index, query)| outcome(index, query, Ok(fixture_value(query))))
+            .collect();
+        let result = summarize(outcomes);
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    // 子请求全部并发执行,RPC 并发由 RpcTransportConfig 中共享的 MAX_CONCURRENT_REQUESTS 许可限制
+    let outcomes = async {
+        let mut tasks = JoinSet::new();
+
+        for (index, query) in args.queries.iter().cloned().enumerate() {
+            let eth_client = eth_client.clone();
+            let erc20_client = erc20_client.clone();
+            let uniswap_client = uniswap_client.clone();
+            let token_registry = token_registry.clone();
+            let native = TokenInfo::native(config.chain());
+
+            tasks.spawn(async move {
+                let value = run_query(
+                    &eth_client,
+                    &erc20_client,
+                    &uniswap_client,
+                    &token_registry,
+                    native,
+                    &query,
+                )
+                .await;
+                outcome(index, &query, value)
+            });
+        }
+
+        let mut outcomes = Vec::with_capacity(args.queries.len());
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(e) => info!(error = %e, "子请求任务异常退出"),
+            }
+        }
+        outcomes
+    }
+    .await;
+
+    let result = summarize(outcomes);
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        succeeded = result.succeeded,
+        failed = result.failed,
+        "成功返回批量查询结果"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 执行单个子请求
+async fn run_query(
+    eth_client: &EthClient,
+    erc20_client: &Erc20Client,
+    uniswap_client: &UniswapV2Client,
+    token_registry: &TokenRegistry,
+    native: TokenInfo,
+    query: &BatchQueryItem,
+) -> Result<serde_json::Value, String> {
+    let value = match query {
+        BatchQueryItem::Balance { address, token } => {
+            let owner = parse_address(address)?;
+            let (token_info, balance) = match token {
+                Some(token) => {
+                    let (token_info, token_addr) =
+                        resolve_token(erc20_client, token_registry, token).await?;
+                    let balance = erc20_client
+                        .balance_of(token_addr, owner, None)
+                        .await
+                        .map_err(|e| format!("查询 ERC20 余额失败: {}", e))?;
+                    (token_info, balance)
+                }
+                None => {
+                    let balance = eth_client
+                        .get_balance(address, None)
+                        .await
+                        .map_err(|e| format!("查询 ETH 余额失败: {}", e))?;
+                    (native, balance)
+                }
+            };
+
+            serde_json::to_value(BalanceValue {
+                address: address.clone(),
+                formatted_balance: format_units(balance, token_info.decimals),
+                balance: balance.to_string(),
+                token: token_info,
+            })
+        }
+        BatchQueryItem::Price { token } => {
+            let (token_info, token_addr) =
+                resolve_token(erc20_client, token_registry, token).await?;
+            let price = fetch_token_price_usd_at(uniswap_client, token_addr, token_info.decimals, None)
+                .await
+                .map_err(|e| e.message.to_string())?;
+
+            serde_json::to_value(PriceValue {
+                token: token_info,
+                price_usd: price.round_dp(6).normalize().to_string(),
+            })
+        }
+        BatchQueryItem::Allowance { token, owner, spender } => {
+            let owner_addr = parse_address(owner)?;
+            let spender_addr = parse_address(spender)?;
+            let (token_info, token_addr) =
+                resolve_token(erc20_client, token_registry, token).await?;
+            let allowance = erc20_client
+                .allowance(token_addr, owner_addr, spender_addr)
+                .await
+                .map_err(|e| format!("查询授权额度失败: {}", e))?;
+
+            serde_json::to_value(AllowanceValue {
+                owner: owner.clone(),
+                spender: spender.clone(),
+                formatted_allowance: format_units(allowance, token_info.decimals),
+                allowance: allowance.to_string(),
+                token: token_info,
+            })
+        }
+    };
+
+    value.map_err(|e| e.to_string())
+}
+
+/// 解析代币,未知代币从链上查询元数据并缓存到注册表
+async fn resolve_token(
+    erc20_client: &Erc20Client,
+    token_registry: &TokenRegistry,
+    token: &str,
+) -> Result<(TokenInfo, Address), String> {
+    let token_info = token_registry
+        .resolve(token)
+        .map_err(|e| e.to_string())?;
+
+    let token_addr: Address = token_info
+        .address
+        .parse()
+        .map_err(|_| "无效的代币地址".to_string())?;
+
+    if token_info.symbol == "UNKNOWN" && erc20_client.is_available() {
+        let real_info = erc20_client
+            .token_info(token_addr)
+            .await
+            .map_err(|e| format!("查询代币信息失败: {}", e))?;
+        token_registry.register(real_info.symbol.clone(), real_info.clone());
+        return Ok((real_info, token_addr));
+    }
+
+    Ok((token_info, token_addr))
+}
+
+fn outcome(
+    index: usize,
+    query: &BatchQueryItem,
+    value: Result<serde_json::Value, String>,
+) -> BatchQueryOutcome {
+    let (ok, result, error) = match value {
+        Ok(value) => (true, Some(value), None),
+        Err(e) => (false, None, Some(e)),
+    };
+
+    BatchQueryOutcome {
+        index,
+        query_type: query.kind().to_string(),
+        ok,
+        result,
+        error,
+    }
+}
+
+/// 按输入顺序整理子请求结果并统计成功/失败数量
+fn summarize(mut outcomes: Vec<BatchQueryOutcome>) -> BatchQueryResult {
+    outcomes.sort_by_key(|o| o.index);
+    let succeeded = outcomes.iter().filter(|o| o.ok).count();
+
+    BatchQueryResult {
+        total: outcomes.len(),
+        succeeded,
+        failed: outcomes.len() - succeeded,
+        results: outcomes,
+    }
+}
+
+fn fixture_value(query: &BatchQueryItem) -> serde_json::Value {
+    let test_token = |token: &str| TokenInfo {
+        symbol: "TEST".to_string(),
+        name: "Test Token".to_string(),
+        address: token.to_string(),
+        decimals: 18,
+        listed_on: Vec::new(),
+    };
+
+    let value = match query {
+        BatchQueryItem::Balance { address, token } => serde_json::to_value(BalanceValue {
+            address: address.clone(),
+            token: token.as_deref().map(test_token).unwrap_or_else(TokenInfo::eth),
+            balance: "100000000000000000000".to_string(),
+            formatted_balance: "100".to_string(),
+        }),
+        BatchQueryItem::Price { token } => serde_json::to_value(PriceValue {
+            token: test_token(token),
+            price_usd: "1.5".to_string(),
+        }),
+        BatchQueryItem::Allowance { token, owner, spender } => {
+            serde_json::to_value(AllowanceValue {
+                token: test_token(token),
+                owner: owner.clone(),
+                spender: spender.clone(),
+                allowance: "0".to_string(),
+                formatted_allowance: "0".to_string(),
+            })
+        }
+    };
+
+    value.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_query_args_deserialization() {
+        let json = r#"{"queries":[
+            {"type":"balance","address":"0x123"},
+            {"type":"price","token":"USDC"},
+            {"type":"allowance","token":"USDC","owner":"0x1","spender":"0x2"}
+        ]}"#;
+        let args: BatchQueryArgs = serde_json::from_str(json).expect("应该能反序列化");
+
+        assert_eq!(args.queries.len(), 3);
+        assert_eq!(args.queries[0].kind(), "balance");
+        assert_eq!(args.queries[1].kind(), "price");
+        assert_eq!(args.queries[2].kind(), "allowance");
+        assert!(matches!(args.queries[0], BatchQueryItem::Balance { token: None, .. }));
+    }
+
+    #[test]
+    fn test_summarize_keeps_input_order() {
+        let price = BatchQueryItem::Price {
+            token: "USDC".to_string(),
+        };
+        let outcomes = vec![
+            outcome(2, &price, Err("未知的代币: FOO".to_string())),
+            outcome(0, &price, Ok(fixture_value(&price))),
+            outcome(1, &price, Ok(fixture_value(&price))),
+        ];
+
+        let result = summarize(outcomes);
+        assert_eq!(result.total, 3);
+        assert_eq!(result.succeeded, 2);
+        assert_eq!(result.failed, 1);
+        assert_eq!(
+            result.results.iter().map(|o| o.index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(result.results[2].error.as_deref(), Some("未知的代币: FOO"));
+
+        let json = serde_json::to_string(&result.results[0]).unwrap();
+        assert!(json.contains(r#""type":"price""#));
+        assert!(!json.contains("error"));
+    }
+}
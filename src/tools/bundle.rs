@@ -0,0 +1,415 @@
+use crate::{
+    config::Config,
+    erc20::Erc20Client,
+    eth_client::EthClient,
+    logging::{info, warn},
+    token_registry::TokenRegistry,
+    tools::preview::{
+        build_rows, collect_deltas, parse_call, render_table, token_metadata, BalanceDeltaRow,
+        DeltaMap,
+    },
+    types::TxType,
+};
+use ethers::prelude::*;
+use ethers::types::spoof;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 单次模拟的最大交易数量
+const MAX_BUNDLE_TRANSACTIONS: usize = 20;
+
+/// 交易序列中的单笔交易
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct BundleTransaction {
+    /// 目标合约或接收地址(必需)
+    pub to: String,
+    /// 调用数据(可选,0x 开头的十六进制)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// 发送的 ETH 数量(可选,如 "0.1",默认 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// 发送方地址(可选,默认使用配置的模拟地址)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+}
+
+/// SimulateTransactions 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SimulateTransactionsArgs {
+    /// 按顺序执行的交易列表(必需,最多 20 笔)
+    pub transactions: Vec<BundleTransaction>,
+    /// 遇到回滚时是否停止后续交易(可选,默认 true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_on_revert: Option<bool>,
+}
+
+/// SimulateTransactions 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SimulateTransactionsResult {
+    /// 所有交易是否都执行成功
+    pub success: bool,
+    /// 模拟所基于的区块
+    pub block_number: u64,
+    pub transactions: Vec<BundleTransactionResult>,
+    /// 所有成功交易累计的余额变化(ETH 变化不含 Gas 费用)
+    pub deltas: Vec<BalanceDeltaRow>,
+    /// 累计余额变化表(Markdown)
+    pub table: String,
+}
+
+/// 单笔交易的模拟结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BundleTransactionResult {
+    pub index: usize,
+    pub from: String,
+    pub to: String,
+    /// success / reverted / skipped
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<String>,
+    /// 返回数据(十六进制)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    pub deltas: Vec<BalanceDeltaRow>,
+}
+
+/// 按顺序模拟交易序列
+pub async fn simulate_transactions(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<SimulateTransactionsArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 simulate_transactions 请求");
+
+    if args.transactions.is_empty() {
+        return Err(McpError::invalid_params("交易列表不能为空", None));
+    }
+    if args.transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+        return Err(McpError::invalid_params(
+            format!("交易数量超过上限 {}", MAX_BUNDLE_TRANSACTIONS),
+            None,
+        ));
+    }
+
+    let calls = args
+        .transactions
+        .iter()
+        .map(|tx| {
+            parse_call(
+                config,
+                &tx.to,
+                tx.data.as_deref(),
+                tx.value.as_deref(),
+                tx.from.as_deref(),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let stop_on_revert = args.stop_on_revert.unwrap_or(true);
+
+    info!(count = calls.len(), stop_on_revert, "模拟交易序列");
+
+    // 测试模式
+    if config.server.test_mode {
+        let transactions = calls
+            .iter()
+            .enumerate()
+            .map(|(index, (from, to, _, _))| BundleTransactionResult {
+                index,
+                from: format!("{:?}", from),
+                to: format!("{:?}", to),
+                status: "success".to_string(),
+                revert_reason: None,
+                gas_used: Some("21000".to_string()),
+                output: Some("0x".to_string()),
+                deltas: Vec::new(),
+            })
+            .collect();
+
+        let result = SimulateTransactionsResult {
+            success: true,
+            block_number: 18_000_000,
+            transactions,
+            deltas: Vec::new(),
+            table: render_table(&[]),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let eth_client = eth_client.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+    let weth = config.chain().wrapped_native_address();
+
+    let result = async {
+        // 所有交易基于同一区块，状态通过 state override 逐笔累积
+        let block_number = eth_client
+            .get_block_number()
+            .await
+            .map_err(|e| McpError::internal_error(format!("获取区块号失败: {}", e), None))?;
+        let block = Some(BlockId::from(block_number));
+
+        let mut state = spoof::state();
+        let mut frames = Vec::with_capacity(calls.len());
+        let mut halted = false;
+
+        for (from, to, data, value) in &calls {
+            if halted {
+                frames.push(None);
+                continue;
+            }
+
+            let mut tx = TxType::Eip1559.new_request();
+            tx.set_from(*from)
+                .set_to(*to)
+                .set_data(data.clone())
+                .set_value(*value);
+
+            let frame = eth_client
+                .trace_call(&tx, block, Some(state.clone()))
+                .await
+                .map_err(|e| {
+                    McpError::internal_error(
+                        format!("跟踪交易失败(需要节点支持 debug_traceCall): {}", e),
+                        None,
+                    )
+                })?;
+
+            if frame.error.is_none() {
+                let diff = eth_client
+                    .trace_state_diff(&tx, block, Some(state.clone()))
+                    .await
+                    .map_err(|e| {
+                        McpError::internal_error(format!("获取状态差异失败: {}", e), None)
+                    })?;
+                apply_state_diff(&mut state, &diff);
+            } else {
+                warn!(reason = ?frame.error, "交易序列中的交易回滚");
+                halted = stop_on_revert;
+            }
+
+            frames.push(Some(frame));
+        }
+
+        let per_tx: Vec<DeltaMap> = frames
+            .iter()
+            .map(|frame| {
+                let mut deltas = DeltaMap::new();
+                if let Some(frame) = frame {
+                    collect_deltas(frame, weth, &mut deltas);
+                }
+                deltas.retain(|_, delta| !delta.is_zero());
+                deltas
+            })
+            .collect();
+
+        let mut total = DeltaMap::new();
+        for (key, delta) in per_tx.iter().flatten() {
+            *total.entry(*key).or_default() += *delta;
+        }
+        total.retain(|_, delta| !delta.is_zero());
+
+        let tokens = token_metadata(&erc20_client, &token_registry, &total_keys(&per_tx)).await;
+
+        let transactions: Vec<_> = frames
+            .into_iter()
+            .zip(per_tx.iter())
+            .zip(calls.iter())
+            .enumerate()
+            .map(|(index, ((frame, deltas), (from, to, _, _)))| {
+                let status = match frame {
+                    None => "skipped",
+                    Some(ref frame) if frame.error.is_some() => "reverted",
+                    Some(_) => "success",
+                };
+                BundleTransactionResult {
+                    index,
+                    from: format!("{:?}", from),
+                    to: format!("{:?}", to),
+                    status: status.to_string(),
+                    revert_reason: frame.as_ref().and_then(|f| f.error.clone()),
+                    gas_used: frame.as_ref().map(|f| f.gas_used.to_string()),
+                    output: frame
+                        .as_ref()
+                        .map(|f| f.output.clone().unwrap_or_default().to_string()),
+                    deltas: build_rows(deltas, *from, *to, &tokens),
+                }
+            })
+            .collect();
+
+        // 累计变化以第一笔交易的发送方为视角排序
+        let (first_from, first_to, _, _) = &calls[0];
+        let rows = build_rows(&total, *first_from, *first_to, &tokens);
+
+        Ok::<_, McpError>(SimulateTransactionsResult {
+            success: transactions.iter().all(|tx| tx.status == "success"),
+            block_number,
+            transactions,
+            table: render_table(&rows),
+            deltas: rows,
+        })
+    }
+    .await?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        success = result.success,
+        count = result.transactions.len(),
+        "成功返回交易序列模拟结果"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 合并各笔交易涉及的资产(用于一次性查询代币元数据)
+fn total_keys(per_tx: &[DeltaMap]) -> DeltaMap {
+    per_tx
+        .iter()
+        .flat_map(|deltas| deltas.keys())
+        .map(|key| (*key, I256::zero()))
+        .collect()
+}
+
+/// 将 prestateTracer 的 diff 结果合并进状态覆盖
+/// post 中只包含被修改的字段；pre 中存在而 post 中缺失的存储槽表示被清零，
+/// 整个账户缺失表示账户被销毁
+fn apply_state_diff(state: &mut spoof::State, diff: &DiffMode) {
+    for (address, post) in &diff.post {
+        let account = state.account(*address);
+        if let Some(balance) = post.balance {
+            account.balance(balance);
+        }
+        if let Some(nonce) = post.nonce {
+            account.nonce(U64::from(nonce.low_u64()));
+        }
+        if let Some(code) = post.code.as_ref().and_then(|code| code.parse::<Bytes>().ok()) {
+            account.code(code);
+        }
+        for (slot, value) in post.storage.iter().flatten() {
+            account.store(*slot, *value);
+        }
+    }
+
+    for (address, pre) in &diff.pre {
+        let post = diff.post.get(address);
+        let account = state.account(*address);
+        if post.is_none() {
+            account
+                .balance(U256::zero())
+                .nonce(U64::zero())
+                .code(Bytes::new());
+        }
+        for slot in pre.storage.iter().flatten().map(|(slot, _)| slot) {
+            let cleared = post
+                .and_then(|post| post.storage.as_ref())
+                .is_none_or(|storage| !storage.contains_key(slot));
+            if cleared {
+                account.store(*slot, H256::zero());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_apply_state_diff_carries_post_state() {
+        let user = Address::repeat_byte(0x01);
+        let token = Address::repeat_byte(0xaa);
+        let kept = H256::from_low_u64_be(1);
+        let cleared = H256::from_low_u64_be(2);
+
+        let pre = BTreeMap::from([
+            (
+                user,
+                AccountState {
+                    balance: Some(U256::from(100)),
+                    nonce: Some(U256::from(1)),
+                    ..Default::default()
+                },
+            ),
+            (
+                token,
+                AccountState {
+                    storage: Some(BTreeMap::from([
+                        (kept, H256::from_low_u64_be(5)),
+                        (cleared, H256::from_low_u64_be(7)),
+                    ])),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let post = BTreeMap::from([
+            (
+                user,
+                AccountState {
+                    balance: Some(U256::from(40)),
+                    nonce: Some(U256::from(2)),
+                    ..Default::default()
+                },
+            ),
+            (
+                token,
+                AccountState {
+                    storage: Some(BTreeMap::from([(kept, H256::from_low_u64_be(9))])),
+                    ..Default::default()
+                },
+            ),
+        ]);
+
+        let mut state = spoof::state();
+        apply_state_diff(&mut state, &DiffMode { pre, post });
+
+        let account = state.account(user).clone();
+        assert_eq!(account.balance, Some(U256::from(40)));
+        assert_eq!(account.nonce, Some(U64::from(2)));
+
+        let storage = state.account(token).storage.clone().unwrap();
+        assert_eq!(storage[&kept], H256::from_low_u64_be(9));
+        assert_eq!(storage[&cleared], H256::zero());
+    }
+
+    #[test]
+    fn test_apply_state_diff_later_diff_overrides_earlier() {
+        let user = Address::repeat_byte(0x01);
+        let diff = |balance: u64| DiffMode {
+            pre: BTreeMap::new(),
+            post: BTreeMap::from([(
+                user,
+                AccountState {
+                    balance: Some(U256::from(balance)),
+                    ..Default::default()
+                },
+            )]),
+        };
+
+        let mut state = spoof::state();
+        apply_state_diff(&mut state, &diff(50));
+        apply_state_diff(&mut state, &diff(20));
+
+        assert_eq!(state.account(user).balance, Some(U256::from(20)));
+    }
+}
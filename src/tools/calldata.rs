@@ -0,0 +1,233 @@
+use crate::{
+    bindings::{find_known_function, function_signature},
+    config::Config,
+    etherscan::EtherscanClient,
+    logging::{info, warn},
+    types::{checksum_address, parse_address},
+};
+use ethers::abi::{Function, Param, Token};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// DecodeCalldata 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct DecodeCalldataArgs {
+    /// 0x 开头的调用数据(必需)
+    pub data: String,
+    /// 被调用的合约地址(可选,内置 ABI 无法识别时从 Etherscan 获取该合约的 ABI)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<String>,
+}
+
+/// DecodeCalldata 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct DecodedCalldataResult {
+    pub selector: String,
+    /// ABI 来源(builtin/etherscan),无法识别时为 unknown
+    pub source: String,
+    /// 内置 ABI 的合约名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<DecodedArgument>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 解码后的参数
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct DecodedArgument {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// 地址按 EIP-55 校验和格式,整数为十进制字符串,字节为十六进制
+    pub value: serde_json::Value,
+}
+
+/// 解码调用数据的函数和参数,先匹配内置 ABI(ERC20、Uniswap V2),再按合约地址查询 Etherscan
+pub async fn decode_calldata(
+    config: &Arc<Config>,
+    etherscan_client: &Arc<EtherscanClient>,
+    Parameters(args): Parameters<DecodeCalldataArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 decode_calldata 请求");
+
+    let data = args
+        .data
+        .trim()
+        .parse::<Bytes>()
+        .map_err(|_| McpError::invalid_params(format!("无效的调用数据: {}", args.data), None))?;
+    if data.len() < 4 {
+        return Err(McpError::invalid_params("调用数据不足 4 字节,没有函数选择器", None));
+    }
+    let contract_address = args
+        .contract_address
+        .as_deref()
+        .map(parse_address)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let selector = Bytes::from(data[..4].to_vec()).to_string();
+    info!(selector = %selector, data_len = data.len(), contract = ?contract_address, "解码调用数据");
+
+    if let Some((contract, function)) = find_known_function(&data[..4]) {
+        let result = build_decoded(&selector, "builtin", Some(contract), function, &data)?;
+        return to_result(result);
+    }
+
+    let mut notes = Vec::new();
+    match contract_address {
+        // 测试模式:不查询 Etherscan
+        Some(_) if config.server.test_mode => {}
+        Some(address) if etherscan_client.is_available() => match etherscan_client.contract_abi(address).await {
+            Ok(abi) => {
+                match abi.functions().find(|function| function.short_signature() == data[..4]) {
+                    Some(function) => {
+                        let result = build_decoded(&selector, "etherscan", None, function, &data)?;
+                        return to_result(result);
+                    }
+                    None => notes.push(format!(
+                        "合约 {} 的 ABI 中没有该函数,可能是代理合约,可传入实现合约地址重试",
+                        checksum_address(address)
+                    )),
+                }
+            }
+            Err(e) => {
+                warn!(error = %e, "获取 Etherscan ABI 失败");
+                notes.push(format!("获取 Etherscan ABI 失败: {}", e));
+            }
+        },
+        Some(_) => notes.push("未配置 ETHERSCAN_API_KEY,无法查询合约 ABI".to_string()),
+        None => notes.push("内置 ABI 无法识别该函数,可传入 contract_address 从 Etherscan 查询".to_string()),
+    }
+
+    to_result(DecodedCalldataResult {
+        selector,
+        source: "unknown".to_string(),
+        contract: None,
+        function: None,
+        signature: None,
+        arguments: Vec::new(),
+        notes,
+    })
+}
+
+fn to_result(result: DecodedCalldataResult) -> Result<CallToolResult, McpError> {
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(source = %result.source, function = ?result.function, "成功解码调用数据");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 按函数定义解码参数
+fn build_decoded(
+    selector: &str,
+    source: &str,
+    contract: Option<&str>,
+    function: &Function,
+    data: &[u8],
+) -> Result<DecodedCalldataResult, McpError> {
+    let signature = function_signature(function);
+    let tokens = function.decode_input(&data[4..]).map_err(|e| {
+        McpError::invalid_params(format!("调用数据与函数 {} 的参数不匹配: {}", signature, e), None)
+    })?;
+
+    let arguments = decoded_arguments(&function.inputs, &tokens);
+
+    Ok(DecodedCalldataResult {
+        selector: selector.to_string(),
+        source: source.to_string(),
+        contract: contract.map(str::to_string),
+        function: Some(function.name.clone()),
+        signature: Some(signature),
+        arguments,
+        notes: Vec::new(),
+    })
+}
+
+/// 按参数定义列出解码后的值,未命名的参数按位置命名为 arg0、arg1……
+pub(crate) fn decoded_arguments(params: &[Param], tokens: &[Token]) -> Vec<DecodedArgument> {
+    params
+        .iter()
+        .zip(tokens)
+        .enumerate()
+        .map(|(index, (param, token))| DecodedArgument {
+            name: if param.name.is_empty() { format!("arg{}", index) } else { param.name.clone() },
+            kind: param.kind.to_string(),
+            value: token_to_json(token),
+        })
+        .collect()
+}
+
+/// ABI 值转换为 JSON(大整数用字符串,避免精度丢失)
+pub(crate) fn token_to_json(token: &Token) -> serde_json::Value {
+    match token {
+        Token::Address(address) => checksum_address(*address).into(),
+        Token::Uint(value) => value.to_string().into(),
+        Token::Int(value) => I256::from_raw(*value).to_string().into(),
+        Token::Bool(value) => (*value).into(),
+        Token::String(value) => value.clone().into(),
+        Token::Bytes(value) | Token::FixedBytes(value) => Bytes::from(value.clone()).to_string().into(),
+        Token::Array(items) | Token::FixedArray(items) | Token::Tuple(items) => {
+            items.iter().map(token_to_json).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::i_uniswap_v2_router_02::SwapExactTokensForTokensCall;
+    use ethers::abi::AbiEncode;
+
+    #[test]
+    fn test_build_decoded_router_call() {
+        let call = SwapExactTokensForTokensCall {
+            amount_in: U256::exp10(18),
+            amount_out_min: U256::from(2_900_000_000u64),
+            path: vec![Address::repeat_byte(0x11), Address::repeat_byte(0x22)],
+            to: Address::repeat_byte(0x33),
+            deadline: U256::from(1_700_000_000u64),
+        };
+        let data = call.encode();
+        let (contract, function) = find_known_function(&data[..4]).unwrap();
+
+        let decoded = build_decoded("0x38ed1739", "builtin", Some(contract), function, &data).unwrap();
+        assert_eq!(decoded.contract.as_deref(), Some("UniswapV2Router02"));
+        assert_eq!(decoded.function.as_deref(), Some("swapExactTokensForTokens"));
+        assert_eq!(decoded.arguments.len(), 5);
+        assert_eq!(decoded.arguments[0].name, "amountIn");
+        assert_eq!(decoded.arguments[0].value, "1000000000000000000");
+        assert_eq!(decoded.arguments[2].kind, "address[]");
+        assert_eq!(
+            decoded.arguments[2].value,
+            serde_json::json!([
+                checksum_address(Address::repeat_byte(0x11)),
+                checksum_address(Address::repeat_byte(0x22))
+            ])
+        );
+
+        // 参数被截断时返回错误
+        assert!(build_decoded("0x38ed1739", "builtin", Some(contract), function, &data[..40]).is_err());
+    }
+
+    #[test]
+    fn test_token_to_json() {
+        assert_eq!(token_to_json(&Token::Int(I256::from(-5).into_raw())), "-5");
+        assert_eq!(token_to_json(&Token::Bool(true)), true);
+        assert_eq!(token_to_json(&Token::Bytes(vec![0xde, 0xad])), "0xdead");
+        assert_eq!(
+            token_to_json(&Token::Tuple(vec![Token::Uint(U256::from(7)), Token::String("x".to_string())])),
+            serde_json::json!(["7", "x"])
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{config::Config, eth_client::EthClient, logging::info};
+use chrono::{DateTime, Utc};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 用于估算平均出块时间的回溯区块数
+const BLOCK_TIME_SAMPLE_SIZE: u64 = 100;
+
+/// GetChainTime 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetChainTimeArgs {
+    /// 区块号(可选,不填则使用最新区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+}
+
+/// GetChainTime 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ChainTimeResult {
+    pub block_number: u64,
+    /// 区块时间戳(Unix 秒)
+    pub block_timestamp: u64,
+    /// 区块时间(UTC, RFC 3339)
+    pub block_time_utc: String,
+    /// 服务器当前时间戳(Unix 秒)
+    pub wall_clock_timestamp: u64,
+    /// 服务器时间与区块时间的差值(秒,正数表示区块时间落后)
+    pub drift_seconds: i64,
+    /// 最近区块的平均出块时间(秒)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_block_time_seconds: Option<f64>,
+    /// 按平均出块时间估算的每日区块数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_blocks_per_day: Option<u64>,
+}
+
+/// 获取链上时间(最新区块时间戳及与服务器时钟的偏差)
+pub async fn get_chain_time(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    Parameters(args): Parameters<GetChainTimeArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_chain_time 请求");
+
+    let wall_clock_timestamp = Utc::now().timestamp() as u64;
+
+    // 测试模式
+    if config.server.test_mode {
+        let block_timestamp = wall_clock_timestamp - 6;
+        let result = build_chain_time_result(
+            args.block_number.unwrap_or(20_000_000),
+            block_timestamp,
+            wall_clock_timestamp,
+            Some(12.0),
+        );
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let eth_client = eth_client.clone();
+
+    let result = async {
+        let block = args
+            .block_number
+            .map(BlockNumber::from)
+            .unwrap_or(BlockNumber::Latest);
+
+        let (block_number, block_timestamp) = eth_client
+            .get_block_timestamp(block)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询区块时间失败: {}", e), None))?;
+
+        // 回溯若干区块估算平均出块时间(失败时不影响主结果)
+        let average_block_time = if block_number >= BLOCK_TIME_SAMPLE_SIZE {
+            eth_client
+                .get_block_timestamp(BlockNumber::from(block_number - BLOCK_TIME_SAMPLE_SIZE))
+                .await
+                .ok()
+                .map(|(_, earlier)| {
+                    block_timestamp.saturating_sub(earlier) as f64 / BLOCK_TIME_SAMPLE_SIZE as f64
+                })
+        } else {
+            None
+        };
+
+        Ok::<_, McpError>(build_chain_time_result(
+            block_number,
+            block_timestamp,
+            wall_clock_timestamp,
+            average_block_time,
+        ))
+    }
+    .await?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!("成功返回链上时间");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 根据区块时间和服务器时间构建返回结果
+fn build_chain_time_result(
+    block_number: u64,
+    block_timestamp: u64,
+    wall_clock_timestamp: u64,
+    average_block_time: Option<f64>,
+) -> ChainTimeResult {
+    let block_time_utc = DateTime::<Utc>::from_timestamp(block_timestamp as i64, 0)
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_default();
+
+    // 平均出块时间为 0 时(异常数据)不输出估算
+    let average_block_time = average_block_time.filter(|t| *t > 0.0);
+
+    ChainTimeResult {
+        block_number,
+        block_timestamp,
+        block_time_utc,
+        wall_clock_timestamp,
+        drift_seconds: wall_clock_timestamp as i64 - block_timestamp as i64,
+        average_block_time_seconds: average_block_time,
+        estimated_blocks_per_day: average_block_time.map(|t| (86_400.0 / t) as u64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_chain_time_result() {
+        let result = build_chain_time_result(100, 1_700_000_000, 1_700_000_030, Some(12.0));
+        assert_eq!(result.drift_seconds, 30);
+        assert_eq!(result.block_time_utc, "2023-11-14T22:13:20+00:00");
+        assert_eq!(result.estimated_blocks_per_day, Some(7200));
+
+        // 区块时间领先于服务器时钟时偏差为负
+        let result = build_chain_time_result(100, 1_700_000_030, 1_700_000_000, None);
+        assert_eq!(result.drift_seconds, -30);
+        assert_eq!(result.estimated_blocks_per_day, None);
+    }
+
+    #[test]
+    fn test_get_chain_time_args_deserialization() {
+        let args: GetChainTimeArgs = serde_json::from_str("{}").expect("应该能反序列化");
+        assert_eq!(args.block_number, None);
+
+        let args: GetChainTimeArgs =
+            serde_json::from_str(r#"{"block_number":123}"#).expect("应该能反序列化");
+        assert_eq!(args.block_number, Some(123));
+    }
+}
@@ -0,0 +1,322 @@
+use crate::{
+    config::Config,
+    eth_client::EthClient,
+    etherscan::{ContractSource, EtherscanClient},
+    logging::{info, warn},
+    token_registry::TokenRegistry,
+    tools::user_operation::token_address,
+    types::checksum_address,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// EIP-1967 实现合约存储槽:bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)
+const EIP1967_IMPLEMENTATION_SLOT: &str =
+    "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc";
+
+/// CheckContractVerification 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CheckContractVerificationArgs {
+    /// 代币符号或合约地址(代币、交易对或任意合约,必需)
+    pub address: String,
+}
+
+/// CheckContractVerification 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ContractVerificationResult {
+    pub address: String,
+    /// 地址上是否有合约代码
+    pub is_contract: bool,
+    /// 源码是否已在 Etherscan 验证
+    pub verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compiler_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimization_used: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimization_runs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evm_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// 是否为代理合约(Etherscan 标记或 EIP-1967 实现槽非空)
+    pub is_proxy: bool,
+    /// 代理合约当前指向的实现合约
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub implementation: Option<ImplementationInfo>,
+    /// 合约及实现合约(代理时)均已验证,可以正常报价
+    pub safe_to_quote: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 代理合约的实现合约
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ImplementationInfo {
+    pub address: String,
+    pub verified: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_name: Option<String>,
+}
+
+/// 查询合约是否在 Etherscan 验证源码,以及合约名称、编译器版本和代理状态
+pub async fn check_contract_verification(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    etherscan_client: &Arc<EtherscanClient>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<CheckContractVerificationArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 check_contract_verification 请求");
+
+    let resolved = token_registry
+        .resolve(&args.address)
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+    let address = token_address(&resolved)?;
+
+    info!(address = %address, "查询合约验证状态");
+
+    // 测试模式
+    if config.server.test_mode {
+        let result = ContractVerificationResult {
+            address: checksum_address(address),
+            is_contract: true,
+            verified: true,
+            contract_name: Some("TestToken".to_string()),
+            compiler_version: Some("v0.8.20+commit.a1b79de6".to_string()),
+            optimization_used: Some(true),
+            optimization_runs: Some(200),
+            evm_version: Some("paris".to_string()),
+            license: Some("MIT".to_string()),
+            is_proxy: false,
+            implementation: None,
+            safe_to_quote: true,
+            notes: Vec::new(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+    if !etherscan_client.is_available() {
+        return Err(McpError::invalid_request(
+            "未配置 ETHERSCAN_API_KEY,无法查询合约验证状态",
+            None,
+        ));
+    }
+
+    let slot: H256 = EIP1967_IMPLEMENTATION_SLOT.parse().expect("硬编码存储槽应该有效");
+    let (code, eip1967_slot, source) = tokio::join!(
+        eth_client.get_code(address, None),
+        eth_client.get_storage_at(address, slot, None),
+        etherscan_client.contract_source(address)
+    );
+
+    let code = code.map_err(|e| McpError::internal_error(format!("查询合约代码失败: {}", e), None))?;
+    let source = source
+        .map_err(|e| McpError::internal_error(format!("查询 Etherscan 源码信息失败: {}", e), None))?;
+
+    let mut notes = Vec::new();
+    if code.is_empty() {
+        notes.push("该地址没有合约代码(普通账户或合约已销毁)".to_string());
+    }
+
+    let eip1967_implementation = eip1967_slot
+        .inspect_err(|e| warn!(error = %e, "读取 EIP-1967 实现槽失败"))
+        .ok()
+        .map(|value| Address::from_slice(&value.as_bytes()[12..]))
+        .filter(|implementation| !implementation.is_zero());
+    if let (Some(onchain), Some(reported)) = (eip1967_implementation, source.implementation)
+        && onchain != reported
+    {
+        notes.push(format!(
+            "EIP-1967 实现槽指向 {},与 Etherscan 记录的实现合约 {} 不一致,代理可能已升级",
+            checksum_address(onchain),
+            checksum_address(reported)
+        ));
+    }
+
+    // 以链上实现槽为准,Etherscan 记录作为补充
+    let implementation_addr = eip1967_implementation.or(source.implementation);
+    let implementation = match implementation_addr {
+        Some(implementation) => {
+            let info = etherscan_client
+                .contract_source(implementation)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询实现合约源码信息失败: {}", e), None))?;
+            Some(ImplementationInfo {
+                address: checksum_address(implementation),
+                verified: info.verified,
+                contract_name: info.contract_name,
+            })
+        }
+        None => None,
+    };
+
+    let is_proxy = source.proxy || implementation.is_some();
+    if is_proxy {
+        notes.push("代理合约的逻辑可以被管理员升级,报价时的行为不代表未来的行为".to_string());
+    }
+    let safe_to_quote = !code.is_empty()
+        && source.verified
+        && implementation.as_ref().is_none_or(|implementation| implementation.verified);
+    if !safe_to_quote {
+        notes.push("合约或实现合约源码未验证,除非用户明确确认,不应交换为该代币".to_string());
+    }
+
+    let ContractSource {
+        verified,
+        contract_name,
+        compiler_version,
+        optimization_used,
+        optimization_runs,
+        evm_version,
+        license,
+        ..
+    } = source;
+
+    let result = ContractVerificationResult {
+        address: checksum_address(address),
+        is_contract: !code.is_empty(),
+        verified,
+        contract_name,
+        compiler_version,
+        optimization_used,
+        optimization_runs,
+        evm_version,
+        license,
+        is_proxy,
+        implementation,
+        safe_to_quote,
+        notes,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        address = %result.address,
+        verified = result.verified,
+        is_proxy = result.is_proxy,
+        "成功返回合约验证状态"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 交换前确认目标代币合约已在 Etherscan 验证源码
+/// 未配置 Etherscan 或 `allow_unverified` 为 true 时跳过；查询失败时只记录警告,不阻断报价
+pub(crate) async fn ensure_verified_contract(
+    etherscan_client: &EtherscanClient,
+    token: Address,
+    allow_unverified: bool,
+) -> Result<(), McpError> {
+    if allow_unverified || !etherscan_client.is_available() {
+        return Ok(());
+    }
+
+    let source = match etherscan_client.contract_source(token).await {
+        Ok(source) => source,
+        Err(e) => {
+            warn!(token = %token, error = %e, "查询合约验证状态失败,跳过检查");
+            return Ok(());
+        }
+    };
+    let implementation = match source.implementation {
+        Some(implementation) if source.verified => match etherscan_client.contract_source(implementation).await {
+            Ok(implementation) => Some(implementation),
+            Err(e) => {
+                warn!(implementation = %implementation, error = %e, "查询实现合约验证状态失败,跳过检查");
+                None
+            }
+        },
+        _ => None,
+    };
+
+    unverified_refusal(token, &source, implementation.as_ref()).map_or(Ok(()), Err)
+}
+
+/// 合约或实现合约未验证时返回结构化的拒绝错误
+fn unverified_refusal(
+    token: Address,
+    source: &ContractSource,
+    implementation: Option<&ContractSource>,
+) -> Option<McpError> {
+    let message = if !source.verified {
+        format!("目标代币合约 {} 未在 Etherscan 验证源码,已拒绝报价", checksum_address(token))
+    } else if implementation.is_some_and(|implementation| !implementation.verified) {
+        format!("目标代币 {} 是代理合约,其实现合约未在 Etherscan 验证源码,已拒绝报价", checksum_address(token))
+    } else {
+        return None;
+    };
+
+    Some(McpError::invalid_request(
+        format!("{}(用户确认后可传入 allow_unverified: true)", message),
+        Some(serde_json::json!({
+            "refused": true,
+            "reason": "unverified_contract",
+            "token": checksum_address(token),
+            "implementation": source.implementation.map(checksum_address),
+        })),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(verified: bool, implementation: Option<Address>) -> ContractSource {
+        ContractSource {
+            verified,
+            contract_name: verified.then(|| "Token".to_string()),
+            compiler_version: None,
+            optimization_used: None,
+            optimization_runs: None,
+            evm_version: None,
+            license: None,
+            proxy: implementation.is_some(),
+            implementation,
+        }
+    }
+
+    #[test]
+    fn test_unverified_refusal() {
+        let token = Address::repeat_byte(0x11);
+        let implementation = Address::repeat_byte(0x22);
+
+        assert!(unverified_refusal(token, &source(true, None), None).is_none());
+
+        let refused = unverified_refusal(token, &source(false, None), None).unwrap();
+        assert_eq!(refused.data.as_ref().unwrap()["reason"], "unverified_contract");
+        assert!(refused.message.contains("allow_unverified"));
+
+        // 代理本身已验证,但实现合约未验证
+        let proxy = source(true, Some(implementation));
+        let refused = unverified_refusal(token, &proxy, Some(&source(false, None))).unwrap();
+        assert_eq!(
+            refused.data.as_ref().unwrap()["implementation"],
+            checksum_address(implementation)
+        );
+        assert!(unverified_refusal(token, &proxy, Some(&source(true, None))).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_ensure_verified_skips_without_etherscan() {
+        let etherscan = EtherscanClient::new(None, 1);
+        assert!(ensure_verified_contract(&etherscan, Address::repeat_byte(0x11), false).await.is_ok());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{
+    logging::info,
+    pnl::{self, Position},
+    store::Store,
+    token_registry::TokenRegistry,
+    tools::pnl::format_usd,
+};
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, tool, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// GetCostBasis 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetCostBasisArgs {
+    /// 钱包地址(必需)
+    pub address: String,
+    /// 代币地址或符号(可选,不填则返回全部代币)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+/// GetCostBasis 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct CostBasisResult {
+    pub address: String,
+    pub positions: Vec<CostBasisPosition>,
+}
+
+/// 单个代币的持仓成本
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct CostBasisPosition {
+    pub token: String,
+    pub symbol: String,
+    pub quantity: String,
+    /// 平均买入成本(含手续费和 Gas)
+    pub average_entry_usd: String,
+    pub cost_basis_usd: String,
+    pub fees_usd: String,
+    /// 全部历史的已实现盈亏
+    pub realized_pnl_usd: String,
+    pub entry_count: usize,
+}
+
+/// 查询钱包的持仓成本(来自服务器自身的成本台账)
+#[tool(description = "查询钱包各代币的平均买入成本、手续费和已实现盈亏(来自本地成本台账,需配置 DATABASE_PATH)")]
+pub fn get_cost_basis(
+    store: &Arc<Store>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<GetCostBasisArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_cost_basis 请求");
+    info!(address = %args.address, token = ?args.token, "查询持仓成本");
+
+    if !store.is_enabled() {
+        return Err(McpError::internal_error(
+            "持久化未启用,请配置 DATABASE_PATH",
+            None,
+        ));
+    }
+
+    let token_address = match args.token {
+        Some(ref token) => Some(
+            token_registry
+                .resolve(token)
+                .map_err(|e| McpError::invalid_params(e.to_string(), None))?
+                .address,
+        ),
+        None => None,
+    };
+
+    let entries = store
+        .ledger_entries(&args.address, token_address.as_deref())
+        .map_err(|e| McpError::internal_error(format!("查询成本台账失败: {}", e), None))?;
+
+    let positions = pnl::compute_positions(&entries, None)
+        .iter()
+        .map(build_cost_basis_position)
+        .collect();
+
+    let result = CostBasisResult {
+        address: args.address,
+        positions,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!("成功返回持仓成本");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+fn build_cost_basis_position(position: &Position) -> CostBasisPosition {
+    CostBasisPosition {
+        token: position.token.clone(),
+        symbol: position.symbol.clone(),
+        quantity: position.quantity.normalize().to_string(),
+        average_entry_usd: position.average_cost().round_dp(6).normalize().to_string(),
+        cost_basis_usd: format_usd(position.cost_basis),
+        fees_usd: format_usd(position.fees_usd),
+        realized_pnl_usd: format_usd(position.realized_pnl),
+        entry_count: position.entry_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pnl::ExecutionFill;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_get_cost_basis_from_execution() {
+        let store = Arc::new(Store::open(Some(":memory:")).unwrap());
+        let registry = Arc::new(TokenRegistry::new());
+        let uni = registry.resolve("UNI").expect("注册表应包含 UNI");
+
+        let fill = ExecutionFill {
+            tx_hash: "0xabc".to_string(),
+            block_number: 100,
+            timestamp: 1_200,
+            from_token: registry.resolve("USDC").unwrap().address,
+            from_symbol: "USDC".to_string(),
+            amount_in: Decimal::from(1000),
+            to_token: uni.address.clone(),
+            to_symbol: "UNI".to_string(),
+            amount_out: Decimal::from(125),
+            from_price_usd: Some(Decimal::ONE),
+            to_price_usd: None,
+            gas_fee_usd: Decimal::from(5),
+        };
+        let entries = pnl::execution_entries(&fill).unwrap();
+        store.record_execution("0xWallet", &entries).unwrap();
+
+        let args = GetCostBasisArgs {
+            address: "0xWallet".to_string(),
+            token: Some("UNI".to_string()),
+        };
+        let result = get_cost_basis(&store, &registry, Parameters(args)).unwrap();
+        let text = result.content[0].as_text().unwrap().text.clone();
+        let parsed: CostBasisResult = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(parsed.positions.len(), 1);
+        assert_eq!(parsed.positions[0].quantity, "125");
+        assert_eq!(parsed.positions[0].average_entry_usd, "8.04");
+        assert_eq!(parsed.positions[0].fees_usd, "5");
+    }
+
+    #[test]
+    fn test_get_cost_basis_requires_store() {
+        let args = GetCostBasisArgs {
+            address: "0xWallet".to_string(),
+            token: None,
+        };
+        let result = get_cost_basis(
+            &Arc::new(Store::disabled()),
+            &Arc::new(TokenRegistry::new()),
+            Parameters(args),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use crate::{
+    compliance::{ComplianceScreen, ScreeningDecision},
+    config::Config,
+    cow::{CowClient, CowOrder, CowOrderStatus, COW_VAULT_RELAYER},
+    erc20::{format_units, parse_units, Erc20Client},
+    eth_client::EthClient,
+    logging::{info, warn},
+    signer::TxSigner,
+    store::Store,
+    token_registry::TokenRegistry,
+    tools::user_operation::{resolve_token, token_address},
+    types::{checksum_address, TokenInfo},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 订单默认有效期(秒)
+const DEFAULT_VALID_SECS: u64 = 1800;
+
+/// PlaceCowOrder 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PlaceCowOrderArgs {
+    /// 卖出代币地址或符号(必需)
+    pub from_token: String,
+    /// 买入代币地址或符号(必需)
+    pub to_token: String,
+    /// 卖出数量(必需)
+    pub amount: String,
+    /// 滑点(基点,可选,默认使用 DEFAULT_SLIPPAGE_BPS 配置)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slippage_bps: Option<u32>,
+    /// 订单有效期(秒,可选,默认 1800)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_secs: Option<u64>,
+    /// 是否提交到订单簿(可选,默认 false,仅返回报价和签名订单)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submit: Option<bool>,
+}
+
+/// PlaceCowOrder 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PlaceCowOrderResult {
+    pub from_token: TokenInfo,
+    pub to_token: TokenInfo,
+    pub owner: String,
+    /// 卖出数量(含协议费)
+    pub sell_amount: String,
+    /// 报价买入数量
+    pub quoted_buy_amount: String,
+    /// 签名订单的最小买入数量(已扣除滑点)
+    pub minimum_buy_amount: String,
+    /// 报价中的协议费(以卖出代币计)
+    pub fee_amount: String,
+    pub valid_to: u32,
+    /// 已签名的订单
+    pub order: CowOrder,
+    pub signature: String,
+    /// 卖出代币是否需要先授权 GPv2VaultRelayer(查询失败时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_required: Option<bool>,
+    /// 订单 UID(已提交时返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_uid: Option<String>,
+    pub submitted: bool,
+    /// 制裁名单命中但只标记时的筛查结论
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ScreeningDecision>,
+}
+
+/// GetOrderStatus 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetOrderStatusArgs {
+    /// place_cow_order 返回的订单 UID(必需)
+    pub order_uid: String,
+}
+
+/// 通过 CoW Protocol 下单
+#[allow(clippy::too_many_arguments)]
+pub async fn place_cow_order(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    cow_client: &Arc<CowClient>,
+    store: &Arc<Store>,
+    compliance: &Arc<ComplianceScreen>,
+    signer: Option<&TxSigner>,
+    Parameters(args): Parameters<PlaceCowOrderArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 place_cow_order 请求");
+
+    let slippage_bps = args.slippage_bps.unwrap_or(config.trading.default_slippage_bps);
+
+    // 🔒 校验滑点范围（0-10000 基点，即 0-100%）
+    if slippage_bps > 10000 {
+        return Err(McpError::invalid_params(
+            format!(
+                "滑点参数无效: {} bps (必须 ≤ 10000，即 ≤ 100%)",
+                slippage_bps
+            ),
+            None,
+        ));
+    }
+
+    let valid_secs = args.valid_secs.unwrap_or(DEFAULT_VALID_SECS);
+    let submit = args.submit.unwrap_or(false);
+    let chain_id = config.ethereum.chain_id;
+
+    info!(
+        from = %args.from_token,
+        to = %args.to_token,
+        amount = %args.amount,
+        slippage = slippage_bps,
+        submit,
+        "创建 CoW 订单"
+    );
+
+    // 测试模式:订单所有者为签名器地址(未配置签名器时为模拟地址)
+    if config.server.test_mode {
+        let owner = signer
+            .map(|wallet| wallet.address())
+            .unwrap_or_else(|| config.get_simulation_address());
+        let token = |address: &str| TokenInfo {
+            symbol: address.to_string(),
+            name: address.to_string(),
+            address: address.to_string(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+        let order = CowOrder {
+            sell_token: Address::zero(),
+            buy_token: Address::zero(),
+            receiver: owner,
+            sell_amount: U256::exp10(18),
+            buy_amount: U256::exp10(20),
+            valid_to: valid_secs as u32,
+            app_data: H256::zero(),
+            fee_amount: U256::zero(),
+            kind: "sell".to_string(),
+            partially_fillable: false,
+            sell_token_balance: "erc20".to_string(),
+            buy_token_balance: "erc20".to_string(),
+        };
+
+        let result = PlaceCowOrderResult {
+            from_token: token(&args.from_token),
+            to_token: token(&args.to_token),
+            owner: checksum_address(owner),
+            sell_amount: args.amount.clone(),
+            quoted_buy_amount: "100.5".to_string(),
+            minimum_buy_amount: "100".to_string(),
+            fee_amount: "0.001".to_string(),
+            valid_to: order.valid_to,
+            order,
+            signature: "0x".to_string(),
+            approval_required: Some(false),
+            order_uid: None,
+            submitted: false,
+            compliance: None,
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    let wallet = signer.ok_or_else(|| McpError::invalid_params("未配置签名器", None))?;
+
+    cow_client
+        .base_url()
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+    // 真实模式:需要检查客户端可用性
+    if !erc20_client.is_available() {
+        return Err(McpError::internal_error(
+            "ERC20 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let eth_client = eth_client.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+    let cow_client = cow_client.clone();
+
+    let result = async {
+        let from_info = resolve_token(&token_registry, &erc20_client, &args.from_token).await?;
+        let to_info = resolve_token(&token_registry, &erc20_client, &args.to_token).await?;
+        let sell_token = token_address(&from_info)?;
+        let buy_token = token_address(&to_info)?;
+        let owner = wallet.address();
+
+        let amount = parse_units(&args.amount, from_info.decimals)
+            .map_err(|e| McpError::invalid_params(format!("解析金额失败: {}", e), None))?;
+
+        let (_, now) = eth_client
+            .get_block_timestamp(BlockNumber::Latest)
+            .await
+            .map_err(|e| McpError::internal_error(format!("获取区块时间失败: {}", e), None))?;
+        let valid_to = u32::try_from(now + valid_secs)
+            .map_err(|_| McpError::invalid_params("订单有效期过长", None))?;
+
+        let (quote, quote_id) = cow_client
+            .quote(sell_token, buy_token, amount, owner, valid_to)
+            .await
+            .map_err(|e| McpError::internal_error(format!("获取 CoW 报价失败: {}", e), None))?;
+
+        // 🔒 报价来自外部 API,签名前校验代币、接收方、数量和类型与请求一致
+        quote
+            .verify(sell_token, buy_token, amount, owner)
+            .map_err(|e| McpError::internal_error(format!("CoW 报价校验失败,拒绝签名: {}", e), None))?;
+
+        let order = CowOrder::from_quote(&quote, slippage_bps);
+
+        // 🛡️ 制裁名单筛查(签名前),接收方为零地址表示订单所有者
+        let receiver = if order.receiver.is_zero() { owner } else { order.receiver };
+        let screening = compliance.screen(store, "place_cow_order", &[("wallet", owner), ("recipient", receiver)])?;
+
+        let typed = order
+            .typed_data(chain_id)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        let signature = wallet
+            .sign_typed_data(&typed)
+            .await
+            .map_err(|e| McpError::internal_error(format!("签名失败: {}", e), None))?;
+
+        // 卖出代币需要授权给 VaultRelayer，否则订单无法成交
+        let relayer: Address = COW_VAULT_RELAYER.parse().expect("硬编码地址应该有效");
+        let approval_required = match erc20_client.allowance(sell_token, owner, relayer).await {
+            Ok(allowance) => Some(allowance < order.sell_amount),
+            Err(e) => {
+                warn!(error = %e, "查询 VaultRelayer 授权额度失败");
+                None
+            }
+        };
+
+        let order_uid = if submit {
+            Some(
+                cow_client
+                    .submit(&order, &signature, owner, quote_id)
+                    .await
+                    .map_err(|e| McpError::internal_error(format!("提交 CoW 订单失败: {}", e), None))?,
+            )
+        } else {
+            None
+        };
+
+        Ok::<_, McpError>(PlaceCowOrderResult {
+            sell_amount: format_units(order.sell_amount, from_info.decimals),
+            quoted_buy_amount: format_units(quote.buy_amount, to_info.decimals),
+            minimum_buy_amount: format_units(order.buy_amount, to_info.decimals),
+            fee_amount: format_units(quote.fee_amount, from_info.decimals),
+            from_token: from_info,
+            to_token: to_info,
+            owner: checksum_address(owner),
+            valid_to,
+            order,
+            signature: format!("0x{}", signature),
+            approval_required,
+            submitted: order_uid.is_some(),
+            order_uid,
+            compliance: screening,
+        })
+    }
+    .await?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        submitted = result.submitted,
+        order_uid = ?result.order_uid,
+        "成功返回 CoW 订单"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 查询 CoW 订单状态
+pub async fn get_order_status(
+    config: &Arc<Config>,
+    cow_client: &Arc<CowClient>,
+    Parameters(args): Parameters<GetOrderStatusArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_order_status 请求");
+
+    // 测试模式
+    let status = if config.server.test_mode {
+        CowOrderStatus {
+            uid: args.order_uid.clone(),
+            status: "fulfilled".to_string(),
+            executed_sell_amount: Some("1000000000000000000".to_string()),
+            executed_buy_amount: Some("3000000000".to_string()),
+            creation_date: None,
+        }
+    } else {
+        cow_client
+            .order_status(&args.order_uid)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询 CoW 订单状态失败: {}", e), None))?
+    };
+
+    let json_str = serde_json::to_string_pretty(&status)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(uid = %status.uid, status = %status.status, "成功返回 CoW 订单状态");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
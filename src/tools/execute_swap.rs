@@ -0,0 +1,638 @@
+use crate::{
+    compliance::{ComplianceScreen, ScreeningDecision},
+    config::Config,
+    erc20::{format_units, parse_units, Erc20Client, TRANSFER_EVENT_TOPIC},
+    eth_client::EthClient,
+    etherscan::EtherscanClient,
+    logging::{info, warn},
+    pnl::{self, ExecutionFill},
+    safe::{SafeClient, SafeProposal},
+    store::{NewRecord, RecordKind, Store},
+    token_registry::TokenRegistry,
+    tools::contract_verification::ensure_verified_contract,
+    tools::price::fetch_token_price_usd_at,
+    tools::swap::{enforce_price_impact_limit, minimum_output, traced_output},
+    tools::user_operation::{resolve_token, token_address},
+    signer::TxSigner,
+    tx_manager::TxManager,
+    types::{checksum_address, TokenInfo, TxType},
+    uniswap::{is_fee_on_transfer_revert, NativeLeg, SwapCall, UniswapV2Client},
+};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 交换默认有效期(秒)
+const DEFAULT_DEADLINE_SECS: u64 = 1200;
+/// 在 eth_estimateGas 结果上预留的余量(百分比)
+const GAS_LIMIT_BUFFER_PERCENT: u64 = 20;
+/// 等待交易回执的最长时间
+pub(crate) const RECEIPT_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// ExecuteSwap 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ExecuteSwapArgs {
+    /// 源代币地址或符号(必需)
+    pub from_token: String,
+    /// 目标代币地址或符号(必需)
+    pub to_token: String,
+    /// 交易数量(必需)
+    pub amount: String,
+    /// 滑点(基点,可选,默认使用 DEFAULT_SLIPPAGE_BPS 配置)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slippage_bps: Option<u32>,
+    /// 交换有效期(秒,可选,默认 1200)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline_secs: Option<u64>,
+    /// 交易类型(可选,auto/legacy/eip1559,默认使用 TX_TYPE 配置)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<String>,
+    /// 允许的最大价格影响(基点,可选,默认使用 MAX_PRICE_IMPACT_BPS 配置,0 表示不限制)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_price_impact_bps: Option<u32>,
+    /// 转账税代币(可选,true 时直接使用 SupportingFeeOnTransferTokens 函数,false 关闭自动检测;
+    /// 默认在标准调用因 UniswapV2: K 回滚时自动切换)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_on_transfer: Option<bool>,
+    /// 允许目标代币为未在 Etherscan 验证源码的合约(可选,默认 false,需用户明确确认)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_unverified: Option<bool>,
+}
+
+/// ExecuteSwap 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ExecuteSwapResult {
+    pub from_token: TokenInfo,
+    pub to_token: TokenInfo,
+    pub wallet: String,
+    pub input_amount: String,
+    pub estimated_output: String,
+    pub minimum_output: String,
+    pub price_impact: String,
+    pub slippage_bps: u32,
+    pub tx_type: String,
+    pub gas_limit: String,
+    /// 是否使用 SupportingFeeOnTransferTokens 函数(最小输出按实际到账数量计算)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_on_transfer: Option<bool>,
+    /// 交易哈希(Safe 模式下不广播,为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    /// success / reverted / pending(等待回执超时,交易仍在内存池中)
+    /// Safe 模式下为 proposed(已提议到 Safe 交易服务)或 unsigned(只返回交易数据)
+    pub status: String,
+    /// 返回时的确认数(包含交易所在区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<String>,
+    /// 制裁名单命中但只标记时的筛查结论
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ScreeningDecision>,
+    /// Safe 模式下的 Safe 交易数据和提议结果
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub safe: Option<SafeProposal>,
+}
+
+/// 签名并广播 Uniswap V2 交换(Safe 模式下构建 Safe 交易并提议)
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_swap(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    store: &Arc<Store>,
+    compliance: &Arc<ComplianceScreen>,
+    tx_manager: &Arc<TxManager>,
+    etherscan_client: &Arc<EtherscanClient>,
+    safe_client: &Arc<SafeClient>,
+    signer: Option<&TxSigner>,
+    Parameters(args): Parameters<ExecuteSwapArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 execute_swap 请求");
+
+    let slippage_bps = args.slippage_bps.unwrap_or(config.trading.default_slippage_bps);
+
+    // 🔒 校验滑点范围（0-10000 基点，即 0-100%）
+    if slippage_bps > 10000 {
+        return Err(McpError::invalid_params(
+            format!(
+                "滑点参数无效: {} bps (必须 ≤ 10000，即 ≤ 100%)",
+                slippage_bps
+            ),
+            None,
+        ));
+    }
+
+    let tx_type_preference = config
+        .tx_type_preference(args.tx_type.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let max_price_impact_bps = config
+        .price_impact_limit(args.max_price_impact_bps)
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let deadline_secs = args.deadline_secs.unwrap_or(DEFAULT_DEADLINE_SECS);
+
+    info!(
+        from = %args.from_token,
+        to = %args.to_token,
+        amount = %args.amount,
+        slippage = slippage_bps,
+        tx_type = ?tx_type_preference,
+        "执行代币交换"
+    );
+
+    // 测试模式:不签名也不广播
+    if config.server.test_mode {
+        let token = |address: &str| TokenInfo {
+            symbol: address.to_string(),
+            name: address.to_string(),
+            address: address.to_string(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+
+        let result = ExecuteSwapResult {
+            from_token: token(&args.from_token),
+            to_token: token(&args.to_token),
+            wallet: checksum_address(config.get_simulation_address()),
+            input_amount: args.amount.clone(),
+            estimated_output: "100.0".to_string(),
+            minimum_output: "99.5".to_string(),
+            price_impact: "0.5%".to_string(),
+            slippage_bps,
+            tx_type: tx_type_preference.unwrap_or(TxType::Eip1559).as_str().to_string(),
+            gas_limit: "180000".to_string(),
+            fee_on_transfer: None,
+            tx_hash: Some(format!("{:?}", H256::zero())),
+            nonce: Some(0),
+            status: "success".to_string(),
+            confirmations: Some(config.trading.tx_confirmations),
+            block_number: None,
+            gas_used: Some("150000".to_string()),
+            compliance: None,
+            safe: None,
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // Safe 模式下资金从 Safe 转出,签名器(可选)只用于提议
+    let safe = safe_client.safe_address();
+    let owner = match safe {
+        Some(safe) => safe,
+        None => signer
+            .ok_or_else(|| McpError::invalid_params("未配置签名器", None))?
+            .address(),
+    };
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() || !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    // 🛡️ 制裁名单筛查
+    let screening = compliance.screen(store, "execute_swap", &[("wallet", owner)])?;
+
+    let eth_client = eth_client.clone();
+    let tx_manager = tx_manager.clone();
+    let uniswap_client = uniswap_client.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+    let gas_strategy = config.trading.gas_price_strategy.clone();
+    let max_gas_limit = config.trading.max_gas_limit;
+    let chain_id = config.ethereum.chain_id;
+
+    let mut result = async {
+        let from_info = resolve_token(&token_registry, &erc20_client, &args.from_token).await?;
+        let to_info = resolve_token(&token_registry, &erc20_client, &args.to_token).await?;
+        let from_addr = token_address(&from_info)?;
+        let to_addr = token_address(&to_info)?;
+
+        // 🔍 目标代币合约未验证源码时拒绝交换
+        ensure_verified_contract(etherscan_client, to_addr, args.allow_unverified.unwrap_or(false)).await?;
+
+        let amount_in = parse_units(&args.amount, from_info.decimals)
+            .map_err(|e| McpError::invalid_params(format!("解析金额失败: {}", e), None))?;
+
+        let tx_type = eth_client
+            .resolve_tx_type(tx_type_preference)
+            .await
+            .map_err(|e| McpError::internal_error(format!("探测交易类型失败: {}", e), None))?;
+
+        let quote = uniswap_client
+            .quote_swap(from_addr, to_addr, amount_in)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询交换报价失败: {}", e), None))?;
+
+        // 🔒 价格影响超过上限时拒绝执行
+        enforce_price_impact_limit(quote.price_impact, max_price_impact_bps)?;
+
+        // 原生代币一侧使用 Router 的 ETH 版本函数(支付时随交易发送 value)
+        let native = uniswap_client.native_leg(&from_info, &to_info);
+        let mut amount_out_min = minimum_output(quote.amount_out, slippage_bps);
+
+        // 授权不足时交易必然回滚，直接拒绝（支付原生代币时不需要授权）
+        let router = uniswap_client.router_address();
+        let allowance = if native == NativeLeg::Input {
+            U256::MAX
+        } else {
+            erc20_client
+                .allowance(from_addr, owner, router)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询授权额度失败: {}", e), None))?
+        };
+        if allowance < amount_in {
+            return Err(McpError::invalid_request(
+                format!(
+                    "钱包对 Router 的授权额度不足: {} < {} {}",
+                    format_units(allowance, from_info.decimals),
+                    args.amount,
+                    from_info.symbol
+                ),
+                Some(serde_json::json!({
+                    "refused": true,
+                    "reason": "insufficient_allowance",
+                    "current_allowance": format_units(allowance, from_info.decimals),
+                })),
+            ));
+        }
+
+        let (_, now) = eth_client
+            .get_block_timestamp(BlockNumber::Latest)
+            .await
+            .map_err(|e| McpError::internal_error(format!("获取区块时间失败: {}", e), None))?;
+
+        // 标准调用因输入代币转账税回滚时改用 SupportingFeeOnTransferTokens 函数
+        let fee_on_transfer = match args.fee_on_transfer {
+            Some(fee_on_transfer) => fee_on_transfer,
+            None => {
+                let simulation = uniswap_client
+                    .simulate_swap(from_addr, to_addr, amount_in, amount_out_min, native, false, Some(owner), tx_type)
+                    .await
+                    .map_err(|e| McpError::internal_error(format!("模拟交换失败: {}", e), None))?;
+                !simulation.simulation_success
+                    && simulation.revert_reason.as_deref().is_some_and(is_fee_on_transfer_revert)
+            }
+        };
+
+        let deadline = U256::from(now + deadline_secs);
+        let mut call = SwapCall {
+            amount_in,
+            amount_out_min,
+            path: quote.path.clone(),
+            to: owner,
+            deadline,
+            native,
+            fee_on_transfer,
+        };
+
+        // 转账税代币先以 amountOutMin = 0 跟踪实际到账数量，再按到账数量计算最小输出
+        if fee_on_transfer {
+            info!("按转账税代币执行交换");
+            call.amount_out_min = U256::zero();
+            let received_token = (native != NativeLeg::Output).then_some(to_addr);
+            let effective_output = traced_output(
+                &eth_client,
+                &uniswap_client,
+                &call,
+                received_token,
+                uniswap_client.weth_address(),
+                tx_type,
+            )
+            .await;
+            amount_out_min = effective_output
+                .map(|received| minimum_output(received, slippage_bps))
+                .unwrap_or(amount_out_min);
+            call.amount_out_min = amount_out_min;
+        }
+        let mut tx = uniswap_client.swap_transaction(&call, owner, tx_type);
+
+        // 按报价路径、滑点推导的最小输出、钱包和 deadline 独立构建批准的参数，用于核对最终交易
+        let approved = SwapCall {
+            amount_in,
+            amount_out_min,
+            path: quote.path.clone(),
+            to: owner,
+            deadline,
+            native,
+            fee_on_transfer,
+        };
+        let verify_final = |tx: &TypedTransaction| {
+            approved
+                .verify_transaction(tx, router)
+                .map_err(|e| McpError::internal_error(format!("最终交易与报价不一致,未广播: {}", e), None))
+        };
+
+        // 估算失败说明交易会回滚，不广播
+        let gas_estimate = eth_client
+            .estimate_gas(&tx)
+            .await
+            .map_err(|e| McpError::internal_error(format!("模拟交换失败,未广播: {}", e), None))?;
+        let gas_limit = gas_limit_with_buffer(gas_estimate, max_gas_limit)?;
+
+        let mut result = ExecuteSwapResult {
+            input_amount: args.amount.clone(),
+            estimated_output: format_units(quote.amount_out, to_info.decimals),
+            minimum_output: format_units(amount_out_min, to_info.decimals),
+            price_impact: format!("{:.2}%", quote.price_impact),
+            from_token: from_info,
+            to_token: to_info,
+            wallet: checksum_address(owner),
+            slippage_bps,
+            tx_type: tx_type.as_str().to_string(),
+            gas_limit: gas_limit.to_string(),
+            fee_on_transfer: fee_on_transfer.then_some(true),
+            tx_hash: None,
+            nonce: None,
+            status: String::new(),
+            confirmations: None,
+            block_number: None,
+            gas_used: None,
+            compliance: None,
+            safe: None,
+        };
+
+        // 🔐 Safe 模式:不广播,构建 Safe 交易并提议
+        if safe.is_some() {
+            verify_final(&tx)?;
+            let proposal = safe_client
+                .prepare(
+                    router,
+                    tx.value().copied().unwrap_or_default(),
+                    tx.data().cloned().unwrap_or_default(),
+                    signer,
+                )
+                .await
+                .map_err(|e| McpError::internal_error(format!("构建 Safe 交易失败: {}", e), None))?;
+            result.status = if proposal.proposed { "proposed" } else { "unsigned" }.to_string();
+            result.safe = Some(proposal);
+            return Ok(result);
+        }
+        let wallet = signer.ok_or_else(|| McpError::invalid_params("未配置签名器", None))?;
+
+        let fees = eth_client
+            .estimate_tx_fees(&gas_strategy, tx_type)
+            .await
+            .map_err(|e| McpError::internal_error(format!("估算 Gas 费用失败: {}", e), None))?;
+        fees.apply(&mut tx);
+        tx.set_gas(gas_limit).set_chain_id(chain_id);
+
+        // 🔒 广播前解码最终交易，核对 Router、value、path、amountOutMin、recipient 和 deadline
+        verify_final(&tx)?;
+
+        let submission = tx_manager
+            .submit("execute_swap", wallet, tx, RECEIPT_TIMEOUT)
+            .await
+            .map_err(|e| McpError::internal_error(format!("广播交易失败: {}", e), None))?;
+        let receipt = submission.receipt.as_ref();
+
+        result.tx_hash = Some(format!("{:?}", submission.tx_hash));
+        result.nonce = Some(submission.nonce);
+        result.status = receipt_status(receipt).to_string();
+        result.confirmations = Some(submission.confirmations);
+        result.block_number = receipt.and_then(|r| r.block_number).map(|n| n.as_u64());
+        result.gas_used = receipt.and_then(|r| r.gas_used).map(|g| g.to_string());
+
+        // 📒 成交写入成本台账(按回执中实际收到的数量,兑换为原生代币时按 Router 收到的 WETH 计算)
+        if let Some(receipt) = receipt {
+            let received = if native == NativeLeg::Output {
+                received_amount(receipt, uniswap_client.weth_address(), router)
+            } else {
+                received_amount(receipt, to_addr, owner)
+            };
+            record_ledger_fill(
+                store,
+                &eth_client,
+                &uniswap_client,
+                owner,
+                receipt,
+                (&result.from_token, amount_in),
+                Some((&result.to_token, received)),
+            )
+            .await;
+        }
+        Ok::<_, McpError>(result)
+    }
+    .await?;
+    result.compliance = screening;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    // 💾 持久化执行记录:只有成功确认的交易记为 execution,Safe 提议、回滚和未确认的记为 submission
+    let kind = if result.status == "success" {
+        RecordKind::Execution
+    } else {
+        RecordKind::Submission
+    };
+    store.record(NewRecord {
+        kind,
+        tool: "execute_swap".to_string(),
+        block_number: result.block_number,
+        from_token: result.from_token.address.clone(),
+        to_token: result.to_token.address.clone(),
+        amount_in: result.input_amount.clone(),
+        amount_out: Some(result.estimated_output.clone()),
+        details: serde_json::to_value(&result).unwrap_or_default(),
+    });
+
+    info!(tx_hash = ?result.tx_hash, status = %result.status, "成功执行交换");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 在 Gas 估算上预留余量，并以 MAX_GAS_LIMIT 为上限
+/// 估算值本身超过上限时拒绝执行
+pub(crate) fn gas_limit_with_buffer(gas_estimate: U256, max_gas_limit: u64) -> Result<U256, McpError> {
+    let max_gas_limit = U256::from(max_gas_limit);
+    if gas_estimate > max_gas_limit {
+        return Err(McpError::invalid_request(
+            format!("预估 Gas {} 超过上限 MAX_GAS_LIMIT={}", gas_estimate, max_gas_limit),
+            Some(serde_json::json!({
+                "refused": true,
+                "reason": "gas_limit_exceeded",
+                "gas_estimate": gas_estimate.to_string(),
+                "max_gas_limit": max_gas_limit.to_string(),
+            })),
+        ));
+    }
+
+    let buffered = gas_estimate * U256::from(100 + GAS_LIMIT_BUFFER_PERCENT) / U256::from(100);
+    Ok(buffered.min(max_gas_limit))
+}
+
+/// 回执状态:status 为 1 表示成功,未取得回执时为 pending
+pub(crate) fn receipt_status(receipt: Option<&TransactionReceipt>) -> &'static str {
+    match receipt {
+        None => "pending",
+        Some(receipt) if receipt.status == Some(U64::from(1)) => "success",
+        Some(_) => "reverted",
+    }
+}
+
+/// 按回执中的 Transfer 事件汇总钱包实际收到的代币数量
+pub(crate) fn received_amount(receipt: &TransactionReceipt, token: Address, wallet: Address) -> U256 {
+    let transfer_topic: H256 = TRANSFER_EVENT_TOPIC.parse().expect("硬编码事件签名应该有效");
+    receipt
+        .logs
+        .iter()
+        .filter(|log| log.address == token && log.topics.len() == 3 && log.topics[0] == transfer_topic)
+        .filter(|log| Address::from(log.topics[2]) == wallet && log.data.len() == 32)
+        .fold(U256::zero(), |total, log| total + U256::from_big_endian(&log.data))
+}
+
+/// 交易成功确认后把成交写入成本台账
+/// `sold` 为卖出(转出)的代币和数量,`bought` 为买入的代币和数量(转账和授权为 None)
+/// 未启用持久化、交易未成功或无法计价时跳过;写入失败只记录警告,不影响工具返回
+pub(crate) async fn record_ledger_fill(
+    store: &Store,
+    eth_client: &EthClient,
+    uniswap_client: &UniswapV2Client,
+    wallet: Address,
+    receipt: &TransactionReceipt,
+    sold: (&TokenInfo, U256),
+    bought: Option<(&TokenInfo, U256)>,
+) {
+    if !store.is_enabled() || receipt_status(Some(receipt)) != "success" {
+        return;
+    }
+    let Some(block_number) = receipt.block_number.map(|n| n.as_u64()) else {
+        return;
+    };
+
+    let timestamp = match eth_client.get_block_timestamp(BlockNumber::Number(block_number.into())).await {
+        Ok((_, timestamp)) => timestamp,
+        Err(e) => {
+            warn!(error = %e, block_number, "查询成交区块时间失败,未写入成本台账");
+            return;
+        }
+    };
+
+    // 原生代币按包装代币计价
+    let price_of = |token: &TokenInfo| {
+        let address = if token.is_eth() {
+            Ok(uniswap_client.weth_address())
+        } else {
+            token.address.parse::<Address>()
+        };
+        let decimals = token.decimals;
+        async move {
+            let address = address.ok()?;
+            fetch_token_price_usd_at(uniswap_client, address, decimals, None).await.ok()
+        }
+    };
+    let to_decimal = |amount: U256, decimals: u8| Decimal::from_str(&format_units(amount, decimals)).ok();
+
+    let gas_wei = receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default();
+    let eth_price = fetch_token_price_usd_at(uniswap_client, uniswap_client.weth_address(), 18, None)
+        .await
+        .ok();
+    let gas_fee_usd = match (to_decimal(gas_wei, 18), eth_price) {
+        (Some(gas), Some(price)) => gas * price,
+        _ => Decimal::ZERO,
+    };
+
+    let (from_token, amount_in) = sold;
+    let from_price_usd = price_of(from_token).await;
+    let (to_token, amount_out, to_price_usd) = match bought {
+        Some((token, amount)) => (Some(token), to_decimal(amount, token.decimals), price_of(token).await),
+        None => (None, Some(Decimal::ZERO), None),
+    };
+    let (Some(amount_in), Some(amount_out)) = (to_decimal(amount_in, from_token.decimals), amount_out) else {
+        return;
+    };
+
+    let fill = ExecutionFill {
+        tx_hash: format!("{:?}", receipt.transaction_hash),
+        block_number,
+        timestamp,
+        from_token: from_token.address.clone(),
+        from_symbol: from_token.symbol.clone(),
+        amount_in,
+        to_token: to_token.map(|t| t.address.clone()).unwrap_or_default(),
+        to_symbol: to_token.map(|t| t.symbol.clone()).unwrap_or_default(),
+        amount_out,
+        from_price_usd,
+        to_price_usd,
+        gas_fee_usd,
+    };
+    let Some(entries) = pnl::execution_entries(&fill) else {
+        warn!(tx_hash = %fill.tx_hash, "成交无法计价,未写入成本台账");
+        return;
+    };
+    if let Err(e) = store.record_execution(&format!("{:?}", wallet), &entries) {
+        warn!(error = %e, tx_hash = %fill.tx_hash, "写入成本台账失败");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::ErrorCode;
+
+    #[test]
+    fn test_gas_limit_with_buffer() {
+        assert_eq!(gas_limit_with_buffer(U256::from(150_000), 500_000).unwrap(), U256::from(180_000));
+        // 余量受上限约束
+        assert_eq!(gas_limit_with_buffer(U256::from(450_000), 500_000).unwrap(), U256::from(500_000));
+
+        let err = gas_limit_with_buffer(U256::from(600_000), 500_000).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_REQUEST);
+        assert_eq!(err.data.unwrap()["reason"], "gas_limit_exceeded");
+    }
+
+    #[test]
+    fn test_receipt_status() {
+        let mut receipt = TransactionReceipt {
+            status: Some(U64::from(1)),
+            ..Default::default()
+        };
+        assert_eq!(receipt_status(Some(&receipt)), "success");
+        receipt.status = Some(U64::zero());
+        assert_eq!(receipt_status(Some(&receipt)), "reverted");
+        assert_eq!(receipt_status(None), "pending");
+    }
+
+    #[test]
+    fn test_received_amount() {
+        let token = Address::from_low_u64_be(1);
+        let wallet = Address::from_low_u64_be(2);
+        let pair = Address::from_low_u64_be(3);
+        let transfer = |address: Address, to: Address, value: u64| Log {
+            address,
+            topics: vec![TRANSFER_EVENT_TOPIC.parse().unwrap(), H256::from(pair), H256::from(to)],
+            data: Bytes::from(ethers::abi::encode(&[ethers::abi::Token::Uint(U256::from(value))])),
+            ..Default::default()
+        };
+        let receipt = TransactionReceipt {
+            logs: vec![
+                transfer(token, wallet, 100),
+                transfer(token, wallet, 20),
+                // 其他代币和转给其他地址的不计入
+                transfer(pair, wallet, 1_000),
+                transfer(token, pair, 1_000),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(received_amount(&receipt, token, wallet), U256::from(120));
+    }
+}
@@ -0,0 +1,470 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, parse_units},
+    eth_client::{EthClient, FeeEstimate, TxFees},
+    logging::{info, warn},
+    tools::price::fetch_token_price_usd_at,
+    types::{checksum_address, parse_address, TxType},
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// 原生代币转账的 Gas 用量
+const TRANSFER_GAS: u64 = 21_000;
+/// 典型 Uniswap V2 两跳以内交换的 Gas 用量
+const SWAP_GAS: u64 = 150_000;
+
+/// EstimateGas 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct EstimateGasArgs {
+    /// 目标合约或接收地址(必需)
+    pub to: String,
+    /// 调用数据(可选,0x 开头的十六进制)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// 发送的 ETH 数量(可选,如 "0.1",默认 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// 发送方地址(可选,默认使用配置的模拟地址)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// 交易类型(可选,auto/legacy/eip1559,默认使用 TX_TYPE 配置)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<String>,
+}
+
+/// EstimateGas 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct GasEstimateResult {
+    pub from: String,
+    pub to: String,
+    pub gas_limit: String,
+    /// 使用的 Gas 价格策略(fast/standard/slow)
+    pub gas_strategy: String,
+    /// 交易类型(legacy/eip1559)
+    pub tx_type: String,
+    /// 传统交易的 gasPrice
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price_gwei: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee_gwei: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_gwei: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_gwei: Option<String>,
+    /// 按预计单位费用(EIP-1559 为 基础费用 + 小费)计算的费用
+    pub estimated_cost_eth: String,
+    /// 按最高单位费用计算的费用
+    pub max_cost_eth: String,
+    /// Gas 是否超过配置的 MAX_GAS_LIMIT
+    pub exceeds_max_gas_limit: bool,
+}
+
+/// 估算任意交易的 Gas 和费用
+pub async fn estimate_gas(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    Parameters(args): Parameters<EstimateGasArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 estimate_gas 请求");
+
+    let to = parse_address(&args.to).map_err(|e| McpError::invalid_params(e, None))?;
+
+    let from: Address = match args.from {
+        Some(ref addr) => parse_address(addr).map_err(|e| McpError::invalid_params(e, None))?,
+        None => config.get_simulation_address(),
+    };
+
+    let data = match args.data {
+        Some(ref hex) => hex
+            .parse::<Bytes>()
+            .map_err(|_| McpError::invalid_params(format!("无效的调用数据: {}", hex), None))?,
+        None => Bytes::new(),
+    };
+
+    let value = match args.value {
+        Some(ref value) => parse_units(value, 18)
+            .map_err(|e| McpError::invalid_params(format!("解析 ETH 数量失败: {}", e), None))?,
+        None => U256::zero(),
+    };
+
+    let strategy = config.trading.gas_price_strategy.clone();
+    let tx_type_preference = config
+        .tx_type_preference(args.tx_type.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    info!(
+        from = %format!("{:?}", from),
+        to = %args.to,
+        data_len = data.len(),
+        value = %value,
+        strategy = %strategy,
+        tx_type = ?tx_type_preference,
+        "估算交易 Gas"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let gwei = |n: u64| U256::from(n) * U256::exp10(9);
+        let fees = match tx_type_preference {
+            Some(TxType::Legacy) => TxFees::Legacy { gas_price: gwei(22) },
+            _ => TxFees::Eip1559(FeeEstimate {
+                base_fee_per_gas: gwei(20),
+                max_priority_fee_per_gas: gwei(2),
+                max_fee_per_gas: gwei(42),
+            }),
+        };
+        let result = build_gas_estimate(config, from, to, U256::from(21_000u64), &fees);
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let eth_client = eth_client.clone();
+
+    let (gas, fees) = async {
+        let tx_type = eth_client
+            .resolve_tx_type(tx_type_preference)
+            .await
+            .map_err(|e| McpError::internal_error(format!("探测交易类型失败: {}", e), None))?;
+
+        let mut tx = tx_type.new_request();
+        tx.set_from(from).set_to(to).set_data(data).set_value(value);
+
+        let (gas, fees) = tokio::join!(
+            eth_client.estimate_gas(&tx),
+            eth_client.estimate_tx_fees(&strategy, tx_type)
+        );
+        let gas = gas.map_err(|e| McpError::internal_error(format!("估算 Gas 失败: {}", e), None))?;
+        let fees = fees.map_err(|e| McpError::internal_error(format!("估算费用失败: {}", e), None))?;
+        Ok::<_, McpError>((gas, fees))
+    }
+    .await?;
+
+    let result = build_gas_estimate(config, from, to, gas, &fees);
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!("成功返回 Gas 估算");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// GetGasPrice 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetGasPriceArgs {
+    /// 是否计算 USD 费用(可选,默认 true,需要一次 Uniswap 报价)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_usd: Option<bool>,
+}
+
+/// 单档建议小费
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PriorityFeeTier {
+    /// slow / standard / fast
+    pub strategy: String,
+    pub max_priority_fee_gwei: String,
+    pub max_fee_gwei: String,
+}
+
+/// 常见操作的预计费用
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct OperationCost {
+    /// transfer / swap
+    pub operation: String,
+    pub gas_limit: u64,
+    pub cost_eth: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<String>,
+}
+
+/// GetGasPrice 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct GasPriceResult {
+    pub chain_id: u64,
+    /// 传统交易的 gasPrice(eth_gasPrice)
+    pub gas_price_gwei: String,
+    /// 下一个区块的基础费用(链不支持 EIP-1559 时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee_gwei: Option<String>,
+    /// 按最近区块小费百分位给出的 slow/standard/fast 建议
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priority_fees: Vec<PriorityFeeTier>,
+    /// 费用估算使用的策略(GAS_PRICE_STRATEGY)
+    pub gas_strategy: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub native_price_usd: Option<String>,
+    pub costs: Vec<OperationCost>,
+}
+
+/// 查询当前 Gas 价格、基础费用、建议小费及常见操作的预计费用
+pub async fn get_gas_price(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    Parameters(args): Parameters<GetGasPriceArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_gas_price 请求");
+
+    let include_usd = args.include_usd.unwrap_or(true);
+    let strategy = config.trading.gas_price_strategy.clone();
+
+    // 测试模式
+    if config.server.test_mode {
+        let gwei = |n: u64| U256::from(n) * U256::exp10(9);
+        let tiers = [("slow", 1), ("standard", 2), ("fast", 3)]
+            .map(|(strategy, tip)| {
+                (strategy, FeeEstimate {
+                    base_fee_per_gas: gwei(20),
+                    max_priority_fee_per_gas: gwei(tip),
+                    max_fee_per_gas: gwei(40 + tip),
+                })
+            })
+            .to_vec();
+        let native_price = include_usd.then(|| Decimal::from(3000));
+        let result = build_gas_price(config, gwei(22), &tiers, native_price);
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let supports_eip1559 = eth_client
+        .supports_eip1559()
+        .await
+        .map_err(|e| McpError::internal_error(format!("探测 EIP-1559 支持失败: {}", e), None))?;
+
+    let (gas_price, tiers, native_price) = tokio::join!(
+        eth_client.get_gas_price(),
+        async {
+            if supports_eip1559 {
+                eth_client.estimate_fee_tiers().await.map(Some)
+            } else {
+                Ok(None)
+            }
+        },
+        async {
+            if !include_usd || !uniswap_client.is_available() {
+                return None;
+            }
+            fetch_token_price_usd_at(uniswap_client, uniswap_client.weth_address(), 18, None)
+                .await
+                .inspect_err(|e| warn!(error = %e, "查询原生代币价格失败"))
+                .ok()
+        }
+    );
+    let gas_price =
+        gas_price.map_err(|e| McpError::internal_error(format!("查询 Gas 价格失败: {}", e), None))?;
+    let tiers = tiers
+        .map_err(|e| McpError::internal_error(format!("估算费用失败: {}", e), None))?
+        .unwrap_or_default();
+
+    let result = build_gas_price(config, gas_price, &tiers, native_price);
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(strategy = %strategy, "成功返回 Gas 价格");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 汇总 Gas 价格结果，预计费用按 GAS_PRICE_STRATEGY 对应档位计算
+/// `tiers` 为空(链不支持 EIP-1559)时按 gasPrice 计算
+fn build_gas_price(
+    config: &Config,
+    gas_price: U256,
+    tiers: &[(&str, FeeEstimate)],
+    native_price_usd: Option<Decimal>,
+) -> GasPriceResult {
+    let strategy = config.trading.gas_price_strategy.as_str();
+    let fees = tiers
+        .iter()
+        .find(|(name, _)| *name == strategy)
+        .map(|(_, fees)| TxFees::Eip1559(*fees))
+        .unwrap_or(TxFees::Legacy { gas_price });
+
+    let costs = [("transfer", TRANSFER_GAS), ("swap", SWAP_GAS)]
+        .into_iter()
+        .map(|(operation, gas_limit)| {
+            let cost = format_units(U256::from(gas_limit) * fees.expected_fee_per_gas(), 18);
+            let cost_usd = native_price_usd.and_then(|price| {
+                let usd = Decimal::from_str(&cost).ok()?.checked_mul(price)?;
+                Some(usd.round_dp(4).normalize().to_string())
+            });
+            OperationCost {
+                operation: operation.to_string(),
+                gas_limit,
+                cost_eth: cost,
+                cost_usd,
+            }
+        })
+        .collect();
+
+    GasPriceResult {
+        chain_id: config.ethereum.chain_id,
+        gas_price_gwei: format_units(gas_price, 9),
+        base_fee_gwei: tiers.first().map(|(_, fees)| format_units(fees.base_fee_per_gas, 9)),
+        priority_fees: tiers
+            .iter()
+            .map(|(strategy, fees)| PriorityFeeTier {
+                strategy: strategy.to_string(),
+                max_priority_fee_gwei: format_units(fees.max_priority_fee_per_gas, 9),
+                max_fee_gwei: format_units(fees.max_fee_per_gas, 9),
+            })
+            .collect(),
+        gas_strategy: strategy.to_string(),
+        native_price_usd: native_price_usd.map(|price| price.normalize().to_string()),
+        costs,
+    }
+}
+
+fn build_gas_estimate(
+    config: &Config,
+    from: Address,
+    to: Address,
+    gas: U256,
+    fees: &TxFees,
+) -> GasEstimateResult {
+    let estimated_cost = gas * fees.expected_fee_per_gas();
+    let max_cost = gas * fees.max_fee_per_gas();
+    let gwei = |amount: U256| Some(format_units(amount, 9));
+
+    let (gas_price_gwei, base_fee_gwei, max_priority_fee_gwei, max_fee_gwei) = match fees {
+        TxFees::Legacy { gas_price } => (gwei(*gas_price), None, None, None),
+        TxFees::Eip1559(fees) => (
+            None,
+            gwei(fees.base_fee_per_gas),
+            gwei(fees.max_priority_fee_per_gas),
+            gwei(fees.max_fee_per_gas),
+        ),
+    };
+
+    GasEstimateResult {
+        from: checksum_address(from),
+        to: checksum_address(to),
+        gas_limit: gas.to_string(),
+        gas_strategy: config.trading.gas_price_strategy.clone(),
+        tx_type: fees.tx_type().as_str().to_string(),
+        gas_price_gwei,
+        base_fee_gwei,
+        max_priority_fee_gwei,
+        max_fee_gwei,
+        estimated_cost_eth: format_units(estimated_cost, 18),
+        max_cost_eth: format_units(max_cost, 18),
+        exceeds_max_gas_limit: gas > U256::from(config.trading.max_gas_limit),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_gas_estimate() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+        config.trading.max_gas_limit = 100_000;
+
+        let gwei = |n: u64| U256::from(n) * U256::exp10(9);
+        let fees = TxFees::Eip1559(FeeEstimate {
+            base_fee_per_gas: gwei(20),
+            max_priority_fee_per_gas: gwei(2),
+            max_fee_per_gas: gwei(42),
+        });
+
+        let result = build_gas_estimate(
+            &config,
+            Address::zero(),
+            Address::zero(),
+            U256::from(21_000u64),
+            &fees,
+        );
+        assert_eq!(result.tx_type, "eip1559");
+        assert_eq!(result.base_fee_gwei.as_deref(), Some("20"));
+        assert!(result.gas_price_gwei.is_none());
+        assert_eq!(result.estimated_cost_eth, "0.000462");
+        assert_eq!(result.max_cost_eth, "0.000882");
+        assert!(!result.exceeds_max_gas_limit);
+
+        let result = build_gas_estimate(
+            &config,
+            Address::zero(),
+            Address::zero(),
+            U256::from(150_000u64),
+            &fees,
+        );
+        assert!(result.exceeds_max_gas_limit);
+
+        let legacy = TxFees::Legacy { gas_price: gwei(30) };
+        let result = build_gas_estimate(
+            &config,
+            Address::zero(),
+            Address::zero(),
+            U256::from(21_000u64),
+            &legacy,
+        );
+        assert_eq!(result.tx_type, "legacy");
+        assert_eq!(result.gas_price_gwei.as_deref(), Some("30"));
+        assert_eq!(result.estimated_cost_eth, "0.00063");
+        assert!(result.max_fee_gwei.is_none());
+    }
+
+    #[test]
+    fn test_build_gas_price() {
+        let mut config = Config::from_env().expect("应该能创建配置");
+        config.trading.gas_price_strategy = "fast".to_string();
+
+        let gwei = |n: u64| U256::from(n) * U256::exp10(9);
+        let tiers: Vec<_> = [("slow", 1), ("standard", 2), ("fast", 4)]
+            .map(|(strategy, tip)| {
+                (strategy, FeeEstimate {
+                    base_fee_per_gas: gwei(10),
+                    max_priority_fee_per_gas: gwei(tip),
+                    max_fee_per_gas: gwei(20 + tip),
+                })
+            })
+            .to_vec();
+
+        // fast 档：(10 + 4) gwei × 21000 = 0.000294 ETH
+        let result = build_gas_price(&config, gwei(12), &tiers, Some(Decimal::from(2000)));
+        assert_eq!(result.base_fee_gwei.as_deref(), Some("10"));
+        assert_eq!(result.priority_fees.len(), 3);
+        assert_eq!(result.priority_fees[2].max_priority_fee_gwei, "4");
+        assert_eq!(result.costs[0].cost_eth, "0.000294");
+        assert_eq!(result.costs[0].cost_usd.as_deref(), Some("0.588"));
+        assert_eq!(result.costs[1].gas_limit, SWAP_GAS);
+
+        // 不支持 EIP-1559 时按 gasPrice 计算
+        let result = build_gas_price(&config, gwei(12), &[], None);
+        assert!(result.base_fee_gwei.is_none());
+        assert!(result.priority_fees.is_empty());
+        assert_eq!(result.costs[0].cost_eth, "0.000252");
+        assert!(result.costs[0].cost_usd.is_none());
+    }
+}
@@ -0,0 +1,360 @@
+use crate::{
+    export::{self, CsvExport, ExportFormat},
+    logging::info,
+    pagination::{PageInfo, PageRequest},
+    store::{RecordKind, RecordQuery, Store, StoredAuditEntry, StoredRecord},
+    token_registry::TokenRegistry,
+};
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, tool, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 默认返回的记录数量
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+/// 单次查询允许的最大记录数量
+const MAX_HISTORY_LIMIT: usize = 500;
+
+/// GetRecordedHistory 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetRecordedHistoryArgs {
+    /// 记录类型(可选,quote/simulation/execution/submission,默认全部)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// 代币地址或符号(可选,匹配源代币或目标代币)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// 起始时间(Unix 秒,可选)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<i64>,
+    /// 返回数量(可选,默认 50,最多 500)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// 分页游标(可选,使用上一页返回的 next_cursor)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// 导出格式(可选,json/csv,默认 json)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export: Option<String>,
+}
+
+/// GetRecordedHistory 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RecordedHistoryResult {
+    pub count: usize,
+    pub records: Vec<StoredRecord>,
+    #[serde(flatten)]
+    pub page: PageInfo,
+}
+
+/// 查询持久化的报价、模拟和执行记录
+#[tool(description = "查询本地持久化的报价、模拟和执行记录(需配置 DATABASE_PATH)")]
+pub fn get_recorded_history(
+    store: &Arc<Store>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<GetRecordedHistoryArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_recorded_history 请求");
+
+    let export_format = ExportFormat::parse(args.export.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let kind = args
+        .kind
+        .as_deref()
+        .map(RecordKind::parse)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let page = PageRequest::parse(
+        args.limit,
+        args.cursor.as_deref(),
+        DEFAULT_HISTORY_LIMIT,
+        MAX_HISTORY_LIMIT,
+    )
+    .map_err(|e| McpError::invalid_params(e, None))?;
+
+    info!(
+        kind = ?args.kind,
+        token = ?args.token,
+        since = ?args.since,
+        limit = page.limit,
+        offset = page.offset,
+        "查询持久化记录"
+    );
+
+    if !store.is_enabled() {
+        return Err(McpError::internal_error(
+            "持久化未启用,请配置 DATABASE_PATH",
+            None,
+        ));
+    }
+
+    // 记录中保存的是代币地址，符号需要先解析；无法解析时按原样匹配(如 USD)
+    let token = args.token.map(|token| {
+        token_registry
+            .resolve(&token)
+            .map(|info| info.address)
+            .unwrap_or(token)
+    });
+
+    // 多查询一条以判断是否还有下一页
+    let records = store
+        .query(&RecordQuery {
+            kind,
+            token,
+            since: args.since,
+            limit: page.limit + 1,
+            offset: page.offset,
+        })
+        .map_err(|e| McpError::internal_error(format!("查询持久化记录失败: {}", e), None))?;
+
+    let result = page.fit(&records, false, |records, page| RecordedHistoryResult {
+        count: records.len(),
+        records,
+        page,
+    });
+
+    info!("成功返回持久化记录");
+
+    export::render(&result, export_format)
+}
+
+/// GetAuditLog 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetAuditLogArgs {
+    /// 产生日志的工具名(可选,如 execute_swap,默认全部)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// 返回数量(可选,默认 50,最多 500)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// GetAuditLog 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AuditLogResult {
+    pub count: usize,
+    pub entries: Vec<StoredAuditEntry>,
+}
+
+/// 查询持久化的审计日志(制裁名单筛查结论等)
+#[tool(description = "查询本地持久化的审计日志(制裁名单筛查结论等,最新在前,需配置 DATABASE_PATH)")]
+pub fn get_audit_log(
+    store: &Arc<Store>,
+    Parameters(args): Parameters<GetAuditLogArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_audit_log 请求");
+
+    let limit = args.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    if limit == 0 || limit > MAX_HISTORY_LIMIT {
+        return Err(McpError::invalid_params(
+            format!("limit 必须在 1 到 {} 之间", MAX_HISTORY_LIMIT),
+            None,
+        ));
+    }
+
+    if !store.is_enabled() {
+        return Err(McpError::internal_error(
+            "持久化未启用,请配置 DATABASE_PATH",
+            None,
+        ));
+    }
+
+    let entries = store
+        .audit_entries(args.tool.as_deref(), limit)
+        .map_err(|e| McpError::internal_error(format!("查询审计日志失败: {}", e), None))?;
+
+    let result = AuditLogResult {
+        count: entries.len(),
+        entries,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(count = result.count, "成功返回审计日志");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+impl CsvExport for RecordedHistoryResult {
+    fn csv_headers(&self) -> Vec<&'static str> {
+        vec![
+            "id",
+            "kind",
+            "tool",
+            "created_at",
+            "block_number",
+            "from_token",
+            "to_token",
+            "amount_in",
+            "amount_out",
+        ]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        self.records
+            .iter()
+            .map(|r| {
+                vec![
+                    r.id.to_string(),
+                    r.kind.clone(),
+                    r.tool.clone(),
+                    r.created_at.to_string(),
+                    r.block_number.map(|b| b.to_string()).unwrap_or_default(),
+                    r.from_token.clone(),
+                    r.to_token.clone(),
+                    r.amount_in.clone(),
+                    r.amount_out.clone().unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::{AuditEntry, NewRecord};
+
+    fn create_store() -> Arc<Store> {
+        let store = Store::open(Some(":memory:")).expect("应该能打开内存数据库");
+        store.record(NewRecord {
+            kind: RecordKind::Quote,
+            tool: "get_token_price".to_string(),
+            block_number: Some(19_000_000),
+            from_token: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+            to_token: "USD".to_string(),
+            amount_in: "1".to_string(),
+            amount_out: Some("1.0001".to_string()),
+            details: serde_json::json!({}),
+        });
+        Arc::new(store)
+    }
+
+    #[test]
+    fn test_get_recorded_history_by_symbol() {
+        let store = create_store();
+        let registry = Arc::new(TokenRegistry::new());
+
+        let args = GetRecordedHistoryArgs {
+            kind: Some("quote".to_string()),
+            token: Some("USDC".to_string()),
+            since: None,
+            limit: None,
+            cursor: None,
+            export: Some("csv".to_string()),
+        };
+
+        let result = get_recorded_history(&store, &registry, Parameters(args)).unwrap();
+        let text = result.content[0].as_text().unwrap().text.clone();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("19000000"));
+        assert!(lines[1].ends_with(",USD,1,1.0001"));
+    }
+
+    #[test]
+    fn test_get_recorded_history_pagination() {
+        let store = create_store();
+        store.record(NewRecord {
+            kind: RecordKind::Simulation,
+            tool: "swap_tokens".to_string(),
+            block_number: Some(19_000_001),
+            from_token: "ETH".to_string(),
+            to_token: "USDC".to_string(),
+            amount_in: "1".to_string(),
+            amount_out: Some("3000".to_string()),
+            details: serde_json::json!({}),
+        });
+        let registry = Arc::new(TokenRegistry::new());
+
+        let query = |cursor: Option<String>| {
+            let args = GetRecordedHistoryArgs {
+                kind: None,
+                token: None,
+                since: None,
+                limit: Some(1),
+                cursor,
+                export: None,
+            };
+            let result = get_recorded_history(&store, &registry, Parameters(args)).unwrap();
+            let text = result.content[0].as_text().unwrap().text.clone();
+            serde_json::from_str::<RecordedHistoryResult>(&text).unwrap()
+        };
+
+        let first = query(None);
+        assert_eq!(first.count, 1);
+        let cursor = first.page.next_cursor.clone();
+        assert_eq!(cursor.as_deref(), Some("1"));
+
+        let second = query(cursor);
+        assert_eq!(second.count, 1);
+        assert_ne!(second.records[0].id, first.records[0].id);
+        assert!(second.page.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_get_audit_log() {
+        let store = create_store();
+        for (tool, decision) in [("execute_swap", "clear"), ("transfer_token", "refused")] {
+            store.audit(AuditEntry {
+                tool: tool.to_string(),
+                category: "sanctions_screening".to_string(),
+                decision: decision.to_string(),
+                details: serde_json::json!({}),
+            });
+        }
+
+        let query = |tool: Option<&str>| {
+            let args = GetAuditLogArgs {
+                tool: tool.map(str::to_string),
+                limit: None,
+            };
+            let result = get_audit_log(&store, Parameters(args)).unwrap();
+            let text = result.content[0].as_text().unwrap().text.clone();
+            serde_json::from_str::<AuditLogResult>(&text).unwrap()
+        };
+
+        let all = query(None);
+        assert_eq!(all.count, 2);
+        assert_eq!(all.entries[0].tool, "transfer_token");
+
+        let filtered = query(Some("execute_swap"));
+        assert_eq!(filtered.count, 1);
+        assert_eq!(filtered.entries[0].decision, "clear");
+
+        let args = GetAuditLogArgs { tool: None, limit: Some(0) };
+        assert!(get_audit_log(&store, Parameters(args)).is_err());
+        let args = GetAuditLogArgs { tool: None, limit: None };
+        assert!(get_audit_log(&Arc::new(Store::disabled()), Parameters(args)).is_err());
+    }
+
+    #[test]
+    fn test_get_recorded_history_errors() {
+        let registry = Arc::new(TokenRegistry::new());
+        let disabled = Arc::new(Store::disabled());
+
+        let args = GetRecordedHistoryArgs {
+            kind: None,
+            token: None,
+            since: None,
+            limit: None,
+            cursor: None,
+            export: None,
+        };
+        assert!(get_recorded_history(&disabled, &registry, Parameters(args)).is_err());
+
+        let args = GetRecordedHistoryArgs {
+            kind: None,
+            token: None,
+            since: None,
+            limit: Some(0),
+            cursor: None,
+            export: None,
+        };
+        assert!(get_recorded_history(&create_store(), &registry, Parameters(args)).is_err());
+    }
+}
@@ -0,0 +1,329 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, parse_units, Erc20Client},
+    eth_client::EthClient,
+    logging::{info, warn},
+    token_registry::TokenRegistry,
+    tools::{
+        lp_position::pool_share_percent,
+        swap::minimum_output,
+        user_operation::{resolve_token, token_address},
+    },
+    types::{checksum_address, parse_address, TokenInfo, TxType},
+    uniswap::{
+        liquidity_minted, optimal_liquidity_amounts, AddLiquidityCall, NativeLeg, UniswapError,
+        UniswapV2Client, MINIMUM_LIQUIDITY,
+    },
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// Uniswap V2 LP 代币精度(所有交易对固定为 18)
+const LP_TOKEN_DECIMALS: u8 = 18;
+
+/// SimulateAddLiquidity 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SimulateAddLiquidityArgs {
+    /// 第一个代币地址或符号(必需,原生代币使用 addLiquidityETH)
+    pub token_a: String,
+    /// 第二个代币地址或符号(必需)
+    pub token_b: String,
+    /// 期望存入的 token_a 数量(必需)
+    pub amount_a: String,
+    /// 期望存入的 token_b 数量(可选,默认按当前储备量比例配对;新交易对必需)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_b: Option<String>,
+    /// 滑点(基点,默认 50 = 0.5%,用于计算 amountAMin/amountBMin)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slippage_bps: Option<u32>,
+    /// 钱包地址(用于模拟和 Gas 估算,可选)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet_address: Option<String>,
+    /// 交易类型(可选,auto/legacy/eip1559,默认使用 TX_TYPE 配置)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<String>,
+}
+
+/// SimulateAddLiquidity 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AddLiquiditySimulationResult {
+    pub token_a: TokenInfo,
+    pub token_b: TokenInfo,
+    /// 交易对地址(交易对尚不存在且无法计算 CREATE2 地址时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pair: Option<String>,
+    /// 交易对尚无流动性,本次为首次存入(由存入数量决定初始价格)
+    pub first_deposit: bool,
+    /// 按当前储备量实际存入的 token_a 数量
+    pub amount_a: String,
+    /// 按当前储备量实际存入的 token_b 数量
+    pub amount_b: String,
+    /// 计入滑点后的最小存入数量(amountAMin)
+    pub amount_a_min: String,
+    /// 计入滑点后的最小存入数量(amountBMin)
+    pub amount_b_min: String,
+    /// 预计铸造的 LP 代币数量
+    pub liquidity_minted: String,
+    /// 存入后在池子中的份额(百分比)
+    pub pool_share_after: String,
+    /// 模拟调用的 Router 函数(addLiquidity/addLiquidityETH)
+    pub router_function: String,
+    /// 模拟使用的交易类型(legacy/eip1559)
+    pub tx_type: String,
+    pub simulation_success: bool,
+    /// Router 模拟返回的 LP 数量(模拟成功时)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulated_liquidity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_estimate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    /// 两侧 ERC20 对 Router 的授权情况(原生代币一侧不需要授权)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approvals: Vec<LiquidityApproval>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 单个代币对 Router 的授权情况
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct LiquidityApproval {
+    pub token: String,
+    /// 钱包当前对 Router 的授权额度(已格式化)
+    pub current_allowance: String,
+    pub approval_required: bool,
+}
+
+/// 模拟 Router.addLiquidity:按当前储备量计算配对数量、铸造的 LP 数量和存入后的份额,并以 eth_call 模拟和估算 Gas
+pub async fn simulate_add_liquidity(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<SimulateAddLiquidityArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 simulate_add_liquidity 请求");
+
+    let slippage_bps = args.slippage_bps.unwrap_or(50);
+    if slippage_bps > 10000 {
+        return Err(McpError::invalid_params(
+            format!("滑点参数无效: {} bps (必须 ≤ 10000，即 ≤ 100%)", slippage_bps),
+            None,
+        ));
+    }
+    let tx_type_preference = config
+        .tx_type_preference(args.tx_type.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+    let wallet = match args.wallet_address.as_deref() {
+        Some(wallet) => parse_address(wallet).map_err(|e| McpError::invalid_params(e, None))?,
+        None => config.get_simulation_address(),
+    };
+
+    info!(
+        token_a = %args.token_a,
+        token_b = %args.token_b,
+        amount_a = %args.amount_a,
+        amount_b = ?args.amount_b,
+        slippage = slippage_bps,
+        "模拟添加流动性"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let token = |query: &str, symbol: &str| TokenInfo {
+            symbol: symbol.to_string(),
+            name: format!("Test {}", symbol),
+            address: query.to_string(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+        let result = AddLiquiditySimulationResult {
+            token_a: token(&args.token_a, "TOKENA"),
+            token_b: token(&args.token_b, "TOKENB"),
+            pair: Some(checksum_address(Address::repeat_byte(0x33))),
+            first_deposit: false,
+            amount_a: args.amount_a.clone(),
+            amount_b: args.amount_b.clone().unwrap_or_else(|| "2".to_string()),
+            amount_a_min: args.amount_a.clone(),
+            amount_b_min: "1.99".to_string(),
+            liquidity_minted: "1.414213562373095048".to_string(),
+            pool_share_after: "0.1".to_string(),
+            router_function: "addLiquidity".to_string(),
+            tx_type: tx_type_preference.unwrap_or(TxType::Eip1559).as_str().to_string(),
+            simulation_success: true,
+            simulated_liquidity: Some("1.414213562373095048".to_string()),
+            gas_estimate: Some("180000".to_string()),
+            revert_reason: None,
+            approvals: Vec::new(),
+            notes: Vec::new(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !erc20_client.is_available() || !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let token_a = resolve_token(token_registry, erc20_client, &args.token_a).await?;
+    let token_b = resolve_token(token_registry, erc20_client, &args.token_b).await?;
+    let (addr_a, addr_b) = (token_address(&token_a)?, token_address(&token_b)?);
+    if addr_a == addr_b {
+        return Err(McpError::invalid_params("token_a 和 token_b 不能是同一个代币", None));
+    }
+    let native = uniswap_client.native_leg(&token_a, &token_b);
+
+    let amount_a_desired = parse_units(&args.amount_a, token_a.decimals)
+        .map_err(|e| McpError::invalid_params(format!("解析 amount_a 失败: {}", e), None))?;
+    let amount_b_desired = args
+        .amount_b
+        .as_deref()
+        .map(|amount| parse_units(amount, token_b.decimals))
+        .transpose()
+        .map_err(|e| McpError::invalid_params(format!("解析 amount_b 失败: {}", e), None))?;
+
+    // 交易对不存在时由 Router 在 addLiquidity 中创建
+    let pair = match uniswap_client.get_pair(addr_a, addr_b).await {
+        Ok(pair) => Some(pair),
+        Err(UniswapError::PairNotFound) => None,
+        Err(e) => return Err(McpError::internal_error(format!("查询交易对失败: {}", e), None)),
+    };
+    let ((reserve_a, reserve_b), total_supply) = match pair {
+        Some(pair) => {
+            let (reserves, total_supply) =
+                tokio::join!(uniswap_client.get_reserves(pair), erc20_client.total_supply(pair));
+            let (reserve0, reserve1) =
+                reserves.map_err(|e| McpError::internal_error(format!("查询储备量失败: {}", e), None))?;
+            let total_supply = total_supply
+                .map_err(|e| McpError::internal_error(format!("查询 LP 代币总供应量失败: {}", e), None))?;
+            let reserves = if addr_a < addr_b { (reserve0, reserve1) } else { (reserve1, reserve0) };
+            (reserves, total_supply)
+        }
+        None => ((U256::zero(), U256::zero()), U256::zero()),
+    };
+    let first_deposit = total_supply.is_zero();
+    if first_deposit && amount_b_desired.is_none() {
+        return Err(McpError::invalid_params(
+            "交易对尚无流动性,需要同时提供 amount_b(两侧数量决定初始价格)",
+            None,
+        ));
+    }
+
+    // 未指定 amount_b 时按储备量比例配对
+    let (amount_a, amount_b) =
+        optimal_liquidity_amounts(amount_a_desired, amount_b_desired.unwrap_or(U256::MAX), reserve_a, reserve_b)
+            .map_err(|e| McpError::invalid_params(format!("计算配对数量失败: {}", e), None))?;
+    let liquidity = liquidity_minted(amount_a, amount_b, reserve_a, reserve_b, total_supply)
+        .map_err(|e| McpError::invalid_params(format!("存入数量过小,无法铸造 LP 代币: {}", e), None))?;
+
+    let call = AddLiquidityCall {
+        token_a: addr_a,
+        token_b: addr_b,
+        amount_a_desired,
+        amount_b_desired: amount_b_desired.unwrap_or(amount_b),
+        amount_a_min: minimum_output(amount_a, slippage_bps),
+        amount_b_min: minimum_output(amount_b, slippage_bps),
+        to: wallet,
+        deadline: U256::MAX,
+        native,
+    };
+
+    let tx_type = eth_client
+        .resolve_tx_type(tx_type_preference)
+        .await
+        .map_err(|e| McpError::internal_error(format!("探测交易类型失败: {}", e), None))?;
+    let simulation = uniswap_client
+        .simulate_add_liquidity(&call, tx_type)
+        .await
+        .map_err(|e| McpError::internal_error(format!("模拟添加流动性失败: {}", e), None))?;
+
+    // 两侧 ERC20 需要授权 Router 划转,原生代币随交易 value 发送
+    let router = uniswap_client.router_address();
+    let mut approvals = Vec::new();
+    for (token, address, amount, is_native) in [
+        (&token_a, addr_a, call.amount_a_desired, native == NativeLeg::Input),
+        (&token_b, addr_b, call.amount_b_desired, native == NativeLeg::Output),
+    ] {
+        if is_native {
+            continue;
+        }
+        match erc20_client.allowance(address, wallet, router).await {
+            Ok(allowance) => approvals.push(LiquidityApproval {
+                token: token.symbol.clone(),
+                current_allowance: format_units(allowance, token.decimals),
+                approval_required: allowance < amount,
+            }),
+            Err(e) => warn!(token = %token.symbol, error = %e, "查询授权额度失败"),
+        }
+    }
+
+    let mut notes = Vec::new();
+    if first_deposit {
+        notes.push(format!(
+            "首次存入按两侧数量确定初始价格,其中 {} 个最小单位的 LP 代币永久锁定",
+            MINIMUM_LIQUIDITY
+        ));
+    }
+    if approvals.iter().any(|approval| approval.approval_required) && !simulation.simulation_success {
+        notes.push("授权额度不足时模拟会在 Router 划转代币时回滚,请先使用 approve_token 授权 Router".to_string());
+    }
+    if !first_deposit {
+        notes.push("LP 数量不含协议费(feeOn 时 mint 前会为协议增发少量 LP 代币),实际份额可能略低".to_string());
+    }
+
+    let total_supply_after = if first_deposit {
+        liquidity + U256::from(MINIMUM_LIQUIDITY)
+    } else {
+        total_supply + liquidity
+    };
+    let pair_address = pair.or_else(|| {
+        let computed = uniswap_client.pair_address(addr_a, addr_b);
+        (!computed.is_zero()).then_some(computed)
+    });
+
+    let result = AddLiquiditySimulationResult {
+        pair: pair_address.map(checksum_address),
+        first_deposit,
+        amount_a: format_units(amount_a, token_a.decimals),
+        amount_b: format_units(amount_b, token_b.decimals),
+        amount_a_min: format_units(call.amount_a_min, token_a.decimals),
+        amount_b_min: format_units(call.amount_b_min, token_b.decimals),
+        liquidity_minted: format_units(liquidity, LP_TOKEN_DECIMALS),
+        pool_share_after: pool_share_percent(liquidity, total_supply_after).normalize().to_string(),
+        router_function: call.function_name().to_string(),
+        tx_type: tx_type.as_str().to_string(),
+        simulation_success: simulation.simulation_success,
+        simulated_liquidity: simulation
+            .deposited
+            .map(|(_, _, liquidity)| format_units(liquidity, LP_TOKEN_DECIMALS)),
+        gas_estimate: simulation.gas_estimate.map(|gas| gas.to_string()),
+        revert_reason: simulation.revert_reason,
+        approvals,
+        notes,
+        token_a,
+        token_b,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        liquidity_minted = %result.liquidity_minted,
+        pool_share_after = %result.pool_share_after,
+        simulation_success = result.simulation_success,
+        "成功返回添加流动性模拟结果"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
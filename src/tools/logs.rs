@@ -0,0 +1,437 @@
+use crate::{
+    bindings::{find_known_event, find_known_event_by_name, ierc20::TransferFilter},
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    eth_client::EthClient,
+    logging::{info, warn},
+    token_registry::TokenRegistry,
+    tools::calldata::{token_to_json, DecodedArgument},
+    types::{checksum_address, parse_address, TokenInfo},
+};
+use ethers::abi::{AbiEncode, RawLog};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 单次查询允许的最大区块区间
+const MAX_LOG_RANGE_BLOCKS: u64 = 10_000;
+/// 未指定 from_block 时回溯的区块数
+const DEFAULT_LOG_RANGE_BLOCKS: u64 = 1_000;
+/// 默认返回的日志数量
+const DEFAULT_LOGS_LIMIT: usize = 100;
+/// 单次查询允许返回的最大日志数量
+const MAX_LOGS_LIMIT: usize = 1_000;
+
+/// GetLogs 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetLogsArgs {
+    /// 发出事件的合约地址或代币符号(可选,与 event/topic0 至少指定一个)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// 常见事件名称(可选,Transfer/Approval/Swap/Sync/Mint/Burn,不能与 topic0 同时指定)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    /// 事件签名哈希(可选,32 字节十六进制)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic0: Option<String>,
+    /// indexed 参数过滤(可选,地址或 32 字节十六进制,如 Transfer 的 from)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic1: Option<String>,
+    /// indexed 参数过滤(可选,如 Transfer 的 to)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic2: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic3: Option<String>,
+    /// 起始区块(可选,默认 to_block 前 1000 个区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_block: Option<u64>,
+    /// 结束区块(可选,默认最新区块,区间最多 10000 个区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_block: Option<u64>,
+    /// 返回数量上限(可选,默认 100,最大 1000,优先返回最新的日志)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// GetLogs 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct GetLogsResult {
+    pub from_block: u64,
+    pub to_block: u64,
+    /// 区间内匹配的日志总数
+    pub total_found: usize,
+    pub logs: Vec<DecodedLog>,
+}
+
+/// 解码后的事件日志
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct DecodedLog {
+    pub address: String,
+    pub block_number: Option<u64>,
+    pub tx_hash: Option<String>,
+    pub log_index: Option<u64>,
+    /// 事件签名,如 Transfer(address,address,uint256)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<DecodedArgument>,
+    /// ERC20 Transfer/Approval 的代币符号和按精度格式化的数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_symbol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_value: Option<String>,
+    /// 无法解码时返回原始 topics 和 data
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+/// 查询事件日志并按已知 ABI 解码(ERC20 Transfer/Approval、Uniswap V2 Swap/Sync/Mint/Burn)
+pub async fn get_logs(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<GetLogsArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_logs 请求");
+
+    let topic0 = match (&args.event, &args.topic0) {
+        (Some(_), Some(_)) => {
+            return Err(McpError::invalid_params("event 与 topic0 不能同时指定", None));
+        }
+        (Some(name), None) => Some(
+            find_known_event_by_name(name.trim())
+                .map(|(_, event)| event.signature())
+                .ok_or_else(|| {
+                    McpError::invalid_params(
+                        format!("未知的事件: {},支持 Transfer/Approval/Swap/Sync/Mint/Burn,其他事件请使用 topic0", name),
+                        None,
+                    )
+                })?,
+        ),
+        (None, Some(topic)) => Some(parse_topic(topic).map_err(|e| McpError::invalid_params(e, None))?),
+        (None, None) => None,
+    };
+    let [topic1, topic2, topic3] = [&args.topic1, &args.topic2, &args.topic3].map(|topic| {
+        topic
+            .as_deref()
+            .map(parse_topic)
+            .transpose()
+            .map_err(|e| McpError::invalid_params(e, None))
+    });
+    let (topic1, topic2, topic3) = (topic1?, topic2?, topic3?);
+
+    let address = match args.address {
+        Some(ref address) => Some(resolve_log_address(token_registry, address)?),
+        None => None,
+    };
+    if address.is_none() && topic0.is_none() {
+        return Err(McpError::invalid_params("address 与 event/topic0 至少指定一个", None));
+    }
+
+    let limit = args.limit.unwrap_or(DEFAULT_LOGS_LIMIT);
+    if limit == 0 || limit > MAX_LOGS_LIMIT {
+        return Err(McpError::invalid_params(
+            format!("limit 必须在 1 到 {} 之间", MAX_LOGS_LIMIT),
+            None,
+        ));
+    }
+
+    info!(
+        address = ?address,
+        topic0 = ?topic0,
+        from_block = ?args.from_block,
+        to_block = ?args.to_block,
+        limit,
+        "查询事件日志"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let to_block = args.to_block.unwrap_or(20_000_000);
+        let from_block = resolve_range(args.from_block, to_block).map_err(|e| McpError::invalid_params(e, None))?;
+        let token = Address::repeat_byte(0x11);
+        let log = Log {
+            address: address.unwrap_or(token),
+            topics: vec![
+                TransferFilter::signature(),
+                H256::from(Address::repeat_byte(0x22)),
+                H256::from(Address::repeat_byte(0x33)),
+            ],
+            data: Bytes::from(U256::exp10(18).encode()),
+            block_number: Some(U64::from(to_block)),
+            transaction_hash: Some(H256::zero()),
+            log_index: Some(U256::zero()),
+            ..Default::default()
+        };
+        let tokens = HashMap::from([(log.address, test_token(log.address))]);
+        let result = GetLogsResult {
+            from_block,
+            to_block,
+            total_found: 1,
+            logs: vec![decode_log(&log, &tokens)],
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let to_block = match args.to_block {
+        Some(block) => block,
+        None => eth_client
+            .get_block_number()
+            .await
+            .map_err(|e| McpError::internal_error(format!("获取区块高度失败: {}", e), None))?,
+    };
+    let from_block = resolve_range(args.from_block, to_block).map_err(|e| McpError::invalid_params(e, None))?;
+
+    let mut filter = Filter::new().from_block(from_block).to_block(to_block);
+    if let Some(address) = address {
+        filter = filter.address(address);
+    }
+    if let Some(topic) = topic0 {
+        filter = filter.topic0(topic);
+    }
+    if let Some(topic) = topic1 {
+        filter = filter.topic1(topic);
+    }
+    if let Some(topic) = topic2 {
+        filter = filter.topic2(topic);
+    }
+    if let Some(topic) = topic3 {
+        filter = filter.topic3(topic);
+    }
+
+    let mut logs = eth_client
+        .get_logs(&filter)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询事件日志失败: {}", e), None))?;
+    let total_found = logs.len();
+    logs.sort_by_key(|log| (log.block_number, log.log_index));
+    let logs = logs.split_off(logs.len().saturating_sub(limit));
+
+    // ERC20 Transfer/Approval 按代币精度格式化数量
+    let token_addresses: Vec<Address> = logs
+        .iter()
+        .filter(|log| is_erc20_amount_event(log))
+        .map(|log| log.address)
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+    let tokens = if token_addresses.is_empty() || !erc20_client.is_available() {
+        HashMap::new()
+    } else {
+        match erc20_client.tokens_info(&token_addresses).await {
+            Ok(infos) => token_addresses.into_iter().zip(infos).collect(),
+            Err(e) => {
+                warn!(error = %e, "查询代币信息失败,数量不做格式化");
+                HashMap::new()
+            }
+        }
+    };
+
+    let result = GetLogsResult {
+        from_block,
+        to_block,
+        total_found,
+        logs: logs.iter().map(|log| decode_log(log, &tokens)).collect(),
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(total_found, returned = result.logs.len(), "成功返回事件日志");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 解析日志地址:地址直接使用,否则按代币符号查找
+fn resolve_log_address(token_registry: &TokenRegistry, value: &str) -> Result<Address, McpError> {
+    let value = value.trim();
+    if value.starts_with("0x") {
+        return parse_address(value).map_err(|e| McpError::invalid_params(e, None));
+    }
+    let token = token_registry
+        .resolve(value)
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+    parse_address(&token.address).map_err(|e| McpError::internal_error(e, None))
+}
+
+/// 解析 topic:20 字节地址左侧补零,或 32 字节十六进制
+fn parse_topic(value: &str) -> Result<H256, String> {
+    let value = value.trim();
+    match value.len() {
+        42 => parse_address(value).map(H256::from),
+        66 => value.parse().map_err(|_| format!("无效的 topic: {}", value)),
+        _ => Err(format!("topic 必须是地址或 32 字节十六进制: {}", value)),
+    }
+}
+
+/// 计算起始区块并限制区块区间
+fn resolve_range(from_block: Option<u64>, to_block: u64) -> Result<u64, String> {
+    let from_block = from_block.unwrap_or(to_block.saturating_sub(DEFAULT_LOG_RANGE_BLOCKS - 1));
+    if from_block > to_block {
+        return Err(format!("from_block ({}) 不能大于 to_block ({})", from_block, to_block));
+    }
+    if to_block - from_block + 1 > MAX_LOG_RANGE_BLOCKS {
+        return Err(format!(
+            "区块区间过大: {} 个区块(最多 {} 个),请缩小区间",
+            to_block - from_block + 1,
+            MAX_LOG_RANGE_BLOCKS
+        ));
+    }
+    Ok(from_block)
+}
+
+/// ERC20 Transfer/Approval(3 个 topic;ERC721 的 tokenId 也是 indexed,有 4 个 topic)
+fn is_erc20_amount_event(log: &Log) -> bool {
+    log.topics.len() == 3
+        && log.data.len() == 32
+        && find_known_event(log.topics[0]).is_some_and(|(contract, _)| contract == "ERC20")
+}
+
+/// 按已知 ABI 解码日志,无法解码时保留原始 topics 和 data
+fn decode_log(log: &Log, tokens: &HashMap<Address, TokenInfo>) -> DecodedLog {
+    let mut decoded = DecodedLog {
+        address: checksum_address(log.address),
+        block_number: log.block_number.map(|n| n.as_u64()),
+        tx_hash: log.transaction_hash.map(|hash| format!("{:?}", hash)),
+        log_index: log.log_index.map(|index| index.as_u64()),
+        event: None,
+        args: Vec::new(),
+        token_symbol: None,
+        formatted_value: None,
+        topics: Vec::new(),
+        data: None,
+    };
+
+    let parsed = log.topics.first().and_then(|topic0| find_known_event(*topic0)).and_then(|(_, event)| {
+        let raw = RawLog {
+            topics: log.topics.clone(),
+            data: log.data.to_vec(),
+        };
+        event.parse_log(raw).ok().map(|parsed| (event, parsed))
+    });
+
+    let Some((event, parsed)) = parsed else {
+        decoded.topics = log.topics.iter().map(|topic| format!("{:?}", topic)).collect();
+        decoded.data = Some(log.data.to_string());
+        return decoded;
+    };
+
+    let kinds: Vec<String> = event.inputs.iter().map(|param| param.kind.to_string()).collect();
+    decoded.event = Some(format!("{}({})", event.name, kinds.join(",")));
+    decoded.args = parsed
+        .params
+        .iter()
+        .zip(kinds)
+        .map(|(param, kind)| DecodedArgument {
+            name: param.name.clone(),
+            kind,
+            value: token_to_json(&param.value),
+        })
+        .collect();
+
+    if is_erc20_amount_event(log)
+        && let Some(token) = tokens.get(&log.address)
+    {
+        decoded.token_symbol = Some(token.symbol.clone());
+        decoded.formatted_value = Some(format_units(U256::from_big_endian(&log.data), token.decimals));
+    }
+
+    decoded
+}
+
+fn test_token(address: Address) -> TokenInfo {
+    TokenInfo {
+        symbol: "TEST".to_string(),
+        name: "Test Token".to_string(),
+        address: checksum_address(address),
+        decimals: 18,
+        listed_on: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::i_uniswap_v2_pair::SyncFilter;
+
+    #[test]
+    fn test_parse_topic() {
+        let address = Address::repeat_byte(0xab);
+        assert_eq!(parse_topic(&format!("{:?}", address)).unwrap(), H256::from(address));
+        assert_eq!(parse_topic(&format!("0x{}", "cd".repeat(32))).unwrap(), H256::repeat_byte(0xcd));
+        assert!(parse_topic("0x1234").is_err());
+    }
+
+    #[test]
+    fn test_resolve_range() {
+        assert_eq!(resolve_range(None, 5_000).unwrap(), 4_001);
+        assert_eq!(resolve_range(None, 10).unwrap(), 0);
+        assert_eq!(resolve_range(Some(1), MAX_LOG_RANGE_BLOCKS).unwrap(), 1);
+        assert!(resolve_range(Some(0), MAX_LOG_RANGE_BLOCKS).is_err());
+        assert!(resolve_range(Some(11), 10).is_err());
+    }
+
+    #[test]
+    fn test_decode_log() {
+        let token = Address::repeat_byte(0x11);
+        let transfer = Log {
+            address: token,
+            topics: vec![
+                TransferFilter::signature(),
+                H256::from(Address::repeat_byte(0x22)),
+                H256::from(Address::repeat_byte(0x33)),
+            ],
+            data: Bytes::from(U256::from(1_500_000u64).encode()),
+            ..Default::default()
+        };
+        let mut usdc = test_token(token);
+        usdc.decimals = 6;
+        let tokens = HashMap::from([(token, usdc)]);
+
+        let decoded = decode_log(&transfer, &tokens);
+        assert_eq!(decoded.event.as_deref(), Some("Transfer(address,address,uint256)"));
+        assert_eq!(decoded.args[0].name, "from");
+        assert_eq!(decoded.args[1].value, checksum_address(Address::repeat_byte(0x33)));
+        assert_eq!(decoded.args[2].value, "1500000");
+        assert_eq!(decoded.formatted_value.as_deref(), Some("1.5"));
+        assert!(decoded.topics.is_empty());
+
+        let mut data = U256::from(10u64).encode();
+        data.extend(U256::from(20u64).encode());
+        let sync = Log {
+            topics: vec![SyncFilter::signature()],
+            data: Bytes::from(data),
+            ..Default::default()
+        };
+        let decoded = decode_log(&sync, &HashMap::new());
+        assert_eq!(decoded.event.as_deref(), Some("Sync(uint112,uint112)"));
+        assert_eq!(decoded.args[1].value, "20");
+        assert_eq!(decoded.formatted_value, None);
+
+        // ERC721 Transfer 有 4 个 topic,按原始数据返回
+        let mut nft = transfer.clone();
+        nft.topics.push(H256::from_low_u64_be(7));
+        nft.data = Bytes::new();
+        let decoded = decode_log(&nft, &tokens);
+        assert_eq!(decoded.event, None);
+        assert_eq!(decoded.topics.len(), 4);
+    }
+}
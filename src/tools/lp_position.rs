@@ -0,0 +1,317 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    logging::{info, warn},
+    token_registry::TokenRegistry,
+    tools::{
+        price::fetch_token_price_usd_at,
+        user_operation::{resolve_token, token_address},
+    },
+    types::{checksum_address, parse_address, TokenInfo},
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Uniswap V2 LP 代币精度(所有交易对固定为 18)
+const LP_TOKEN_DECIMALS: u8 = 18;
+
+/// GetLpPosition 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetLpPositionArgs {
+    /// 钱包地址(必需)
+    pub wallet: String,
+    /// Uniswap V2 交易对地址,即 LP 代币地址(与 token_a/token_b 二选一)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pair: Option<String>,
+    /// 交易对的一侧代币地址或符号(未提供 pair 时必需)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_a: Option<String>,
+    /// 交易对的另一侧代币地址或符号(未提供 pair 时必需)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_b: Option<String>,
+    /// 是否计算 USD 价值(可选,默认 true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_usd: Option<bool>,
+}
+
+/// GetLpPosition 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct LpPositionResult {
+    pub wallet: String,
+    pub pair: String,
+    pub token0: TokenInfo,
+    pub token1: TokenInfo,
+    /// 钱包持有的 LP 代币数量(已格式化)
+    pub lp_balance: String,
+    /// LP 代币总供应量(已格式化)
+    pub lp_total_supply: String,
+    /// 钱包在池子中的份额(百分比)
+    pub share_percent: String,
+    /// 按当前储备量可赎回的 token0 数量(已格式化)
+    pub amount0: String,
+    /// 按当前储备量可赎回的 token1 数量(已格式化)
+    pub amount1: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value0_usd: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value1_usd: Option<String>,
+    /// 头寸 USD 总价值(未请求或两侧都没有报价时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_value_usd: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 计算钱包持有的 Uniswap V2 LP 头寸:池子份额、可赎回的两侧代币数量和 USD 价值
+pub async fn get_lp_position(
+    config: &Arc<Config>,
+    erc20_client: &Arc<Erc20Client>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<GetLpPositionArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_lp_position 请求");
+
+    let wallet = parse_address(&args.wallet).map_err(|e| McpError::invalid_params(e, None))?;
+    let pair = args
+        .pair
+        .as_deref()
+        .map(parse_address)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(e, None))?;
+    if pair.is_none() && (args.token_a.is_none() || args.token_b.is_none()) {
+        return Err(McpError::invalid_params(
+            "需要提供 pair(LP 代币地址),或同时提供 token_a 和 token_b",
+            None,
+        ));
+    }
+    let include_usd = args.include_usd.unwrap_or(true);
+
+    info!(wallet = ?wallet, pair = ?pair, include_usd, "查询 LP 头寸");
+
+    // 测试模式
+    if config.server.test_mode {
+        let token = |symbol: &str, address: &str, decimals: u8| TokenInfo {
+            symbol: symbol.to_string(),
+            name: format!("Test {}", symbol),
+            address: address.to_string(),
+            decimals,
+            listed_on: Vec::new(),
+        };
+        let result = LpPositionResult {
+            wallet: checksum_address(wallet),
+            pair: pair.map(checksum_address).unwrap_or_else(|| checksum_address(Address::repeat_byte(0x33))),
+            token0: token("USDC", "0x0000000000000000000000000000000000000001", 6),
+            token1: token("WETH", "0x0000000000000000000000000000000000000002", 18),
+            lp_balance: "0.001".to_string(),
+            lp_total_supply: "1".to_string(),
+            share_percent: "0.1".to_string(),
+            amount0: "3000".to_string(),
+            amount1: "1".to_string(),
+            value0_usd: include_usd.then(|| "3000".to_string()),
+            value1_usd: include_usd.then(|| "3000".to_string()),
+            total_value_usd: include_usd.then(|| "6000".to_string()),
+            notes: Vec::new(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !erc20_client.is_available() || !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let pair = match (pair, args.token_a.as_deref(), args.token_b.as_deref()) {
+        (Some(pair), _, _) => pair,
+        (None, Some(token_a), Some(token_b)) => {
+            let token_a = resolve_token(token_registry, erc20_client, token_a).await?;
+            let token_b = resolve_token(token_registry, erc20_client, token_b).await?;
+            uniswap_client
+                .get_pair(token_address(&token_a)?, token_address(&token_b)?)
+                .await
+                .map_err(|e| {
+                    McpError::invalid_params(
+                        format!("{}/{} 交易对不存在: {}", token_a.symbol, token_b.symbol, e),
+                        None,
+                    )
+                })?
+        }
+        _ => unreachable!("参数已在前面校验"),
+    };
+
+    let (pair_tokens, reserves, lp_balance, total_supply) = tokio::join!(
+        uniswap_client.get_pair_tokens(pair),
+        uniswap_client.get_reserves(pair),
+        erc20_client.balance_of(pair, wallet, None),
+        erc20_client.total_supply(pair)
+    );
+    let (token0_addr, token1_addr) = pair_tokens.map_err(|e| {
+        McpError::invalid_params(
+            format!("{} 不是 Uniswap V2 交易对: {}", checksum_address(pair), e),
+            None,
+        )
+    })?;
+    let (reserve0, reserve1) =
+        reserves.map_err(|e| McpError::internal_error(format!("查询储备量失败: {}", e), None))?;
+    let lp_balance =
+        lp_balance.map_err(|e| McpError::internal_error(format!("查询 LP 代币余额失败: {}", e), None))?;
+    let total_supply =
+        total_supply.map_err(|e| McpError::internal_error(format!("查询 LP 代币总供应量失败: {}", e), None))?;
+
+    // 两个代币的信息通过 Multicall3 一次查询
+    let [token0, token1]: [TokenInfo; 2] = erc20_client
+        .tokens_info(&[token0_addr, token1_addr])
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?
+        .try_into()
+        .map_err(|_| McpError::internal_error("代币信息数量不符", None))?;
+
+    let amount0 = redeemable_amount(lp_balance, reserve0, total_supply);
+    let amount1 = redeemable_amount(lp_balance, reserve1, total_supply);
+
+    let mut notes = Vec::new();
+    if lp_balance.is_zero() {
+        notes.push("钱包未持有该交易对的 LP 代币".to_string());
+    }
+
+    let (value0, value1) = if include_usd && !lp_balance.is_zero() {
+        let (price0, price1) = tokio::join!(
+            fetch_token_price_usd_at(uniswap_client, token0_addr, token0.decimals, None),
+            fetch_token_price_usd_at(uniswap_client, token1_addr, token1.decimals, None)
+        );
+        let value = |price: Result<Decimal, McpError>, amount: U256, token: &TokenInfo| {
+            price
+                .inspect_err(|e| warn!(token = %token.symbol, error = %e, "查询代币 USD 价格失败"))
+                .ok()
+                .and_then(|price| amount_value_usd(amount, token.decimals, price))
+        };
+        (value(price0, amount0, &token0), value(price1, amount1, &token1))
+    } else {
+        (None, None)
+    };
+
+    let total_value = match (include_usd, lp_balance.is_zero()) {
+        (false, _) => None,
+        (true, true) => Some(Decimal::ZERO),
+        (true, false) => {
+            if value0.is_none() != value1.is_none() {
+                notes.push("一侧代币没有 USD 报价,总价值按 V2 池子两侧价值相等估算为另一侧的两倍".to_string());
+            }
+            let total = total_value_usd(value0, value1);
+            if total.is_none() {
+                notes.push("两侧代币都没有经由 WETH/USDC 池子的 USD 报价,无法估算价值".to_string());
+            }
+            total
+        }
+    };
+
+    let result = LpPositionResult {
+        wallet: checksum_address(wallet),
+        pair: checksum_address(pair),
+        lp_balance: format_units(lp_balance, LP_TOKEN_DECIMALS),
+        lp_total_supply: format_units(total_supply, LP_TOKEN_DECIMALS),
+        share_percent: pool_share_percent(lp_balance, total_supply).normalize().to_string(),
+        amount0: format_units(amount0, token0.decimals),
+        amount1: format_units(amount1, token1.decimals),
+        value0_usd: value0.map(|value| value.normalize().to_string()),
+        value1_usd: value1.map(|value| value.normalize().to_string()),
+        total_value_usd: total_value.map(|value| value.normalize().to_string()),
+        token0,
+        token1,
+        notes,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        pair = %result.pair,
+        share_percent = %result.share_percent,
+        total_value_usd = ?result.total_value_usd,
+        "成功返回 LP 头寸"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 按份额可赎回的代币数量 = LP 余额 × 储备量 / 总供应量(与 Pair.burn 的计算一致,向下取整)
+fn redeemable_amount(lp_balance: U256, reserve: U256, total_supply: U256) -> U256 {
+    if total_supply.is_zero() {
+        return U256::zero();
+    }
+    let amount = lp_balance.full_mul(reserve) / U512::from(total_supply);
+    U256::try_from(amount).unwrap_or(U256::MAX)
+}
+
+/// 池子份额百分比(保留 6 位小数)
+pub(crate) fn pool_share_percent(lp_balance: U256, total_supply: U256) -> Decimal {
+    // 以 10^8 为分母计算后换算为百分比,避免大数直接转换为 Decimal
+    let scaled = redeemable_amount(lp_balance, U256::exp10(8), total_supply);
+    Decimal::from_i128_with_scale(scaled.low_u128() as i128, 6)
+}
+
+/// 代币数量的 USD 价值(保留 2 位小数)
+fn amount_value_usd(amount: U256, decimals: u8, price_usd: Decimal) -> Option<Decimal> {
+    let amount = Decimal::from_str(&format_units(amount, decimals)).ok()?;
+    Some(amount.checked_mul(price_usd)?.round_dp(2))
+}
+
+/// 头寸总价值;V2 池子两侧价值相等,只有一侧有报价时按两倍估算
+fn total_value_usd(value0: Option<Decimal>, value1: Option<Decimal>) -> Option<Decimal> {
+    match (value0, value1) {
+        (Some(value0), Some(value1)) => Some(value0 + value1),
+        (Some(value), None) | (None, Some(value)) => Some(value * Decimal::TWO),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redeemable_amount() {
+        let total_supply = U256::exp10(18);
+        let reserve = U256::from(3_000_000_000u64); // 3000 USDC
+        assert_eq!(redeemable_amount(U256::exp10(15), reserve, total_supply), U256::from(3_000_000u64));
+        assert_eq!(redeemable_amount(total_supply, reserve, total_supply), reserve);
+        assert_eq!(redeemable_amount(U256::one(), reserve, U256::zero()), U256::zero());
+
+        // 乘积超过 256 位时不溢出
+        let large = U256::MAX / 2;
+        assert_eq!(redeemable_amount(large, large, large), large);
+    }
+
+    #[test]
+    fn test_pool_share_percent() {
+        let total_supply = U256::exp10(18);
+        assert_eq!(pool_share_percent(U256::exp10(15), total_supply), Decimal::from_str("0.1").unwrap());
+        assert_eq!(pool_share_percent(total_supply, total_supply), Decimal::from(100));
+        assert_eq!(pool_share_percent(U256::zero(), total_supply), Decimal::ZERO);
+        assert_eq!(pool_share_percent(U256::one(), U256::zero()), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_position_value() {
+        let price = Decimal::from_str("2.5").unwrap();
+        assert_eq!(amount_value_usd(U256::from(1_500_000u64), 6, price), Some(Decimal::from_str("3.75").unwrap()));
+
+        let value = Decimal::from(100);
+        assert_eq!(total_value_usd(Some(value), Some(Decimal::from(99))), Some(Decimal::from(199)));
+        assert_eq!(total_value_usd(None, Some(value)), Some(Decimal::from(200)));
+        assert_eq!(total_value_usd(None, None), None);
+    }
+}
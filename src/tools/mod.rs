@@ -3,3 +3,56 @@ pub mod balance;
 pub mod price;
 
 pub mod swap;
+
+pub mod reserve_history;
+
+pub mod chain_time;
+
+pub mod proof;
+
+pub mod staking;
+
+pub mod aggregate_balance;
+
+pub mod history;
+pub mod pnl;
+pub mod cost_basis;
+pub mod tax_report;
+pub mod position_size;
+pub mod quote_drift;
+pub mod new_pairs;
+pub mod trending;
+pub mod gas;
+pub mod batch;
+pub mod workers;
+pub mod snapshot;
+pub mod preview;
+pub mod bundle;
+pub mod user_operation;
+pub mod relay;
+pub mod authorization;
+pub mod cow;
+pub mod quotes;
+pub mod execute_swap;
+pub mod allowance;
+pub mod approve;
+pub mod portfolio;
+pub mod registry;
+pub mod token_metadata;
+pub mod twap;
+pub mod transaction;
+pub mod account;
+pub mod calldata;
+pub mod simulate;
+pub mod logs;
+pub mod token_transfers;
+pub mod transfer;
+pub mod raw_transaction;
+pub mod submissions;
+pub mod token_safety;
+pub mod contract_verification;
+pub mod approval_audit;
+pub mod nft;
+pub mod lp_position;
+pub mod liquidity;
+pub mod v3_position;
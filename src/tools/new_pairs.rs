@@ -0,0 +1,343 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    eth_client::EthClient,
+    logging::info,
+    pagination::{PageInfo, PageRequest},
+    pnl::{parse_period, SECONDS_PER_BLOCK},
+    token_registry::TokenRegistry,
+    types::TokenInfo,
+    uniswap::{PairCreatedLog, UniswapV2Client},
+    workers::WorkerManager,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, service::Peer,
+    ErrorData as McpError, RoleServer,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// 默认返回的交易对数量
+const DEFAULT_NEW_PAIRS_LIMIT: usize = 20;
+/// 单次查询允许返回的最大交易对数量（每个交易对需要查询代币信息和两次储备量）
+const MAX_NEW_PAIRS_LIMIT: usize = 100;
+/// 新交易对通知的轮询间隔
+const NEW_PAIR_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// GetNewPairs 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetNewPairsArgs {
+    /// 回溯时间区间(可选,如 1h、24h、7d,默认 24h)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// 返回数量上限(可选,默认 20,最大 100,优先返回最新的交易对)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// 分页游标(可选,使用上一页返回的 next_cursor)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+/// GetNewPairs 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct NewPairsResult {
+    pub factory: String,
+    pub from_block: u64,
+    pub to_block: u64,
+    /// 区间内新建的交易对总数
+    pub total_found: usize,
+    pub pairs: Vec<NewPair>,
+    #[serde(flatten)]
+    pub page: PageInfo,
+}
+
+/// 新建的交易对及其流动性
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct NewPair {
+    pub pair: String,
+    pub token0: TokenInfo,
+    pub token1: TokenInfo,
+    pub created_block: u64,
+    pub tx_hash: String,
+    /// 创建区块结束时的储备量（同一交易内添加的初始流动性）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_reserve0: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial_reserve1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_reserve0: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_reserve1: Option<String>,
+}
+
+/// 查询最近新建的 Uniswap V2 交易对
+pub async fn get_new_pairs(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<GetNewPairsArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_new_pairs 请求");
+
+    let since = args.since.as_deref().unwrap_or("24h");
+    let period_secs = parse_period(since).map_err(|e| McpError::invalid_params(e, None))?;
+    let page = PageRequest::parse(
+        args.limit,
+        args.cursor.as_deref(),
+        DEFAULT_NEW_PAIRS_LIMIT,
+        MAX_NEW_PAIRS_LIMIT,
+    )
+    .map_err(|e| McpError::invalid_params(e, None))?;
+
+    info!(since = %since, limit = page.limit, offset = page.offset, "查询新建交易对");
+
+    // 测试模式
+    if config.server.test_mode {
+        let test_token = |symbol: &str, address: &str| TokenInfo {
+            symbol: symbol.to_string(),
+            name: format!("{} Token", symbol),
+            address: address.to_string(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+
+        let result = NewPairsResult {
+            factory: format!("{:?}", uniswap_client.factory_address()),
+            from_block: 20_000_000 - period_secs / SECONDS_PER_BLOCK,
+            to_block: 20_000_000,
+            total_found: 1,
+            pairs: vec![NewPair {
+                pair: "0x0000000000000000000000000000000000000003".to_string(),
+                token0: test_token("NEW", "0x0000000000000000000000000000000000000001"),
+                token1: test_token("WETH", "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+                created_block: 19_999_990,
+                tx_hash: format!("{:?}", H256::zero()),
+                initial_reserve0: Some("1000000".to_string()),
+                initial_reserve1: Some("5".to_string()),
+                current_reserve0: Some("900000".to_string()),
+                current_reserve1: Some("5.6".to_string()),
+            }],
+            page: PageInfo::default(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Uniswap 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let eth_client = eth_client.clone();
+    let uniswap_client = uniswap_client.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+
+    let result = async {
+        let to_block = eth_client.get_block_number().await.map_err(|e| {
+            McpError::internal_error(format!("查询最新区块失败: {}", e), None)
+        })?;
+        let from_block = to_block.saturating_sub(period_secs / SECONDS_PER_BLOCK);
+
+        let created = uniswap_client
+            .pair_created_logs(from_block, to_block)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询 PairCreated 事件失败: {}", e), None))?;
+        let total_found = created.len();
+
+        // 按从新到旧排序后取当前页，并发查询交易对详情
+        let newest_first: Vec<PairCreatedLog> = created.into_iter().rev().collect();
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, log) in page.window(&newest_first).iter().take(page.limit).enumerate() {
+            let log = log.clone();
+            let uniswap_client = uniswap_client.clone();
+            let erc20_client = erc20_client.clone();
+            let token_registry = token_registry.clone();
+            tasks.spawn(async move {
+                let pair = build_new_pair(&uniswap_client, &erc20_client, &token_registry, log).await;
+                (index, pair)
+            });
+        }
+
+        let mut pairs = Vec::with_capacity(tasks.len());
+        while let Some(joined) = tasks.join_next().await {
+            let pair = joined
+                .map_err(|e| McpError::internal_error(format!("查询交易对任务失败: {}", e), None))?;
+            pairs.push(pair);
+        }
+        pairs.sort_by_key(|(index, _)| *index);
+        let pairs: Vec<NewPair> = pairs.into_iter().map(|(_, pair)| pair).collect();
+
+        let has_more = page.offset + pairs.len() < total_found;
+        Ok::<_, McpError>(page.fit(&pairs, has_more, |pairs, page| NewPairsResult {
+            factory: format!("{:?}", uniswap_client.factory_address()),
+            from_block,
+            to_block,
+            total_found,
+            pairs,
+            page,
+        }))
+    }
+    .await?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(count = result.pairs.len(), "成功返回新建交易对");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 启动新交易对监听任务，每个新建的交易对通过 MCP 日志通知(logger = new_pairs)推送给客户端
+/// 首次运行从当前区块开始，不推送历史交易对
+pub fn spawn_new_pair_notifier(
+    workers: &WorkerManager,
+    eth_client: Arc<EthClient>,
+    uniswap_client: Arc<UniswapV2Client>,
+    erc20_client: Arc<Erc20Client>,
+    token_registry: Arc<TokenRegistry>,
+    peer: Peer<RoleServer>,
+) {
+    let last_block = Arc::new(AtomicU64::new(0));
+
+    workers.spawn_periodic(
+        "new_pair_notifier",
+        "推送 Uniswap V2 新建交易对通知",
+        NEW_PAIR_POLL_INTERVAL,
+        move || {
+            let eth_client = eth_client.clone();
+            let uniswap_client = uniswap_client.clone();
+            let erc20_client = erc20_client.clone();
+            let token_registry = token_registry.clone();
+            let peer = peer.clone();
+            let last_block = last_block.clone();
+
+            async move {
+                let latest = eth_client
+                    .get_block_number()
+                    .await
+                    .map_err(|e| format!("查询最新区块失败: {}", e))?;
+
+                let from_block = last_block.load(Ordering::SeqCst);
+                if from_block == 0 || latest <= from_block {
+                    last_block.store(latest.max(from_block), Ordering::SeqCst);
+                    return Ok(());
+                }
+
+                let created = uniswap_client
+                    .pair_created_logs(from_block + 1, latest)
+                    .await
+                    .map_err(|e| format!("查询 PairCreated 事件失败: {}", e))?;
+
+                for log in created {
+                    let pair = build_new_pair(&uniswap_client, &erc20_client, &token_registry, log).await;
+                    let data = serde_json::to_value(&pair).map_err(|e| e.to_string())?;
+                    peer.notify_logging_message(LoggingMessageNotificationParam {
+                        level: LoggingLevel::Info,
+                        logger: Some("new_pairs".to_string()),
+                        data,
+                    })
+                    .await
+                    .map_err(|e| format!("发送新交易对通知失败: {}", e))?;
+                }
+
+                last_block.store(latest, Ordering::SeqCst);
+                Ok(())
+            }
+        },
+    );
+}
+
+/// 查询交易对的代币信息和初始/当前储备量
+/// 单个查询失败只省略对应字段，不影响其他交易对
+async fn build_new_pair(
+    uniswap_client: &UniswapV2Client,
+    erc20_client: &Erc20Client,
+    token_registry: &TokenRegistry,
+    log: PairCreatedLog,
+) -> NewPair {
+    let (token0, token1) = tokio::join!(
+        lookup_token(erc20_client, token_registry, log.token0),
+        lookup_token(erc20_client, token_registry, log.token1)
+    );
+
+    let (initial, current) = tokio::join!(
+        uniswap_client.get_reserves_at(log.pair, Some(BlockId::from(log.block_number))),
+        uniswap_client.get_reserves(log.pair)
+    );
+
+    let format_reserves = |reserves: Option<(U256, U256)>| match reserves {
+        Some((reserve0, reserve1)) => (
+            Some(format_units(reserve0, token0.decimals)),
+            Some(format_units(reserve1, token1.decimals)),
+        ),
+        None => (None, None),
+    };
+    let (initial_reserve0, initial_reserve1) = format_reserves(initial.ok());
+    let (current_reserve0, current_reserve1) = format_reserves(current.ok());
+
+    NewPair {
+        pair: format!("{:?}", log.pair),
+        created_block: log.block_number,
+        tx_hash: format!("{:?}", log.tx_hash),
+        initial_reserve0,
+        initial_reserve1,
+        current_reserve0,
+        current_reserve1,
+        token0,
+        token1,
+    }
+}
+
+/// 查询代币信息
+/// 新代币不写入注册表:新上线的代币常仿冒知名符号,注册会覆盖已有的同名代币
+pub(crate) async fn lookup_token(
+    erc20_client: &Erc20Client,
+    token_registry: &TokenRegistry,
+    address: Address,
+) -> TokenInfo {
+    let placeholder = token_registry
+        .resolve(&format!("{:?}", address))
+        .expect("地址格式的输入总能解析");
+
+    if placeholder.symbol != "UNKNOWN" || !erc20_client.is_available() {
+        return placeholder;
+    }
+
+    erc20_client.token_info(address).await.unwrap_or(placeholder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_new_pairs_args_deserialization() {
+        let args: GetNewPairsArgs = serde_json::from_str(r#"{"since": "1h"}"#).unwrap();
+        assert_eq!(args.since.as_deref(), Some("1h"));
+        assert!(args.limit.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_known_token_without_provider() {
+        let registry = TokenRegistry::new();
+        let usdc = registry.resolve("USDC").unwrap();
+        let address: Address = usdc.address.parse().unwrap();
+
+        let token = lookup_token(&Erc20Client::new(None), &registry, address).await;
+        assert_eq!(token.symbol, "USDC");
+
+        let unknown = lookup_token(&Erc20Client::new(None), &registry, Address::repeat_byte(0x42)).await;
+        assert_eq!(unknown.symbol, "UNKNOWN");
+    }
+}
@@ -0,0 +1,365 @@
+use crate::{
+    config::Config,
+    erc1155::{parse_token_id, Erc1155Client},
+    logging::info,
+    nft::{CollectionInfo, NftClient},
+    types::{checksum_address, parse_address},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 默认列出的 token ID 数量
+const DEFAULT_MAX_TOKEN_IDS: usize = 100;
+/// 单次查询允许列出的最大 token ID 数量
+const MAX_TOKEN_IDS: usize = 1000;
+
+/// GetNftBalance 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetNftBalanceArgs {
+    /// 钱包地址(必需)
+    pub address: String,
+    /// NFT 合集合约地址(必需)
+    pub collection: String,
+    /// 最多列出的 token ID 数量(可选,默认 100,最大 1000;为 0 时只返回数量,仅用于 ERC-721)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_token_ids: Option<usize>,
+    /// 要查询余额的 token ID 列表(十进制或 0x 十六进制,ERC-1155 合集必需,最多 1000 个)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_ids: Option<Vec<String>>,
+}
+
+/// GetNftBalance 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct NftBalanceResult {
+    pub address: String,
+    pub collection: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    /// 代币标准,"ERC-721" 或 "ERC-1155"
+    pub standard: String,
+    /// 持有的 NFT 数量(ERC-1155 为所查询 token ID 的余额合计)
+    pub balance: String,
+    /// 合集是否支持 ERC-721 Enumerable(支持时才能列出 token ID)
+    pub enumerable: bool,
+    /// 持有的 token ID(十进制字符串,ERC-721)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub token_ids: Vec<String>,
+    /// 每个 token ID 的持有数量(ERC-1155,顺序与请求一致)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub balances: Vec<TokenIdBalance>,
+    /// 持有数量超过 max_token_ids,token_ids 只包含前一部分
+    pub token_ids_truncated: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// ERC-1155 单个 token ID 的持有数量
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TokenIdBalance {
+    pub token_id: String,
+    pub balance: String,
+}
+
+/// 查询钱包持有的 NFT
+/// ERC-721 合集返回持有数量,支持 Enumerable 时同时列出 token ID;ERC-1155 合集返回指定 token ID 的持有数量
+pub async fn get_nft_balance(
+    config: &Arc<Config>,
+    nft_client: &Arc<NftClient>,
+    erc1155_client: &Arc<Erc1155Client>,
+    Parameters(args): Parameters<GetNftBalanceArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_nft_balance 请求");
+
+    let owner = parse_address(&args.address).map_err(|e| McpError::invalid_params(e, None))?;
+    let collection = parse_address(&args.collection).map_err(|e| McpError::invalid_params(e, None))?;
+    let max_token_ids = args.max_token_ids.unwrap_or(DEFAULT_MAX_TOKEN_IDS);
+    if max_token_ids > MAX_TOKEN_IDS {
+        return Err(McpError::invalid_params(
+            format!("max_token_ids 不能超过 {}", MAX_TOKEN_IDS),
+            None,
+        ));
+    }
+    let requested_ids = args
+        .token_ids
+        .as_deref()
+        .map(parse_token_ids)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    info!(owner = ?owner, collection = ?collection, "查询 NFT 余额");
+
+    // 测试模式
+    if config.server.test_mode {
+        let info = CollectionInfo {
+            name: Some("Test Collection".to_string()),
+            symbol: Some("TNFT".to_string()),
+            erc721: requested_ids.is_none(),
+            enumerable: requested_ids.is_none(),
+            erc1155: requested_ids.is_some(),
+        };
+        let result = match requested_ids {
+            Some(ids) => {
+                let balances = vec![U256::from(5); ids.len()];
+                build_erc1155_result(owner, collection, info, &ids, &balances)
+            }
+            None => {
+                let token_ids = vec![U256::from(1), U256::from(42)];
+                build_result(owner, collection, info, U256::from(2), token_ids, max_token_ids)
+            }
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !nft_client.is_available() || !erc1155_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let info = nft_client
+        .collection_info(collection)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询合集信息失败: {}", e), None))?;
+
+    match (&requested_ids, select_standard(&info, requested_ids.is_some())) {
+        (Some(ids), Standard::Erc1155) => {
+            let balances = match ids.as_slice() {
+                [id] => erc1155_client.balance_of(collection, owner, *id).await.map(|balance| vec![balance]),
+                _ => erc1155_client.balance_of_batch(collection, owner, ids).await,
+            }
+            .map_err(|e| {
+                McpError::internal_error(format!("查询 ERC-1155 余额失败(合约可能不是 ERC-1155): {}", e), None)
+            })?;
+            let result = build_erc1155_result(owner, collection, info, ids, &balances);
+            return respond(result);
+        }
+        (None, Standard::Erc1155) => {
+            return Err(McpError::invalid_params(
+                "ERC-1155 合集无法列出持有的 token ID,请通过 token_ids 指定要查询的 token ID",
+                None,
+            ));
+        }
+        (Some(_), Standard::Erc721) => {
+            return Err(McpError::invalid_params(
+                "token_ids 仅用于 ERC-1155 合集,ERC-721 合集会直接列出持有的 token ID",
+                None,
+            ));
+        }
+        (None, Standard::Erc721) => {}
+    }
+
+    let balance = nft_client.erc721_balance(collection, owner).await.map_err(|e| {
+        McpError::internal_error(format!("查询 NFT 余额失败(合约可能不是 ERC-721): {}", e), None)
+    })?;
+
+    let token_ids = if info.enumerable {
+        let count = balance.min(U256::from(max_token_ids)).as_usize();
+        nft_client
+            .owned_token_ids(collection, owner, count)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询持有的 token ID 失败: {}", e), None))?
+    } else {
+        Vec::new()
+    };
+
+    respond(build_result(owner, collection, info, balance, token_ids, max_token_ids))
+}
+
+fn respond(result: NftBalanceResult) -> Result<CallToolResult, McpError> {
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        collection = %result.collection,
+        standard = %result.standard,
+        balance = %result.balance,
+        "成功返回 NFT 余额"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 合集按哪种标准查询
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Standard {
+    Erc721,
+    Erc1155,
+}
+
+/// 根据 ERC-165 声明选择查询方式
+/// 两种接口都未声明时,传入 token_ids 按 ERC-1155 查询,否则按 ERC-721 查询
+fn select_standard(info: &CollectionInfo, has_token_ids: bool) -> Standard {
+    match (info.erc721, info.erc1155) {
+        (true, false) => Standard::Erc721,
+        (false, true) => Standard::Erc1155,
+        _ if has_token_ids => Standard::Erc1155,
+        _ => Standard::Erc721,
+    }
+}
+
+/// 解析 token_ids 参数
+fn parse_token_ids(values: &[String]) -> Result<Vec<U256>, String> {
+    if values.is_empty() {
+        return Err("token_ids 不能为空列表".to_string());
+    }
+    if values.len() > MAX_TOKEN_IDS {
+        return Err(format!("token_ids 最多 {} 个", MAX_TOKEN_IDS));
+    }
+    values.iter().map(|value| parse_token_id(value)).collect()
+}
+
+fn build_result(
+    owner: Address,
+    collection: Address,
+    info: CollectionInfo,
+    balance: U256,
+    token_ids: Vec<U256>,
+    max_token_ids: usize,
+) -> NftBalanceResult {
+    let mut notes = Vec::new();
+    if !info.erc721 {
+        notes.push("合约未通过 ERC-165 声明支持 ERC-721,余额按 balanceOf 返回值解读,可能不准确".to_string());
+    }
+    if !info.enumerable && !balance.is_zero() {
+        notes.push("合集不支持 ERC-721 Enumerable,无法直接列出持有的 token ID".to_string());
+    }
+
+    let token_ids_truncated = info.enumerable && balance > U256::from(max_token_ids);
+    if token_ids_truncated {
+        notes.push(format!("持有数量超过 {},token_ids 只列出前 {} 个", max_token_ids, max_token_ids));
+    }
+
+    NftBalanceResult {
+        address: checksum_address(owner),
+        collection: checksum_address(collection),
+        name: info.name,
+        symbol: info.symbol,
+        standard: "ERC-721".to_string(),
+        balance: balance.to_string(),
+        enumerable: info.enumerable,
+        token_ids: token_ids.iter().map(U256::to_string).collect(),
+        balances: Vec::new(),
+        token_ids_truncated,
+        notes,
+    }
+}
+
+fn build_erc1155_result(
+    owner: Address,
+    collection: Address,
+    info: CollectionInfo,
+    ids: &[U256],
+    balances: &[U256],
+) -> NftBalanceResult {
+    let mut notes = Vec::new();
+    if !info.erc1155 {
+        notes.push("合约未通过 ERC-165 声明支持 ERC-1155,余额按 balanceOf 返回值解读,可能不准确".to_string());
+    }
+    let total = balances.iter().fold(U256::zero(), |total, balance| total.saturating_add(*balance));
+
+    NftBalanceResult {
+        address: checksum_address(owner),
+        collection: checksum_address(collection),
+        name: info.name,
+        symbol: info.symbol,
+        standard: "ERC-1155".to_string(),
+        balance: total.to_string(),
+        enumerable: false,
+        token_ids: Vec::new(),
+        balances: ids
+            .iter()
+            .zip(balances)
+            .map(|(id, balance)| TokenIdBalance { token_id: id.to_string(), balance: balance.to_string() })
+            .collect(),
+        token_ids_truncated: false,
+        notes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(erc721: bool, enumerable: bool) -> CollectionInfo {
+        CollectionInfo {
+            name: Some("Test".to_string()),
+            symbol: Some("T".to_string()),
+            erc721,
+            enumerable,
+            erc1155: false,
+        }
+    }
+
+    #[test]
+    fn test_build_result() {
+        let owner = Address::repeat_byte(0x11);
+        let collection = Address::repeat_byte(0x22);
+
+        let result = build_result(owner, collection, info(true, true), U256::from(2), vec![1.into(), 7.into()], 100);
+        assert_eq!(result.balance, "2");
+        assert_eq!(result.token_ids, vec!["1", "7"]);
+        assert!(!result.token_ids_truncated);
+        assert!(result.notes.is_empty());
+
+        // 持有数量超过上限
+        let result = build_result(owner, collection, info(true, true), U256::from(5), vec![1.into(), 2.into()], 2);
+        assert!(result.token_ids_truncated);
+        assert_eq!(result.notes.len(), 1);
+
+        // 不支持 Enumerable 且未声明 ERC-721
+        let result = build_result(owner, collection, info(false, false), U256::from(3), Vec::new(), 100);
+        assert!(!result.token_ids_truncated);
+        assert!(result.token_ids.is_empty());
+        assert_eq!(result.notes.len(), 2);
+    }
+
+    #[test]
+    fn test_build_erc1155_result() {
+        let owner = Address::repeat_byte(0x11);
+        let collection = Address::repeat_byte(0x22);
+        let collection_info = CollectionInfo { erc1155: true, ..info(false, false) };
+
+        let result = build_erc1155_result(
+            owner,
+            collection,
+            collection_info,
+            &[U256::from(1), U256::from(2)],
+            &[U256::from(3), U256::from(4)],
+        );
+        assert_eq!(result.standard, "ERC-1155");
+        assert_eq!(result.balance, "7");
+        assert_eq!(result.balances[1].token_id, "2");
+        assert_eq!(result.balances[1].balance, "4");
+        assert!(result.notes.is_empty());
+    }
+
+    #[test]
+    fn test_select_standard() {
+        let erc1155 = CollectionInfo { erc1155: true, ..info(false, false) };
+        assert_eq!(select_standard(&info(true, false), false), Standard::Erc721);
+        assert_eq!(select_standard(&info(true, false), true), Standard::Erc721);
+        assert_eq!(select_standard(&erc1155, false), Standard::Erc1155);
+        // 未声明任何接口时按是否传入 token_ids 判断
+        assert_eq!(select_standard(&info(false, false), true), Standard::Erc1155);
+        assert_eq!(select_standard(&info(false, false), false), Standard::Erc721);
+    }
+
+    #[test]
+    fn test_parse_token_ids() {
+        assert_eq!(parse_token_ids(&["1".to_string(), "0x10".to_string()]).unwrap(), vec![U256::from(1), U256::from(16)]);
+        assert!(parse_token_ids(&[]).is_err());
+        assert!(parse_token_ids(&["x".to_string()]).is_err());
+        assert!(parse_token_ids(&vec!["1".to_string(); MAX_TOKEN_IDS + 1]).is_err());
+    }
+}
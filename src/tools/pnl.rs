@@ -0,0 +1,524 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    eth_client::EthClient,
+    export::{self, CsvExport, ExportFormat},
+    logging::info,
+    pnl::{self, LedgerEntry, LedgerSide, Position, SECONDS_PER_BLOCK},
+    store::Store,
+    token_registry::TokenRegistry,
+    tools::price::fetch_token_price_usd_at,
+    types::{parse_address, TokenInfo},
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use rust_decimal::Decimal;
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// 单次计算允许扫描的最大 Transfer 事件数量
+const MAX_PNL_TRANSFERS: usize = 500;
+
+/// 默认统计区间
+const DEFAULT_PNL_PERIOD: &str = "30d";
+
+/// GetPnl 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetPnlArgs {
+    /// 钱包地址(必需)
+    pub address: String,
+    /// 统计区间(可选,如 24h/7d/30d/1y,默认 30d)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period: Option<String>,
+    /// 导出格式(可选,json/csv,默认 json)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export: Option<String>,
+}
+
+/// GetPnl 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PnlResult {
+    pub address: String,
+    pub period: String,
+    pub from_block: u64,
+    pub to_block: u64,
+    /// 本次扫描到的 Transfer 事件数量
+    pub transfers_scanned: usize,
+    pub positions: Vec<TokenPnl>,
+    pub total_realized_pnl_usd: String,
+    pub total_unrealized_pnl_usd: String,
+    /// 无法定价(缺少 WETH 交易对等)而未计入的代币
+    pub skipped_tokens: Vec<String>,
+    /// 成本台账是否来自持久化存储(包含区间之前的历史)
+    pub persisted: bool,
+}
+
+/// 单个代币的盈亏
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TokenPnl {
+    pub token: String,
+    pub symbol: String,
+    pub quantity: String,
+    pub average_cost_usd: String,
+    pub cost_basis_usd: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_price_usd: Option<String>,
+    pub realized_pnl_usd: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unrealized_pnl_usd: Option<String>,
+    /// 超出已知持仓的卖出数量(可能在统计区间之前买入)
+    pub unmatched_disposal: String,
+}
+
+/// 从 Transfer 事件构建的成本台账
+pub(crate) struct TransferLedger {
+    pub entries: Vec<LedgerEntry>,
+    pub transfers_scanned: usize,
+    pub skipped_tokens: Vec<String>,
+}
+
+/// 计算钱包的已实现/未实现盈亏(基于 ERC20 Transfer 历史和历史价格)
+pub async fn get_pnl(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    store: &Arc<Store>,
+    Parameters(args): Parameters<GetPnlArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_pnl 请求");
+
+    let export_format = ExportFormat::parse(args.export.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let period = args.period.unwrap_or_else(|| DEFAULT_PNL_PERIOD.to_string());
+    let period_secs = pnl::parse_period(&period).map_err(|e| McpError::invalid_params(e, None))?;
+
+    info!(address = %args.address, period = %period, "计算钱包盈亏");
+
+    // 测试模式
+    if config.server.test_mode {
+        let position = Position {
+            token: "0x0000000000000000000000000000000000000001".to_string(),
+            symbol: "TEST".to_string(),
+            quantity: Decimal::from(10),
+            cost_basis: Decimal::from(1000),
+            realized_pnl: Decimal::from(250),
+            unmatched_disposal: Decimal::ZERO,
+            fees_usd: Decimal::ZERO,
+            entry_count: 4,
+        };
+
+        let result = build_pnl_result(
+            args.address.clone(),
+            period,
+            (0, period_secs / SECONDS_PER_BLOCK),
+            4,
+            &[position],
+            &HashMap::from([(
+                "0x0000000000000000000000000000000000000001".to_string(),
+                Decimal::from(120),
+            )]),
+            Vec::new(),
+            false,
+        );
+
+        return export::render(&result, export_format);
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() || !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let wallet = parse_address(&args.address).map_err(|e| McpError::invalid_params(e, None))?;
+
+    let eth_client = eth_client.clone();
+    let uniswap_client = uniswap_client.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+
+    let result = async {
+        let (to_block, latest_timestamp) = eth_client
+            .get_block_timestamp(BlockNumber::Latest)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询最新区块失败: {}", e), None))?;
+        let from_block = to_block.saturating_sub(period_secs / SECONDS_PER_BLOCK);
+
+        let ledger = collect_transfer_ledger(
+            &eth_client,
+            &uniswap_client,
+            &erc20_client,
+            &token_registry,
+            wallet,
+            (from_block, to_block),
+            MAX_PNL_TRANSFERS,
+        )
+        .await?;
+
+        // 💾 启用持久化时合并历史台账，使成本基础覆盖统计区间之前的买入
+        let wallet_key = format!("{:?}", wallet);
+        let (entries, persisted) = merge_with_store(store, &wallet_key, ledger.entries)?;
+
+        let since = latest_timestamp.saturating_sub(period_secs);
+        let positions = pnl::compute_positions(&entries, Some(since));
+
+        // 查询仍有持仓的代币现价
+        let mut current_prices = HashMap::new();
+        for position in positions.iter().filter(|p| !p.quantity.is_zero()) {
+            let Ok(token_addr) = position.token.parse::<Address>() else {
+                continue;
+            };
+            let decimals = token_registry
+                .resolve(&position.token)
+                .map(|t| t.decimals)
+                .unwrap_or(18);
+            if let Ok(price) = fetch_token_price_usd_at(&uniswap_client, token_addr, decimals, None).await {
+                current_prices.insert(position.token.to_lowercase(), price);
+            }
+        }
+
+        Ok::<_, McpError>(build_pnl_result(
+            wallet_key,
+            period,
+            (from_block, to_block),
+            ledger.transfers_scanned,
+            &positions,
+            &current_prices,
+            ledger.skipped_tokens,
+            persisted,
+        ))
+    }
+    .await?;
+
+    info!("成功返回钱包盈亏");
+
+    export::render(&result, export_format)
+}
+
+/// 扫描钱包在区块区间内的 ERC20 转账，并按转账所在区块的 USD 价格构建成本台账
+/// 转入记为买入、转出记为卖出；无法定价的代币整体跳过，避免部分数据造成误导
+pub(crate) async fn collect_transfer_ledger(
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    wallet: Address,
+    (from_block, to_block): (u64, u64),
+    max_transfers: usize,
+) -> Result<TransferLedger, McpError> {
+    let transfers = erc20_client
+        .transfer_logs(wallet, &[], from_block, to_block)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询转账记录失败: {}", e), None))?;
+
+    if transfers.len() > max_transfers {
+        return Err(McpError::invalid_params(
+            format!(
+                "转账记录过多: {} (最多 {} 条),请缩短统计区间",
+                transfers.len(),
+                max_transfers
+            ),
+            None,
+        ));
+    }
+
+    // 解析涉及的代币信息
+    let mut tokens: HashMap<Address, TokenInfo> = HashMap::new();
+    for token_addr in transfers.iter().map(|t| t.token).collect::<BTreeSet<_>>() {
+        let mut token_info = token_registry
+            .resolve(&format!("{:?}", token_addr))
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        // 🔍 动态查询未知代币信息
+        if token_info.symbol == "UNKNOWN" {
+            let real_info = erc20_client
+                .token_info(token_addr)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
+
+            // 缓存到注册表
+            token_registry.register(real_info.symbol.clone(), real_info.clone());
+            token_info = real_info;
+        }
+
+        tokens.insert(token_addr, token_info);
+    }
+
+    // 并发查询区块时间戳和历史价格
+    let mut timestamp_tasks = tokio::task::JoinSet::new();
+    for block_number in transfers.iter().map(|t| t.block_number).collect::<BTreeSet<_>>() {
+        let eth_client = eth_client.clone();
+        timestamp_tasks.spawn(async move {
+            let timestamp = eth_client
+                .get_block_timestamp(BlockNumber::Number(block_number.into()))
+                .await
+                .map(|(_, ts)| ts);
+            (block_number, timestamp)
+        });
+    }
+
+    let mut price_tasks = tokio::task::JoinSet::new();
+    for (token_addr, block_number) in transfers
+        .iter()
+        .map(|t| (t.token, t.block_number))
+        .collect::<BTreeSet<_>>()
+    {
+        let client = (**uniswap_client).clone();
+        let decimals = tokens[&token_addr].decimals;
+        price_tasks.spawn(async move {
+            let price =
+                fetch_token_price_usd_at(&client, token_addr, decimals, Some(BlockId::from(block_number))).await;
+            ((token_addr, block_number), price.ok())
+        });
+    }
+
+    let mut timestamps = HashMap::new();
+    while let Some(joined) = timestamp_tasks.join_next().await {
+        let (block_number, timestamp) = joined
+            .map_err(|e| McpError::internal_error(format!("区块查询任务失败: {}", e), None))?;
+        let timestamp = timestamp.map_err(|e| {
+            McpError::internal_error(format!("查询区块 {} 时间戳失败: {}", block_number, e), None)
+        })?;
+        timestamps.insert(block_number, timestamp);
+    }
+
+    let mut prices = HashMap::new();
+    while let Some(joined) = price_tasks.join_next().await {
+        let (key, price) = joined
+            .map_err(|e| McpError::internal_error(format!("价格查询任务失败: {}", e), None))?;
+        prices.insert(key, price);
+    }
+
+    // 构建台账，记录无法定价或数量无法表示的代币
+    let mut entries = Vec::new();
+    let mut skipped: BTreeSet<Address> = BTreeSet::new();
+    for transfer in &transfers {
+        // 自转账不改变持仓
+        if transfer.from == transfer.to {
+            continue;
+        }
+
+        let token_info = &tokens[&transfer.token];
+        let price = prices.get(&(transfer.token, transfer.block_number)).copied().flatten();
+        let amount = Decimal::from_str(&format_units(transfer.value, token_info.decimals)).ok();
+
+        let (Some(price_usd), Some(amount)) = (price, amount) else {
+            skipped.insert(transfer.token);
+            continue;
+        };
+
+        entries.push(LedgerEntry {
+            token: format!("{:?}", transfer.token),
+            symbol: token_info.symbol.clone(),
+            side: if transfer.to == wallet {
+                LedgerSide::Acquire
+            } else {
+                LedgerSide::Dispose
+            },
+            amount,
+            price_usd,
+            fee_usd: Decimal::ZERO,
+            block_number: transfer.block_number,
+            timestamp: timestamps[&transfer.block_number],
+            tx_hash: format!("{:?}", transfer.tx_hash),
+            log_index: transfer.log_index as i64,
+            source: "transfer".to_string(),
+        });
+    }
+
+    entries.retain(|e| {
+        e.token
+            .parse::<Address>()
+            .map(|addr| !skipped.contains(&addr))
+            .unwrap_or(true)
+    });
+
+    Ok(TransferLedger {
+        entries,
+        transfers_scanned: transfers.len(),
+        skipped_tokens: skipped
+            .iter()
+            .map(|addr| format!("{} ({:?})", tokens[addr].symbol, addr))
+            .collect(),
+    })
+}
+
+/// 启用持久化时写入新台账并返回钱包的完整台账，否则原样返回
+/// 第二个返回值表示台账是否来自持久化存储
+pub(crate) fn merge_with_store(
+    store: &Store,
+    wallet: &str,
+    entries: Vec<LedgerEntry>,
+) -> Result<(Vec<LedgerEntry>, bool), McpError> {
+    if !store.is_enabled() {
+        return Ok((entries, false));
+    }
+
+    store
+        .insert_ledger_entries(wallet, &entries)
+        .and_then(|_| store.ledger_entries(wallet, None))
+        .map(|entries| (entries, true))
+        .map_err(|e| McpError::internal_error(format!("读写成本台账失败: {}", e), None))
+}
+
+impl CsvExport for PnlResult {
+    fn csv_headers(&self) -> Vec<&'static str> {
+        vec![
+            "token",
+            "symbol",
+            "quantity",
+            "average_cost_usd",
+            "cost_basis_usd",
+            "current_price_usd",
+            "realized_pnl_usd",
+            "unrealized_pnl_usd",
+        ]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        self.positions
+            .iter()
+            .map(|p| {
+                vec![
+                    p.token.clone(),
+                    p.symbol.clone(),
+                    p.quantity.clone(),
+                    p.average_cost_usd.clone(),
+                    p.cost_basis_usd.clone(),
+                    p.current_price_usd.clone().unwrap_or_default(),
+                    p.realized_pnl_usd.clone(),
+                    p.unrealized_pnl_usd.clone().unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// 格式化 USD 金额(保留 2 位小数)
+pub(crate) fn format_usd(value: Decimal) -> String {
+    value.round_dp(2).to_string()
+}
+
+/// 汇总各代币持仓为盈亏结果
+/// `current_prices` 以小写代币地址为键，缺少现价的持仓不计算未实现盈亏
+#[allow(clippy::too_many_arguments)]
+fn build_pnl_result(
+    address: String,
+    period: String,
+    (from_block, to_block): (u64, u64),
+    transfers_scanned: usize,
+    positions: &[Position],
+    current_prices: &HashMap<String, Decimal>,
+    skipped_tokens: Vec<String>,
+    persisted: bool,
+) -> PnlResult {
+    let mut total_realized = Decimal::ZERO;
+    let mut total_unrealized = Decimal::ZERO;
+
+    let positions = positions
+        .iter()
+        .map(|position| {
+            let current_price = current_prices.get(&position.token.to_lowercase()).copied();
+            let unrealized = if position.quantity.is_zero() {
+                Some(Decimal::ZERO)
+            } else {
+                current_price.map(|price| position.unrealized_pnl(price))
+            };
+
+            total_realized += position.realized_pnl;
+            total_unrealized += unrealized.unwrap_or_default();
+
+            TokenPnl {
+                token: position.token.clone(),
+                symbol: position.symbol.clone(),
+                quantity: position.quantity.normalize().to_string(),
+                average_cost_usd: position.average_cost().round_dp(6).normalize().to_string(),
+                cost_basis_usd: format_usd(position.cost_basis),
+                current_price_usd: current_price.map(|p| p.round_dp(6).normalize().to_string()),
+                realized_pnl_usd: format_usd(position.realized_pnl),
+                unrealized_pnl_usd: unrealized.map(format_usd),
+                unmatched_disposal: position.unmatched_disposal.normalize().to_string(),
+            }
+        })
+        .collect();
+
+    PnlResult {
+        address,
+        period,
+        from_block,
+        to_block,
+        transfers_scanned,
+        positions,
+        total_realized_pnl_usd: format_usd(total_realized),
+        total_unrealized_pnl_usd: format_usd(total_unrealized),
+        skipped_tokens,
+        persisted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_pnl_result() {
+        let positions = vec![
+            Position {
+                token: "0xAAA".to_string(),
+                symbol: "AAA".to_string(),
+                quantity: Decimal::from(10),
+                cost_basis: Decimal::from(1000),
+                realized_pnl: Decimal::from(50),
+                unmatched_disposal: Decimal::ZERO,
+                fees_usd: Decimal::ZERO,
+                entry_count: 1,
+            },
+            Position {
+                token: "0xBBB".to_string(),
+                symbol: "BBB".to_string(),
+                quantity: Decimal::from(5),
+                cost_basis: Decimal::from(500),
+                realized_pnl: Decimal::from(-20),
+                unmatched_disposal: Decimal::ZERO,
+                fees_usd: Decimal::ZERO,
+                entry_count: 1,
+            },
+        ];
+        // BBB 缺少现价
+        let prices = HashMap::from([("0xaaa".to_string(), Decimal::from(110))]);
+
+        let result = build_pnl_result(
+            "0xwallet".to_string(),
+            "30d".to_string(),
+            (1, 2),
+            3,
+            &positions,
+            &prices,
+            Vec::new(),
+            false,
+        );
+
+        assert_eq!(result.total_realized_pnl_usd, "30");
+        assert_eq!(result.total_unrealized_pnl_usd, "100");
+        assert_eq!(result.positions[0].average_cost_usd, "100");
+        assert_eq!(result.positions[0].unrealized_pnl_usd, Some("100".to_string()));
+        assert_eq!(result.positions[1].unrealized_pnl_usd, None);
+    }
+
+    #[test]
+    fn test_get_pnl_args_deserialization() {
+        let json = r#"{"address":"0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb","period":"7d"}"#;
+        let args: GetPnlArgs = serde_json::from_str(json).expect("应该能反序列化");
+        assert_eq!(args.period, Some("7d".to_string()));
+        assert_eq!(args.export, None);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::{
+    alchemy::AlchemyClient,
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    eth_client::EthClient,
+    logging::{info, warn},
+    token_registry::TokenRegistry,
+    tools::price::fetch_token_price_usd_at,
+    types::{checksum_address, parse_address, TokenInfo},
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// GetPortfolio 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetPortfolioArgs {
+    /// 钱包地址(必需)
+    pub address: String,
+    /// 是否计算 USD 价值(可选,默认 false,每个代币需要额外的价格查询)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_usd: Option<bool>,
+}
+
+/// 单个持仓
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PortfolioHolding {
+    pub token: TokenInfo,
+    pub balance: String,
+    pub formatted_balance: String,
+    /// USD 价值(未请求或没有 Uniswap V2 报价时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_usd: Option<String>,
+}
+
+/// GetPortfolio 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PortfolioResult {
+    pub address: String,
+    /// 非零余额,原生代币在前,其余按 USD 价值(未计算时按符号)排序
+    pub holdings: Vec<PortfolioHolding>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_value_usd: Option<String>,
+    /// 候选代币来源(token_registry / alchemy)
+    pub sources: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 查询钱包的全部代币持仓
+#[allow(clippy::too_many_arguments)]
+pub async fn get_portfolio(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    token_registry: &Arc<TokenRegistry>,
+    alchemy_client: &Arc<AlchemyClient>,
+    Parameters(args): Parameters<GetPortfolioArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_portfolio 请求");
+
+    let owner = parse_address(&args.address).map_err(|e| McpError::invalid_params(e, None))?;
+    let include_usd = args.include_usd.unwrap_or(false);
+    let native = TokenInfo::native(config.chain());
+
+    info!(address = ?owner, include_usd, "查询钱包持仓");
+
+    // 测试模式
+    if config.server.test_mode {
+        let holdings = vec![
+            PortfolioHolding {
+                token: native,
+                balance: "1500000000000000000".to_string(),
+                formatted_balance: "1.5".to_string(),
+                value_usd: include_usd.then(|| "3000".to_string()),
+            },
+            PortfolioHolding {
+                token: TokenInfo {
+                    symbol: "TEST".to_string(),
+                    name: "Test Token".to_string(),
+                    address: format!("{:?}", Address::repeat_byte(0x11)),
+                    decimals: 18,
+                    listed_on: Vec::new(),
+                },
+                balance: "100000000000000000000".to_string(),
+                formatted_balance: "100".to_string(),
+                value_usd: include_usd.then(|| "150".to_string()),
+            },
+        ];
+        let result = PortfolioResult {
+            address: checksum_address(owner),
+            total_value_usd: include_usd.then(|| "3150".to_string()),
+            holdings,
+            sources: vec!["test_mode".to_string()],
+            notes: Vec::new(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() || !erc20_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let eth_client = eth_client.clone();
+    let erc20_client = erc20_client.clone();
+    let uniswap_client = uniswap_client.clone();
+    let alchemy_client = alchemy_client.clone();
+    let registry_tokens = registry_candidates(&token_registry.all_tokens(), &native.symbol);
+
+    let result = async {
+        let mut sources = vec!["token_registry".to_string()];
+        let mut notes = Vec::new();
+
+        // 候选代币:注册表 + Alchemy 发现的代币(Alchemy 失败时只用注册表)
+        let mut candidates: BTreeMap<Address, Option<TokenInfo>> = registry_tokens
+            .into_iter()
+            .map(|(address, info)| (address, Some(info)))
+            .collect();
+        if alchemy_client.is_available() {
+            match alchemy_client.token_balances(owner).await {
+                Ok(balances) => {
+                    sources.push("alchemy".to_string());
+                    for (token, _) in balances {
+                        candidates.entry(token).or_insert(None);
+                    }
+                }
+                Err(e) => {
+                    warn!(error = %e, "Alchemy 查询代币余额失败");
+                    notes.push(format!("Alchemy 查询失败,仅包含注册表代币: {}", e));
+                }
+            }
+        } else {
+            notes.push("未配置 ALCHEMY_API_KEY,仅包含注册表代币".to_string());
+        }
+
+        // 原生代币余额,以及所有候选代币余额(一次 Multicall)
+        let native_balance = eth_client
+            .get_balance(&format!("{:?}", owner), None)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询原生代币余额失败: {}", e), None))?;
+        let tokens: Vec<Address> = candidates.keys().copied().collect();
+        let queries: Vec<(Address, Address)> = tokens.iter().map(|token| (*token, owner)).collect();
+        let balances = erc20_client
+            .balances_of(&queries)
+            .await
+            .map_err(|e| McpError::internal_error(format!("批量查询代币余额失败: {}", e), None))?;
+
+        let held: Vec<(Address, U256)> = tokens
+            .into_iter()
+            .zip(balances)
+            .filter_map(|(token, balance)| balance.filter(|b| !b.is_zero()).map(|b| (token, b)))
+            .collect();
+
+        // 补全 Alchemy 发现的代币元数据(不写入注册表,避免仿冒符号覆盖已知代币)
+        let unknown: Vec<Address> = held
+            .iter()
+            .filter(|(token, _)| candidates[token].is_none())
+            .map(|(token, _)| *token)
+            .collect();
+        if !unknown.is_empty() {
+            let infos = erc20_client
+                .tokens_info(&unknown)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
+            for (token, info) in unknown.into_iter().zip(infos) {
+                candidates.insert(token, Some(info));
+            }
+        }
+
+        let mut holdings = Vec::with_capacity(held.len() + 1);
+        if !native_balance.is_zero() {
+            holdings.push((native.clone(), native_balance, uniswap_client.weth_address()));
+        }
+        for (token, balance) in held {
+            if let Some(info) = candidates.remove(&token).flatten() {
+                holdings.push((info, balance, token));
+            }
+        }
+
+        let mut result = Vec::with_capacity(holdings.len());
+        for (token, balance, price_token) in holdings {
+            let value_usd = if include_usd {
+                fetch_token_price_usd_at(&uniswap_client, price_token, token.decimals, None)
+                    .await
+                    .ok()
+                    .and_then(|price| holding_value_usd(balance, token.decimals, price))
+            } else {
+                None
+            };
+            result.push(PortfolioHolding {
+                balance: balance.to_string(),
+                formatted_balance: format_units(balance, token.decimals),
+                token,
+                value_usd,
+            });
+        }
+
+        Ok::<_, McpError>((result, sources, notes))
+    }
+    .await;
+    let (mut holdings, sources, notes) = result?;
+
+    sort_holdings(&mut holdings);
+    let total_value_usd = include_usd.then(|| total_value(&holdings));
+
+    let result = PortfolioResult {
+        address: checksum_address(owner),
+        holdings,
+        total_value_usd,
+        sources,
+        notes,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(holdings = result.holdings.len(), "成功返回钱包持仓");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 注册表中的代币按地址去重,原生代币别名与包装代币共享地址,保留包装代币
+fn registry_candidates(tokens: &[TokenInfo], native_symbol: &str) -> BTreeMap<Address, TokenInfo> {
+    let mut candidates: BTreeMap<Address, TokenInfo> = BTreeMap::new();
+    for info in tokens {
+        let Ok(address) = info.address.parse::<Address>() else {
+            continue;
+        };
+        match candidates.get(&address) {
+            Some(existing) if existing.symbol != native_symbol => {}
+            _ => {
+                candidates.insert(address, info.clone());
+            }
+        }
+    }
+    candidates
+}
+
+/// 持仓 USD 价值 = 余额 × 单价
+fn holding_value_usd(balance: U256, decimals: u8, price_usd: Decimal) -> Option<String> {
+    let amount = Decimal::from_str(&format_units(balance, decimals)).ok()?;
+    Some(amount.checked_mul(price_usd)?.round_dp(2).normalize().to_string())
+}
+
+/// 原生代币在前,其余按 USD 价值降序,价值相同或未知时按符号排序
+fn sort_holdings(holdings: &mut [PortfolioHolding]) {
+    let value = |holding: &PortfolioHolding| {
+        holding
+            .value_usd
+            .as_deref()
+            .and_then(|v| Decimal::from_str(v).ok())
+            .unwrap_or(Decimal::ZERO)
+    };
+    holdings.sort_by(|a, b| {
+        b.token
+            .is_eth()
+            .cmp(&a.token.is_eth())
+            .then_with(|| value(b).cmp(&value(a)))
+            .then_with(|| a.token.symbol.cmp(&b.token.symbol))
+    });
+}
+
+/// 已知价值的持仓合计
+fn total_value(holdings: &[PortfolioHolding]) -> String {
+    holdings
+        .iter()
+        .filter_map(|holding| holding.value_usd.as_deref())
+        .filter_map(|value| Decimal::from_str(value).ok())
+        .sum::<Decimal>()
+        .normalize()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::MAINNET;
+
+    fn holding(symbol: &str, address: &str, value_usd: Option<&str>) -> PortfolioHolding {
+        PortfolioHolding {
+            token: TokenInfo {
+                symbol: symbol.to_string(),
+                name: symbol.to_string(),
+                address: address.to_string(),
+                decimals: 18,
+                listed_on: Vec::new(),
+            },
+            balance: "1".to_string(),
+            formatted_balance: "1".to_string(),
+            value_usd: value_usd.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_registry_candidates_dedupes_native_alias() {
+        let registry = TokenRegistry::new();
+        let candidates = registry_candidates(&registry.all_tokens(), "ETH");
+
+        let weth = MAINNET.wrapped_native_address();
+        assert_eq!(candidates[&weth].symbol, "WETH");
+        assert!(candidates.values().all(|info| info.symbol != "ETH"));
+    }
+
+    #[test]
+    fn test_holding_value_and_sorting() {
+        assert_eq!(
+            holding_value_usd(U256::from(1_500_000u64), 6, Decimal::from_str("2.5").unwrap()).as_deref(),
+            Some("3.75")
+        );
+
+        let zero = TokenInfo::eth().address;
+        let mut holdings = vec![
+            holding("AAA", "0x01", None),
+            holding("USDC", "0x02", Some("10")),
+            holding("ETH", &zero, Some("5")),
+            holding("UNI", "0x03", Some("20")),
+        ];
+        sort_holdings(&mut holdings);
+        let symbols: Vec<_> = holdings.iter().map(|h| h.token.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["ETH", "UNI", "USDC", "AAA"]);
+        assert_eq!(total_value(&holdings), "35");
+    }
+}
@@ -0,0 +1,318 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    logging::info,
+    token_registry::TokenRegistry,
+    tools::price::fetch_token_price_usd_at,
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// 默认允许的最大价格影响(基点,100 = 1%)
+const DEFAULT_MAX_PRICE_IMPACT_BPS: u32 = 100;
+
+/// SuggestPositionSize 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SuggestPositionSizeArgs {
+    /// 代币地址或符号(必需)
+    pub token: String,
+    /// 组合总价值(USD,必需)
+    pub portfolio_value: String,
+    /// 单笔交易愿意承担的风险占组合比例(百分比,必需,如 1 = 1%)
+    pub risk_pct: String,
+    /// 止损价格(USD,必需,需低于当前价格)
+    pub stop_price: String,
+    /// 允许的最大价格影响(基点,可选,默认 100 = 1%)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_price_impact_bps: Option<u32>,
+}
+
+/// SuggestPositionSize 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PositionSizeResult {
+    pub token: String,
+    pub current_price_usd: String,
+    pub stop_price_usd: String,
+    /// 单位代币触发止损时的亏损
+    pub risk_per_unit_usd: String,
+    /// 允许承担的最大亏损
+    pub risk_budget_usd: String,
+    /// 池子输入侧(WETH 或 USDC)的储备价值
+    pub pool_depth_usd: String,
+    /// 按风险预算计算的仓位
+    pub risk_limited_size_usd: String,
+    /// 按价格影响上限计算的仓位
+    pub impact_limited_size_usd: String,
+    pub suggested_size_usd: String,
+    pub suggested_quantity: String,
+    /// 起决定作用的约束(risk / price_impact / portfolio)
+    pub binding_constraint: String,
+    pub expected_price_impact: String,
+    pub max_loss_at_stop_usd: String,
+}
+
+/// 仓位计算的输入
+struct SizingInputs {
+    price: Decimal,
+    stop_price: Decimal,
+    portfolio_value: Decimal,
+    risk_pct: Decimal,
+    pool_depth_usd: Decimal,
+    max_price_impact_bps: u32,
+}
+
+/// 结合实时价格、池子深度和风险参数建议交易仓位
+pub async fn suggest_position_size(
+    config: &Arc<Config>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<SuggestPositionSizeArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 suggest_position_size 请求");
+
+    let parse_decimal = |name: &str, value: &str| {
+        Decimal::from_str(value)
+            .ok()
+            .filter(|d| d.is_sign_positive() && !d.is_zero())
+            .ok_or_else(|| McpError::invalid_params(format!("{} 参数无效: {}", name, value), None))
+    };
+
+    let portfolio_value = parse_decimal("portfolio_value", &args.portfolio_value)?;
+    let risk_pct = parse_decimal("risk_pct", &args.risk_pct)?;
+    let stop_price = parse_decimal("stop_price", &args.stop_price)?;
+    let max_price_impact_bps = args.max_price_impact_bps.unwrap_or(DEFAULT_MAX_PRICE_IMPACT_BPS);
+
+    if risk_pct > Decimal::from(100) {
+        return Err(McpError::invalid_params(
+            format!("风险比例无效: {}% (必须 ≤ 100%)", risk_pct),
+            None,
+        ));
+    }
+
+    if max_price_impact_bps == 0 || max_price_impact_bps > 10000 {
+        return Err(McpError::invalid_params(
+            format!(
+                "价格影响上限无效: {} bps (必须在 1-10000 之间)",
+                max_price_impact_bps
+            ),
+            None,
+        ));
+    }
+
+    info!(
+        token = %args.token,
+        portfolio_value = %portfolio_value,
+        risk_pct = %risk_pct,
+        stop_price = %stop_price,
+        max_price_impact_bps,
+        "计算建议仓位"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let inputs = SizingInputs {
+            price: Decimal::from(2000),
+            stop_price,
+            portfolio_value,
+            risk_pct,
+            pool_depth_usd: Decimal::from(10_000_000),
+            max_price_impact_bps,
+        };
+
+        let result = calculate_position_size(args.token.clone(), &inputs)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Uniswap 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    // 解析代币
+    let mut token_info = token_registry
+        .resolve(&args.token)
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+    let token_addr: Address = token_info.address.parse().map_err(|_| {
+        McpError::internal_error("无效的代币地址".to_string(), None)
+    })?;
+
+    // 🔍 动态查询未知代币信息
+    if token_info.symbol == "UNKNOWN" && erc20_client.is_available() {
+        let erc20_client_clone = erc20_client.clone();
+        let real_info = erc20_client_clone
+            .token_info(token_addr)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
+
+        // 缓存到注册表
+        token_registry.register(real_info.symbol.clone(), real_info.clone());
+        token_info = real_info;
+    }
+
+    let weth_addr = uniswap_client.weth_address();
+    let usdc_addr = uniswap_client.usdc_address();
+    let decimals = token_info.decimals;
+    let uniswap_client = uniswap_client.clone();
+
+    let (price, pool_depth_usd) = async {
+        let price = fetch_token_price_usd_at(&uniswap_client, token_addr, decimals, None).await?;
+
+        // 买入时的输入侧:普通代币用 WETH,WETH 本身用 USDC
+        let (input_token, input_decimals) = if token_addr == weth_addr {
+            (usdc_addr, 6u8)
+        } else {
+            (weth_addr, 18u8)
+        };
+
+        let pair = uniswap_client.pair_address(token_addr, input_token);
+        let reserves = uniswap_client
+            .get_reserves(pair)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询储备量失败: {}", e), None))?;
+        let input_reserve = if input_token < token_addr { reserves.0 } else { reserves.1 };
+
+        let input_price = if input_token == weth_addr {
+            fetch_token_price_usd_at(&uniswap_client, weth_addr, 18, None).await?
+        } else {
+            Decimal::ONE
+        };
+
+        let input_reserve = Decimal::from_str(&format_units(input_reserve, input_decimals))
+            .map_err(|e| McpError::internal_error(format!("储备量无法表示: {}", e), None))?;
+
+        Ok::<_, McpError>((price, input_reserve * input_price))
+    }
+    .await?;
+
+    let inputs = SizingInputs {
+        price,
+        stop_price,
+        portfolio_value,
+        risk_pct,
+        pool_depth_usd,
+        max_price_impact_bps,
+    };
+
+    let result = calculate_position_size(token_info.symbol.clone(), &inputs)
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!("成功返回建议仓位");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 计算建议仓位:取风险预算、价格影响上限和组合价值三者中的最小值
+/// 价格影响按 输入量 / 输入侧储备 近似估算(不含手续费,按单个池子计算)
+fn calculate_position_size(token: String, inputs: &SizingInputs) -> Result<PositionSizeResult, String> {
+    if inputs.stop_price >= inputs.price {
+        return Err(format!(
+            "止损价 {} 必须低于当前价格 {}",
+            inputs.stop_price,
+            inputs.price.round_dp(6)
+        ));
+    }
+
+    let hundred = Decimal::from(100);
+    let risk_per_unit = inputs.price - inputs.stop_price;
+    let risk_budget = inputs.portfolio_value * inputs.risk_pct / hundred;
+
+    let risk_limited = risk_budget / risk_per_unit * inputs.price;
+    let impact_limited =
+        inputs.pool_depth_usd * Decimal::from(inputs.max_price_impact_bps) / Decimal::from(10000);
+
+    let (suggested, binding) = [
+        (risk_limited, "risk"),
+        (impact_limited, "price_impact"),
+        (inputs.portfolio_value, "portfolio"),
+    ]
+    .into_iter()
+    .min_by(|a, b| a.0.cmp(&b.0))
+    .expect("候选仓位不为空");
+
+    let quantity = suggested / inputs.price;
+    let expected_impact = if inputs.pool_depth_usd.is_zero() {
+        Decimal::ZERO
+    } else {
+        suggested / inputs.pool_depth_usd * hundred
+    };
+
+    let usd = |d: Decimal| d.round_dp(2).normalize().to_string();
+
+    Ok(PositionSizeResult {
+        token,
+        current_price_usd: inputs.price.round_dp(6).normalize().to_string(),
+        stop_price_usd: inputs.stop_price.normalize().to_string(),
+        risk_per_unit_usd: risk_per_unit.round_dp(6).normalize().to_string(),
+        risk_budget_usd: usd(risk_budget),
+        pool_depth_usd: usd(inputs.pool_depth_usd),
+        risk_limited_size_usd: usd(risk_limited),
+        impact_limited_size_usd: usd(impact_limited),
+        suggested_size_usd: usd(suggested),
+        suggested_quantity: quantity.round_dp(6).normalize().to_string(),
+        binding_constraint: binding.to_string(),
+        expected_price_impact: format!("{:.2}%", expected_impact),
+        max_loss_at_stop_usd: usd(quantity * risk_per_unit),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(portfolio: i64, pool_depth: i64) -> SizingInputs {
+        SizingInputs {
+            price: Decimal::from(100),
+            stop_price: Decimal::from(90),
+            portfolio_value: Decimal::from(portfolio),
+            risk_pct: Decimal::ONE,
+            pool_depth_usd: Decimal::from(pool_depth),
+            max_price_impact_bps: 100,
+        }
+    }
+
+    #[test]
+    fn test_risk_limited_size() {
+        // 风险预算 1000,单位风险 10 → 100 个 × 100 = 10000
+        let result = calculate_position_size("TKN".to_string(), &inputs(100_000, 10_000_000)).unwrap();
+        assert_eq!(result.binding_constraint, "risk");
+        assert_eq!(result.suggested_size_usd, "10000");
+        assert_eq!(result.suggested_quantity, "100");
+        assert_eq!(result.max_loss_at_stop_usd, "1000");
+        assert_eq!(result.expected_price_impact, "0.10%");
+    }
+
+    #[test]
+    fn test_impact_limited_size() {
+        // 池子深度 200000,1% 上限 → 2000
+        let result = calculate_position_size("TKN".to_string(), &inputs(100_000, 200_000)).unwrap();
+        assert_eq!(result.binding_constraint, "price_impact");
+        assert_eq!(result.suggested_size_usd, "2000");
+        assert_eq!(result.expected_price_impact, "1.00%");
+    }
+
+    #[test]
+    fn test_stop_above_price_rejected() {
+        let mut bad = inputs(100_000, 200_000);
+        bad.stop_price = Decimal::from(120);
+        assert!(calculate_position_size("TKN".to_string(), &bad).is_err());
+    }
+}
@@ -0,0 +1,420 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, parse_units, Erc20Client, TRANSFER_EVENT_TOPIC},
+    eth_client::EthClient,
+    logging::info,
+    token_registry::TokenRegistry,
+    types::{checksum_address, parse_address, TxType},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// WETH Deposit(address indexed dst, uint256 wad) 事件签名
+const WETH_DEPOSIT_TOPIC: &str =
+    "0xe1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c";
+
+/// WETH Withdrawal(address indexed src, uint256 wad) 事件签名
+const WETH_WITHDRAWAL_TOPIC: &str =
+    "0x7fcf532c15f0a6db0bd6d0e038bea71d30d808c7d98cb3bf7268a95bf5081b65";
+
+/// PreviewTransaction 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PreviewTransactionArgs {
+    /// 目标合约或接收地址(必需)
+    pub to: String,
+    /// 调用数据(可选,0x 开头的十六进制)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// 发送的 ETH 数量(可选,如 "0.1",默认 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// 发送方地址(可选,默认使用配置的模拟地址)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+}
+
+/// PreviewTransaction 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PreviewTransactionResult {
+    pub from: String,
+    pub to: String,
+    /// 交易是否会回滚(回滚时没有余额变化)
+    pub reverted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    pub gas_used: String,
+    /// 各地址的余额变化(ETH 变化不含 Gas 费用)
+    pub deltas: Vec<BalanceDeltaRow>,
+    /// 余额变化表(Markdown)
+    pub table: String,
+}
+
+/// 单个地址在单个资产上的余额变化
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BalanceDeltaRow {
+    pub address: String,
+    /// sender / recipient(其他地址为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub token: String,
+    /// 代币合约地址(ETH 为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_address: Option<String>,
+    /// 带符号的变化量(已格式化,如 +1.5 / -3000)
+    pub delta: String,
+}
+
+/// (持有地址, 代币地址) -> 变化量，代币地址为 None 表示 ETH
+pub(crate) type DeltaMap = BTreeMap<(Address, Option<Address>), I256>;
+
+/// 预览交易的余额变化
+pub async fn preview_transaction(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<PreviewTransactionArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 preview_transaction 请求");
+
+    let (from, to, data, value) = parse_call(
+        config,
+        &args.to,
+        args.data.as_deref(),
+        args.value.as_deref(),
+        args.from.as_deref(),
+    )?;
+
+    info!(
+        from = %format!("{:?}", from),
+        to = %args.to,
+        data_len = data.len(),
+        value = %value,
+        "预览交易余额变化"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let mut deltas = DeltaMap::new();
+        let amount = I256::from_raw(value.max(U256::exp10(18)));
+        *deltas.entry((from, None)).or_default() -= amount;
+        *deltas.entry((to, None)).or_default() += amount;
+
+        let rows = build_rows(&deltas, from, to, &BTreeMap::new());
+        let result = PreviewTransactionResult {
+            from: checksum_address(from),
+            to: checksum_address(to),
+            reverted: false,
+            revert_reason: None,
+            gas_used: "21000".to_string(),
+            table: render_table(&rows),
+            deltas: rows,
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let eth_client = eth_client.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+    let weth = config.chain().wrapped_native_address();
+
+    let result = async {
+        let mut tx = TxType::Eip1559.new_request();
+        tx.set_from(from).set_to(to).set_data(data).set_value(value);
+
+        let frame = eth_client.trace_call(&tx, None, None).await.map_err(|e| {
+            McpError::internal_error(format!("跟踪交易失败(需要节点支持 debug_traceCall): {}", e), None)
+        })?;
+
+        let deltas = if frame.error.is_some() {
+            DeltaMap::new()
+        } else {
+            let mut deltas = DeltaMap::new();
+            collect_deltas(&frame, weth, &mut deltas);
+            deltas.retain(|_, delta| !delta.is_zero());
+            deltas
+        };
+
+        let tokens = token_metadata(&erc20_client, &token_registry, &deltas).await;
+
+        let rows = build_rows(&deltas, from, to, &tokens);
+
+        Ok::<_, McpError>(PreviewTransactionResult {
+            from: checksum_address(from),
+            to: checksum_address(to),
+            reverted: frame.error.is_some(),
+            revert_reason: frame.error.clone(),
+            gas_used: frame.gas_used.to_string(),
+            table: render_table(&rows),
+            deltas: rows,
+        })
+    }
+    .await?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(deltas = result.deltas.len(), reverted = result.reverted, "成功返回交易预览");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 解析交易参数:(from, to, data, value)
+/// 未指定发送方时使用配置的模拟地址
+pub(crate) fn parse_call(
+    config: &Config,
+    to: &str,
+    data: Option<&str>,
+    value: Option<&str>,
+    from: Option<&str>,
+) -> Result<(Address, Address, Bytes, U256), McpError> {
+    let to = parse_address(to).map_err(|e| McpError::invalid_params(e, None))?;
+
+    let from: Address = match from {
+        Some(addr) => parse_address(addr)
+            .map_err(|e| McpError::invalid_params(e, None))?,
+        None => config.get_simulation_address(),
+    };
+
+    let data = match data {
+        Some(hex) => hex
+            .parse::<Bytes>()
+            .map_err(|_| McpError::invalid_params(format!("无效的调用数据: {}", hex), None))?,
+        None => Bytes::new(),
+    };
+
+    let value = match value {
+        Some(value) => parse_units(value, 18)
+            .map_err(|e| McpError::invalid_params(format!("解析 ETH 数量失败: {}", e), None))?,
+        None => U256::zero(),
+    };
+
+    Ok((from, to, data, value))
+}
+
+/// 查询余额变化中涉及代币的元数据(symbol, decimals)
+/// 注册表中没有的代币通过链上查询并注册，查询失败按 UNKNOWN 和原始数量显示
+pub(crate) async fn token_metadata(
+    erc20_client: &Erc20Client,
+    token_registry: &TokenRegistry,
+    deltas: &DeltaMap,
+) -> BTreeMap<Address, (String, u8)> {
+    let mut tokens = BTreeMap::new();
+    for token in deltas.keys().filter_map(|(_, token)| *token) {
+        if tokens.contains_key(&token) {
+            continue;
+        }
+        let info = match token_registry.resolve(&format!("{:?}", token)) {
+            Ok(info) if info.symbol != "UNKNOWN" => Some(info),
+            _ => erc20_client.token_info(token).await.ok().inspect(|info| {
+                token_registry.register(info.symbol.clone(), info.clone());
+            }),
+        };
+        tokens.insert(
+            token,
+            info.map(|i| (i.symbol, i.decimals)).unwrap_or(("UNKNOWN".to_string(), 0)),
+        );
+    }
+    tokens
+}
+
+/// 递归累计调用帧中的 ETH 转账和代币事件
+/// 出错(回滚)的调用帧及其子调用不产生任何状态变化，直接跳过
+/// `weth` 为当前链的包装原生代币，用于识别 Deposit/Withdrawal 事件
+pub(crate) fn collect_deltas(frame: &CallFrame, weth: Address, deltas: &mut DeltaMap) {
+    if frame.error.is_some() {
+        return;
+    }
+
+    // DELEGATECALL / STATICCALL 不转移 ETH
+    let transfers_value = matches!(
+        frame.typ.as_str(),
+        "CALL" | "CREATE" | "CREATE2" | "SELFDESTRUCT"
+    );
+    if transfers_value
+        && let Some(value) = frame.value.filter(|v| !v.is_zero())
+        && let Some(NameOrAddress::Address(to)) = frame.to
+    {
+        let value = I256::from_raw(value);
+        *deltas.entry((frame.from, None)).or_default() -= value;
+        *deltas.entry((to, None)).or_default() += value;
+    }
+
+    for log in frame.logs.iter().flatten() {
+        apply_log(log, weth, deltas);
+    }
+
+    for call in frame.calls.iter().flatten() {
+        collect_deltas(call, weth, deltas);
+    }
+}
+
+/// 解析 ERC20 Transfer 和 WETH Deposit/Withdrawal 事件
+fn apply_log(log: &CallLogFrame, weth: Address, deltas: &mut DeltaMap) {
+    let (Some(token), Some(topics), Some(data)) = (log.address, log.topics.as_ref(), log.data.as_ref())
+    else {
+        return;
+    };
+    // ERC721 Transfer 的 tokenId 是 indexed，data 为空
+    if topics.is_empty() || data.len() != 32 {
+        return;
+    }
+
+    let amount = I256::from_raw(U256::from_big_endian(data));
+    let topic_address = |topic: &H256| Address::from_slice(&topic.as_bytes()[12..]);
+    let topic0 = format!("{:?}", topics[0]);
+
+    if topic0 == TRANSFER_EVENT_TOPIC && topics.len() == 3 {
+        *deltas.entry((topic_address(&topics[1]), Some(token))).or_default() -= amount;
+        *deltas.entry((topic_address(&topics[2]), Some(token))).or_default() += amount;
+    } else if token == weth && topics.len() == 2 && topic0 == WETH_DEPOSIT_TOPIC {
+        *deltas.entry((topic_address(&topics[1]), Some(token))).or_default() += amount;
+    } else if token == weth && topics.len() == 2 && topic0 == WETH_WITHDRAWAL_TOPIC {
+        *deltas.entry((topic_address(&topics[1]), Some(token))).or_default() -= amount;
+    }
+}
+
+/// 构建余额变化行：发送方优先，其次接收方，其余按地址排序
+pub(crate) fn build_rows(
+    deltas: &DeltaMap,
+    from: Address,
+    to: Address,
+    tokens: &BTreeMap<Address, (String, u8)>,
+) -> Vec<BalanceDeltaRow> {
+    let mut entries: Vec<_> = deltas.iter().collect();
+    entries.sort_by_key(|((address, token), _)| (*address != from, *address != to, *address, *token));
+
+    entries
+        .into_iter()
+        .map(|((address, token), delta)| {
+            let (symbol, decimals) = match token {
+                None => ("ETH".to_string(), 18),
+                Some(token) => tokens.get(token).cloned().unwrap_or(("UNKNOWN".to_string(), 0)),
+            };
+            let sign = if delta.is_negative() { "-" } else { "+" };
+
+            BalanceDeltaRow {
+                address: checksum_address(*address),
+                label: if *address == from {
+                    Some("sender".to_string())
+                } else if *address == to {
+                    Some("recipient".to_string())
+                } else {
+                    None
+                },
+                token: symbol,
+                token_address: token.map(checksum_address),
+                delta: format!("{}{}", sign, format_units(delta.unsigned_abs(), decimals)),
+            }
+        })
+        .collect()
+}
+
+/// 渲染 Markdown 余额变化表
+pub(crate) fn render_table(rows: &[BalanceDeltaRow]) -> String {
+    let mut table = String::from("| 地址 | 代币 | 变化 |\n|---|---|---|\n");
+    for row in rows {
+        let address = match row.label {
+            Some(ref label) => format!("{} ({})", row.address, label),
+            None => row.address.clone(),
+        };
+        table.push_str(&format!("| {} | {} | {} |\n", address, row.token, row.delta));
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer_log(token: Address, from: Address, to: Address, amount: u64) -> CallLogFrame {
+        let topic = |addr: Address| H256::from(addr);
+        let mut data = [0u8; 32];
+        U256::from(amount).to_big_endian(&mut data);
+        CallLogFrame {
+            address: Some(token),
+            topics: Some(vec![TRANSFER_EVENT_TOPIC.parse().unwrap(), topic(from), topic(to)]),
+            data: Some(Bytes::from(data.to_vec())),
+        }
+    }
+
+    fn call(from: Address, to: Address, value: u64) -> CallFrame {
+        CallFrame {
+            typ: "CALL".to_string(),
+            from,
+            to: Some(NameOrAddress::Address(to)),
+            value: Some(U256::from(value)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_collect_deltas_skips_reverted_frames() {
+        let user = Address::repeat_byte(0x01);
+        let router = Address::repeat_byte(0x02);
+        let pair = Address::repeat_byte(0x03);
+        let usdc = Address::repeat_byte(0xaa);
+
+        let mut swap = call(router, pair, 0);
+        swap.logs = Some(vec![transfer_log(usdc, pair, user, 3000)]);
+
+        let mut reverted = call(router, pair, 5);
+        reverted.error = Some("execution reverted".to_string());
+        reverted.logs = Some(vec![transfer_log(usdc, pair, user, 999)]);
+
+        let mut root = call(user, router, 100);
+        root.calls = Some(vec![swap, reverted]);
+
+        let mut deltas = DeltaMap::new();
+        collect_deltas(&root, Address::repeat_byte(0xee), &mut deltas);
+
+        assert_eq!(deltas[&(user, None)], I256::from(-100));
+        assert_eq!(deltas[&(router, None)], I256::from(100));
+        assert_eq!(deltas[&(user, Some(usdc))], I256::from(3000));
+        assert_eq!(deltas[&(pair, Some(usdc))], I256::from(-3000));
+        assert!(!deltas.contains_key(&(pair, None)));
+    }
+
+    #[test]
+    fn test_build_rows_signed_table() {
+        let user = Address::repeat_byte(0x01);
+        let router = Address::repeat_byte(0x02);
+        let usdc = Address::repeat_byte(0xaa);
+
+        let mut deltas = DeltaMap::new();
+        deltas.insert((router, Some(usdc)), I256::from(-2_500_000));
+        deltas.insert((user, None), I256::from_raw(U256::exp10(18)).wrapping_neg());
+        deltas.insert((user, Some(usdc)), I256::from(2_500_000));
+
+        let tokens = BTreeMap::from([(usdc, ("USDC".to_string(), 6))]);
+        let rows = build_rows(&deltas, user, router, &tokens);
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].label.as_deref(), Some("sender"));
+        assert_eq!(rows[0].token, "ETH");
+        assert_eq!(rows[0].delta, "-1");
+        assert_eq!(rows[1].delta, "+2.5");
+        assert_eq!(rows[2].label.as_deref(), Some("recipient"));
+        assert_eq!(rows[2].delta, "-2.5");
+
+        let table = render_table(&rows);
+        assert!(table.contains("| USDC | +2.5 |"));
+        assert_eq!(table.lines().count(), 5);
+    }
+}
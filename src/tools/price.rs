@@ -1,14 +1,21 @@
 use crate::{
+    coingecko::CoinGeckoClient,
     config::Config,
+    defillama::{deviation_percent, DefiLlamaClient, UNISWAP_V2_PROTOCOL},
     erc20::{format_units, Erc20Client},
-    logging::info,
+    eth_client::EthClient,
+    logging::{info, warn},
+    snapshot::{MarketSnapshot, SnapshotStore},
+    store::{NewRecord, RecordKind, Store},
+    subgraph::{PoolActivity, SubgraphClient},
+    token_lists::TokenListClient,
     token_registry::TokenRegistry,
     types::TokenInfo,
-    uniswap::UniswapV2Client,
+    uniswap::{UniswapError, UniswapV2Client},
 };
 use ethers::prelude::*;
 use rmcp::{
-    handler::server::wrapper::Parameters, model::*, schemars, tool, ErrorData as McpError,
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
 };
 use rust_decimal::Decimal;
 use std::str::FromStr;
@@ -22,6 +29,15 @@ pub struct GetTokenPriceArgs {
     /// 报价货币(USD/ETH,默认 USD)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quote_currency: Option<String>,
+    /// 确认深度(可选,latest/confirmed/finalized,FINALITY_BLOCKS > 0 时默认 confirmed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finality: Option<String>,
+    /// 历史区块号(可选,读取该区块的储备量计算历史价格,需要归档节点,不能与 finality 同时指定)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// 跳过储备量缓存,强制从链上读取(可选,默认 false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_refresh: Option<bool>,
 }
 
 /// GetTokenPrice 工具的返回结果
@@ -32,21 +48,87 @@ pub struct TokenPriceResult {
     pub quote_currency: String,
     pub source: String,
     pub liquidity: Option<String>,
+    /// 以 USD 计的总流动性(基于 ETH/USD 报价换算)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub liquidity_usd: Option<String>,
+    /// 池子两侧的储备量(已按 decimals 格式化)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reserves: Option<PoolReserves>,
+    /// 读取储备量的区块号(读取最新区块时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Token/WETH 池子最近 24 小时的交易量、手续费和交易笔数(配置 V2 子图时返回)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_activity: Option<PoolActivity>,
+    /// 与 DefiLlama 聚合市场价格的交叉验证结果(启用 DEFILLAMA_ENABLED 且价格来自 Uniswap 时返回)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub market_check: Option<MarketPriceCheck>,
+    /// Uniswap V2 协议在所有链上的 TVL(USD,来自 DefiLlama)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_tvl_usd: Option<String>,
+}
+
+/// 链上价格与聚合市场价格的对比
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MarketPriceCheck {
+    pub source: String,
+    /// 以 quote_currency 计的市场价格
+    pub market_price: String,
+    /// 链上价格相对市场价格的偏离百分比(正数表示链上价格更高)
+    pub deviation_percent: String,
+    /// 偏离是否超过 PRICE_DEVIATION_ALERT_BPS
+    pub deviates: bool,
+    /// DefiLlama 对市场价格的置信度(0-1)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// 交易对两侧的储备量
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct PoolReserves {
+    /// 目标代币储备量
+    pub token_reserve: String,
+    /// WETH 储备量
+    pub weth_reserve: String,
 }
 
 /// 获取代币价格(支持 USD 和 ETH 报价)
-#[tool(description = "获取代币在 Uniswap V2 上的价格(支持 USD 和 ETH 报价)")]
-pub fn get_token_price(
+#[allow(clippy::too_many_arguments)]
+pub async fn get_token_price(
     config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
     uniswap_client: &Arc<UniswapV2Client>,
     erc20_client: &Arc<Erc20Client>,
     token_registry: &Arc<TokenRegistry>,
+    store: &Arc<Store>,
+    snapshots: &Arc<SnapshotStore>,
+    token_lists: &Arc<TokenListClient>,
+    coingecko_client: &Arc<CoinGeckoClient>,
+    subgraph_client: &Arc<SubgraphClient>,
+    defillama_client: &Arc<DefiLlamaClient>,
     Parameters(args): Parameters<GetTokenPriceArgs>,
 ) -> Result<CallToolResult, McpError> {
     info!("收到 get_token_price 请求");
 
     let quote_currency = args.quote_currency.unwrap_or_else(|| "USD".to_string());
-    info!(token = %args.token, quote = %quote_currency, "查询代币价格");
+    if args.block_number.is_some() && args.finality.is_some() {
+        return Err(McpError::invalid_params(
+            "block_number 与 finality 不能同时指定",
+            None,
+        ));
+    }
+    let finality = config
+        .read_finality(args.finality.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+    info!(
+        token = %args.token,
+        quote = %quote_currency,
+        finality = ?finality,
+        block_number = ?args.block_number,
+        "查询代币价格"
+    );
 
     // 测试模式
     if config.server.test_mode {
@@ -55,6 +137,7 @@ pub fn get_token_price(
             name: "Test Token".to_string(),
             address: args.token.clone(),
             decimals: 18,
+            listed_on: Vec::new(),
         };
 
         let result = TokenPriceResult {
@@ -63,6 +146,29 @@ pub fn get_token_price(
             quote_currency,
             source: "Test Mode".to_string(),
             liquidity: Some("1000000.0".to_string()),
+            liquidity_usd: Some("2000000000.0".to_string()),
+            reserves: Some(PoolReserves {
+                token_reserve: "500000.0".to_string(),
+                weth_reserve: "500000.0".to_string(),
+            }),
+            block_number: args.block_number,
+            pool_activity: subgraph_client.has_v2().then(|| PoolActivity {
+                volume_24h_usd: "1250000".to_string(),
+                fees_24h_usd: "3750".to_string(),
+                tx_count_24h: 420,
+                tvl_usd: Some("2000000000".to_string()),
+                active_hours: 24,
+                source: "Test Mode".to_string(),
+            }),
+            market_check: config.trading.defillama_enabled.then(|| MarketPriceCheck {
+                source: "Test Mode".to_string(),
+                market_price: "1998.5".to_string(),
+                deviation_percent: "0.08".to_string(),
+                deviates: false,
+                confidence: Some("0.99".to_string()),
+                warning: None,
+            }),
+            protocol_tvl_usd: config.trading.defillama_enabled.then(|| "1500000000".to_string()),
         };
 
         let json_str = serde_json::to_string_pretty(&result)
@@ -71,6 +177,27 @@ pub fn get_token_price(
         return Ok(CallToolResult::success(vec![Content::text(json_str)]));
     }
 
+    // 离线模式:基于快照储备量计算价格,不访问 RPC
+    if let Some(snapshot) = snapshots.current() {
+        if let Some(block_number) = args.block_number.filter(|block| *block != snapshot.block_number) {
+            return Err(McpError::invalid_params(
+                format!(
+                    "离线模式只能查询快照区块 {} 的价格,不能查询区块 {}",
+                    snapshot.block_number, block_number
+                ),
+                None,
+            ));
+        }
+        let result = offline_price(&snapshot, token_registry, uniswap_client, &args.token, &quote_currency)?;
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        info!(block = snapshot.block_number, "成功返回离线价格");
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
     // 真实模式:需要检查客户端可用性
     if !uniswap_client.is_available() {
         return Err(McpError::internal_error(
@@ -82,9 +209,7 @@ pub fn get_token_price(
     // 解析代币
     let mut token_info = token_registry
         .resolve(&args.token)
-        .ok_or_else(|| {
-            McpError::invalid_params(format!("未知的代币: {}", args.token), None)
-        })?;
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
     let token_addr: Address = token_info.address.parse().map_err(|_| {
         McpError::internal_error("无效的代币地址".to_string(), None)
@@ -93,49 +218,205 @@ pub fn get_token_price(
     // 🔍 动态查询未知代币信息
     if token_info.symbol == "UNKNOWN" && erc20_client.is_available() {
         let erc20_client_clone = erc20_client.clone();
-        let real_info = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                erc20_client_clone.token_info(token_addr).await
-            })
-        })
-        .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
+        let real_info = erc20_client_clone
+            .token_info(token_addr)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
 
         // 缓存到注册表
         token_registry.register(real_info.symbol.clone(), real_info.clone());
         token_info = real_info;
     }
 
-    // WETH 地址
-    let weth_addr: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
-        .parse()
-        .unwrap();
+    // 当前链的包装原生代币地址
+    let weth_addr = uniswap_client.weth_address();
 
-    let uniswap_client = uniswap_client.clone();
+    // force_refresh 时跳过储备量缓存(刷新结果仍写回缓存)
+    let uniswap_client = if args.force_refresh.unwrap_or(false) {
+        Arc::new(uniswap_client.bypassing_cache())
+    } else {
+        uniswap_client.clone()
+    };
+    let eth_client = eth_client.clone();
+    let record_enabled = store.is_enabled();
+    let finality_blocks = config.ethereum.finality_blocks;
+    let historical_block = args.block_number;
 
     // 查询 Token/WETH 池子
-    let (pair, reserves) = tokio::task::block_in_place(|| {
-        tokio::runtime::Handle::current().block_on(async {
-            let pair = uniswap_client
-                .get_pair(token_addr, weth_addr)
+    let (pair, reserves, read_block, block_number) = async {
+        // 指定历史区块时直接读取该区块,否则按确认深度确定读取区块
+        let read_block = match historical_block {
+            Some(block) => Some(block),
+            None => eth_client
+                .resolve_read_block(finality, finality_blocks)
                 .await
-                .map_err(|e| McpError::internal_error(format!("查询交易对失败: {}", e), None))?;
+                .map_err(|e| McpError::internal_error(format!("确定读取区块失败: {}", e), None))?,
+        };
 
-            let reserves = uniswap_client
-                .get_reserves(pair)
-                .await
-                .map_err(|e| McpError::internal_error(format!("查询储备量失败: {}", e), None))?;
+        let pair = uniswap_client.pair_address(token_addr, weth_addr);
 
-            Ok::<_, McpError>((pair, reserves))
-        })
-    })?;
+        // 没有交易对或池子为空时返回 None,改用 CoinGecko 报价
+        let reserves = match uniswap_client.get_reserves_at(pair, read_block.map(BlockId::from)).await {
+            Ok(reserves) => Some(reserves),
+            Err(UniswapError::PairNotFound | UniswapError::InsufficientLiquidity) => None,
+            Err(e) => {
+                let message = match historical_block {
+                    Some(block) => format!("查询区块 {} 的储备量失败(历史状态需要归档节点): {}", block, e),
+                    None => format!("查询储备量失败: {}", e),
+                };
+                return Err(McpError::internal_error(message, None));
+            }
+        };
 
-    // 确定储备量顺序(token0 < token1)
-    let (token_reserve, weth_reserve) = if token_addr < weth_addr {
-        (reserves.0, reserves.1)
-    } else {
-        (reserves.1, reserves.0)
+        // 仅在启用持久化时记录报价所在区块
+        let block_number = match read_block {
+            Some(block) => Some(block),
+            None if record_enabled => eth_client.get_block_number().await.ok(),
+            None => None,
+        };
+
+        Ok::<_, McpError>((pair, reserves, read_block, block_number))
+    }
+    .await?;
+
+    let chain_id = config.ethereum.chain_id;
+    let min_liquidity_usd = config.trading.price_fallback_min_liquidity_usd;
+
+    // CoinGecko 只提供当前价格,历史报价不回退
+    if reserves.is_none()
+        && let Some(block) = historical_block
+    {
+        return Err(McpError::invalid_params(
+            format!("区块 {} 时没有有流动性的 Uniswap V2 交易对", block),
+            None,
+        ));
+    }
+
+    let result = match reserves {
+        None => {
+            let (price, listed_on) = tokio::join!(
+                coingecko_client.token_price(token_addr, fallback_currency(&quote_currency)),
+                token_lists.listed_on(chain_id, token_addr)
+            );
+            let price = price.map_err(|e| {
+                McpError::internal_error(
+                    format!("未找到有流动性的 Uniswap V2 交易对,CoinGecko 回退也失败: {}", e),
+                    None,
+                )
+            })?;
+            token_info.listed_on = listed_on;
+            coingecko_price_result(token_info, price, &quote_currency, "no Uniswap V2 pair", None)
+        }
+        Some(reserves) => {
+            // 确定储备量顺序(token0 < token1)
+            let (token_reserve, weth_reserve) = if token_addr < weth_addr {
+                (reserves.0, reserves.1)
+            } else {
+                (reserves.1, reserves.0)
+            };
+
+            // 查询 WETH/USDC 价格(用于 USD 报价和 USD 流动性换算),同时查询代币列表收录情况和子图活跃度
+            let (eth_price_usd, listed_on, pool_activity) = tokio::join!(
+                fetch_eth_price_usd_at(&uniswap_client, weth_addr, read_block.map(BlockId::from)),
+                token_lists.listed_on(chain_id, token_addr),
+                fetch_pool_activity(subgraph_client, pair, historical_block)
+            );
+            token_info.listed_on = listed_on;
+
+            let mut result = build_price_result(
+                token_info,
+                (token_reserve, weth_reserve),
+                eth_price_usd,
+                &quote_currency,
+                format!("Uniswap V2 (Pair: {:?})", pair),
+                read_block,
+            )?;
+            result.pool_activity = pool_activity;
+
+            // 池子流动性过低时价格容易被操纵,优先使用 CoinGecko 报价(回退失败时仍返回池子价格)
+            // 历史报价不回退到 CoinGecko 当前价格
+            match low_liquidity_usd(&result, min_liquidity_usd)
+                .filter(|_| historical_block.is_none() && coingecko_client.is_available())
+            {
+                Some(liquidity_usd) => {
+                    match coingecko_client
+                        .token_price(token_addr, fallback_currency(&quote_currency))
+                        .await
+                    {
+                        Ok(price) => {
+                            let reason = format!(
+                                "Uniswap V2 liquidity ${} below ${}",
+                                liquidity_usd.round_dp(2),
+                                min_liquidity_usd
+                            );
+                            let token_info = result.token.clone();
+                            coingecko_price_result(token_info, price, &quote_currency, &reason, Some(result))
+                        }
+                        Err(e) => {
+                            warn!(error = %e, "流动性不足,CoinGecko 回退失败,返回 Uniswap 价格");
+                            result
+                        }
+                    }
+                }
+                None => result,
+            }
+        }
     };
 
+    // DefiLlama 只有当前价格,历史报价不做交叉验证
+    let mut result = result;
+    if config.trading.defillama_enabled && historical_block.is_none() && defillama_client.is_available() {
+        let alert_bps = config.trading.price_deviation_alert_bps;
+        let (market_check, protocol_tvl) = tokio::join!(
+            async {
+                // CoinGecko 回退价格不是链上价格,不需要交叉验证
+                if !result.source.starts_with("Uniswap V2") {
+                    return None;
+                }
+                cross_check_price(defillama_client, token_addr, weth_addr, &result, alert_bps).await
+            },
+            defillama_client.protocol_tvl(UNISWAP_V2_PROTOCOL)
+        );
+        if let Some(warning) = market_check.as_ref().and_then(|check| check.warning.as_ref()) {
+            warn!(token = %result.token.symbol, "{}", warning);
+        }
+        result.market_check = market_check;
+        result.protocol_tvl_usd = protocol_tvl
+            .inspect_err(|e| warn!(error = %e, "查询 Uniswap V2 TVL 失败"))
+            .ok()
+            .map(|tvl| tvl.round_dp(0).to_string());
+    }
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    // 💾 持久化报价记录
+    store.record(NewRecord {
+        kind: RecordKind::Quote,
+        tool: "get_token_price".to_string(),
+        block_number,
+        from_token: result.token.address.clone(),
+        to_token: result.quote_currency.clone(),
+        amount_in: "1".to_string(),
+        amount_out: Some(result.price.clone()),
+        details: serde_json::to_value(&result).unwrap_or_default(),
+    });
+
+    info!("成功返回价格");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 根据 Token/WETH 储备量和 ETH/USD 价格构建价格结果
+/// ETH 报价模式下 `eth_price_usd` 失败不影响主结果，仅缺少 USD 流动性
+fn build_price_result(
+    token_info: TokenInfo,
+    (token_reserve, weth_reserve): (U256, U256),
+    eth_price_usd: Result<String, McpError>,
+    quote_currency: &str,
+    source: String,
+    block_number: Option<u64>,
+) -> Result<TokenPriceResult, McpError> {
     // 🎯 使用 U256 精确计算价格，避免溢出
     let token_decimals = token_info.decimals;
     let weth_decimals = 18u8;
@@ -152,71 +433,296 @@ pub fn get_token_price(
     let (final_price, final_quote) = if quote_currency.to_uppercase() == "ETH" {
         (price_in_eth_str, "ETH".to_string())
     } else {
-        // 查询 WETH/USDC 价格来转换成 USD
-        let usdc_addr: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"
-            .parse()
-            .unwrap();
-
-        let eth_price_usd_str = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                let usdc_pair = uniswap_client
-                    .get_pair(weth_addr, usdc_addr)
-                    .await
-                    .map_err(|e| {
-                        McpError::internal_error(format!("查询 ETH/USDC 交易对失败: {}", e), None)
-                    })?;
-
-                let usdc_reserves = uniswap_client
-                    .get_reserves(usdc_pair)
-                    .await
-                    .map_err(|e| {
-                        McpError::internal_error(format!("查询 ETH/USDC 储备量失败: {}", e), None)
-                    })?;
-
-                // WETH < USDC in address order
-                let (weth_res, usdc_res) = if weth_addr < usdc_addr {
-                    (usdc_reserves.0, usdc_reserves.1)
-                } else {
-                    (usdc_reserves.1, usdc_reserves.0)
-                };
-
-                // 🎯 使用 U256 计算 ETH/USD 价格
-                // eth_price = (usdc_reserve * 10^18) / (weth_reserve * 10^6)
-                let eth_price = calculate_price_ratio(usdc_res, weth_res, 18, 6);
-
-                Ok::<_, McpError>(eth_price)
-            })
-        })?;
-
         // 计算 Token 价格（USD） = Token/ETH 价格 × ETH/USD 价格
-        let token_price_usd = multiply_price_strings(&price_in_eth_str, &eth_price_usd_str);
+        let eth_price_usd_str = eth_price_usd.as_ref().map_err(|e| e.clone())?;
+        let token_price_usd = multiply_price_strings(&price_in_eth_str, eth_price_usd_str);
         (token_price_usd, "USD".to_string())
     };
 
     // 计算流动性(以 WETH 计)
     let liquidity_eth = format_units(weth_reserve * U256::from(2), 18); // 总流动性 = weth * 2
 
-    let result = TokenPriceResult {
+    // ETH 报价模式下 ETH/USD 查询失败不影响主结果，仅缺少 USD 流动性
+    let liquidity_usd = eth_price_usd
+        .ok()
+        .map(|eth_price| multiply_price_strings(&liquidity_eth, &eth_price));
+
+    let reserves = PoolReserves {
+        token_reserve: format_units(token_reserve, token_decimals),
+        weth_reserve: format_units(weth_reserve, weth_decimals),
+    };
+
+    Ok(TokenPriceResult {
         token: token_info,
         price: final_price,
         quote_currency: final_quote,
-        source: format!("Uniswap V2 (Pair: {:?})", pair),
+        source,
         liquidity: Some(format!("{} ETH", liquidity_eth)),
+        liquidity_usd,
+        reserves: Some(reserves),
+        block_number,
+        pool_activity: None,
+        market_check: None,
+        protocol_tvl_usd: None,
+    })
+}
+
+/// CoinGecko 报价货币:ETH 报价用 eth,其余按 USD
+fn fallback_currency(quote_currency: &str) -> &'static str {
+    if quote_currency.eq_ignore_ascii_case("ETH") { "eth" } else { "usd" }
+}
+
+/// Uniswap 池子的 USD 流动性低于阈值时返回该流动性(阈值为 0 或流动性未知时返回 None)
+fn low_liquidity_usd(result: &TokenPriceResult, min_liquidity_usd: u64) -> Option<Decimal> {
+    if min_liquidity_usd == 0 {
+        return None;
+    }
+    let liquidity = Decimal::from_str(result.liquidity_usd.as_deref()?).ok()?;
+    (liquidity < Decimal::from(min_liquidity_usd)).then_some(liquidity)
+}
+
+/// 构建 CoinGecko 回退报价,`pool` 为流动性不足的 Uniswap 报价时保留其流动性和储备量供参考
+fn coingecko_price_result(
+    token_info: TokenInfo,
+    price: Decimal,
+    quote_currency: &str,
+    reason: &str,
+    pool: Option<TokenPriceResult>,
+) -> TokenPriceResult {
+    let (liquidity, liquidity_usd, reserves, block_number, pool_activity) = match pool {
+        Some(pool) => (pool.liquidity, pool.liquidity_usd, pool.reserves, pool.block_number, pool.pool_activity),
+        None => (None, None, None, None, None),
     };
 
-    let json_str = serde_json::to_string_pretty(&result)
-        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    TokenPriceResult {
+        token: token_info,
+        price: price.normalize().to_string(),
+        quote_currency: fallback_currency(quote_currency).to_uppercase(),
+        source: format!("CoinGecko (Fallback: {})", reason),
+        liquidity,
+        liquidity_usd,
+        reserves,
+        block_number,
+        pool_activity,
+        market_check: None,
+        protocol_tvl_usd: None,
+    }
+}
 
-    info!("成功返回价格");
+/// 用 DefiLlama 聚合市场价格交叉验证 Uniswap 报价(ETH 报价按 WETH 市场价格换算),查询失败时返回 None
+async fn cross_check_price(
+    defillama_client: &DefiLlamaClient,
+    token_addr: Address,
+    weth_addr: Address,
+    result: &TokenPriceResult,
+    alert_bps: u32,
+) -> Option<MarketPriceCheck> {
+    let prices = defillama_client
+        .token_prices(&[token_addr, weth_addr])
+        .await
+        .inspect_err(|e| warn!(error = %e, "查询 DefiLlama 价格失败"))
+        .ok()?;
+    let [Some(token_price), weth_price] = <[_; 2]>::try_from(prices).ok()? else {
+        return None;
+    };
 
-    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+    let market_price = if result.quote_currency == "ETH" {
+        let weth_price = weth_price?.price_usd;
+        if weth_price.is_zero() {
+            return None;
+        }
+        token_price.price_usd / weth_price
+    } else {
+        token_price.price_usd
+    };
+    let onchain_price = Decimal::from_str(&result.price).ok()?;
+
+    Some(market_price_check(onchain_price, market_price, token_price.confidence, &result.quote_currency, alert_bps))
+}
+
+/// 根据链上价格和市场价格构建对比结果,偏离达到 `alert_bps` 时附带告警
+fn market_price_check(
+    onchain_price: Decimal,
+    market_price: Decimal,
+    confidence: Option<Decimal>,
+    quote_currency: &str,
+    alert_bps: u32,
+) -> MarketPriceCheck {
+    let deviation = deviation_percent(onchain_price, market_price).unwrap_or_default();
+    let deviates = deviation.abs() * Decimal::ONE_HUNDRED >= Decimal::from(alert_bps);
+    let market_price = market_price.round_dp(8).normalize();
+
+    MarketPriceCheck {
+        source: "DefiLlama".to_string(),
+        market_price: market_price.to_string(),
+        deviation_percent: deviation.normalize().to_string(),
+        deviates,
+        confidence: confidence.map(|confidence| confidence.normalize().to_string()),
+        warning: deviates.then(|| {
+            format!(
+                "链上价格 {} {} 与市场价格 {} {} 偏离 {}%,池子可能流动性不足或被操纵",
+                onchain_price.normalize(),
+                quote_currency,
+                market_price,
+                quote_currency,
+                deviation.normalize()
+            )
+        }),
+    }
+}
+
+/// 池子最近 24 小时的子图活跃度;未配置子图或查询历史价格时跳过,查询失败只记录警告
+async fn fetch_pool_activity(
+    subgraph_client: &SubgraphClient,
+    pair: Address,
+    historical_block: Option<u64>,
+) -> Option<PoolActivity> {
+    if !subgraph_client.has_v2() || historical_block.is_some() {
+        return None;
+    }
+    subgraph_client
+        .v2_pair_activity(pair)
+        .await
+        .inspect_err(|e| warn!(pair = ?pair, error = %e, "查询子图活跃度失败"))
+        .ok()
+}
+
+/// 离线价格:代币元数据和储备量全部来自快照
+fn offline_price(
+    snapshot: &MarketSnapshot,
+    token_registry: &TokenRegistry,
+    uniswap_client: &UniswapV2Client,
+    token: &str,
+    quote_currency: &str,
+) -> Result<TokenPriceResult, McpError> {
+    let token_info = snapshot.resolve_token(token_registry, token).ok_or_else(|| {
+        McpError::invalid_params(format!("快照中没有代币信息: {}", token), None)
+    })?;
+
+    let token_addr: Address = token_info.address.parse().map_err(|_| {
+        McpError::internal_error(format!("快照中的代币地址无效: {}", token_info.address), None)
+    })?;
+    let weth_addr = uniswap_client.weth_address();
+    let usdc_addr = uniswap_client.usdc_address();
+
+    let (pair, token_reserve, weth_reserve) = snapshot
+        .reserves(token_addr, weth_addr)
+        .map_err(|e| McpError::invalid_params(format!("离线报价失败: {}", e), None))?;
+
+    let eth_price_usd = snapshot
+        .reserves(weth_addr, usdc_addr)
+        .map(|(_, weth_res, usdc_res)| calculate_price_ratio(usdc_res, weth_res, 18, 6))
+        .map_err(|e| McpError::invalid_params(format!("离线查询 ETH/USD 价格失败: {}", e), None));
+
+    build_price_result(
+        token_info,
+        (token_reserve, weth_reserve),
+        eth_price_usd,
+        quote_currency,
+        format!("Offline Snapshot (Block: {}, Pair: {:?})", snapshot.block_number, pair),
+        Some(snapshot.block_number),
+    )
+}
+
+/// 查询 ETH/USD 价格(基于 Uniswap V2 WETH/USDC 池子)
+/// `block` 为 None 时查询最新价格，历史区块需要归档节点
+async fn fetch_eth_price_usd_at(
+    uniswap_client: &UniswapV2Client,
+    weth_addr: Address,
+    block: Option<BlockId>,
+) -> Result<String, McpError> {
+    let usdc_addr = uniswap_client.usdc_address();
+
+    let usdc_pair = uniswap_client.pair_address(weth_addr, usdc_addr);
+
+    let usdc_reserves = uniswap_client
+        .get_reserves_at(usdc_pair, block)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询 ETH/USDC 储备量失败: {}", e), None))?;
+
+    // WETH < USDC in address order
+    let (weth_res, usdc_res) = if weth_addr < usdc_addr {
+        (usdc_reserves.0, usdc_reserves.1)
+    } else {
+        (usdc_reserves.1, usdc_reserves.0)
+    };
+
+    // 🎯 使用 U256 计算 ETH/USD 价格
+    // eth_price = (usdc_reserve * 10^18) / (weth_reserve * 10^6)
+    Ok(calculate_price_ratio(usdc_res, weth_res, 18, 6))
+}
+
+/// 查询代币在指定区块的 USD 价格(经由 Token/WETH 和 WETH/USDC 池子换算)
+/// `block` 为 None 时查询最新价格，历史区块需要归档节点
+pub(crate) async fn fetch_token_price_usd_at(
+    uniswap_client: &UniswapV2Client,
+    token_addr: Address,
+    token_decimals: u8,
+    block: Option<BlockId>,
+) -> Result<Decimal, McpError> {
+    let weth_addr = uniswap_client.weth_address();
+
+    let eth_price_usd = fetch_eth_price_usd_at(uniswap_client, weth_addr, block).await?;
+    let eth_price_usd = Decimal::from_str(&eth_price_usd)
+        .map_err(|e| McpError::internal_error(format!("解析 ETH 价格失败: {}", e), None))?;
+
+    if token_addr == weth_addr {
+        return Ok(eth_price_usd);
+    }
+
+    let pair = uniswap_client.pair_address(token_addr, weth_addr);
+
+    let reserves = uniswap_client
+        .get_reserves_at(pair, block)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询储备量失败: {}", e), None))?;
+
+    let (token_reserve, weth_reserve) = if token_addr < weth_addr {
+        (reserves.0, reserves.1)
+    } else {
+        (reserves.1, reserves.0)
+    };
+
+    let price_in_eth = calculate_price_ratio(weth_reserve, token_reserve, token_decimals, 18);
+    let price_in_eth = Decimal::from_str(&price_in_eth)
+        .map_err(|e| McpError::internal_error(format!("解析代币价格失败: {}", e), None))?;
+
+    Ok(price_in_eth * eth_price_usd)
+}
+
+/// 查询 1 个包装原生代币按 Token/WETH 池子中间价可兑换的代币数量(用于把 Gas 费用折算为代币)
+pub(crate) async fn fetch_tokens_per_eth(
+    uniswap_client: &UniswapV2Client,
+    token_addr: Address,
+    token_decimals: u8,
+) -> Result<Decimal, McpError> {
+    let weth_addr = uniswap_client.weth_address();
+    if token_addr == weth_addr {
+        return Ok(Decimal::ONE);
+    }
+
+    let pair = uniswap_client.pair_address(token_addr, weth_addr);
+    let reserves = uniswap_client
+        .get_reserves(pair)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询储备量失败: {}", e), None))?;
+
+    let (token_reserve, weth_reserve) = if token_addr < weth_addr {
+        (reserves.0, reserves.1)
+    } else {
+        (reserves.1, reserves.0)
+    };
+
+    let tokens_per_eth = calculate_price_ratio(token_reserve, weth_reserve, 18, token_decimals);
+    Decimal::from_str(&tokens_per_eth)
+        .map_err(|e| McpError::internal_error(format!("解析代币价格失败: {}", e), None))
 }
 
 /// 计算价格比率（U256 储备 + Decimal 价格）
 /// 符合原始需求：使用 rust_decimal 进行金融精度计算
 /// price = (numerator_reserve * 10^numerator_decimals) / (denominator_reserve * 10^denominator_decimals)
 /// 返回格式化的字符串，保留 6 位小数
-fn calculate_price_ratio(
+pub(crate) fn calculate_price_ratio(
     numerator_reserve: U256,
     denominator_reserve: U256,
     numerator_decimals: u8,
@@ -247,10 +753,10 @@ fn calculate_price_ratio(
     let scale_diff = numerator_decimals as i32 - denominator_decimals as i32;
     let decimals_adjustment = if scale_diff >= 0 {
         // numerator 小数位更多
-        decimal_pow10(scale_diff.abs() as u8)
+        decimal_pow10(scale_diff.unsigned_abs() as u8)
     } else {
         // denominator 小数位更多
-        Decimal::ONE / decimal_pow10(scale_diff.abs() as u8)
+        Decimal::ONE / decimal_pow10(scale_diff.unsigned_abs() as u8)
     };
 
     // 4. 计算最终价格（Decimal 精确运算）
@@ -264,7 +770,7 @@ fn calculate_price_ratio(
 }
 
 /// 两个价格字符串相乘（避免精度损失）
-fn multiply_price_strings(price1_str: &str, price2_str: &str) -> String {
+pub(crate) fn multiply_price_strings(price1_str: &str, price2_str: &str) -> String {
     // 解析为 f64 相乘（这里的精度损失可接受，因为是最终显示）
     let price1: f64 = price1_str.parse().unwrap_or(0.0);
     let price2: f64 = price2_str.parse().unwrap_or(0.0);
@@ -300,10 +806,10 @@ fn format_u256_division_fallback(
     // 处理小数位差异
     let scale_diff = numerator_decimals as i32 - denominator_decimals as i32;
     let (num, denom) = if scale_diff >= 0 {
-        let scale = U256::from(10u64).pow(U256::from(scale_diff.abs() as u64));
+        let scale = U256::from(10u64).pow(U256::from(scale_diff.unsigned_abs() as u64));
         (numerator * scale, denominator)
     } else {
-        let scale = U256::from(10u64).pow(U256::from(scale_diff.abs() as u64));
+        let scale = U256::from(10u64).pow(U256::from(scale_diff.unsigned_abs() as u64));
         (numerator, denominator * scale)
     };
 
@@ -426,4 +932,133 @@ mod tests {
         let result_f64: f64 = result.parse().unwrap();
         assert!((result_f64 - 1.25).abs() < 0.000001);
     }
+
+    #[test]
+    fn test_get_token_price_args_block_number() {
+        let json = r#"{"token":"USDC","block_number":17000000}"#;
+        let args: GetTokenPriceArgs = serde_json::from_str(json).expect("应该能反序列化");
+        assert_eq!(args.block_number, Some(17_000_000));
+        assert_eq!(args.finality, None);
+
+        let args: GetTokenPriceArgs = serde_json::from_str(r#"{"token":"USDC"}"#).unwrap();
+        assert_eq!(args.block_number, None);
+    }
+
+    #[test]
+    fn test_offline_price_from_snapshot() {
+        let snapshot = crate::snapshot::tests::sample_snapshot();
+        let registry = TokenRegistry::new();
+        let client = UniswapV2Client::new(None, &crate::chains::MAINNET);
+
+        // 100 WETH / 300000 USDC => 1 USDC = 1/3000 ETH, ETH/USD = 3000
+        let result = offline_price(&snapshot, &registry, &client, "USDC", "USD").unwrap();
+        assert_eq!(result.block_number, Some(19_000_000));
+        assert!(result.source.contains("Offline Snapshot (Block: 19000000"));
+        let price: f64 = result.price.parse().unwrap();
+        assert!((price - 1.0).abs() < 0.01, "price = {}", price);
+        assert_eq!(result.liquidity.as_deref(), Some("200 ETH"));
+
+        let result = offline_price(&snapshot, &registry, &client, "USDC", "ETH").unwrap();
+        assert_eq!(result.quote_currency, "ETH");
+
+        assert!(offline_price(&snapshot, &registry, &client, "DAI", "USD").is_err());
+    }
+
+    #[test]
+    fn test_token_price_result_liquidity_fields() {
+        let result = TokenPriceResult {
+            token: TokenInfo::eth(),
+            price: "2000".to_string(),
+            quote_currency: "USD".to_string(),
+            source: "Uniswap V2".to_string(),
+            liquidity: Some("20 ETH".to_string()),
+            liquidity_usd: Some("40000.000000".to_string()),
+            reserves: Some(PoolReserves {
+                token_reserve: "20000".to_string(),
+                weth_reserve: "10".to_string(),
+            }),
+            block_number: None,
+            pool_activity: None,
+            market_check: None,
+            protocol_tvl_usd: None,
+        };
+
+        let json = serde_json::to_string(&result).expect("应该能序列化");
+        assert!(json.contains("liquidity_usd"));
+        assert!(json.contains("token_reserve"));
+        assert!(json.contains("weth_reserve"));
+
+        // 缺失的 USD 流动性不输出字段
+        let result = TokenPriceResult {
+            liquidity_usd: None,
+            reserves: None,
+            ..result
+        };
+        let json = serde_json::to_string(&result).expect("应该能序列化");
+        assert!(!json.contains("liquidity_usd"));
+        assert!(!json.contains("reserves"));
+    }
+
+    #[test]
+    fn test_market_price_check() {
+        let market = Decimal::from(2000);
+        let check = market_price_check(Decimal::from(2010), market, Some(Decimal::from_str("0.99").unwrap()), "USD", 500);
+        assert_eq!(check.deviation_percent, "0.5");
+        assert!(!check.deviates);
+        assert!(check.warning.is_none());
+        assert_eq!(check.confidence.as_deref(), Some("0.99"));
+
+        // 偏离达到阈值时告警
+        let check = market_price_check(Decimal::from(1900), market, None, "USD", 500);
+        assert_eq!(check.deviation_percent, "-5");
+        assert!(check.deviates);
+        assert!(check.warning.unwrap().contains("-5%"));
+
+        let check = market_price_check(Decimal::from_str("0.00051").unwrap(), Decimal::from_str("0.0005").unwrap(), None, "ETH", 100);
+        assert_eq!(check.market_price, "0.0005");
+        assert!(check.deviates);
+    }
+
+    #[test]
+    fn test_coingecko_fallback_on_low_liquidity() {
+        let pool = TokenPriceResult {
+            token: TokenInfo::eth(),
+            price: "0.5".to_string(),
+            quote_currency: "USD".to_string(),
+            source: "Uniswap V2".to_string(),
+            liquidity: Some("2 ETH".to_string()),
+            liquidity_usd: Some("6000.5".to_string()),
+            reserves: None,
+            block_number: Some(100),
+            pool_activity: Some(PoolActivity {
+                volume_24h_usd: "120".to_string(),
+                fees_24h_usd: "0.36".to_string(),
+                tx_count_24h: 3,
+                tvl_usd: None,
+                active_hours: 2,
+                source: "Uniswap V2 Subgraph".to_string(),
+            }),
+            market_check: None,
+            protocol_tvl_usd: None,
+        };
+
+        assert_eq!(low_liquidity_usd(&pool, 10_000), Some(Decimal::from_str("6000.5").unwrap()));
+        assert_eq!(low_liquidity_usd(&pool, 5_000), None);
+        assert_eq!(low_liquidity_usd(&pool, 0), None);
+
+        // 低流动性回退保留池子信息
+        let token = pool.token.clone();
+        let result = coingecko_price_result(token.clone(), Decimal::from_str("0.98").unwrap(), "usd", "low", Some(pool));
+        assert_eq!(result.price, "0.98");
+        assert_eq!(result.quote_currency, "USD");
+        assert_eq!(result.source, "CoinGecko (Fallback: low)");
+        assert_eq!(result.liquidity_usd.as_deref(), Some("6000.5"));
+        assert_eq!(result.block_number, Some(100));
+        assert_eq!(result.pool_activity.unwrap().tx_count_24h, 3);
+
+        // 没有交易对时只有价格
+        let result = coingecko_price_result(token, Decimal::from_str("0.0003").unwrap(), "ETH", "no Uniswap V2 pair", None);
+        assert_eq!(result.quote_currency, "ETH");
+        assert!(result.liquidity.is_none() && result.reserves.is_none() && result.pool_activity.is_none());
+    }
 }
@@ -0,0 +1,190 @@
+use crate::{config::Config, eth_client::EthClient, logging::info, types::{checksum_address, parse_address}};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// GetProof 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetProofArgs {
+    /// 账户或合约地址(必需)
+    pub address: String,
+    /// 存储槽列表(可选,十进制或 0x 十六进制)
+    #[serde(default)]
+    pub storage_keys: Vec<String>,
+    /// 区块号(可选,不填则使用最新区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+}
+
+/// GetProof 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ProofResult {
+    pub address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub balance: String,
+    pub nonce: String,
+    pub code_hash: String,
+    pub storage_hash: String,
+    pub account_proof: Vec<String>,
+    pub storage_proof: Vec<StorageProofEntry>,
+}
+
+/// 单个存储槽的证明
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct StorageProofEntry {
+    pub key: String,
+    pub value: String,
+    pub proof: Vec<String>,
+}
+
+/// 获取账户/存储槽的 Merkle 证明(eth_getProof)
+pub async fn get_proof(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    Parameters(args): Parameters<GetProofArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_proof 请求");
+
+    info!(
+        address = %args.address,
+        storage_keys = args.storage_keys.len(),
+        block_number = ?args.block_number,
+        "查询 Merkle 证明"
+    );
+
+    let storage_keys = args
+        .storage_keys
+        .iter()
+        .map(|key| parse_storage_key(key))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    // 测试模式
+    if config.server.test_mode {
+        let result = ProofResult {
+            address: args.address.clone(),
+            block_number: args.block_number,
+            balance: "100000000000000000000".to_string(),
+            nonce: "0".to_string(),
+            code_hash: format!("{:?}", H256::zero()),
+            storage_hash: format!("{:?}", H256::zero()),
+            account_proof: vec!["0x".to_string()],
+            storage_proof: storage_keys
+                .iter()
+                .map(|key| StorageProofEntry {
+                    key: format!("{:?}", key),
+                    value: "0".to_string(),
+                    proof: vec![],
+                })
+                .collect(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let eth_client = eth_client.clone();
+    let address = parse_address(&args.address).map_err(|e| McpError::invalid_params(e, None))?;
+    let block = args.block_number.map(BlockId::from);
+
+    let proof = eth_client
+        .get_proof(&checksum_address(address), storage_keys, block)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询 Merkle 证明失败: {}", e), None))?;
+
+    let result = ProofResult {
+        address: checksum_address(proof.address),
+        block_number: args.block_number,
+        balance: proof.balance.to_string(),
+        nonce: proof.nonce.to_string(),
+        code_hash: format!("{:?}", proof.code_hash),
+        storage_hash: format!("{:?}", proof.storage_hash),
+        account_proof: proof.account_proof.iter().map(|p| p.to_string()).collect(),
+        storage_proof: proof
+            .storage_proof
+            .iter()
+            .map(|entry| {
+                let mut key = [0u8; 32];
+                entry.key.to_big_endian(&mut key);
+                StorageProofEntry {
+                    key: format!("{:?}", H256::from(key)),
+                    value: entry.value.to_string(),
+                    proof: entry.proof.iter().map(|p| p.to_string()).collect(),
+                }
+            })
+            .collect(),
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!("成功返回 Merkle 证明");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 解析存储槽(支持十进制槽位号和 0x 十六进制)
+fn parse_storage_key(key: &str) -> Result<H256, String> {
+    let value = if let Some(hex) = key.strip_prefix("0x") {
+        if hex.len() > 64 {
+            return Err(format!("存储槽超过 32 字节: {}", key));
+        }
+        U256::from_str_radix(hex, 16).ok()
+    } else {
+        U256::from_dec_str(key).ok()
+    }
+    .ok_or_else(|| format!("无效的存储槽: {}", key))?;
+
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    Ok(H256::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_storage_key() {
+        // 十进制槽位
+        let key = parse_storage_key("1").unwrap();
+        assert_eq!(key.as_bytes()[31], 1);
+
+        // 十六进制槽位
+        let key = parse_storage_key("0x0a").unwrap();
+        assert_eq!(key.as_bytes()[31], 10);
+
+        // 完整 32 字节
+        let full = format!("0x{}", "ff".repeat(32));
+        let key = parse_storage_key(&full).unwrap();
+        assert_eq!(key, H256::repeat_byte(0xff));
+    }
+
+    #[test]
+    fn test_parse_storage_key_errors() {
+        assert!(parse_storage_key("abc").is_err());
+        assert!(parse_storage_key("0xzz").is_err());
+        assert!(parse_storage_key(&format!("0x{}", "1".repeat(65))).is_err());
+    }
+
+    #[test]
+    fn test_get_proof_args_deserialization() {
+        let json = r#"{"address":"0x123"}"#;
+        let args: GetProofArgs = serde_json::from_str(json).expect("应该能反序列化");
+        assert!(args.storage_keys.is_empty());
+        assert_eq!(args.block_number, None);
+    }
+}
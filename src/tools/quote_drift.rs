@@ -0,0 +1,333 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, parse_units, Erc20Client},
+    eth_client::EthClient,
+    logging::info,
+    token_registry::TokenRegistry,
+    types::TokenInfo,
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// 未指定 from_block 时默认回溯的区块数
+const DEFAULT_BLOCKS_AGO: u64 = 10;
+
+/// CompareQuoteDrift 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CompareQuoteDriftArgs {
+    /// 源代币地址或符号(必需)
+    pub from_token: String,
+    /// 目标代币地址或符号(必需)
+    pub to_token: String,
+    /// 交易数量(必需)
+    pub amount: String,
+    /// 较早的区块号(可选,不填则使用 to_block - blocks_ago)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_block: Option<u64>,
+    /// 较晚的区块号(可选,默认最新区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_block: Option<u64>,
+    /// 回溯区块数(可选,仅在未指定 from_block 时生效,默认 10)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_ago: Option<u64>,
+}
+
+/// CompareQuoteDrift 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct QuoteDriftResult {
+    pub from_token: TokenInfo,
+    pub to_token: TokenInfo,
+    pub input_amount: String,
+    pub path: Vec<String>,
+    pub earlier: BlockQuote,
+    pub later: BlockQuote,
+    pub blocks_elapsed: u64,
+    /// 输出变化量(later - earlier,带符号)
+    pub output_change: String,
+    pub output_change_pct: String,
+}
+
+/// 单个区块的报价
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BlockQuote {
+    pub block_number: u64,
+    pub amount_out: String,
+    pub price_impact: String,
+}
+
+/// 比较同一笔交易在两个区块高度的报价变化
+pub async fn compare_quote_drift(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<CompareQuoteDriftArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 compare_quote_drift 请求");
+
+    info!(
+        from = %args.from_token,
+        to = %args.to_token,
+        amount = %args.amount,
+        from_block = ?args.from_block,
+        to_block = ?args.to_block,
+        blocks_ago = ?args.blocks_ago,
+        "比较报价漂移"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let to_block = args.to_block.unwrap_or(20_000_000);
+        let from_block = resolve_from_block(args.from_block, to_block, args.blocks_ago)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let test_token = |symbol: &str, address: &str| TokenInfo {
+            symbol: symbol.to_string(),
+            name: format!("{} Token", symbol),
+            address: address.to_string(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+
+        let (output_change, output_change_pct) =
+            calculate_drift(U256::from(100u64), U256::from(99u64), 0);
+
+        let result = QuoteDriftResult {
+            from_token: test_token("FROM", &args.from_token),
+            to_token: test_token("TO", &args.to_token),
+            input_amount: args.amount.clone(),
+            path: vec![args.from_token.clone(), args.to_token.clone()],
+            earlier: BlockQuote {
+                block_number: from_block,
+                amount_out: "100".to_string(),
+                price_impact: "0.50%".to_string(),
+            },
+            later: BlockQuote {
+                block_number: to_block,
+                amount_out: "99".to_string(),
+                price_impact: "0.51%".to_string(),
+            },
+            blocks_elapsed: to_block - from_block,
+            output_change,
+            output_change_pct,
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Uniswap 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    // 解析源代币
+    let mut from_token_info = token_registry
+        .resolve(&args.from_token)
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+    let from_token_addr: Address = from_token_info.address.parse().map_err(|_| {
+        McpError::internal_error("无效的源代币地址".to_string(), None)
+    })?;
+
+    // 🔍 动态查询未知源代币信息
+    if from_token_info.symbol == "UNKNOWN" && erc20_client.is_available() {
+        let erc20_client_clone = erc20_client.clone();
+        let real_info = erc20_client_clone
+            .token_info(from_token_addr)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询源代币信息失败: {}", e), None))?;
+
+        // 缓存到注册表
+        token_registry.register(real_info.symbol.clone(), real_info.clone());
+        from_token_info = real_info;
+    }
+
+    // 解析目标代币
+    let mut to_token_info = token_registry
+        .resolve(&args.to_token)
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+    let to_token_addr: Address = to_token_info.address.parse().map_err(|_| {
+        McpError::internal_error("无效的目标代币地址".to_string(), None)
+    })?;
+
+    // 🔍 动态查询未知目标代币信息
+    if to_token_info.symbol == "UNKNOWN" && erc20_client.is_available() {
+        let erc20_client_clone = erc20_client.clone();
+        let real_info = erc20_client_clone
+            .token_info(to_token_addr)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询目标代币信息失败: {}", e), None))?;
+
+        // 缓存到注册表
+        token_registry.register(real_info.symbol.clone(), real_info.clone());
+        to_token_info = real_info;
+    }
+
+    // 解析输入金额（使用 rust_decimal 保持精度）
+    let amount_in = parse_units(&args.amount, from_token_info.decimals).map_err(|e| {
+        McpError::invalid_params(format!("解析金额失败: {}", e), None)
+    })?;
+
+    let eth_client = eth_client.clone();
+    let uniswap_client = uniswap_client.clone();
+
+    let (from_block, to_block, earlier, later) = async {
+        let to_block = match args.to_block {
+            Some(block) => block,
+            None => eth_client.get_block_number().await.map_err(|e| {
+                McpError::internal_error(format!("查询最新区块失败: {}", e), None)
+            })?,
+        };
+
+        let from_block = resolve_from_block(args.from_block, to_block, args.blocks_ago)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let (earlier, later) = tokio::join!(
+            uniswap_client.quote_swap_at(
+                from_token_addr,
+                to_token_addr,
+                amount_in,
+                Some(BlockId::from(from_block))
+            ),
+            uniswap_client.quote_swap_at(
+                from_token_addr,
+                to_token_addr,
+                amount_in,
+                Some(BlockId::from(to_block))
+            )
+        );
+
+        let quote_error = |block: u64, e| {
+            McpError::internal_error(
+                format!("查询区块 {} 的报价失败(可能需要归档节点): {}", block, e),
+                None,
+            )
+        };
+        let earlier = earlier.map_err(|e| quote_error(from_block, e))?;
+        let later = later.map_err(|e| quote_error(to_block, e))?;
+
+        Ok::<_, McpError>((from_block, to_block, earlier, later))
+    }
+    .await?;
+
+    let (output_change, output_change_pct) =
+        calculate_drift(earlier.amount_out, later.amount_out, to_token_info.decimals);
+
+    let result = QuoteDriftResult {
+        input_amount: args.amount,
+        path: later.path.iter().map(|addr| format!("{:?}", addr)).collect(),
+        earlier: BlockQuote {
+            block_number: from_block,
+            amount_out: format_units(earlier.amount_out, to_token_info.decimals),
+            price_impact: format!("{:.2}%", earlier.price_impact),
+        },
+        later: BlockQuote {
+            block_number: to_block,
+            amount_out: format_units(later.amount_out, to_token_info.decimals),
+            price_impact: format!("{:.2}%", later.price_impact),
+        },
+        blocks_elapsed: to_block - from_block,
+        output_change,
+        output_change_pct,
+        from_token: from_token_info,
+        to_token: to_token_info,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!("成功返回报价漂移");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 确定较早的区块号:显式指定优先,否则从 to_block 回溯 blocks_ago 个区块
+fn resolve_from_block(
+    from_block: Option<u64>,
+    to_block: u64,
+    blocks_ago: Option<u64>,
+) -> Result<u64, String> {
+    let from_block = match from_block {
+        Some(block) => block,
+        None => {
+            let blocks_ago = blocks_ago.unwrap_or(DEFAULT_BLOCKS_AGO);
+            if blocks_ago == 0 {
+                return Err("blocks_ago 必须大于 0".to_string());
+            }
+            to_block.saturating_sub(blocks_ago)
+        }
+    };
+
+    if from_block >= to_block {
+        return Err(format!(
+            "较早区块 {} 必须小于较晚区块 {}",
+            from_block, to_block
+        ));
+    }
+
+    Ok(from_block)
+}
+
+/// 计算输出变化量(带符号)和变化百分比
+fn calculate_drift(earlier: U256, later: U256, decimals: u8) -> (String, String) {
+    let to_decimal =
+        |amount: U256| Decimal::from_str(&format_units(amount, decimals)).unwrap_or_default();
+
+    let earlier = to_decimal(earlier);
+    let later = to_decimal(later);
+    let change = later - earlier;
+
+    let change_pct = if earlier.is_zero() {
+        Decimal::ZERO
+    } else {
+        change / earlier * Decimal::from(100)
+    };
+
+    (
+        change.normalize().to_string(),
+        format!("{:.4}%", change_pct),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_from_block() {
+        assert_eq!(resolve_from_block(None, 1000, None).unwrap(), 990);
+        assert_eq!(resolve_from_block(None, 1000, Some(100)).unwrap(), 900);
+        assert_eq!(resolve_from_block(Some(500), 1000, Some(100)).unwrap(), 500);
+        assert!(resolve_from_block(Some(1000), 1000, None).is_err());
+        assert!(resolve_from_block(None, 1000, Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_calculate_drift() {
+        let (change, pct) = calculate_drift(
+            U256::from(2_000_000u64),
+            U256::from(1_990_000u64),
+            6,
+        );
+        assert_eq!(change, "-0.01");
+        assert_eq!(pct, "-0.5000%");
+
+        let (change, pct) = calculate_drift(U256::from(100u64), U256::from(100u64), 0);
+        assert_eq!(change, "0");
+        assert_eq!(pct, "0.0000%");
+    }
+}
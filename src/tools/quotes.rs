@@ -0,0 +1,256 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, parse_units, Erc20Client},
+    logging::info,
+    quoting::{best_quote, QuoteAggregator, QuoteKind, QuoteRequest, VenueQuote},
+    token_registry::TokenRegistry,
+    tools::user_operation::{resolve_token, token_address},
+    types::TokenInfo,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// CompareQuotes 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CompareQuotesArgs {
+    /// 卖出代币地址或符号(必需)
+    pub from_token: String,
+    /// 买入代币地址或符号(必需)
+    pub to_token: String,
+    /// 卖出数量(必需)
+    pub amount: String,
+}
+
+/// 单个场所的报价
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct VenueQuoteRow {
+    pub venue: String,
+    pub kind: QuoteKind,
+    /// 预计买入数量(已扣除协议费)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buy_amount: Option<String>,
+    /// 协议费(以卖出代币计)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_amount: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_impact: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_to: Option<u64>,
+    /// 是否为最优场所
+    pub best: bool,
+    /// 相对最优报价少得到的比例(基点,仅非最优场所)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shortfall_bps: Option<u64>,
+    /// 报价失败原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// CompareQuotes 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct CompareQuotesResult {
+    pub from_token: TokenInfo,
+    pub to_token: TokenInfo,
+    pub amount: String,
+    pub quotes: Vec<VenueQuoteRow>,
+    /// 买入数量最多的场所
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub best_venue: Option<String>,
+}
+
+/// 比较各场所报价
+pub async fn compare_quotes(
+    config: &Arc<Config>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    quote_aggregator: &Arc<QuoteAggregator>,
+    Parameters(args): Parameters<CompareQuotesArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 compare_quotes 请求");
+
+    info!(
+        from = %args.from_token,
+        to = %args.to_token,
+        amount = %args.amount,
+        "比较报价"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let token = |address: &str| TokenInfo {
+            symbol: address.to_string(),
+            name: address.to_string(),
+            address: address.to_string(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+        let quotes = vec![
+            VenueQuote {
+                venue: "uniswap_v2".to_string(),
+                kind: QuoteKind::OnChain,
+                buy_amount: U256::exp10(20),
+                fee_amount: U256::zero(),
+                price_impact: Some(0.3),
+                valid_to: None,
+                path: Vec::new(),
+            },
+            VenueQuote {
+                venue: "sushiswap".to_string(),
+                kind: QuoteKind::OnChain,
+                buy_amount: U256::exp10(20) - U256::exp10(17),
+                fee_amount: U256::zero(),
+                price_impact: Some(0.4),
+                valid_to: None,
+                path: Vec::new(),
+            },
+            VenueQuote {
+                venue: "cow".to_string(),
+                kind: QuoteKind::Firm,
+                buy_amount: U256::exp10(20) + U256::exp10(18),
+                fee_amount: U256::exp10(15),
+                price_impact: None,
+                valid_to: Some(1_800),
+                path: Vec::new(),
+            },
+        ];
+        let best = best_quote(&quotes).cloned();
+        let result = CompareQuotesResult {
+            from_token: token(&args.from_token),
+            to_token: token(&args.to_token),
+            amount: args.amount.clone(),
+            best_venue: best.as_ref().map(|q| q.venue.clone()),
+            quotes: quotes.iter().map(|q| quote_row(q, best.as_ref(), 18, 18)).collect(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !erc20_client.is_available() {
+        return Err(McpError::internal_error(
+            "ERC20 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let owner = config.get_simulation_address();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+    let quote_aggregator = quote_aggregator.clone();
+
+    let result = async {
+        let from_info = resolve_token(&token_registry, &erc20_client, &args.from_token).await?;
+        let to_info = resolve_token(&token_registry, &erc20_client, &args.to_token).await?;
+
+        let request = QuoteRequest {
+            sell_token: token_address(&from_info)?,
+            buy_token: token_address(&to_info)?,
+            sell_amount: parse_units(&args.amount, from_info.decimals)
+                .map_err(|e| McpError::invalid_params(format!("解析金额失败: {}", e), None))?,
+            owner,
+        };
+
+        let backend_quotes = quote_aggregator.quote_all(&request).await;
+        let best = best_quote(backend_quotes.iter().filter_map(|q| q.result.as_ref().ok())).cloned();
+        let best_venue = best.as_ref().map(|q| q.venue.clone());
+
+        let quotes = backend_quotes
+            .into_iter()
+            .map(|quote| match quote.result {
+                Ok(q) => quote_row(&q, best.as_ref(), from_info.decimals, to_info.decimals),
+                Err(e) => VenueQuoteRow {
+                    venue: quote.venue.to_string(),
+                    kind: quote.kind,
+                    buy_amount: None,
+                    fee_amount: None,
+                    price_impact: None,
+                    valid_to: None,
+                    best: false,
+                    shortfall_bps: None,
+                    error: Some(e.to_string()),
+                },
+            })
+            .collect();
+
+        Ok::<_, McpError>(CompareQuotesResult {
+            from_token: from_info,
+            to_token: to_info,
+            amount: args.amount.clone(),
+            quotes,
+            best_venue,
+        })
+    }
+    .await?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        venues = result.quotes.len(),
+        best = ?result.best_venue,
+        "成功返回报价比较"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+fn quote_row(quote: &VenueQuote, best: Option<&VenueQuote>, sell_decimals: u8, buy_decimals: u8) -> VenueQuoteRow {
+    let is_best = best.is_some_and(|best| best.venue == quote.venue);
+    VenueQuoteRow {
+        venue: quote.venue.clone(),
+        kind: quote.kind,
+        buy_amount: Some(format_units(quote.buy_amount, buy_decimals)),
+        fee_amount: Some(format_units(quote.fee_amount, sell_decimals)),
+        price_impact: quote.price_impact,
+        valid_to: quote.valid_to,
+        best: is_best,
+        shortfall_bps: best
+            .filter(|_| !is_best)
+            .and_then(|best| shortfall_bps(quote.buy_amount, best.buy_amount)),
+        error: None,
+    }
+}
+
+/// 相对最优买入数量的差距(基点,向下取整)
+fn shortfall_bps(buy_amount: U256, best_amount: U256) -> Option<u64> {
+    if best_amount.is_zero() || buy_amount >= best_amount {
+        return Some(0);
+    }
+    let bps = (best_amount - buy_amount).checked_mul(U256::from(10_000u64))? / best_amount;
+    Some(bps.as_u64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_row_marks_best() {
+        let quote = |venue: &str, amount: u64| VenueQuote {
+            venue: venue.to_string(),
+            kind: QuoteKind::OnChain,
+            buy_amount: U256::from(amount),
+            fee_amount: U256::zero(),
+            price_impact: None,
+            valid_to: None,
+            path: Vec::new(),
+        };
+        let quotes = [quote("uniswap_v2", 1_000_000), quote("sushiswap", 987_650)];
+        let best = best_quote(&quotes);
+
+        let rows: Vec<_> = quotes.iter().map(|q| quote_row(q, best, 18, 6)).collect();
+        assert!(rows[0].best);
+        assert_eq!(rows[0].shortfall_bps, None);
+        assert!(!rows[1].best);
+        assert_eq!(rows[1].shortfall_bps, Some(123));
+        assert_eq!(rows[1].buy_amount.as_deref(), Some("0.98765"));
+
+        assert_eq!(shortfall_bps(U256::from(5u64), U256::zero()), Some(0));
+    }
+}
@@ -0,0 +1,418 @@
+use crate::{
+    compliance::{ComplianceScreen, ScreeningDecision},
+    config::Config,
+    erc20::format_units,
+    eth_client::EthClient,
+    logging::{info, warn},
+    store::Store,
+    tools::execute_swap::{receipt_status, RECEIPT_TIMEOUT},
+    tx_manager::{is_already_known, TxManager},
+    types::checksum_address,
+};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::utils::rlp::Rlp;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError, Peer, RoleServer,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// 默认等待的确认数(包含交易所在区块)
+const DEFAULT_CONFIRMATIONS: u64 = 1;
+/// 允许等待的最大确认数
+const MAX_CONFIRMATIONS: u64 = 64;
+/// 允许的最长等待时间(秒)
+const MAX_WAIT_SECS: u64 = 900;
+
+/// SendRawTransaction 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SendRawTransactionArgs {
+    /// 已签名交易的 0x 十六进制编码(必需,legacy/EIP-2930/EIP-1559)
+    pub raw_transaction: String,
+    /// 等待的确认数(可选,默认 1,最大 64,0 表示广播后立即返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+    /// 最长等待时间(秒,可选,默认 180,最大 900)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+/// SendRawTransaction 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SendRawTransactionResult {
+    pub tx_hash: String,
+    /// 从签名恢复的发送方
+    pub from: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    pub nonce: String,
+    /// 转账的 ETH 数量
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// 节点已有该交易(重复广播),继续跟踪状态
+    pub already_known: bool,
+    /// pending / success / reverted
+    pub status: String,
+    pub confirmations: u64,
+    pub target_confirmations: u64,
+    /// 是否达到目标确认数
+    pub confirmed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<String>,
+    pub elapsed_secs: u64,
+    /// 广播和确认进度(状态或确认数变化时记录)
+    pub progress: Vec<ProgressEvent>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    /// 制裁名单命中但只标记时的筛查结论
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ScreeningDecision>,
+}
+
+/// 交易进度事件
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProgressEvent {
+    /// broadcast / pending / mined / confirmed / reverted / timeout
+    pub stage: String,
+    pub confirmations: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub elapsed_secs: u64,
+}
+
+/// 从签名交易中解析出的字段
+#[derive(Debug)]
+struct SignedTransaction {
+    tx: TypedTransaction,
+    from: Address,
+    hash: H256,
+}
+
+/// 广播外部签名的原始交易,并轮询回执直到达到目标确认数
+/// 客户端请求附带 progressToken 时,同时以 MCP 进度通知推送确认进度
+#[allow(clippy::too_many_arguments)]
+pub async fn send_raw_transaction(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    store: &Arc<Store>,
+    compliance: &Arc<ComplianceScreen>,
+    tx_manager: &Arc<TxManager>,
+    progress_reporter: Option<(Peer<RoleServer>, ProgressToken)>,
+    Parameters(args): Parameters<SendRawTransactionArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 send_raw_transaction 请求");
+
+    let raw = args
+        .raw_transaction
+        .trim()
+        .parse::<Bytes>()
+        .map_err(|_| McpError::invalid_params("无效的原始交易: 不是十六进制编码", None))?;
+    let signed = decode_signed_transaction(&raw).map_err(|e| McpError::invalid_params(e, None))?;
+
+    let target_confirmations = args.confirmations.unwrap_or(DEFAULT_CONFIRMATIONS);
+    if target_confirmations > MAX_CONFIRMATIONS {
+        return Err(McpError::invalid_params(
+            format!("confirmations 不能超过 {}", MAX_CONFIRMATIONS),
+            None,
+        ));
+    }
+    let timeout_secs = args.timeout_secs.unwrap_or(RECEIPT_TIMEOUT.as_secs());
+    if timeout_secs == 0 || timeout_secs > MAX_WAIT_SECS {
+        return Err(McpError::invalid_params(
+            format!("timeout_secs 必须在 1 到 {} 之间", MAX_WAIT_SECS),
+            None,
+        ));
+    }
+
+    let mut notes = Vec::new();
+    match signed.tx.chain_id().map(|id| id.as_u64()) {
+        Some(chain_id) if chain_id != config.ethereum.chain_id => {
+            return Err(McpError::invalid_params(
+                format!(
+                    "交易的 chain_id ({}) 与当前链 ({}) 不一致",
+                    chain_id, config.ethereum.chain_id
+                ),
+                None,
+            ));
+        }
+        Some(_) => {}
+        None => notes.push("交易未包含 chain_id(EIP-155 之前的格式),可在任意链上被重放".to_string()),
+    }
+
+    info!(
+        tx_hash = ?signed.hash,
+        from = ?signed.from,
+        nonce = ?signed.tx.nonce(),
+        target_confirmations,
+        timeout_secs,
+        "广播原始交易"
+    );
+
+    let mut result = SendRawTransactionResult {
+        tx_hash: format!("{:?}", signed.hash),
+        from: checksum_address(signed.from),
+        to: signed.tx.to_addr().map(|to| checksum_address(*to)),
+        nonce: signed.tx.nonce().copied().unwrap_or_default().to_string(),
+        value: format_units(signed.tx.value().copied().unwrap_or_default(), 18),
+        chain_id: signed.tx.chain_id().map(|id| id.as_u64()),
+        already_known: false,
+        status: "pending".to_string(),
+        confirmations: 0,
+        target_confirmations,
+        confirmed: target_confirmations == 0,
+        block_number: None,
+        gas_used: None,
+        elapsed_secs: 0,
+        progress: vec![ProgressEvent {
+            stage: "broadcast".to_string(),
+            confirmations: 0,
+            block_number: None,
+            elapsed_secs: 0,
+        }],
+        notes,
+        compliance: None,
+    };
+
+    // 测试模式:不广播,直接返回已确认
+    if config.server.test_mode {
+        result.status = "success".to_string();
+        result.confirmations = target_confirmations;
+        result.confirmed = true;
+        result.block_number = Some(20_000_000);
+        result.gas_used = Some("21000".to_string());
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    // 🛡️ 制裁名单筛查(广播前)
+    let mut parties = vec![("wallet", signed.from)];
+    parties.extend(signed.tx.to_addr().map(|to| ("recipient", *to)));
+    result.compliance = compliance.screen(store, "send_raw_transaction", &parties)?;
+
+    let mut tx_hash = signed.hash;
+    match eth_client.send_raw_transaction(raw).await {
+        Ok(hash) if hash != signed.hash => {
+            warn!(expected = ?signed.hash, actual = ?hash, "节点返回的交易哈希与本地计算不一致");
+            tx_hash = hash;
+            result.tx_hash = format!("{:?}", hash);
+        }
+        Ok(_) => {}
+        // 重复广播:交易已在内存池或已打包,继续跟踪
+        Err(e) if is_already_known(&e.to_string()) => {
+            info!(tx_hash = ?signed.hash, "节点已有该交易,继续跟踪状态");
+            result.already_known = true;
+        }
+        Err(e) => {
+            return Err(McpError::internal_error(format!("广播交易失败: {}", e), None));
+        }
+    }
+
+    let nonce = signed.tx.nonce().copied().unwrap_or_default().as_u64();
+    tx_manager.track_broadcast("send_raw_transaction", signed.from, nonce, tx_hash, 1);
+
+    if target_confirmations > 0 {
+        let reporter = progress_reporter.map(|(peer, token)| ProgressReporter {
+            peer,
+            token,
+            total: target_confirmations,
+        });
+        wait_for_confirmations(tx_manager, tx_hash, reporter, timeout_secs, &mut result).await;
+    }
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        tx_hash = %result.tx_hash,
+        status = %result.status,
+        confirmations = result.confirmations,
+        "成功广播原始交易"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 通过交易管理器等待目标确认数、交易回滚或超时,进度写入 `result`
+async fn wait_for_confirmations(
+    tx_manager: &TxManager,
+    tx_hash: H256,
+    reporter: Option<ProgressReporter>,
+    timeout_secs: u64,
+    result: &mut SendRawTransactionResult,
+) {
+    let started = Instant::now();
+    let target = result.target_confirmations;
+
+    let (progress, mut updates) = mpsc::unbounded_channel();
+    let wait = tx_manager.wait_for_confirmations(tx_hash, target, Duration::from_secs(timeout_secs), Some(progress));
+    let track = async {
+        while let Some((receipt, confirmations)) = updates.recv().await {
+            let block_number = receipt.as_ref().and_then(|r| r.block_number).map(|n| n.as_u64());
+            result.status = receipt_status(receipt.as_ref()).to_string();
+            result.block_number = block_number;
+            result.confirmations = confirmations;
+            result.gas_used = receipt.as_ref().and_then(|r| r.gas_used).map(|gas| gas.to_string());
+            result.confirmed = confirmations >= target;
+
+            let stage = progress_stage(&result.status, confirmations, target);
+            let changed = result
+                .progress
+                .last()
+                .is_none_or(|last| last.stage != stage || last.confirmations != confirmations);
+            if changed {
+                let event = ProgressEvent {
+                    stage: stage.to_string(),
+                    confirmations,
+                    block_number,
+                    elapsed_secs: started.elapsed().as_secs(),
+                };
+                if let Some(ref reporter) = reporter {
+                    reporter.notify(&event).await;
+                }
+                result.progress.push(event);
+            }
+        }
+    };
+    tokio::join!(wait, track);
+
+    // 回滚的交易不会因更多确认而改变结果
+    if !result.confirmed && result.status != "reverted" {
+        result.progress.push(ProgressEvent {
+            stage: "timeout".to_string(),
+            confirmations: result.confirmations,
+            block_number: result.block_number,
+            elapsed_secs: started.elapsed().as_secs(),
+        });
+        result.notes.push(format!(
+            "等待 {} 秒后仍未达到 {} 个确认,可用 get_transaction 继续跟踪",
+            timeout_secs, target
+        ));
+    }
+    result.elapsed_secs = started.elapsed().as_secs();
+}
+
+/// 以 MCP 进度通知推送确认进度
+struct ProgressReporter {
+    peer: Peer<RoleServer>,
+    token: ProgressToken,
+    total: u64,
+}
+
+impl ProgressReporter {
+    async fn notify(&self, event: &ProgressEvent) {
+        let message = match event.block_number {
+            Some(block_number) => format!("{}: 区块 {},{} 个确认", event.stage, block_number, event.confirmations),
+            None => event.stage.clone(),
+        };
+        let notification = self.peer.notify_progress(ProgressNotificationParam {
+            progress_token: self.token.clone(),
+            progress: event.confirmations as f64,
+            total: Some(self.total as f64),
+            message: Some(message),
+        });
+        if let Err(e) = notification.await {
+            warn!(error = %e, "发送进度通知失败");
+        }
+    }
+}
+
+/// 解码签名交易并从签名恢复发送方
+fn decode_signed_transaction(raw: &Bytes) -> Result<SignedTransaction, String> {
+    if raw.is_empty() {
+        return Err("原始交易为空".to_string());
+    }
+    let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(raw))
+        .map_err(|e| format!("无法解码签名交易: {}", e))?;
+    let from = signature
+        .recover(tx.sighash())
+        .map_err(|e| format!("无法从签名恢复发送方: {}", e))?;
+
+    Ok(SignedTransaction {
+        hash: H256::from(ethers::utils::keccak256(raw)),
+        tx,
+        from,
+    })
+}
+
+fn progress_stage(status: &str, confirmations: u64, target: u64) -> &'static str {
+    match status {
+        "pending" => "pending",
+        "reverted" => "reverted",
+        _ if confirmations >= target => "confirmed",
+        _ => "mined",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_raw(tx: TypedTransaction) -> (Bytes, Address) {
+        let wallet: LocalWallet = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(1u64);
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        (tx.rlp_signed(&signature), wallet.address())
+    }
+
+    #[test]
+    fn test_decode_signed_transaction() {
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .to(Address::repeat_byte(0x22))
+            .value(U256::exp10(17))
+            .nonce(5)
+            .gas(21_000)
+            .max_fee_per_gas(30_000_000_000u64)
+            .max_priority_fee_per_gas(1_000_000_000u64)
+            .chain_id(1)
+            .into();
+        tx.set_data(Bytes::new());
+        let (raw, address) = signed_raw(tx);
+
+        let signed = decode_signed_transaction(&raw).unwrap();
+        assert_eq!(signed.from, address);
+        assert_eq!(signed.tx.to_addr(), Some(&Address::repeat_byte(0x22)));
+        assert_eq!(signed.tx.nonce(), Some(&U256::from(5)));
+        assert_eq!(signed.tx.chain_id(), Some(U64::from(1)));
+        assert_eq!(signed.hash, H256::from(ethers::utils::keccak256(&raw)));
+
+        // legacy 交易
+        let legacy: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(0x33))
+            .nonce(1)
+            .gas(21_000)
+            .gas_price(20_000_000_000u64)
+            .chain_id(1)
+            .into();
+        let (raw, address) = signed_raw(legacy);
+        assert_eq!(decode_signed_transaction(&raw).unwrap().from, address);
+
+        assert!(decode_signed_transaction(&Bytes::new()).is_err());
+        assert!(decode_signed_transaction(&Bytes::from(vec![0x02, 0xde, 0xad])).is_err());
+    }
+
+    #[test]
+    fn test_progress_helpers() {
+        assert_eq!(progress_stage("pending", 0, 3), "pending");
+        assert_eq!(progress_stage("success", 1, 3), "mined");
+        assert_eq!(progress_stage("success", 3, 3), "confirmed");
+        assert_eq!(progress_stage("reverted", 1, 3), "reverted");
+    }
+}
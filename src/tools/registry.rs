@@ -0,0 +1,221 @@
+use crate::{
+    config::Config,
+    erc20::Erc20Client,
+    logging::info,
+    token_registry::TokenRegistry,
+    types::{checksum_address, parse_address, TokenInfo},
+};
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 别名最大长度
+const MAX_ALIAS_LEN: usize = 32;
+
+/// RegisterToken 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct RegisterTokenArgs {
+    /// ERC20 代币合约地址(必需)
+    pub address: String,
+    /// 注册使用的符号别名(可选,默认使用链上 symbol)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// 别名已指向其他地址时是否覆盖(可选,默认 false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overwrite: Option<bool>,
+}
+
+/// RegisterToken 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RegisterTokenResult {
+    /// 注册后的代币信息(symbol 为别名)
+    pub token: TokenInfo,
+    /// 链上 symbol
+    pub onchain_symbol: String,
+    /// 被覆盖的原有代币
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced: Option<TokenInfo>,
+}
+
+/// RemoveToken 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct RemoveTokenArgs {
+    /// 要移除的代币符号或别名(必需)
+    pub symbol: String,
+}
+
+/// RemoveToken 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RemoveTokenResult {
+    pub removed: TokenInfo,
+}
+
+/// 读取链上元数据,把代币以指定别名注册到代币注册表
+pub async fn register_token(
+    config: &Arc<Config>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<RegisterTokenArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 register_token 请求");
+
+    let address = parse_address(&args.address).map_err(|e| McpError::invalid_params(e, None))?;
+    if address.is_zero() {
+        return Err(McpError::invalid_params("代币地址不能是零地址", None));
+    }
+    if let Some(ref alias) = args.alias {
+        validate_alias(alias)?;
+    }
+    let overwrite = args.overwrite.unwrap_or(false);
+
+    info!(address = ?address, alias = ?args.alias, overwrite, "注册代币");
+
+    // 测试模式:不读取链上元数据
+    let onchain = if config.server.test_mode {
+        TokenInfo {
+            symbol: "TEST".to_string(),
+            name: "Test Token".to_string(),
+            address: checksum_address(address),
+            decimals: 18,
+            listed_on: Vec::new(),
+        }
+    } else {
+        if !erc20_client.is_available() {
+            return Err(McpError::internal_error(
+                "ERC20 客户端不可用,请检查 RPC 配置",
+                None,
+            ));
+        }
+        erc20_client
+            .token_info(address)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?
+    };
+
+    let alias = match args.alias {
+        Some(alias) => alias.trim().to_string(),
+        None => {
+            validate_alias(&onchain.symbol).map_err(|_| {
+                McpError::invalid_params(
+                    format!("链上 symbol 无法作为符号使用: {:?},请指定 alias", onchain.symbol),
+                    None,
+                )
+            })?;
+            onchain.symbol.trim().to_string()
+        }
+    };
+
+    // 别名已指向其他地址时需要显式覆盖,避免误把常用符号解析到新合约
+    let existing = token_registry.resolve(&alias).ok();
+    if let Some(ref existing) = existing
+        && existing.address.to_lowercase() != onchain.address.to_lowercase()
+        && !overwrite
+    {
+        return Err(McpError::invalid_request(
+            format!("符号 {} 已指向 {},如需覆盖请传入 overwrite: true", alias, existing.address),
+            Some(serde_json::json!({
+                "refused": true,
+                "reason": "alias_exists",
+                "existing": existing,
+            })),
+        ));
+    }
+
+    let token = TokenInfo {
+        symbol: alias.clone(),
+        ..onchain.clone()
+    };
+    token_registry.register(alias, token.clone());
+
+    let result = RegisterTokenResult {
+        token,
+        onchain_symbol: onchain.symbol,
+        replaced: existing.filter(|existing| existing.address.to_lowercase() != onchain.address.to_lowercase()),
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(symbol = %result.token.symbol, "成功注册代币");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 从代币注册表移除代币
+pub async fn remove_token(
+    config: &Arc<Config>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<RemoveTokenArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 remove_token 请求");
+
+    let symbol = args.symbol.trim();
+    info!(symbol = %symbol, "移除代币");
+
+    if !token_registry.contains(symbol) {
+        return Err(McpError::invalid_params(format!("未注册的代币: {}", symbol), None));
+    }
+
+    // 原生代币别名和包装原生代币用于原生代币交换,不允许移除
+    let chain = config.chain();
+    if [chain.native_symbol, chain.wrapped_native_symbol]
+        .iter()
+        .any(|protected| protected.eq_ignore_ascii_case(symbol))
+    {
+        return Err(McpError::invalid_request(
+            format!("{} 是当前链的原生代币或包装原生代币,不能移除", symbol),
+            Some(serde_json::json!({
+                "refused": true,
+                "reason": "protected_token",
+            })),
+        ));
+    }
+
+    let removed = token_registry
+        .remove(symbol)
+        .ok_or_else(|| McpError::invalid_params(format!("未注册的代币: {}", symbol), None))?;
+
+    let result = RemoveTokenResult { removed };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(symbol = %result.removed.symbol, "成功移除代币");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 别名不能为空、包含空白或看起来像地址(否则会被当作地址解析)
+fn validate_alias(alias: &str) -> Result<(), McpError> {
+    let alias = alias.trim();
+    if alias.is_empty() || alias.len() > MAX_ALIAS_LEN {
+        return Err(McpError::invalid_params(
+            format!("别名长度必须在 1 到 {} 之间", MAX_ALIAS_LEN),
+            None,
+        ));
+    }
+    if alias.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(McpError::invalid_params(format!("别名不能包含空白: {:?}", alias), None));
+    }
+    if alias.starts_with("0x") || alias.eq_ignore_ascii_case("UNKNOWN") {
+        return Err(McpError::invalid_params(format!("别名不能以 0x 开头或使用 UNKNOWN: {}", alias), None));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_alias() {
+        assert!(validate_alias("MYTOKEN").is_ok());
+        assert!(validate_alias(" usdc.e ").is_ok());
+        assert!(validate_alias("").is_err());
+        assert!(validate_alias("MY TOKEN").is_err());
+        assert!(validate_alias("0x1234").is_err());
+        assert!(validate_alias("unknown").is_err());
+        assert!(validate_alias(&"A".repeat(MAX_ALIAS_LEN + 1)).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{
+    compliance::{ComplianceScreen, ScreeningDecision},
+    config::Config,
+    eth_client::EthClient,
+    logging::info,
+    relay::{
+        Erc2771Request, GelatoRelayClient, RelayMode, RelayTaskStatus, GELATO_ERC2771_RELAY,
+        NATIVE_FEE_TOKEN,
+    },
+    signer::TxSigner,
+    store::Store,
+    token_registry::TokenRegistry,
+    tools::user_operation::token_address,
+    types::{checksum_address, parse_address, TxType},
+};
+use ethers::prelude::*;
+use ethers::utils::id;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// ERC-2771 签名默认有效期(秒)
+const DEFAULT_DEADLINE_SECS: u64 = 3600;
+
+/// RelayTransaction 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct RelayTransactionArgs {
+    /// 目标合约地址(必需)
+    pub target: String,
+    /// 调用数据(必需,0x 开头的十六进制)
+    pub data: String,
+    /// 中继模式(必需,sponsored / sync_fee / erc2771)
+    pub mode: String,
+    /// sync_fee 模式的手续费代币地址或符号(可选,默认原生 ETH)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_token: Option<String>,
+    /// erc2771 模式签名的有效期(秒,可选,默认 3600)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deadline_secs: Option<u64>,
+}
+
+/// RelayTransaction 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RelayTransactionResult {
+    pub task_id: String,
+    pub mode: String,
+    pub chain_id: u64,
+    pub target: String,
+    /// erc2771 模式下签名的用户地址
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_deadline: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_token: Option<String>,
+    /// 制裁名单命中但只标记时的筛查结论
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ScreeningDecision>,
+}
+
+/// GetRelayTaskStatus 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetRelayTaskStatusArgs {
+    /// relay_transaction 返回的任务 ID(必需)
+    pub task_id: String,
+}
+
+/// 通过 Gelato Relay 提交免 Gas 交易
+#[allow(clippy::too_many_arguments)]
+pub async fn relay_transaction(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    token_registry: &Arc<TokenRegistry>,
+    relay_client: &Arc<GelatoRelayClient>,
+    store: &Arc<Store>,
+    compliance: &Arc<ComplianceScreen>,
+    signer: Option<&TxSigner>,
+    Parameters(args): Parameters<RelayTransactionArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 relay_transaction 请求");
+
+    let mode = RelayMode::parse(&args.mode).map_err(|e| McpError::invalid_params(e, None))?;
+    let target = parse_address(&args.target)
+        .map_err(|e| McpError::invalid_params(e, None))?;
+    let data = args
+        .data
+        .parse::<Bytes>()
+        .map_err(|_| McpError::invalid_params(format!("无效的调用数据: {}", args.data), None))?;
+    let chain_id = config.ethereum.chain_id;
+
+    // 🛡️ 制裁名单筛查
+    let screening = compliance.screen(store, "relay_transaction", &[("target", target)])?;
+
+    info!(mode = mode.as_str(), target = %args.target, data_len = data.len(), "提交中继交易");
+
+    // 测试模式
+    if config.server.test_mode {
+        let result = RelayTransactionResult {
+            task_id: "0xtest".to_string(),
+            mode: mode.as_str().to_string(),
+            chain_id,
+            target: checksum_address(target),
+            user: None,
+            user_nonce: None,
+            user_deadline: None,
+            fee_token: None,
+            compliance: screening,
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:赞助模式需要 1Balance Key
+    if mode != RelayMode::SyncFee && !relay_client.has_api_key() {
+        return Err(McpError::invalid_params(
+            "sponsored 和 erc2771 模式需要配置 GELATO_API_KEY",
+            None,
+        ));
+    }
+
+    let mut result = RelayTransactionResult {
+        task_id: String::new(),
+        mode: mode.as_str().to_string(),
+        chain_id,
+        target: checksum_address(target),
+        user: None,
+        user_nonce: None,
+        user_deadline: None,
+        fee_token: None,
+        compliance: screening,
+    };
+
+    let eth_client = eth_client.clone();
+    let relay_client = relay_client.clone();
+
+    let task_id = match mode {
+        RelayMode::Sponsored => relay_client
+            .sponsored_call(chain_id, target, &data)
+            .await
+            .map_err(|e| McpError::internal_error(format!("提交中继交易失败: {}", e), None))?,
+
+        RelayMode::SyncFee => {
+            let fee_token: Address = match args.fee_token.as_deref() {
+                None => NATIVE_FEE_TOKEN.parse().expect("硬编码地址应该有效"),
+                Some(token) if token.eq_ignore_ascii_case("ETH") => {
+                    NATIVE_FEE_TOKEN.parse().expect("硬编码地址应该有效")
+                }
+                Some(token) => token_registry
+                    .resolve(token)
+                    .map_err(|e| McpError::invalid_params(format!("手续费代币无效: {}", e), None))
+                    .and_then(|info| token_address(&info))?,
+            };
+            result.fee_token = Some(checksum_address(fee_token));
+
+            relay_client
+                .call_with_sync_fee(chain_id, target, &data, fee_token)
+                .await
+                .map_err(|e| McpError::internal_error(format!("提交中继交易失败: {}", e), None))?
+        }
+
+        RelayMode::Erc2771 => {
+            let wallet = signer.ok_or_else(|| McpError::invalid_params("erc2771 模式需要配置签名器", None))?;
+            let deadline_secs = args.deadline_secs.unwrap_or(DEFAULT_DEADLINE_SECS);
+
+            let request = async {
+                let user_nonce = erc2771_user_nonce(&eth_client, wallet.address()).await?;
+                let (_, now) = eth_client
+                    .get_block_timestamp(BlockNumber::Latest)
+                    .await
+                    .map_err(|e| McpError::internal_error(format!("获取区块时间失败: {}", e), None))?;
+
+                Ok::<_, McpError>(Erc2771Request {
+                    chain_id,
+                    target,
+                    data,
+                    user: wallet.address(),
+                    user_nonce,
+                    user_deadline: now + deadline_secs,
+                })
+            }
+            .await?;
+
+            result.user = Some(checksum_address(request.user));
+            result.user_nonce = Some(request.user_nonce.to_string());
+            result.user_deadline = Some(request.user_deadline);
+
+            async {
+                let typed = request
+                    .typed_data()
+                    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+                let signature = wallet
+                    .sign_typed_data(&typed)
+                    .await
+                    .map_err(|e| McpError::internal_error(format!("签名失败: {}", e), None))?;
+
+                relay_client
+                    .sponsored_call_erc2771(&request, &signature)
+                    .await
+                    .map_err(|e| McpError::internal_error(format!("提交中继交易失败: {}", e), None))
+            }
+            .await?
+        }
+    };
+
+    result.task_id = task_id;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(task_id = %result.task_id, "中继交易已提交");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 查询 Gelato 中继任务状态
+pub async fn get_relay_task_status(
+    config: &Arc<Config>,
+    relay_client: &Arc<GelatoRelayClient>,
+    Parameters(args): Parameters<GetRelayTaskStatusArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_relay_task_status 请求");
+
+    // 测试模式
+    let status = if config.server.test_mode {
+        RelayTaskStatus {
+            task_id: args.task_id.clone(),
+            task_state: "ExecSuccess".to_string(),
+            transaction_hash: Some(format!("{:?}", H256::zero())),
+            block_number: Some(18_000_000),
+            last_check_message: None,
+        }
+    } else {
+        relay_client
+            .task_status(&args.task_id)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询中继任务状态失败: {}", e), None))?
+    };
+
+    let json_str = serde_json::to_string_pretty(&status)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(task_id = %status.task_id, state = %status.task_state, "成功返回中继任务状态");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 查询用户在 GelatoRelay1BalanceERC2771 上的 nonce(userNonce(address))
+async fn erc2771_user_nonce(eth_client: &EthClient, user: Address) -> Result<U256, McpError> {
+    let mut data = id("userNonce(address)").to_vec();
+    data.extend_from_slice(&[0u8; 12]);
+    data.extend_from_slice(user.as_bytes());
+
+    let mut tx = TxType::Eip1559.new_request();
+    tx.set_to(GELATO_ERC2771_RELAY.parse::<Address>().expect("硬编码地址应该有效"))
+        .set_data(Bytes::from(data));
+
+    let output = eth_client
+        .call(&tx)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询 ERC-2771 nonce 失败: {}", e), None))?;
+    if output.len() < 32 {
+        return Err(McpError::internal_error("ERC-2771 nonce 返回数据长度异常", None));
+    }
+    Ok(U256::from_big_endian(&output[..32]))
+}
@@ -0,0 +1,313 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    eth_client::EthClient,
+    export::{self, CsvExport, ExportFormat},
+    logging::info,
+    tools::price::calculate_price_ratio,
+    types::{checksum_address, parse_address, TokenInfo},
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 单次查询允许的最大采样点数量（每个采样点对应一次归档 eth_call）
+const MAX_RESERVE_SAMPLES: u64 = 100;
+
+/// GetReserveHistory 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetReserveHistoryArgs {
+    /// Uniswap V2 交易对地址(必需)
+    pub pair: String,
+    /// 起始区块号(必需)
+    pub from_block: u64,
+    /// 结束区块号(可选,默认最新区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_block: Option<u64>,
+    /// 采样间隔(区块数,可选,默认按最多 100 个采样点自动计算)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<u64>,
+    /// 导出格式(可选,json/csv,默认 json)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub export: Option<String>,
+}
+
+/// GetReserveHistory 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ReserveHistoryResult {
+    pub pair: String,
+    pub token0: TokenInfo,
+    pub token1: TokenInfo,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub step: u64,
+    pub samples: Vec<ReserveSample>,
+}
+
+/// 单个区块的储备量采样
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ReserveSample {
+    pub block_number: u64,
+    /// token0 储备量(已格式化)
+    pub reserve0: String,
+    /// token1 储备量(已格式化)
+    pub reserve1: String,
+    /// 以 token1 计价的 token0 价格
+    pub price0: String,
+    /// 以 token0 计价的 token1 价格
+    pub price1: String,
+}
+
+/// 按区间采样交易对储备量历史(需要归档节点)
+pub async fn get_reserve_history(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    Parameters(args): Parameters<GetReserveHistoryArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_reserve_history 请求");
+
+    let export_format = ExportFormat::parse(args.export.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    info!(
+        pair = %args.pair,
+        from_block = args.from_block,
+        to_block = ?args.to_block,
+        step = ?args.step,
+        "查询储备量历史"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let to_block = args.to_block.unwrap_or(args.from_block + 200);
+        let (blocks, step) = sample_blocks(args.from_block, to_block, args.step)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let samples = blocks
+            .into_iter()
+            .map(|block_number| ReserveSample {
+                block_number,
+                reserve0: "1000000".to_string(),
+                reserve1: "500".to_string(),
+                price0: "0.0005".to_string(),
+                price1: "2000".to_string(),
+            })
+            .collect();
+
+        let result = ReserveHistoryResult {
+            pair: args.pair.clone(),
+            token0: TokenInfo {
+                symbol: "TEST0".to_string(),
+                name: "Test Token 0".to_string(),
+                address: "0x0000000000000000000000000000000000000001".to_string(),
+                decimals: 18,
+                listed_on: Vec::new(),
+            },
+            token1: TokenInfo {
+                symbol: "TEST1".to_string(),
+                name: "Test Token 1".to_string(),
+                address: "0x0000000000000000000000000000000000000002".to_string(),
+                decimals: 18,
+                listed_on: Vec::new(),
+            },
+            from_block: args.from_block,
+            to_block,
+            step,
+            samples,
+        };
+
+        return export::render(&result, export_format);
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Uniswap 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let pair_addr = parse_address(&args.pair)
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let eth_client = eth_client.clone();
+    let uniswap_client = uniswap_client.clone();
+    let erc20_client = erc20_client.clone();
+
+    let result = async {
+        let to_block = match args.to_block {
+            Some(block) => block,
+            None => eth_client.get_block_number().await.map_err(|e| {
+                McpError::internal_error(format!("查询最新区块失败: {}", e), None)
+            })?,
+        };
+
+        let (blocks, step) = sample_blocks(args.from_block, to_block, args.step)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let (token0_addr, token1_addr) = uniswap_client
+            .get_pair_tokens(pair_addr)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询交易对代币失败: {}", e), None))?;
+
+        // 两个代币的信息通过 Multicall3 一次查询
+        let [token0, token1]: [TokenInfo; 2] = erc20_client
+            .tokens_info(&[token0_addr, token1_addr])
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?
+            .try_into()
+            .map_err(|_| McpError::internal_error("代币信息数量不符", None))?;
+
+        // 并发查询各采样区块的储备量
+        let mut tasks = tokio::task::JoinSet::new();
+        for block_number in blocks {
+            let client = (*uniswap_client).clone();
+            tasks.spawn(async move {
+                let reserves = client
+                    .get_reserves_at(pair_addr, Some(BlockId::from(block_number)))
+                    .await;
+                (block_number, reserves)
+            });
+        }
+
+        let mut samples = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            let (block_number, reserves) = joined
+                .map_err(|e| McpError::internal_error(format!("采样任务失败: {}", e), None))?;
+            let (reserve0, reserve1) = reserves.map_err(|e| {
+                McpError::internal_error(
+                    format!("查询区块 {} 的储备量失败(可能需要归档节点): {}", block_number, e),
+                    None,
+                )
+            })?;
+
+            samples.push(ReserveSample {
+                block_number,
+                reserve0: format_units(reserve0, token0.decimals),
+                reserve1: format_units(reserve1, token1.decimals),
+                price0: calculate_price_ratio(reserve1, reserve0, token0.decimals, token1.decimals),
+                price1: calculate_price_ratio(reserve0, reserve1, token1.decimals, token0.decimals),
+            });
+        }
+        samples.sort_by_key(|s| s.block_number);
+
+        Ok::<_, McpError>(ReserveHistoryResult {
+            pair: checksum_address(pair_addr),
+            token0,
+            token1,
+            from_block: args.from_block,
+            to_block,
+            step,
+            samples,
+        })
+    }
+    .await?;
+
+    info!("成功返回储备量历史");
+
+    export::render(&result, export_format)
+}
+
+impl CsvExport for ReserveHistoryResult {
+    fn csv_headers(&self) -> Vec<&'static str> {
+        vec!["block_number", "reserve0", "reserve1", "price0", "price1"]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        self.samples
+            .iter()
+            .map(|s| {
+                vec![
+                    s.block_number.to_string(),
+                    s.reserve0.clone(),
+                    s.reserve1.clone(),
+                    s.price0.clone(),
+                    s.price1.clone(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// 计算采样区块列表
+/// 未指定 step 时按 MAX_RESERVE_SAMPLES 自动计算间隔，结束区块总是包含在内
+fn sample_blocks(from_block: u64, to_block: u64, step: Option<u64>) -> Result<(Vec<u64>, u64), String> {
+    if from_block > to_block {
+        return Err(format!(
+            "起始区块 {} 不能大于结束区块 {}",
+            from_block, to_block
+        ));
+    }
+
+    let range = to_block - from_block;
+    let step = match step {
+        Some(0) => return Err("采样间隔必须大于 0".to_string()),
+        Some(step) => step,
+        None => range.div_ceil(MAX_RESERVE_SAMPLES - 1).max(1),
+    };
+
+    let mut blocks: Vec<u64> = (from_block..=to_block).step_by(step as usize).collect();
+    if blocks.last() != Some(&to_block) {
+        blocks.push(to_block);
+    }
+
+    if blocks.len() as u64 > MAX_RESERVE_SAMPLES {
+        return Err(format!(
+            "采样点过多: {} (最多 {} 个),请增大 step 或缩小区块范围",
+            blocks.len(),
+            MAX_RESERVE_SAMPLES
+        ));
+    }
+
+    Ok((blocks, step))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_blocks_with_step() {
+        let (blocks, step) = sample_blocks(100, 130, Some(10)).unwrap();
+        assert_eq!(step, 10);
+        assert_eq!(blocks, vec![100, 110, 120, 130]);
+
+        // 结束区块不在步长上时也要包含
+        let (blocks, _) = sample_blocks(100, 125, Some(10)).unwrap();
+        assert_eq!(blocks, vec![100, 110, 120, 125]);
+    }
+
+    #[test]
+    fn test_sample_blocks_auto_step() {
+        let (blocks, step) = sample_blocks(0, 9900, None).unwrap();
+        assert_eq!(step, 100);
+        assert!(blocks.len() as u64 <= MAX_RESERVE_SAMPLES);
+        assert_eq!(*blocks.last().unwrap(), 9900);
+
+        // 单个区块
+        let (blocks, step) = sample_blocks(5, 5, None).unwrap();
+        assert_eq!(step, 1);
+        assert_eq!(blocks, vec![5]);
+    }
+
+    #[test]
+    fn test_sample_blocks_errors() {
+        assert!(sample_blocks(200, 100, None).is_err());
+        assert!(sample_blocks(100, 200, Some(0)).is_err());
+        assert!(sample_blocks(0, 1000, Some(1)).is_err());
+    }
+
+    #[test]
+    fn test_get_reserve_history_args_deserialization() {
+        let json = r#"{"pair":"0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc","from_block":100}"#;
+        let args: GetReserveHistoryArgs = serde_json::from_str(json).expect("应该能反序列化");
+        assert_eq!(args.from_block, 100);
+        assert_eq!(args.to_block, None);
+        assert_eq!(args.step, None);
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    bindings::{find_known_function, function_signature},
+    config::Config,
+    erc20::parse_units,
+    eth_client::{EthClient, EthClientError},
+    logging::{info, warn},
+    tools::calldata::{decoded_arguments, DecodedArgument},
+    types::{checksum_address, parse_address, TxType},
+    uniswap::{decode_revert_data, extract_revert_reason, revert_data},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// SimulateTransaction 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SimulateTransactionArgs {
+    /// 目标合约或接收地址(必需)
+    pub to: String,
+    /// 发送方地址(可选,默认使用配置的模拟地址)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// 发送的 ETH 数量(可选,如 "0.1",默认 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// 调用数据(可选,0x 开头的十六进制)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// 在指定区块上模拟(可选,默认最新区块,历史区块需要归档节点)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+}
+
+/// SimulateTransaction 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SimulateTransactionResult {
+    pub from: String,
+    pub to: String,
+    /// 模拟使用的区块(最新区块时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    pub success: bool,
+    /// 原始返回数据
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_data: Option<String>,
+    /// 识别出的函数签名(ERC20、Uniswap V2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// 按已知 ABI 解码的返回值
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decoded_return: Vec<DecodedArgument>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_estimate: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    /// 原始 revert 数据(自定义错误可用 decode_calldata 解码)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_data: Option<String>,
+}
+
+/// 以 eth_call 模拟任意交易,成功时估算 Gas 并解码返回值,失败时返回 revert 原因
+pub async fn simulate_transaction(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    Parameters(args): Parameters<SimulateTransactionArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 simulate_transaction 请求");
+
+    let to = parse_address(&args.to).map_err(|e| McpError::invalid_params(e, None))?;
+
+    let from: Address = match args.from {
+        Some(ref addr) => parse_address(addr).map_err(|e| McpError::invalid_params(e, None))?,
+        None => config.get_simulation_address(),
+    };
+
+    let data = match args.data {
+        Some(ref hex) => hex
+            .parse::<Bytes>()
+            .map_err(|_| McpError::invalid_params(format!("无效的调用数据: {}", hex), None))?,
+        None => Bytes::new(),
+    };
+
+    let value = match args.value {
+        Some(ref value) => parse_units(value, 18)
+            .map_err(|e| McpError::invalid_params(format!("解析 ETH 数量失败: {}", e), None))?,
+        None => U256::zero(),
+    };
+
+    info!(
+        from = %format!("{:?}", from),
+        to = %args.to,
+        data_len = data.len(),
+        value = %value,
+        block = ?args.block_number,
+        "模拟交易"
+    );
+
+    let mut result = SimulateTransactionResult {
+        from: checksum_address(from),
+        to: checksum_address(to),
+        block_number: args.block_number,
+        success: true,
+        return_data: None,
+        method: data
+            .get(..4)
+            .and_then(find_known_function)
+            .map(|(_, function)| function_signature(function)),
+        decoded_return: Vec::new(),
+        gas_estimate: None,
+        revert_reason: None,
+        revert_data: None,
+    };
+
+    // 测试模式
+    if config.server.test_mode {
+        result.return_data = Some(Bytes::new().to_string());
+        result.gas_estimate = Some(U256::from(21_000u64).to_string());
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let mut tx = TxType::Eip1559.new_request();
+    tx.set_from(from).set_to(to).set_data(data.clone()).set_value(value);
+    let block = args.block_number.map(|n| BlockId::Number(n.into()));
+
+    match eth_client.call_at(&tx, block).await {
+        Ok(output) => {
+            result.decoded_return = decode_return_data(&data, &output);
+            result.return_data = Some(output.to_string());
+
+            // 调用成功,估算 Gas
+            result.gas_estimate = eth_client
+                .estimate_gas_at(&tx, block)
+                .await
+                .inspect_err(|e| warn!(error = %e, "Gas 估算失败"))
+                .ok()
+                .map(|gas| gas.to_string());
+        }
+        Err(EthClientError::ProviderError(e)) if RpcError::as_error_response(&e).is_some() => {
+            let revert = revert_data(&e);
+            result.success = false;
+            result.revert_reason = revert
+                .as_deref()
+                .and_then(decode_revert_data)
+                .or_else(|| extract_revert_reason(&e));
+            result.revert_data = revert.map(|revert| revert.to_string());
+        }
+        Err(e) => {
+            return Err(McpError::internal_error(format!("模拟交易失败: {}", e), None));
+        }
+    }
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(success = result.success, revert_reason = ?result.revert_reason, "成功模拟交易");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 按调用数据识别已知函数并解码返回值,无法解码时为空
+fn decode_return_data(data: &[u8], output: &[u8]) -> Vec<DecodedArgument> {
+    data.get(..4)
+        .and_then(find_known_function)
+        .and_then(|(_, function)| {
+            let tokens = function.decode_output(output).ok()?;
+            Some(decoded_arguments(&function.outputs, &tokens))
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindings::ierc20::BalanceOfCall;
+    use ethers::abi::AbiEncode;
+
+    #[test]
+    fn test_decode_return_data() {
+        let data = BalanceOfCall {
+            owner: Address::repeat_byte(0x11),
+        }
+        .encode();
+        let output = U256::exp10(18).encode();
+
+        let decoded = decode_return_data(&data, &output);
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].name, "arg0");
+        assert_eq!(decoded[0].kind, "uint256");
+        assert_eq!(decoded[0].value, "1000000000000000000");
+
+        // 返回数据不足或函数未知时不解码
+        assert!(decode_return_data(&data, &[]).is_empty());
+        assert!(decode_return_data(&[0xde, 0xad, 0xbe, 0xef], &output).is_empty());
+    }
+}
@@ -0,0 +1,445 @@
+use crate::{
+    chains::{self, ChainInfo},
+    config::Config,
+    erc20::Erc20Client,
+    eth_client::EthClient,
+    logging::info,
+    snapshot::{MarketSnapshot, PairSnapshot, SnapshotPrice, SnapshotStore},
+    token_registry::TokenRegistry,
+    tools::price::{calculate_price_ratio, multiply_price_strings},
+    types::{parse_address, TokenInfo},
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, tool, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 单个快照允许的最大交易对数量(含默认的 WETH/USDC)
+const MAX_SNAPSHOT_PAIRS: usize = 50;
+
+/// ExportMarketSnapshot 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ExportMarketSnapshotArgs {
+    /// 代币地址或符号列表(可选,导出各代币与 WETH 的交易对)
+    #[serde(default)]
+    pub tokens: Vec<String>,
+    /// 交易对地址列表(可选)
+    #[serde(default)]
+    pub pairs: Vec<String>,
+    /// 快照区块号(可选,默认最新区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// 快照保存路径(可选,不填时在结果中返回完整快照)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// ImportMarketSnapshot 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ImportMarketSnapshotArgs {
+    /// 快照文件路径(必需)
+    pub path: String,
+}
+
+/// 快照概要
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotSummary {
+    pub block_number: u64,
+    pub chain_id: u64,
+    pub exported_at: i64,
+    pub token_count: usize,
+    pub pair_count: usize,
+}
+
+impl SnapshotSummary {
+    fn of(snapshot: &MarketSnapshot) -> Self {
+        Self {
+            block_number: snapshot.block_number,
+            chain_id: snapshot.chain_id,
+            exported_at: snapshot.exported_at,
+            token_count: snapshot.tokens.len(),
+            pair_count: snapshot.pairs.len(),
+        }
+    }
+}
+
+/// ExportMarketSnapshot 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ExportMarketSnapshotResult {
+    #[serde(flatten)]
+    pub summary: SnapshotSummary,
+    pub prices: Vec<SnapshotPrice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 完整快照(未指定保存路径时返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<MarketSnapshot>,
+}
+
+/// ImportMarketSnapshot 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ImportMarketSnapshotResult {
+    #[serde(flatten)]
+    pub summary: SnapshotSummary,
+    pub prices: Vec<SnapshotPrice>,
+    /// 被替换的快照区块号(之前未加载快照时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_block: Option<u64>,
+}
+
+/// 导出指定区块的交易对储备量、价格和代币元数据
+pub async fn export_market_snapshot(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<ExportMarketSnapshotArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 export_market_snapshot 请求");
+
+    info!(
+        tokens = args.tokens.len(),
+        pairs = args.pairs.len(),
+        block = ?args.block_number,
+        "导出市场快照"
+    );
+
+    if args.tokens.is_empty() && args.pairs.is_empty() {
+        return Err(McpError::invalid_params("至少需要指定一个代币或交易对", None));
+    }
+
+    // 测试模式
+    if config.server.test_mode {
+        let snapshot = fixture_snapshot(args.block_number.unwrap_or(19_000_000), config.chain());
+        return render_export(snapshot, args.path);
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Uniswap 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let weth_addr = uniswap_client.weth_address();
+    let usdc_addr = uniswap_client.usdc_address();
+
+    // 始终包含 WETH/USDC 池子，用于 USD 报价
+    let mut pair_addrs = vec![uniswap_client.pair_address(weth_addr, usdc_addr)];
+
+    for token in &args.tokens {
+        let info = token_registry
+            .resolve(token)
+            .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+        let token_addr: Address = info
+            .address
+            .parse()
+            .map_err(|_| McpError::invalid_params(format!("无效的代币地址: {}", info.address), None))?;
+        if token_addr != weth_addr {
+            pair_addrs.push(uniswap_client.pair_address(token_addr, weth_addr));
+        }
+    }
+
+    for pair in &args.pairs {
+        let pair_addr = parse_address(pair)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        pair_addrs.push(pair_addr);
+    }
+
+    pair_addrs.sort();
+    pair_addrs.dedup();
+
+    if pair_addrs.len() > MAX_SNAPSHOT_PAIRS {
+        return Err(McpError::invalid_params(
+            format!("交易对过多: {} (最多 {} 个)", pair_addrs.len(), MAX_SNAPSHOT_PAIRS),
+            None,
+        ));
+    }
+
+    let eth_client = eth_client.clone();
+    let uniswap_client = uniswap_client.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+    let chain_id = config.ethereum.chain_id;
+
+    let snapshot = async {
+        // 所有储备量固定在同一区块读取，保证快照一致
+        let block_number = match args.block_number {
+            Some(block) => block,
+            None => eth_client.get_block_number().await.map_err(|e| {
+                McpError::internal_error(format!("查询最新区块失败: {}", e), None)
+            })?,
+        };
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for pair in pair_addrs {
+            let client = (*uniswap_client).clone();
+            tasks.spawn(async move {
+                let tokens = client.get_pair_tokens(pair).await;
+                let reserves = client.get_reserves_at(pair, Some(BlockId::from(block_number))).await;
+                (pair, tokens, reserves)
+            });
+        }
+
+        let mut pairs = Vec::new();
+        let mut token_addrs = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            let (pair, tokens, reserves) = joined
+                .map_err(|e| McpError::internal_error(format!("快照任务失败: {}", e), None))?;
+            let (token0, token1) = tokens.map_err(|e| {
+                McpError::internal_error(format!("查询交易对 {:?} 的代币失败: {}", pair, e), None)
+            })?;
+            let (reserve0, reserve1) = reserves.map_err(|e| {
+                McpError::internal_error(format!("查询交易对 {:?} 的储备量失败: {}", pair, e), None)
+            })?;
+
+            token_addrs.extend([token0, token1]);
+            pairs.push(PairSnapshot {
+                pair: format!("{:?}", pair),
+                token0: format!("{:?}", token0),
+                token1: format!("{:?}", token1),
+                reserve0: reserve0.to_string(),
+                reserve1: reserve1.to_string(),
+            });
+        }
+        pairs.sort_by(|a, b| a.pair.cmp(&b.pair));
+
+        token_addrs.sort();
+        token_addrs.dedup();
+
+        let mut tokens = Vec::with_capacity(token_addrs.len());
+        for token_addr in token_addrs {
+            tokens.push(token_metadata(&erc20_client, &token_registry, token_addr).await?);
+        }
+
+        Ok::<_, McpError>(MarketSnapshot {
+            block_number,
+            chain_id,
+            exported_at: chrono::Utc::now().timestamp(),
+            tokens,
+            pairs,
+            prices: Vec::new(),
+        })
+    }
+    .await?;
+
+    render_export(snapshot, args.path)
+}
+
+/// 导入快照文件并切换报价类工具到离线模式
+#[tool(description = "导入市场快照文件,之后 get_token_price 和 swap_tokens 基于快照储备量离线计算")]
+pub fn import_market_snapshot(
+    config: &Arc<Config>,
+    snapshots: &Arc<SnapshotStore>,
+    Parameters(args): Parameters<ImportMarketSnapshotArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 import_market_snapshot 请求");
+
+    info!(path = %args.path, "导入市场快照");
+
+    let snapshot = MarketSnapshot::load(&args.path)
+        .map_err(|e| McpError::invalid_params(format!("导入快照失败: {}", e), None))?;
+
+    if snapshot.chain_id != config.ethereum.chain_id {
+        return Err(McpError::invalid_params(
+            format!(
+                "快照的 Chain ID {} 与当前配置的 {} 不一致",
+                snapshot.chain_id, config.ethereum.chain_id
+            ),
+            None,
+        ));
+    }
+
+    let summary = SnapshotSummary::of(&snapshot);
+    let prices = snapshot_prices(&snapshot);
+    let previous_block = snapshots.replace(snapshot).map(|s| s.block_number);
+
+    let result = ImportMarketSnapshotResult {
+        summary,
+        prices,
+        previous_block,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(block = result.summary.block_number, "成功导入市场快照");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 计算快照中价格并保存或返回完整快照
+fn render_export(mut snapshot: MarketSnapshot, path: Option<String>) -> Result<CallToolResult, McpError> {
+    snapshot.prices = snapshot_prices(&snapshot);
+
+    if let Some(ref path) = path {
+        snapshot
+            .save(path)
+            .map_err(|e| McpError::internal_error(format!("保存快照失败: {}", e), None))?;
+    }
+
+    let result = ExportMarketSnapshotResult {
+        summary: SnapshotSummary::of(&snapshot),
+        prices: snapshot.prices.clone(),
+        snapshot: if path.is_none() { Some(snapshot) } else { None },
+        path,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(block = result.summary.block_number, pairs = result.summary.pair_count, "成功导出市场快照");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 查询代币元数据(优先使用注册表，未知代币查询链上并缓存)
+async fn token_metadata(
+    erc20_client: &Erc20Client,
+    token_registry: &TokenRegistry,
+    token_addr: Address,
+) -> Result<TokenInfo, McpError> {
+    if let Ok(info) = token_registry.resolve(&format!("{:?}", token_addr))
+        && info.symbol != "UNKNOWN"
+    {
+        return Ok(info);
+    }
+
+    let info = erc20_client
+        .token_info(token_addr)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询代币 {:?} 信息失败: {}", token_addr, e), None))?;
+
+    // 缓存到注册表
+    token_registry.register(info.symbol.clone(), info.clone());
+    Ok(info)
+}
+
+/// 基于快照储备量计算各代币价格(没有 WETH 交易对的代币跳过)
+/// WETH/USDC 地址取自快照所属链,不支持的链返回空列表
+fn snapshot_prices(snapshot: &MarketSnapshot) -> Vec<SnapshotPrice> {
+    let Some(chain) = chains::chain_info(snapshot.chain_id) else {
+        return Vec::new();
+    };
+    let weth_addr = chain.wrapped_native_address();
+    let usdc_addr = chain.usdc_address();
+
+    let eth_price_usd = snapshot
+        .reserves(weth_addr, usdc_addr)
+        .ok()
+        .map(|(_, weth_res, usdc_res)| calculate_price_ratio(usdc_res, weth_res, 18, 6));
+
+    snapshot
+        .tokens
+        .iter()
+        .filter_map(|token| {
+            let token_addr: Address = token.address.parse().ok()?;
+            let price_eth = if token_addr == weth_addr {
+                "1".to_string()
+            } else {
+                let (_, token_res, weth_res) = snapshot.reserves(token_addr, weth_addr).ok()?;
+                calculate_price_ratio(weth_res, token_res, token.decimals, 18)
+            };
+
+            Some(SnapshotPrice {
+                token: token.address.clone(),
+                symbol: token.symbol.clone(),
+                price_usd: eth_price_usd
+                    .as_ref()
+                    .map(|eth_price| multiply_price_strings(&price_eth, eth_price)),
+                price_eth,
+            })
+        })
+        .collect()
+}
+
+/// 测试模式使用的快照(WETH/USDC 池子 100 WETH / 300000 USDC)
+fn fixture_snapshot(block_number: u64, chain: &ChainInfo) -> MarketSnapshot {
+    let usdc = (chain.usdc_address(), "300000000000");
+    let weth = (chain.wrapped_native_address(), "100000000000000000000");
+    let (token0, token1) = if usdc.0 < weth.0 { (usdc, weth) } else { (weth, usdc) };
+
+    MarketSnapshot {
+        block_number,
+        chain_id: chain.chain_id,
+        exported_at: chrono::Utc::now().timestamp(),
+        tokens: vec![
+            TokenInfo {
+                symbol: "USDC".to_string(),
+                name: "USD Coin".to_string(),
+                address: chain.usdc.to_string(),
+                decimals: 6,
+                listed_on: Vec::new(),
+            },
+            TokenInfo {
+                symbol: chain.wrapped_native_symbol.to_string(),
+                name: chain.wrapped_native_name.to_string(),
+                address: chain.wrapped_native.to_string(),
+                decimals: 18,
+                listed_on: Vec::new(),
+            },
+        ],
+        pairs: vec![PairSnapshot {
+            pair: format!("{:?}", UniswapV2Client::new(None, chain).pair_address(token0.0, token1.0)),
+            token0: format!("{:?}", token0.0),
+            token1: format!("{:?}", token1.0),
+            reserve0: token0.1.to_string(),
+            reserve1: token1.1.to_string(),
+        }],
+        prices: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_prices() {
+        let prices = snapshot_prices(&fixture_snapshot(1, &chains::MAINNET));
+        assert_eq!(prices.len(), 2);
+
+        let weth = prices.iter().find(|p| p.symbol == "WETH").unwrap();
+        assert_eq!(weth.price_eth, "1");
+        let eth_usd: f64 = weth.price_usd.as_deref().unwrap().parse().unwrap();
+        assert!((eth_usd - 3000.0).abs() < 0.01);
+
+        let usdc = prices.iter().find(|p| p.symbol == "USDC").unwrap();
+        let usdc_usd: f64 = usdc.price_usd.as_deref().unwrap().parse().unwrap();
+        assert!((usdc_usd - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_snapshot_prices_follow_chain() {
+        // Polygon 上 WPOL 地址小于 USDC,交易对代币顺序与主网相反
+        let prices = snapshot_prices(&fixture_snapshot(1, &chains::POLYGON));
+        let wpol = prices.iter().find(|p| p.symbol == "WPOL").unwrap();
+        let pol_usd: f64 = wpol.price_usd.as_deref().unwrap().parse().unwrap();
+        assert!((pol_usd - 3000.0).abs() < 0.01);
+
+        let mut snapshot = fixture_snapshot(1, &chains::MAINNET);
+        snapshot.chain_id = 5;
+        assert!(snapshot_prices(&snapshot).is_empty());
+    }
+
+    #[test]
+    fn test_export_then_import_snapshot() {
+        let path = std::env::temp_dir().join(format!("market-snapshot-{}.json", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+
+        let exported = render_export(fixture_snapshot(19_000_000, &chains::MAINNET), Some(path.clone())).unwrap();
+        let text = exported.content[0].as_text().unwrap().text.clone();
+        let exported: ExportMarketSnapshotResult = serde_json::from_str(&text).unwrap();
+        assert_eq!(exported.summary.pair_count, 1);
+        assert!(exported.snapshot.is_none());
+
+        let loaded = MarketSnapshot::load(&path).unwrap();
+        assert_eq!(loaded.block_number, 19_000_000);
+        assert_eq!(loaded.prices.len(), 2);
+        std::fs::remove_file(&path).ok();
+    }
+}
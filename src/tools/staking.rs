@@ -0,0 +1,188 @@
+use crate::{config::Config, logging::info, staking::StakingClient};
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// GetStakingApr 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetStakingAprArgs {
+    /// 是否包含 Beacon API 共识层估算(可选,默认在配置了 BEACON_API_URL 时包含)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_beacon: Option<bool>,
+}
+
+/// GetStakingApr 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct StakingAprResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lido: Option<LidoAprInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub beacon_estimate: Option<BeaconAprInfo>,
+    /// 数据源不可用时的说明
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// Lido stETH APR(基于最近一次 oracle 报告)
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct LidoAprInfo {
+    pub apr: String,
+    pub report_timestamp: u64,
+    pub time_elapsed_seconds: u64,
+    pub block_number: u64,
+    pub source: String,
+}
+
+/// 共识层收益估算
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BeaconAprInfo {
+    pub consensus_apr: String,
+    pub active_validators: u64,
+    pub total_staked_eth: String,
+    pub source: String,
+}
+
+/// 获取 ETH 质押年化收益(Lido oracle + Beacon API 估算)
+pub async fn get_staking_apr(
+    config: &Arc<Config>,
+    staking_client: &Arc<StakingClient>,
+    Parameters(args): Parameters<GetStakingAprArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_staking_apr 请求");
+
+    let include_beacon = args.include_beacon.unwrap_or(true);
+
+    // 测试模式
+    if config.server.test_mode {
+        let result = StakingAprResult {
+            lido: Some(LidoAprInfo {
+                apr: "3.10%".to_string(),
+                report_timestamp: 1_700_000_000,
+                time_elapsed_seconds: 86_400,
+                block_number: 20_000_000,
+                source: "Test Mode".to_string(),
+            }),
+            beacon_estimate: include_beacon.then(|| BeaconAprInfo {
+                consensus_apr: "2.85%".to_string(),
+                active_validators: 1_062_500,
+                total_staked_eth: "34000000".to_string(),
+                source: "Test Mode".to_string(),
+            }),
+            notes: vec![],
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    let mut notes = Vec::new();
+    let staking_client = staking_client.clone();
+    let query_beacon = include_beacon && staking_client.has_beacon_api();
+
+    if include_beacon && !staking_client.has_beacon_api() {
+        notes.push("未配置 BEACON_API_URL,跳过共识层估算".to_string());
+    }
+
+    // Lido 仅部署在以太坊主网
+    let query_lido = config.ethereum.chain_id == 1;
+    if !query_lido {
+        notes.push(format!(
+            "Lido 仅支持以太坊主网,当前 Chain ID: {}",
+            config.ethereum.chain_id
+        ));
+    }
+
+    let (lido_res, beacon_res) = tokio::join!(
+        async {
+            if query_lido {
+                Some(staking_client.lido_apr().await)
+            } else {
+                None
+            }
+        },
+        async {
+            if query_beacon {
+                Some(staking_client.beacon_apr_estimate().await)
+            } else {
+                None
+            }
+        }
+    );
+
+    let lido = match lido_res {
+        Some(Ok(apr)) => Some(LidoAprInfo {
+            apr: format!("{:.2}%", apr.apr_percent),
+            report_timestamp: apr.report_timestamp,
+            time_elapsed_seconds: apr.time_elapsed,
+            block_number: apr.block_number,
+            source: "Lido TokenRebased".to_string(),
+        }),
+        Some(Err(e)) => {
+            notes.push(format!("查询 Lido APR 失败: {}", e));
+            None
+        }
+        None => None,
+    };
+
+    let beacon_estimate = match beacon_res {
+        Some(Ok(estimate)) => Some(BeaconAprInfo {
+            consensus_apr: format!("{:.2}%", estimate.consensus_apr_percent),
+            active_validators: estimate.active_validators,
+            total_staked_eth: format!("{:.0}", estimate.total_staked_eth),
+            source: "Beacon API".to_string(),
+        }),
+        Some(Err(e)) => {
+            notes.push(format!("查询 Beacon API 失败: {}", e));
+            None
+        }
+        None => None,
+    };
+
+    if lido.is_none() && beacon_estimate.is_none() {
+        return Err(McpError::internal_error(
+            format!("无法获取质押收益: {}", notes.join("; ")),
+            None,
+        ));
+    }
+
+    let result = StakingAprResult {
+        lido,
+        beacon_estimate,
+        notes,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!("成功返回质押收益");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staking_apr_result_serialization() {
+        let result = StakingAprResult {
+            lido: Some(LidoAprInfo {
+                apr: "3.10%".to_string(),
+                report_timestamp: 1,
+                time_elapsed_seconds: 86_400,
+                block_number: 2,
+                source: "Lido TokenRebased".to_string(),
+            }),
+            beacon_estimate: None,
+            notes: vec![],
+        };
+
+        let json = serde_json::to_string(&result).expect("应该能序列化");
+        assert!(json.contains("3.10%"));
+        assert!(!json.contains("beacon_estimate"));
+        assert!(!json.contains("notes"));
+    }
+}
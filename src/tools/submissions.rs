@@ -0,0 +1,77 @@
+use crate::{
+    config::Config,
+    logging::info,
+    tx_manager::{TrackedTransaction, TxManager, TxStatus},
+    types::{checksum_address, parse_address},
+};
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// GetSubmittedTransactions 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetSubmittedTransactionsArgs {
+    /// 只返回该钱包提交的交易(可选)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+    /// 只返回待确认的交易(可选,默认 false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_only: Option<bool>,
+}
+
+/// GetSubmittedTransactions 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SubmittedTransactionsResult {
+    /// 执行类工具等待的确认数(TX_CONFIRMATIONS)
+    pub target_confirmations: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+    /// 本地记录的下一个 nonce(指定钱包且提交过交易时返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_nonce: Option<u64>,
+    pub pending: usize,
+    pub transactions: Vec<TrackedTransaction>,
+}
+
+/// 查询本次运行中执行类工具提交的交易及其最新状态
+pub async fn get_submitted_transactions(
+    config: &Arc<Config>,
+    tx_manager: &Arc<TxManager>,
+    Parameters(args): Parameters<GetSubmittedTransactionsArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_submitted_transactions 请求");
+
+    let wallet = args
+        .wallet
+        .as_deref()
+        .map(parse_address)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(e, None))?;
+    let pending_only = args.pending_only.unwrap_or(false);
+
+    // 测试模式不会真实提交交易,记录为空,也不刷新回执
+    let mut transactions = if config.server.test_mode {
+        Vec::new()
+    } else {
+        tx_manager.transactions(wallet).await
+    };
+    if pending_only {
+        transactions.retain(|tx| tx.status == TxStatus::Pending);
+    }
+
+    let result = SubmittedTransactionsResult {
+        target_confirmations: tx_manager.confirmations(),
+        wallet: wallet.map(checksum_address),
+        next_nonce: wallet.and_then(|wallet| tx_manager.next_nonce(wallet)),
+        pending: transactions.iter().filter(|tx| tx.status == TxStatus::Pending).count(),
+        transactions,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(count = result.transactions.len(), pending = result.pending, "成功返回已提交的交易");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
@@ -1,15 +1,33 @@
 use crate::{
+    compliance::{ComplianceScreen, ScreeningDecision},
     config::Config,
+    curve::{CurveClient, CurveQuote},
     erc20::{format_units, parse_units, Erc20Client},
-    logging::info,
+    eth_client::EthClient,
+    etherscan::EtherscanClient,
+    tools::contract_verification::ensure_verified_contract,
+    tools::preview::{collect_deltas, DeltaMap},
+    logging::{info, warn},
+    mempool::{MempoolWatcher, SlippageAdvice},
+    mev::{self, max_safe_slippage_bps, sandwich_activity, sandwich_profit, HopState, MevRisk, SANDWICH_GAS, SANDWICH_LOOKBACK_BLOCKS},
+    quoting::{AggregatorQuote, AggregatorSource, QuoteRequest, NATIVE_TOKEN_ADDRESS},
+    tools::price::{calculate_price_ratio, fetch_token_price_usd_at, fetch_tokens_per_eth},
+    snapshot::{MarketSnapshot, SnapshotStore},
+    store::{NewRecord, RecordKind, Store},
+    token_lists::TokenListClient,
     token_registry::TokenRegistry,
-    types::TokenInfo,
-    uniswap::UniswapV2Client,
+    types::{checksum_address, parse_address, TokenInfo, TxType},
+    uniswap::{
+        fee_on_transfer_function, is_fee_on_transfer_revert, router_function, select_best_route, NativeLeg,
+        PathQuote, SwapCall, SwapQuote, UniswapV2Client,
+    },
 };
 use ethers::prelude::*;
 use rmcp::{
-    handler::server::wrapper::Parameters, model::*, schemars, tool, ErrorData as McpError,
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
 };
+use rust_decimal::Decimal;
+use std::str::FromStr;
 use std::sync::Arc;
 
 /// SwapTokens 工具的参数
@@ -19,7 +37,7 @@ pub struct SwapTokensArgs {
     pub from_token: String,
     /// 目标代币地址或符号(必需)
     pub to_token: String,
-    /// 交易数量(必需)
+    /// 交易数量(必需,exact_output 模式下为期望得到的目标代币数量)
     pub amount: String,
     /// 滑点(基点,默认 50 = 0.5%)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -27,6 +45,25 @@ pub struct SwapTokensArgs {
     /// 钱包地址(用于 Gas 估算,可选)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub wallet_address: Option<String>,
+    /// 交易类型(可选,auto/legacy/eip1559,默认使用 TX_TYPE 配置)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<String>,
+    /// 允许的最大价格影响(基点,可选,默认使用 MAX_PRICE_IMPACT_BPS 配置,0 表示不限制)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_price_impact_bps: Option<u32>,
+    /// 跳过储备量缓存,强制从链上读取(可选,默认 false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_refresh: Option<bool>,
+    /// 为 true 时按指定输出数量反推所需输入(swapTokensForExactTokens,可选,默认 false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exact_output: Option<bool>,
+    /// 转账税代币(可选,true 时直接使用 SupportingFeeOnTransferTokens 函数,false 关闭自动检测;
+    /// 默认在标准调用因 UniswapV2: K 回滚时自动切换)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_on_transfer: Option<bool>,
+    /// 允许目标代币为未在 Etherscan 验证源码的合约(可选,默认 false,需用户明确确认)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_unverified: Option<bool>,
 }
 
 /// SwapTokens 工具的返回结果
@@ -34,16 +71,106 @@ pub struct SwapTokensArgs {
 pub struct SwapSimulationResult {
     pub from_token: TokenInfo,
     pub to_token: TokenInfo,
+    /// 交换模式(exact_input/exact_output)
+    pub mode: String,
+    /// exact_output 模式下为按储备量计算的所需输入
     pub input_amount: String,
     pub estimated_output: String,
     pub minimum_output: String,
+    /// 计入滑点后愿意支付的最大输入(仅 exact_output 模式)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum_input: Option<String>,
+    /// 整条路径的价格影响:(中间价 - 成交价) / 中间价,包含 0.3% 手续费
     pub price_impact: String,
+    /// 每一跳的价格影响(与 route.pools 一一对应)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub price_impact_per_hop: Vec<String>,
     pub route: SwapRoute,
     pub simulation_success: bool,
+    /// 模拟调用的 Router 函数(原生代币一侧使用 ETH 版本,如 swapExactETHForTokens)
+    pub router_function: String,
+    /// 模拟使用的交易类型(legacy/eip1559)
+    pub tx_type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gas_estimate: Option<String>,
+    /// 按 GAS_PRICE_STRATEGY 估算的交换 Gas 费用(原生代币,不含 approve)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_cost_eth: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_cost_usd: Option<String>,
+    /// 预估输出扣除按目标代币折算的 Gas 费用后的净输出(为负表示 Gas 费用超过交换所得)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub net_output_after_gas: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub revert_reason: Option<String>,
+    /// 钱包是否需要先授权 Router(查询授权额度失败时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_required: Option<bool>,
+    /// 钱包当前对 Router 的授权额度(已格式化)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_allowance: Option<String>,
+    /// approve 交易的预估 Gas(仅在需要授权时返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approve_gas_estimate: Option<String>,
+    /// 钱包原生代币余额(已格式化,仅支付原生代币时返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub native_balance: Option<String>,
+    /// 原生代币余额是否足以支付交易 value(不含 Gas 费)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub native_balance_sufficient: Option<bool>,
+    /// 是否按转账税代币模拟(使用 SupportingFeeOnTransferTokens 函数)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_on_transfer: Option<bool>,
+    /// 扣除转账税后接收方实际到账的数量(需要节点支持 debug_traceCall)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_output: Option<String>,
+    /// 实际到账相对报价的损耗(基点,包含输入和输出两侧的转账税)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer_fee_bps: Option<u32>,
+    /// 离线模式下报价所用快照的区块号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_block: Option<u64>,
+    /// 基于内存池竞争交易的滑点建议(启用 MEMPOOL_WATCH 时返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slippage_advice: Option<SlippageAdvice>,
+    /// 三明治攻击风险评估(基于路径储备量和近期 Swap 事件本地计算)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mev_risk: Option<MevRisk>,
+    /// 制裁名单命中但只标记时的筛查结论
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ScreeningDecision>,
+    /// 其他场所对同一交换的报价(如稳定币交易对的 Curve 报价)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alternative_quotes: Vec<AlternativeQuote>,
+}
+
+/// 其他场所的报价
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AlternativeQuote {
+    pub venue: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool: Option<String>,
+    pub estimated_output: String,
+    /// 是否优于本次模拟的 Uniswap V2 报价
+    pub better: bool,
+    // 以下字段仅聚合器的可执行报价返回
+    /// 成交价(每单位源代币可得的目标代币)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<String>,
+    /// 保证得到的最少输出(已计入聚合器滑点)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guaranteed_output: Option<String>,
+    /// 源代币需要授权给的地址
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowance_target: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calldata: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_estimate: Option<String>,
 }
 
 /// 交换路径信息
@@ -52,15 +179,43 @@ pub struct SwapRoute {
     pub protocol: String,
     pub path: Vec<String>,
     pub pools: Vec<String>,
+    /// 路由搜索比较过的所有候选路径(含选中的路径)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<RouteCandidate>,
+}
+
+/// 单条候选路径的报价
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RouteCandidate {
+    pub path: Vec<String>,
+    /// 所需输入(已格式化,路径不可用时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_amount: Option<String>,
+    /// 预估输出(已格式化,路径不可用时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_output: Option<String>,
+    /// 是否为选中的最优路径
+    pub best: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// 模拟代币交换(Uniswap V2)
-#[tool(description = "模拟 Uniswap V2 代币交换,返回预估输出和价格影响")]
-pub fn swap_tokens(
+#[allow(clippy::too_many_arguments)]
+pub async fn swap_tokens(
     config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
     uniswap_client: &Arc<UniswapV2Client>,
     erc20_client: &Arc<Erc20Client>,
     token_registry: &Arc<TokenRegistry>,
+    store: &Arc<Store>,
+    snapshots: &Arc<SnapshotStore>,
+    mempool: &Arc<MempoolWatcher>,
+    compliance: &Arc<ComplianceScreen>,
+    token_lists: &Arc<TokenListClient>,
+    curve_client: &Arc<CurveClient>,
+    aggregator_sources: &Arc<Vec<Arc<dyn AggregatorSource>>>,
+    etherscan_client: &Arc<EtherscanClient>,
     Parameters(args): Parameters<SwapTokensArgs>,
 ) -> Result<CallToolResult, McpError> {
     info!("收到 swap_tokens 请求");
@@ -78,11 +233,32 @@ pub fn swap_tokens(
         ));
     }
 
+    let tx_type_preference = config
+        .tx_type_preference(args.tx_type.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let max_price_impact_bps = config
+        .price_impact_limit(args.max_price_impact_bps)
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let exact_output = args.exact_output.unwrap_or(false);
+
+    // Router 只有 exact-input 的转账税版本
+    if exact_output && args.fee_on_transfer == Some(true) {
+        return Err(McpError::invalid_params(
+            "转账税代币只支持 exact-input 交换(Router 没有 exact-output 的 SupportingFeeOnTransferTokens 函数)",
+            None,
+        ));
+    }
+
     info!(
         from = %args.from_token,
         to = %args.to_token,
         amount = %args.amount,
+        exact_output,
         slippage = slippage_bps,
+        tx_type = ?tx_type_preference,
+        max_price_impact_bps = ?max_price_impact_bps,
         "模拟代币交换"
     );
 
@@ -93,6 +269,7 @@ pub fn swap_tokens(
             name: "From Token".to_string(),
             address: args.from_token.clone(),
             decimals: 18,
+            listed_on: Vec::new(),
         };
 
         let to_token = TokenInfo {
@@ -100,24 +277,54 @@ pub fn swap_tokens(
             name: "To Token".to_string(),
             address: args.to_token.clone(),
             decimals: 18,
+            listed_on: Vec::new(),
         };
 
-        let result = SwapSimulationResult {
+        let mut result = SwapSimulationResult {
             from_token,
             to_token,
-            input_amount: args.amount.clone(),
-            estimated_output: "100.0".to_string(),
-            minimum_output: "99.5".to_string(),
+            mode: swap_mode(exact_output).to_string(),
+            input_amount: if exact_output { "1.0".to_string() } else { args.amount.clone() },
+            estimated_output: if exact_output { args.amount.clone() } else { "100.0".to_string() },
+            minimum_output: if exact_output { args.amount.clone() } else { "99.5".to_string() },
+            maximum_input: exact_output.then(|| "1.005".to_string()),
             price_impact: "0.5%".to_string(),
+            price_impact_per_hop: vec!["0.5%".to_string()],
             route: SwapRoute {
                 protocol: "Uniswap V2".to_string(),
                 path: vec![args.from_token.clone(), args.to_token.clone()],
                 pools: vec!["0xtest".to_string()],
+                candidates: Vec::new(),
             },
             simulation_success: true,
+            router_function: router_function(exact_output, NativeLeg::None).to_string(),
+            tx_type: tx_type_preference.unwrap_or(TxType::Eip1559).as_str().to_string(),
             gas_estimate: Some("150000".to_string()),
+            gas_cost_eth: None,
+            gas_cost_usd: None,
+            net_output_after_gas: None,
             revert_reason: None,
+            approval_required: Some(false),
+            current_allowance: None,
+            approve_gas_estimate: None,
+            native_balance: None,
+            native_balance_sufficient: None,
+            fee_on_transfer: None,
+            effective_output: None,
+            transfer_fee_bps: None,
+            snapshot_block: None,
+            slippage_advice: None,
+            mev_risk: None,
+            compliance: None,
+            alternative_quotes: Vec::new(),
         };
+        apply_gas_cost(
+            &mut result,
+            U256::from(150_000u64),
+            U256::from(20u64) * U256::exp10(9),
+            Some(Decimal::from(3000)),
+            Some(Decimal::from(2000)),
+        );
 
         let json_str = serde_json::to_string_pretty(&result)
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -125,6 +332,27 @@ pub fn swap_tokens(
         return Ok(CallToolResult::success(vec![Content::text(json_str)]));
     }
 
+    // 离线模式:基于快照储备量报价,不访问 RPC
+    if let Some(snapshot) = snapshots.current() {
+        let tx_type = tx_type_preference.unwrap_or(TxType::Eip1559);
+        let result = offline_swap(
+            &snapshot,
+            uniswap_client,
+            token_registry,
+            args,
+            slippage_bps,
+            tx_type,
+            max_price_impact_bps,
+        )?;
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        info!(block = snapshot.block_number, "成功返回离线交换报价");
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
     // 真实模式:需要检查客户端可用性
     if !uniswap_client.is_available() {
         return Err(McpError::internal_error(
@@ -136,9 +364,7 @@ pub fn swap_tokens(
     // 解析源代币
     let mut from_token_info = token_registry
         .resolve(&args.from_token)
-        .ok_or_else(|| {
-            McpError::invalid_params(format!("未知的源代币: {}", args.from_token), None)
-        })?;
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
     let from_token_addr: Address = from_token_info.address.parse().map_err(|_| {
         McpError::internal_error("无效的源代币地址".to_string(), None)
@@ -147,12 +373,10 @@ pub fn swap_tokens(
     // 🔍 动态查询未知源代币信息
     if from_token_info.symbol == "UNKNOWN" && erc20_client.is_available() {
         let erc20_client_clone = erc20_client.clone();
-        let real_info = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                erc20_client_clone.token_info(from_token_addr).await
-            })
-        })
-        .map_err(|e| McpError::internal_error(format!("查询源代币信息失败: {}", e), None))?;
+        let real_info = erc20_client_clone
+            .token_info(from_token_addr)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询源代币信息失败: {}", e), None))?;
 
         // 缓存到注册表
         token_registry.register(real_info.symbol.clone(), real_info.clone());
@@ -162,9 +386,7 @@ pub fn swap_tokens(
     // 解析目标代币
     let mut to_token_info = token_registry
         .resolve(&args.to_token)
-        .ok_or_else(|| {
-            McpError::invalid_params(format!("未知的目标代币: {}", args.to_token), None)
-        })?;
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
 
     let to_token_addr: Address = to_token_info.address.parse().map_err(|_| {
         McpError::internal_error("无效的目标代币地址".to_string(), None)
@@ -173,101 +395,1136 @@ pub fn swap_tokens(
     // 🔍 动态查询未知目标代币信息
     if to_token_info.symbol == "UNKNOWN" && erc20_client.is_available() {
         let erc20_client_clone = erc20_client.clone();
-        let real_info = tokio::task::block_in_place(|| {
-            tokio::runtime::Handle::current().block_on(async {
-                erc20_client_clone.token_info(to_token_addr).await
-            })
-        })
-        .map_err(|e| McpError::internal_error(format!("查询目标代币信息失败: {}", e), None))?;
+        let real_info = erc20_client_clone
+            .token_info(to_token_addr)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询目标代币信息失败: {}", e), None))?;
 
         // 缓存到注册表
         token_registry.register(real_info.symbol.clone(), real_info.clone());
         to_token_info = real_info;
     }
 
-    // 解析输入金额（使用 rust_decimal 保持精度）
-    let amount_in = parse_units(&args.amount, from_token_info.decimals).map_err(|e| {
+    // 🔍 目标代币合约未验证源码时拒绝报价
+    ensure_verified_contract(etherscan_client, to_token_addr, args.allow_unverified.unwrap_or(false)).await?;
+
+    // 原生代币一侧使用 Router 的 ETH 版本函数(支付时随交易发送 value)
+    let native = uniswap_client.native_leg(&from_token_info, &to_token_info);
+
+    // 解析金额（使用 rust_decimal 保持精度），exact_output 模式下按目标代币精度解析
+    let amount_decimals = if exact_output { to_token_info.decimals } else { from_token_info.decimals };
+    let amount = parse_units(&args.amount, amount_decimals).map_err(|e| {
         McpError::invalid_params(format!("解析金额失败: {}", e), None)
     })?;
 
-    // 计算最小输出(考虑滑点)
-    let slippage_factor = 10000 - slippage_bps; // 9950 for 0.5% slippage
-
     // 解析钱包地址（用于模拟）
     let wallet_addr = if let Some(ref addr_str) = args.wallet_address {
-        addr_str.parse::<Address>().map_err(|_| {
-            McpError::invalid_params(format!("无效的钱包地址: {}", addr_str), None)
-        })?
+        parse_address(addr_str).map_err(|e| McpError::invalid_params(e, None))?
     } else {
         // 使用配置的模拟地址（从 private_key 派生或使用默认地址）
         config.get_simulation_address()
     };
 
-    let uniswap_client = uniswap_client.clone();
+    // 🛡️ 制裁名单筛查
+    let screening = compliance.screen(store, "swap_tokens", &[("wallet", wallet_addr)])?;
+
+    // 📡 同一交易对上有待确认交换时给出滑点建议；未指定滑点时直接采用建议值
+    let path = uniswap_client.swap_path(from_token_addr, to_token_addr);
+    let slippage_advice =
+        mempool.slippage_advice(&path, wallet_addr, slippage_bps, chrono::Utc::now().timestamp());
+    let slippage_bps = match (&slippage_advice, args.slippage_bps) {
+        (Some(advice), None) => advice.recommended_slippage_bps,
+        _ => slippage_bps,
+    };
+
+    // force_refresh 时跳过储备量缓存(刷新结果仍写回缓存)
+    let uniswap_client = if args.force_refresh.unwrap_or(false) {
+        Arc::new(uniswap_client.bypassing_cache())
+    } else {
+        uniswap_client.clone()
+    };
+    let eth_client = eth_client.clone();
+    let erc20_client = erc20_client.clone();
+    let record_enabled = store.is_enabled();
 
-    // 使用 simulate_swap 进行真实的 Router 模拟
-    let simulation = tokio::task::block_in_place(|| {
-        tokio::runtime::Handle::current().block_on(async {
-            // 首先计算最小输出（我们需要先获取报价）
+    // 使用 simulate_swap / simulate_swap_exact_output 进行真实的 Router 模拟
+    let weth = uniswap_client.weth_address();
+    let (simulation, amount_in, fee_on_transfer, approval, native_balance, tx_type, block_number) = async {
+        let tx_type = eth_client
+            .resolve_tx_type(tx_type_preference)
+            .await
+            .map_err(|e| McpError::internal_error(format!("探测交易类型失败: {}", e), None))?;
+
+        // 首先获取报价，计算滑点保护后的最小输出或最大输入
+        let (simulation, amount_in, fee_on_transfer) = if exact_output {
             let quote = uniswap_client
-                .quote_swap(from_token_addr, to_token_addr, amount_in)
+                .quote_swap_exact_output(from_token_addr, to_token_addr, amount)
                 .await
                 .map_err(|e| McpError::internal_error(format!("查询交换报价失败: {}", e), None))?;
 
-            let minimum_output = quote.amount_out * U256::from(slippage_factor) / U256::from(10000);
+            let amount_in_max = maximum_input(quote.amount_in, slippage_bps);
 
-            // 进行真实的 Router 模拟
-            uniswap_client
-                .simulate_swap(from_token_addr, to_token_addr, amount_in, minimum_output, Some(wallet_addr))
+            let simulation = uniswap_client
+                .simulate_swap_exact_output(from_token_addr, to_token_addr, amount, amount_in_max, native, Some(wallet_addr), tx_type)
                 .await
-                .map_err(|e| McpError::internal_error(format!("模拟交换失败: {}", e), None))
-        })
-    })?;
+                .map_err(|e| McpError::internal_error(format!("模拟交换失败: {}", e), None))?;
+            (simulation, amount_in_max, None)
+        } else {
+            let quote = uniswap_client
+                .quote_swap(from_token_addr, to_token_addr, amount)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询交换报价失败: {}", e), None))?;
+
+            let quoted_minimum = minimum_output(quote.amount_out, slippage_bps);
+
+            // 进行真实的 Router 模拟(指定转账税代币时跳过标准调用)
+            let standard = if args.fee_on_transfer == Some(true) {
+                None
+            } else {
+                Some(
+                    uniswap_client
+                        .simulate_swap(from_token_addr, to_token_addr, amount, quoted_minimum, native, false, Some(wallet_addr), tx_type)
+                        .await
+                        .map_err(|e| McpError::internal_error(format!("模拟交换失败: {}", e), None))?,
+                )
+            };
+
+            // 标准调用因输入代币转账税回滚时改用 SupportingFeeOnTransferTokens 函数
+            let auto_detect = args.fee_on_transfer.is_none();
+            match standard {
+                Some(simulation)
+                    if !auto_detect
+                        || simulation.simulation_success
+                        || !simulation.revert_reason.as_deref().is_some_and(is_fee_on_transfer_revert) =>
+                {
+                    (simulation, amount, None)
+                }
+                _ => {
+                    info!("按转账税代币模拟交换");
+
+                    // 先以 amountOutMin = 0 跟踪实际到账数量，再按到账数量计算最小输出
+                    let mut call = SwapCall {
+                        amount_in: amount,
+                        amount_out_min: U256::zero(),
+                        path: quote.path.clone(),
+                        to: wallet_addr,
+                        deadline: U256::MAX,
+                        native,
+                        fee_on_transfer: true,
+                    };
+                    let received_token = (native != NativeLeg::Output).then_some(to_token_addr);
+                    let effective_output =
+                        traced_output(&eth_client, &uniswap_client, &call, received_token, weth, tx_type).await;
+                    call.amount_out_min = effective_output
+                        .map(|received| minimum_output(received, slippage_bps))
+                        .unwrap_or(quoted_minimum);
+
+                    let simulation = uniswap_client
+                        .simulate_swap(from_token_addr, to_token_addr, amount, call.amount_out_min, native, true, Some(wallet_addr), tx_type)
+                        .await
+                        .map_err(|e| McpError::internal_error(format!("模拟交换失败: {}", e), None))?;
+                    let outcome = FeeOnTransferOutcome {
+                        effective_output,
+                        minimum_output: call.amount_out_min,
+                    };
+                    (simulation, amount, Some(outcome))
+                }
+            }
+        };
+
+        // 支付原生代币时检查余额，否则检查钱包对 Router 的授权额度（exact_output 按最大输入），授权不足时估算 approve Gas
+        let router = uniswap_client.router_address();
+        let (approval, native_balance) = if native == NativeLeg::Input {
+            let balance = match eth_client.get_balance(&format!("{:?}", wallet_addr), None).await {
+                Ok(balance) => Some(balance),
+                Err(e) => {
+                    warn!(error = %e, "查询原生代币余额失败");
+                    None
+                }
+            };
+            (None, balance)
+        } else {
+            let approval = match erc20_client.allowance(from_token_addr, wallet_addr, router).await {
+                Ok(allowance) if allowance < amount_in => {
+                    let approve_gas = erc20_client
+                        .estimate_approve_gas(from_token_addr, wallet_addr, router, amount_in)
+                        .await
+                        .ok();
+                    Some((allowance, approve_gas))
+                }
+                Ok(allowance) => Some((allowance, None)),
+                Err(e) => {
+                    warn!(error = %e, "查询授权额度失败");
+                    None
+                }
+            };
+            (approval, None)
+        };
+
+        // 仅在启用持久化时记录模拟所在区块
+        let block_number = if record_enabled {
+            eth_client.get_block_number().await.ok()
+        } else {
+            None
+        };
+
+        Ok::<_, McpError>((simulation, amount_in, fee_on_transfer, approval, native_balance, tx_type, block_number))
+    }
+    .await?;
 
     let quote = &simulation.quote;
 
-    // 计算最小输出
-    let minimum_output = quote.amount_out * U256::from(slippage_factor) / U256::from(10000);
+    // 🔒 价格影响超过上限时拒绝返回结果
+    enforce_price_impact_limit(quote.price_impact, max_price_impact_bps)?;
+
+    // 查询两侧代币的代币列表收录情况
+    let chain_id = config.ethereum.chain_id;
+    (from_token_info.listed_on, to_token_info.listed_on) = tokio::join!(
+        token_lists.listed_on(chain_id, from_token_addr),
+        token_lists.listed_on(chain_id, to_token_addr)
+    );
+
+    let mut result = build_result(from_token_info, to_token_info, args.amount, quote, slippage_bps, tx_type, exact_output, native);
+    result.simulation_success = simulation.simulation_success;
+    result.gas_estimate = simulation.gas_estimate.map(|g| g.to_string());
+    result.revert_reason = simulation.revert_reason;
+    result.slippage_advice = slippage_advice;
+    result.compliance = screening;
+    if let Some((allowance, approve_gas)) = approval {
+        apply_approval(&mut result, allowance, amount_in, approve_gas);
+    }
+    if let Some(balance) = native_balance {
+        apply_native_balance(&mut result, balance, amount_in);
+    }
+    if let Some(outcome) = fee_on_transfer {
+        apply_fee_on_transfer(&mut result, &outcome, simulation.quote.amount_out, native);
+    }
+
+    // 💰 Gas 费用按当前费用估算折算为原生代币、USD 和目标代币
+    if let Some(gas) = simulation.gas_estimate {
+        let output_token = if native == NativeLeg::Output { weth } else { to_token_addr };
+        let strategy = config.trading.gas_price_strategy.as_str();
+        let (fees, eth_price_usd, output_per_eth) = tokio::join!(
+            eth_client.estimate_tx_fees(strategy, tx_type),
+            fetch_token_price_usd_at(&uniswap_client, weth, 18, None),
+            fetch_tokens_per_eth(&uniswap_client, output_token, result.to_token.decimals)
+        );
+        match fees {
+            Ok(fees) => apply_gas_cost(
+                &mut result,
+                gas,
+                fees.expected_fee_per_gas(),
+                eth_price_usd.inspect_err(|e| warn!(error = %e, "查询原生代币价格失败")).ok(),
+                output_per_eth.inspect_err(|e| warn!(error = %e, "查询目标代币价格失败")).ok(),
+            ),
+            Err(e) => warn!(error = %e, "估算 Gas 费用失败"),
+        }
+    }
+
+    // 🥪 三明治攻击风险:按路径储备量估算夹击利润,并检查池子近期是否常被夹击
+    result.mev_risk = assess_mev_risk(
+        &eth_client,
+        &uniswap_client,
+        &config.trading.gas_price_strategy,
+        &simulation.quote,
+        slippage_bps,
+        tx_type,
+        result.from_token.decimals,
+    )
+    .await
+    .inspect_err(|e| warn!(error = %e, "评估 MEV 风险失败"))
+    .ok();
+
+    // 稳定币和锚定资产交易对同时比较 Curve 报价(exact-input)
+    if !exact_output && curve_client.is_available() && curve_client.find_pool(from_token_addr, to_token_addr).is_some() {
+        match curve_client.quote(from_token_addr, to_token_addr, amount).await {
+            Ok(curve) => result
+                .alternative_quotes
+                .push(curve_alternative(&curve, simulation.quote.amount_out, result.to_token.decimals)),
+            Err(e) => warn!(error = %e, "Curve 报价失败"),
+        }
+    }
+
+    // 已配置的 DEX 聚合器(如 0x)返回可执行报价(exact-input),原生代币一侧使用聚合器约定地址
+    if !exact_output {
+        let native_token: Address = NATIVE_TOKEN_ADDRESS.parse().expect("硬编码地址应该有效");
+        let request = QuoteRequest {
+            sell_token: if native == NativeLeg::Input { native_token } else { from_token_addr },
+            buy_token: if native == NativeLeg::Output { native_token } else { to_token_addr },
+            sell_amount: amount,
+            owner: wallet_addr,
+        };
+        for source in aggregator_sources.iter().filter(|source| source.is_available()) {
+            match source.firm_quote(&request).await {
+                Ok(quote) => result.alternative_quotes.push(aggregator_alternative(
+                    &quote,
+                    simulation.quote.amount_out,
+                    result.from_token.decimals,
+                    result.to_token.decimals,
+                )),
+                Err(e) => warn!(source = source.name(), error = %e, "聚合器报价失败"),
+            }
+        }
+    }
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    // 💾 持久化模拟记录
+    store.record(NewRecord {
+        kind: RecordKind::Simulation,
+        tool: "swap_tokens".to_string(),
+        block_number,
+        from_token: result.from_token.address.clone(),
+        to_token: result.to_token.address.clone(),
+        amount_in: result.input_amount.clone(),
+        amount_out: Some(result.estimated_output.clone()),
+        details: serde_json::to_value(&result).unwrap_or_default(),
+    });
+
+    info!("成功返回交换模拟结果");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 评估交换被三明治攻击的风险
+/// exact_output 模式按报价的输入数量近似(滑点体现在最大输入上,夹击空间与 exact-input 相当)
+async fn assess_mev_risk(
+    eth_client: &EthClient,
+    uniswap_client: &UniswapV2Client,
+    gas_strategy: &str,
+    quote: &SwapQuote,
+    slippage_bps: u32,
+    tx_type: TxType,
+    from_decimals: u8,
+) -> Result<MevRisk, String> {
+    let (reserves, pools) = uniswap_client
+        .get_reserves_for_path(&quote.path, None)
+        .await
+        .map_err(|e| format!("查询储备量失败: {}", e))?;
+    let amounts = uniswap_client
+        .calculate_amounts_out(quote.amount_in, &reserves)
+        .map_err(|e| format!("计算路径输出失败: {}", e))?;
+    let hops: Vec<HopState> = reserves
+        .iter()
+        .zip(&pools)
+        .zip(&amounts)
+        .map(|(((reserve_in, reserve_out), pool), amount_in)| HopState {
+            pool: *pool,
+            reserve_in: *reserve_in,
+            reserve_out: *reserve_out,
+            amount_in: *amount_in,
+        })
+        .collect();
+
+    let latest = eth_client
+        .get_block_number()
+        .await
+        .map_err(|e| format!("查询最新区块失败: {}", e))?;
+    let from_block = latest.saturating_sub(SANDWICH_LOOKBACK_BLOCKS - 1);
+    let (fees, gas_token_rate, swaps) = tokio::join!(
+        eth_client.estimate_tx_fees(gas_strategy, tx_type),
+        tokens_per_wei(uniswap_client, quote.path[0]),
+        uniswap_client.swap_logs(&pools, from_block, latest)
+    );
+
+    // 攻击 Gas 折算为源代币数量(没有 WETH 交易对时无法折算,按 0 处理)
+    let attack_gas_cost = match (fees, gas_token_rate) {
+        (Ok(fees), Some((numerator, denominator))) => {
+            Some(U256::from(SANDWICH_GAS) * fees.expected_fee_per_gas() * numerator / denominator)
+        }
+        _ => None,
+    };
+    let gas_threshold = attack_gas_cost.unwrap_or_default();
+
+    // 各跳利润按受害交换自身的兑换比例折算为源代币
+    let to_source = |value: U256, hop: usize| value * amounts[0] / amounts[hop].max(U256::one());
+    let from_source = |value: U256, hop: usize| value * amounts[hop] / amounts[0].max(U256::one());
+    let (worst_hop, expected_profit) = hops
+        .iter()
+        .enumerate()
+        .map(|(index, hop)| (index, to_source(sandwich_profit(hop, slippage_bps), index)))
+        .max_by_key(|(_, profit)| *profit)
+        .ok_or_else(|| "路径为空".to_string())?;
+    let recommended_max_slippage_bps = hops
+        .iter()
+        .enumerate()
+        .map(|(index, hop)| max_safe_slippage_bps(hop, from_source(gas_threshold, index)))
+        .min()
+        .unwrap_or(10_000);
+
+    let activity = swaps
+        .inspect_err(|e| warn!(error = %e, "查询近期 Swap 事件失败"))
+        .ok()
+        .map(|swaps| sandwich_activity(&swaps));
+    let common_sandwich_target = activity
+        .as_ref()
+        .is_some_and(|activity| activity.values().any(|pool| pool.is_common_target()));
+    let recent_swaps = activity.as_ref().map(|activity| activity.values().map(|pool| pool.swaps).sum());
+    let recent_sandwiches = activity.as_ref().map(|activity| activity.values().map(|pool| pool.sandwiches).sum());
+
+    let profitable_after_gas = expected_profit > gas_threshold;
+    let risk_level = mev::risk_level(profitable_after_gas, common_sandwich_target);
+    let mut reason = if profitable_after_gas {
+        format!(
+            "当前滑点 {} bps 下夹击利润超过攻击 Gas 成本,建议将滑点降到 {} bps 以内或通过私有交易池提交",
+            slippage_bps, recommended_max_slippage_bps
+        )
+    } else {
+        format!("当前滑点 {} bps 下夹击利润不足以覆盖攻击 Gas 成本", slippage_bps)
+    };
+    if common_sandwich_target {
+        reason.push_str(&format!(
+            ";路径池子最近 {} 个区块内被夹击 {} 次",
+            SANDWICH_LOOKBACK_BLOCKS,
+            recent_sandwiches.unwrap_or_default()
+        ));
+    }
+
+    Ok(MevRisk {
+        risk_level: risk_level.to_string(),
+        expected_sandwich_profit: format_units(expected_profit, from_decimals),
+        attack_gas_cost: attack_gas_cost.map(|cost| format_units(cost, from_decimals)),
+        profitable_after_gas,
+        attacked_pool: checksum_address(hops[worst_hop].pool),
+        common_sandwich_target,
+        recent_swaps,
+        recent_sandwiches,
+        lookback_blocks: SANDWICH_LOOKBACK_BLOCKS,
+        recommended_max_slippage_bps,
+        reason,
+    })
+}
+
+/// 1 wei 折合的代币数量,以 (分子, 分母) 表示(按代币与 WETH 交易对的储备量)
+async fn tokens_per_wei(uniswap_client: &UniswapV2Client, token: Address) -> Option<(U256, U256)> {
+    let weth = uniswap_client.weth_address();
+    if token == weth {
+        return Some((U256::one(), U256::one()));
+    }
+    let (reserve0, reserve1) = uniswap_client
+        .get_reserves(uniswap_client.pair_address(token, weth))
+        .await
+        .ok()?;
+    let (token_reserve, weth_reserve) = if token < weth { (reserve0, reserve1) } else { (reserve1, reserve0) };
+    (!weth_reserve.is_zero()).then_some((token_reserve, weth_reserve))
+}
+
+/// Curve 报价与 Uniswap V2 报价比较
+fn curve_alternative(curve: &CurveQuote, uniswap_output: U256, decimals: u8) -> AlternativeQuote {
+    AlternativeQuote {
+        venue: "curve".to_string(),
+        pool: Some(format!("{} ({:?})", curve.pool, curve.pool_address)),
+        estimated_output: format_units(curve.amount_out, decimals),
+        better: curve.amount_out > uniswap_output,
+        price: None,
+        guaranteed_output: None,
+        allowance_target: None,
+        to: None,
+        calldata: None,
+        value: None,
+        gas_estimate: None,
+    }
+}
+
+/// 聚合器可执行报价与 Uniswap V2 报价比较
+fn aggregator_alternative(
+    quote: &AggregatorQuote,
+    uniswap_output: U256,
+    from_decimals: u8,
+    to_decimals: u8,
+) -> AlternativeQuote {
+    AlternativeQuote {
+        venue: quote.source.to_string(),
+        pool: None,
+        estimated_output: format_units(quote.buy_amount, to_decimals),
+        better: quote.buy_amount > uniswap_output,
+        price: Some(calculate_price_ratio(quote.buy_amount, quote.sell_amount, from_decimals, to_decimals)),
+        guaranteed_output: Some(format_units(quote.min_buy_amount, to_decimals)),
+        allowance_target: quote.allowance_target.map(checksum_address),
+        to: Some(checksum_address(quote.to)),
+        calldata: Some(format!("{}", quote.calldata)),
+        value: Some(quote.value.to_string()),
+        gas_estimate: quote.gas.map(|gas| gas.to_string()),
+    }
+}
+
+/// 交换模式名称
+fn swap_mode(exact_output: bool) -> &'static str {
+    if exact_output {
+        "exact_output"
+    } else {
+        "exact_input"
+    }
+}
+
+/// 扣除滑点后的最小输出(exact-input)
+pub(crate) fn minimum_output(amount_out: U256, slippage_bps: u32) -> U256 {
+    amount_out * U256::from(10000 - slippage_bps) / U256::from(10000)
+}
+
+/// 计入滑点后的最大输入(exact-output)
+fn maximum_input(amount_in: U256, slippage_bps: u32) -> U256 {
+    amount_in * U256::from(10000 + slippage_bps) / U256::from(10000)
+}
+
+/// 根据报价构建模拟结果(模拟相关字段由调用方填写)
+/// exact_output 模式下 `amount` 为用户指定的输出数量,输出即为最小输出,滑点体现在最大输入上
+#[allow(clippy::too_many_arguments)]
+fn build_result(
+    from_token: TokenInfo,
+    to_token: TokenInfo,
+    amount: String,
+    quote: &SwapQuote,
+    slippage_bps: u32,
+    tx_type: TxType,
+    exact_output: bool,
+    native: NativeLeg,
+) -> SwapSimulationResult {
+    let (input_amount, minimum_output, maximum_input) = if exact_output {
+        (
+            format_units(quote.amount_in, from_token.decimals),
+            quote.amount_out,
+            Some(format_units(maximum_input(quote.amount_in, slippage_bps), from_token.decimals)),
+        )
+    } else {
+        (amount, minimum_output(quote.amount_out, slippage_bps), None)
+    };
 
     // 格式化输出
-    let estimated_output_formatted = format_units(quote.amount_out, to_token_info.decimals);
-    let minimum_output_formatted = format_units(minimum_output, to_token_info.decimals);
+    let estimated_output_formatted = format_units(quote.amount_out, to_token.decimals);
+    let minimum_output_formatted = format_units(minimum_output, to_token.decimals);
 
     // 构建路径字符串
     let path_strings: Vec<String> = quote
         .path
         .iter()
-        .map(|addr| format!("{:?}", addr))
+        .map(|addr| checksum_address(*addr))
         .collect();
 
     // 🚀 使用缓存的 pair 地址，避免重复 RPC 调用
     let pool_addresses: Vec<String> = quote
         .pair_addresses
         .iter()
-        .map(|addr| format!("{:?}", addr))
+        .map(|addr| checksum_address(*addr))
         .collect();
 
-    let result = SwapSimulationResult {
-        from_token: from_token_info,
-        to_token: to_token_info,
-        input_amount: args.amount,
+    let candidates = route_candidates(&quote.routes, &quote.path, from_token.decimals, to_token.decimals);
+
+    SwapSimulationResult {
+        from_token,
+        to_token,
+        mode: swap_mode(exact_output).to_string(),
+        input_amount,
         estimated_output: estimated_output_formatted,
         minimum_output: minimum_output_formatted,
+        maximum_input,
         price_impact: format!("{:.2}%", quote.price_impact),
+        price_impact_per_hop: quote
+            .hop_price_impacts
+            .iter()
+            .map(|impact| format!("{:.2}%", impact))
+            .collect(),
         route: SwapRoute {
             protocol: "Uniswap V2".to_string(),
             path: path_strings,
             pools: pool_addresses,
+            candidates,
         },
-        simulation_success: simulation.simulation_success,
-        gas_estimate: simulation.gas_estimate.map(|g| g.to_string()),
-        revert_reason: simulation.revert_reason,
+        simulation_success: false,
+        router_function: router_function(exact_output, native).to_string(),
+        tx_type: tx_type.as_str().to_string(),
+        gas_estimate: None,
+        gas_cost_eth: None,
+        gas_cost_usd: None,
+        net_output_after_gas: None,
+        revert_reason: None,
+        // 支付原生代币不需要授权
+        approval_required: (native == NativeLeg::Input).then_some(false),
+        current_allowance: None,
+        approve_gas_estimate: None,
+        native_balance: None,
+        native_balance_sufficient: None,
+        fee_on_transfer: None,
+        effective_output: None,
+        transfer_fee_bps: None,
+        snapshot_block: None,
+        slippage_advice: None,
+        mev_risk: None,
+        compliance: None,
+        alternative_quotes: Vec::new(),
+    }
+}
+
+/// 格式化候选路径明细(只有一条候选路径时不返回)
+fn route_candidates(routes: &[PathQuote], best: &[Address], from_decimals: u8, to_decimals: u8) -> Vec<RouteCandidate> {
+    if routes.len() < 2 {
+        return Vec::new();
+    }
+
+    routes
+        .iter()
+        .map(|route| {
+            let (amounts, error) = match &route.amounts {
+                Ok(amounts) => (Some(*amounts), None),
+                Err(e) => (None, Some(e.clone())),
+            };
+            RouteCandidate {
+                path: route.path.iter().map(|addr| checksum_address(*addr)).collect(),
+                input_amount: amounts.map(|(amount_in, _)| format_units(amount_in, from_decimals)),
+                estimated_output: amounts.map(|(_, amount_out)| format_units(amount_out, to_decimals)),
+                best: route.path == best,
+                error,
+            }
+        })
+        .collect()
+}
+
+/// 价格影响超过上限时返回结构化的拒绝错误(`max_bps` 为 None 时不限制)
+/// `price_impact` 为百分比，与 SwapQuote::price_impact 一致
+pub(crate) fn enforce_price_impact_limit(price_impact: f64, max_bps: Option<u32>) -> Result<(), McpError> {
+    let Some(max_bps) = max_bps else {
+        return Ok(());
     };
 
-    let json_str = serde_json::to_string_pretty(&result)
-        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+    let impact_bps = (price_impact * 100.0).round() as u64;
+    if impact_bps <= max_bps as u64 {
+        return Ok(());
+    }
 
-    info!("成功返回交换模拟结果");
+    Err(McpError::invalid_request(
+        format!(
+            "价格影响 {:.2}% 超过上限 {:.2}%,已拒绝返回报价",
+            price_impact,
+            max_bps as f64 / 100.0
+        ),
+        Some(serde_json::json!({
+            "refused": true,
+            "reason": "price_impact_exceeded",
+            "price_impact_bps": impact_bps,
+            "max_price_impact_bps": max_bps,
+        })),
+    ))
+}
 
-    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+/// 填写授权相关字段(授权额度为 U256::MAX 时显示为 unlimited)
+fn apply_approval(
+    result: &mut SwapSimulationResult,
+    allowance: U256,
+    amount_in: U256,
+    approve_gas: Option<U256>,
+) {
+    let required = allowance < amount_in;
+    result.approval_required = Some(required);
+    result.current_allowance = Some(if allowance == U256::MAX {
+        "unlimited".to_string()
+    } else {
+        format_units(allowance, result.from_token.decimals)
+    });
+    result.approve_gas_estimate = if required {
+        approve_gas.map(|g| g.to_string())
+    } else {
+        None
+    };
+}
+
+/// 填写原生代币余额字段,`value` 为随交易发送的数量
+fn apply_native_balance(result: &mut SwapSimulationResult, balance: U256, value: U256) {
+    result.native_balance = Some(format_units(balance, result.from_token.decimals));
+    result.native_balance_sufficient = Some(balance >= value);
+}
+
+/// 填写 Gas 费用字段,`output_per_eth` 为 1 个原生代币可兑换的目标代币数量
+fn apply_gas_cost(
+    result: &mut SwapSimulationResult,
+    gas: U256,
+    fee_per_gas: U256,
+    eth_price_usd: Option<Decimal>,
+    output_per_eth: Option<Decimal>,
+) {
+    let cost_eth = format_units(gas * fee_per_gas, 18);
+    let Ok(cost) = Decimal::from_str(&cost_eth) else {
+        return;
+    };
+    result.gas_cost_eth = Some(cost_eth);
+    result.gas_cost_usd = eth_price_usd
+        .and_then(|price| cost.checked_mul(price))
+        .map(|usd| usd.round_dp(4).normalize().to_string());
+    result.net_output_after_gas = output_per_eth.and_then(|rate| {
+        let output = Decimal::from_str(&result.estimated_output).ok()?;
+        let net = output.checked_sub(cost.checked_mul(rate)?)?;
+        Some(net.round_dp(result.to_token.decimals as u32).normalize().to_string())
+    });
+}
+
+/// 转账税代币的模拟结果
+struct FeeOnTransferOutcome {
+    /// 接收方实际到账数量(节点不支持 debug_traceCall 时为空)
+    effective_output: Option<U256>,
+    /// 按实际到账数量计算的最小输出
+    minimum_output: U256,
+}
+
+/// 跟踪交换调用，返回接收方实际到账的数量(`token` 为 None 表示原生代币)
+/// 跟踪失败或调用回滚时返回 None
+pub(crate) async fn traced_output(
+    eth_client: &EthClient,
+    uniswap_client: &UniswapV2Client,
+    call: &SwapCall,
+    token: Option<Address>,
+    weth: Address,
+    tx_type: TxType,
+) -> Option<U256> {
+    let tx = uniswap_client.swap_transaction(call, call.to, tx_type);
+    let frame = match eth_client.trace_call(&tx, None, None).await {
+        Ok(frame) => frame,
+        Err(e) => {
+            warn!(error = %e, "跟踪转账税交换失败(需要节点支持 debug_traceCall)");
+            return None;
+        }
+    };
+    if frame.error.is_some() {
+        return None;
+    }
+
+    let mut deltas = DeltaMap::new();
+    collect_deltas(&frame, weth, &mut deltas);
+    deltas
+        .get(&(call.to, token))
+        .filter(|delta| delta.is_positive())
+        .map(|delta| delta.into_raw())
+}
+
+/// 实际到账相对报价的损耗(基点)
+pub(crate) fn transfer_fee_bps(quoted: U256, effective: U256) -> u32 {
+    if quoted.is_zero() || effective >= quoted {
+        return 0;
+    }
+    ((quoted - effective) * U256::from(10000) / quoted).as_u32()
+}
+
+/// 填写转账税相关字段，最小输出改为按实际到账数量计算
+fn apply_fee_on_transfer(result: &mut SwapSimulationResult, outcome: &FeeOnTransferOutcome, quoted: U256, native: NativeLeg) {
+    let decimals = result.to_token.decimals;
+    result.fee_on_transfer = Some(true);
+    result.router_function = fee_on_transfer_function(native).to_string();
+    result.minimum_output = format_units(outcome.minimum_output, decimals);
+    result.effective_output = outcome.effective_output.map(|received| format_units(received, decimals));
+    result.transfer_fee_bps = outcome.effective_output.map(|received| transfer_fee_bps(quoted, received));
+}
+
+/// 离线报价:代币元数据和储备量全部来自快照,不进行 Router 模拟
+fn offline_swap(
+    snapshot: &MarketSnapshot,
+    uniswap_client: &UniswapV2Client,
+    token_registry: &TokenRegistry,
+    args: SwapTokensArgs,
+    slippage_bps: u32,
+    tx_type: TxType,
+    max_price_impact_bps: Option<u32>,
+) -> Result<SwapSimulationResult, McpError> {
+    let resolve = |query: &str| {
+        let info = snapshot.resolve_token(token_registry, query).ok_or_else(|| {
+            McpError::invalid_params(format!("快照中没有代币信息: {}", query), None)
+        })?;
+        let address: Address = info.address.parse().map_err(|_| {
+            McpError::internal_error(format!("快照中的代币地址无效: {}", info.address), None)
+        })?;
+        Ok::<_, McpError>((info, address))
+    };
+
+    let (from_token_info, from_token_addr) = resolve(&args.from_token)?;
+    let (to_token_info, to_token_addr) = resolve(&args.to_token)?;
+
+    let exact_output = args.exact_output.unwrap_or(false);
+    let amount_decimals = if exact_output { to_token_info.decimals } else { from_token_info.decimals };
+    let amount = parse_units(&args.amount, amount_decimals).map_err(|e| {
+        McpError::invalid_params(format!("解析金额失败: {}", e), None)
+    })?;
+
+    // 在快照包含的交易对中搜索最优路径
+    let quotes = uniswap_client
+        .candidate_paths(from_token_addr, to_token_addr)
+        .into_iter()
+        .map(|path| {
+            let quote = snapshot.path_reserves(&path).map_err(|e| e.to_string()).and_then(|(reserves, pairs)| {
+                if exact_output {
+                    uniswap_client.quote_exact_output_from_reserves(path.clone(), reserves, pairs, amount)
+                } else {
+                    uniswap_client.quote_from_reserves(path.clone(), reserves, pairs, amount)
+                }
+                .map_err(|e| e.to_string())
+            });
+            (path, quote)
+        })
+        .collect();
+    let quote = select_best_route(quotes, exact_output)
+        .map_err(|e| McpError::invalid_params(format!("离线报价失败: {}", e), None))?;
+
+    enforce_price_impact_limit(quote.price_impact, max_price_impact_bps)?;
+
+    let native = uniswap_client.native_leg(&from_token_info, &to_token_info);
+    let mut result = build_result(from_token_info, to_token_info, args.amount, &quote, slippage_bps, tx_type, exact_output, native);
+    result.snapshot_block = Some(snapshot.block_number);
+    // 离线模式无法测量转账税，只标注将使用的 Router 函数
+    if args.fee_on_transfer == Some(true) {
+        result.fee_on_transfer = Some(true);
+        result.router_function = fee_on_transfer_function(native).to_string();
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::MAINNET;
+    use crate::snapshot::tests::{sample_snapshot, USDC, WETH};
+
+    #[test]
+    fn test_offline_swap_uses_snapshot_reserves() {
+        let snapshot = sample_snapshot();
+        let uniswap_client = UniswapV2Client::new(None, &MAINNET);
+        let registry = TokenRegistry::new();
+
+        let args = SwapTokensArgs {
+            from_token: "WETH".to_string(),
+            to_token: "USDC".to_string(),
+            amount: "1".to_string(),
+            slippage_bps: None,
+            wallet_address: None,
+            tx_type: None,
+            max_price_impact_bps: None,
+            force_refresh: None,
+            exact_output: None,
+            fee_on_transfer: None,
+            allow_unverified: None,
+        };
+
+        let result = offline_swap(&snapshot, &uniswap_client, &registry, args, 50, TxType::Eip1559, None).unwrap();
+        assert_eq!(result.snapshot_block, Some(19_000_000));
+        assert!(!result.simulation_success);
+        assert!(result.gas_estimate.is_none());
+        assert_eq!(result.route.path.len(), 2);
+        assert_eq!(result.route.path[0].to_lowercase(), WETH.to_lowercase());
+        assert_eq!(result.route.path[1].to_lowercase(), USDC.to_lowercase());
+
+        // 1 WETH -> 300000 USDC 池子,扣除 0.3% 手续费和价格影响后约 2961 USDC
+        let output: f64 = result.estimated_output.parse().unwrap();
+        assert!(output > 2950.0 && output < 2970.0, "output = {}", output);
+    }
+
+    #[test]
+    fn test_offline_swap_exact_output() {
+        let snapshot = sample_snapshot();
+        let uniswap_client = UniswapV2Client::new(None, &MAINNET);
+        let registry = TokenRegistry::new();
+
+        // 期望得到 2961 USDC,所需 WETH 应略低于 1
+        let args = SwapTokensArgs {
+            from_token: "WETH".to_string(),
+            to_token: "USDC".to_string(),
+            amount: "2961".to_string(),
+            slippage_bps: None,
+            wallet_address: None,
+            tx_type: None,
+            max_price_impact_bps: None,
+            force_refresh: None,
+            exact_output: Some(true),
+            fee_on_transfer: None,
+            allow_unverified: None,
+        };
+
+        let result = offline_swap(&snapshot, &uniswap_client, &registry, args, 100, TxType::Eip1559, None).unwrap();
+        assert_eq!(result.mode, "exact_output");
+        assert_eq!(result.estimated_output, "2961");
+        assert_eq!(result.minimum_output, "2961");
+
+        let input: f64 = result.input_amount.parse().unwrap();
+        assert!(input > 0.99 && input <= 1.0, "input = {}", input);
+
+        // 最大输入 = 所需输入 × (1 + 1%)
+        let maximum: f64 = result.maximum_input.unwrap().parse().unwrap();
+        assert!((maximum / input - 1.01).abs() < 1e-9, "maximum = {}", maximum);
+    }
+
+    #[test]
+    fn test_offline_swap_native_eth() {
+        let snapshot = sample_snapshot();
+        let uniswap_client = UniswapV2Client::new(None, &MAINNET);
+        let registry = TokenRegistry::new();
+        let args = |from: &str, to: &str, exact_output| SwapTokensArgs {
+            from_token: from.to_string(),
+            to_token: to.to_string(),
+            amount: "1".to_string(),
+            slippage_bps: None,
+            wallet_address: None,
+            tx_type: None,
+            max_price_impact_bps: None,
+            force_refresh: None,
+            exact_output: Some(exact_output),
+            fee_on_transfer: None,
+            allow_unverified: None,
+        };
+
+        // ETH 与 WETH 使用相同路径,但 Router 函数不同,支付 ETH 不需要授权
+        let result = offline_swap(&snapshot, &uniswap_client, &registry, args("ETH", "USDC", false), 50, TxType::Eip1559, None).unwrap();
+        assert_eq!(result.router_function, "swapExactETHForTokens");
+        assert_eq!(result.approval_required, Some(false));
+        assert_eq!(result.route.path[0].to_lowercase(), WETH.to_lowercase());
+
+        let result = offline_swap(&snapshot, &uniswap_client, &registry, args("WETH", "USDC", false), 50, TxType::Eip1559, None).unwrap();
+        assert_eq!(result.router_function, "swapExactTokensForTokens");
+        assert!(result.approval_required.is_none());
+
+        let result = offline_swap(&snapshot, &uniswap_client, &registry, args("USDC", "ETH", true), 50, TxType::Eip1559, None).unwrap();
+        assert_eq!(result.router_function, "swapTokensForExactETH");
+    }
+
+    #[test]
+    fn test_apply_native_balance() {
+        let snapshot = sample_snapshot();
+        let uniswap_client = UniswapV2Client::new(None, &MAINNET);
+        let registry = TokenRegistry::new();
+        let args = SwapTokensArgs {
+            from_token: "ETH".to_string(),
+            to_token: "USDC".to_string(),
+            amount: "1".to_string(),
+            slippage_bps: None,
+            wallet_address: None,
+            tx_type: None,
+            max_price_impact_bps: None,
+            force_refresh: None,
+            exact_output: None,
+            fee_on_transfer: None,
+            allow_unverified: None,
+        };
+        let mut result = offline_swap(&snapshot, &uniswap_client, &registry, args, 50, TxType::Eip1559, None).unwrap();
+
+        apply_native_balance(&mut result, U256::exp10(17), U256::exp10(18));
+        assert_eq!(result.native_balance.as_deref(), Some("0.1"));
+        assert_eq!(result.native_balance_sufficient, Some(false));
+
+        apply_native_balance(&mut result, U256::exp10(18), U256::exp10(18));
+        assert_eq!(result.native_balance_sufficient, Some(true));
+    }
+
+    #[test]
+    fn test_apply_gas_cost() {
+        let snapshot = sample_snapshot();
+        let uniswap_client = UniswapV2Client::new(None, &MAINNET);
+        let registry = TokenRegistry::new();
+        let args = SwapTokensArgs {
+            from_token: "ETH".to_string(),
+            to_token: "USDC".to_string(),
+            amount: "1".to_string(),
+            slippage_bps: None,
+            wallet_address: None,
+            tx_type: None,
+            max_price_impact_bps: None,
+            force_refresh: None,
+            exact_output: None,
+            fee_on_transfer: None,
+            allow_unverified: None,
+        };
+        let mut result = offline_swap(&snapshot, &uniswap_client, &registry, args, 50, TxType::Eip1559, None).unwrap();
+        result.estimated_output = "2960.5".to_string();
+
+        // 150000 Gas × 20 gwei = 0.003 ETH,ETH 价格 3000 USD
+        let gwei = U256::exp10(9);
+        apply_gas_cost(&mut result, U256::from(150_000u64), gwei * 20, Some(Decimal::from(3000)), Some(Decimal::from(3000)));
+        assert_eq!(result.gas_cost_eth.as_deref(), Some("0.003"));
+        assert_eq!(result.gas_cost_usd.as_deref(), Some("9"));
+        assert_eq!(result.net_output_after_gas.as_deref(), Some("2951.5"));
+
+        // 价格查询失败时只返回原生代币费用
+        apply_gas_cost(&mut result, U256::from(150_000u64), gwei * 20, None, None);
+        assert_eq!(result.gas_cost_eth.as_deref(), Some("0.003"));
+        assert!(result.gas_cost_usd.is_none());
+        assert!(result.net_output_after_gas.is_none());
+    }
+
+    #[test]
+    fn test_apply_fee_on_transfer() {
+        let snapshot = sample_snapshot();
+        let uniswap_client = UniswapV2Client::new(None, &MAINNET);
+        let registry = TokenRegistry::new();
+        let args = SwapTokensArgs {
+            from_token: "WETH".to_string(),
+            to_token: "USDC".to_string(),
+            amount: "1".to_string(),
+            slippage_bps: None,
+            wallet_address: None,
+            tx_type: None,
+            max_price_impact_bps: None,
+            force_refresh: None,
+            exact_output: None,
+            fee_on_transfer: Some(true),
+            allow_unverified: None,
+        };
+        let mut result = offline_swap(&snapshot, &uniswap_client, &registry, args, 50, TxType::Eip1559, None).unwrap();
+        assert_eq!(result.fee_on_transfer, Some(true));
+        assert_eq!(result.router_function, "swapExactTokensForTokensSupportingFeeOnTransferTokens");
+
+        // 报价 100 USDC,扣除 5% 转账税后到账 95 USDC
+        let outcome = FeeOnTransferOutcome {
+            effective_output: Some(U256::from(95_000_000u64)),
+            minimum_output: minimum_output(U256::from(95_000_000u64), 50),
+        };
+        apply_fee_on_transfer(&mut result, &outcome, U256::from(100_000_000u64), NativeLeg::Output);
+        assert_eq!(result.router_function, "swapExactTokensForETHSupportingFeeOnTransferTokens");
+        assert_eq!(result.effective_output.as_deref(), Some("95"));
+        assert_eq!(result.minimum_output, "94.525");
+        assert_eq!(result.transfer_fee_bps, Some(500));
+    }
+
+    #[test]
+    fn test_transfer_fee_bps() {
+        assert_eq!(transfer_fee_bps(U256::from(1000), U256::from(900)), 1000);
+        assert_eq!(transfer_fee_bps(U256::from(1000), U256::from(1000)), 0);
+        assert_eq!(transfer_fee_bps(U256::from(1000), U256::from(1001)), 0);
+        assert_eq!(transfer_fee_bps(U256::zero(), U256::zero()), 0);
+    }
+
+    #[test]
+    fn test_slippage_bounds() {
+        let amount = U256::from(1_000_000u64);
+        assert_eq!(minimum_output(amount, 50), U256::from(995_000u64));
+        assert_eq!(maximum_input(amount, 50), U256::from(1_005_000u64));
+        assert_eq!(maximum_input(amount, 0), amount);
+    }
+
+    #[test]
+    fn test_apply_approval() {
+        let snapshot = sample_snapshot();
+        let uniswap_client = UniswapV2Client::new(None, &MAINNET);
+        let registry = TokenRegistry::new();
+        let args = SwapTokensArgs {
+            from_token: "USDC".to_string(),
+            to_token: "WETH".to_string(),
+            amount: "100".to_string(),
+            slippage_bps: None,
+            wallet_address: None,
+            tx_type: None,
+            max_price_impact_bps: None,
+            force_refresh: None,
+            exact_output: None,
+            fee_on_transfer: None,
+            allow_unverified: None,
+        };
+        let mut result = offline_swap(&snapshot, &uniswap_client, &registry, args, 50, TxType::Eip1559, None).unwrap();
+        let amount_in = U256::from(100_000_000u64);
+
+        apply_approval(&mut result, U256::from(50_000_000u64), amount_in, Some(U256::from(46_000)));
+        assert_eq!(result.approval_required, Some(true));
+        assert_eq!(result.current_allowance.as_deref(), Some("50"));
+        assert_eq!(result.approve_gas_estimate.as_deref(), Some("46000"));
+
+        apply_approval(&mut result, U256::MAX, amount_in, None);
+        assert_eq!(result.approval_required, Some(false));
+        assert_eq!(result.current_allowance.as_deref(), Some("unlimited"));
+        assert!(result.approve_gas_estimate.is_none());
+    }
+
+    #[test]
+    fn test_curve_alternative() {
+        let curve = CurveQuote {
+            pool: "3pool",
+            pool_address: Address::repeat_byte(0x33),
+            amount_in: U256::from(1_000_000_000u64),
+            amount_out: U256::from(999_500_000u64),
+        };
+        let alternative = curve_alternative(&curve, U256::from(990_000_000u64), 6);
+        assert_eq!(alternative.venue, "curve");
+        assert_eq!(alternative.estimated_output, "999.5");
+        assert!(alternative.better);
+        assert!(alternative.pool.unwrap().starts_with("3pool (0x3333"));
+
+        assert!(!curve_alternative(&curve, U256::from(999_500_000u64), 6).better);
+    }
+
+    #[test]
+    fn test_aggregator_alternative() {
+        let quote = AggregatorQuote {
+            source: "0x",
+            sell_amount: U256::exp10(18),
+            buy_amount: U256::from(3_010_000_000u64),
+            min_buy_amount: U256::from(2_979_900_000u64),
+            allowance_target: Some(Address::repeat_byte(0xaa)),
+            to: Address::repeat_byte(0xaa),
+            calldata: Bytes::from(vec![0x12, 0x34]),
+            value: U256::zero(),
+            gas: Some(U256::from(180_000u64)),
+        };
+        let alternative = aggregator_alternative(&quote, U256::from(3_000_000_000u64), 18, 6);
+        assert_eq!(alternative.venue, "0x");
+        assert!(alternative.better);
+        assert_eq!(alternative.estimated_output, "3010");
+        assert_eq!(alternative.guaranteed_output.as_deref(), Some("2979.9"));
+        assert_eq!(alternative.price.as_deref(), Some("3010"));
+        assert_eq!(alternative.calldata.as_deref(), Some("0x1234"));
+        assert_eq!(alternative.gas_estimate.as_deref(), Some("180000"));
+    }
+
+    #[test]
+    fn test_enforce_price_impact_limit() {
+        assert!(enforce_price_impact_limit(12.5, None).is_ok());
+        assert!(enforce_price_impact_limit(0.5, Some(100)).is_ok());
+        assert!(enforce_price_impact_limit(1.0, Some(100)).is_ok());
+
+        let err = enforce_price_impact_limit(2.5, Some(100)).unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_REQUEST);
+        let data = err.data.unwrap();
+        assert_eq!(data["reason"], "price_impact_exceeded");
+        assert_eq!(data["price_impact_bps"], 250);
+        assert_eq!(data["max_price_impact_bps"], 100);
+    }
+
+    #[test]
+    fn test_offline_swap_refuses_high_impact() {
+        let snapshot = sample_snapshot();
+        let uniswap_client = UniswapV2Client::new(None, &MAINNET);
+        let registry = TokenRegistry::new();
+
+        // 10 WETH 占池子 WETH 储备的 10%
+        let args = SwapTokensArgs {
+            from_token: "WETH".to_string(),
+            to_token: "USDC".to_string(),
+            amount: "10".to_string(),
+            slippage_bps: None,
+            wallet_address: None,
+            tx_type: None,
+            max_price_impact_bps: None,
+            force_refresh: None,
+            exact_output: None,
+            fee_on_transfer: None,
+            allow_unverified: None,
+        };
+
+        let err = offline_swap(&snapshot, &uniswap_client, &registry, args, 50, TxType::Eip1559, Some(500)).unwrap_err();
+        assert_eq!(err.data.unwrap()["reason"], "price_impact_exceeded");
+    }
+
+    #[test]
+    fn test_offline_swap_missing_pair() {
+        let snapshot = sample_snapshot();
+        let uniswap_client = UniswapV2Client::new(None, &MAINNET);
+        let registry = TokenRegistry::new();
+
+        let args = SwapTokensArgs {
+            from_token: "WETH".to_string(),
+            to_token: "DAI".to_string(),
+            amount: "1".to_string(),
+            slippage_bps: None,
+            wallet_address: None,
+            tx_type: None,
+            max_price_impact_bps: None,
+            force_refresh: None,
+            exact_output: None,
+            fee_on_transfer: None,
+            allow_unverified: None,
+        };
+
+        let err = offline_swap(&snapshot, &uniswap_client, &registry, args, 50, TxType::Eip1559, None).unwrap_err();
+        assert!(err.message.contains("快照中没有交易对"));
+    }
 }
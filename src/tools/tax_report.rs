@@ -0,0 +1,471 @@
+use crate::{
+    config::Config,
+    erc20::Erc20Client,
+    eth_client::EthClient,
+    export::{self, CsvExport},
+    logging::info,
+    pnl::{self, LedgerEntry, LedgerSide},
+    store::Store,
+    token_registry::TokenRegistry,
+    tools::pnl::{collect_transfer_ledger, format_usd, merge_with_store},
+    types::{checksum_address, parse_address},
+    uniswap::UniswapV2Client,
+};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 单份报告允许扫描的最大 Transfer 事件数量
+const MAX_TAX_TRANSFERS: usize = 2000;
+
+/// 支持的最早年份(以太坊主网上线)
+const MIN_TAX_YEAR: i32 = 2015;
+
+/// 报告 CSV 格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaxReportFormat {
+    /// 通用格式:每次变动一行,附带成本和损益
+    Generic,
+    /// Koinly Universal 格式:同一交易的卖出和买入合并为一行
+    Koinly,
+}
+
+impl TaxReportFormat {
+    fn parse(value: Option<&str>) -> Result<Self, String> {
+        match value.map(|v| v.to_lowercase()).as_deref() {
+            None | Some("generic") => Ok(Self::Generic),
+            Some("koinly") => Ok(Self::Koinly),
+            Some(other) => Err(format!("不支持的报告格式: {} (支持 generic、koinly)", other)),
+        }
+    }
+}
+
+/// GenerateTaxReport 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GenerateTaxReportArgs {
+    /// 钱包地址(必需)
+    pub address: String,
+    /// 报告年份(必需,按 UTC 自然年)
+    pub year: i32,
+    /// CSV 格式(可选,generic/koinly,默认 generic)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+/// 报告中的一次应税事件
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TaxEvent {
+    pub timestamp: u64,
+    pub tx_hash: String,
+    /// acquisition / disposal
+    pub kind: String,
+    /// trade(同一交易内有卖出和买入)/ transfer
+    pub label: String,
+    pub token: String,
+    pub symbol: String,
+    pub amount: String,
+    pub price_usd: String,
+    pub value_usd: String,
+    pub fee_usd: String,
+    /// 卖出部分的成本(平均成本法)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_basis_usd: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gain_usd: Option<String>,
+    /// 成本未知的卖出数量(买入发生在已知历史之前)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unmatched_amount: Option<String>,
+}
+
+/// 年度税务报告
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TaxReport {
+    pub address: String,
+    pub year: i32,
+    pub format: TaxReportFormat,
+    pub events: Vec<TaxEvent>,
+}
+
+/// 生成年度税务报告(CSV)
+pub async fn generate_tax_report(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    store: &Arc<Store>,
+    Parameters(args): Parameters<GenerateTaxReportArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 generate_tax_report 请求");
+
+    let format = TaxReportFormat::parse(args.format.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let (year_start, year_end) =
+        year_bounds(args.year).map_err(|e| McpError::invalid_params(e, None))?;
+
+    info!(address = %args.address, year = args.year, format = ?format, "生成税务报告");
+
+    // 测试模式
+    if config.server.test_mode {
+        let entries = sample_entries(year_start);
+        let report = build_tax_report(args.address.clone(), args.year, format, &entries);
+
+        return Ok(CallToolResult::success(vec![Content::text(export::to_csv(&report))]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() || !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let wallet = parse_address(&args.address).map_err(|e| McpError::invalid_params(e, None))?;
+
+    let eth_client = eth_client.clone();
+    let uniswap_client = uniswap_client.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+
+    let report = async {
+        // 按时间戳定位年度区块区间
+        let from_block = eth_client
+            .find_block_by_timestamp(year_start)
+            .await
+            .map_err(|e| McpError::internal_error(format!("定位起始区块失败: {}", e), None))?;
+        let next_year_block = eth_client
+            .find_block_by_timestamp(year_end + 1)
+            .await
+            .map_err(|e| McpError::internal_error(format!("定位结束区块失败: {}", e), None))?;
+        let to_block = next_year_block.saturating_sub(1).max(from_block);
+
+        let ledger = collect_transfer_ledger(
+            &eth_client,
+            &uniswap_client,
+            &erc20_client,
+            &token_registry,
+            wallet,
+            (from_block, to_block),
+            MAX_TAX_TRANSFERS,
+        )
+        .await?;
+
+        // 💾 合并持久化台账(包含往年买入的成本和执行记录的手续费)
+        let wallet_key = format!("{:?}", wallet);
+        let (entries, _) = merge_with_store(store, &wallet_key, ledger.entries)?;
+
+        Ok::<_, McpError>(build_tax_report(checksum_address(wallet), args.year, format, &entries))
+    }
+    .await?;
+
+    info!(events = report.events.len(), "成功返回税务报告");
+
+    Ok(CallToolResult::success(vec![Content::text(export::to_csv(&report))]))
+}
+
+/// 计算自然年的起止时间戳(UTC,闭区间)
+fn year_bounds(year: i32) -> Result<(u64, u64), String> {
+    let current_year = Utc::now().year();
+    if !(MIN_TAX_YEAR..=current_year).contains(&year) {
+        return Err(format!(
+            "无效的年份: {} (支持 {}-{})",
+            year, MIN_TAX_YEAR, current_year
+        ));
+    }
+
+    let start_of = |y: i32| {
+        NaiveDate::from_ymd_opt(y, 1, 1)
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|dt| dt.and_utc().timestamp() as u64)
+            .ok_or_else(|| format!("无效的年份: {}", y))
+    };
+
+    Ok((start_of(year)?, start_of(year + 1)? - 1))
+}
+
+/// 从完整台账构建指定年份的报告
+/// 成本按全部台账计算，只输出该年份内的事件
+fn build_tax_report(
+    address: String,
+    year: i32,
+    format: TaxReportFormat,
+    entries: &[LedgerEntry],
+) -> TaxReport {
+    let (year_start, year_end) = year_bounds(year).unwrap_or((0, u64::MAX));
+
+    let gains: HashMap<(String, i64), pnl::DisposalGain> = pnl::compute_disposal_gains(entries)
+        .into_iter()
+        .map(|g| ((g.tx_hash.to_lowercase(), g.log_index), g))
+        .collect();
+
+    // 同一交易内同时有卖出和买入的视为交换
+    let mut sides_by_tx: HashMap<String, (bool, bool)> = HashMap::new();
+    for entry in entries {
+        let sides = sides_by_tx.entry(entry.tx_hash.to_lowercase()).or_default();
+        match entry.side {
+            LedgerSide::Acquire => sides.0 = true,
+            LedgerSide::Dispose => sides.1 = true,
+        }
+    }
+
+    let mut in_year: Vec<&LedgerEntry> = entries
+        .iter()
+        .filter(|e| (year_start..=year_end).contains(&e.timestamp))
+        .collect();
+    // 同一交易内先卖出后买入，便于合并为一行
+    in_year.sort_by_key(|e| (e.block_number, e.tx_hash.to_lowercase(), e.side == LedgerSide::Acquire, e.log_index));
+
+    let events = in_year
+        .into_iter()
+        .map(|entry| {
+            let tx_hash = entry.tx_hash.to_lowercase();
+            let is_trade = sides_by_tx.get(&tx_hash) == Some(&(true, true));
+            let gain = gains.get(&(tx_hash, entry.log_index));
+
+            TaxEvent {
+                timestamp: entry.timestamp,
+                tx_hash: entry.tx_hash.clone(),
+                kind: match entry.side {
+                    LedgerSide::Acquire => "acquisition",
+                    LedgerSide::Dispose => "disposal",
+                }
+                .to_string(),
+                label: if is_trade { "trade" } else { "transfer" }.to_string(),
+                token: entry.token.clone(),
+                symbol: entry.symbol.clone(),
+                amount: entry.amount.normalize().to_string(),
+                price_usd: entry.price_usd.round_dp(6).normalize().to_string(),
+                value_usd: format_usd(entry.amount * entry.price_usd),
+                fee_usd: format_usd(entry.fee_usd),
+                cost_basis_usd: gain.map(|g| format_usd(g.cost_basis_usd)),
+                gain_usd: gain.map(|g| format_usd(g.gain_usd)),
+                unmatched_amount: gain
+                    .filter(|g| !g.unmatched.is_zero())
+                    .map(|g| g.unmatched.normalize().to_string()),
+            }
+        })
+        .collect();
+
+    TaxReport {
+        address,
+        year,
+        format,
+        events,
+    }
+}
+
+impl CsvExport for TaxReport {
+    fn csv_headers(&self) -> Vec<&'static str> {
+        match self.format {
+            TaxReportFormat::Generic => vec![
+                "date",
+                "type",
+                "label",
+                "token",
+                "symbol",
+                "amount",
+                "price_usd",
+                "value_usd",
+                "fee_usd",
+                "cost_basis_usd",
+                "gain_usd",
+                "unmatched_amount",
+                "tx_hash",
+            ],
+            TaxReportFormat::Koinly => vec![
+                "Date",
+                "Sent Amount",
+                "Sent Currency",
+                "Received Amount",
+                "Received Currency",
+                "Fee Amount",
+                "Fee Currency",
+                "Net Worth Amount",
+                "Net Worth Currency",
+                "Label",
+                "Description",
+                "TxHash",
+            ],
+        }
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        match self.format {
+            TaxReportFormat::Generic => self
+                .events
+                .iter()
+                .map(|e| {
+                    vec![
+                        format_timestamp(e.timestamp, "%Y-%m-%dT%H:%M:%SZ"),
+                        e.kind.clone(),
+                        e.label.clone(),
+                        e.token.clone(),
+                        e.symbol.clone(),
+                        e.amount.clone(),
+                        e.price_usd.clone(),
+                        e.value_usd.clone(),
+                        e.fee_usd.clone(),
+                        e.cost_basis_usd.clone().unwrap_or_default(),
+                        e.gain_usd.clone().unwrap_or_default(),
+                        e.unmatched_amount.clone().unwrap_or_default(),
+                        e.tx_hash.clone(),
+                    ]
+                })
+                .collect(),
+            TaxReportFormat::Koinly => koinly_rows(&self.events),
+        }
+    }
+}
+
+/// 构建 Koinly Universal 格式的数据行
+/// 交换中的卖出与紧随其后的同一交易买入合并为一行
+fn koinly_rows(events: &[TaxEvent]) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut i = 0;
+
+    while i < events.len() {
+        let event = &events[i];
+        let paired = events
+            .get(i + 1)
+            .filter(|next| {
+                event.kind == "disposal"
+                    && next.kind == "acquisition"
+                    && next.tx_hash.eq_ignore_ascii_case(&event.tx_hash)
+            });
+
+        let (sent, received) = match (event.kind.as_str(), paired) {
+            (_, Some(next)) => (Some(event), Some(next)),
+            ("disposal", None) => (Some(event), None),
+            _ => (None, Some(event)),
+        };
+
+        let fee: Decimal = [sent, received]
+            .iter()
+            .flatten()
+            .filter_map(|e| e.fee_usd.parse::<Decimal>().ok())
+            .sum();
+        let description = match (sent, received) {
+            (Some(_), Some(_)) => "trade",
+            (Some(_), None) => "transfer out",
+            _ => "transfer in",
+        };
+
+        rows.push(vec![
+            format_timestamp(event.timestamp, "%Y-%m-%d %H:%M:%S UTC"),
+            sent.map(|e| e.amount.clone()).unwrap_or_default(),
+            sent.map(|e| e.symbol.clone()).unwrap_or_default(),
+            received.map(|e| e.amount.clone()).unwrap_or_default(),
+            received.map(|e| e.symbol.clone()).unwrap_or_default(),
+            if fee.is_zero() { String::new() } else { format_usd(fee) },
+            if fee.is_zero() { String::new() } else { "USD".to_string() },
+            sent.or(received).map(|e| e.value_usd.clone()).unwrap_or_default(),
+            "USD".to_string(),
+            String::new(),
+            description.to_string(),
+            event.tx_hash.clone(),
+        ]);
+
+        i += if paired.is_some() { 2 } else { 1 };
+    }
+
+    rows
+}
+
+/// 按格式输出 UTC 时间
+fn format_timestamp(timestamp: u64, fmt: &str) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp as i64, 0)
+        .map(|dt| dt.format(fmt).to_string())
+        .unwrap_or_default()
+}
+
+/// 测试模式使用的示例台账:一次转入和一次交换
+fn sample_entries(year_start: u64) -> Vec<LedgerEntry> {
+    let entry = |tx: &str, token: &str, symbol: &str, side, amount: i64, price: i64, day: u64| LedgerEntry {
+        token: token.to_string(),
+        symbol: symbol.to_string(),
+        side,
+        amount: Decimal::from(amount),
+        price_usd: Decimal::from(price),
+        fee_usd: Decimal::ZERO,
+        block_number: day,
+        timestamp: year_start + day * 86_400,
+        tx_hash: tx.to_string(),
+        log_index: if side == LedgerSide::Dispose { 0 } else { 1 },
+        source: "transfer".to_string(),
+    };
+
+    vec![
+        entry("0x01", "0x0000000000000000000000000000000000000001", "TEST", LedgerSide::Acquire, 10, 100, 1),
+        entry("0x02", "0x0000000000000000000000000000000000000001", "TEST", LedgerSide::Dispose, 5, 150, 30),
+        entry("0x02", "0x0000000000000000000000000000000000000002", "USDC", LedgerSide::Acquire, 750, 1, 30),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_year_bounds() {
+        let (start, end) = year_bounds(2024).unwrap();
+        assert_eq!(start, 1_704_067_200);
+        assert_eq!(end, 1_735_689_599);
+
+        assert!(year_bounds(2014).is_err());
+        assert!(year_bounds(Utc::now().year() + 1).is_err());
+    }
+
+    #[test]
+    fn test_build_generic_report() {
+        let (start, _) = year_bounds(2024).unwrap();
+        let mut entries = sample_entries(start);
+        // 往年的记录不输出，但仍参与成本计算
+        entries[0].timestamp = start - 86_400;
+
+        let report = build_tax_report("0xwallet".to_string(), 2024, TaxReportFormat::Generic, &entries);
+        assert_eq!(report.events.len(), 2);
+
+        let disposal = &report.events[0];
+        assert_eq!(disposal.kind, "disposal");
+        assert_eq!(disposal.label, "trade");
+        assert_eq!(disposal.cost_basis_usd, Some("500".to_string()));
+        assert_eq!(disposal.gain_usd, Some("250".to_string()));
+
+        let csv = export::to_csv(&report);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("date,type,label,token"));
+        assert!(lines[1].starts_with("2024-01-31T00:00:00Z,disposal,trade,"));
+    }
+
+    #[test]
+    fn test_build_koinly_report() {
+        let (start, _) = year_bounds(2024).unwrap();
+        let report = build_tax_report(
+            "0xwallet".to_string(),
+            2024,
+            TaxReportFormat::Koinly,
+            &sample_entries(start),
+        );
+
+        let csv = export::to_csv(&report);
+        let lines: Vec<&str> = csv.lines().collect();
+        // 表头 + 一次转入 + 一次合并后的交换
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "2024-01-02 00:00:00 UTC,,,10,TEST,,,1000,USD,,transfer in,0x01");
+        assert_eq!(lines[2], "2024-01-31 00:00:00 UTC,5,TEST,750,USDC,,,750,USD,,trade,0x02");
+    }
+
+    #[test]
+    fn test_tax_report_format_parse() {
+        assert_eq!(TaxReportFormat::parse(None).unwrap(), TaxReportFormat::Generic);
+        assert_eq!(TaxReportFormat::parse(Some("Koinly")).unwrap(), TaxReportFormat::Koinly);
+        assert!(TaxReportFormat::parse(Some("turbotax")).is_err());
+    }
+}
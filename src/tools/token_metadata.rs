@@ -0,0 +1,216 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    eth_client::EthClient,
+    logging::{info, warn},
+    token_lists::TokenListClient,
+    token_registry::TokenRegistry,
+    types::TokenInfo,
+};
+use chrono::{DateTime, Utc};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// GetTokenMetadata 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetTokenMetadataArgs {
+    /// 代币地址或符号(必需)
+    pub token: String,
+    /// 是否定位合约创建区块(可选,默认 true,需要归档节点,约 log2(区块高度) 次 RPC 调用)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub find_creation: Option<bool>,
+}
+
+/// GetTokenMetadata 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TokenMetadataResult {
+    /// 链上 symbol/name/decimals,以及收录该代币的主流代币列表
+    pub token: TokenInfo,
+    /// 地址上是否有合约代码
+    pub is_contract: bool,
+    /// 合约代码大小(字节)
+    pub code_size: usize,
+    /// 总供应量(最小单位)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_supply: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted_total_supply: Option<String>,
+    /// 合约代码首次出现的区块
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_block: Option<u64>,
+    /// 创建区块时间(UTC, RFC 3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creation_time_utc: Option<String>,
+    /// 代币是否已在代币注册表中(内置、文件、远程列表或手动注册)
+    pub in_registry: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 查询代币的链上元数据、总供应量和合约创建信息,用于在报价前审查陌生代币
+pub async fn get_token_metadata(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    token_lists: &Arc<TokenListClient>,
+    Parameters(args): Parameters<GetTokenMetadataArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_token_metadata 请求");
+
+    let find_creation = args.find_creation.unwrap_or(true);
+    info!(token = %args.token, find_creation, "查询代币元数据");
+
+    // 测试模式
+    if config.server.test_mode {
+        let total_supply = U256::exp10(24);
+        let result = TokenMetadataResult {
+            token: TokenInfo {
+                symbol: "TEST".to_string(),
+                name: "Test Token".to_string(),
+                address: args.token.clone(),
+                decimals: 18,
+                listed_on: Vec::new(),
+            },
+            is_contract: true,
+            code_size: 2048,
+            total_supply: Some(total_supply.to_string()),
+            formatted_total_supply: Some(format_units(total_supply, 18)),
+            creation_block: find_creation.then_some(18_000_000),
+            creation_time_utc: find_creation.then(|| "2023-08-26T00:00:00+00:00".to_string()),
+            in_registry: false,
+            notes: Vec::new(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() || !erc20_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let registered = token_registry
+        .resolve(&args.token)
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+    let token_addr: Address = registered
+        .address
+        .parse()
+        .map_err(|_| McpError::internal_error("无效的代币地址".to_string(), None))?;
+    let in_registry = registered.symbol != "UNKNOWN";
+
+    let code = eth_client
+        .get_code(token_addr, None)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询合约代码失败: {}", e), None))?;
+
+    let mut notes = Vec::new();
+    if code.is_empty() {
+        notes.push("该地址没有合约代码,不是 ERC20 代币".to_string());
+        let result = TokenMetadataResult {
+            token: registered,
+            is_contract: false,
+            code_size: 0,
+            total_supply: None,
+            formatted_total_supply: None,
+            creation_block: None,
+            creation_time_utc: None,
+            in_registry,
+            notes,
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 元数据、总供应量、代币列表收录情况和创建区块并行查询
+    // 未注册的代币不写入注册表,避免仿冒符号覆盖已有代币
+    let chain_id = config.ethereum.chain_id;
+    let (token_info, total_supply, listed_on, creation) = tokio::join!(
+        erc20_client.token_info(token_addr),
+        erc20_client.total_supply(token_addr),
+        token_lists.listed_on(chain_id, token_addr),
+        async {
+            if !find_creation {
+                return Ok(None);
+            }
+            let Some(block) = eth_client.find_contract_creation_block(token_addr).await? else {
+                return Ok(None);
+            };
+            let (_, timestamp) = eth_client
+                .get_block_timestamp(BlockNumber::Number(block.into()))
+                .await?;
+            Ok::<_, crate::eth_client::EthClientError>(Some((block, timestamp)))
+        }
+    );
+
+    let mut token = token_info
+        .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
+    token.listed_on = listed_on;
+
+    let total_supply = total_supply
+        .inspect_err(|e| {
+            warn!(error = %e, "查询 totalSupply 失败");
+            notes.push(format!("查询 totalSupply 失败: {}", e));
+        })
+        .ok();
+    let creation = creation
+        .inspect_err(|e| {
+            warn!(error = %e, "定位合约创建区块失败");
+            notes.push(format!("无法定位合约创建区块(需要归档节点): {}", e));
+        })
+        .ok()
+        .flatten();
+    if token.symbol == "UNKNOWN" {
+        notes.push("合约没有返回 symbol,可能不是标准 ERC20 代币".to_string());
+    }
+
+    let result = TokenMetadataResult {
+        is_contract: true,
+        code_size: code.len(),
+        formatted_total_supply: total_supply.map(|supply| format_units(supply, token.decimals)),
+        total_supply: total_supply.map(|supply| supply.to_string()),
+        creation_block: creation.map(|(block, _)| block),
+        creation_time_utc: creation.and_then(|(_, timestamp)| creation_time_utc(timestamp)),
+        in_registry,
+        notes,
+        token,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        symbol = %result.token.symbol,
+        creation_block = ?result.creation_block,
+        "成功返回代币元数据"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 区块时间戳转换为 RFC 3339 时间
+fn creation_time_utc(timestamp: u64) -> Option<String> {
+    DateTime::<Utc>::from_timestamp(timestamp as i64, 0).map(|t| t.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_creation_time_utc() {
+        assert_eq!(creation_time_utc(1_438_269_988).as_deref(), Some("2015-07-30T15:26:28+00:00"));
+    }
+}
@@ -0,0 +1,512 @@
+use crate::{
+    bindings::{i_uniswap_v2_pair, ierc20},
+    config::Config,
+    erc20::{approve_calldata, format_units, parse_units, Erc20Client},
+    eth_client::EthClient,
+    logging::{info, warn},
+    multicall::{Call3, Call3Result, MULTICALL3_ADDRESS},
+    token_registry::TokenRegistry,
+    tools::{
+        swap::transfer_fee_bps,
+        user_operation::{resolve_token, token_address},
+    },
+    types::{checksum_address, TokenInfo},
+    uniswap::{decode_revert_data, NativeLeg, SwapCall, UniswapV2Client},
+};
+use ethers::abi::AbiEncode;
+use ethers::prelude::*;
+use ethers::types::spoof;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 默认试买金额(ETH)
+const DEFAULT_BUY_AMOUNT_ETH: &str = "0.05";
+
+/// 买入或卖出税达到该值(基点)时标记为高税率
+const HIGH_TAX_BPS: u32 = 1000;
+
+/// 卖出税达到该值(基点)时视为无法正常卖出
+const HONEYPOT_TAX_BPS: u32 = 5000;
+
+/// 模拟调用方(没有代码的普通地址,通过状态覆盖注入 ETH)
+const PROBE_ACCOUNT: Address = H160([0x5a; 20]);
+
+/// CheckTokenSafety 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CheckTokenSafetyArgs {
+    /// 代币地址或符号(必需)
+    pub token: String,
+    /// 试买使用的 ETH 数量(可选,默认 "0.05")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub amount_eth: Option<String>,
+}
+
+/// CheckTokenSafety 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TokenSafetyResult {
+    pub token: TokenInfo,
+    /// 与包装原生代币的 Uniswap V2 交易对
+    pub pair: String,
+    /// 模拟所基于的区块
+    pub block_number: u64,
+    /// 卖出失败或卖出税过高,买入后无法正常卖出
+    pub is_honeypot: bool,
+    /// honeypot / high / medium / low
+    pub risk_level: String,
+    pub buy: TradeCheck,
+    /// 买入失败时不进行卖出模拟
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sell: Option<TradeCheck>,
+    /// 触发的风险标记
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 单次买入或卖出的模拟结果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TradeCheck {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    /// 支付数量(买入为 ETH,卖出为代币)
+    pub amount_in: String,
+    /// 按储备量计算的应得数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_output: Option<String>,
+    /// 实际到账数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual_output: Option<String>,
+    /// 实际到账相对应得数量的损耗(基点,包含代币合约收取的税费)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tax_bps: Option<u32>,
+}
+
+impl TradeCheck {
+    fn reverted(amount_in: String, reason: String) -> Self {
+        Self {
+            success: false,
+            revert_reason: Some(reason),
+            amount_in,
+            expected_output: None,
+            actual_output: None,
+            tax_bps: None,
+        }
+    }
+}
+
+/// 风险结论
+#[derive(Debug, PartialEq, Eq)]
+struct Verdict {
+    is_honeypot: bool,
+    risk_level: &'static str,
+    flags: Vec<String>,
+}
+
+/// 在状态覆盖的 eth_call 中通过 Router 试买并卖出代币,检测蜜罐和买卖税
+pub async fn check_token_safety(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<CheckTokenSafetyArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 check_token_safety 请求");
+
+    let amount_str = args.amount_eth.as_deref().unwrap_or(DEFAULT_BUY_AMOUNT_ETH);
+    let amount_in = parse_units(amount_str, 18)
+        .map_err(|e| McpError::invalid_params(format!("无效的 amount_eth: {}", e), None))?;
+    if amount_in.is_zero() {
+        return Err(McpError::invalid_params("amount_eth 必须大于 0", None));
+    }
+
+    info!(token = %args.token, amount_eth = %amount_str, "检查代币安全性");
+
+    // 测试模式
+    if config.server.test_mode {
+        let buy = TradeCheck {
+            success: true,
+            revert_reason: None,
+            amount_in: format_units(amount_in, 18),
+            expected_output: Some("100.0".to_string()),
+            actual_output: Some("100.0".to_string()),
+            tax_bps: Some(0),
+        };
+        let sell = TradeCheck {
+            amount_in: "100.0".to_string(),
+            expected_output: Some("0.0497".to_string()),
+            actual_output: Some("0.0497".to_string()),
+            ..buy.clone()
+        };
+        let verdict = verdict(&buy, Some(&sell));
+
+        let result = TokenSafetyResult {
+            token: TokenInfo {
+                symbol: "TEST".to_string(),
+                name: "Test Token".to_string(),
+                address: args.token.clone(),
+                decimals: 18,
+                listed_on: Vec::new(),
+            },
+            pair: "0x0000000000000000000000000000000000000003".to_string(),
+            block_number: 18_000_000,
+            is_honeypot: verdict.is_honeypot,
+            risk_level: verdict.risk_level.to_string(),
+            buy,
+            sell: Some(sell),
+            flags: verdict.flags,
+            notes: Vec::new(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !uniswap_client.is_available() || !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Uniswap 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let token = resolve_token(token_registry, erc20_client, &args.token).await?;
+    let token_addr = token_address(&token)?;
+    let weth = uniswap_client.weth_address();
+    if token_addr == weth {
+        return Err(McpError::invalid_params("不能检查包装原生代币本身", None));
+    }
+
+    let eth_client = eth_client.clone();
+    let uniswap_client = uniswap_client.clone();
+
+    let result = async {
+        let pair = uniswap_client.get_pair(token_addr, weth).await.map_err(|e| {
+            McpError::invalid_request(format!("代币没有 Uniswap V2 WETH 交易对: {}", e), None)
+        })?;
+        let block_number = eth_client
+            .get_block_number()
+            .await
+            .map_err(|e| McpError::internal_error(format!("获取区块号失败: {}", e), None))?;
+        let block = Some(BlockId::from(block_number));
+
+        let reserves = uniswap_client
+            .get_reserves_at(pair, block)
+            .await
+            .map_err(|e| McpError::internal_error(format!("获取储备量失败: {}", e), None))?;
+        let token_is_token0 = token_addr < weth;
+        let (weth_reserve, token_reserve) = orient(reserves, token_is_token0);
+        let expected_tokens = uniswap_client
+            .calculate_amount_out(amount_in, weth_reserve, token_reserve)
+            .map_err(|e| McpError::internal_error(format!("计算买入数量失败: {}", e), None))?;
+
+        let multicall: Address = MULTICALL3_ADDRESS.parse().expect("硬编码地址应该有效");
+        let router = uniswap_client.router_address();
+        let buy_call = Call3::new(
+            router,
+            SwapCall {
+                amount_in,
+                amount_out_min: U256::zero(),
+                path: vec![weth, token_addr],
+                to: multicall,
+                deadline: U256::MAX,
+                native: NativeLeg::Input,
+                fee_on_transfer: true,
+            }
+            .encode(),
+        )
+        .with_value(amount_in);
+        let balance_call = Call3::new(token_addr, ierc20::BalanceOfCall { owner: multicall }.encode());
+
+        let mut state = spoof::state();
+        state
+            .account(PROBE_ACCOUNT)
+            .balance(amount_in.saturating_add(U256::exp10(18)));
+
+        // 第一次调用:买入并读取到账数量和买入后的储备量
+        let results = eth_client
+            .simulate_multicall(
+                PROBE_ACCOUNT,
+                &[
+                    balance_call.clone(),
+                    buy_call.clone(),
+                    balance_call.clone(),
+                    Call3::new(pair, i_uniswap_v2_pair::GetReservesCall.encode()),
+                ],
+                &state,
+                block,
+            )
+            .await
+            .map_err(|e| McpError::internal_error(format!("模拟买入失败: {}", e), None))?;
+
+        let eth_amount = format_units(amount_in, 18);
+        let mut notes = Vec::new();
+        if !results[1].success {
+            let buy = TradeCheck::reverted(eth_amount, revert_reason(&results[1]));
+            notes.push("试买通过 Multicall3 合约执行,限制合约买入的代币也会回滚".to_string());
+            return Ok::<_, McpError>((pair, block_number, buy, None, notes));
+        }
+
+        let received = match (results[0].as_u256(), results[2].as_u256()) {
+            (Some(before), Some(after)) => after.saturating_sub(before),
+            _ => return Err(McpError::internal_error("读取买入后的代币余额失败", None)),
+        };
+        let buy = TradeCheck {
+            success: true,
+            revert_reason: None,
+            amount_in: eth_amount,
+            expected_output: Some(format_units(expected_tokens, token.decimals)),
+            actual_output: Some(format_units(received, token.decimals)),
+            tax_bps: Some(transfer_fee_bps(expected_tokens, received)),
+        };
+        let token_amount = format_units(received, token.decimals);
+        if received.is_zero() {
+            let sell = TradeCheck::reverted(token_amount, "买入后没有收到代币".to_string());
+            return Ok((pair, block_number, buy, Some(sell), notes));
+        }
+
+        let reserves_after = decode_reserves(&results[3])
+            .ok_or_else(|| McpError::internal_error("读取买入后的储备量失败", None))?;
+        let (weth_after, token_after) = orient(reserves_after, token_is_token0);
+        let expected_eth = uniswap_client
+            .calculate_amount_out(received, token_after, weth_after)
+            .map_err(|e| McpError::internal_error(format!("计算卖出数量失败: {}", e), None))?;
+
+        // 第二次调用:在同一次执行中重复买入,授权 Router 后卖出全部到账数量
+        let sell_call = Call3::new(
+            router,
+            SwapCall {
+                amount_in: received,
+                amount_out_min: U256::zero(),
+                path: vec![token_addr, weth],
+                to: PROBE_ACCOUNT,
+                deadline: U256::MAX,
+                native: NativeLeg::Output,
+                fee_on_transfer: true,
+            }
+            .encode(),
+        );
+        let results = eth_client
+            .simulate_multicall(
+                PROBE_ACCOUNT,
+                &[
+                    buy_call,
+                    Call3::new(token_addr, approve_calldata(router, U256::MAX)),
+                    Call3::eth_balance(PROBE_ACCOUNT),
+                    sell_call,
+                    Call3::eth_balance(PROBE_ACCOUNT),
+                ],
+                &state,
+                block,
+            )
+            .await
+            .map_err(|e| McpError::internal_error(format!("模拟卖出失败: {}", e), None))?;
+
+        let sell = if !results[1].success {
+            TradeCheck::reverted(token_amount, format!("授权 Router 失败: {}", revert_reason(&results[1])))
+        } else if !results[3].success {
+            TradeCheck::reverted(token_amount, revert_reason(&results[3]))
+        } else {
+            let proceeds = match (results[2].as_u256(), results[4].as_u256()) {
+                (Some(before), Some(after)) => after.saturating_sub(before),
+                _ => return Err(McpError::internal_error("读取卖出后的 ETH 余额失败", None)),
+            };
+            TradeCheck {
+                success: true,
+                revert_reason: None,
+                amount_in: token_amount,
+                expected_output: Some(format_units(expected_eth, 18)),
+                actual_output: Some(format_units(proceeds, 18)),
+                tax_bps: Some(transfer_fee_bps(expected_eth, proceeds)),
+            }
+        };
+
+        Ok((pair, block_number, buy, Some(sell), notes))
+    }
+    .await;
+
+    let (pair, block_number, buy, sell, mut notes) = result.inspect_err(|e| {
+        warn!(error = %e.message, "代币安全检查失败");
+    })?;
+
+    let verdict = verdict(&buy, sell.as_ref());
+    if verdict.flags.iter().any(|flag| flag.ends_with("_tax")) {
+        notes.push("税率按储备量计算的应得数量与实际到账数量之差估算,包含 0.3% 以外的全部损耗".to_string());
+    }
+
+    let result = TokenSafetyResult {
+        token,
+        pair: checksum_address(pair),
+        block_number,
+        is_honeypot: verdict.is_honeypot,
+        risk_level: verdict.risk_level.to_string(),
+        buy,
+        sell,
+        flags: verdict.flags,
+        notes,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        symbol = %result.token.symbol,
+        is_honeypot = result.is_honeypot,
+        risk_level = %result.risk_level,
+        "成功返回代币安全检查结果"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 按代币在交易对中的位置返回 (WETH 储备量, 代币储备量)
+fn orient((reserve0, reserve1): (U256, U256), token_is_token0: bool) -> (U256, U256) {
+    if token_is_token0 {
+        (reserve1, reserve0)
+    } else {
+        (reserve0, reserve1)
+    }
+}
+
+/// 解码子调用返回的 getReserves() -> (uint112, uint112, uint32)
+fn decode_reserves(result: &Call3Result) -> Option<(U256, U256)> {
+    if !result.success || result.return_data.len() < 64 {
+        return None;
+    }
+    let data = &result.return_data;
+    Some((U256::from_big_endian(&data[0..32]), U256::from_big_endian(&data[32..64])))
+}
+
+/// 失败子调用的回滚原因(无法解码时返回原始数据)
+fn revert_reason(result: &Call3Result) -> String {
+    if result.return_data.is_empty() {
+        return "交易回滚(没有返回原因)".to_string();
+    }
+    decode_revert_data(&result.return_data).unwrap_or_else(|| result.return_data.to_string())
+}
+
+/// 根据买入和卖出模拟结果给出风险结论
+fn verdict(buy: &TradeCheck, sell: Option<&TradeCheck>) -> Verdict {
+    let mut flags = Vec::new();
+
+    if !buy.success {
+        flags.push("buy_reverted".to_string());
+        return Verdict {
+            is_honeypot: false,
+            risk_level: "high",
+            flags,
+        };
+    }
+
+    let buy_tax = buy.tax_bps.unwrap_or(0);
+    let sell_tax = sell.and_then(|sell| sell.tax_bps).unwrap_or(0);
+    let sell_failed = sell.is_some_and(|sell| !sell.success);
+
+    if sell_failed {
+        flags.push("sell_reverted".to_string());
+    }
+    if buy_tax >= HONEYPOT_TAX_BPS || sell_tax >= HONEYPOT_TAX_BPS {
+        flags.push("absurd_tax".to_string());
+    }
+    if buy_tax >= HIGH_TAX_BPS {
+        flags.push("high_buy_tax".to_string());
+    }
+    if sell_tax >= HIGH_TAX_BPS {
+        flags.push("high_sell_tax".to_string());
+    }
+
+    let is_honeypot = sell_failed || sell_tax >= HONEYPOT_TAX_BPS;
+    let risk_level = if is_honeypot {
+        "honeypot"
+    } else if !flags.is_empty() {
+        "high"
+    } else if buy_tax > 0 || sell_tax > 0 {
+        "medium"
+    } else {
+        "low"
+    };
+
+    Verdict {
+        is_honeypot,
+        risk_level,
+        flags,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(tax_bps: u32) -> TradeCheck {
+        TradeCheck {
+            success: true,
+            revert_reason: None,
+            amount_in: "1.0".to_string(),
+            expected_output: Some("1.0".to_string()),
+            actual_output: Some("1.0".to_string()),
+            tax_bps: Some(tax_bps),
+        }
+    }
+
+    #[test]
+    fn test_verdict_clean_token() {
+        let verdict = verdict(&trade(0), Some(&trade(0)));
+        assert!(!verdict.is_honeypot);
+        assert_eq!(verdict.risk_level, "low");
+        assert!(verdict.flags.is_empty());
+
+        assert_eq!(super::verdict(&trade(300), Some(&trade(300))).risk_level, "medium");
+    }
+
+    #[test]
+    fn test_verdict_taxes_and_reverts() {
+        let high = verdict(&trade(0), Some(&trade(1500)));
+        assert!(!high.is_honeypot);
+        assert_eq!(high.risk_level, "high");
+        assert_eq!(high.flags, vec!["high_sell_tax"]);
+
+        let absurd = verdict(&trade(0), Some(&trade(9000)));
+        assert!(absurd.is_honeypot);
+        assert_eq!(absurd.flags, vec!["absurd_tax", "high_sell_tax"]);
+
+        let stuck = verdict(&trade(0), Some(&TradeCheck::reverted("1.0".to_string(), "TRANSFER_FAILED".to_string())));
+        assert!(stuck.is_honeypot);
+        assert_eq!(stuck.risk_level, "honeypot");
+        assert_eq!(stuck.flags, vec!["sell_reverted"]);
+
+        let unbuyable = verdict(&TradeCheck::reverted("0.05".to_string(), "Trading not open".to_string()), None);
+        assert!(!unbuyable.is_honeypot);
+        assert_eq!(unbuyable.risk_level, "high");
+        assert_eq!(unbuyable.flags, vec!["buy_reverted"]);
+    }
+
+    #[test]
+    fn test_decode_reserves_and_orient() {
+        let mut data = vec![0u8; 96];
+        data[31] = 7;
+        data[63] = 9;
+        let result = Call3Result {
+            success: true,
+            return_data: Bytes::from(data),
+        };
+        let reserves = decode_reserves(&result).unwrap();
+        assert_eq!(reserves, (U256::from(7), U256::from(9)));
+
+        // 代币为 token0 时 WETH 储备量在第二位
+        assert_eq!(orient(reserves, true), (U256::from(9), U256::from(7)));
+        assert_eq!(orient(reserves, false), (U256::from(7), U256::from(9)));
+
+        let failed = Call3Result {
+            success: false,
+            return_data: Bytes::new(),
+        };
+        assert!(decode_reserves(&failed).is_none());
+        assert_eq!(revert_reason(&failed), "交易回滚(没有返回原因)");
+    }
+}
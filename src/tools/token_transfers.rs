@@ -0,0 +1,367 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client, TransferLog},
+    eth_client::EthClient,
+    etherscan::{EtherscanClient, ETHERSCAN_TRANSFER_PAGE_SIZE},
+    logging::{info, warn},
+    token_registry::TokenRegistry,
+    tools::user_operation::{resolve_token, token_address},
+    types::{checksum_address, parse_address, TokenInfo},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 未指定 from_block 时回溯的区块数(约一周)
+const DEFAULT_TRANSFER_RANGE_BLOCKS: u64 = 50_000;
+/// 通过 eth_getLogs 查询的最大区块区间,更长的历史使用 Etherscan
+const MAX_RPC_TRANSFER_RANGE_BLOCKS: u64 = 200_000;
+/// 默认返回的转账数量
+const DEFAULT_TRANSFERS_LIMIT: usize = 50;
+/// 单次查询允许返回的最大转账数量
+const MAX_TRANSFERS_LIMIT: usize = 500;
+
+/// GetTokenTransfers 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetTokenTransfersArgs {
+    /// 钱包地址(必需)
+    pub wallet: String,
+    /// 代币地址或符号(可选,不填则查询代币注册表中的所有代币)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// 起始区块(可选,默认 to_block 前 50000 个区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_block: Option<u64>,
+    /// 结束区块(可选,默认最新区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_block: Option<u64>,
+    /// 返回数量上限(可选,默认 50,最大 500,优先返回最新的转账)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// GetTokenTransfers 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TokenTransfersResult {
+    pub wallet: String,
+    /// 查询的代币,查询注册表全部代币时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<TokenInfo>,
+    pub from_block: u64,
+    pub to_block: u64,
+    /// 数据来源(rpc/etherscan)
+    pub source: String,
+    /// 区间内匹配的转账总数
+    pub total_found: usize,
+    pub transfers: Vec<WalletTransfer>,
+    /// 因代币不在注册表中而忽略的转账数(常见于空投的垃圾代币)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub skipped_unregistered: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 钱包的一笔代币转账
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct WalletTransfer {
+    pub token_symbol: String,
+    pub token_address: String,
+    /// 转账方向(in/out/self)
+    pub direction: String,
+    /// 对手方地址,自转账时为钱包本身
+    pub counterparty: String,
+    pub amount: String,
+    pub formatted_amount: String,
+    pub block_number: u64,
+    pub tx_hash: String,
+    pub log_index: u64,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+/// 查询钱包最近的 ERC20 转入和转出记录
+#[allow(clippy::too_many_arguments)]
+pub async fn get_token_transfers(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    etherscan_client: &Arc<EtherscanClient>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<GetTokenTransfersArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_token_transfers 请求");
+
+    let wallet = parse_address(&args.wallet).map_err(|e| McpError::invalid_params(e, None))?;
+    let limit = args.limit.unwrap_or(DEFAULT_TRANSFERS_LIMIT);
+    if limit == 0 || limit > MAX_TRANSFERS_LIMIT {
+        return Err(McpError::invalid_params(
+            format!("limit 必须在 1 到 {} 之间", MAX_TRANSFERS_LIMIT),
+            None,
+        ));
+    }
+    if let (Some(from_block), Some(to_block)) = (args.from_block, args.to_block)
+        && from_block > to_block
+    {
+        return Err(McpError::invalid_params(
+            format!("from_block ({}) 不能大于 to_block ({})", from_block, to_block),
+            None,
+        ));
+    }
+
+    info!(
+        wallet = ?wallet,
+        token = ?args.token,
+        from_block = ?args.from_block,
+        to_block = ?args.to_block,
+        limit,
+        "查询钱包代币转账"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let token = TokenInfo {
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            address: checksum_address(Address::repeat_byte(0x11)),
+            decimals: 6,
+            listed_on: Vec::new(),
+        };
+        let to_block = args.to_block.unwrap_or(20_000_000);
+        let from_block = args
+            .from_block
+            .unwrap_or(to_block.saturating_sub(DEFAULT_TRANSFER_RANGE_BLOCKS - 1));
+        let transfers = vec![TransferLog {
+            token: Address::repeat_byte(0x11),
+            from: Address::repeat_byte(0x22),
+            to: wallet,
+            value: U256::from(1_500_000_000u64),
+            block_number: to_block,
+            tx_hash: H256::zero(),
+            log_index: 0,
+        }];
+        let tokens = HashMap::from([(Address::repeat_byte(0x11), token)]);
+        let (total_found, transfers, skipped_unregistered) = build_transfers(wallet, transfers, &tokens, limit);
+        let result = TokenTransfersResult {
+            wallet: checksum_address(wallet),
+            token: None,
+            from_block,
+            to_block,
+            source: "rpc".to_string(),
+            total_found,
+            transfers,
+            skipped_unregistered,
+            notes: Vec::new(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() || !erc20_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let token = match args.token {
+        Some(ref token) => Some(resolve_token(token_registry, erc20_client, token).await?),
+        None => None,
+    };
+    let token_addr = token.as_ref().map(token_address).transpose()?;
+    // 指定代币时只查询该代币;否则查询全部转账并只保留注册表中的代币
+    let tokens: HashMap<Address, TokenInfo> = match (&token, token_addr) {
+        (Some(token), Some(address)) => HashMap::from([(address, token.clone())]),
+        _ => token_registry
+            .all_tokens()
+            .into_iter()
+            .filter_map(|info| Some((info.address.parse().ok()?, info)))
+            .collect(),
+    };
+
+    let to_block = match args.to_block {
+        Some(block) => block,
+        None => eth_client
+            .get_block_number()
+            .await
+            .map_err(|e| McpError::internal_error(format!("获取区块高度失败: {}", e), None))?,
+    };
+    let from_block = args
+        .from_block
+        .unwrap_or(to_block.saturating_sub(DEFAULT_TRANSFER_RANGE_BLOCKS - 1))
+        .min(to_block);
+
+    let mut notes = Vec::new();
+    let range_blocks = to_block - from_block + 1;
+    let use_rpc = range_blocks <= MAX_RPC_TRANSFER_RANGE_BLOCKS;
+    if !use_rpc && !etherscan_client.is_available() {
+        return Err(McpError::invalid_params(
+            format!(
+                "区块区间过大: {} 个区块(eth_getLogs 最多 {} 个),请缩小区间或配置 ETHERSCAN_API_KEY 查询更早的历史",
+                range_blocks, MAX_RPC_TRANSFER_RANGE_BLOCKS
+            ),
+            None,
+        ));
+    }
+
+    let rpc_transfers = if use_rpc {
+        let filter: Vec<Address> = token_addr.into_iter().collect();
+        match erc20_client.transfer_logs(wallet, &filter, from_block, to_block).await {
+            Ok(transfers) => Some(transfers),
+            Err(e) if etherscan_client.is_available() => {
+                // 节点限制日志查询时回退到 Etherscan
+                warn!(error = %e, "eth_getLogs 查询转账失败,改用 Etherscan");
+                notes.push(format!("eth_getLogs 查询失败,改用 Etherscan: {}", e));
+                None
+            }
+            Err(e) => {
+                return Err(McpError::internal_error(format!("查询转账记录失败: {}", e), None));
+            }
+        }
+    } else {
+        None
+    };
+
+    let (source, transfers) = match rpc_transfers {
+        Some(transfers) => ("rpc", transfers),
+        None => {
+            let transfers = etherscan_client
+                .token_transfers(wallet, token_addr, from_block, to_block)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询 Etherscan 转账记录失败: {}", e), None))?;
+            if transfers.len() >= ETHERSCAN_TRANSFER_PAGE_SIZE {
+                notes.push(format!(
+                    "Etherscan 最多返回最新的 {} 条转账,更早的记录请缩小区间查询",
+                    ETHERSCAN_TRANSFER_PAGE_SIZE
+                ));
+            }
+            ("etherscan", transfers)
+        }
+    };
+
+    let (total_found, transfers, skipped_unregistered) = build_transfers(wallet, transfers, &tokens, limit);
+
+    let result = TokenTransfersResult {
+        wallet: checksum_address(wallet),
+        token,
+        from_block,
+        to_block,
+        source: source.to_string(),
+        total_found,
+        transfers,
+        skipped_unregistered,
+        notes,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        source = %result.source,
+        total_found,
+        returned = result.transfers.len(),
+        "成功返回钱包代币转账"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 只保留 `tokens` 中的代币,按区块倒序取最新的 `limit` 条
+/// 返回 (匹配总数, 转账列表, 忽略的未注册代币转账数)
+fn build_transfers(
+    wallet: Address,
+    mut transfers: Vec<TransferLog>,
+    tokens: &HashMap<Address, TokenInfo>,
+    limit: usize,
+) -> (usize, Vec<WalletTransfer>, usize) {
+    let scanned = transfers.len();
+    transfers.retain(|transfer| tokens.contains_key(&transfer.token));
+    let skipped = scanned - transfers.len();
+    let total_found = transfers.len();
+
+    transfers.sort_by_key(|transfer| std::cmp::Reverse((transfer.block_number, transfer.log_index)));
+    let transfers = transfers
+        .into_iter()
+        .take(limit)
+        .map(|transfer| {
+            let token = &tokens[&transfer.token];
+            let (direction, counterparty) = if transfer.from == wallet && transfer.to == wallet {
+                ("self", wallet)
+            } else if transfer.to == wallet {
+                ("in", transfer.from)
+            } else {
+                ("out", transfer.to)
+            };
+            WalletTransfer {
+                token_symbol: token.symbol.clone(),
+                token_address: checksum_address(transfer.token),
+                direction: direction.to_string(),
+                counterparty: checksum_address(counterparty),
+                amount: transfer.value.to_string(),
+                formatted_amount: format_units(transfer.value, token.decimals),
+                block_number: transfer.block_number,
+                tx_hash: format!("{:?}", transfer.tx_hash),
+                log_index: transfer.log_index,
+            }
+        })
+        .collect();
+
+    (total_found, transfers, skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_transfers() {
+        let wallet = Address::repeat_byte(0xaa);
+        let usdc = Address::repeat_byte(0x11);
+        let spam = Address::repeat_byte(0x99);
+        let transfer = |token, from, to, block_number| TransferLog {
+            token,
+            from,
+            to,
+            value: U256::from(2_500_000u64),
+            block_number,
+            tx_hash: H256::zero(),
+            log_index: 0,
+        };
+        let transfers = vec![
+            transfer(usdc, Address::repeat_byte(0x22), wallet, 10),
+            transfer(usdc, wallet, Address::repeat_byte(0x33), 12),
+            transfer(spam, Address::repeat_byte(0x44), wallet, 13),
+            transfer(usdc, wallet, wallet, 11),
+        ];
+        let tokens = HashMap::from([(
+            usdc,
+            TokenInfo {
+                symbol: "USDC".to_string(),
+                name: "USD Coin".to_string(),
+                address: checksum_address(usdc),
+                decimals: 6,
+                listed_on: Vec::new(),
+            },
+        )]);
+
+        let (total_found, result, skipped) = build_transfers(wallet, transfers, &tokens, 2);
+        assert_eq!(total_found, 3);
+        assert_eq!(skipped, 1);
+        assert_eq!(result.len(), 2);
+
+        // 按区块倒序
+        assert_eq!(result[0].block_number, 12);
+        assert_eq!(result[0].direction, "out");
+        assert_eq!(result[0].counterparty, checksum_address(Address::repeat_byte(0x33)));
+        assert_eq!(result[0].formatted_amount, "2.5");
+        assert_eq!(result[1].direction, "self");
+    }
+}
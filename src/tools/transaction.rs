@@ -0,0 +1,230 @@
+use crate::{
+    bindings::known_method_signature,
+    config::Config,
+    erc20::format_units,
+    eth_client::EthClient,
+    logging::info,
+    tools::execute_swap::receipt_status,
+    types::checksum_address,
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// GetTransaction 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetTransactionArgs {
+    /// 交易哈希(必需,0x 开头的 32 字节十六进制)
+    pub tx_hash: String,
+}
+
+/// GetTransaction 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TransactionResult {
+    pub tx_hash: String,
+    /// 交易状态(pending/success/reverted)
+    pub status: String,
+    pub from: String,
+    /// 接收地址,创建合约的交易为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// 创建的合约地址
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_address: Option<String>,
+    pub nonce: String,
+    /// 转账的 ETH 数量
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// 确认数(包含所在区块),未打包时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+    pub gas_limit: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_used: Option<String>,
+    /// 实际支付的单位 Gas 费用,未打包时为交易的 gasPrice
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_gas_price_gwei: Option<String>,
+    /// 实际支付的交易费用
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_eth: Option<String>,
+    /// 函数选择器
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// 识别出的函数签名(ERC20、Uniswap V2 Router)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// 事件日志数量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_count: Option<usize>,
+}
+
+/// 按哈希查询交易状态、回执和确认数
+pub async fn get_transaction(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    Parameters(args): Parameters<GetTransactionArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_transaction 请求");
+
+    let hash = parse_tx_hash(&args.tx_hash).map_err(|e| McpError::invalid_params(e, None))?;
+
+    info!(tx_hash = ?hash, "查询交易");
+
+    // 测试模式
+    if config.server.test_mode {
+        let tx = Transaction {
+            hash,
+            from: config.get_simulation_address(),
+            to: Some(Address::repeat_byte(0x11)),
+            gas: U256::from(150_000u64),
+            gas_price: Some(U256::from(20u64) * U256::exp10(9)),
+            input: Bytes::from(vec![0x38, 0xed, 0x17, 0x39]),
+            block_number: Some(U64::from(18_000_000u64)),
+            ..Default::default()
+        };
+        let receipt = TransactionReceipt {
+            transaction_hash: hash,
+            status: Some(U64::from(1)),
+            block_number: Some(U64::from(18_000_000u64)),
+            gas_used: Some(U256::from(120_000u64)),
+            effective_gas_price: Some(U256::from(20u64) * U256::exp10(9)),
+            ..Default::default()
+        };
+        let result = build_transaction_result(&tx, Some(&receipt), 18_000_011);
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let (transaction, latest_block) = tokio::join!(eth_client.get_transaction(hash), eth_client.get_block_number());
+    let (tx, receipt) = transaction
+        .map_err(|e| McpError::internal_error(format!("查询交易失败: {}", e), None))?
+        .ok_or_else(|| {
+            McpError::invalid_params(
+                format!("未找到交易 {:?},可能尚未广播、已被替换或节点未同步", hash),
+                None,
+            )
+        })?;
+    let latest_block = latest_block
+        .map_err(|e| McpError::internal_error(format!("获取区块高度失败: {}", e), None))?;
+
+    let result = build_transaction_result(&tx, receipt.as_ref(), latest_block);
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(tx_hash = %result.tx_hash, status = %result.status, "成功返回交易信息");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 解析 0x 开头的 32 字节交易哈希
+fn parse_tx_hash(value: &str) -> Result<H256, String> {
+    let value = value.trim();
+    let hex = value
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("交易哈希必须以 0x 开头: {}", value))?;
+    if hex.len() != 64 {
+        return Err(format!("交易哈希长度必须为 32 字节: {}", value));
+    }
+    value.parse().map_err(|_| format!("无效的交易哈希: {}", value))
+}
+
+/// 汇总交易和回执,`latest_block` 用于计算确认数
+fn build_transaction_result(
+    tx: &Transaction,
+    receipt: Option<&TransactionReceipt>,
+    latest_block: u64,
+) -> TransactionResult {
+    let block_number = receipt
+        .and_then(|r| r.block_number)
+        .or(tx.block_number)
+        .map(|n| n.as_u64());
+    let gas_used = receipt.and_then(|r| r.gas_used);
+    let effective_gas_price = receipt.and_then(|r| r.effective_gas_price).or(tx.gas_price);
+
+    TransactionResult {
+        tx_hash: format!("{:?}", tx.hash),
+        status: receipt_status(receipt).to_string(),
+        from: checksum_address(tx.from),
+        to: tx.to.map(checksum_address),
+        contract_address: receipt.and_then(|r| r.contract_address).map(checksum_address),
+        nonce: tx.nonce.to_string(),
+        value: format_units(tx.value, 18),
+        block_number,
+        confirmations: receipt
+            .and(block_number)
+            .map(|block| latest_block.saturating_sub(block) + 1),
+        gas_limit: tx.gas.to_string(),
+        gas_used: gas_used.map(|gas| gas.to_string()),
+        effective_gas_price_gwei: effective_gas_price.map(|price| format_units(price, 9)),
+        fee_eth: gas_used
+            .zip(effective_gas_price)
+            .map(|(gas, price)| format_units(gas * price, 18)),
+        selector: tx.input.get(..4).map(|selector| Bytes::from(selector.to_vec()).to_string()),
+        method: known_method_signature(&tx.input),
+        log_count: receipt.map(|r| r.logs.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tx_hash() {
+        let hash = format!("0x{}", "ab".repeat(32));
+        assert_eq!(parse_tx_hash(&hash).unwrap(), H256::repeat_byte(0xab));
+        assert!(parse_tx_hash(&"ab".repeat(32)).is_err());
+        assert!(parse_tx_hash("0x1234").is_err());
+        assert!(parse_tx_hash(&format!("0x{}", "zz".repeat(32))).is_err());
+    }
+
+    #[test]
+    fn test_build_transaction_result() {
+        let tx = Transaction {
+            gas: U256::from(100_000u64),
+            gas_price: Some(U256::from(30u64) * U256::exp10(9)),
+            input: Bytes::from(vec![0x09, 0x5e, 0xa7, 0xb3, 0x00]),
+            value: U256::exp10(17),
+            ..Default::default()
+        };
+
+        // 未打包:没有确认数和费用,单位费用取交易的 gasPrice
+        let pending = build_transaction_result(&tx, None, 100);
+        assert_eq!(pending.status, "pending");
+        assert_eq!(pending.confirmations, None);
+        assert_eq!(pending.fee_eth, None);
+        assert_eq!(pending.effective_gas_price_gwei.as_deref(), Some("30"));
+        assert_eq!(pending.value, "0.1");
+        assert_eq!(pending.selector.as_deref(), Some("0x095ea7b3"));
+        assert_eq!(pending.method.as_deref(), Some("approve(address,uint256)"));
+
+        let receipt = TransactionReceipt {
+            status: Some(U64::zero()),
+            block_number: Some(U64::from(95u64)),
+            gas_used: Some(U256::from(50_000u64)),
+            effective_gas_price: Some(U256::from(20u64) * U256::exp10(9)),
+            ..Default::default()
+        };
+        let reverted = build_transaction_result(&tx, Some(&receipt), 100);
+        assert_eq!(reverted.status, "reverted");
+        assert_eq!(reverted.block_number, Some(95));
+        assert_eq!(reverted.confirmations, Some(6));
+        assert_eq!(reverted.effective_gas_price_gwei.as_deref(), Some("20"));
+        assert_eq!(reverted.fee_eth.as_deref(), Some("0.001"));
+    }
+}
@@ -0,0 +1,615 @@
+use crate::{
+    compliance::{ComplianceScreen, ScreeningDecision},
+    config::Config,
+    erc20::{format_units, parse_units, transfer_calldata, Erc20Client},
+    eth_client::EthClient,
+    logging::info,
+    safe::{SafeClient, SafeProposal},
+    store::Store,
+    token_registry::TokenRegistry,
+    tools::execute_swap::{gas_limit_with_buffer, receipt_status, record_ledger_fill, RECEIPT_TIMEOUT},
+    tools::user_operation::{resolve_token, token_address},
+    signer::TxSigner,
+    tx_manager::TxManager,
+    types::{checksum_address, parse_address, TokenInfo, TxType},
+    uniswap::UniswapV2Client,
+};
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// TransferToken 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct TransferTokenArgs {
+    /// ERC20 代币地址或符号,原生代币符号(如 ETH)表示直接转账 ETH(必需)
+    pub token: String,
+    /// 接收地址(必需)
+    pub to: String,
+    /// 转账数量(必需,如 "1.5")
+    pub amount: String,
+    /// 发送方地址(可选,只模拟时使用,默认使用配置的模拟地址;广播时固定为签名器地址)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// 交易类型(可选,auto/legacy/eip1559,默认使用 TX_TYPE 配置)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<String>,
+    /// 为 true 时使用配置的签名器签名并广播(可选,默认 false,只构建和模拟)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<bool>,
+}
+
+/// TransferToken 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TransferTokenResult {
+    pub token: TokenInfo,
+    /// 是否为原生代币转账
+    pub native: bool,
+    pub from: String,
+    pub to: String,
+    /// 转账数量(最小单位)
+    pub amount: String,
+    pub formatted_amount: String,
+    /// 发送方的代币余额(最小单位)
+    pub balance: String,
+    pub formatted_balance: String,
+    /// 代币余额足够转账,且 ETH 余额足够支付 Gas
+    pub balance_sufficient: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance_error: Option<String>,
+    /// 未签名的交易字段,可交给外部钱包签名
+    pub transaction: UnsignedTransaction,
+    /// eth_call 模拟是否成功(回滚或返回 false 时为 false)
+    pub simulation_success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simulation_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_estimate: Option<String>,
+    /// 按最高单位 Gas 费用计算的手续费上限(ETH)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_eth: Option<String>,
+    /// 是否已签名并广播
+    pub sent: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+    /// success / reverted / pending(仅广播后返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// 返回时的确认数(仅广播后返回)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmations: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    /// 制裁名单命中但只标记时的筛查结论
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ScreeningDecision>,
+    /// Safe 模式下的 Safe 交易数据和提议结果
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub safe: Option<SafeProposal>,
+}
+
+/// 未签名交易字段(数值为十进制字符串)
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct UnsignedTransaction {
+    pub tx_type: String,
+    pub chain_id: u64,
+    pub from: String,
+    pub to: String,
+    pub value: String,
+    pub data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_priority_fee_per_gas: Option<String>,
+}
+
+/// 构建并模拟 ERC20 或 ETH 转账,确认后签名广播(Safe 模式下确认后提议到 Safe 交易服务)
+#[allow(clippy::too_many_arguments)]
+pub async fn transfer_token(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    erc20_client: &Arc<Erc20Client>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    token_registry: &Arc<TokenRegistry>,
+    store: &Arc<Store>,
+    compliance: &Arc<ComplianceScreen>,
+    tx_manager: &Arc<TxManager>,
+    safe_client: &Arc<SafeClient>,
+    signer: Option<&TxSigner>,
+    Parameters(args): Parameters<TransferTokenArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 transfer_token 请求");
+
+    let confirm = args.confirm.unwrap_or(false);
+    let recipient = parse_address(&args.to).map_err(|e| McpError::invalid_params(e, None))?;
+    if recipient.is_zero() {
+        return Err(McpError::invalid_params("接收地址不能是零地址", None));
+    }
+    let from = args
+        .from
+        .as_deref()
+        .map(parse_address)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let tx_type_preference = config
+        .tx_type_preference(args.tx_type.as_deref())
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    // Safe 模式下从 Safe 转出,签名器(可选)只用于提议
+    let safe = safe_client.safe_address();
+    if let (Some(safe), Some(from)) = (safe, from)
+        && from != safe
+    {
+        return Err(McpError::invalid_params(
+            format!(
+                "from ({}) 与 SAFE_ADDRESS ({}) 不一致",
+                checksum_address(from),
+                checksum_address(safe)
+            ),
+            None,
+        ));
+    }
+
+    // 广播需要签名器,只模拟时以 from 或默认模拟地址作为发送方
+    let wallet = if confirm && safe.is_none() {
+        let wallet = signer.ok_or_else(|| McpError::invalid_params("未配置签名器,无法广播转账交易", None))?;
+        if let Some(from) = from
+            && from != wallet.address()
+        {
+            return Err(McpError::invalid_params(
+                format!(
+                    "from ({}) 与签名器地址 ({}) 不一致",
+                    checksum_address(from),
+                    checksum_address(wallet.address())
+                ),
+                None,
+            ));
+        }
+        Some(wallet)
+    } else {
+        None
+    };
+    let owner = safe
+        .or(wallet.as_ref().map(|wallet| wallet.address()))
+        .or(from)
+        .unwrap_or_else(|| config.get_simulation_address());
+
+    let chain = config.chain();
+    let native = args.token.trim().eq_ignore_ascii_case(chain.native_symbol);
+    let chain_id = config.ethereum.chain_id;
+
+    info!(
+        token = %args.token,
+        to = ?recipient,
+        amount = %args.amount,
+        native,
+        confirm,
+        "构建转账交易"
+    );
+
+    // 测试模式:不访问 RPC,也不签名
+    if config.server.test_mode {
+        let token = if native {
+            TokenInfo::native(chain)
+        } else {
+            TokenInfo {
+                symbol: "TEST".to_string(),
+                name: "Test Token".to_string(),
+                address: args.token.clone(),
+                decimals: 18,
+                listed_on: Vec::new(),
+            }
+        };
+        let amount = parse_transfer_amount(&args.amount, token.decimals)?;
+        let mut tx = build_transfer_tx(TxType::Eip1559, owner, recipient, None, amount);
+        tx.set_chain_id(chain_id).set_nonce(0).set_gas(if native { 21_000 } else { 65_000 });
+
+        let result = TransferTokenResult {
+            native,
+            from: checksum_address(owner),
+            to: checksum_address(recipient),
+            amount: amount.to_string(),
+            formatted_amount: format_units(amount, token.decimals),
+            balance: amount.to_string(),
+            formatted_balance: format_units(amount, token.decimals),
+            balance_sufficient: true,
+            balance_error: None,
+            transaction: unsigned_transaction(&tx, TxType::Eip1559),
+            simulation_success: true,
+            simulation_error: None,
+            gas_estimate: tx.gas().map(|gas| gas.to_string()),
+            max_fee_eth: None,
+            sent: confirm,
+            tx_hash: confirm.then(|| format!("{:?}", H256::zero())),
+            nonce: confirm.then_some(0),
+            status: confirm.then(|| "success".to_string()),
+            confirmations: confirm.then_some(config.trading.tx_confirmations),
+            block_number: None,
+            notes: Vec::new(),
+            compliance: None,
+            safe: None,
+            token,
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !eth_client.is_available() || !erc20_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    // 🛡️ 制裁名单筛查
+    let screening = compliance.screen(store, "transfer_token", &[("wallet", owner), ("recipient", recipient)])?;
+
+    let eth_client = eth_client.clone();
+    let tx_manager = tx_manager.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+    let gas_strategy = config.trading.gas_price_strategy.clone();
+    let max_gas_limit = config.trading.max_gas_limit;
+
+    let mut result = async {
+        let (token_info, token_addr) = if native {
+            (TokenInfo::native(chain), None)
+        } else {
+            let info = resolve_token(&token_registry, &erc20_client, &args.token).await?;
+            let address = token_address(&info)?;
+            (info, Some(address))
+        };
+        let amount = parse_transfer_amount(&args.amount, token_info.decimals)?;
+
+        let mut notes = Vec::new();
+        if token_addr == Some(recipient) {
+            notes.push("接收地址是代币合约本身,转入的代币通常无法取回".to_string());
+        }
+
+        let owner_str = format!("{:?}", owner);
+        let (eth_balance, token_balance, nonce, tx_type) = tokio::join!(
+            eth_client.get_balance(&owner_str, None),
+            async {
+                match token_addr {
+                    Some(token_addr) => erc20_client.balance_of(token_addr, owner, None).await.map(Some),
+                    None => Ok(None),
+                }
+            },
+            eth_client.get_transaction_count(owner, BlockNumber::Pending),
+            eth_client.resolve_tx_type(tx_type_preference),
+        );
+        let eth_balance = eth_balance
+            .map_err(|e| McpError::internal_error(format!("查询 ETH 余额失败: {}", e), None))?;
+        let token_balance = token_balance
+            .map_err(|e| McpError::internal_error(format!("查询代币余额失败: {}", e), None))?;
+        let nonce = nonce.map_err(|e| McpError::internal_error(format!("查询 nonce 失败: {}", e), None))?;
+        let tx_type = tx_type.map_err(|e| McpError::internal_error(format!("探测交易类型失败: {}", e), None))?;
+        let balance = token_balance.unwrap_or(eth_balance);
+
+        let mut tx = build_transfer_tx(tx_type, owner, recipient, token_addr, amount);
+        tx.set_chain_id(chain_id).set_nonce(nonce);
+
+        // 模拟:回滚或返回 false 都视为失败
+        let simulation = match eth_client.call(&tx).await {
+            Ok(output) if transfer_succeeded(&output) => Ok(()),
+            Ok(_) => Err("transfer 返回 false".to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+
+        let mut gas_estimate = None;
+        let mut max_fee = None;
+        if simulation.is_ok() {
+            let estimate = eth_client
+                .estimate_gas(&tx)
+                .await
+                .map_err(|e| McpError::internal_error(format!("估算 Gas 失败: {}", e), None))?;
+            let gas_limit = gas_limit_with_buffer(estimate, max_gas_limit)?;
+            let fees = eth_client
+                .estimate_tx_fees(&gas_strategy, tx_type)
+                .await
+                .map_err(|e| McpError::internal_error(format!("估算 Gas 费用失败: {}", e), None))?;
+            fees.apply(&mut tx);
+            tx.set_gas(gas_limit);
+            gas_estimate = Some(estimate);
+            max_fee = Some(gas_limit * fees.max_fee_per_gas());
+        }
+
+        // Safe 交易的 Gas 由执行者支付,不检查 Safe 的 ETH 是否足够支付 Gas
+        let balance_check = check_balances(
+            amount,
+            balance,
+            eth_balance,
+            native,
+            max_fee.filter(|_| safe.is_none()),
+            &token_info,
+        );
+        if safe.is_some() {
+            notes.push("Safe 模式下 transaction 字段仅用于模拟,请使用 safe 字段中的 Safe 交易".to_string());
+        }
+
+        let mut result = TransferTokenResult {
+            native,
+            from: checksum_address(owner),
+            to: checksum_address(recipient),
+            amount: amount.to_string(),
+            formatted_amount: format_units(amount, token_info.decimals),
+            balance: balance.to_string(),
+            formatted_balance: format_units(balance, token_info.decimals),
+            balance_sufficient: balance_check.is_ok(),
+            balance_error: balance_check.as_ref().err().cloned(),
+            transaction: unsigned_transaction(&tx, tx_type),
+            simulation_success: simulation.is_ok(),
+            simulation_error: simulation.as_ref().err().cloned(),
+            gas_estimate: gas_estimate.map(|gas| gas.to_string()),
+            max_fee_eth: max_fee.map(|fee| format_units(fee, 18)),
+            sent: false,
+            tx_hash: None,
+            nonce: None,
+            status: None,
+            confirmations: None,
+            block_number: None,
+            notes,
+            compliance: None,
+            safe: None,
+            token: token_info,
+        };
+
+        if !confirm && safe.is_none() {
+            return Ok(result);
+        }
+
+        // 模拟失败或余额不足时不广播,也不提议
+        let refusal = match (simulation, balance_check) {
+            (Err(reason), _) => Some(("simulation_failed", format!("转账交易模拟失败,未广播: {}", reason))),
+            (Ok(()), Err(reason)) => Some(("insufficient_balance", format!("余额不足,未广播: {}", reason))),
+            (Ok(()), Ok(())) => None,
+        };
+        if let (true, Some((reason, message))) = (confirm, refusal) {
+            return Err(McpError::invalid_request(
+                message,
+                Some(serde_json::json!({
+                    "refused": true,
+                    "reason": reason,
+                    "simulation_error": result.simulation_error,
+                    "balance_error": result.balance_error,
+                })),
+            ));
+        }
+
+        // 🔐 Safe 模式:不广播,返回 Safe 交易数据,确认时签名并提议
+        if safe.is_some() {
+            let value = if native { amount } else { U256::zero() };
+            let proposal = safe_client
+                .prepare(
+                    token_addr.unwrap_or(recipient),
+                    value,
+                    tx.data().cloned().unwrap_or_default(),
+                    signer.filter(|_| confirm),
+                )
+                .await
+                .map_err(|e| McpError::internal_error(format!("构建 Safe 交易失败: {}", e), None))?;
+            result.safe = Some(proposal);
+            return Ok(result);
+        }
+        let Some(wallet) = wallet else {
+            return Ok(result);
+        };
+
+        let submission = tx_manager
+            .submit("transfer_token", wallet, tx, RECEIPT_TIMEOUT)
+            .await
+            .map_err(|e| McpError::internal_error(format!("广播交易失败: {}", e), None))?;
+        let receipt = submission.receipt.as_ref();
+
+        result.sent = true;
+        result.tx_hash = Some(format!("{:?}", submission.tx_hash));
+        result.nonce = Some(submission.nonce);
+        result.transaction.nonce = Some(submission.nonce.to_string());
+        result.status = Some(receipt_status(receipt).to_string());
+        result.confirmations = Some(submission.confirmations);
+        result.block_number = receipt.and_then(|r| r.block_number).map(|n| n.as_u64());
+
+        // 📒 转出写入成本台账
+        if let Some(receipt) = receipt {
+            record_ledger_fill(
+                store,
+                &eth_client,
+                uniswap_client,
+                owner,
+                receipt,
+                (&result.token, amount),
+                None,
+            )
+            .await;
+        }
+        Ok::<_, McpError>(result)
+    }
+    .await?;
+    result.compliance = screening;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        simulation_success = result.simulation_success,
+        balance_sufficient = result.balance_sufficient,
+        sent = result.sent,
+        "成功返回转账交易"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 解析转账数量,必须大于 0
+fn parse_transfer_amount(amount: &str, decimals: u8) -> Result<U256, McpError> {
+    let amount = parse_units(amount, decimals)
+        .map_err(|e| McpError::invalid_params(format!("解析金额失败: {}", e), None))?;
+    if amount.is_zero() {
+        return Err(McpError::invalid_params("转账数量必须大于 0", None));
+    }
+    Ok(amount)
+}
+
+/// 构建转账交易:原生代币直接转账,ERC20 调用 transfer(to, amount)
+fn build_transfer_tx(
+    tx_type: TxType,
+    from: Address,
+    to: Address,
+    token: Option<Address>,
+    amount: U256,
+) -> TypedTransaction {
+    let mut tx = tx_type.new_request();
+    tx.set_from(from);
+    match token {
+        Some(token) => {
+            tx.set_to(token).set_data(Bytes::from(transfer_calldata(to, amount)));
+        }
+        None => {
+            tx.set_to(to).set_value(amount);
+        }
+    }
+    tx
+}
+
+/// transfer 调用是否成功:部分代币(如 USDT)不返回值,视为成功
+fn transfer_succeeded(output: &Bytes) -> bool {
+    output.is_empty() || output.iter().any(|byte| *byte != 0)
+}
+
+/// 检查代币余额是否足够转账、ETH 余额是否足够支付 Gas(原生代币转账合并计算)
+fn check_balances(
+    amount: U256,
+    balance: U256,
+    eth_balance: U256,
+    native: bool,
+    max_fee: Option<U256>,
+    token: &TokenInfo,
+) -> Result<(), String> {
+    if balance < amount {
+        return Err(format!(
+            "{} 余额 {} 小于转账数量 {}",
+            token.symbol,
+            format_units(balance, token.decimals),
+            format_units(amount, token.decimals)
+        ));
+    }
+
+    let Some(max_fee) = max_fee else {
+        return Ok(());
+    };
+    let required = if native { amount.saturating_add(max_fee) } else { max_fee };
+    if eth_balance < required {
+        return Err(format!(
+            "ETH 余额 {} 不足以支付转账和 Gas 费用 {}",
+            format_units(eth_balance, 18),
+            format_units(required, 18)
+        ));
+    }
+    Ok(())
+}
+
+/// 导出未签名交易字段
+fn unsigned_transaction(tx: &TypedTransaction, tx_type: TxType) -> UnsignedTransaction {
+    let mut unsigned = UnsignedTransaction {
+        tx_type: tx_type.as_str().to_string(),
+        chain_id: tx.chain_id().map(|id| id.as_u64()).unwrap_or_default(),
+        from: tx.from().map(|from| checksum_address(*from)).unwrap_or_default(),
+        to: tx
+            .to_addr()
+            .map(|to| checksum_address(*to))
+            .unwrap_or_default(),
+        value: tx.value().copied().unwrap_or_default().to_string(),
+        data: tx.data().cloned().unwrap_or_default().to_string(),
+        nonce: tx.nonce().map(|nonce| nonce.to_string()),
+        gas_limit: tx.gas().map(|gas| gas.to_string()),
+        ..Default::default()
+    };
+    match tx {
+        TypedTransaction::Eip1559(request) => {
+            unsigned.max_fee_per_gas = request.max_fee_per_gas.map(|fee| fee.to_string());
+            unsigned.max_priority_fee_per_gas = request.max_priority_fee_per_gas.map(|fee| fee.to_string());
+        }
+        _ => unsigned.gas_price = tx.gas_price().map(|price| price.to_string()),
+    }
+    unsigned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usdc() -> TokenInfo {
+        TokenInfo {
+            symbol: "USDC".to_string(),
+            name: "USD Coin".to_string(),
+            address: checksum_address(Address::repeat_byte(0x11)),
+            decimals: 6,
+            listed_on: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_build_transfer_tx() {
+        let from = Address::repeat_byte(0xaa);
+        let to = Address::repeat_byte(0xbb);
+        let token = Address::repeat_byte(0x11);
+
+        let erc20 = build_transfer_tx(TxType::Eip1559, from, to, Some(token), U256::from(1_500_000u64));
+        assert_eq!(erc20.to_addr(), Some(&token));
+        assert_eq!(erc20.value(), None);
+        assert_eq!(erc20.data().unwrap().to_vec(), transfer_calldata(to, U256::from(1_500_000u64)));
+
+        let mut native = build_transfer_tx(TxType::Legacy, from, to, None, U256::exp10(18));
+        native.set_chain_id(1).set_nonce(7).set_gas(21_000).set_gas_price(U256::from(30_000_000_000u64));
+        let unsigned = unsigned_transaction(&native, TxType::Legacy);
+        assert_eq!(unsigned.to, checksum_address(to));
+        assert_eq!(unsigned.value, "1000000000000000000");
+        assert_eq!(unsigned.data, "0x");
+        assert_eq!(unsigned.nonce.as_deref(), Some("7"));
+        assert_eq!(unsigned.gas_price.as_deref(), Some("30000000000"));
+        assert_eq!(unsigned.max_fee_per_gas, None);
+    }
+
+    #[test]
+    fn test_check_balances() {
+        let token = usdc();
+        let fee = Some(U256::exp10(15));
+
+        assert!(check_balances(U256::from(100u64), U256::from(100u64), U256::exp10(18), false, fee, &token).is_ok());
+        assert!(check_balances(U256::from(101u64), U256::from(100u64), U256::exp10(18), false, fee, &token).is_err());
+        // ERC20 转账时 ETH 只需覆盖 Gas
+        assert!(check_balances(U256::from(100u64), U256::from(100u64), U256::zero(), false, fee, &token).is_err());
+
+        // 原生代币转账需要覆盖数量和 Gas
+        let eth = TokenInfo::eth();
+        let balance = U256::exp10(18);
+        assert!(check_balances(balance, balance, balance, true, fee, &eth).is_err());
+        assert!(check_balances(balance - U256::exp10(15), balance, balance, true, fee, &eth).is_ok());
+        // 未估算 Gas 时只检查数量
+        assert!(check_balances(balance, balance, balance, true, None, &eth).is_ok());
+    }
+
+    #[test]
+    fn test_parse_transfer_amount() {
+        assert_eq!(parse_transfer_amount("1.5", 6).unwrap(), U256::from(1_500_000u64));
+        assert!(parse_transfer_amount("0", 6).is_err());
+        assert!(parse_transfer_amount("abc", 6).is_err());
+    }
+}
@@ -0,0 +1,522 @@
+use crate::{
+    config::Config,
+    erc20::{format_units, Erc20Client},
+    eth_client::EthClient,
+    logging::info,
+    pnl::{parse_period, SECONDS_PER_BLOCK},
+    token_registry::TokenRegistry,
+    tools::new_pairs::lookup_token,
+    types::TokenInfo,
+    uniswap::{SwapLog, UniswapV2Client},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// 默认返回的代币数量
+const DEFAULT_TRENDING_LIMIT: usize = 10;
+/// 单次查询允许返回的最大代币数量
+const MAX_TRENDING_LIMIT: usize = 50;
+/// 允许的最长统计窗口（需要扫描两个窗口的 Swap 日志）
+const MAX_TRENDING_WINDOW_SECS: u64 = 7 * 86_400;
+/// 参与排名的新交易对上限（按创建时间取最新的）
+const MAX_NEW_PAIR_CANDIDATES: usize = 50;
+/// 低于该 WETH 储备量视为流动性过低
+const MIN_LIQUIDITY_WETH: u64 = 1;
+/// 低于该独立买家数视为买盘过于集中
+const MIN_UNIQUE_BUYERS: usize = 3;
+
+/// GetTrendingTokens 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetTrendingTokensArgs {
+    /// 统计窗口(可选,如 1h、24h、7d,默认 24h,最长 7d)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<String>,
+    /// 返回数量上限(可选,默认 10,最大 50)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// 是否包含未通过安全筛查的代币(可选,默认 false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_flagged: Option<bool>,
+}
+
+/// GetTrendingTokens 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TrendingTokensResult {
+    pub window: String,
+    /// 上一窗口起始区块
+    pub from_block: u64,
+    /// 当前窗口起始区块
+    pub window_start_block: u64,
+    pub to_block: u64,
+    /// 参与统计的 WETH 交易对数量
+    pub pairs_scanned: usize,
+    /// 因安全筛查被排除的代币数量
+    pub excluded_count: usize,
+    pub tokens: Vec<TrendingToken>,
+}
+
+/// 单个代币的活跃度统计(成交量以 WETH 计)
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TrendingToken {
+    pub token: TokenInfo,
+    pub pair: String,
+    /// 交易对是否在当前窗口内创建
+    pub is_new_pair: bool,
+    pub volume_weth: String,
+    pub previous_volume_weth: String,
+    /// 成交量增长(当前窗口 - 上一窗口,用于排名)
+    pub volume_growth_weth: String,
+    /// 上一窗口无成交时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume_change_pct: Option<String>,
+    pub unique_buyers: usize,
+    pub buys: usize,
+    pub sells: usize,
+    pub liquidity_weth: String,
+    pub safety_flags: Vec<String>,
+}
+
+/// 参与排名的 WETH 交易对
+struct Candidate {
+    token: Address,
+    pair: Address,
+    weth_is_token0: bool,
+    is_new_pair: bool,
+}
+
+/// 单个交易对在两个窗口内的成交统计
+#[derive(Debug, Default)]
+struct PairActivity {
+    volume: U256,
+    previous_volume: U256,
+    buyers: HashSet<Address>,
+    buys: usize,
+    sells: usize,
+}
+
+/// 查询近期最活跃的代币
+pub async fn get_trending_tokens(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<GetTrendingTokensArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_trending_tokens 请求");
+
+    let window = args.window.clone().unwrap_or_else(|| "24h".to_string());
+    let window_secs = parse_period(&window).map_err(|e| McpError::invalid_params(e, None))?;
+    if window_secs > MAX_TRENDING_WINDOW_SECS {
+        return Err(McpError::invalid_params(
+            format!("统计窗口过长: {} (最长 7d)", window),
+            None,
+        ));
+    }
+
+    let limit = args.limit.unwrap_or(DEFAULT_TRENDING_LIMIT);
+    if limit == 0 || limit > MAX_TRENDING_LIMIT {
+        return Err(McpError::invalid_params(
+            format!("limit 参数无效: {} (必须在 1-{} 之间)", limit, MAX_TRENDING_LIMIT),
+            None,
+        ));
+    }
+    let include_flagged = args.include_flagged.unwrap_or(false);
+
+    info!(window = %window, limit, include_flagged, "查询热门代币");
+
+    let window_blocks = window_secs / SECONDS_PER_BLOCK;
+
+    // 测试模式
+    if config.server.test_mode {
+        let to_block = 20_000_000;
+        let result = TrendingTokensResult {
+            window,
+            from_block: to_block - 2 * window_blocks,
+            window_start_block: to_block - window_blocks,
+            to_block,
+            pairs_scanned: 2,
+            excluded_count: 1,
+            tokens: vec![TrendingToken {
+                token: TokenInfo {
+                    symbol: "TREND".to_string(),
+                    name: "Trending Token".to_string(),
+                    address: "0x0000000000000000000000000000000000000001".to_string(),
+                    decimals: 18,
+                    listed_on: Vec::new(),
+                },
+                pair: "0x0000000000000000000000000000000000000003".to_string(),
+                is_new_pair: false,
+                volume_weth: "150".to_string(),
+                previous_volume_weth: "50".to_string(),
+                volume_growth_weth: "100".to_string(),
+                volume_change_pct: Some("200.00%".to_string()),
+                unique_buyers: 42,
+                buys: 60,
+                sells: 35,
+                liquidity_weth: "300".to_string(),
+                safety_flags: Vec::new(),
+            }],
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !uniswap_client.is_available() {
+        return Err(McpError::internal_error(
+            "Uniswap 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let weth = uniswap_client.weth_address();
+    let eth_client = eth_client.clone();
+    let uniswap_client = uniswap_client.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+
+    let result = async {
+        let to_block = eth_client.get_block_number().await.map_err(|e| {
+            McpError::internal_error(format!("查询最新区块失败: {}", e), None)
+        })?;
+        let window_start_block = to_block.saturating_sub(window_blocks);
+        let from_block = window_start_block.saturating_sub(window_blocks);
+
+        let candidates = collect_candidates(
+            &uniswap_client,
+            &token_registry,
+            weth,
+            window_start_block,
+            to_block,
+        )
+        .await?;
+
+        let pair_addresses: Vec<Address> = candidates.iter().map(|c| c.pair).collect();
+        let swaps = uniswap_client
+            .swap_logs(&pair_addresses, from_block, to_block)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询 Swap 事件失败: {}", e), None))?;
+
+        let mut swaps_by_pair: HashMap<Address, Vec<SwapLog>> = HashMap::new();
+        for swap in swaps {
+            swaps_by_pair.entry(swap.pair).or_default().push(swap);
+        }
+
+        // 仅统计当前窗口内有成交的交易对
+        let mut tasks = tokio::task::JoinSet::new();
+        for candidate in &candidates {
+            let swaps = swaps_by_pair.remove(&candidate.pair).unwrap_or_default();
+            let activity = aggregate_activity(&swaps, candidate.weth_is_token0, window_start_block);
+            if activity.volume.is_zero() {
+                continue;
+            }
+
+            let uniswap_client = uniswap_client.clone();
+            let erc20_client = erc20_client.clone();
+            let token_registry = token_registry.clone();
+            let (token, pair, weth_is_token0, is_new_pair) = (
+                candidate.token,
+                candidate.pair,
+                candidate.weth_is_token0,
+                candidate.is_new_pair,
+            );
+            tasks.spawn(async move {
+                let (token_info, reserves) = tokio::join!(
+                    lookup_token(&erc20_client, &token_registry, token),
+                    uniswap_client.get_reserves(pair)
+                );
+                let liquidity = reserves
+                    .map(|(r0, r1)| if weth_is_token0 { r0 } else { r1 })
+                    .unwrap_or_default();
+                build_trending_token(token_info, pair, is_new_pair, &activity, liquidity)
+            });
+        }
+
+        let mut tokens = Vec::with_capacity(tasks.len());
+        while let Some(joined) = tasks.join_next().await {
+            tokens.push(
+                joined.map_err(|e| McpError::internal_error(format!("统计任务失败: {}", e), None))?,
+            );
+        }
+
+        let before = tokens.len();
+        if !include_flagged {
+            tokens.retain(|t| t.safety_flags.is_empty());
+        }
+        let excluded_count = before - tokens.len();
+
+        rank_tokens(&mut tokens);
+        tokens.truncate(limit);
+
+        Ok::<_, McpError>(TrendingTokensResult {
+            window,
+            from_block,
+            window_start_block,
+            to_block,
+            pairs_scanned: candidates.len(),
+            excluded_count,
+            tokens,
+        })
+    }
+    .await?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(count = result.tokens.len(), "成功返回热门代币");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 收集候选交易对:注册表代币的 WETH 交易对 + 当前窗口内新建的 WETH 交易对
+async fn collect_candidates(
+    uniswap_client: &UniswapV2Client,
+    token_registry: &TokenRegistry,
+    weth: Address,
+    window_start_block: u64,
+    to_block: u64,
+) -> Result<Vec<Candidate>, McpError> {
+    let mut candidates = Vec::new();
+    let mut seen = HashSet::new();
+
+    let created = uniswap_client
+        .pair_created_logs(window_start_block, to_block)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询 PairCreated 事件失败: {}", e), None))?;
+
+    for log in created.iter().rev() {
+        if candidates.len() >= MAX_NEW_PAIR_CANDIDATES {
+            break;
+        }
+        let token = if log.token0 == weth {
+            log.token1
+        } else if log.token1 == weth {
+            log.token0
+        } else {
+            continue;
+        };
+        if seen.insert(log.pair) {
+            candidates.push(Candidate {
+                token,
+                pair: log.pair,
+                weth_is_token0: log.token0 == weth,
+                is_new_pair: true,
+            });
+        }
+    }
+
+    let registry_tokens: Vec<Address> = token_registry
+        .all_tokens()
+        .iter()
+        .filter_map(|t| t.address.parse::<Address>().ok())
+        .filter(|addr| *addr != weth)
+        .collect();
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for token in registry_tokens {
+        let client = uniswap_client.clone();
+        tasks.spawn(async move { (token, client.get_pair(token, weth).await) });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        let (token, pair) =
+            joined.map_err(|e| McpError::internal_error(format!("查询交易对任务失败: {}", e), None))?;
+        // 没有 WETH 交易对的注册表代币直接跳过
+        let Ok(pair) = pair else { continue };
+        if seen.insert(pair) {
+            candidates.push(Candidate {
+                token,
+                pair,
+                weth_is_token0: weth < token,
+                is_new_pair: false,
+            });
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// 按窗口统计交易对成交:window_start_block 之后计入当前窗口,之前计入上一窗口
+/// 买入 = WETH 换入代币,买家为 Swap 的接收方
+fn aggregate_activity(swaps: &[SwapLog], weth_is_token0: bool, window_start_block: u64) -> PairActivity {
+    let mut activity = PairActivity::default();
+
+    for swap in swaps {
+        let (weth_in, weth_out) = if weth_is_token0 {
+            (swap.amount0_in, swap.amount0_out)
+        } else {
+            (swap.amount1_in, swap.amount1_out)
+        };
+        let volume = weth_in.saturating_add(weth_out);
+
+        if swap.block_number <= window_start_block {
+            activity.previous_volume = activity.previous_volume.saturating_add(volume);
+            continue;
+        }
+
+        activity.volume = activity.volume.saturating_add(volume);
+        if !weth_in.is_zero() {
+            activity.buys += 1;
+            activity.buyers.insert(swap.to);
+        }
+        if !weth_out.is_zero() {
+            activity.sells += 1;
+        }
+    }
+
+    activity
+}
+
+/// 基础安全筛查,返回命中的风险标记
+fn safety_flags(token: &TokenInfo, activity: &PairActivity, liquidity: U256) -> Vec<String> {
+    let mut flags = Vec::new();
+
+    if token.symbol == "UNKNOWN" {
+        flags.push("unknown_metadata".to_string());
+    }
+    if liquidity < U256::from(MIN_LIQUIDITY_WETH) * U256::exp10(18) {
+        flags.push("low_liquidity".to_string());
+    }
+    // 只有买入没有卖出是貔貅盘(honeypot)的典型特征
+    if activity.buys > 0 && activity.sells == 0 {
+        flags.push("no_sells".to_string());
+    }
+    if activity.buyers.len() < MIN_UNIQUE_BUYERS {
+        flags.push("few_buyers".to_string());
+    }
+
+    flags
+}
+
+fn build_trending_token(
+    token: TokenInfo,
+    pair: Address,
+    is_new_pair: bool,
+    activity: &PairActivity,
+    liquidity: U256,
+) -> TrendingToken {
+    let growth_negative = activity.volume < activity.previous_volume;
+    let growth = if growth_negative {
+        format!("-{}", format_units(activity.previous_volume - activity.volume, 18))
+    } else {
+        format_units(activity.volume - activity.previous_volume, 18)
+    };
+
+    let volume_change_pct = (!activity.previous_volume.is_zero()).then(|| {
+        let current = activity.volume.as_u128() as f64;
+        let previous = activity.previous_volume.as_u128() as f64;
+        format!("{:.2}%", (current - previous) / previous * 100.0)
+    });
+
+    TrendingToken {
+        safety_flags: safety_flags(&token, activity, liquidity),
+        token,
+        pair: format!("{:?}", pair),
+        is_new_pair,
+        volume_weth: format_units(activity.volume, 18),
+        previous_volume_weth: format_units(activity.previous_volume, 18),
+        volume_growth_weth: growth,
+        volume_change_pct,
+        unique_buyers: activity.buyers.len(),
+        buys: activity.buys,
+        sells: activity.sells,
+        liquidity_weth: format_units(liquidity, 18),
+    }
+}
+
+/// 按成交量增长降序排名,增长相同时买家多的在前
+fn rank_tokens(tokens: &mut [TrendingToken]) {
+    let growth = |t: &TrendingToken| t.volume_growth_weth.parse::<f64>().unwrap_or(0.0);
+    tokens.sort_by(|a, b| {
+        growth(b)
+            .total_cmp(&growth(a))
+            .then_with(|| b.unique_buyers.cmp(&a.unique_buyers))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn swap(block: u64, weth_in: u64, weth_out: u64, to: u8) -> SwapLog {
+        // WETH 为 token0
+        SwapLog {
+            pair: Address::repeat_byte(0x03),
+            sender: Address::zero(),
+            to: Address::repeat_byte(to),
+            amount0_in: U256::from(weth_in),
+            amount1_in: if weth_out > 0 { U256::from(1u64) } else { U256::zero() },
+            amount0_out: U256::from(weth_out),
+            amount1_out: if weth_in > 0 { U256::from(1u64) } else { U256::zero() },
+            block_number: block,
+            log_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_activity() {
+        let swaps = vec![
+            swap(90, 100, 0, 1),
+            swap(110, 200, 0, 1),
+            swap(120, 300, 0, 2),
+            swap(130, 0, 50, 3),
+        ];
+
+        let activity = aggregate_activity(&swaps, true, 100);
+        assert_eq!(activity.previous_volume, U256::from(100u64));
+        assert_eq!(activity.volume, U256::from(550u64));
+        assert_eq!(activity.buys, 2);
+        assert_eq!(activity.sells, 1);
+        assert_eq!(activity.buyers.len(), 2);
+    }
+
+    #[test]
+    fn test_safety_flags() {
+        let token = TokenInfo {
+            symbol: "UNKNOWN".to_string(),
+            name: "Unknown Token".to_string(),
+            address: "0x0000000000000000000000000000000000000001".to_string(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+        let activity = aggregate_activity(&[swap(110, 100, 0, 1)], true, 100);
+
+        let flags = safety_flags(&token, &activity, U256::exp10(17));
+        assert_eq!(flags, vec!["unknown_metadata", "low_liquidity", "no_sells", "few_buyers"]);
+    }
+
+    #[test]
+    fn test_rank_tokens_by_growth() {
+        let token = |growth: &str| {
+            let activity = PairActivity::default();
+            let mut t = build_trending_token(
+                TokenInfo {
+                    symbol: "T".to_string(),
+                    name: "T".to_string(),
+                    address: String::new(),
+                    decimals: 18,
+                    listed_on: Vec::new(),
+                },
+                Address::zero(),
+                false,
+                &activity,
+                U256::zero(),
+            );
+            t.volume_growth_weth = growth.to_string();
+            t
+        };
+
+        let mut tokens = vec![token("-5"), token("10"), token("2.5")];
+        rank_tokens(&mut tokens);
+        let order: Vec<_> = tokens.iter().map(|t| t.volume_growth_weth.as_str()).collect();
+        assert_eq!(order, vec!["10", "2.5", "-5"]);
+    }
+}
@@ -0,0 +1,309 @@
+use crate::{
+    config::Config,
+    erc20::Erc20Client,
+    eth_client::EthClient,
+    logging::info,
+    token_registry::TokenRegistry,
+    tools::{
+        price::calculate_price_ratio,
+        user_operation::{resolve_token, token_address},
+    },
+    types::{checksum_address, TokenInfo},
+    uniswap::{time_weighted_average, CumulativePrices, UniswapV2Client},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// 默认 TWAP 窗口(区块数,主网约 1 小时)
+const DEFAULT_TWAP_WINDOW_BLOCKS: u64 = 300;
+
+/// GetTwapPrice 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetTwapPriceArgs {
+    /// 代币地址或符号(必需)
+    pub token: String,
+    /// 计价代币地址或符号(可选,默认当前链的包装原生代币)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote_token: Option<String>,
+    /// TWAP 窗口(区块数,可选,默认 300)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_blocks: Option<u64>,
+    /// 窗口结束区块(可选,默认最新区块)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+}
+
+/// GetTwapPrice 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct TwapPriceResult {
+    pub token: TokenInfo,
+    pub quote_token: TokenInfo,
+    pub pair: String,
+    pub from_block: u64,
+    pub to_block: u64,
+    /// 窗口时长(秒)
+    pub window_seconds: u64,
+    /// 窗口内的时间加权平均价格(以 quote_token 计)
+    pub twap_price: String,
+    /// 结束区块的现货价格(以 quote_token 计)
+    pub spot_price: String,
+    /// 现货价格相对 TWAP 的偏离(带符号,偏离过大可能是池子被操纵)
+    pub spot_deviation_pct: String,
+}
+
+/// 基于 Uniswap V2 累计价格计算时间加权平均价格,并与现货价格对比
+pub async fn get_twap_price(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    Parameters(args): Parameters<GetTwapPriceArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_twap_price 请求");
+
+    let window_blocks = args.window_blocks.unwrap_or(DEFAULT_TWAP_WINDOW_BLOCKS);
+    if window_blocks == 0 {
+        return Err(McpError::invalid_params("window_blocks 必须大于 0", None));
+    }
+    let quote_query = args
+        .quote_token
+        .clone()
+        .unwrap_or_else(|| config.chain().wrapped_native_symbol.to_string());
+
+    info!(
+        token = %args.token,
+        quote = %quote_query,
+        window_blocks,
+        block_number = ?args.block_number,
+        "查询 TWAP 价格"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let to_block = args.block_number.unwrap_or(20_000_000);
+        let test_token = |symbol: &str, address: &str| TokenInfo {
+            symbol: symbol.to_string(),
+            name: format!("{} Token", symbol),
+            address: address.to_string(),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+
+        let result = TwapPriceResult {
+            token: test_token("TEST", &args.token),
+            quote_token: test_token("WETH", &quote_query),
+            pair: "0x0000000000000000000000000000000000000003".to_string(),
+            from_block: to_block.saturating_sub(window_blocks),
+            to_block,
+            window_seconds: window_blocks * 12,
+            twap_price: "0.0005".to_string(),
+            spot_price: "0.000505".to_string(),
+            spot_deviation_pct: spot_deviation_pct("0.000505", "0.0005"),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !uniswap_client.is_available() || !eth_client.is_available() {
+        return Err(McpError::internal_error(
+            "Uniswap 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let token = resolve_token(token_registry, erc20_client, &args.token).await?;
+    let quote_token = resolve_token(token_registry, erc20_client, &quote_query).await?;
+    let token_addr = token_address(&token)?;
+    let quote_addr = token_address(&quote_token)?;
+    if token_addr == quote_addr {
+        return Err(McpError::invalid_params("代币和计价代币不能相同", None));
+    }
+
+    let eth_client = eth_client.clone();
+    let uniswap_client = uniswap_client.clone();
+
+    let result = async {
+        let to_block = match args.block_number {
+            Some(block) => block,
+            None => eth_client.get_block_number().await.map_err(|e| {
+                McpError::internal_error(format!("查询最新区块失败: {}", e), None)
+            })?,
+        };
+        let from_block = to_block.checked_sub(window_blocks).ok_or_else(|| {
+            McpError::invalid_params(
+                format!("窗口 {} 个区块超过了结束区块 {}", window_blocks, to_block),
+                None,
+            )
+        })?;
+
+        let pair = uniswap_client
+            .get_pair(token_addr, quote_addr)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询交易对失败: {}", e), None))?;
+
+        let block_error = |block: u64, e: &dyn std::fmt::Display| {
+            McpError::internal_error(
+                format!("查询区块 {} 的累计价格失败(可能需要归档节点或交易对尚未创建): {}", block, e),
+                None,
+            )
+        };
+        let (start, end, start_time, end_time) = tokio::join!(
+            uniswap_client.get_cumulative_prices_at(pair, Some(BlockId::from(from_block))),
+            uniswap_client.get_cumulative_prices_at(pair, Some(BlockId::from(to_block))),
+            eth_client.get_block_timestamp(BlockNumber::Number(from_block.into())),
+            eth_client.get_block_timestamp(BlockNumber::Number(to_block.into()))
+        );
+        let start = start.map_err(|e| block_error(from_block, &e))?;
+        let end = end.map_err(|e| block_error(to_block, &e))?;
+        let (_, start_time) = start_time.map_err(|e| block_error(from_block, &e))?;
+        let (_, end_time) = end_time.map_err(|e| block_error(to_block, &e))?;
+
+        let token_is_token0 = token_addr < quote_addr;
+        let twap = twap_price(&start, &end, start_time, end_time, token_is_token0).ok_or_else(|| {
+            McpError::invalid_params("窗口内区块时间没有变化,请增大 window_blocks", None)
+        })?;
+        let twap_price = uq112x112_to_price(twap, token.decimals, quote_token.decimals);
+
+        let (token_reserve, quote_reserve) = if token_is_token0 {
+            (end.reserve0, end.reserve1)
+        } else {
+            (end.reserve1, end.reserve0)
+        };
+        if token_reserve.is_zero() || quote_reserve.is_zero() {
+            return Err(McpError::internal_error("交易对流动性不足", None));
+        }
+        let spot_price = calculate_price_ratio(quote_reserve, token_reserve, token.decimals, quote_token.decimals);
+
+        Ok::<_, McpError>(TwapPriceResult {
+            pair: checksum_address(pair),
+            from_block,
+            to_block,
+            window_seconds: end_time.saturating_sub(start_time),
+            spot_deviation_pct: spot_deviation_pct(&spot_price, &twap_price),
+            twap_price,
+            spot_price,
+            token,
+            quote_token,
+        })
+    }
+    .await?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(twap = %result.twap_price, spot = %result.spot_price, "成功返回 TWAP 价格");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 计算代币以计价代币计的 TWAP(UQ112x112),累计值先按当前储备量推算到各自区块时间
+fn twap_price(
+    start: &CumulativePrices,
+    end: &CumulativePrices,
+    start_time: u64,
+    end_time: u64,
+    token_is_token0: bool,
+) -> Option<U256> {
+    let (start0, start1) = start.at_timestamp(start_time);
+    let (end0, end1) = end.at_timestamp(end_time);
+    let elapsed = end_time.checked_sub(start_time)?;
+    if token_is_token0 {
+        time_weighted_average(start0, end0, elapsed)
+    } else {
+        time_weighted_average(start1, end1, elapsed)
+    }
+}
+
+/// UQ112x112 原始单位价格转换为按 decimals 调整后的价格
+/// 数值过大时同时右移分子分母,避免 calculate_price_ratio 中乘以 10^18 溢出
+fn uq112x112_to_price(value: U256, base_decimals: u8, quote_decimals: u8) -> String {
+    let shift = value.bits().saturating_sub(190).min(112);
+    calculate_price_ratio(value >> shift, U256::one() << (112 - shift), base_decimals, quote_decimals)
+}
+
+/// 现货价格相对 TWAP 的偏离百分比
+fn spot_deviation_pct(spot: &str, twap: &str) -> String {
+    let (Ok(spot), Ok(twap)) = (Decimal::from_str(spot), Decimal::from_str(twap)) else {
+        return "0.0000%".to_string();
+    };
+    if twap.is_zero() {
+        return "0.0000%".to_string();
+    }
+    format!("{:.4}%", (spot - twap) / twap * Decimal::from(100))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cumulative(price0: U256, price1: U256, reserves: (u64, u64), timestamp: u32) -> CumulativePrices {
+        CumulativePrices {
+            price0_cumulative_last: price0,
+            price1_cumulative_last: price1,
+            reserve0: U256::from(reserves.0),
+            reserve1: U256::from(reserves.1),
+            block_timestamp_last: timestamp,
+        }
+    }
+
+    #[test]
+    fn test_twap_extrapolates_from_last_update() {
+        let q112 = U256::one() << 112;
+        // 起点:累计值为 0,价格 token1/token0 = 2
+        let start = cumulative(U256::zero(), U256::zero(), (1_000, 2_000), 1_000);
+        // 终点:累计值更新到 t=1500(前 500 秒价格为 2),之后价格变为 4 持续 500 秒
+        let end = cumulative(q112 * U256::from(1_000u64), q112 * U256::from(250u64), (1_000, 4_000), 1_500);
+
+        let twap0 = twap_price(&start, &end, 1_000, 2_000, true).unwrap();
+        // (2 * 500 + 4 * 500) / 1000 = 3
+        assert_eq!(uq112x112_to_price(twap0, 18, 18), "3");
+
+        let twap1 = twap_price(&start, &end, 1_000, 2_000, false).unwrap();
+        // (0.5 * 500 + 0.25 * 500) / 1000 = 0.375
+        assert_eq!(uq112x112_to_price(twap1, 18, 18), "0.375");
+
+        assert!(twap_price(&start, &start, 1_000, 1_000, true).is_none());
+    }
+
+    #[test]
+    fn test_twap_handles_cumulative_overflow() {
+        let q112 = U256::one() << 112;
+        // 累计值在窗口内按 2^256 回绕
+        let start_value = U256::MAX - q112 * U256::from(100u64) + U256::one();
+        let end_value = q112 * U256::from(200u64);
+        let start = cumulative(start_value, U256::zero(), (1, 1), 0);
+        let end = cumulative(end_value, U256::zero(), (1, 1), 100);
+
+        let twap = twap_price(&start, &end, 0, 100, true).unwrap();
+        assert_eq!(uq112x112_to_price(twap, 18, 18), "3");
+    }
+
+    #[test]
+    fn test_uq112x112_to_price_decimals() {
+        let q112 = U256::one() << 112;
+        // 1 USDC(6 位) = 0.0005 WETH(18 位):原始单位价格 = 0.0005 * 10^12 = 5 * 10^8
+        let raw = q112 * U256::from(500_000_000u64);
+        assert_eq!(uq112x112_to_price(raw, 6, 18), "0.0005");
+
+        // 接近 uint224 上限的价格不溢出
+        let huge = (U256::one() << 224) - U256::one();
+        assert!(!uq112x112_to_price(huge, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_spot_deviation_pct() {
+        assert_eq!(spot_deviation_pct("1.05", "1"), "5.0000%");
+        assert_eq!(spot_deviation_pct("0.9", "1"), "-10.0000%");
+        assert_eq!(spot_deviation_pct("1", "0"), "0.0000%");
+    }
+}
@@ -0,0 +1,511 @@
+use crate::{
+    account_abstraction::{account_call_data, AccountCall, BundlerClient, UserOperation},
+    compliance::{ComplianceScreen, ScreeningDecision},
+    config::Config,
+    erc20::{approve_calldata, format_units, parse_units, transfer_calldata, Erc20Client},
+    eth_client::EthClient,
+    logging::{info, warn},
+    signer::TxSigner,
+    store::Store,
+    token_registry::TokenRegistry,
+    tools::approve::parse_approve_amount,
+    tools::swap::enforce_price_impact_limit,
+    types::{checksum_address, parse_address, TokenInfo},
+    uniswap::{NativeLeg, SwapCall, UniswapV2Client},
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// 交换交易的有效期(秒)
+const SWAP_DEADLINE_SECS: u64 = 1200;
+
+/// SendUserOperation 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SendUserOperationArgs {
+    /// 操作类型(必需,transfer、approve 或 swap)
+    pub action: String,
+    /// 数量(必需;transfer 为转账数量,approve 为授权数量("max" 表示无限授权),swap 为输入代币数量)
+    pub amount: String,
+    /// 代币地址或符号(transfer 和 approve 必需,transfer 时 ETH 表示原生 ETH)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// 被授权方地址(approve 可选,默认 Uniswap V2 Router)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spender: Option<String>,
+    /// 转账接收地址(transfer 必需)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    /// 源代币地址或符号(swap 必需)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_token: Option<String>,
+    /// 目标代币地址或符号(swap 必需)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_token: Option<String>,
+    /// 滑点(基点,可选,默认使用 DEFAULT_SLIPPAGE_BPS 配置)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slippage_bps: Option<u32>,
+    /// 是否通过 Paymaster 赞助 Gas(可选,默认 false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub use_paymaster: Option<bool>,
+    /// 是否提交到 Bundler(可选,默认 false,仅构建、估算并签名)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submit: Option<bool>,
+    /// 智能账户地址(可选,默认使用 AA_SMART_ACCOUNT 配置)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smart_account: Option<String>,
+    /// EntryPoint 合约地址(可选,默认使用 AA_ENTRY_POINT 配置,需为 v0.6 兼容)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry_point: Option<String>,
+    /// 为 true 时不签名,返回未签名的 UserOperation 供外部签名(可选,默认 false;未配置签名器时总是不签名)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsigned: Option<bool>,
+}
+
+/// SendUserOperation 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct SendUserOperationResult {
+    pub action: String,
+    /// 智能账户地址
+    pub sender: String,
+    pub entry_point: String,
+    /// 智能账户执行的内部调用
+    pub calls: Vec<AccountCallSummary>,
+    /// UserOperation(已签名时可直接提交给任意 Bundler;未签名时 signature 为估算 Gas 用的占位签名)
+    pub user_operation: UserOperation,
+    /// 智能账户所有者需要签名的 userOpHash
+    pub user_op_hash: String,
+    /// 是否已用配置的签名器签名
+    pub signed: bool,
+    /// Gas 是否由 Paymaster 赞助
+    pub sponsored: bool,
+    /// 最大 Gas 费用(ETH,Paymaster 赞助时由 Paymaster 支付)
+    pub max_gas_cost: String,
+    /// 交换的预估输出(仅 swap)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_output: Option<String>,
+    /// 交换的最小输出(仅 swap)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum_output: Option<String>,
+    /// 是否已提交到 Bundler
+    pub submitted: bool,
+    /// 制裁名单命中但只标记时的筛查结论
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compliance: Option<ScreeningDecision>,
+}
+
+/// 内部调用摘要
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct AccountCallSummary {
+    pub to: String,
+    pub value: String,
+    pub description: String,
+}
+
+/// 通过 ERC-4337 智能账户执行转账或交换
+#[allow(clippy::too_many_arguments)]
+pub async fn send_user_operation(
+    config: &Arc<Config>,
+    eth_client: &Arc<EthClient>,
+    uniswap_client: &Arc<UniswapV2Client>,
+    erc20_client: &Arc<Erc20Client>,
+    token_registry: &Arc<TokenRegistry>,
+    bundler_client: &Arc<BundlerClient>,
+    store: &Arc<Store>,
+    compliance: &Arc<ComplianceScreen>,
+    signer: Option<&TxSigner>,
+    Parameters(args): Parameters<SendUserOperationArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 send_user_operation 请求");
+
+    let use_paymaster = args.use_paymaster.unwrap_or(false);
+    let submit = args.submit.unwrap_or(false);
+    let slippage_bps = args.slippage_bps.unwrap_or(config.trading.default_slippage_bps);
+
+    // 🔒 校验滑点范围（0-10000 基点，即 0-100%）
+    if slippage_bps > 10000 {
+        return Err(McpError::invalid_params(
+            format!(
+                "滑点参数无效: {} bps (必须 ≤ 10000，即 ≤ 100%)",
+                slippage_bps
+            ),
+            None,
+        ));
+    }
+
+    if !matches!(args.action.as_str(), "transfer" | "approve" | "swap") {
+        return Err(McpError::invalid_params(
+            format!("未知的操作类型: {} (支持 transfer、approve、swap)", args.action),
+            None,
+        ));
+    }
+
+    let smart_account = args
+        .smart_account
+        .as_deref()
+        .map(parse_address)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(format!("smart_account: {}", e), None))?;
+    let entry_point = args
+        .entry_point
+        .as_deref()
+        .map(parse_address)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(format!("entry_point: {}", e), None))?;
+
+    // 未配置签名器或 unsigned 时只构建和估算,由外部签名
+    let owner = signer.filter(|_| !args.unsigned.unwrap_or(false));
+    if submit && owner.is_none() {
+        return Err(McpError::invalid_params(
+            "提交到 Bundler 需要签名,请配置签名器(智能账户所有者)且不要设置 unsigned",
+            None,
+        ));
+    }
+
+    info!(
+        action = %args.action,
+        amount = %args.amount,
+        use_paymaster,
+        submit,
+        "构建 UserOperation"
+    );
+
+    // 测试模式
+    if config.server.test_mode {
+        let sender = smart_account.unwrap_or(Address::repeat_byte(0x11));
+        let entry_point = entry_point.unwrap_or(bundler_client.entry_point());
+        let mut user_operation = UserOperation::new(sender, U256::zero(), Bytes::new());
+        user_operation.call_gas_limit = U256::from(150_000);
+        user_operation.verification_gas_limit = U256::from(100_000);
+        user_operation.pre_verification_gas = U256::from(50_000);
+
+        let result = SendUserOperationResult {
+            action: args.action.clone(),
+            sender: checksum_address(sender),
+            entry_point: checksum_address(entry_point),
+            calls: Vec::new(),
+            user_op_hash: format!("{:?}", user_operation.hash(entry_point, config.ethereum.chain_id)),
+            user_operation,
+            signed: false,
+            sponsored: use_paymaster,
+            max_gas_cost: "0".to_string(),
+            estimated_output: None,
+            minimum_output: None,
+            submitted: false,
+            compliance: None,
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !bundler_client.is_available() {
+        return Err(McpError::internal_error(
+            "Bundler 不可用,请配置 AA_BUNDLER_URL 和 ETHEREUM_RPC_URL",
+            None,
+        ));
+    }
+    if use_paymaster && !bundler_client.has_paymaster() {
+        return Err(McpError::invalid_params("未配置 AA_PAYMASTER_URL,无法使用 Paymaster", None));
+    }
+
+    let sender: Address = match smart_account {
+        Some(sender) => sender,
+        None => config
+            .account_abstraction
+            .smart_account
+            .as_deref()
+            .ok_or_else(|| McpError::invalid_params("未配置 AA_SMART_ACCOUNT,请传入 smart_account", None))?
+            .parse()
+            .map_err(|_| McpError::invalid_params("AA_SMART_ACCOUNT 不是有效的地址", None))?,
+    };
+
+    let max_price_impact_bps = config
+        .price_impact_limit(None)
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+    let chain_id = config.ethereum.chain_id;
+    let gas_strategy = config.trading.gas_price_strategy.clone();
+    let eth_client = eth_client.clone();
+    let uniswap_client = uniswap_client.clone();
+    let erc20_client = erc20_client.clone();
+    let token_registry = token_registry.clone();
+    let bundler_client = match entry_point {
+        Some(entry_point) => Arc::new(bundler_client.with_entry_point(entry_point)),
+        None => bundler_client.clone(),
+    };
+    let store = store.clone();
+    let compliance = compliance.clone();
+
+    let result = async {
+        let mut calls = Vec::new();
+        let mut outputs = None;
+        let mut screening = None;
+
+        if args.action == "transfer" {
+            let token = args
+                .token
+                .as_deref()
+                .ok_or_else(|| McpError::invalid_params("transfer 需要 token 参数", None))?;
+            let recipient: Address = args
+                .recipient
+                .as_deref()
+                .ok_or_else(|| McpError::invalid_params("transfer 需要 recipient 参数", None))
+                .and_then(|recipient| {
+                    parse_address(recipient)
+                        .map_err(|e| McpError::invalid_params(e, None))
+                })?;
+
+            // 🛡️ 制裁名单筛查(签名前)
+            screening = compliance.screen(&store, "send_user_operation", &[("recipient", recipient)])?;
+
+            let token_info = resolve_token(&token_registry, &erc20_client, token).await?;
+            let amount = parse_units(&args.amount, token_info.decimals)
+                .map_err(|e| McpError::invalid_params(format!("解析金额失败: {}", e), None))?;
+
+            calls.push(if token_info.is_eth() {
+                summarize(
+                    AccountCall { to: recipient, value: amount, data: Bytes::new() },
+                    format!("转账 {} ETH", args.amount),
+                )
+            } else {
+                summarize(
+                    AccountCall {
+                        to: token_address(&token_info)?,
+                        value: U256::zero(),
+                        data: Bytes::from(transfer_calldata(recipient, amount)),
+                    },
+                    format!("转账 {} {} 到 {:?}", args.amount, token_info.symbol, recipient),
+                )
+            });
+        } else if args.action == "approve" {
+            let token = args
+                .token
+                .as_deref()
+                .ok_or_else(|| McpError::invalid_params("approve 需要 token 参数", None))?;
+            let spender = match args.spender.as_deref() {
+                Some(spender) => parse_address(spender).map_err(|e| McpError::invalid_params(e, None))?,
+                None => uniswap_client.router_address(),
+            };
+
+            // 🛡️ 制裁名单筛查(签名前)
+            screening = compliance.screen(&store, "send_user_operation", &[("spender", spender)])?;
+
+            let token_info = resolve_token(&token_registry, &erc20_client, token).await?;
+            let amount = parse_approve_amount(&args.amount, token_info.decimals)?;
+            calls.push(summarize(
+                AccountCall {
+                    to: token_address(&token_info)?,
+                    value: U256::zero(),
+                    data: Bytes::from(approve_calldata(spender, amount)),
+                },
+                format!("授权 {} 使用 {} {}", checksum_address(spender), args.amount, token_info.symbol),
+            ));
+        } else {
+            let from = args
+                .from_token
+                .as_deref()
+                .ok_or_else(|| McpError::invalid_params("swap 需要 from_token 参数", None))?;
+            let to = args
+                .to_token
+                .as_deref()
+                .ok_or_else(|| McpError::invalid_params("swap 需要 to_token 参数", None))?;
+
+            let from_info = resolve_token(&token_registry, &erc20_client, from).await?;
+            let to_info = resolve_token(&token_registry, &erc20_client, to).await?;
+            let from_addr = token_address(&from_info)?;
+            let to_addr = token_address(&to_info)?;
+
+            let amount_in = parse_units(&args.amount, from_info.decimals)
+                .map_err(|e| McpError::invalid_params(format!("解析金额失败: {}", e), None))?;
+
+            let quote = uniswap_client
+                .quote_swap(from_addr, to_addr, amount_in)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询交换报价失败: {}", e), None))?;
+
+            // 🔒 价格影响超过上限时拒绝构建
+            enforce_price_impact_limit(quote.price_impact, max_price_impact_bps)?;
+
+            let minimum_output =
+                quote.amount_out * U256::from(10000 - slippage_bps) / U256::from(10000);
+
+            // 智能账户授权不足时，在同一个 UserOperation 中先 approve
+            let router = uniswap_client.router_address();
+            let allowance = erc20_client
+                .allowance(from_addr, sender, router)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询授权额度失败: {}", e), None))?;
+            if allowance < amount_in {
+                calls.push(summarize(
+                    AccountCall {
+                        to: from_addr,
+                        value: U256::zero(),
+                        data: Bytes::from(approve_calldata(router, amount_in)),
+                    },
+                    format!("授权 Router 使用 {} {}", args.amount, from_info.symbol),
+                ));
+            }
+
+            let (_, now) = eth_client
+                .get_block_timestamp(BlockNumber::Latest)
+                .await
+                .map_err(|e| McpError::internal_error(format!("获取区块时间失败: {}", e), None))?;
+            let swap = SwapCall {
+                amount_in,
+                amount_out_min: minimum_output,
+                path: quote.path.clone(),
+                to: sender,
+                deadline: U256::from(now + SWAP_DEADLINE_SECS),
+                native: NativeLeg::None,
+                fee_on_transfer: false,
+            };
+            calls.push(summarize(
+                AccountCall {
+                    to: router,
+                    value: U256::zero(),
+                    data: Bytes::from(swap.encode()),
+                },
+                format!("交换 {} {} → {}", args.amount, from_info.symbol, to_info.symbol),
+            ));
+
+            outputs = Some((
+                format_units(quote.amount_out, to_info.decimals),
+                format_units(minimum_output, to_info.decimals),
+            ));
+        }
+
+        let (account_calls, summaries): (Vec<_>, Vec<_>) = calls.into_iter().unzip();
+        let call_data = account_call_data(&account_calls)
+            .ok_or_else(|| McpError::internal_error("无法构建智能账户 callData", None))?;
+
+        let nonce = bundler_client
+            .get_nonce(sender)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询智能账户 nonce 失败: {}", e), None))?;
+        let fees = eth_client
+            .estimate_fees(&gas_strategy)
+            .await
+            .map_err(|e| McpError::internal_error(format!("估算 Gas 费用失败: {}", e), None))?;
+
+        let mut op = UserOperation::new(sender, nonce, call_data);
+        op.max_fee_per_gas = fees.max_fee_per_gas;
+        op.max_priority_fee_per_gas = fees.max_priority_fee_per_gas;
+
+        // Paymaster 通常同时返回 Gas 估算，未返回时再向 Bundler 估算
+        let mut estimated = false;
+        if use_paymaster {
+            let sponsorship = bundler_client
+                .sponsor(&op)
+                .await
+                .map_err(|e| McpError::internal_error(format!("Paymaster 赞助失败: {}", e), None))?;
+            op.paymaster_and_data = sponsorship.paymaster_and_data;
+            if let Some(gas) = sponsorship.gas {
+                op.apply_gas(&gas);
+                estimated = true;
+            }
+        }
+        if !estimated {
+            let gas = bundler_client
+                .estimate_gas(&op)
+                .await
+                .map_err(|e| McpError::internal_error(format!("估算 UserOperation Gas 失败: {}", e), None))?;
+            op.apply_gas(&gas);
+        }
+
+        let entry_point = bundler_client.entry_point();
+        let hash = match owner {
+            Some(owner) => op
+                .sign(owner, entry_point, chain_id)
+                .await
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?,
+            None => op.hash(entry_point, chain_id),
+        };
+
+        // 提交前签名已完成；提交失败直接返回错误，不重试
+        let user_op_hash = if submit {
+            let accepted = bundler_client
+                .send(&op)
+                .await
+                .map_err(|e| McpError::internal_error(format!("提交 UserOperation 失败: {}", e), None))?;
+            if accepted != hash {
+                warn!(local = ?hash, bundler = ?accepted, "Bundler 返回的 userOpHash 与本地计算不一致");
+            }
+            accepted
+        } else {
+            hash
+        };
+
+        let (estimated_output, minimum_output) = outputs.unzip();
+
+        Ok::<_, McpError>(SendUserOperationResult {
+            action: args.action.clone(),
+            sender: checksum_address(sender),
+            entry_point: checksum_address(entry_point),
+            calls: summaries,
+            max_gas_cost: format_units(op.max_gas_cost(), 18),
+            user_operation: op,
+            user_op_hash: format!("{:?}", user_op_hash),
+            signed: owner.is_some(),
+            sponsored: use_paymaster,
+            estimated_output,
+            minimum_output,
+            submitted: submit,
+            compliance: screening,
+        })
+    }
+    .await?;
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        user_op_hash = %result.user_op_hash,
+        signed = result.signed,
+        submitted = result.submitted,
+        "成功返回 UserOperation"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 解析代币，未知代币通过链上查询补全元数据并缓存到注册表
+pub(crate) async fn resolve_token(
+    token_registry: &TokenRegistry,
+    erc20_client: &Erc20Client,
+    query: &str,
+) -> Result<TokenInfo, McpError> {
+    let info = token_registry
+        .resolve(query)
+        .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+    if info.symbol != "UNKNOWN" {
+        return Ok(info);
+    }
+
+    let real_info = erc20_client
+        .token_info(token_address(&info)?)
+        .await
+        .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
+    token_registry.register(real_info.symbol.clone(), real_info.clone());
+    Ok(real_info)
+}
+
+pub(crate) fn token_address(info: &TokenInfo) -> Result<Address, McpError> {
+    info.address
+        .parse()
+        .map_err(|_| McpError::internal_error(format!("无效的代币地址: {}", info.address), None))
+}
+
+fn summarize(call: AccountCall, description: String) -> (AccountCall, AccountCallSummary) {
+    let summary = AccountCallSummary {
+        to: checksum_address(call.to),
+        value: format_units(call.value, 18),
+        description,
+    };
+    (call, summary)
+}
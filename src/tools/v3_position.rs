@@ -0,0 +1,431 @@
+use crate::{
+    config::Config,
+    defillama::{DefiLlamaClient, UNISWAP_V3_PROTOCOL},
+    erc1155::parse_token_id,
+    erc20::{format_units, Erc20Client},
+    logging::{info, warn},
+    subgraph::{PoolActivity, SubgraphClient},
+    types::{checksum_address, parse_address, TokenInfo},
+    uniswap_v3::{
+        position_amounts, price_from_sqrt, sqrt_ratio_at_tick, Position, UniswapV3Client,
+        UniswapV3Error,
+    },
+};
+use ethers::prelude::*;
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, ErrorData as McpError,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 按钱包查询时默认返回的最大头寸数量
+const DEFAULT_MAX_POSITIONS: usize = 20;
+
+/// 按钱包查询时允许的最大头寸数量
+const MAX_POSITIONS: usize = 100;
+
+/// 价格的输出精度
+const PRICE_DECIMALS: u8 = 18;
+
+/// GetV3Position 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GetV3PositionArgs {
+    /// 头寸 NFT 的 token ID,十进制或 0x 开头的十六进制(与 wallet 二选一)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_id: Option<String>,
+    /// 钱包地址,列出其持有的全部头寸(与 token_id 二选一)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+    /// 按钱包查询时是否包含流动性和待领取手续费均为 0 的已关闭头寸(可选,默认 false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_closed: Option<bool>,
+    /// 按钱包查询时最多读取的头寸数量(可选,默认 20,最大 100)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_positions: Option<usize>,
+}
+
+/// 单个 Uniswap V3 头寸
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct V3PositionInfo {
+    pub token_id: String,
+    pub owner: String,
+    pub pool: String,
+    pub token0: TokenInfo,
+    pub token1: TokenInfo,
+    /// 手续费档位(百万分之一,如 3000 = 0.3%)
+    pub fee_tier: u32,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub current_tick: i32,
+    /// 区间下边界、上边界和当前价格(1 token0 可兑换的 token1 数量)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_lower: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_upper: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_current: Option<String>,
+    /// 当前价格是否在头寸区间内(区间外的头寸不赚取手续费)
+    pub in_range: bool,
+    pub liquidity: String,
+    /// 按当前价格可赎回的 token0 / token1 数量(已格式化)
+    pub amount0: String,
+    pub amount1: String,
+    /// 未领取的手续费(已格式化)
+    pub unclaimed_fees0: String,
+    pub unclaimed_fees1: String,
+    /// 流动性和待领取手续费均为 0
+    pub closed: bool,
+    /// 池子最近 24 小时的交易量、手续费和交易笔数(配置 V3 子图时返回)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_activity: Option<PoolActivity>,
+}
+
+/// GetV3Position 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct V3PositionsResult {
+    pub position_manager: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+    /// 钱包持有的头寸 NFT 总数(按 token_id 查询时为空)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_positions: Option<String>,
+    pub positions: Vec<V3PositionInfo>,
+    /// Uniswap V3 协议在所有链上的 TVL(USD,启用 DEFILLAMA_ENABLED 时返回)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_tvl_usd: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// 读取 Uniswap V3 头寸 NFT:价格区间、当前可赎回数量、未领取手续费和是否在区间内
+pub async fn get_v3_position(
+    config: &Arc<Config>,
+    erc20_client: &Arc<Erc20Client>,
+    uniswap_v3_client: &Arc<UniswapV3Client>,
+    subgraph_client: &Arc<SubgraphClient>,
+    defillama_client: &Arc<DefiLlamaClient>,
+    Parameters(args): Parameters<GetV3PositionArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 get_v3_position 请求");
+
+    let token_id = args
+        .token_id
+        .as_deref()
+        .map(parse_token_id)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(e, None))?;
+    let wallet = args
+        .wallet
+        .as_deref()
+        .map(parse_address)
+        .transpose()
+        .map_err(|e| McpError::invalid_params(e, None))?;
+    if token_id.is_some() == wallet.is_some() {
+        return Err(McpError::invalid_params(
+            "需要提供 token_id 或 wallet 其中之一",
+            None,
+        ));
+    }
+    let include_closed = args.include_closed.unwrap_or(false);
+    let max_positions = args.max_positions.unwrap_or(DEFAULT_MAX_POSITIONS);
+    if max_positions == 0 || max_positions > MAX_POSITIONS {
+        return Err(McpError::invalid_params(
+            format!("max_positions 必须在 1 到 {} 之间", MAX_POSITIONS),
+            None,
+        ));
+    }
+
+    info!(token_id = ?token_id, wallet = ?wallet, include_closed, "查询 Uniswap V3 头寸");
+
+    let position_manager = checksum_address(uniswap_v3_client.position_manager_address());
+
+    // 测试模式
+    if config.server.test_mode {
+        let token = |symbol: &str, address: &str, decimals: u8| TokenInfo {
+            symbol: symbol.to_string(),
+            name: format!("Test {}", symbol),
+            address: address.to_string(),
+            decimals,
+            listed_on: Vec::new(),
+        };
+        let position = V3PositionInfo {
+            token_id: token_id.unwrap_or_else(|| U256::from(12345)).to_string(),
+            owner: checksum_address(wallet.unwrap_or_else(|| Address::repeat_byte(0x11))),
+            pool: checksum_address(Address::repeat_byte(0x33)),
+            token0: token("USDC", "0x0000000000000000000000000000000000000001", 6),
+            token1: token("WETH", "0x0000000000000000000000000000000000000002", 18),
+            fee_tier: 3000,
+            tick_lower: 193380,
+            tick_upper: 199380,
+            current_tick: 196260,
+            price_lower: Some("0.00025".to_string()),
+            price_upper: Some("0.00045".to_string()),
+            price_current: Some("0.000333".to_string()),
+            in_range: true,
+            liquidity: "1000000000000000".to_string(),
+            amount0: "1500".to_string(),
+            amount1: "0.5".to_string(),
+            unclaimed_fees0: "12.5".to_string(),
+            unclaimed_fees1: "0.004".to_string(),
+            closed: false,
+            pool_activity: subgraph_client.has_v3().then(|| PoolActivity {
+                volume_24h_usd: "85000000".to_string(),
+                fees_24h_usd: "255000".to_string(),
+                tx_count_24h: 6200,
+                tvl_usd: Some("150000000".to_string()),
+                active_hours: 24,
+                source: "Test Mode".to_string(),
+            }),
+        };
+        let result = V3PositionsResult {
+            position_manager,
+            wallet: wallet.map(checksum_address),
+            total_positions: wallet.map(|_| "1".to_string()),
+            positions: vec![position],
+            protocol_tvl_usd: config.trading.defillama_enabled.then(|| "4200000000".to_string()),
+            notes: Vec::new(),
+        };
+
+        let json_str = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        return Ok(CallToolResult::success(vec![Content::text(json_str)]));
+    }
+
+    // 真实模式:需要检查客户端可用性
+    if !erc20_client.is_available() || !uniswap_v3_client.is_available() {
+        return Err(McpError::internal_error(
+            "Ethereum 客户端不可用,请检查 RPC 配置",
+            None,
+        ));
+    }
+
+    let mut notes = Vec::new();
+    let (owned, total_positions) = match (token_id, wallet) {
+        (Some(token_id), _) => {
+            let owner = uniswap_v3_client
+                .owner_of(token_id)
+                .await
+                .map_err(|e| match e {
+                    UniswapV3Error::PositionNotFound(_) => McpError::invalid_params(e.to_string(), None),
+                    e => McpError::internal_error(format!("查询头寸持有者失败: {}", e), None),
+                })?;
+            (vec![(token_id, owner)], None)
+        }
+        (None, Some(wallet)) => {
+            let (ids, total) = uniswap_v3_client
+                .position_ids(wallet, max_positions)
+                .await
+                .map_err(|e| McpError::internal_error(format!("查询头寸列表失败: {}", e), None))?;
+            if total > U256::from(max_positions) {
+                notes.push(format!(
+                    "钱包持有 {} 个头寸 NFT,只读取了前 {} 个",
+                    total, max_positions
+                ));
+            }
+            (ids.into_iter().map(|id| (id, wallet)).collect(), Some(total))
+        }
+        _ => unreachable!("参数已在前面校验"),
+    };
+
+    let mut positions = Vec::new();
+    let mut skipped = 0;
+    for (token_id, owner) in owned {
+        let position = uniswap_v3_client
+            .position(token_id)
+            .await
+            .map_err(|e| McpError::internal_error(format!("读取头寸 {} 失败: {}", token_id, e), None))?;
+        if is_closed(&position) && !include_closed && wallet.is_some() {
+            skipped += 1;
+            continue;
+        }
+        positions.push((position, owner));
+    }
+    if skipped > 0 {
+        notes.push(format!("跳过了 {} 个已关闭的头寸(可设置 include_closed 查看)", skipped));
+    }
+
+    // 所有头寸涉及的代币信息通过 Multicall3 一次查询
+    let mut token_addresses: Vec<Address> = positions
+        .iter()
+        .flat_map(|(position, _)| [position.token0, position.token1])
+        .collect();
+    token_addresses.sort();
+    token_addresses.dedup();
+    let tokens: HashMap<Address, TokenInfo> = if token_addresses.is_empty() {
+        HashMap::new()
+    } else {
+        let infos = erc20_client
+            .tokens_info(&token_addresses)
+            .await
+            .map_err(|e| McpError::internal_error(format!("查询代币信息失败: {}", e), None))?;
+        token_addresses.into_iter().zip(infos).collect()
+    };
+
+    let mut results = Vec::with_capacity(positions.len());
+    for (position, owner) in positions {
+        let (Some(token0), Some(token1)) = (tokens.get(&position.token0), tokens.get(&position.token1)) else {
+            return Err(McpError::internal_error("代币信息数量不符", None));
+        };
+        match position_info(uniswap_v3_client, subgraph_client, &position, owner, token0, token1).await {
+            Ok(info) => results.push(info),
+            Err(e) if wallet.is_some() => {
+                warn!(token_id = %position.token_id, error = %e, "读取头寸池子状态失败");
+                notes.push(format!("头寸 {} 的池子状态读取失败: {}", position.token_id, e));
+            }
+            Err(e) => {
+                return Err(McpError::internal_error(
+                    format!("读取头寸 {} 的池子状态失败: {}", position.token_id, e),
+                    None,
+                ))
+            }
+        }
+    }
+
+    if wallet.is_some() && results.is_empty() && notes.is_empty() {
+        notes.push("钱包未持有 Uniswap V3 头寸".to_string());
+    }
+
+    let protocol_tvl_usd = if config.trading.defillama_enabled {
+        defillama_client
+            .protocol_tvl(UNISWAP_V3_PROTOCOL)
+            .await
+            .inspect_err(|e| warn!(error = %e, "查询 Uniswap V3 TVL 失败"))
+            .ok()
+            .map(|tvl| tvl.round_dp(0).to_string())
+    } else {
+        None
+    };
+
+    let result = V3PositionsResult {
+        position_manager,
+        wallet: wallet.map(checksum_address),
+        total_positions: total_positions.map(|total| total.to_string()),
+        positions: results,
+        protocol_tvl_usd,
+        notes,
+    };
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(
+        wallet = ?result.wallet,
+        positions = result.positions.len(),
+        "成功返回 Uniswap V3 头寸"
+    );
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+/// 读取头寸所在池子的状态并计算数量、手续费和价格
+async fn position_info(
+    uniswap_v3_client: &UniswapV3Client,
+    subgraph_client: &SubgraphClient,
+    position: &Position,
+    owner: Address,
+    token0: &TokenInfo,
+    token1: &TokenInfo,
+) -> Result<V3PositionInfo, UniswapV3Error> {
+    let pool = uniswap_v3_client
+        .pool_address(position.token0, position.token1, position.fee)
+        .await?;
+    let (state, pool_activity) = tokio::join!(
+        uniswap_v3_client.pool_state(pool, position.tick_lower, position.tick_upper),
+        fetch_pool_activity(subgraph_client, pool)
+    );
+    let state = state?;
+    let amounts = position_amounts(position, &state).map_err(UniswapV3Error::AbiError)?;
+
+    let price = |sqrt_price: U256| {
+        price_from_sqrt(sqrt_price, token0.decimals, token1.decimals)
+            .map(|price| format_units(price, PRICE_DECIMALS))
+    };
+    let tick_price = |tick: i32| sqrt_ratio_at_tick(tick).ok().and_then(price);
+
+    Ok(V3PositionInfo {
+        token_id: position.token_id.to_string(),
+        owner: checksum_address(owner),
+        pool: checksum_address(pool),
+        token0: token0.clone(),
+        token1: token1.clone(),
+        fee_tier: position.fee,
+        tick_lower: position.tick_lower,
+        tick_upper: position.tick_upper,
+        current_tick: state.tick,
+        price_lower: tick_price(position.tick_lower),
+        price_upper: tick_price(position.tick_upper),
+        price_current: price(state.sqrt_price_x96),
+        in_range: amounts.in_range,
+        liquidity: position.liquidity.to_string(),
+        amount0: format_units(amounts.amount0, token0.decimals),
+        amount1: format_units(amounts.amount1, token1.decimals),
+        unclaimed_fees0: format_units(amounts.fees0, token0.decimals),
+        unclaimed_fees1: format_units(amounts.fees1, token1.decimals),
+        closed: is_closed(position),
+        pool_activity,
+    })
+}
+
+/// 池子最近 24 小时的子图活跃度;未配置 V3 子图时跳过,查询失败只记录警告
+async fn fetch_pool_activity(subgraph_client: &SubgraphClient, pool: Address) -> Option<PoolActivity> {
+    if !subgraph_client.has_v3() {
+        return None;
+    }
+    subgraph_client
+        .v3_pool_activity(pool)
+        .await
+        .inspect_err(|e| warn!(pool = ?pool, error = %e, "查询子图活跃度失败"))
+        .ok()
+}
+
+/// 流动性已全部移除且没有待领取的代币
+fn is_closed(position: &Position) -> bool {
+    position.liquidity == 0 && position.tokens_owed0 == 0 && position.tokens_owed1 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(liquidity: u128, tokens_owed0: u128) -> Position {
+        Position {
+            token_id: U256::one(),
+            token0: Address::repeat_byte(0x11),
+            token1: Address::repeat_byte(0x22),
+            fee: 500,
+            tick_lower: -10,
+            tick_upper: 10,
+            liquidity,
+            fee_growth_inside0_last_x128: U256::zero(),
+            fee_growth_inside1_last_x128: U256::zero(),
+            tokens_owed0,
+            tokens_owed1: 0,
+        }
+    }
+
+    #[test]
+    fn test_is_closed() {
+        assert!(is_closed(&position(0, 0)));
+        assert!(!is_closed(&position(1, 0)));
+        // 已移除流动性但手续费未领取的头寸仍视为未关闭
+        assert!(!is_closed(&position(0, 7)));
+    }
+
+    #[tokio::test]
+    async fn test_position_info_without_provider() {
+        let client = UniswapV3Client::new(None, Address::zero());
+        let token = TokenInfo {
+            symbol: "T".to_string(),
+            name: "Test".to_string(),
+            address: checksum_address(Address::zero()),
+            decimals: 18,
+            listed_on: Vec::new(),
+        };
+        assert!(matches!(
+            position_info(&client, &SubgraphClient::new(None, None), &position(1, 0), Address::zero(), &token, &token)
+                .await,
+            Err(UniswapV3Error::ProviderUnavailable)
+        ));
+    }
+}
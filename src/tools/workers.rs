@@ -0,0 +1,110 @@
+use crate::{
+    logging::info,
+    workers::{WorkerManager, WorkerStatus},
+};
+use rmcp::{
+    handler::server::wrapper::Parameters, model::*, schemars, tool, ErrorData as McpError,
+};
+use std::sync::Arc;
+
+/// ListWorkers 工具的参数
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ListWorkersArgs {
+    /// 只返回不健康的后台任务(可选,默认 false)
+    #[serde(default)]
+    pub unhealthy_only: bool,
+}
+
+/// ListWorkers 工具的返回结果
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ListWorkersResult {
+    pub total: usize,
+    pub healthy: usize,
+    pub workers: Vec<WorkerHealth>,
+}
+
+/// 单个后台任务的状态
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct WorkerHealth {
+    #[serde(flatten)]
+    pub status: WorkerStatus,
+    pub healthy: bool,
+}
+
+/// 列出后台任务及其健康状态
+#[tool(description = "列出正在运行的后台任务(价格刷新、提醒轮询、事件监听、缓存维护等)及其健康状态")]
+pub fn list_workers(
+    workers: &Arc<WorkerManager>,
+    Parameters(args): Parameters<ListWorkersArgs>,
+) -> Result<CallToolResult, McpError> {
+    info!("收到 list_workers 请求");
+
+    let result = build_workers_result(workers.statuses(), args.unhealthy_only);
+
+    let json_str = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    info!(total = result.total, healthy = result.healthy, "成功返回后台任务状态");
+
+    Ok(CallToolResult::success(vec![Content::text(json_str)]))
+}
+
+fn build_workers_result(statuses: Vec<WorkerStatus>, unhealthy_only: bool) -> ListWorkersResult {
+    let total = statuses.len();
+    let healthy = statuses.iter().filter(|s| s.is_healthy()).count();
+
+    let workers = statuses
+        .into_iter()
+        .filter(|s| !unhealthy_only || !s.is_healthy())
+        .map(|status| WorkerHealth {
+            healthy: status.is_healthy(),
+            status,
+        })
+        .collect();
+
+    ListWorkersResult {
+        total,
+        healthy,
+        workers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workers::WorkerState;
+
+    fn status(name: &str, consecutive_failures: u32) -> WorkerStatus {
+        WorkerStatus {
+            name: name.to_string(),
+            description: String::new(),
+            state: WorkerState::Idle,
+            interval_secs: 60,
+            started_at: 0,
+            runs: 3,
+            restarts: consecutive_failures as u64,
+            consecutive_failures,
+            last_success_at: None,
+            last_error: None,
+            last_error_at: None,
+        }
+    }
+
+    #[test]
+    fn test_build_workers_result() {
+        let statuses = vec![status("new_pairs", 0), status("price_refresh", 2)];
+
+        let result = build_workers_result(statuses.clone(), false);
+        assert_eq!(result.total, 2);
+        assert_eq!(result.healthy, 1);
+        assert_eq!(result.workers.len(), 2);
+
+        let result = build_workers_result(statuses, true);
+        assert_eq!(result.workers.len(), 1);
+        assert_eq!(result.workers[0].status.name, "price_refresh");
+        assert!(!result.workers[0].healthy);
+
+        let json = serde_json::to_string(&result.workers[0]).unwrap();
+        assert!(json.contains(r#""state":"idle""#));
+    }
+}
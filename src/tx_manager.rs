@@ -0,0 +1,443 @@
+use crate::eth_client::{EthClient, EthClientError};
+use crate::signer::TxSigner;
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, info, instrument, warn};
+
+/// 单笔交易最多尝试广播的次数（nonce 冲突或费用过低时重试）
+const MAX_SUBMIT_ATTEMPTS: u32 = 4;
+/// 节点拒绝费用过低时每次上调的费用比例（百分比）
+const FEE_BUMP_PERCENT: u64 = 15;
+/// 轮询回执的间隔
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// 保留的最近提交记录数
+const MAX_TRACKED_TRANSACTIONS: usize = 100;
+
+/// 交易管理错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum TxManagerError {
+    #[error(transparent)]
+    ClientError(#[from] EthClientError),
+
+    #[error("签名交易失败: {0}")]
+    SigningError(String),
+
+    #[error("节点拒绝交易: {0}")]
+    Rejected(String),
+}
+
+/// 交易状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxStatus {
+    Pending,
+    Success,
+    Reverted,
+}
+
+impl TxStatus {
+    pub fn from_receipt(receipt: Option<&TransactionReceipt>) -> Self {
+        match receipt {
+            None => Self::Pending,
+            Some(receipt) if receipt.status == Some(U64::from(1)) => Self::Success,
+            Some(_) => Self::Reverted,
+        }
+    }
+}
+
+/// 一笔通过交易管理器提交的交易
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TrackedTransaction {
+    pub tx_hash: String,
+    pub wallet: String,
+    pub nonce: u64,
+    /// 发起提交的工具
+    pub tool: String,
+    pub status: TxStatus,
+    pub confirmations: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// 广播尝试次数（大于 1 表示处理过 nonce 冲突或费用过低）
+    pub attempts: u32,
+    pub submitted_at: i64,
+}
+
+/// 提交结果
+#[derive(Debug)]
+pub struct Submission {
+    pub tx_hash: H256,
+    pub nonce: u64,
+    /// 在等待时间内未打包时为 None，交易仍在内存池中
+    pub receipt: Option<TransactionReceipt>,
+    pub confirmations: u64,
+}
+
+/// 节点拒绝交易的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SendFailure {
+    /// 节点已有该交易
+    AlreadyKnown,
+    /// nonce 已被使用
+    NonceTooLow,
+    /// 同一 nonce 已有待确认交易，且新交易费用不足以替换
+    ReplacementUnderpriced,
+    /// 费用低于节点的最低要求
+    Underpriced,
+    Other,
+}
+
+/// 钱包的本地 nonce 状态，互斥锁保证同一钱包的提交串行执行
+#[derive(Debug, Default)]
+struct WalletState {
+    /// 下一笔交易应使用的 nonce（None 表示需要从链上读取）
+    next_nonce: Option<u64>,
+}
+
+/// 交易提交管理：按钱包串行分配 nonce，处理 nonce 冲突和费用过低，等待确认并记录状态
+pub struct TxManager {
+    eth_client: Arc<EthClient>,
+    /// 等待的确认数（包含交易所在区块）
+    confirmations: u64,
+    wallets: Mutex<HashMap<Address, Arc<tokio::sync::Mutex<WalletState>>>>,
+    history: RwLock<VecDeque<TrackedTransaction>>,
+}
+
+impl TxManager {
+    pub fn new(eth_client: Arc<EthClient>, confirmations: u64) -> Self {
+        Self {
+            eth_client,
+            confirmations,
+            wallets: Mutex::new(HashMap::new()),
+            history: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    /// 签名并广播交易，然后等待确认
+    /// 同一钱包的广播串行执行；等待确认不占用钱包锁，后续交易可继续提交
+    #[instrument(skip(self, wallet, tx), fields(wallet = ?wallet.address()))]
+    pub async fn submit(
+        &self,
+        tool: &str,
+        wallet: &TxSigner,
+        tx: TypedTransaction,
+        receipt_timeout: Duration,
+    ) -> Result<Submission, TxManagerError> {
+        let (tx_hash, nonce, attempts) = self.broadcast(wallet, tx).await?;
+        self.track_broadcast(tool, wallet.address(), nonce, tx_hash, attempts);
+
+        let (receipt, confirmations) = self
+            .wait_for_confirmations(tx_hash, self.confirmations, receipt_timeout, None)
+            .await;
+
+        Ok(Submission {
+            tx_hash,
+            nonce,
+            receipt,
+            confirmations,
+        })
+    }
+
+    /// 记录已广播的交易（外部签名的交易由调用方广播后登记）
+    pub fn track_broadcast(&self, tool: &str, wallet: Address, nonce: u64, tx_hash: H256, attempts: u32) {
+        self.track(TrackedTransaction {
+            tx_hash: format!("{:?}", tx_hash),
+            wallet: format!("{:?}", wallet),
+            nonce,
+            tool: tool.to_string(),
+            status: TxStatus::Pending,
+            confirmations: 0,
+            block_number: None,
+            attempts,
+            submitted_at: chrono::Utc::now().timestamp(),
+        });
+    }
+
+    /// 轮询回执直到达到 `confirmations` 个确认、交易回滚或超时，并更新提交记录
+    /// 指定 `progress` 时每次轮询后发送最新回执和确认数，返回时关闭通道
+    pub async fn wait_for_confirmations(
+        &self,
+        tx_hash: H256,
+        confirmations: u64,
+        timeout: Duration,
+        progress: Option<mpsc::UnboundedSender<(Option<TransactionReceipt>, u64)>>,
+    ) -> (Option<TransactionReceipt>, u64) {
+        let started = Instant::now();
+        loop {
+            let (receipt, current) = self
+                .receipt_with_confirmations(tx_hash)
+                .await
+                .inspect_err(|e| warn!(tx_hash = ?tx_hash, error = %e, "查询交易回执失败"))
+                .unwrap_or_default();
+            if let Some(ref progress) = progress {
+                let _ = progress.send((receipt.clone(), current));
+            }
+
+            let status = TxStatus::from_receipt(receipt.as_ref());
+            let done = status == TxStatus::Reverted || (status == TxStatus::Success && current >= confirmations);
+            if done || started.elapsed() + RECEIPT_POLL_INTERVAL > timeout {
+                if !done {
+                    warn!(tx_hash = ?tx_hash, confirmations = current, "等待交易确认超时");
+                }
+                self.update(tx_hash, receipt.as_ref(), current);
+                return (receipt, current);
+            }
+            tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// 最近提交的交易（最新在前），待确认的交易会先刷新回执
+    pub async fn transactions(&self, wallet: Option<Address>) -> Vec<TrackedTransaction> {
+        let wallet = wallet.map(|wallet| format!("{:?}", wallet));
+        let pending: Vec<H256> = self
+            .snapshot()
+            .iter()
+            .filter(|tx| tx.status == TxStatus::Pending)
+            .filter_map(|tx| tx.tx_hash.parse().ok())
+            .collect();
+
+        for tx_hash in pending {
+            match self.receipt_with_confirmations(tx_hash).await {
+                Ok((receipt, confirmations)) => self.update(tx_hash, receipt.as_ref(), confirmations),
+                Err(e) => warn!(tx_hash = ?tx_hash, error = %e, "刷新交易状态失败"),
+            }
+        }
+
+        self.snapshot()
+            .into_iter()
+            .rev()
+            .filter(|tx| wallet.as_ref().is_none_or(|wallet| &tx.wallet == wallet))
+            .collect()
+    }
+
+    /// 钱包本地记录的下一个 nonce（尚未提交过或提交失败后为 None）
+    pub fn next_nonce(&self, wallet: Address) -> Option<u64> {
+        let state = self.wallets.lock().unwrap().get(&wallet).cloned()?;
+        state.try_lock().ok()?.next_nonce
+    }
+
+    pub fn confirmations(&self) -> u64 {
+        self.confirmations
+    }
+
+    /// 持有钱包锁分配 nonce 并广播，按节点错误调整 nonce 或费用后重试
+    async fn broadcast(
+        &self,
+        wallet: &TxSigner,
+        mut tx: TypedTransaction,
+    ) -> Result<(H256, u64, u32), TxManagerError> {
+        let address = wallet.address();
+        let state = self.wallet_state(address);
+        let mut state = state.lock().await;
+
+        let chain_nonce = self.eth_client.get_transaction_count(address, BlockNumber::Pending).await?;
+        let mut nonce = state.next_nonce.map_or(chain_nonce, |local| local.max(chain_nonce));
+        let mut last_error = String::new();
+
+        for attempt in 1..=MAX_SUBMIT_ATTEMPTS {
+            tx.set_from(address).set_nonce(nonce);
+            let signature = wallet
+                .sign_transaction(&tx)
+                .await
+                .map_err(|e| TxManagerError::SigningError(e.to_string()))?;
+            let raw = tx.rlp_signed(&signature);
+            let local_hash = H256::from(ethers::utils::keccak256(&raw));
+
+            let error = match self.eth_client.send_raw_transaction(raw).await {
+                Ok(tx_hash) => {
+                    state.next_nonce = Some(nonce + 1);
+                    return Ok((tx_hash, nonce, attempt));
+                }
+                Err(e) => e.to_string(),
+            };
+
+            match classify_send_error(&error) {
+                SendFailure::AlreadyKnown => {
+                    state.next_nonce = Some(nonce + 1);
+                    return Ok((local_hash, nonce, attempt));
+                }
+                SendFailure::NonceTooLow => {
+                    let refreshed = self.eth_client.get_transaction_count(address, BlockNumber::Pending).await?;
+                    nonce = refreshed.max(nonce + 1);
+                }
+                // 同一 nonce 上已有其他待确认交易（例如节点视图滞后），改用下一个 nonce，避免替换掉它
+                SendFailure::ReplacementUnderpriced => nonce += 1,
+                SendFailure::Underpriced => bump_fees(&mut tx, FEE_BUMP_PERCENT),
+                SendFailure::Other => {
+                    state.next_nonce = None;
+                    return Err(TxManagerError::Rejected(error));
+                }
+            }
+            warn!(attempt, nonce, error = %error, "广播被拒绝,调整后重试");
+            last_error = error;
+        }
+
+        // 放弃时清除本地 nonce，下次从链上重新读取
+        state.next_nonce = None;
+        Err(TxManagerError::Rejected(format!(
+            "重试 {} 次后仍失败: {}",
+            MAX_SUBMIT_ATTEMPTS, last_error
+        )))
+    }
+
+    async fn receipt_with_confirmations(
+        &self,
+        tx_hash: H256,
+    ) -> Result<(Option<TransactionReceipt>, u64), EthClientError> {
+        let receipt = self.eth_client.get_transaction_receipt(tx_hash).await?;
+        let Some(block_number) = receipt.as_ref().and_then(|r| r.block_number) else {
+            return Ok((receipt, 0));
+        };
+        let latest = self.eth_client.get_block_number().await?;
+        Ok((receipt, confirmations_at(block_number.as_u64(), latest)))
+    }
+
+    fn wallet_state(&self, wallet: Address) -> Arc<tokio::sync::Mutex<WalletState>> {
+        self.wallets.lock().unwrap().entry(wallet).or_default().clone()
+    }
+
+    fn track(&self, tx: TrackedTransaction) {
+        info!(tx_hash = %tx.tx_hash, nonce = tx.nonce, attempts = tx.attempts, "记录已提交的交易");
+        let mut history = self.history.write().unwrap();
+        history.push_back(tx);
+        while history.len() > MAX_TRACKED_TRANSACTIONS {
+            history.pop_front();
+        }
+    }
+
+    fn update(&self, tx_hash: H256, receipt: Option<&TransactionReceipt>, confirmations: u64) {
+        let tx_hash = format!("{:?}", tx_hash);
+        let mut history = self.history.write().unwrap();
+        if let Some(tracked) = history.iter_mut().find(|tx| tx.tx_hash == tx_hash) {
+            tracked.status = TxStatus::from_receipt(receipt);
+            tracked.confirmations = confirmations;
+            tracked.block_number = receipt.and_then(|r| r.block_number).map(|n| n.as_u64());
+            debug!(tx_hash = %tx_hash, status = ?tracked.status, confirmations, "更新交易状态");
+        }
+    }
+
+    fn snapshot(&self) -> Vec<TrackedTransaction> {
+        self.history.read().unwrap().iter().cloned().collect()
+    }
+}
+
+/// 确认数包含交易所在区块
+pub fn confirmations_at(block_number: u64, latest: u64) -> u64 {
+    latest.saturating_sub(block_number) + 1
+}
+
+/// 节点返回的“交易已存在”错误（geth: already known，其他客户端: known transaction）
+pub fn is_already_known(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("already known") || message.contains("known transaction") || message.contains("already imported")
+}
+
+/// 按节点错误信息分类（geth、erigon、nethermind 的常见措辞）
+fn classify_send_error(message: &str) -> SendFailure {
+    let message = message.to_lowercase();
+    if is_already_known(&message) {
+        SendFailure::AlreadyKnown
+    } else if message.contains("nonce too low") || message.contains("nonce has already been used") {
+        SendFailure::NonceTooLow
+    } else if message.contains("replacement transaction underpriced") || message.contains("replacement fee too low") {
+        SendFailure::ReplacementUnderpriced
+    } else if message.contains("underpriced") || message.contains("fee too low") || message.contains("max fee per gas less than block base fee") {
+        SendFailure::Underpriced
+    } else {
+        SendFailure::Other
+    }
+}
+
+/// 按比例上调交易费用
+fn bump_fees(tx: &mut TypedTransaction, percent: u64) {
+    let bump = |fee: U256| fee * U256::from(100 + percent) / U256::from(100) + 1;
+    match tx {
+        TypedTransaction::Eip1559(request) => {
+            request.max_fee_per_gas = request.max_fee_per_gas.map(bump);
+            request.max_priority_fee_per_gas = request.max_priority_fee_per_gas.map(bump);
+        }
+        tx => {
+            if let Some(gas_price) = tx.gas_price() {
+                tx.set_gas_price(bump(gas_price));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::RpcTransportConfig;
+
+    #[test]
+    fn test_classify_send_error() {
+        assert_eq!(classify_send_error("(code: -32000, message: already known, data: None)"), SendFailure::AlreadyKnown);
+        assert_eq!(classify_send_error("Known transaction: 0xabc"), SendFailure::AlreadyKnown);
+        assert_eq!(classify_send_error("nonce too low: next nonce 8, tx nonce 7"), SendFailure::NonceTooLow);
+        assert_eq!(
+            classify_send_error("replacement transaction underpriced"),
+            SendFailure::ReplacementUnderpriced
+        );
+        assert_eq!(classify_send_error("transaction underpriced"), SendFailure::Underpriced);
+        assert_eq!(
+            classify_send_error("max fee per gas less than block base fee: address 0x.., maxFeePerGas: 1"),
+            SendFailure::Underpriced
+        );
+        assert_eq!(classify_send_error("insufficient funds for gas * price + value"), SendFailure::Other);
+    }
+
+    #[test]
+    fn test_bump_fees() {
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .max_fee_per_gas(100u64)
+            .max_priority_fee_per_gas(10u64)
+            .into();
+        bump_fees(&mut tx, 15);
+        let TypedTransaction::Eip1559(ref request) = tx else { unreachable!() };
+        assert_eq!(request.max_fee_per_gas, Some(U256::from(116u64)));
+        assert_eq!(request.max_priority_fee_per_gas, Some(U256::from(12u64)));
+
+        let mut legacy: TypedTransaction = TransactionRequest::new().gas_price(1_000u64).into();
+        bump_fees(&mut legacy, 15);
+        assert_eq!(legacy.gas_price(), Some(U256::from(1_151u64)));
+    }
+
+    #[tokio::test]
+    async fn test_tracked_history() {
+        let transport = RpcTransportConfig::new(0, Duration::from_secs(30), 0);
+        let manager = TxManager::new(Arc::new(EthClient::new(&[], None, &transport).await.unwrap()), 1);
+        for nonce in 0..(MAX_TRACKED_TRANSACTIONS as u64 + 5) {
+            manager.track(TrackedTransaction {
+                tx_hash: format!("{:?}", H256::from_low_u64_be(nonce + 1)),
+                wallet: format!("{:?}", Address::repeat_byte(0x11)),
+                nonce,
+                tool: "transfer_token".to_string(),
+                status: TxStatus::Pending,
+                confirmations: 0,
+                block_number: None,
+                attempts: 1,
+                submitted_at: 0,
+            });
+        }
+        assert_eq!(manager.snapshot().len(), MAX_TRACKED_TRANSACTIONS);
+        assert_eq!(manager.snapshot()[0].nonce, 5);
+
+        let receipt = TransactionReceipt {
+            status: Some(U64::from(1)),
+            block_number: Some(U64::from(100)),
+            ..Default::default()
+        };
+        manager.update(H256::from_low_u64_be(105), Some(&receipt), 3);
+        let tracked = manager.snapshot().pop().unwrap();
+        assert_eq!(tracked.status, TxStatus::Success);
+        assert_eq!(tracked.confirmations, 3);
+        assert_eq!(tracked.block_number, Some(100));
+
+        assert_eq!(confirmations_at(100, 102), 3);
+        // 节点落后于回执所在区块时至少为 1
+        assert_eq!(confirmations_at(100, 99), 1);
+    }
+}
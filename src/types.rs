@@ -1,3 +1,7 @@
+use crate::chains::{ChainInfo, MAINNET};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Eip1559TransactionRequest, TransactionRequest};
+use ethers::utils::to_checksum;
 use serde::{Deserialize, Serialize};
 
 /// 代币信息
@@ -7,9 +11,13 @@ pub struct TokenInfo {
     pub name: String,
     pub address: String,
     pub decimals: u8,
+    /// 收录该代币的主流代币列表(如 uniswap、coingecko),未查询时为空
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub listed_on: Vec<String>,
 }
 
 /// Gas 估算信息
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GasEstimate {
     pub gas_limit: u64,
@@ -18,6 +26,7 @@ pub struct GasEstimate {
 }
 
 /// 交换路径信息
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwapRoute {
     pub protocol: String,
@@ -25,14 +34,85 @@ pub struct SwapRoute {
     pub pools: Vec<String>,
 }
 
+/// 交易信封类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxType {
+    /// 传统交易（type 0，使用 gasPrice）
+    Legacy,
+    /// EIP-1559 交易（type 2，使用 maxFeePerGas / maxPriorityFeePerGas）
+    Eip1559,
+}
+
+impl TxType {
+    /// 解析交易类型偏好，`auto` 返回 None（由链上探测决定）
+    pub fn parse_preference(value: &str) -> Result<Option<Self>, String> {
+        match value.trim().to_lowercase().as_str() {
+            "auto" => Ok(None),
+            "legacy" => Ok(Some(Self::Legacy)),
+            "eip1559" => Ok(Some(Self::Eip1559)),
+            other => Err(format!(
+                "无效的交易类型: {} (支持 auto、legacy、eip1559)",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Legacy => "legacy",
+            Self::Eip1559 => "eip1559",
+        }
+    }
+
+    /// 构建对应类型的空交易请求
+    pub fn new_request(self) -> TypedTransaction {
+        match self {
+            Self::Legacy => TransactionRequest::new().into(),
+            Self::Eip1559 => Eip1559TransactionRequest::new().into(),
+        }
+    }
+}
+
+/// 读取数据时使用的区块确认深度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadFinality {
+    /// 最新区块
+    Latest,
+    /// latest - FINALITY_BLOCKS
+    Confirmed,
+    /// 共识层已最终确定的区块（finalized 标签）
+    Finalized,
+}
+
+impl ReadFinality {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "latest" => Ok(Self::Latest),
+            "confirmed" => Ok(Self::Confirmed),
+            "finalized" => Ok(Self::Finalized),
+            other => Err(format!(
+                "无效的确认深度: {} (支持 latest、confirmed、finalized)",
+                other
+            )),
+        }
+    }
+}
+
 impl TokenInfo {
     /// 创建 ETH 代币信息
     pub fn eth() -> Self {
+        Self::native(&MAINNET)
+    }
+
+    /// 创建指定链的原生代币信息（Polygon 上为 POL）
+    pub fn native(chain: &ChainInfo) -> Self {
         Self {
-            symbol: "ETH".to_string(),
-            name: "Ether".to_string(),
+            symbol: chain.native_symbol.to_string(),
+            name: chain.native_name.to_string(),
             address: "0x0000000000000000000000000000000000000000".to_string(),
             decimals: 18,
+            listed_on: Vec::new(),
         }
     }
 
@@ -43,10 +123,51 @@ impl TokenInfo {
     }
 }
 
+/// 解析用户输入的地址
+/// 全小写或全大写时不含校验信息，直接接受；大小写混合时必须符合 EIP-55 校验和，避免输错的地址被静默接受
+pub fn parse_address(value: &str) -> Result<Address, String> {
+    let value = value.trim();
+    let address: Address = value
+        .parse()
+        .map_err(|_| format!("无效的地址: {}", value))?;
+
+    let hex = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(value);
+    let has_lower = hex.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = hex.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper && to_checksum(&address, None)[2..] != *hex {
+        return Err(format!("地址的 EIP-55 校验和不匹配,请检查是否输入错误: {}", value));
+    }
+    Ok(address)
+}
+
+/// EIP-55 校验和格式的地址，用于工具返回结果
+pub fn checksum_address(address: Address) -> String {
+    to_checksum(&address, None)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_address_checksum() {
+        let usdc = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+        let expected: Address = usdc.parse().unwrap();
+
+        assert_eq!(parse_address(usdc).unwrap(), expected);
+        assert_eq!(parse_address(&usdc.to_lowercase()).unwrap(), expected);
+        assert_eq!(parse_address(&format!("0x{}", usdc[2..].to_uppercase())).unwrap(), expected);
+
+        // 大小写混合但校验和错误（最后一个字母大小写反转）
+        let err = parse_address("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB4B").unwrap_err();
+        assert!(err.contains("EIP-55"));
+        assert!(parse_address("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eb48").is_err());
+        assert!(parse_address("0x1234").is_err());
+
+        assert_eq!(checksum_address(expected), usdc);
+        assert_eq!(checksum_address(Address::zero()), "0x0000000000000000000000000000000000000000");
+    }
+
     #[test]
     fn test_eth_token_info() {
         let eth = TokenInfo::eth();
@@ -62,6 +183,7 @@ mod tests {
             name: "USD Coin".to_string(),
             address: "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
             decimals: 6,
+            listed_on: Vec::new(),
         };
 
         let json = serde_json::to_string(&token).unwrap();
@@ -72,4 +194,23 @@ mod tests {
         assert_eq!(deserialized.symbol, token.symbol);
         assert_eq!(deserialized.decimals, token.decimals);
     }
+
+    #[test]
+    fn test_read_finality_parse() {
+        assert_eq!(ReadFinality::parse("latest").unwrap(), ReadFinality::Latest);
+        assert_eq!(ReadFinality::parse("Confirmed").unwrap(), ReadFinality::Confirmed);
+        assert_eq!(ReadFinality::parse("finalized").unwrap(), ReadFinality::Finalized);
+        assert!(ReadFinality::parse("safe").is_err());
+    }
+
+    #[test]
+    fn test_tx_type_preference() {
+        assert_eq!(TxType::parse_preference("auto").unwrap(), None);
+        assert_eq!(TxType::parse_preference("Legacy").unwrap(), Some(TxType::Legacy));
+        assert_eq!(TxType::parse_preference("eip1559").unwrap(), Some(TxType::Eip1559));
+        assert!(TxType::parse_preference("type3").is_err());
+
+        assert!(matches!(TxType::Legacy.new_request(), TypedTransaction::Legacy(_)));
+        assert!(matches!(TxType::Eip1559.new_request(), TypedTransaction::Eip1559(_)));
+    }
 }
@@ -1,7 +1,28 @@
+use crate::bindings::{self, i_uniswap_v2_factory, i_uniswap_v2_pair, i_uniswap_v2_router_02};
+use crate::chains::{ChainInfo, V2Venue};
+use crate::diagnostics::record_cache_lookup;
+use crate::eth_client::RpcProvider;
+use crate::erc20::LOG_CHUNK_BLOCKS;
+use crate::types::{TokenInfo, TxType};
+use ethers::abi::{AbiDecode, AbiEncode};
 use ethers::prelude::*;
-use std::sync::Arc;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tracing::{debug, instrument};
 
+/// 储备量缓存条目数超过该值时清理过期条目
+const RESERVE_CACHE_PRUNE_THRESHOLD: usize = 1024;
+
+/// PairCreated(address indexed token0, address indexed token1, address pair, uint256) 事件签名
+pub const PAIR_CREATED_EVENT_TOPIC: &str =
+    "0x0d3648bd0f6ba80134a33ba9275ac585d9d315f0ad8355cddefde31afa28d0e9";
+
+/// Swap(address indexed sender, uint256 amount0In, uint256 amount1In, uint256 amount0Out, uint256 amount1Out, address indexed to) 事件签名
+pub const SWAP_EVENT_TOPIC: &str =
+    "0xd78ad95fa46c994b6551d0da85fc275fe613ce37657fb8d5e3d130840159d822";
+
 /// Uniswap 错误类型
 #[derive(Debug, thiserror::Error)]
 pub enum UniswapError {
@@ -23,41 +44,209 @@ pub enum UniswapError {
     #[error("无效的数量")]
     InvalidAmount,
 
+    #[error("calldata 校验失败: {0}")]
+    CalldataMismatch(String),
+
     #[error("其他错误: {0}")]
     Other(String),
 }
 
-/// Uniswap V2 客户端
+/// 解析后的 PairCreated 事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairCreatedLog {
+    /// 发出事件的 Factory 地址
+    pub factory: Address,
+    pub token0: Address,
+    pub token1: Address,
+    pub pair: Address,
+    pub block_number: u64,
+    pub tx_hash: H256,
+}
+
+/// 解析后的 Swap 事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapLog {
+    pub pair: Address,
+    pub sender: Address,
+    /// 输出代币的接收方
+    pub to: Address,
+    pub amount0_in: U256,
+    pub amount1_in: U256,
+    pub amount0_out: U256,
+    pub amount1_out: U256,
+    pub block_number: u64,
+    /// 区块内的日志序号（同一区块内的成交顺序）
+    pub log_index: u64,
+}
+
+impl SwapLog {
+    /// 是否向池子卖出 token0（否则为卖出 token1）
+    pub fn sells_token0(&self) -> bool {
+        self.amount0_in > self.amount0_out
+    }
+}
+
+/// 路径上每一跳的 (reserve_in, reserve_out) 以及对应的 pair 地址
+pub type PathReserves = (Vec<(U256, U256)>, Vec<Address>);
+
+/// 储备量缓存键：(交易对, 区块)
+type ReserveCacheKey = (Address, Option<BlockId>);
+/// 储备量缓存值：(储备量, 写入时间)
+type ReserveCacheEntry = ((U256, U256), Instant);
+
+/// 储备量 TTL 缓存，按 (交易对, 区块) 缓存 getReserves 结果
+/// TTL 为 0 时不缓存
+#[derive(Debug, Default)]
+pub struct ReserveCache {
+    ttl: Duration,
+    entries: RwLock<HashMap<ReserveCacheKey, ReserveCacheEntry>>,
+    /// 已确认部署的交易对地址（部署后不会消失，无需过期）
+    pairs: RwLock<HashSet<Address>>,
+}
+
+impl ReserveCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+            pairs: RwLock::new(HashSet::new()),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    fn get(&self, pair: Address, block: Option<BlockId>) -> Option<(U256, U256)> {
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(&(pair, block))
+            .filter(|(_, cached_at)| cached_at.elapsed() < self.ttl)
+            .map(|(reserves, _)| *reserves)
+    }
+
+    fn insert(&self, pair: Address, block: Option<BlockId>, reserves: (U256, U256)) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= RESERVE_CACHE_PRUNE_THRESHOLD {
+            let ttl = self.ttl;
+            entries.retain(|_, (_, cached_at)| cached_at.elapsed() < ttl);
+        }
+        entries.insert((pair, block), (reserves, Instant::now()));
+    }
+
+    /// 丢弃按最新区块缓存的储备量（指定区块的结果不会变化，保留）
+    fn invalidate_latest(&self) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|(_, block), _| block.is_some());
+    }
+
+    fn is_known_pair(&self, pair: Address) -> bool {
+        self.pairs.read().unwrap_or_else(|e| e.into_inner()).contains(&pair)
+    }
+
+    fn insert_pair(&self, pair: Address) {
+        self.pairs.write().unwrap_or_else(|e| e.into_inner()).insert(pair);
+    }
+}
+
+/// Uniswap V2 客户端（也可用于 SushiSwap 等接口相同的场所）
 #[derive(Clone)]
 pub struct UniswapV2Client {
-    provider: Option<Arc<Provider<Http>>>,
+    provider: Option<Arc<RpcProvider>>,
+    /// 场所 ID（uniswap_v2、sushiswap）
+    venue_id: &'static str,
     factory_address: Address,
     router_address: Address,
+    /// 交易对合约 init code hash（各场所不同，未内置时通过 getPair 查询交易对）
+    init_code_hash: Option<H256>,
+    weth_address: Address,
+    usdc_address: Address,
+    /// 原生代币符号（注册表中原生代币与包装代币共享地址，以符号区分）
+    native_symbol: &'static str,
+    /// 路由搜索额外尝试的中间代币（包装原生代币始终参与）
+    route_intermediates: Vec<Address>,
+    reserve_cache: Arc<ReserveCache>,
+    /// 为 true 时跳过缓存读取（查询结果仍写入缓存）
+    bypass_cache: bool,
 }
 
 impl UniswapV2Client {
-    /// 创建新的 Uniswap V2 客户端（主网地址）
-    pub fn new(provider: Option<Arc<Provider<Http>>>) -> Self {
+    /// 创建新的 Uniswap V2 客户端（使用指定链上的 Factory、Router02 和基础代币地址）
+    pub fn new(provider: Option<Arc<RpcProvider>>, chain: &ChainInfo) -> Self {
+        Self::for_venue(provider, chain, &chain.uniswap_v2())
+    }
+
+    /// 创建指定 V2 兼容场所（如 SushiSwap）的客户端
+    pub fn for_venue(provider: Option<Arc<RpcProvider>>, chain: &ChainInfo, venue: &V2Venue) -> Self {
         Self {
             provider,
-            // Uniswap V2 Factory
-            factory_address: "0x5C69bEe701ef814a2B6a3EDD4B1652CB9cc5aA6f"
-                .parse()
-                .unwrap(),
-            // Uniswap V2 Router02
-            router_address: "0x7a250d5630B4cF539739dF2C5dAcb4c659F2488D"
-                .parse()
-                .unwrap(),
+            venue_id: venue.id,
+            factory_address: venue.factory_address(),
+            router_address: venue.router_address(),
+            init_code_hash: venue.init_code_hash(),
+            weth_address: chain.wrapped_native_address(),
+            usdc_address: chain.usdc_address(),
+            native_symbol: chain.native_symbol,
+            route_intermediates: Vec::new(),
+            reserve_cache: Arc::new(ReserveCache::default()),
+            bypass_cache: false,
+        }
+    }
+
+    /// 启用储备量缓存（PRICE_CACHE_TTL，0 表示不缓存）
+    pub fn with_reserve_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.reserve_cache = Arc::new(ReserveCache::new(ttl));
+        self
+    }
+
+    /// 设置路由搜索额外尝试的中间代币（ROUTE_INTERMEDIATES）
+    pub fn with_route_intermediates(mut self, intermediates: Vec<Address>) -> Self {
+        self.route_intermediates = intermediates;
+        self
+    }
+
+    /// 返回同一链上另一场所的客户端，共享 Provider、储备量缓存和路由配置
+    /// 各场所的交易对地址不同，共享缓存不会互相覆盖
+    pub fn with_venue(&self, venue: &V2Venue) -> Self {
+        Self {
+            venue_id: venue.id,
+            factory_address: venue.factory_address(),
+            router_address: venue.router_address(),
+            init_code_hash: venue.init_code_hash(),
+            ..self.clone()
+        }
+    }
+
+    /// 返回跳过缓存读取的客户端副本（force_refresh），刷新后的结果写回共享缓存
+    pub fn bypassing_cache(&self) -> Self {
+        Self {
+            bypass_cache: true,
+            ..self.clone()
         }
     }
 
+    /// 新区块到达时调用：最新区块的储备量已经过期
+    pub fn on_new_block(&self) {
+        self.reserve_cache.invalidate_latest();
+    }
+
     /// 检查客户端是否可用
     pub fn is_available(&self) -> bool {
         self.provider.is_some()
     }
 
-    /// 获取交易对地址
-    /// getPair(address tokenA, address tokenB) -> address pair
+    /// 本地计算交易对地址（CREATE2，不需要 RPC）
+    /// 交易对不存在时该地址没有合约代码，读取储备量会返回 PairNotFound
+    /// 场所没有内置 init code hash 时返回零地址，需要通过 get_pair 查询
+    pub fn pair_address(&self, token_a: Address, token_b: Address) -> Address {
+        match self.init_code_hash {
+            Some(init_code_hash) => compute_pair_address(self.factory_address, init_code_hash, token_a, token_b),
+            None => Address::zero(),
+        }
+    }
+
+    /// 获取交易对地址并确认存在
+    /// 先检查 CREATE2 计算出的地址是否有合约代码，没有时才回退到 getPair(address,address) 查询
     #[instrument(skip(self))]
     pub async fn get_pair(
         &self,
@@ -69,36 +258,36 @@ impl UniswapV2Client {
             .as_ref()
             .ok_or(UniswapError::ProviderUnavailable)?;
 
+        if self.init_code_hash.is_some() {
+            let computed = self.pair_address(token_a, token_b);
+            if self.reserve_cache.enabled() && !self.bypass_cache {
+                let known = self.reserve_cache.is_known_pair(computed);
+                record_cache_lookup("pair", &format!("{:?}", computed), known);
+                if known {
+                    return Ok(computed);
+                }
+            }
+            if !provider.get_code(computed, None).await?.is_empty() {
+                debug!(pair_address = %computed, "找到交易对");
+                if self.reserve_cache.enabled() {
+                    self.reserve_cache.insert_pair(computed);
+                }
+                return Ok(computed);
+            }
+        }
+
         debug!(
             token_a = %token_a,
             token_b = %token_b,
             factory = %self.factory_address,
-            "查询 Uniswap V2 交易对"
+            venue = self.venue_id,
+            "查询 V2 交易对"
         );
 
-        // getPair(address,address) selector: 0xe6a43905
-        let mut data = vec![0xe6, 0xa4, 0x39, 0x05];
-        // tokenA (32 bytes)
-        data.extend_from_slice(&[0u8; 12]);
-        data.extend_from_slice(token_a.as_bytes());
-        // tokenB (32 bytes)
-        data.extend_from_slice(&[0u8; 12]);
-        data.extend_from_slice(token_b.as_bytes());
-
-        let tx = Eip1559TransactionRequest::new()
-            .to(self.factory_address)
-            .data(Bytes::from(data));
-
-        let result = provider.call(&tx.into(), None).await?;
+        let call = i_uniswap_v2_factory::GetPairCall { token_a, token_b };
+        let result = bindings::eth_call(provider, self.factory_address, call, None).await?;
 
-        if result.len() != 32 {
-            return Err(UniswapError::AbiError(format!(
-                "期望 32 字节返回值，实际 {} 字节",
-                result.len()
-            )));
-        }
-
-        let pair_address = Address::from_slice(&result[12..32]);
+        let pair_address = decode_return::<i_uniswap_v2_factory::GetPairReturn>(&result)?.0;
 
         // 检查是否为零地址（表示交易对不存在）
         if pair_address == Address::zero() {
@@ -113,33 +302,42 @@ impl UniswapV2Client {
     /// getReserves() -> (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)
     #[instrument(skip(self))]
     pub async fn get_reserves(&self, pair: Address) -> Result<(U256, U256), UniswapError> {
+        self.get_reserves_at(pair, None).await
+    }
+
+    /// 获取指定区块的储备量（历史区块需要归档节点）
+    #[instrument(skip(self))]
+    pub async fn get_reserves_at(
+        &self,
+        pair: Address,
+        block: Option<BlockId>,
+    ) -> Result<(U256, U256), UniswapError> {
         let provider = self
             .provider
             .as_ref()
             .ok_or(UniswapError::ProviderUnavailable)?;
 
-        debug!(pair_address = %pair, "查询储备量");
-
-        // getReserves() selector: 0x0902f1ac
-        let data = vec![0x09, 0x02, 0xf1, 0xac];
+        if self.reserve_cache.enabled() && !self.bypass_cache {
+            let cached = self.reserve_cache.get(pair, block);
+            record_cache_lookup("reserves", &format!("{:?}@{:?}", pair, block), cached.is_some());
+            if let Some(reserves) = cached {
+                debug!(pair_address = %pair, block = ?block, "命中储备量缓存");
+                return Ok(reserves);
+            }
+        }
 
-        let tx = Eip1559TransactionRequest::new()
-            .to(pair)
-            .data(Bytes::from(data));
+        debug!(pair_address = %pair, block = ?block, "查询储备量");
 
-        let result = provider.call(&tx.into(), None).await?;
+        let result = bindings::eth_call(provider, pair, i_uniswap_v2_pair::GetReservesCall, block).await?;
 
-        if result.len() < 64 {
-            return Err(UniswapError::AbiError(format!(
-                "期望至少 64 字节返回值，实际 {} 字节",
-                result.len()
-            )));
+        // 地址上没有合约代码时 eth_call 返回空数据
+        if result.is_empty() {
+            return Err(UniswapError::PairNotFound);
         }
 
-        // reserve0 (uint112, 但存储在 32 字节中)
-        let reserve0 = U256::from_big_endian(&result[0..32]);
-        // reserve1 (uint112, 但存储在 32 字节中)
-        let reserve1 = U256::from_big_endian(&result[32..64]);
+        let reserves = decode_return::<i_uniswap_v2_pair::GetReservesReturn>(&result)?;
+        let reserve0 = U256::from(reserves.0);
+        let reserve1 = U256::from(reserves.1);
 
         // 检查流动性
         if reserve0.is_zero() || reserve1.is_zero() {
@@ -152,9 +350,67 @@ impl UniswapV2Client {
             "获取到储备量"
         );
 
+        if self.reserve_cache.enabled() {
+            self.reserve_cache.insert(pair, block, (reserve0, reserve1));
+        }
+
         Ok((reserve0, reserve1))
     }
 
+    /// 获取交易对的 token0 和 token1 地址
+    #[instrument(skip(self))]
+    pub async fn get_pair_tokens(&self, pair: Address) -> Result<(Address, Address), UniswapError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(UniswapError::ProviderUnavailable)?;
+
+        let result = bindings::eth_call(provider, pair, i_uniswap_v2_pair::Token0Call, None).await?;
+        let token0 = decode_return::<i_uniswap_v2_pair::Token0Return>(&result)?.0;
+        let result = bindings::eth_call(provider, pair, i_uniswap_v2_pair::Token1Call, None).await?;
+        let token1 = decode_return::<i_uniswap_v2_pair::Token1Return>(&result)?.0;
+
+        debug!(token0 = %token0, token1 = %token1, "获取到交易对代币");
+        Ok((token0, token1))
+    }
+
+    /// 获取指定区块的累计价格和储备量（TWAP 计算用，不使用储备量缓存，历史区块需要归档节点）
+    #[instrument(skip(self))]
+    pub async fn get_cumulative_prices_at(
+        &self,
+        pair: Address,
+        block: Option<BlockId>,
+    ) -> Result<CumulativePrices, UniswapError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(UniswapError::ProviderUnavailable)?;
+
+        let (price0, price1, reserves) = tokio::join!(
+            bindings::eth_call(provider, pair, i_uniswap_v2_pair::Price0CumulativeLastCall, block),
+            bindings::eth_call(provider, pair, i_uniswap_v2_pair::Price1CumulativeLastCall, block),
+            bindings::eth_call(provider, pair, i_uniswap_v2_pair::GetReservesCall, block)
+        );
+
+        // 地址上没有合约代码（该区块时交易对尚未部署）时 eth_call 返回空数据
+        let reserves = reserves?;
+        if reserves.is_empty() {
+            return Err(UniswapError::PairNotFound);
+        }
+        let reserves = decode_return::<i_uniswap_v2_pair::GetReservesReturn>(&reserves)?;
+
+        let cumulative = CumulativePrices {
+            price0_cumulative_last: decode_return::<i_uniswap_v2_pair::Price0CumulativeLastReturn>(&price0?)?.0,
+            price1_cumulative_last: decode_return::<i_uniswap_v2_pair::Price1CumulativeLastReturn>(&price1?)?.0,
+            reserve0: U256::from(reserves.0),
+            reserve1: U256::from(reserves.1),
+            block_timestamp_last: reserves.2,
+        };
+
+        debug!(pair_address = %pair, block = ?block, cumulative = ?cumulative, "获取到累计价格");
+        Ok(cumulative)
+    }
+
     /// 计算输出数量（含 0.3% 手续费）
     /// 使用 Uniswap V2 公式: amountOut = (amountIn * 997 * reserveOut) / (reserveIn * 1000 + amountIn * 997)
     pub fn calculate_amount_out(
@@ -186,36 +442,77 @@ impl UniswapV2Client {
         Ok(numerator / denominator)
     }
 
-    /// 计算价格影响（百分比）
-    /// 使用 checked_mul 避免溢出
-    pub fn calculate_price_impact(
+    /// 计算得到指定输出所需的输入数量（含 0.3% 手续费，calculate_amount_out 的逆运算）
+    /// 使用 Uniswap V2 公式: amountIn = (reserveIn * amountOut * 1000) / ((reserveOut - amountOut) * 997) + 1
+    pub fn calculate_amount_in(
         &self,
-        amount_in: U256,
+        amount_out: U256,
         reserve_in: U256,
-    ) -> Result<f64, UniswapError> {
-        if reserve_in.is_zero() {
+        reserve_out: U256,
+    ) -> Result<U256, UniswapError> {
+        if amount_out.is_zero() {
+            return Err(UniswapError::InvalidAmount);
+        }
+
+        // 输出不能达到或超过池子储备
+        if reserve_in.is_zero() || amount_out >= reserve_out {
             return Err(UniswapError::InsufficientLiquidity);
         }
 
-        // 🔒 使用 checked_mul 防止溢出
-        // impact = (amount_in * 10000) / reserve_in  (保留 2 位小数的百分比)
-        let impact_scaled = amount_in
-            .checked_mul(U256::from(10000))
+        let numerator = reserve_in
+            .checked_mul(amount_out)
             .ok_or(UniswapError::InvalidAmount)?
-            / reserve_in;
+            .checked_mul(U256::from(1000))
+            .ok_or(UniswapError::InvalidAmount)?;
+        let denominator = (reserve_out - amount_out)
+            .checked_mul(U256::from(997))
+            .ok_or(UniswapError::InvalidAmount)?;
+
+        Ok(numerator / denominator + 1)
+    }
+
+    /// 计算路径的价格影响（百分比）：(中间价 - 成交价) / 中间价
+    /// 中间价为各跳 reserve_out / reserve_in 之积，成交价为 amount_out / amount_in（包含 0.3% 手续费）
+    /// `amounts` 为路径上每个代币的数量（与 calculate_amounts_out 返回值一致）
+    /// 返回 (总价格影响, 每一跳的价格影响)
+    pub fn calculate_price_impact(
+        &self,
+        amounts: &[U256],
+        reserves: &[(U256, U256)],
+    ) -> Result<(f64, Vec<f64>), UniswapError> {
+        if amounts.len() != reserves.len() + 1 {
+            return Err(UniswapError::Other("数量与储备量不匹配".to_string()));
+        }
+
+        let mut ratios = Vec::with_capacity(reserves.len());
+        for (hop, (reserve_in, reserve_out)) in reserves.iter().enumerate() {
+            if reserve_in.is_zero() || reserve_out.is_zero() {
+                return Err(UniswapError::InsufficientLiquidity);
+            }
+            if amounts[hop].is_zero() {
+                return Err(UniswapError::InvalidAmount);
+            }
+
+            // 成交价 / 中间价 = (amount_out / amount_in) * (reserve_in / reserve_out)
+            let execution = u256_to_f64(amounts[hop + 1]) / u256_to_f64(amounts[hop]);
+            let mid = u256_to_f64(*reserve_out) / u256_to_f64(*reserve_in);
+            ratios.push(execution / mid);
+        }
 
-        // 转换为 f64 显示（除以 100 得到百分比）
-        let impact_u128 = impact_scaled.as_u128();
-        Ok((impact_u128 as f64) / 100.0)
+        let total = (1.0 - ratios.iter().product::<f64>()) * 100.0;
+        let per_hop = ratios.iter().map(|ratio| (1.0 - ratio) * 100.0).collect();
+        Ok((total, per_hop))
     }
 
     /// 获取路径对应的储备量和 pair 地址
     /// 返回 (Vec<(reserve_in, reserve_out)>, Vec<pair_addresses>)
+    /// `block` 为 None 时查询最新区块（历史区块需要归档节点）
     #[instrument(skip(self))]
     pub async fn get_reserves_for_path(
         &self,
         path: &[Address],
-    ) -> Result<(Vec<(U256, U256)>, Vec<Address>), UniswapError> {
+        block: Option<BlockId>,
+    ) -> Result<PathReserves, UniswapError> {
         if path.len() < 2 {
             return Err(UniswapError::AbiError(
                 "路径至少需要 2 个代币".to_string(),
@@ -229,12 +526,16 @@ impl UniswapV2Client {
             let token_a = path[i];
             let token_b = path[i + 1];
 
-            // 获取交易对
-            let pair = self.get_pair(token_a, token_b).await?;
+            // 本地计算交易对地址，不存在时读取储备量返回 PairNotFound
+            // 没有 init code hash 的场所通过 getPair 查询
+            let pair = match self.init_code_hash {
+                Some(_) => self.pair_address(token_a, token_b),
+                None => self.get_pair(token_a, token_b).await?,
+            };
             pair_addresses.push(pair);
 
             // 获取储备量
-            let (reserve0, reserve1) = self.get_reserves(pair).await?;
+            let (reserve0, reserve1) = self.get_reserves_at(pair, block).await?;
 
             // Uniswap V2 按地址排序确定 token0/token1
             // token0 < token1 (按地址字典序)
@@ -266,6 +567,23 @@ impl UniswapV2Client {
         Ok(amounts)
     }
 
+    /// 从最后一跳向前计算路径的输入数量（对应 Router 的 getAmountsIn）
+    pub fn calculate_amounts_in(
+        &self,
+        amount_out: U256,
+        reserves: &[(U256, U256)],
+    ) -> Result<Vec<U256>, UniswapError> {
+        let mut amounts = vec![amount_out];
+
+        for (reserve_in, reserve_out) in reserves.iter().rev() {
+            let amount_in = self.calculate_amount_in(*amounts.last().unwrap(), *reserve_in, *reserve_out)?;
+            amounts.push(amount_in);
+        }
+
+        amounts.reverse();
+        Ok(amounts)
+    }
+
     /// 计算交换的详细信息（用于价格查询和交换模拟）
     #[instrument(skip(self))]
     pub async fn quote_swap(
@@ -274,48 +592,309 @@ impl UniswapV2Client {
         token_out: Address,
         amount_in: U256,
     ) -> Result<SwapQuote, UniswapError> {
-        // 构建路径（直接或通过 WETH）
-        let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
-            .parse()
-            .unwrap();
+        self.quote_swap_at(token_in, token_out, amount_in, None).await
+    }
+
+    /// 按指定区块的储备量计算交换报价（历史区块需要归档节点）
+    /// 在所有候选路径中选择输出最多的路径
+    #[instrument(skip(self))]
+    pub async fn quote_swap_at(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        block: Option<BlockId>,
+    ) -> Result<SwapQuote, UniswapError> {
+        self.quote_best_route(token_in, token_out, amount_in, false, block).await
+    }
+
+    /// 计算得到指定输出数量的交换报价（exact-output）
+    /// 在所有候选路径中选择所需输入最少的路径
+    #[instrument(skip(self))]
+    pub async fn quote_swap_exact_output(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_out: U256,
+    ) -> Result<SwapQuote, UniswapError> {
+        self.quote_best_route(token_in, token_out, amount_out, true, None).await
+    }
+
+    /// 并发报价所有候选路径，返回最优路径的报价（附带各路径明细）
+    async fn quote_best_route(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount: U256,
+        exact_output: bool,
+        block: Option<BlockId>,
+    ) -> Result<SwapQuote, UniswapError> {
+        let paths = self.candidate_paths(token_in, token_out);
+
+        debug!(candidates = paths.len(), "构建候选交换路径");
+
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, path) in paths.into_iter().enumerate() {
+            let client = self.clone();
+            tasks.spawn(async move {
+                let quote = async {
+                    let (reserves, pair_addresses) = client.get_reserves_for_path(&path, block).await?;
+                    if exact_output {
+                        client.quote_exact_output_from_reserves(path.clone(), reserves, pair_addresses, amount)
+                    } else {
+                        client.quote_from_reserves(path.clone(), reserves, pair_addresses, amount)
+                    }
+                }
+                .await;
+                (index, path, quote)
+            });
+        }
+
+        let mut results = Vec::with_capacity(tasks.len());
+        while let Some(joined) = tasks.join_next().await {
+            results.push(joined.map_err(|e| UniswapError::Other(format!("路径报价任务失败: {}", e)))?);
+        }
+        results.sort_by_key(|(index, _, _)| *index);
+
+        select_best_route(results.into_iter().map(|(_, path, quote)| (path, quote)).collect(), exact_output)
+    }
+
+    /// 候选交换路径：默认路径（直接或经包装原生代币）在前，其次直接路径和经各中间代币的两跳路径
+    pub fn candidate_paths(&self, token_in: Address, token_out: Address) -> Vec<Vec<Address>> {
+        let mut paths = vec![self.swap_path(token_in, token_out), vec![token_in, token_out]];
+        for &middle in std::iter::once(&self.weth_address).chain(&self.route_intermediates) {
+            if middle != token_in && middle != token_out {
+                paths.push(vec![token_in, middle, token_out]);
+            }
+        }
 
-        let path = if token_in == weth || token_out == weth {
+        let mut seen = HashSet::new();
+        paths.retain(|path| seen.insert(path.clone()));
+        paths
+    }
+
+    /// 构建交换路径（直接或通过包装原生代币）
+    pub fn swap_path(&self, token_in: Address, token_out: Address) -> Vec<Address> {
+        let weth = self.weth_address;
+
+        if token_in == weth || token_out == weth {
             // 直接路径
             vec![token_in, token_out]
         } else {
             // 通过 WETH
             vec![token_in, weth, token_out]
-        };
-
-        debug!(path_length = path.len(), "构建交换路径");
+        }
+    }
 
-        // 获取所有储备量和 pair 地址
-        let (reserves, pair_addresses) = self.get_reserves_for_path(&path).await?;
+    /// 判断交换哪一侧为原生代币（两侧都是原生代币时按普通交换处理）
+    pub fn native_leg(&self, from: &TokenInfo, to: &TokenInfo) -> NativeLeg {
+        match (from.symbol == self.native_symbol, to.symbol == self.native_symbol) {
+            (true, false) => NativeLeg::Input,
+            (false, true) => NativeLeg::Output,
+            _ => NativeLeg::None,
+        }
+    }
 
+    /// 基于已知储备量计算交换报价（储备量可以来自链上或离线快照）
+    pub fn quote_from_reserves(
+        &self,
+        path: Vec<Address>,
+        reserves: Vec<(U256, U256)>,
+        pair_addresses: Vec<Address>,
+        amount_in: U256,
+    ) -> Result<SwapQuote, UniswapError> {
         // 计算所有中间输出
         let amounts = self.calculate_amounts_out(amount_in, &reserves)?;
 
         let amount_out = *amounts.last().unwrap();
 
-        // 计算价格影响（使用第一个池子）
-        let (reserve_in, _) = reserves[0];
-        let price_impact = self.calculate_price_impact(amount_in, reserve_in)?;
+        let (price_impact, hop_price_impacts) = self.calculate_price_impact(&amounts, &reserves)?;
+
+        Ok(SwapQuote {
+            path,
+            amount_in,
+            amount_out,
+            price_impact,
+            hop_price_impacts,
+            pair_addresses,
+            routes: Vec::new(),
+        })
+    }
+
+    /// 基于已知储备量计算 exact-output 报价（amount_in 为所需输入）
+    pub fn quote_exact_output_from_reserves(
+        &self,
+        path: Vec<Address>,
+        reserves: Vec<(U256, U256)>,
+        pair_addresses: Vec<Address>,
+        amount_out: U256,
+    ) -> Result<SwapQuote, UniswapError> {
+        let amounts = self.calculate_amounts_in(amount_out, &reserves)?;
+
+        let amount_in = amounts[0];
+
+        let (price_impact, hop_price_impacts) = self.calculate_price_impact(&amounts, &reserves)?;
 
         Ok(SwapQuote {
             path,
+            amount_in,
             amount_out,
             price_impact,
+            hop_price_impacts,
             pair_addresses,
+            routes: Vec::new(),
         })
     }
 
+    /// 场所 ID（uniswap_v2、sushiswap）
+    pub fn venue_id(&self) -> &'static str {
+        self.venue_id
+    }
+
+    /// 获取 Factory 地址
+    pub fn factory_address(&self) -> Address {
+        self.factory_address
+    }
+
+    /// 获取包装原生代币地址（主网为 WETH，Polygon 为 WPOL）
+    pub fn weth_address(&self) -> Address {
+        self.weth_address
+    }
+
+    /// 获取 USD 报价使用的稳定币地址（USDC）
+    pub fn usdc_address(&self) -> Address {
+        self.usdc_address
+    }
+
+    /// 查询区块区间内 Factory 新建的交易对
+    /// 按 LOG_CHUNK_BLOCKS 分段查询，结果按区块号排序
+    #[instrument(skip(self))]
+    pub async fn pair_created_logs(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<PairCreatedLog>, UniswapError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(UniswapError::ProviderUnavailable)?;
+
+        let topic: H256 = PAIR_CREATED_EVENT_TOPIC.parse().expect("硬编码事件签名应该有效");
+
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut chunk_start = from_block;
+        while chunk_start <= to_block {
+            let chunk_end = (chunk_start + LOG_CHUNK_BLOCKS - 1).min(to_block);
+
+            let filter = Filter::new()
+                .address(self.factory_address)
+                .from_block(chunk_start)
+                .to_block(chunk_end)
+                .topic0(topic);
+            let provider = provider.clone();
+            tasks.spawn(async move { provider.get_logs(&filter).await });
+
+            chunk_start = chunk_end + 1;
+        }
+
+        let mut pairs = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            let logs = joined.map_err(|e| UniswapError::Other(format!("日志查询任务失败: {}", e)))??;
+            pairs.extend(logs.iter().filter_map(parse_pair_created_log));
+        }
+
+        pairs.sort_by_key(|p| p.block_number);
+
+        debug!(count = pairs.len(), "查询到 PairCreated 事件");
+
+        Ok(pairs)
+    }
+
+    /// 查询区块区间内指定交易对的 Swap 事件
+    /// 所有交易对合并为单个地址过滤器，按 LOG_CHUNK_BLOCKS 分段查询，结果按成交顺序排序
+    #[instrument(skip(self, pairs), fields(pair_count = pairs.len()))]
+    pub async fn swap_logs(
+        &self,
+        pairs: &[Address],
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<SwapLog>, UniswapError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(UniswapError::ProviderUnavailable)?;
+
+        if pairs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let topic: H256 = SWAP_EVENT_TOPIC.parse().expect("硬编码事件签名应该有效");
+
+        let mut tasks = tokio::task::JoinSet::new();
+        let mut chunk_start = from_block;
+        while chunk_start <= to_block {
+            let chunk_end = (chunk_start + LOG_CHUNK_BLOCKS - 1).min(to_block);
+
+            let filter = Filter::new()
+                .address(pairs.to_vec())
+                .from_block(chunk_start)
+                .to_block(chunk_end)
+                .topic0(topic);
+            let provider = provider.clone();
+            tasks.spawn(async move { provider.get_logs(&filter).await });
+
+            chunk_start = chunk_end + 1;
+        }
+
+        let mut swaps = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            let logs = joined.map_err(|e| UniswapError::Other(format!("日志查询任务失败: {}", e)))??;
+            swaps.extend(logs.iter().filter_map(parse_swap_log));
+        }
+
+        swaps.sort_by_key(|s| (s.block_number, s.log_index));
+
+        debug!(count = swaps.len(), "查询到 Swap 事件");
+
+        Ok(swaps)
+    }
+
     /// 获取 Router 地址
     pub fn router_address(&self) -> Address {
         self.router_address
     }
 
+    /// 构建发往 Router 的交换交易（按链支持情况选择 legacy 或 EIP-1559 信封）
+    /// 广播前应使用 `SwapCall::verify_transaction` 按报价独立核对最终交易
+    pub fn swap_transaction(&self, call: &SwapCall, from: Address, tx_type: TxType) -> TypedTransaction {
+        self.router_transaction(call.encode(), call.value(), from, tx_type)
+    }
+
+    /// 构建发往 Router 的 exact-output 交换交易
+    pub fn exact_output_swap_transaction(
+        &self,
+        call: &ExactOutputSwapCall,
+        from: Address,
+        tx_type: TxType,
+    ) -> TypedTransaction {
+        self.router_transaction(call.encode(), call.value(), from, tx_type)
+    }
+
+    /// 支付原生代币时 `value` 为随交易发送的数量
+    fn router_transaction(&self, data: Vec<u8>, value: U256, from: Address, tx_type: TxType) -> TypedTransaction {
+        let mut tx = tx_type.new_request();
+        tx.set_to(self.router_address())
+            .set_from(from)
+            .set_data(Bytes::from(data));
+        if !value.is_zero() {
+            tx.set_value(value);
+        }
+        tx
+    }
+
     /// 模拟真实的 Router 交易
-    /// 使用 eth_call 调用 swapExactTokensForTokens 进行模拟
+    /// 使用 eth_call 调用 swapExactTokensForTokens（原生代币一侧为 swapExactETHForTokens / swapExactTokensForETH）进行模拟
+    #[allow(clippy::too_many_arguments)]
     #[instrument(skip(self))]
     pub async fn simulate_swap(
         &self,
@@ -323,7 +902,10 @@ impl UniswapV2Client {
         token_out: Address,
         amount_in: U256,
         amount_out_min: U256,
+        native: NativeLeg,
+        fee_on_transfer: bool,
         from_address: Option<Address>,
+        tx_type: TxType,
     ) -> Result<SwapSimulation, UniswapError> {
         let provider = self
             .provider
@@ -333,103 +915,209 @@ impl UniswapV2Client {
         // 首先获取报价
         let quote = self.quote_swap(token_in, token_out, amount_in).await?;
 
-        // 构建路径（直接或通过 WETH）
-        let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"
-            .parse()
-            .unwrap();
+        // to (address) - 使用提供的地址（不应该是零地址）
+        let to_addr = from_address.ok_or_else(|| {
+            UniswapError::Other("需要提供有效的钱包地址进行模拟".to_string())
+        })?;
 
-        let path = if token_in == weth || token_out == weth {
-            vec![token_in, token_out]
-        } else {
-            vec![token_in, weth, token_out]
+        // 按报价的路径构建 swapExactTokensForTokens 调用，deadline 使用一个很大的值
+        let call = SwapCall {
+            amount_in,
+            amount_out_min,
+            path: quote.path.clone(),
+            to: to_addr,
+            deadline: U256::MAX,
+            native,
+            fee_on_transfer,
         };
+        let tx = self.swap_transaction(&call, to_addr, tx_type);
 
-        // 构建 swapExactTokensForTokens calldata
-        // function swapExactTokensForTokens(
-        //   uint amountIn,
-        //   uint amountOutMin,
-        //   address[] calldata path,
-        //   address to,
-        //   uint deadline
-        // ) external returns (uint[] memory amounts);
-        // selector: 0x38ed1739
-
-        let mut data = vec![0x38, 0xed, 0x17, 0x39];
-
-        // amountIn (uint256)
-        let mut amount_in_bytes = [0u8; 32];
-        amount_in.to_big_endian(&mut amount_in_bytes);
-        data.extend_from_slice(&amount_in_bytes);
+        Ok(simulate_router_call(provider, &tx, quote).await)
+    }
 
-        // amountOutMin (uint256)
-        let mut amount_out_min_bytes = [0u8; 32];
-        amount_out_min.to_big_endian(&mut amount_out_min_bytes);
-        data.extend_from_slice(&amount_out_min_bytes);
+    /// 模拟 exact-output 的 Router 交易
+    /// 使用 eth_call 调用 swapTokensForExactTokens（原生代币一侧为 swapETHForExactTokens / swapTokensForExactETH）进行模拟
+    #[allow(clippy::too_many_arguments)]
+    #[instrument(skip(self))]
+    pub async fn simulate_swap_exact_output(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_out: U256,
+        amount_in_max: U256,
+        native: NativeLeg,
+        from_address: Option<Address>,
+        tx_type: TxType,
+    ) -> Result<SwapSimulation, UniswapError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(UniswapError::ProviderUnavailable)?;
 
-        // path offset (uint256) - 0xa0 (160)
-        data.extend_from_slice(&[0u8; 31]);
-        data.push(0xa0);
+        let quote = self.quote_swap_exact_output(token_in, token_out, amount_out).await?;
 
-        // to (address) - 使用提供的地址（不应该是零地址）
         let to_addr = from_address.ok_or_else(|| {
             UniswapError::Other("需要提供有效的钱包地址进行模拟".to_string())
         })?;
-        data.extend_from_slice(&[0u8; 12]);
-        data.extend_from_slice(to_addr.as_bytes());
-
-        // deadline (uint256) - 使用一个很大的值
-        data.extend_from_slice(&[0xff; 32]);
-
-        // path 数组
-        // length
-        let mut path_len_bytes = [0u8; 32];
-        U256::from(path.len()).to_big_endian(&mut path_len_bytes);
-        data.extend_from_slice(&path_len_bytes);
-
-        // path 元素
-        for addr in &path {
-            data.extend_from_slice(&[0u8; 12]);
-            data.extend_from_slice(addr.as_bytes());
-        }
-
-        // 构建交易请求
-        let tx = Eip1559TransactionRequest::new()
-            .to(self.router_address())
-            .from(to_addr)
-            .data(Bytes::from(data.clone()));
-
-        // 尝试模拟调用
-        let (simulation_success, revert_reason, gas_estimate) = match provider.call(&tx.clone().into(), None).await {
-            Ok(_) => {
-                // 调用成功，尝试估算 gas
-                let gas = match provider.estimate_gas(&tx.into(), None).await {
-                    Ok(g) => Some(g),
+
+        let call = ExactOutputSwapCall {
+            amount_out,
+            amount_in_max,
+            path: quote.path.clone(),
+            to: to_addr,
+            deadline: U256::MAX,
+            native,
+        };
+        let tx = self.exact_output_swap_transaction(&call, to_addr, tx_type);
+
+        Ok(simulate_router_call(provider, &tx, quote).await)
+    }
+
+    /// 构建发往 Router 的添加流动性交易
+    pub fn add_liquidity_transaction(&self, call: &AddLiquidityCall, from: Address, tx_type: TxType) -> TypedTransaction {
+        self.router_transaction(call.encode(), call.value(), from, tx_type)
+    }
+
+    /// 模拟 Router.addLiquidity（原生代币一侧为 addLiquidityETH），以接收方 `to` 作为发送方
+    /// 调用成功时解码实际存入的数量和铸造的 LP 数量，并估算 Gas
+    #[instrument(skip(self))]
+    pub async fn simulate_add_liquidity(
+        &self,
+        call: &AddLiquidityCall,
+        tx_type: TxType,
+    ) -> Result<AddLiquiditySimulation, UniswapError> {
+        let provider = self
+            .provider
+            .as_ref()
+            .ok_or(UniswapError::ProviderUnavailable)?;
+
+        let tx = self.add_liquidity_transaction(call, call.to, tx_type);
+        let simulation = match provider.call(&tx, None).await {
+            Ok(data) => {
+                let gas_estimate = match provider.estimate_gas(&tx, None).await {
+                    Ok(gas) => Some(gas),
                     Err(e) => {
                         debug!(error = %e, "Gas 估算失败");
                         None
                     }
                 };
-                (true, None, gas)
+                AddLiquiditySimulation {
+                    deposited: call.decode_output(&data).ok(),
+                    gas_estimate,
+                    simulation_success: true,
+                    revert_reason: None,
+                }
             }
             Err(e) => {
-                // 调用失败，提取 revert 原因
                 let reason = extract_revert_reason(&e);
-                debug!(error = %e, reason = ?reason, "交易模拟失败");
-                (false, reason, None)
+                debug!(error = %e, reason = ?reason, "添加流动性模拟失败");
+                AddLiquiditySimulation {
+                    deposited: None,
+                    gas_estimate: None,
+                    simulation_success: false,
+                    revert_reason: reason,
+                }
             }
         };
 
-        Ok(SwapSimulation {
-            quote,
-            gas_estimate,
-            simulation_success,
-            revert_reason,
+        Ok(simulation)
+    }
+}
+
+/// 以 eth_call 模拟 Router 交易,成功时估算 Gas,失败时提取 revert 原因
+async fn simulate_router_call(provider: &RpcProvider, tx: &TypedTransaction, quote: SwapQuote) -> SwapSimulation {
+    let (simulation_success, revert_reason, gas_estimate) = match provider.call(tx, None).await {
+        Ok(_) => {
+            // 调用成功，尝试估算 gas
+            let gas = match provider.estimate_gas(tx, None).await {
+                Ok(g) => Some(g),
+                Err(e) => {
+                    debug!(error = %e, "Gas 估算失败");
+                    None
+                }
+            };
+            (true, None, gas)
+        }
+        Err(e) => {
+            // 调用失败，提取 revert 原因
+            let reason = extract_revert_reason(&e);
+            debug!(error = %e, reason = ?reason, "交易模拟失败");
+            (false, reason, None)
+        }
+    };
+
+    SwapSimulation {
+        quote,
+        gas_estimate,
+        simulation_success,
+        revert_reason,
+    }
+}
+
+/// 从各候选路径的报价中选出最优路径，并在其中记录所有路径的明细
+/// exact-output 时选择所需输入最少的路径，否则选择输出最多的路径；相同时保留靠前的路径
+/// 所有路径都失败时返回第一条（默认）路径的错误
+pub fn select_best_route<E: std::fmt::Display>(
+    results: Vec<(Vec<Address>, Result<SwapQuote, E>)>,
+    exact_output: bool,
+) -> Result<SwapQuote, E> {
+    let routes: Vec<PathQuote> = results
+        .iter()
+        .map(|(path, quote)| PathQuote {
+            path: path.clone(),
+            amounts: quote
+                .as_ref()
+                .map(|q| (q.amount_in, q.amount_out))
+                .map_err(|e| e.to_string()),
         })
+        .collect();
+
+    let mut best: Option<SwapQuote> = None;
+    let mut first_error = None;
+    for (_, quote) in results {
+        match quote {
+            Ok(quote) => {
+                let better = match &best {
+                    None => true,
+                    Some(current) if exact_output => quote.amount_in < current.amount_in,
+                    Some(current) => quote.amount_out > current.amount_out,
+                };
+                if better {
+                    best = Some(quote);
+                }
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    match (best, first_error) {
+        (Some(mut quote), _) => {
+            quote.routes = routes;
+            Ok(quote)
+        }
+        (None, Some(e)) => Err(e),
+        (None, None) => unreachable!("候选路径至少包含默认路径"),
     }
 }
 
+/// U256 转换为 f64（仅用于比率计算，超过 53 位有效数字时损失精度）
+fn u256_to_f64(value: U256) -> f64 {
+    value
+        .0
+        .iter()
+        .rev()
+        .fold(0.0, |acc, limb| acc * 18_446_744_073_709_551_616.0 + *limb as f64)
+}
+
+/// 按绑定的返回类型解码 eth_call 结果
+fn decode_return<R: AbiDecode>(data: &[u8]) -> Result<R, UniswapError> {
+    R::decode(data).map_err(|e| UniswapError::AbiError(format!("解码返回值失败: {}", e)))
+}
+
 /// 从 ProviderError 中提取 revert 原因
-fn extract_revert_reason(error: &ProviderError) -> Option<String> {
+pub(crate) fn extract_revert_reason(error: &ProviderError) -> Option<String> {
     // 尝试从错误消息中提取 revert 原因
     let error_msg = error.to_string();
 
@@ -447,13 +1135,591 @@ fn extract_revert_reason(error: &ProviderError) -> Option<String> {
     }
 }
 
+/// 取出节点在错误响应 data 字段中返回的 revert 数据
+pub(crate) fn revert_data(error: &ProviderError) -> Option<Bytes> {
+    RpcError::as_error_response(error)?
+        .as_revert_data()
+        .filter(|data| !data.is_empty())
+}
+
+/// 解码标准 revert 数据：Error(string) 和 Panic(uint256)，自定义错误返回 None
+pub(crate) fn decode_revert_data(data: &[u8]) -> Option<String> {
+    const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+    const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+    let (selector, payload) = data.split_at_checked(4)?;
+    if selector == ERROR_SELECTOR {
+        String::decode(payload).ok()
+    } else if selector == PANIC_SELECTOR {
+        let code = U256::decode(payload).ok()?;
+        let reason = match code.low_u64() {
+            0x01 => "断言失败",
+            0x11 => "算术溢出",
+            0x12 => "除以零",
+            0x21 => "无效的枚举值",
+            0x31 => "空数组 pop",
+            0x32 => "数组越界",
+            0x41 => "内存分配过大",
+            _ => "未知 Panic",
+        };
+        Some(format!("Panic(0x{:02x}): {}", code, reason))
+    } else {
+        None
+    }
+}
+
+/// 解析 PairCreated 日志
+/// data 为 (address pair, uint256 allPairsLength)，各占 32 字节
+pub fn parse_pair_created_log(log: &Log) -> Option<PairCreatedLog> {
+    if log.topics.len() != 3 || log.data.len() < 64 {
+        return None;
+    }
+
+    Some(PairCreatedLog {
+        factory: log.address,
+        token0: Address::from(log.topics[1]),
+        token1: Address::from(log.topics[2]),
+        pair: Address::from_slice(&log.data[12..32]),
+        block_number: log.block_number?.as_u64(),
+        tx_hash: log.transaction_hash?,
+    })
+}
+
+/// 解析 Swap 日志
+/// data 为 (amount0In, amount1In, amount0Out, amount1Out)，各占 32 字节
+pub fn parse_swap_log(log: &Log) -> Option<SwapLog> {
+    if log.topics.len() != 3 || log.data.len() != 128 {
+        return None;
+    }
+
+    let word = |i: usize| U256::from_big_endian(&log.data[i * 32..(i + 1) * 32]);
+
+    Some(SwapLog {
+        pair: log.address,
+        sender: Address::from(log.topics[1]),
+        to: Address::from(log.topics[2]),
+        amount0_in: word(0),
+        amount1_in: word(1),
+        amount0_out: word(2),
+        amount1_out: word(3),
+        block_number: log.block_number?.as_u64(),
+        log_index: log.log_index.map_or(0, |index| index.as_u64()),
+    })
+}
+
 /// 交换报价结果
 #[derive(Debug, Clone)]
 pub struct SwapQuote {
     pub path: Vec<Address>,
+    pub amount_in: U256,
     pub amount_out: U256,
+    /// 整条路径的价格影响（百分比）
     pub price_impact: f64,
+    /// 每一跳的价格影响（百分比）
+    pub hop_price_impacts: Vec<f64>,
     pub pair_addresses: Vec<Address>, // 🆕 缓存 pair 地址，避免重复查询
+    /// 路由搜索时所有候选路径的报价明细（单路径报价时为空）
+    pub routes: Vec<PathQuote>,
+}
+
+/// 单条候选路径的报价
+#[derive(Debug, Clone)]
+pub struct PathQuote {
+    pub path: Vec<Address>,
+    /// (amount_in, amount_out)，路径不可用时为错误信息
+    pub amounts: Result<(U256, U256), String>,
+}
+
+/// 交换中以原生代币结算的一侧，决定 Router 函数和交易 value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NativeLeg {
+    /// 两侧都是 ERC20
+    #[default]
+    None,
+    /// 支付原生代币（随交易发送 value，Router 包装为 WETH）
+    Input,
+    /// 收到原生代币（Router 解包 WETH 后转给接收方）
+    Output,
+}
+
+/// 按交换模式和原生代币一侧选择的 Router 函数名
+pub fn router_function(exact_output: bool, native: NativeLeg) -> &'static str {
+    match (exact_output, native) {
+        (false, NativeLeg::None) => "swapExactTokensForTokens",
+        (false, NativeLeg::Input) => "swapExactETHForTokens",
+        (false, NativeLeg::Output) => "swapExactTokensForETH",
+        (true, NativeLeg::None) => "swapTokensForExactTokens",
+        (true, NativeLeg::Input) => "swapETHForExactTokens",
+        (true, NativeLeg::Output) => "swapTokensForExactETH",
+    }
+}
+
+/// 转账税代币(fee-on-transfer)使用的 Router 函数名(只有 exact-input 版本)
+pub fn fee_on_transfer_function(native: NativeLeg) -> &'static str {
+    match native {
+        NativeLeg::None => "swapExactTokensForTokensSupportingFeeOnTransferTokens",
+        NativeLeg::Input => "swapExactETHForTokensSupportingFeeOnTransferTokens",
+        NativeLeg::Output => "swapExactTokensForETHSupportingFeeOnTransferTokens",
+    }
+}
+
+/// 回滚原因是否符合输入代币收取转账税的特征
+/// 交易对实际收到的数量少于 Router 按报价计算的数量，swap 的 K 值校验失败
+pub fn is_fee_on_transfer_revert(reason: &str) -> bool {
+    reason.contains("UniswapV2: K")
+}
+
+/// 解码任意 Router 调用
+fn decode_router_call(data: &[u8]) -> Result<i_uniswap_v2_router_02::IUniswapV2Router02Calls, UniswapError> {
+    i_uniswap_v2_router_02::IUniswapV2Router02Calls::decode(data)
+        .map_err(|e| UniswapError::AbiError(format!("解码 calldata 失败: {}", e)))
+}
+
+/// swapExactTokensForTokens 调用参数
+/// function swapExactTokensForTokens(
+///   uint amountIn,
+///   uint amountOutMin,
+///   address[] calldata path,
+///   address to,
+///   uint deadline
+/// ) external returns (uint[] memory amounts);
+/// `native` 为 Input 时编码为 swapExactETHForTokens（amountIn 作为交易 value），为 Output 时编码为 swapExactTokensForETH
+/// `fee_on_transfer` 为 true 时使用对应的 SupportingFeeOnTransferTokens 版本（按实际到账数量校验 amountOutMin）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapCall {
+    pub amount_in: U256,
+    pub amount_out_min: U256,
+    pub path: Vec<Address>,
+    pub to: Address,
+    pub deadline: U256,
+    pub native: NativeLeg,
+    pub fee_on_transfer: bool,
+}
+
+impl SwapCall {
+    /// Router 函数名
+    pub fn function_name(&self) -> &'static str {
+        if self.fee_on_transfer {
+            fee_on_transfer_function(self.native)
+        } else {
+            router_function(false, self.native)
+        }
+    }
+
+    /// 随交易发送的原生代币数量
+    pub fn value(&self) -> U256 {
+        if self.native == NativeLeg::Input {
+            self.amount_in
+        } else {
+            U256::zero()
+        }
+    }
+
+    /// 编码为 Router calldata
+    pub fn encode(&self) -> Vec<u8> {
+        use i_uniswap_v2_router_02 as router;
+
+        let (amount_in, amount_out_min, path, to, deadline) =
+            (self.amount_in, self.amount_out_min, self.path.clone(), self.to, self.deadline);
+        match (self.native, self.fee_on_transfer) {
+            (NativeLeg::None, false) => router::SwapExactTokensForTokensCall {
+                amount_in,
+                amount_out_min,
+                path,
+                to,
+                deadline,
+            }
+            .encode(),
+            (NativeLeg::Input, false) => router::SwapExactETHForTokensCall {
+                amount_out_min,
+                path,
+                to,
+                deadline,
+            }
+            .encode(),
+            (NativeLeg::Output, false) => router::SwapExactTokensForETHCall {
+                amount_in,
+                amount_out_min,
+                path,
+                to,
+                deadline,
+            }
+            .encode(),
+            (NativeLeg::None, true) => router::SwapExactTokensForTokensSupportingFeeOnTransferTokensCall {
+                amount_in,
+                amount_out_min,
+                path,
+                to,
+                deadline,
+            }
+            .encode(),
+            (NativeLeg::Input, true) => router::SwapExactETHForTokensSupportingFeeOnTransferTokensCall {
+                amount_out_min,
+                path,
+                to,
+                deadline,
+            }
+            .encode(),
+            (NativeLeg::Output, true) => router::SwapExactTokensForETHSupportingFeeOnTransferTokensCall {
+                amount_in,
+                amount_out_min,
+                path,
+                to,
+                deadline,
+            }
+            .encode(),
+        }
+    }
+
+    /// 解码 Router calldata（swapExactETHForTokens 的 amountIn 在交易 value 中，解码结果为 0）
+    pub fn decode(data: &[u8]) -> Result<Self, UniswapError> {
+        use i_uniswap_v2_router_02::IUniswapV2Router02Calls as Calls;
+
+        let (amount_in, amount_out_min, path, to, deadline, native, fee_on_transfer) = match decode_router_call(data)? {
+            Calls::SwapExactTokensForTokens(c) => (c.amount_in, c.amount_out_min, c.path, c.to, c.deadline, NativeLeg::None, false),
+            Calls::SwapExactETHForTokens(c) => (U256::zero(), c.amount_out_min, c.path, c.to, c.deadline, NativeLeg::Input, false),
+            Calls::SwapExactTokensForETH(c) => (c.amount_in, c.amount_out_min, c.path, c.to, c.deadline, NativeLeg::Output, false),
+            Calls::SwapExactTokensForTokensSupportingFeeOnTransferTokens(c) => {
+                (c.amount_in, c.amount_out_min, c.path, c.to, c.deadline, NativeLeg::None, true)
+            }
+            Calls::SwapExactETHForTokensSupportingFeeOnTransferTokens(c) => {
+                (U256::zero(), c.amount_out_min, c.path, c.to, c.deadline, NativeLeg::Input, true)
+            }
+            Calls::SwapExactTokensForETHSupportingFeeOnTransferTokens(c) => {
+                (c.amount_in, c.amount_out_min, c.path, c.to, c.deadline, NativeLeg::Output, true)
+            }
+            _ => {
+                return Err(UniswapError::AbiError(
+                    "calldata 不是 exact-input 交换调用".to_string(),
+                ))
+            }
+        };
+
+        Ok(Self {
+            amount_in,
+            amount_out_min,
+            path,
+            to,
+            deadline,
+            native,
+            fee_on_transfer,
+        })
+    }
+
+    /// 解码最终 calldata，逐项核对 Router 函数、amountIn、amountOutMin、path、recipient 和 deadline
+    /// 任何一项与批准的参数不一致时拒绝发送（防御编码错误或篡改）
+    pub fn verify(&self, calldata: &[u8]) -> Result<(), UniswapError> {
+        let decoded = Self::decode(calldata)?;
+
+        let mut mismatches = Vec::new();
+        if decoded.native != self.native || decoded.fee_on_transfer != self.fee_on_transfer {
+            mismatches.push(format!("function {} != {}", decoded.function_name(), self.function_name()));
+        }
+        // 支付原生代币时 amountIn 不在 calldata 中，由 verify_transaction 核对交易 value
+        if self.native != NativeLeg::Input && decoded.amount_in != self.amount_in {
+            mismatches.push(format!("amountIn {} != {}", decoded.amount_in, self.amount_in));
+        }
+        if decoded.amount_out_min != self.amount_out_min {
+            mismatches.push(format!(
+                "amountOutMin {} != {}",
+                decoded.amount_out_min, self.amount_out_min
+            ));
+        }
+        if decoded.path != self.path {
+            mismatches.push(format!("path {:?} != {:?}", decoded.path, self.path));
+        }
+        if decoded.to != self.to {
+            mismatches.push(format!("recipient {:?} != {:?}", decoded.to, self.to));
+        }
+        if decoded.deadline != self.deadline {
+            mismatches.push(format!("deadline {} != {}", decoded.deadline, self.deadline));
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(UniswapError::CalldataMismatch(mismatches.join("; ")))
+        }
+    }
+
+    /// 核对即将广播的最终交易：目标为 Router、value 为原生代币支付数量，calldata 与批准的参数一致
+    /// `self` 应由报价独立构建，而不是用于构建交易的同一个调用
+    pub fn verify_transaction(&self, tx: &TypedTransaction, router: Address) -> Result<(), UniswapError> {
+        if tx.to_addr() != Some(&router) {
+            return Err(UniswapError::CalldataMismatch(format!("to {:?} != {:?}", tx.to_addr(), router)));
+        }
+        let value = tx.value().copied().unwrap_or_default();
+        if value != self.value() {
+            return Err(UniswapError::CalldataMismatch(format!("value {} != {}", value, self.value())));
+        }
+        self.verify(tx.data().map(|data| data.as_ref()).unwrap_or_default())
+    }
+}
+
+/// swapTokensForExactTokens 调用参数
+/// function swapTokensForExactTokens(
+///   uint amountOut,
+///   uint amountInMax,
+///   address[] calldata path,
+///   address to,
+///   uint deadline
+/// ) external returns (uint[] memory amounts);
+/// `native` 为 Input 时编码为 swapETHForExactTokens（amountInMax 作为交易 value，多余部分由 Router 退回），为 Output 时编码为 swapTokensForExactETH
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExactOutputSwapCall {
+    pub amount_out: U256,
+    pub amount_in_max: U256,
+    pub path: Vec<Address>,
+    pub to: Address,
+    pub deadline: U256,
+    pub native: NativeLeg,
+}
+
+impl ExactOutputSwapCall {
+    /// 随交易发送的原生代币数量
+    pub fn value(&self) -> U256 {
+        if self.native == NativeLeg::Input {
+            self.amount_in_max
+        } else {
+            U256::zero()
+        }
+    }
+
+    /// 编码为 Router calldata
+    pub fn encode(&self) -> Vec<u8> {
+        let (amount_out, amount_in_max, path, to, deadline) =
+            (self.amount_out, self.amount_in_max, self.path.clone(), self.to, self.deadline);
+        match self.native {
+            NativeLeg::None => i_uniswap_v2_router_02::SwapTokensForExactTokensCall {
+                amount_out,
+                amount_in_max,
+                path,
+                to,
+                deadline,
+            }
+            .encode(),
+            NativeLeg::Input => i_uniswap_v2_router_02::SwapETHForExactTokensCall {
+                amount_out,
+                path,
+                to,
+                deadline,
+            }
+            .encode(),
+            NativeLeg::Output => i_uniswap_v2_router_02::SwapTokensForExactETHCall {
+                amount_out,
+                amount_in_max,
+                path,
+                to,
+                deadline,
+            }
+            .encode(),
+        }
+    }
+
+    /// 解码 Router calldata（swapETHForExactTokens 的 amountInMax 在交易 value 中，解码结果为 0）
+    #[cfg(test)]
+    pub fn decode(data: &[u8]) -> Result<Self, UniswapError> {
+        use i_uniswap_v2_router_02::IUniswapV2Router02Calls as Calls;
+
+        match decode_router_call(data)? {
+            Calls::SwapTokensForExactTokens(call) => Ok(Self {
+                amount_out: call.amount_out,
+                amount_in_max: call.amount_in_max,
+                path: call.path,
+                to: call.to,
+                deadline: call.deadline,
+                native: NativeLeg::None,
+            }),
+            Calls::SwapETHForExactTokens(call) => Ok(Self {
+                amount_out: call.amount_out,
+                amount_in_max: U256::zero(),
+                path: call.path,
+                to: call.to,
+                deadline: call.deadline,
+                native: NativeLeg::Input,
+            }),
+            Calls::SwapTokensForExactETH(call) => Ok(Self {
+                amount_out: call.amount_out,
+                amount_in_max: call.amount_in_max,
+                path: call.path,
+                to: call.to,
+                deadline: call.deadline,
+                native: NativeLeg::Output,
+            }),
+            _ => Err(UniswapError::AbiError(
+                "calldata 不是 exact-output 交换调用".to_string(),
+            )),
+        }
+    }
+
+}
+
+/// addLiquidity 调用参数
+/// function addLiquidity(
+///   address tokenA,
+///   address tokenB,
+///   uint amountADesired,
+///   uint amountBDesired,
+///   uint amountAMin,
+///   uint amountBMin,
+///   address to,
+///   uint deadline
+/// ) external returns (uint amountA, uint amountB, uint liquidity);
+/// `native` 为 Input 时 token_a 为原生代币、为 Output 时 token_b 为原生代币，
+/// 编码为 addLiquidityETH（原生代币一侧的期望数量作为交易 value）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddLiquidityCall {
+    pub token_a: Address,
+    pub token_b: Address,
+    pub amount_a_desired: U256,
+    pub amount_b_desired: U256,
+    pub amount_a_min: U256,
+    pub amount_b_min: U256,
+    pub to: Address,
+    pub deadline: U256,
+    pub native: NativeLeg,
+}
+
+impl AddLiquidityCall {
+    /// Router 函数名
+    pub fn function_name(&self) -> &'static str {
+        match self.native {
+            NativeLeg::None => "addLiquidity",
+            NativeLeg::Input | NativeLeg::Output => "addLiquidityETH",
+        }
+    }
+
+    /// 随交易发送的原生代币数量
+    pub fn value(&self) -> U256 {
+        match self.native {
+            NativeLeg::None => U256::zero(),
+            NativeLeg::Input => self.amount_a_desired,
+            NativeLeg::Output => self.amount_b_desired,
+        }
+    }
+
+    /// 编码为 Router calldata
+    pub fn encode(&self) -> Vec<u8> {
+        use i_uniswap_v2_router_02 as router;
+
+        let eth_call = |token, amount_token_desired, amount_token_min, amount_eth_min| {
+            router::AddLiquidityETHCall {
+                token,
+                amount_token_desired,
+                amount_token_min,
+                amount_eth_min,
+                to: self.to,
+                deadline: self.deadline,
+            }
+            .encode()
+        };
+        match self.native {
+            NativeLeg::None => router::AddLiquidityCall {
+                token_a: self.token_a,
+                token_b: self.token_b,
+                amount_a_desired: self.amount_a_desired,
+                amount_b_desired: self.amount_b_desired,
+                amount_a_min: self.amount_a_min,
+                amount_b_min: self.amount_b_min,
+                to: self.to,
+                deadline: self.deadline,
+            }
+            .encode(),
+            NativeLeg::Input => eth_call(self.token_b, self.amount_b_desired, self.amount_b_min, self.amount_a_min),
+            NativeLeg::Output => eth_call(self.token_a, self.amount_a_desired, self.amount_a_min, self.amount_b_min),
+        }
+    }
+
+    /// 解码 Router 返回值为 (amountA, amountB, liquidity)
+    pub fn decode_output(&self, data: &[u8]) -> Result<(U256, U256, U256), UniswapError> {
+        use i_uniswap_v2_router_02 as router;
+
+        match self.native {
+            NativeLeg::None => {
+                let output = decode_return::<router::AddLiquidityReturn>(data)?;
+                Ok((output.amount_a, output.amount_b, output.liquidity))
+            }
+            NativeLeg::Input => {
+                let output = decode_return::<router::AddLiquidityETHReturn>(data)?;
+                Ok((output.amount_eth, output.amount_token, output.liquidity))
+            }
+            NativeLeg::Output => {
+                let output = decode_return::<router::AddLiquidityETHReturn>(data)?;
+                Ok((output.amount_token, output.amount_eth, output.liquidity))
+            }
+        }
+    }
+}
+
+/// 添加流动性的模拟结果
+#[derive(Debug, Clone)]
+pub struct AddLiquiditySimulation {
+    /// Router 返回的 (amountA, amountB, liquidity)
+    pub deposited: Option<(U256, U256, U256)>,
+    pub gas_estimate: Option<U256>,
+    pub simulation_success: bool,
+    pub revert_reason: Option<String>,
+}
+
+/// 首次添加流动性时永久锁定的 LP 数量（UniswapV2Pair.MINIMUM_LIQUIDITY）
+pub const MINIMUM_LIQUIDITY: u64 = 1000;
+
+/// 按当前储备量计算 addLiquidity 实际存入的数量（同 UniswapV2Router02._addLiquidity）
+/// 新交易对（储备量为 0）按期望数量全部存入；否则以一侧的期望数量为准，另一侧按储备量比例配对
+pub fn optimal_liquidity_amounts(
+    amount_a_desired: U256,
+    amount_b_desired: U256,
+    reserve_a: U256,
+    reserve_b: U256,
+) -> Result<(U256, U256), UniswapError> {
+    if amount_a_desired.is_zero() || amount_b_desired.is_zero() {
+        return Err(UniswapError::InvalidAmount);
+    }
+    if reserve_a.is_zero() && reserve_b.is_zero() {
+        return Ok((amount_a_desired, amount_b_desired));
+    }
+    if reserve_a.is_zero() || reserve_b.is_zero() {
+        return Err(UniswapError::InsufficientLiquidity);
+    }
+
+    let amount_b_optimal = mul_div(amount_a_desired, reserve_b, reserve_a)?;
+    if amount_b_optimal <= amount_b_desired {
+        return Ok((amount_a_desired, amount_b_optimal));
+    }
+    let amount_a_optimal = mul_div(amount_b_desired, reserve_a, reserve_b)?;
+    Ok((amount_a_optimal, amount_b_desired))
+}
+
+/// 存入后铸造的 LP 数量（同 UniswapV2Pair.mint，不含 feeOn 时为协议增发的部分）
+/// 新交易对为 sqrt(amountA × amountB) - MINIMUM_LIQUIDITY，否则按两侧占储备量比例的较小值计算
+pub fn liquidity_minted(
+    amount_a: U256,
+    amount_b: U256,
+    reserve_a: U256,
+    reserve_b: U256,
+    total_supply: U256,
+) -> Result<U256, UniswapError> {
+    let liquidity = if total_supply.is_zero() {
+        let root = amount_a.full_mul(amount_b).integer_sqrt();
+        let root = U256::try_from(root).map_err(|_| UniswapError::InvalidAmount)?;
+        root.saturating_sub(U256::from(MINIMUM_LIQUIDITY))
+    } else {
+        if reserve_a.is_zero() || reserve_b.is_zero() {
+            return Err(UniswapError::InsufficientLiquidity);
+        }
+        mul_div(amount_a, total_supply, reserve_a)?.min(mul_div(amount_b, total_supply, reserve_b)?)
+    };
+
+    // Pair 合约在铸造数量为 0 时以 INSUFFICIENT_LIQUIDITY_MINTED 回滚
+    if liquidity.is_zero() {
+        return Err(UniswapError::InsufficientLiquidity);
+    }
+    Ok(liquidity)
+}
+
+/// a × b / c，中间结果使用 512 位避免溢出
+fn mul_div(a: U256, b: U256, c: U256) -> Result<U256, UniswapError> {
+    U256::try_from(a.full_mul(b) / U512::from(c)).map_err(|_| UniswapError::InvalidAmount)
 }
 
 /// 交易模拟结果
@@ -465,13 +1731,285 @@ pub struct SwapSimulation {
     pub revert_reason: Option<String>,
 }
 
+/// 交易对在某一区块的累计价格（UQ112x112 定点数价格对时间的累加）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CumulativePrices {
+    pub price0_cumulative_last: U256,
+    pub price1_cumulative_last: U256,
+    pub reserve0: U256,
+    pub reserve1: U256,
+    /// 累计价格最后更新时的区块时间戳（mod 2^32）
+    pub block_timestamp_last: u32,
+}
+
+impl CumulativePrices {
+    /// 把累计价格推算到 `timestamp`（同 UniswapV2OracleLibrary.currentCumulativePrices）
+    /// 交易对只在有交易的区块更新累计值，之后的时间按当前储备量补上
+    pub fn at_timestamp(&self, timestamp: u64) -> (U256, U256) {
+        let elapsed = (timestamp as u32).wrapping_sub(self.block_timestamp_last);
+        if elapsed == 0 || self.reserve0.is_zero() || self.reserve1.is_zero() {
+            return (self.price0_cumulative_last, self.price1_cumulative_last);
+        }
+
+        let elapsed = U256::from(elapsed);
+        let price0 = (self.reserve1 << 112) / self.reserve0;
+        let price1 = (self.reserve0 << 112) / self.reserve1;
+        (
+            self.price0_cumulative_last
+                .overflowing_add(price0.overflowing_mul(elapsed).0)
+                .0,
+            self.price1_cumulative_last
+                .overflowing_add(price1.overflowing_mul(elapsed).0)
+                .0,
+        )
+    }
+}
+
+/// 由两个时间点的累计价格计算时间加权平均价格（UQ112x112）
+/// 累计值按 2^256 回绕，与合约的溢出语义一致；时间差为 0 时返回 None
+pub fn time_weighted_average(start: U256, end: U256, elapsed_seconds: u64) -> Option<U256> {
+    if elapsed_seconds == 0 {
+        return None;
+    }
+    Some(end.overflowing_sub(start).0 / U256::from(elapsed_seconds))
+}
+
+/// 按 CREATE2 规则计算 V2 交易对地址（init code hash 因场所而异）
+/// salt = keccak256(token0 ++ token1)，token0 为地址较小的代币
+pub fn compute_pair_address(factory: Address, init_code_hash: H256, token_a: Address, token_b: Address) -> Address {
+    let (token0, token1) = if token_a < token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    };
+
+    let mut packed = Vec::with_capacity(40);
+    packed.extend_from_slice(token0.as_bytes());
+    packed.extend_from_slice(token1.as_bytes());
+    let salt = ethers::utils::keccak256(packed);
+
+    ethers::utils::get_create2_address_from_hash(factory, salt, init_code_hash)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chains::{MAINNET, POLYGON};
+
+    #[test]
+    fn test_reserve_cache_ttl() {
+        let pair = Address::repeat_byte(0x01);
+        let reserves = (U256::from(100u64), U256::from(200u64));
+        let latest = None;
+        let pinned = Some(BlockId::Number(BlockNumber::Number(19_000_000u64.into())));
+
+        let cache = ReserveCache::new(Duration::from_secs(60));
+        assert!(cache.enabled());
+        assert_eq!(cache.get(pair, latest), None);
+        cache.insert(pair, latest, reserves);
+        assert_eq!(cache.get(pair, latest), Some(reserves));
+        // 不同区块分别缓存
+        assert_eq!(cache.get(pair, pinned), None);
+
+        // 过期后不再命中
+        let cache = ReserveCache::new(Duration::from_millis(1));
+        cache.insert(pair, latest, reserves);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.get(pair, latest), None);
+
+        // 新区块只清除最新区块的缓存
+        let cache = ReserveCache::new(Duration::from_secs(60));
+        cache.insert(pair, latest, reserves);
+        cache.insert(pair, pinned, reserves);
+        cache.invalidate_latest();
+        assert_eq!(cache.get(pair, latest), None);
+        assert_eq!(cache.get(pair, pinned), Some(reserves));
+
+        assert!(!ReserveCache::default().enabled());
+        let client = UniswapV2Client::new(None, &MAINNET);
+        assert!(!client.reserve_cache.enabled());
+        let client = client.with_reserve_cache_ttl(Duration::from_secs(60));
+        assert!(client.reserve_cache.enabled());
+        assert!(client.bypassing_cache().bypass_cache);
+        assert!(!client.bypass_cache);
+    }
+
+    #[test]
+    fn test_compute_pair_address() {
+        let client = UniswapV2Client::new(None, &MAINNET);
+        let usdc: Address = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".parse().unwrap();
+        let weth: Address = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".parse().unwrap();
+        let expected: Address = "0xB4e16d0168e52d35CaCD2c6185b44281Ec28C9Dc".parse().unwrap();
+
+        // USDC/WETH 主网交易对，与代币顺序无关
+        assert_eq!(client.pair_address(usdc, weth), expected);
+        assert_eq!(client.pair_address(weth, usdc), expected);
+
+        // SushiSwap 没有内置 init code hash，交易对通过 getPair 查询
+        let sushi = client.with_venue(&MAINNET.sushiswap.unwrap());
+        assert_eq!(sushi.pair_address(usdc, weth), Address::zero());
+        assert_eq!(sushi.venue_id(), "sushiswap");
+        assert_eq!(sushi.router_address(), "0xd9e1cE17f2641f24aE83637ab66a2cca9C378B9F".parse::<Address>().unwrap());
+        assert!(Arc::ptr_eq(&sushi.reserve_cache, &client.reserve_cache));
+        assert_eq!(client.venue_id(), "uniswap_v2");
+    }
+
+    #[test]
+    fn test_candidate_paths() {
+        let usdc = MAINNET.usdc_address();
+        let dai = Address::repeat_byte(0xda);
+        let token = Address::repeat_byte(0x11);
+        let client = UniswapV2Client::new(None, &MAINNET).with_route_intermediates(vec![usdc, dai]);
+        let weth = client.weth_address();
+
+        // 默认路径在前，重复路径只保留一次
+        assert_eq!(
+            client.candidate_paths(token, usdc),
+            vec![vec![token, weth, usdc], vec![token, usdc], vec![token, dai, usdc]]
+        );
+        assert_eq!(
+            client.candidate_paths(weth, token),
+            vec![vec![weth, token], vec![weth, usdc, token], vec![weth, dai, token]]
+        );
+    }
+
+    #[test]
+    fn test_select_best_route() {
+        let client = UniswapV2Client::new(None, &MAINNET);
+        let (a, b, c) = (Address::repeat_byte(0x01), Address::repeat_byte(0x02), Address::repeat_byte(0x03));
+        let quote = |path: Vec<Address>, reserves: Vec<(u64, u64)>, exact_output: bool| {
+            let reserves = reserves.into_iter().map(|(x, y)| (U256::from(x), U256::from(y))).collect();
+            let pairs = vec![Address::zero(); path.len() - 1];
+            let result = if exact_output {
+                client.quote_exact_output_from_reserves(path.clone(), reserves, pairs, U256::from(1_000u64))
+            } else {
+                client.quote_from_reserves(path.clone(), reserves, pairs, U256::from(1_000u64))
+            };
+            (path, result.map_err(|e| e.to_string()))
+        };
+
+        for exact_output in [false, true] {
+            let results = vec![
+                quote(vec![a, c], vec![(1_000_000, 1_000_000)], exact_output),
+                quote(vec![a, b, c], vec![(1_000_000, 2_000_000), (2_000_000, 1_000_000)], exact_output),
+                (vec![a, c, b], Err("没有交易对".to_string())),
+                quote(vec![a, b], vec![(1_000_000, 1_200_000)], exact_output),
+            ];
+            let best = select_best_route(results, exact_output).unwrap();
+            assert_eq!(best.path, vec![a, b]);
+            assert_eq!(best.routes.len(), 4);
+            assert_eq!(best.routes[2].amounts, Err("没有交易对".to_string()));
+        }
+
+        // 全部失败时返回默认路径的错误
+        let failed: Vec<(Vec<Address>, Result<SwapQuote, String>)> =
+            vec![(vec![a, c], Err("first".to_string())), (vec![a, b, c], Err("second".to_string()))];
+        assert_eq!(select_best_route(failed, false).unwrap_err(), "first");
+    }
+
+    #[test]
+    fn test_swap_call_encode_decode() {
+        let call = SwapCall {
+            amount_in: U256::exp10(18),
+            amount_out_min: U256::from(2_900_000_000u64),
+            path: vec![Address::repeat_byte(0x11), Address::repeat_byte(0x22)],
+            to: Address::repeat_byte(0x33),
+            deadline: U256::MAX,
+            native: NativeLeg::None,
+            fee_on_transfer: false,
+        };
+        let data = call.encode();
+
+        // selector + 5 个参数头 + path 长度 + 2 个地址
+        assert_eq!(&data[..4], &[0x38, 0xed, 0x17, 0x39]);
+        assert_eq!(data.len(), 4 + 32 * 8);
+        // path 偏移量为 0xa0
+        assert_eq!(data[4 + 32 * 3 - 1], 0xa0);
+
+        assert_eq!(SwapCall::decode(&data).unwrap(), call);
+        assert!(call.verify(&data).is_ok());
+    }
+
+    #[test]
+    fn test_swap_call_verify_detects_tampering() {
+        let call = SwapCall {
+            amount_in: U256::exp10(18),
+            amount_out_min: U256::from(2_900_000_000u64),
+            path: vec![Address::repeat_byte(0x11), Address::repeat_byte(0x22)],
+            to: Address::repeat_byte(0x33),
+            deadline: U256::from(1_700_000_000u64),
+            native: NativeLeg::None,
+            fee_on_transfer: false,
+        };
+
+        // recipient 被替换
+        let tampered = SwapCall {
+            to: Address::repeat_byte(0x66),
+            ..call.clone()
+        };
+        let err = call.verify(&tampered.encode()).unwrap_err();
+        assert!(matches!(err, UniswapError::CalldataMismatch(ref m) if m.contains("recipient")));
+
+        // amountOutMin 被改为 0
+        let tampered = SwapCall {
+            amount_out_min: U256::zero(),
+            ..call.clone()
+        };
+        assert!(matches!(call.verify(&tampered.encode()), Err(UniswapError::CalldataMismatch(_))));
+
+        // 截断或非 swap 调用无法解码
+        let data = call.encode();
+        assert!(matches!(call.verify(&data[..100]), Err(UniswapError::AbiError(_))));
+        assert!(matches!(call.verify(&[0x09, 0x5e, 0xa7, 0xb3]), Err(UniswapError::AbiError(_))));
+    }
+
+    #[test]
+    fn test_swap_call_verify_transaction() {
+        let client = UniswapV2Client::new(None, &MAINNET);
+        let router = client.router_address();
+        let weth = MAINNET.wrapped_native_address();
+        let owner = Address::repeat_byte(0x33);
+        let approved = SwapCall {
+            amount_in: U256::exp10(18),
+            amount_out_min: U256::from(2_900_000_000u64),
+            path: vec![weth, Address::repeat_byte(0x22)],
+            to: owner,
+            deadline: U256::from(1_700_000_000u64),
+            native: NativeLeg::Input,
+            fee_on_transfer: false,
+        };
+        let tx = client.swap_transaction(&approved, owner, TxType::Eip1559);
+        assert!(approved.verify_transaction(&tx, router).is_ok());
+
+        // 构建交易后 amountOutMin 被改动
+        let loosened = SwapCall {
+            amount_out_min: U256::zero(),
+            ..approved.clone()
+        };
+        let tx = client.swap_transaction(&loosened, owner, TxType::Eip1559);
+        assert!(matches!(
+            approved.verify_transaction(&tx, router),
+            Err(UniswapError::CalldataMismatch(ref m)) if m.contains("amountOutMin")
+        ));
+
+        // 目标地址或 value 被替换
+        let mut tx = client.swap_transaction(&approved, owner, TxType::Eip1559);
+        tx.set_to(Address::repeat_byte(0x66));
+        assert!(matches!(
+            approved.verify_transaction(&tx, router),
+            Err(UniswapError::CalldataMismatch(ref m)) if m.starts_with("to ")
+        ));
+        let mut tx = client.swap_transaction(&approved, owner, TxType::Eip1559);
+        tx.set_value(U256::exp10(19));
+        assert!(matches!(
+            approved.verify_transaction(&tx, router),
+            Err(UniswapError::CalldataMismatch(ref m)) if m.starts_with("value ")
+        ));
+    }
 
     #[test]
     fn test_calculate_amount_out() {
-        let client = UniswapV2Client::new(None);
+        let client = UniswapV2Client::new(None, &MAINNET);
 
         // 示例：1 ETH 换 USDC
         // reserve_in = 100 ETH, reserve_out = 200000 USDC
@@ -494,20 +2032,43 @@ mod tests {
 
     #[test]
     fn test_calculate_price_impact() {
-        let client = UniswapV2Client::new(None);
+        let client = UniswapV2Client::new(None, &MAINNET);
 
-        // 1 ETH in 100 ETH reserve = 1% impact
-        let amount_in = U256::from(1_000_000_000_000_000_000u64);
-        let reserve_in = U256::from(100u64) * U256::exp10(18);
+        // 1 ETH 卖入 100 ETH / 200000 USDC 的池子:中间价 2000,成交价 1974.32
+        // 价格影响 = 1 - 1974.32 / 2000 ≈ 1.284%(包含 0.3% 手续费)
+        let reserves = vec![(U256::from(100u64) * U256::exp10(18), U256::from(200_000u64) * U256::exp10(6))];
+        let amounts = client.calculate_amounts_out(U256::exp10(18), &reserves).unwrap();
+        let (impact, per_hop) = client.calculate_price_impact(&amounts, &reserves).unwrap();
 
-        let impact = client.calculate_price_impact(amount_in, reserve_in).unwrap();
+        assert!((impact - 1.284).abs() < 0.001);
+        assert_eq!(per_hop.len(), 1);
+        assert!((per_hop[0] - impact).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_calculate_price_impact_multi_hop() {
+        let client = UniswapV2Client::new(None, &MAINNET);
+
+        // 第二跳池子较浅,总价格影响按各跳成交价/中间价之积计算,而不只看第一个池子
+        let reserves = vec![
+            (U256::from(1_000_000u64) * U256::exp10(18), U256::from(500u64) * U256::exp10(18)),
+            (U256::from(10u64) * U256::exp10(18), U256::from(20_000u64) * U256::exp10(6)),
+        ];
+        let amounts = client.calculate_amounts_out(U256::from(2_000u64) * U256::exp10(18), &reserves).unwrap();
+        let (impact, per_hop) = client.calculate_price_impact(&amounts, &reserves).unwrap();
 
-        assert!((impact - 1.0).abs() < 0.001); // 约 1%
+        assert_eq!(per_hop.len(), 2);
+        assert!(per_hop[1] > per_hop[0]);
+        let expected = (1.0 - (1.0 - per_hop[0] / 100.0) * (1.0 - per_hop[1] / 100.0)) * 100.0;
+        assert!((impact - expected).abs() < 1e-9);
+        assert!(impact > per_hop[1]);
+
+        assert!(client.calculate_price_impact(&amounts[..2], &reserves).is_err());
     }
 
     #[test]
     fn test_calculate_amount_out_with_fee() {
-        let client = UniswapV2Client::new(None);
+        let client = UniswapV2Client::new(None, &MAINNET);
 
         // 测试 0.3% 手续费
         let amount_in = U256::from(1000);
@@ -525,7 +2086,7 @@ mod tests {
 
     #[test]
     fn test_calculate_amount_out_zero_amount() {
-        let client = UniswapV2Client::new(None);
+        let client = UniswapV2Client::new(None, &MAINNET);
 
         let result = client.calculate_amount_out(
             U256::zero(),
@@ -539,7 +2100,7 @@ mod tests {
 
     #[test]
     fn test_calculate_amount_out_zero_reserves() {
-        let client = UniswapV2Client::new(None);
+        let client = UniswapV2Client::new(None, &MAINNET);
 
         let result = client.calculate_amount_out(
             U256::from(100),
@@ -556,7 +2117,7 @@ mod tests {
 
     #[test]
     fn test_calculate_amounts_out_multi_hop() {
-        let client = UniswapV2Client::new(None);
+        let client = UniswapV2Client::new(None, &MAINNET);
 
         // 两跳交换：Token A -> WETH -> Token B
         let amount_in = U256::from(1000);
@@ -577,9 +2138,267 @@ mod tests {
         assert_eq!(amounts[2], U256::from(1656));
     }
 
+    #[test]
+    fn test_calculate_amount_in_inverts_amount_out() {
+        let client = UniswapV2Client::new(None, &MAINNET);
+
+        // 与 test_calculate_amount_out_with_fee 相同的池子：得到 906 需要输入 1000
+        let reserve = U256::from(10000);
+        let amount_in = client.calculate_amount_in(U256::from(906), reserve, reserve).unwrap();
+        assert_eq!(amount_in, U256::from(1000));
+        assert!(client.calculate_amount_out(amount_in, reserve, reserve).unwrap() >= U256::from(906));
+
+        // 输出达到储备量或为 0 时报错
+        assert!(matches!(
+            client.calculate_amount_in(reserve, reserve, reserve),
+            Err(UniswapError::InsufficientLiquidity)
+        ));
+        assert!(matches!(
+            client.calculate_amount_in(U256::zero(), reserve, reserve),
+            Err(UniswapError::InvalidAmount)
+        ));
+    }
+
+    #[test]
+    fn test_calculate_amounts_in_multi_hop() {
+        let client = UniswapV2Client::new(None, &MAINNET);
+
+        let reserves = vec![
+            (U256::from(10000), U256::from(5000)),  // A -> WETH
+            (U256::from(5000), U256::from(20000)),  // WETH -> B
+        ];
+
+        let amounts = client.calculate_amounts_in(U256::from(1656), &reserves).unwrap();
+        assert_eq!(amounts.len(), 3);
+        assert_eq!(*amounts.last().unwrap(), U256::from(1656));
+
+        // 按计算出的输入正向交换至少得到目标输出
+        let forward = client.calculate_amounts_out(amounts[0], &reserves).unwrap();
+        assert!(forward[2] >= U256::from(1656));
+        assert!(amounts[0] <= U256::from(1000));
+    }
+
+    #[test]
+    fn test_exact_output_swap_call_encode_decode() {
+        let call = ExactOutputSwapCall {
+            amount_out: U256::from(3_000_000_000u64),
+            amount_in_max: U256::exp10(18),
+            path: vec![Address::repeat_byte(0x11), Address::repeat_byte(0x22)],
+            to: Address::repeat_byte(0x33),
+            deadline: U256::MAX,
+            native: NativeLeg::None,
+        };
+        let data = call.encode();
+
+        assert_eq!(&data[..4], &[0x88, 0x03, 0xdb, 0xee]);
+        assert_eq!(ExactOutputSwapCall::decode(&data).unwrap(), call);
+
+        // exact-input 的 calldata 不能按 exact-output 解码
+        let exact_input = SwapCall {
+            amount_in: U256::exp10(18),
+            amount_out_min: U256::zero(),
+            path: call.path.clone(),
+            to: call.to,
+            deadline: U256::MAX,
+            native: NativeLeg::None,
+            fee_on_transfer: false,
+        };
+        assert!(matches!(ExactOutputSwapCall::decode(&exact_input.encode()), Err(UniswapError::AbiError(_))));
+    }
+
+    #[test]
+    fn test_optimal_liquidity_amounts() {
+        let (reserve_a, reserve_b) = (U256::from(1000), U256::from(2000));
+
+        // 按 token_a 配对
+        assert_eq!(
+            optimal_liquidity_amounts(U256::from(10), U256::MAX, reserve_a, reserve_b).unwrap(),
+            (U256::from(10), U256::from(20))
+        );
+        // token_b 不足时改按 token_b 配对
+        assert_eq!(
+            optimal_liquidity_amounts(U256::from(10), U256::from(15), reserve_a, reserve_b).unwrap(),
+            (U256::from(7), U256::from(15))
+        );
+        // 新交易对按期望数量全部存入
+        assert_eq!(
+            optimal_liquidity_amounts(U256::from(10), U256::from(15), U256::zero(), U256::zero()).unwrap(),
+            (U256::from(10), U256::from(15))
+        );
+        assert!(matches!(
+            optimal_liquidity_amounts(U256::zero(), U256::one(), reserve_a, reserve_b),
+            Err(UniswapError::InvalidAmount)
+        ));
+    }
+
+    #[test]
+    fn test_liquidity_minted() {
+        // 首次存入:sqrt(4e18 × 1e18) - 1000
+        let minted = liquidity_minted(U256::exp10(18) * 4, U256::exp10(18), U256::zero(), U256::zero(), U256::zero()).unwrap();
+        assert_eq!(minted, U256::exp10(18) * 2 - U256::from(MINIMUM_LIQUIDITY));
+
+        // 按两侧比例的较小值铸造
+        let minted =
+            liquidity_minted(U256::from(10), U256::from(30), U256::from(100), U256::from(200), U256::from(1000)).unwrap();
+        assert_eq!(minted, U256::from(100));
+
+        assert!(matches!(
+            liquidity_minted(U256::from(10), U256::from(10), U256::zero(), U256::zero(), U256::zero()),
+            Err(UniswapError::InsufficientLiquidity)
+        ));
+    }
+
+    #[test]
+    fn test_add_liquidity_call_encode() {
+        let token = Address::repeat_byte(0x11);
+        let weth = Address::repeat_byte(0x22);
+        let call = AddLiquidityCall {
+            token_a: token,
+            token_b: weth,
+            amount_a_desired: U256::from(100),
+            amount_b_desired: U256::from(200),
+            amount_a_min: U256::from(99),
+            amount_b_min: U256::from(198),
+            to: Address::repeat_byte(0x33),
+            deadline: U256::MAX,
+            native: NativeLeg::None,
+        };
+        assert_eq!(call.function_name(), "addLiquidity");
+        assert_eq!(&call.encode()[..4], &[0xe8, 0xe3, 0x37, 0x00]);
+        assert!(call.value().is_zero());
+
+        // token_b 为原生代币时使用 addLiquidityETH,原生代币数量作为 value
+        let eth_call = AddLiquidityCall { native: NativeLeg::Output, ..call.clone() };
+        assert_eq!(eth_call.function_name(), "addLiquidityETH");
+        assert_eq!(eth_call.value(), U256::from(200));
+        let decoded = i_uniswap_v2_router_02::AddLiquidityETHCall::decode(eth_call.encode()).unwrap();
+        assert_eq!(decoded.token, token);
+        assert_eq!(decoded.amount_token_min, U256::from(99));
+        assert_eq!(decoded.amount_eth_min, U256::from(198));
+
+        // 返回值按 token_a/token_b 顺序解码
+        let output = (U256::from(100), U256::from(200), U256::from(5)).encode();
+        assert_eq!(eth_call.decode_output(&output).unwrap(), (U256::from(100), U256::from(200), U256::from(5)));
+        let native_a = AddLiquidityCall { native: NativeLeg::Input, ..call };
+        assert_eq!(native_a.decode_output(&output).unwrap(), (U256::from(200), U256::from(100), U256::from(5)));
+    }
+
+    #[test]
+    fn test_native_swap_calls() {
+        let client = UniswapV2Client::new(None, &MAINNET);
+        let weth = MAINNET.wrapped_native_address();
+        let token = Address::repeat_byte(0x22);
+        let from = Address::repeat_byte(0x33);
+
+        // ETH -> token: swapExactETHForTokens，amountIn 作为 value 发送
+        let call = SwapCall {
+            amount_in: U256::exp10(18),
+            amount_out_min: U256::from(2_900_000_000u64),
+            path: vec![weth, token],
+            to: from,
+            deadline: U256::MAX,
+            native: NativeLeg::Input,
+            fee_on_transfer: false,
+        };
+        let data = call.encode();
+        assert_eq!(&data[..4], &[0x7f, 0xf3, 0x6a, 0xb5]);
+        assert!(call.verify(&data).is_ok());
+        assert_eq!(call.function_name(), "swapExactETHForTokens");
+
+        let tx = client.swap_transaction(&call, from, TxType::Eip1559);
+        assert_eq!(tx.value(), Some(&U256::exp10(18)));
+
+        // 改为 ERC20 版本的 calldata 被识别为函数不一致
+        let tokens = SwapCall {
+            native: NativeLeg::None,
+            ..call.clone()
+        };
+        assert!(matches!(call.verify(&tokens.encode()), Err(UniswapError::CalldataMismatch(ref m)) if m.contains("function")));
+
+        // token -> ETH: swapExactTokensForETH，不发送 value
+        let call = SwapCall {
+            path: vec![token, weth],
+            native: NativeLeg::Output,
+            ..call
+        };
+        assert_eq!(&call.encode()[..4], &[0x18, 0xcb, 0xaf, 0xe5]);
+        assert_eq!(SwapCall::decode(&call.encode()).unwrap(), call);
+        let tx = client.swap_transaction(&call, from, TxType::Eip1559);
+        assert!(tx.value().is_none());
+
+        // ETH -> 指定数量 token: swapETHForExactTokens，最大输入作为 value 发送
+        let call = ExactOutputSwapCall {
+            amount_out: U256::from(3_000_000_000u64),
+            amount_in_max: U256::exp10(18),
+            path: vec![weth, token],
+            to: from,
+            deadline: U256::MAX,
+            native: NativeLeg::Input,
+        };
+        assert_eq!(&call.encode()[..4], &[0xfb, 0x3b, 0xdb, 0x41]);
+        let decoded = ExactOutputSwapCall::decode(&call.encode()).unwrap();
+        assert_eq!(decoded, ExactOutputSwapCall { amount_in_max: U256::zero(), ..call.clone() });
+        let tx = client.exact_output_swap_transaction(&call, from, TxType::Legacy);
+        assert_eq!(tx.value(), Some(&U256::exp10(18)));
+
+        let call = ExactOutputSwapCall {
+            native: NativeLeg::Output,
+            ..call
+        };
+        assert_eq!(router_function(true, call.native), "swapTokensForExactETH");
+        assert_eq!(ExactOutputSwapCall::decode(&call.encode()).unwrap(), call);
+    }
+
+    #[test]
+    fn test_fee_on_transfer_swap_call() {
+        let call = SwapCall {
+            amount_in: U256::exp10(18),
+            amount_out_min: U256::from(2_900_000_000u64),
+            path: vec![Address::repeat_byte(0x11), Address::repeat_byte(0x22)],
+            to: Address::repeat_byte(0x33),
+            deadline: U256::MAX,
+            native: NativeLeg::None,
+            fee_on_transfer: true,
+        };
+        let data = call.encode();
+        assert_eq!(&data[..4], &[0x5c, 0x11, 0xd7, 0x95]);
+        assert_eq!(SwapCall::decode(&data).unwrap(), call);
+        assert_eq!(call.function_name(), "swapExactTokensForTokensSupportingFeeOnTransferTokens");
+
+        // 标准版本的 calldata 与批准的转账税版本不一致
+        let standard = SwapCall {
+            fee_on_transfer: false,
+            ..call.clone()
+        };
+        assert!(matches!(call.verify(&standard.encode()), Err(UniswapError::CalldataMismatch(ref m)) if m.contains("function")));
+
+        let eth_in = SwapCall {
+            native: NativeLeg::Input,
+            ..call.clone()
+        };
+        assert_eq!(&eth_in.encode()[..4], &[0xb6, 0xf9, 0xde, 0x95]);
+        assert!(eth_in.verify(&eth_in.encode()).is_ok());
+
+        assert!(is_fee_on_transfer_revert("execution reverted: UniswapV2: K"));
+        assert!(!is_fee_on_transfer_revert("execution reverted: UniswapV2Router: INSUFFICIENT_OUTPUT_AMOUNT"));
+    }
+
+    #[test]
+    fn test_native_leg() {
+        let client = UniswapV2Client::new(None, &POLYGON);
+        let registry = crate::token_registry::TokenRegistry::for_chain(&POLYGON);
+        let native = registry.resolve("POL").unwrap();
+        let wrapped = registry.resolve("WPOL").unwrap();
+        let usdc = registry.resolve("USDC").unwrap();
+
+        assert_eq!(client.native_leg(&native, &usdc), NativeLeg::Input);
+        assert_eq!(client.native_leg(&usdc, &native), NativeLeg::Output);
+        assert_eq!(client.native_leg(&wrapped, &usdc), NativeLeg::None);
+    }
+
     #[tokio::test]
     async fn test_client_creation() {
-        let client = UniswapV2Client::new(None);
+        let client = UniswapV2Client::new(None, &MAINNET);
 
         assert!(!client.is_available());
 
@@ -598,9 +2417,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_swap_path_uses_chain_wrapped_native() {
+        let token_a = Address::repeat_byte(0xaa);
+        let token_b = Address::repeat_byte(0xbb);
+
+        let mainnet = UniswapV2Client::new(None, &MAINNET);
+        assert_eq!(
+            mainnet.swap_path(token_a, token_b),
+            vec![token_a, MAINNET.wrapped_native_address(), token_b]
+        );
+
+        // Polygon 通过 WPOL 中转，直接与 WPOL 交换时不加中间跳
+        let polygon = UniswapV2Client::new(None, &POLYGON);
+        let wpol = POLYGON.wrapped_native_address();
+        assert_eq!(polygon.swap_path(token_a, token_b), vec![token_a, wpol, token_b]);
+        assert_eq!(polygon.swap_path(wpol, token_b), vec![wpol, token_b]);
+        assert_eq!(polygon.usdc_address(), POLYGON.usdc_address());
+        assert_eq!(polygon.router_address(), POLYGON.uniswap_v2().router_address());
+    }
+
     #[tokio::test]
     async fn test_get_pair_without_provider() {
-        let client = UniswapV2Client::new(None);
+        let client = UniswapV2Client::new(None, &MAINNET);
 
         let result = client.get_pair(Address::zero(), Address::zero()).await;
 
@@ -613,7 +2452,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_get_reserves_without_provider() {
-        let client = UniswapV2Client::new(None);
+        let client = UniswapV2Client::new(None, &MAINNET);
 
         let result = client.get_reserves(Address::zero()).await;
 
@@ -623,4 +2462,100 @@ mod tests {
             UniswapError::ProviderUnavailable
         ));
     }
+
+    #[test]
+    fn test_decode_revert_data() {
+        let mut data = vec![0x08, 0xc3, 0x79, 0xa0];
+        data.extend("UniswapV2Router: EXPIRED".to_string().encode());
+        assert_eq!(decode_revert_data(&data).as_deref(), Some("UniswapV2Router: EXPIRED"));
+
+        let mut data = vec![0x4e, 0x48, 0x7b, 0x71];
+        data.extend(U256::from(0x11).encode());
+        assert_eq!(decode_revert_data(&data).as_deref(), Some("Panic(0x11): 算术溢出"));
+
+        // 自定义错误和空数据无法解码
+        assert_eq!(decode_revert_data(&[0xde, 0xad, 0xbe, 0xef]), None);
+        assert_eq!(decode_revert_data(&[]), None);
+    }
+
+    #[test]
+    fn test_parse_pair_created_log() {
+        assert_eq!(
+            PAIR_CREATED_EVENT_TOPIC.parse::<H256>().unwrap(),
+            H256::from(ethers::utils::keccak256(
+                "PairCreated(address,address,address,uint256)"
+            ))
+        );
+
+        let token0 = Address::repeat_byte(0x01);
+        let token1 = Address::repeat_byte(0x02);
+        let pair = Address::repeat_byte(0x03);
+
+        let mut data = H256::from(pair).as_bytes().to_vec();
+        let mut length = [0u8; 32];
+        U256::from(42u64).to_big_endian(&mut length);
+        data.extend_from_slice(&length);
+
+        let mut log = Log {
+            address: Address::repeat_byte(0xff),
+            topics: vec![
+                PAIR_CREATED_EVENT_TOPIC.parse().unwrap(),
+                H256::from(token0),
+                H256::from(token1),
+            ],
+            data: Bytes::from(data),
+            block_number: Some(U64::from(100)),
+            transaction_hash: Some(H256::repeat_byte(0x11)),
+            ..Default::default()
+        };
+
+        let created = parse_pair_created_log(&log).expect("应该能解析 PairCreated 日志");
+        assert_eq!(created.factory, Address::repeat_byte(0xff));
+        assert_eq!(created.token0, token0);
+        assert_eq!(created.token1, token1);
+        assert_eq!(created.pair, pair);
+        assert_eq!(created.block_number, 100);
+
+        log.data = Bytes::from(vec![0u8; 32]);
+        assert!(parse_pair_created_log(&log).is_none());
+    }
+
+    #[test]
+    fn test_parse_swap_log() {
+        assert_eq!(
+            SWAP_EVENT_TOPIC.parse::<H256>().unwrap(),
+            H256::from(ethers::utils::keccak256(
+                "Swap(address,uint256,uint256,uint256,uint256,address)"
+            ))
+        );
+
+        let mut data = Vec::new();
+        for amount in [1000u64, 0, 0, 5] {
+            let mut word = [0u8; 32];
+            U256::from(amount).to_big_endian(&mut word);
+            data.extend_from_slice(&word);
+        }
+
+        let log = Log {
+            address: Address::repeat_byte(0x03),
+            topics: vec![
+                SWAP_EVENT_TOPIC.parse().unwrap(),
+                H256::from(Address::repeat_byte(0x0a)),
+                H256::from(Address::repeat_byte(0x0b)),
+            ],
+            data: Bytes::from(data),
+            block_number: Some(U64::from(7)),
+            log_index: Some(U256::from(4u64)),
+            ..Default::default()
+        };
+
+        let swap = parse_swap_log(&log).expect("应该能解析 Swap 日志");
+        assert_eq!(swap.pair, Address::repeat_byte(0x03));
+        assert_eq!(swap.to, Address::repeat_byte(0x0b));
+        assert_eq!(swap.amount0_in, U256::from(1000u64));
+        assert_eq!(swap.amount1_out, U256::from(5u64));
+        assert_eq!(swap.block_number, 7);
+        assert_eq!(swap.log_index, 4);
+        assert!(swap.sells_token0());
+    }
 }
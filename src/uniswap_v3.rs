@@ -0,0 +1,500 @@
+use crate::bindings::{self, i_nonfungible_position_manager, i_uniswap_v3_factory, i_uniswap_v3_pool};
+use crate::eth_client::RpcProvider;
+use crate::multicall::{self, Call3, MulticallError};
+use ethers::abi::{AbiDecode, AbiEncode};
+use ethers::prelude::*;
+use std::sync::Arc;
+use tracing::{debug, instrument};
+
+/// TickMath.MIN_TICK / MAX_TICK
+pub const MIN_TICK: i32 = -887272;
+pub const MAX_TICK: i32 = 887272;
+
+/// getSqrtRatioAtTick 按 tick 的各个二进制位累乘的 Q128.128 系数（同 Uniswap V3 TickMath）
+const TICK_RATIO_FACTORS: [(u32, &str); 19] = [
+    (0x2, "fff97272373d413259a46990580e213a"),
+    (0x4, "fff2e50f5f656932ef12357cf3c7fdcc"),
+    (0x8, "ffe5caca7e10e4e61c3624eaa0941cd0"),
+    (0x10, "ffcb9843d60f6159c9db58835c926644"),
+    (0x20, "ff973b41fa98c081472e6896dfb254c0"),
+    (0x40, "ff2ea16466c96a3843ec78b326b52861"),
+    (0x80, "fe5dee046a99a2a811c461f1969c3053"),
+    (0x100, "fcbe86c7900a88aedcffc83b479aa3a4"),
+    (0x200, "f987a7253ac413176f2b074cf7815e54"),
+    (0x400, "f3392b0822b70005940c7a398e4b70f3"),
+    (0x800, "e7159475a2c29b7443b29c7fa6e889d9"),
+    (0x1000, "d097f3bdfd2022b8845ad8f792aa5825"),
+    (0x2000, "a9f746462d870fdf8a65dc1f90e061e5"),
+    (0x4000, "70d869a156d2a1b890bb3df62baf32f7"),
+    (0x8000, "31be135f97d08fd981231505542fcfa6"),
+    (0x10000, "9aa508b5b7a84e1c677de54f3e99bc9"),
+    (0x20000, "5d6af8dedb81196699c329225ee604"),
+    (0x40000, "2216e584f5fa1ea926041bedfe98"),
+    (0x80000, "48a170391f7dc42444e8fa2"),
+];
+
+/// Uniswap V3 错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum UniswapV3Error {
+    #[error("提供者错误: {0}")]
+    ProviderError(#[from] ProviderError),
+
+    #[error("ABI 编码/解码错误: {0}")]
+    AbiError(String),
+
+    #[error("Provider 不可用")]
+    ProviderUnavailable,
+
+    #[error("Multicall 错误: {0}")]
+    MulticallError(#[from] MulticallError),
+
+    #[error("未找到 V3 池子")]
+    PoolNotFound,
+
+    #[error("头寸不存在: {0}")]
+    PositionNotFound(U256),
+}
+
+/// NonfungiblePositionManager.positions(tokenId) 返回的头寸数据
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    pub token_id: U256,
+    pub token0: Address,
+    pub token1: Address,
+    /// 手续费档位（百万分之一，如 3000 = 0.3%）
+    pub fee: u32,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside0_last_x128: U256,
+    pub fee_growth_inside1_last_x128: U256,
+    /// 已结算但未领取的代币数量
+    pub tokens_owed0: u128,
+    pub tokens_owed1: u128,
+}
+
+/// 计算头寸价值所需的池子状态
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolState {
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub fee_growth_global0_x128: U256,
+    pub fee_growth_global1_x128: U256,
+    /// 下边界 tick 的 (feeGrowthOutside0X128, feeGrowthOutside1X128)
+    pub lower_fee_growth_outside: (U256, U256),
+    /// 上边界 tick 的 (feeGrowthOutside0X128, feeGrowthOutside1X128)
+    pub upper_fee_growth_outside: (U256, U256),
+}
+
+/// 头寸按当前池子状态折算的代币数量
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PositionAmounts {
+    /// 当前可赎回的 token0 / token1 数量
+    pub amount0: U256,
+    pub amount1: U256,
+    /// 未领取的手续费（含已结算的 tokensOwed）
+    pub fees0: U256,
+    pub fees1: U256,
+    /// 当前价格是否在区间内（tickLower <= tick < tickUpper）
+    pub in_range: bool,
+}
+
+/// 头寸查询被合约 revert 时视为头寸不存在（如 "Invalid token ID"），超时等其他 RPC 错误原样返回
+fn position_call_error(token_id: U256, error: ProviderError) -> UniswapV3Error {
+    let reverted = RpcError::as_error_response(&error)
+        .is_some_and(|e| e.code == 3 || e.message.contains("execution reverted"));
+    if reverted {
+        UniswapV3Error::PositionNotFound(token_id)
+    } else {
+        UniswapV3Error::ProviderError(error)
+    }
+}
+
+/// Uniswap V3 NonfungiblePositionManager 查询客户端
+pub struct UniswapV3Client {
+    provider: Option<Arc<RpcProvider>>,
+    position_manager: Address,
+}
+
+impl UniswapV3Client {
+    pub fn new(provider: Option<Arc<RpcProvider>>, position_manager: Address) -> Self {
+        Self {
+            provider,
+            position_manager,
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    pub fn position_manager_address(&self) -> Address {
+        self.position_manager
+    }
+
+    fn provider(&self) -> Result<&Arc<RpcProvider>, UniswapV3Error> {
+        self.provider.as_ref().ok_or(UniswapV3Error::ProviderUnavailable)
+    }
+
+    /// 查询头寸 NFT 的持有者
+    #[instrument(skip(self))]
+    pub async fn owner_of(&self, token_id: U256) -> Result<Address, UniswapV3Error> {
+        let provider = self.provider()?;
+
+        let call = i_nonfungible_position_manager::OwnerOfCall { token_id };
+        let result = bindings::eth_call(provider, self.position_manager, call, None)
+            .await
+            .map_err(|e| position_call_error(token_id, e))?;
+
+        decode_return::<i_nonfungible_position_manager::OwnerOfReturn>(&result).map(|r| r.0)
+    }
+
+    /// 列出钱包持有的头寸 NFT（最多 `limit` 个），返回 (token ID 列表, 持有总数)
+    #[instrument(skip(self))]
+    pub async fn position_ids(&self, owner: Address, limit: usize) -> Result<(Vec<U256>, U256), UniswapV3Error> {
+        let provider = self.provider()?;
+
+        let call = i_nonfungible_position_manager::BalanceOfCall { owner };
+        let result = bindings::eth_call(provider, self.position_manager, call, None).await?;
+        let balance = decode_return::<i_nonfungible_position_manager::BalanceOfReturn>(&result)?.0;
+
+        let count = balance.min(U256::from(limit)).as_usize();
+        if count == 0 {
+            return Ok((Vec::new(), balance));
+        }
+
+        let calls = (0..count)
+            .map(|index| {
+                let call = i_nonfungible_position_manager::TokenOfOwnerByIndexCall { owner, index: U256::from(index) };
+                Call3::new(self.position_manager, call.encode())
+            })
+            .collect();
+        let results = multicall::aggregate3(provider, calls, None).await?;
+
+        Ok((results.iter().filter_map(|result| result.as_u256()).collect(), balance))
+    }
+
+    /// 读取头寸数据 positions(tokenId)
+    #[instrument(skip(self))]
+    pub async fn position(&self, token_id: U256) -> Result<Position, UniswapV3Error> {
+        let provider = self.provider()?;
+
+        let call = i_nonfungible_position_manager::PositionsCall { token_id };
+        let result = bindings::eth_call(provider, self.position_manager, call, None)
+            .await
+            .map_err(|e| position_call_error(token_id, e))?;
+        let position = decode_return::<i_nonfungible_position_manager::PositionsReturn>(&result)?;
+
+        debug!(token_id = %token_id, token0 = %position.token_0, token1 = %position.token_1, "获取到 V3 头寸");
+        Ok(Position {
+            token_id,
+            token0: position.token_0,
+            token1: position.token_1,
+            fee: position.fee,
+            tick_lower: position.tick_lower,
+            tick_upper: position.tick_upper,
+            liquidity: position.liquidity,
+            fee_growth_inside0_last_x128: position.fee_growth_inside_0_last_x128,
+            fee_growth_inside1_last_x128: position.fee_growth_inside_1_last_x128,
+            tokens_owed0: position.tokens_owed_0,
+            tokens_owed1: position.tokens_owed_1,
+        })
+    }
+
+    /// 通过 PositionManager.factory() 和 Factory.getPool 查询头寸所在的池子
+    #[instrument(skip(self))]
+    pub async fn pool_address(&self, token0: Address, token1: Address, fee: u32) -> Result<Address, UniswapV3Error> {
+        let provider = self.provider()?;
+
+        let result =
+            bindings::eth_call(provider, self.position_manager, i_nonfungible_position_manager::FactoryCall, None)
+                .await?;
+        let factory = decode_return::<i_nonfungible_position_manager::FactoryReturn>(&result)?.0;
+
+        let call = i_uniswap_v3_factory::GetPoolCall { token_a: token0, token_b: token1, fee };
+        let result = bindings::eth_call(provider, factory, call, None).await?;
+        let pool = decode_return::<i_uniswap_v3_factory::GetPoolReturn>(&result)?.pool;
+
+        if pool.is_zero() {
+            return Err(UniswapV3Error::PoolNotFound);
+        }
+        Ok(pool)
+    }
+
+    /// 读取池子当前价格、全局手续费增长和头寸边界 tick 的手续费增长（Multicall3 单次 RPC）
+    #[instrument(skip(self))]
+    pub async fn pool_state(&self, pool: Address, tick_lower: i32, tick_upper: i32) -> Result<PoolState, UniswapV3Error> {
+        let provider = self.provider()?;
+
+        let calls = vec![
+            Call3::new(pool, i_uniswap_v3_pool::Slot0Call.encode()),
+            Call3::new(pool, i_uniswap_v3_pool::FeeGrowthGlobal0X128Call.encode()),
+            Call3::new(pool, i_uniswap_v3_pool::FeeGrowthGlobal1X128Call.encode()),
+            Call3::new(pool, i_uniswap_v3_pool::TicksCall { tick: tick_lower }.encode()),
+            Call3::new(pool, i_uniswap_v3_pool::TicksCall { tick: tick_upper }.encode()),
+        ];
+        let results = multicall::aggregate3(provider, calls, None).await?;
+
+        let field = |index: usize| -> Result<&[u8], UniswapV3Error> {
+            results
+                .get(index)
+                .filter(|result| result.success)
+                .map(|result| &result.return_data[..])
+                .ok_or_else(|| UniswapV3Error::AbiError(format!("池子状态子调用 {} 失败", index)))
+        };
+        let slot0 = decode_return::<i_uniswap_v3_pool::Slot0Return>(field(0)?)?;
+        let lower = decode_return::<i_uniswap_v3_pool::TicksReturn>(field(3)?)?;
+        let upper = decode_return::<i_uniswap_v3_pool::TicksReturn>(field(4)?)?;
+
+        Ok(PoolState {
+            sqrt_price_x96: slot0.sqrt_price_x96,
+            tick: slot0.tick,
+            fee_growth_global0_x128: decode_return::<U256>(field(1)?)?,
+            fee_growth_global1_x128: decode_return::<U256>(field(2)?)?,
+            lower_fee_growth_outside: (lower.fee_growth_outside_0x128, lower.fee_growth_outside_1x128),
+            upper_fee_growth_outside: (upper.fee_growth_outside_0x128, upper.fee_growth_outside_1x128),
+        })
+    }
+}
+
+/// 按当前池子状态计算头寸可赎回的代币数量和未领取的手续费
+pub fn position_amounts(position: &Position, pool: &PoolState) -> Result<PositionAmounts, String> {
+    let sqrt_lower = sqrt_ratio_at_tick(position.tick_lower)?;
+    let sqrt_upper = sqrt_ratio_at_tick(position.tick_upper)?;
+    let liquidity = U256::from(position.liquidity);
+    let (amount0, amount1) = amounts_for_liquidity(pool.sqrt_price_x96, sqrt_lower, sqrt_upper, liquidity);
+
+    let (inside0, inside1) = fee_growth_inside(position.tick_lower, position.tick_upper, pool);
+    let fees0 = U256::from(position.tokens_owed0)
+        .saturating_add(uncollected_fees(inside0, position.fee_growth_inside0_last_x128, liquidity));
+    let fees1 = U256::from(position.tokens_owed1)
+        .saturating_add(uncollected_fees(inside1, position.fee_growth_inside1_last_x128, liquidity));
+
+    Ok(PositionAmounts {
+        amount0,
+        amount1,
+        fees0,
+        fees1,
+        in_range: position.tick_lower <= pool.tick && pool.tick < position.tick_upper,
+    })
+}
+
+/// tick 对应的 sqrt(1.0001^tick) × 2^96（同 TickMath.getSqrtRatioAtTick，向上取整）
+pub fn sqrt_ratio_at_tick(tick: i32) -> Result<U256, String> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return Err(format!("tick 超出范围: {}", tick));
+    }
+    let abs_tick = tick.unsigned_abs();
+
+    let mut ratio = if abs_tick & 0x1 != 0 {
+        U256::from_str_radix("fffcb933bd6fad37aa2d162d1a594001", 16).expect("硬编码常量应该有效")
+    } else {
+        U256::one() << 128
+    };
+    for (bit, factor) in TICK_RATIO_FACTORS {
+        if abs_tick & bit != 0 {
+            let factor = U256::from_str_radix(factor, 16).expect("硬编码常量应该有效");
+            ratio = (ratio * factor) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Q128.128 转换为 Q64.96，向上取整
+    let round_up = !(ratio & U256::from(u32::MAX)).is_zero();
+    Ok((ratio >> 32) + U256::from(round_up as u8))
+}
+
+/// 流动性在价格区间内对应的代币数量（同 LiquidityAmounts.getAmountsForLiquidity，向下取整）
+pub fn amounts_for_liquidity(sqrt_price: U256, sqrt_lower: U256, sqrt_upper: U256, liquidity: U256) -> (U256, U256) {
+    let (sqrt_lower, sqrt_upper) = (sqrt_lower.min(sqrt_upper), sqrt_lower.max(sqrt_upper));
+    if sqrt_price <= sqrt_lower {
+        (amount0_for_liquidity(sqrt_lower, sqrt_upper, liquidity), U256::zero())
+    } else if sqrt_price < sqrt_upper {
+        (
+            amount0_for_liquidity(sqrt_price, sqrt_upper, liquidity),
+            amount1_for_liquidity(sqrt_lower, sqrt_price, liquidity),
+        )
+    } else {
+        (U256::zero(), amount1_for_liquidity(sqrt_lower, sqrt_upper, liquidity))
+    }
+}
+
+/// L × (sqrtB - sqrtA) × 2^96 / (sqrtB × sqrtA)
+fn amount0_for_liquidity(sqrt_a: U256, sqrt_b: U256, liquidity: U256) -> U256 {
+    if sqrt_a.is_zero() {
+        return U256::zero();
+    }
+    let numerator = (liquidity << 96).full_mul(sqrt_b - sqrt_a) / U512::from(sqrt_b);
+    U256::try_from(numerator / U512::from(sqrt_a)).unwrap_or(U256::MAX)
+}
+
+/// L × (sqrtB - sqrtA) / 2^96
+fn amount1_for_liquidity(sqrt_a: U256, sqrt_b: U256, liquidity: U256) -> U256 {
+    U256::try_from(liquidity.full_mul(sqrt_b - sqrt_a) >> 96).unwrap_or(U256::MAX)
+}
+
+/// 区间内的累计手续费增长（同 Tick.getFeeGrowthInside，按 2^256 取模运算）
+fn fee_growth_inside(tick_lower: i32, tick_upper: i32, pool: &PoolState) -> (U256, U256) {
+    let inside = |global: U256, lower_outside: U256, upper_outside: U256| {
+        let below = if pool.tick >= tick_lower {
+            lower_outside
+        } else {
+            global.overflowing_sub(lower_outside).0
+        };
+        let above = if pool.tick < tick_upper {
+            upper_outside
+        } else {
+            global.overflowing_sub(upper_outside).0
+        };
+        global.overflowing_sub(below).0.overflowing_sub(above).0
+    };
+
+    (
+        inside(pool.fee_growth_global0_x128, pool.lower_fee_growth_outside.0, pool.upper_fee_growth_outside.0),
+        inside(pool.fee_growth_global1_x128, pool.lower_fee_growth_outside.1, pool.upper_fee_growth_outside.1),
+    )
+}
+
+/// 上次结算后新增的手续费 = (feeGrowthInside - feeGrowthInsideLast) × L / 2^128
+fn uncollected_fees(inside: U256, inside_last: U256, liquidity: U256) -> U256 {
+    let growth = inside.overflowing_sub(inside_last).0;
+    U256::try_from(growth.full_mul(liquidity) >> 128).unwrap_or(U256::MAX)
+}
+
+/// 以 token1 计价的 token0 价格：(sqrtPriceX96 / 2^96)^2 × 10^(decimals0 - decimals1)
+/// 返回按 18 位精度缩放的整数，溢出时返回 None
+pub fn price_from_sqrt(sqrt_price_x96: U256, decimals0: u8, decimals1: u8) -> Option<U256> {
+    let ten = U512::from(10u8);
+    let numerator = (U512::from(sqrt_price_x96) * U512::from(sqrt_price_x96))
+        .checked_mul(ten.checked_pow(U512::from(decimals0 as u32 + 18))?)?;
+    let denominator = (U512::one() << 192).checked_mul(ten.checked_pow(U512::from(decimals1))?)?;
+    U256::try_from(numerator / denominator).ok()
+}
+
+fn decode_return<R: AbiDecode>(data: &[u8]) -> Result<R, UniswapV3Error> {
+    R::decode(data).map_err(|e| UniswapV3Error::AbiError(format!("解码返回值失败: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::RpcTransportError;
+    use crate::erc20::format_units;
+    use ethers::providers::{HttpClientError, JsonRpcError};
+
+    #[test]
+    fn test_position_call_error() {
+        let rpc_error = |code: i64, message: &str| {
+            ProviderError::from(RpcTransportError::Http(HttpClientError::JsonRpcError(JsonRpcError {
+                code,
+                message: message.to_string(),
+                data: None,
+            })))
+        };
+        let token_id = U256::from(42);
+
+        assert!(matches!(
+            position_call_error(token_id, rpc_error(3, "execution reverted: Invalid token ID")),
+            UniswapV3Error::PositionNotFound(id) if id == token_id
+        ));
+        assert!(matches!(
+            position_call_error(token_id, rpc_error(-32000, "execution reverted")),
+            UniswapV3Error::PositionNotFound(_)
+        ));
+        assert!(matches!(
+            position_call_error(token_id, rpc_error(-32005, "daily rate limit reached")),
+            UniswapV3Error::ProviderError(_)
+        ));
+        let timeout = RpcTransportError::Timeout { method: "eth_call".to_string(), timeout_secs: 30 };
+        assert!(matches!(
+            position_call_error(token_id, timeout.into()),
+            UniswapV3Error::ProviderError(_)
+        ));
+    }
+
+    #[test]
+    fn test_sqrt_ratio_at_tick() {
+        assert_eq!(sqrt_ratio_at_tick(0).unwrap(), U256::one() << 96);
+        assert_eq!(sqrt_ratio_at_tick(MIN_TICK).unwrap(), U256::from(4295128739u64));
+        assert_eq!(
+            sqrt_ratio_at_tick(MAX_TICK).unwrap(),
+            U256::from_dec_str("1461446703485210103287273052203988822378723970342").unwrap()
+        );
+        assert!(sqrt_ratio_at_tick(MAX_TICK + 1).is_err());
+
+        // 与浮点数计算的 sqrt(1.0001^tick) 对比，覆盖全部系数
+        for tick in [1, -1, 7, 100, -2_000, 50_000, -123_457, 262_143, -524_287] {
+            let expected = 1.0001f64.powf(tick as f64 / 2.0);
+            let actual = sqrt_ratio_at_tick(tick).unwrap();
+            let actual = actual.as_u128() as f64 / 2f64.powi(96);
+            assert!((actual / expected - 1.0).abs() < 1e-9, "tick {}: {} != {}", tick, actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_amounts_for_liquidity() {
+        let sqrt_lower = sqrt_ratio_at_tick(-600).unwrap();
+        let sqrt_upper = sqrt_ratio_at_tick(600).unwrap();
+        let liquidity = U256::exp10(18);
+
+        // 价格在区间中点时两侧数量近似相等
+        let (amount0, amount1) = amounts_for_liquidity(U256::one() << 96, sqrt_lower, sqrt_upper, liquidity);
+        assert!(!amount0.is_zero() && !amount1.is_zero());
+        assert!(amount0.abs_diff(amount1) < amount0 / 1000);
+
+        // 价格低于区间时全部为 token0,高于区间时全部为 token1
+        let (amount0, amount1) = amounts_for_liquidity(sqrt_ratio_at_tick(-1000).unwrap(), sqrt_lower, sqrt_upper, liquidity);
+        assert!(!amount0.is_zero() && amount1.is_zero());
+        let (amount0, amount1) = amounts_for_liquidity(sqrt_ratio_at_tick(1000).unwrap(), sqrt_lower, sqrt_upper, liquidity);
+        assert!(amount0.is_zero() && !amount1.is_zero());
+    }
+
+    #[test]
+    fn test_position_fees() {
+        let position = Position {
+            token_id: U256::one(),
+            token0: Address::repeat_byte(0x11),
+            token1: Address::repeat_byte(0x22),
+            fee: 3000,
+            tick_lower: -60,
+            tick_upper: 60,
+            liquidity: 1u128 << 64,
+            fee_growth_inside0_last_x128: U256::zero(),
+            fee_growth_inside1_last_x128: U256::MAX, // 手续费增长按 2^256 取模回绕
+            tokens_owed0: 5,
+            tokens_owed1: 0,
+        };
+        let pool = PoolState {
+            sqrt_price_x96: U256::one() << 96,
+            tick: 0,
+            fee_growth_global0_x128: U256::from(3) << 128,
+            fee_growth_global1_x128: U256::from(2) << 128,
+            lower_fee_growth_outside: (U256::from(1) << 128, U256::zero()),
+            upper_fee_growth_outside: (U256::zero(), U256::zero()),
+        };
+
+        let amounts = position_amounts(&position, &pool).unwrap();
+        assert!(amounts.in_range);
+        // 区间内增长 = 3 - 1 - 0 = 2 (×2^128),手续费 = 2 × 2^64 + tokensOwed
+        assert_eq!(amounts.fees0, U256::from(2u128 << 64) + U256::from(5));
+        // 上次记录的增长为 2^256 - 1,本次 2 × 2^128 回绕后新增 2 × 2^128 + 1
+        assert_eq!(amounts.fees1, U256::from(2u128 << 64));
+
+        let out_of_range = PoolState { tick: 60, ..pool };
+        assert!(!position_amounts(&position, &out_of_range).unwrap().in_range);
+    }
+
+    #[test]
+    fn test_price_from_sqrt() {
+        // USDC(6)/WETH(18) 池子:1 USDC = 0.0005 WETH
+        let sqrt_price = U256::from_dec_str("1771595571142957166518320255467520").unwrap();
+        let price = price_from_sqrt(sqrt_price, 6, 18).unwrap();
+        assert_eq!(format_units(price, 18), "0.0005");
+
+        assert_eq!(price_from_sqrt(U256::one() << 96, 18, 18).unwrap(), U256::exp10(18));
+        assert_eq!(price_from_sqrt(U256::one() << 96, 18, 6).unwrap(), U256::exp10(30));
+        assert_eq!(price_from_sqrt(U256::one() << 96, 255, 0), None);
+    }
+}
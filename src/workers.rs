@@ -0,0 +1,202 @@
+use crate::logging::{info, warn};
+use crate::panic_guard::catch_panic;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 连续失败后重试间隔的上限
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// 后台任务的运行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerState {
+    /// 正在执行
+    Running,
+    /// 等待下一次执行
+    Idle,
+    /// 上次执行失败，等待退避后重启
+    Backoff,
+}
+
+/// 后台任务的健康状态快照
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WorkerStatus {
+    pub name: String,
+    pub description: String,
+    pub state: WorkerState,
+    /// 执行间隔(秒)
+    pub interval_secs: u64,
+    /// 启动时间(Unix 秒)
+    pub started_at: i64,
+    pub runs: u64,
+    /// 失败后重启的次数
+    pub restarts: u64,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_success_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error_at: Option<i64>,
+}
+
+impl WorkerStatus {
+    /// 没有连续失败时视为健康
+    pub fn is_healthy(&self) -> bool {
+        self.consecutive_failures == 0
+    }
+
+    fn record_success(&mut self, now: i64) {
+        self.runs += 1;
+        self.consecutive_failures = 0;
+        self.last_success_at = Some(now);
+        self.state = WorkerState::Idle;
+    }
+
+    fn record_failure(&mut self, error: String, now: i64) {
+        self.runs += 1;
+        self.restarts += 1;
+        self.consecutive_failures += 1;
+        self.last_error = Some(error);
+        self.last_error_at = Some(now);
+        self.state = WorkerState::Backoff;
+    }
+}
+
+/// 后台任务管理器
+/// 负责启动周期性任务、捕获任务中的错误和 panic，并在失败后按指数退避重启
+#[derive(Default)]
+pub struct WorkerManager {
+    workers: Mutex<Vec<Arc<Mutex<WorkerStatus>>>>,
+}
+
+impl WorkerManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 启动周期性后台任务
+    /// `job` 每隔 `interval` 执行一次；返回错误或 panic 时记录失败，退避后重新执行
+    pub fn spawn_periodic<F, Fut>(&self, name: &str, description: &str, interval: Duration, mut job: F)
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send,
+    {
+        let status = Arc::new(Mutex::new(WorkerStatus {
+            name: name.to_string(),
+            description: description.to_string(),
+            state: WorkerState::Idle,
+            interval_secs: interval.as_secs(),
+            started_at: chrono::Utc::now().timestamp(),
+            runs: 0,
+            restarts: 0,
+            consecutive_failures: 0,
+            last_success_at: None,
+            last_error: None,
+            last_error_at: None,
+        }));
+        lock(&self.workers).push(status.clone());
+
+        info!(worker = %name, interval_secs = interval.as_secs(), "启动后台任务");
+
+        let name = name.to_string();
+        tokio::spawn(async move {
+            loop {
+                lock(&status).state = WorkerState::Running;
+
+                let outcome = match catch_panic(job()).await {
+                    Ok(result) => result,
+                    Err(message) => Err(format!("panic: {}", message)),
+                };
+
+                let now = chrono::Utc::now().timestamp();
+                let failures = {
+                    let mut status = lock(&status);
+                    match outcome {
+                        Ok(()) => status.record_success(now),
+                        Err(error) => {
+                            warn!(worker = %name, error = %error, "后台任务失败,将在退避后重启");
+                            status.record_failure(error, now);
+                        }
+                    }
+                    status.consecutive_failures
+                };
+
+                tokio::time::sleep(next_delay(interval, failures)).await;
+            }
+        });
+    }
+
+    /// 所有后台任务的状态快照(按启动顺序)
+    pub fn statuses(&self) -> Vec<WorkerStatus> {
+        lock(&self.workers).iter().map(|status| lock(status).clone()).collect()
+    }
+}
+
+/// 计算下一次执行前的等待时间：失败时按 interval × 2^n 退避，最多 MAX_BACKOFF
+fn next_delay(interval: Duration, consecutive_failures: u32) -> Duration {
+    if consecutive_failures == 0 {
+        return interval;
+    }
+
+    let factor = 1u32 << consecutive_failures.min(16);
+    interval.saturating_mul(factor).min(MAX_BACKOFF.max(interval))
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_next_delay_backoff() {
+        let interval = Duration::from_secs(10);
+        assert_eq!(next_delay(interval, 0), interval);
+        assert_eq!(next_delay(interval, 1), Duration::from_secs(20));
+        assert_eq!(next_delay(interval, 3), Duration::from_secs(80));
+        assert_eq!(next_delay(interval, 10), MAX_BACKOFF);
+
+        // 间隔本身超过上限时不缩短
+        let slow = Duration::from_secs(600);
+        assert_eq!(next_delay(slow, 2), slow);
+    }
+
+    #[tokio::test]
+    async fn test_worker_restarts_after_failure_and_panic() {
+        let manager = WorkerManager::new();
+        let calls = Arc::new(AtomicU32::new(0));
+
+        let counter = calls.clone();
+        manager.spawn_periodic("test", "测试任务", Duration::from_millis(1), move || {
+            let call = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match call {
+                    0 => Err("RPC 超时".to_string()),
+                    1 => panic!("返回值异常"),
+                    _ => Ok(()),
+                }
+            }
+        });
+
+        for _ in 0..200 {
+            if calls.load(Ordering::SeqCst) >= 3 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let statuses = manager.statuses();
+        assert_eq!(statuses.len(), 1);
+        let status = &statuses[0];
+        assert_eq!(status.restarts, 2);
+        assert!(status.last_error.as_deref().unwrap().contains("返回值异常"));
+        assert!(status.last_success_at.is_some());
+        assert!(status.is_healthy());
+    }
+}
@@ -0,0 +1,226 @@
+use crate::diagnostics::record_rpc_call;
+use crate::quoting::{AggregatorFuture, AggregatorQuote, AggregatorSource, QuoteError, QuoteRequest};
+use ethers::prelude::*;
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument};
+
+/// 0x Swap API 地址（v2，通过 chainId 参数区分链）
+const ZEROX_API_BASE: &str = "https://api.0x.org";
+/// 0x 请求超时时间
+const ZEROX_TIMEOUT: Duration = Duration::from_secs(10);
+/// 0x 支持的链
+const ZEROX_CHAINS: [u64; 5] = [1, 10, 137, 8453, 42161];
+/// 报价默认滑点（基点）
+const ZEROX_SLIPPAGE_BPS: u32 = 100;
+
+/// 0x Swap API 错误类型
+#[derive(Debug, thiserror::Error)]
+pub enum ZeroExError {
+    #[error("HTTP 请求错误: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("未配置 ZEROX_API_KEY")]
+    MissingApiKey,
+
+    #[error("0x 不支持 Chain ID {0}")]
+    UnsupportedChain(u64),
+
+    #[error("0x API 返回错误 ({status}): {message}")]
+    ApiError { status: u16, message: String },
+
+    #[error("0x 没有该交易对的流动性")]
+    NoLiquidity,
+
+    #[error("响应格式错误: {0}")]
+    InvalidResponse(String),
+}
+
+/// 0x Swap API 客户端（AllowanceHolder 报价）
+#[derive(Clone)]
+pub struct ZeroExClient {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    chain_id: u64,
+}
+
+impl ZeroExClient {
+    /// 创建 0x 客户端（未配置 API Key 时不可用）
+    pub fn new(api_key: Option<String>, chain_id: u64) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(ZEROX_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            base_url: ZEROX_API_BASE.to_string(),
+            api_key,
+            chain_id,
+        }
+    }
+
+    /// 按卖出数量获取可直接执行的报价（taker 为下单地址）
+    #[instrument(skip(self))]
+    pub async fn quote(
+        &self,
+        sell_token: Address,
+        buy_token: Address,
+        sell_amount: U256,
+        taker: Address,
+    ) -> Result<AggregatorQuote, ZeroExError> {
+        let api_key = self.api_key.as_deref().ok_or(ZeroExError::MissingApiKey)?;
+        if !ZEROX_CHAINS.contains(&self.chain_id) {
+            return Err(ZeroExError::UnsupportedChain(self.chain_id));
+        }
+
+        let url = format!("{}/swap/allowance-holder/quote", self.base_url);
+        let request = self
+            .http
+            .get(&url)
+            .header("0x-api-key", api_key)
+            .header("0x-version", "v2")
+            .query(&[
+                ("chainId", self.chain_id.to_string()),
+                ("sellToken", format!("{:?}", sell_token)),
+                ("buyToken", format!("{:?}", buy_token)),
+                ("sellAmount", sell_amount.to_string()),
+                ("taker", format!("{:?}", taker)),
+                ("slippageBps", ZEROX_SLIPPAGE_BPS.to_string()),
+            ]);
+
+        let started = Instant::now();
+        let response = request.send().await;
+        record_rpc_call("zerox_quote", None, started.elapsed(), 0, response.is_ok());
+        let response = response?;
+
+        let status = response.status();
+        let body: serde_json::Value = response.json().await?;
+        if !status.is_success() {
+            // 0x 错误响应格式: {"name": "...", "message": "..."}
+            let message = format!(
+                "{} {}",
+                body["name"].as_str().unwrap_or_default(),
+                body["message"].as_str().unwrap_or_default()
+            )
+            .trim()
+            .to_string();
+            return Err(ZeroExError::ApiError {
+                status: status.as_u16(),
+                message: if message.is_empty() { status.to_string() } else { message },
+            });
+        }
+
+        let quote = parse_quote(&body)?;
+        debug!(buy_amount = %quote.buy_amount, min_buy_amount = %quote.min_buy_amount, "获取 0x 报价");
+        Ok(quote)
+    }
+}
+
+/// 解析 allowance-holder/quote 响应（uint256 以十进制字符串表示）
+fn parse_quote(body: &serde_json::Value) -> Result<AggregatorQuote, ZeroExError> {
+    if body["liquidityAvailable"] == serde_json::Value::Bool(false) {
+        return Err(ZeroExError::NoLiquidity);
+    }
+
+    let field = |path: &[&str]| {
+        path.iter()
+            .try_fold(body, |value, key| value.get(key))
+            .and_then(|value| value.as_str())
+            .ok_or_else(|| ZeroExError::InvalidResponse(format!("缺少字段 {}", path.join("."))))
+    };
+    let amount = |path: &[&str]| {
+        let text = field(path)?;
+        U256::from_dec_str(text).map_err(|_| ZeroExError::InvalidResponse(format!("无效的数量 {}: {}", path.join("."), text)))
+    };
+    let address = |path: &[&str]| {
+        let text = field(path)?;
+        text.parse::<Address>()
+            .map_err(|_| ZeroExError::InvalidResponse(format!("无效的地址 {}: {}", path.join("."), text)))
+    };
+
+    let calldata: Bytes = field(&["transaction", "data"])?
+        .parse()
+        .map_err(|_| ZeroExError::InvalidResponse("无效的 calldata".to_string()))?;
+
+    Ok(AggregatorQuote {
+        source: "0x",
+        sell_amount: amount(&["sellAmount"])?,
+        buy_amount: amount(&["buyAmount"])?,
+        min_buy_amount: amount(&["minBuyAmount"])?,
+        // 卖出原生代币时不需要授权，issues.allowance 为空
+        allowance_target: address(&["issues", "allowance", "spender"]).ok(),
+        to: address(&["transaction", "to"])?,
+        calldata,
+        value: amount(&["transaction", "value"]).unwrap_or_default(),
+        gas: amount(&["transaction", "gas"]).ok(),
+    })
+}
+
+impl AggregatorSource for ZeroExClient {
+    fn name(&self) -> &'static str {
+        "0x"
+    }
+
+    fn is_available(&self) -> bool {
+        self.api_key.is_some() && ZEROX_CHAINS.contains(&self.chain_id)
+    }
+
+    fn firm_quote<'a>(&'a self, request: &'a QuoteRequest) -> AggregatorFuture<'a> {
+        Box::pin(async move {
+            self.quote(request.sell_token, request.buy_token, request.sell_amount, request.owner)
+                .await
+                .map_err(QuoteError::from)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quote() {
+        let body = serde_json::json!({
+            "liquidityAvailable": true,
+            "sellAmount": "1000000000000000000",
+            "buyAmount": "3000000000",
+            "minBuyAmount": "2970000000",
+            "issues": {
+                "allowance": { "actual": "0", "spender": "0x0000000000001ff3684f28c67538d4d072c22734" },
+                "balance": null
+            },
+            "transaction": {
+                "to": "0x0000000000001ff3684f28c67538d4d072c22734",
+                "data": "0x2213bc0b",
+                "gas": "180000",
+                "gasPrice": "10000000000",
+                "value": "0"
+            }
+        });
+
+        let quote = parse_quote(&body).unwrap();
+        assert_eq!(quote.buy_amount, U256::from(3_000_000_000u64));
+        assert_eq!(quote.min_buy_amount, U256::from(2_970_000_000u64));
+        assert_eq!(
+            quote.allowance_target,
+            Some("0x0000000000001ff3684f28c67538d4d072c22734".parse().unwrap())
+        );
+        assert_eq!(quote.calldata, Bytes::from(vec![0x22, 0x13, 0xbc, 0x0b]));
+        assert_eq!(quote.gas, Some(U256::from(180_000u64)));
+
+        assert!(matches!(
+            parse_quote(&serde_json::json!({ "liquidityAvailable": false })),
+            Err(ZeroExError::NoLiquidity)
+        ));
+        assert!(matches!(
+            parse_quote(&serde_json::json!({ "liquidityAvailable": true })),
+            Err(ZeroExError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn test_availability() {
+        assert!(ZeroExClient::new(Some("key".to_string()), 1).is_available());
+        assert!(!ZeroExClient::new(None, 1).is_available());
+        assert!(!ZeroExClient::new(Some("key".to_string()), 11155111).is_available());
+    }
+}